@@ -0,0 +1,407 @@
+//! Texture atlas allocation
+//!
+//! Packs sprites (font glyphs, particles, dynamic textures) into sub-rectangles
+//! of a single atlas texture using guillotiere's bin-packing allocator. The
+//! atlas only tracks rectangles; the texture itself is owned by the caller.
+//!
+//! # Long sessions
+//!
+//! As resource sets change, stale sprites keep their space forever unless
+//! they are evicted. [`TextureAtlas::evict`] frees a region and
+//! [`TextureAtlas::repack`] compacts the remaining sprites, returning the
+//! relocations so the texels can be moved on the GPU (see
+//! `BasaltDevice::apply_atlas_relocations`) and UVs updated.
+//!
+//! Caches of dynamically loaded textures (player skins, map tiles) can
+//! instead create the atlas with [`AtlasEvictionPolicy::Lru`]: a full atlas
+//! then evicts the sprites used least recently, as marked by
+//! [`TextureAtlas::touch`], until the new one fits.
+
+use std::collections::HashMap;
+
+use guillotiere::{size2, AllocId, AtlasAllocator};
+
+/// Identifier of a sprite inside a [`TextureAtlas`]
+pub type AtlasEntryId = u64;
+
+/// A rectangle inside the atlas, in texels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AtlasRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl AtlasRect {
+    /// Whether two rectangles share any texels
+    pub fn overlaps(&self, other: &AtlasRect) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+/// A sprite placed in the atlas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasEntry {
+    pub id: AtlasEntryId,
+    pub rect: AtlasRect,
+}
+
+/// What [`TextureAtlas::insert_or_evict`] does when a sprite doesn't fit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AtlasEvictionPolicy {
+    /// Fail the insert and leave the atlas as it is
+    #[default]
+    Reject,
+    /// Evict least recently used sprites until the new one fits
+    Lru,
+}
+
+/// Result of [`TextureAtlas::insert_or_evict`]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct AtlasInsert {
+    /// The placed sprite, or `None` if it doesn't fit
+    pub entry: Option<AtlasEntry>,
+    /// Sprites evicted to make room and the regions they freed, least
+    /// recently used first
+    pub evicted: Vec<AtlasEntry>,
+}
+
+/// A sprite moved by [`TextureAtlas::repack`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasRelocation {
+    pub id: AtlasEntryId,
+    pub old: AtlasRect,
+    pub new: AtlasRect,
+}
+
+/// Result of [`TextureAtlas::repack`]
+#[derive(Debug, Default)]
+pub struct AtlasRepack {
+    /// Sprites whose position changed
+    pub relocations: Vec<AtlasRelocation>,
+    /// Sprites that no longer fit after repacking and were evicted
+    pub dropped: Vec<AtlasEntryId>,
+}
+
+#[derive(Clone)]
+struct Slot {
+    alloc_id: AllocId,
+    rect: AtlasRect,
+    /// `TextureAtlas::clock` when the sprite was inserted or last touched
+    last_used: u64,
+}
+
+/// Rectangle allocator for a single atlas texture
+#[derive(Clone)]
+pub struct TextureAtlas {
+    allocator: AtlasAllocator,
+    width: u32,
+    height: u32,
+    entries: HashMap<AtlasEntryId, Slot>,
+    next_id: AtlasEntryId,
+    policy: AtlasEvictionPolicy,
+    clock: u64,
+}
+
+impl TextureAtlas {
+    /// Create an empty atlas of `width` x `height` texels
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::with_policy(width, height, AtlasEvictionPolicy::Reject)
+    }
+
+    /// Create an empty atlas whose `insert_or_evict` follows `policy`
+    pub fn with_policy(width: u32, height: u32, policy: AtlasEvictionPolicy) -> Self {
+        Self {
+            allocator: AtlasAllocator::new(size2(width as i32, height as i32)),
+            width,
+            height,
+            entries: HashMap::new(),
+            next_id: 1, // 0 is reserved as the null entry
+            policy,
+            clock: 0,
+        }
+    }
+
+    pub fn policy(&self) -> AtlasEvictionPolicy {
+        self.policy
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Number of live sprites
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Look up a live sprite
+    pub fn get(&self, id: AtlasEntryId) -> Option<AtlasEntry> {
+        self.entries.get(&id).map(|slot| AtlasEntry { id, rect: slot.rect })
+    }
+
+    /// Place a `width` x `height` sprite, or `None` if the atlas is full
+    pub fn insert(&mut self, width: u32, height: u32) -> Option<AtlasEntry> {
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let allocation = self.allocator.allocate(size2(width as i32, height as i32))?;
+        let rect = AtlasRect {
+            x: allocation.rectangle.min.x as u32,
+            y: allocation.rectangle.min.y as u32,
+            width,
+            height,
+        };
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.clock += 1;
+        self.entries.insert(id, Slot { alloc_id: allocation.id, rect, last_used: self.clock });
+
+        Some(AtlasEntry { id, rect })
+    }
+
+    /// Mark a sprite as used, so LRU eviction takes it last; false if it isn't live
+    pub fn touch(&mut self, id: AtlasEntryId) -> bool {
+        let Some(slot) = self.entries.get_mut(&id) else {
+            return false;
+        };
+        self.clock += 1;
+        slot.last_used = self.clock;
+        true
+    }
+
+    /// Place a sprite, evicting per the atlas's policy if it doesn't fit
+    ///
+    /// With [`AtlasEvictionPolicy::Lru`] sprites are evicted least recently
+    /// used first until the new one fits. A sprite larger than the whole
+    /// atlas evicts nothing.
+    pub fn insert_or_evict(&mut self, width: u32, height: u32) -> AtlasInsert {
+        let mut result = AtlasInsert { entry: self.insert(width, height), evicted: Vec::new() };
+        if result.entry.is_some()
+            || self.policy == AtlasEvictionPolicy::Reject
+            || width > self.width
+            || height > self.height
+        {
+            return result;
+        }
+
+        while result.entry.is_none() {
+            let Some(lru) = self.entries.iter().min_by_key(|(_, slot)| slot.last_used).map(|(&id, _)| id) else {
+                break;
+            };
+            if let Some(rect) = self.evict(lru) {
+                result.evicted.push(AtlasEntry { id: lru, rect });
+            }
+            result.entry = self.insert(width, height);
+        }
+        result
+    }
+
+    /// Free a sprite by id, returning the region it occupied
+    pub fn evict(&mut self, id: AtlasEntryId) -> Option<AtlasRect> {
+        let slot = self.entries.remove(&id)?;
+        self.allocator.deallocate(slot.alloc_id);
+        Some(slot.rect)
+    }
+
+    /// Free the sprite occupying exactly `rect`, returning its id
+    pub fn evict_rect(&mut self, rect: AtlasRect) -> Option<AtlasEntryId> {
+        let id = self
+            .entries
+            .iter()
+            .find_map(|(&id, slot)| (slot.rect == rect).then_some(id))?;
+        self.evict(id);
+        Some(id)
+    }
+
+    /// Free every sprite, leaving the whole atlas empty
+    ///
+    /// Ids keep increasing, so a stale id never names a new sprite.
+    pub fn clear(&mut self) {
+        self.allocator.clear();
+        self.entries.clear();
+    }
+
+    /// Compact live sprites to reduce fragmentation
+    ///
+    /// Every previous rectangle is invalidated; the returned relocations
+    /// describe where each moved sprite now lives. In the rare case that a
+    /// sprite no longer fits with the new packing it is evicted and reported
+    /// in `dropped`.
+    pub fn repack(&mut self) -> AtlasRepack {
+        let by_alloc: HashMap<AllocId, AtlasEntryId> = self
+            .entries
+            .iter()
+            .map(|(&id, slot)| (slot.alloc_id, id))
+            .collect();
+
+        let changes = self.allocator.rearrange();
+        let mut result = AtlasRepack::default();
+
+        for change in changes.changes {
+            let Some(&id) = by_alloc.get(&change.old.id) else { continue };
+            let slot = self.entries.get_mut(&id).expect("atlas entry for live allocation");
+            let new = AtlasRect {
+                x: change.new.rectangle.min.x as u32,
+                y: change.new.rectangle.min.y as u32,
+                ..slot.rect
+            };
+
+            slot.alloc_id = change.new.id;
+            if new != slot.rect {
+                result.relocations.push(AtlasRelocation { id, old: slot.rect, new });
+                slot.rect = new;
+            }
+        }
+
+        for failure in changes.failures {
+            if let Some(id) = by_alloc.get(&failure.id) {
+                self.entries.remove(id);
+                result.dropped.push(*id);
+            }
+        }
+
+        log::debug!(
+            "Atlas repack: {} sprites moved, {} dropped, {} live",
+            result.relocations.len(),
+            result.dropped.len(),
+            self.entries.len()
+        );
+
+        result
+    }
+
+    /// Normalized UV rectangle `[u0, v0, u1, v1]` for an atlas rectangle
+    pub fn uv_rect(&self, rect: &AtlasRect) -> [f32; 4] {
+        let w = self.width as f32;
+        let h = self.height as f32;
+        [
+            rect.x as f32 / w,
+            rect.y as f32 / h,
+            (rect.x + rect.width) as f32 / w,
+            (rect.y + rect.height) as f32 / h,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// CPU mirror of the atlas texture: one value per texel
+    fn blit(texels: &mut [u32], atlas_width: u32, rect: &AtlasRect, value: u32) {
+        for y in rect.y..rect.y + rect.height {
+            for x in rect.x..rect.x + rect.width {
+                texels[(y * atlas_width + x) as usize] = value;
+            }
+        }
+    }
+
+    fn read(texels: &[u32], atlas_width: u32, rect: &AtlasRect) -> Vec<u32> {
+        let mut out = Vec::new();
+        for y in rect.y..rect.y + rect.height {
+            for x in rect.x..rect.x + rect.width {
+                out.push(texels[(y * atlas_width + x) as usize]);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_insert_evict_repack() {
+        let mut atlas = TextureAtlas::new(128, 128);
+        let mut texels = vec![0u32; 128 * 128];
+
+        let entries: Vec<_> = (0..12)
+            .map(|i| {
+                let entry = atlas.insert(16 + (i % 3) * 8, 16 + (i % 4) * 4).unwrap();
+                blit(&mut texels, 128, &entry.rect, entry.id as u32);
+                entry
+            })
+            .collect();
+
+        // Evict every third sprite, one of them by rectangle
+        assert_eq!(atlas.evict_rect(entries[0].rect), Some(entries[0].id));
+        for entry in entries.iter().skip(3).step_by(3) {
+            assert!(atlas.evict(entry.id).is_some());
+        }
+        assert_eq!(atlas.len(), 8);
+
+        // Apply relocations the way the GPU path does: through a snapshot
+        let repack = atlas.repack();
+        assert!(repack.dropped.is_empty());
+        let snapshot = texels.clone();
+        for relocation in &repack.relocations {
+            let sprite = read(&snapshot, 128, &relocation.old);
+            let mut i = 0;
+            for y in relocation.new.y..relocation.new.y + relocation.new.height {
+                for x in relocation.new.x..relocation.new.x + relocation.new.width {
+                    texels[(y * 128 + x) as usize] = sprite[i];
+                    i += 1;
+                }
+            }
+        }
+
+        let live: Vec<_> = entries.iter().filter_map(|e| atlas.get(e.id)).collect();
+        assert_eq!(live.len(), 8);
+        for (i, a) in live.iter().enumerate() {
+            assert_eq!((a.rect.width, a.rect.height), {
+                let original = entries.iter().find(|e| e.id == a.id).unwrap();
+                (original.rect.width, original.rect.height)
+            });
+            assert!(read(&texels, 128, &a.rect).iter().all(|&t| t == a.id as u32));
+            for b in &live[i + 1..] {
+                assert!(!a.rect.overlaps(&b.rect), "sprites {} and {} overlap", a.id, b.id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lru_eviction_makes_room() {
+        let mut atlas = TextureAtlas::with_policy(64, 64, AtlasEvictionPolicy::Lru);
+        let quarters: Vec<_> = (0..4).map(|_| atlas.insert(32, 32).unwrap().id).collect();
+
+        // Touching the oldest sprites leaves the third least recently used
+        assert!(atlas.touch(quarters[0]));
+        assert!(atlas.touch(quarters[1]));
+        let third = atlas.get(quarters[2]).unwrap();
+        let insert = atlas.insert_or_evict(32, 32);
+        assert_eq!(insert.evicted, [third]);
+        let entry = insert.entry.unwrap();
+        assert!(atlas.get(quarters[2]).is_none());
+        for &id in [quarters[0], quarters[1], quarters[3]].iter() {
+            assert!(!entry.rect.overlaps(&atlas.get(id).unwrap().rect));
+        }
+
+        // Too big for the atlas: nothing is evicted
+        assert_eq!(atlas.insert_or_evict(128, 8), AtlasInsert::default());
+        assert_eq!(atlas.len(), 4);
+
+        let mut rejecting = TextureAtlas::new(32, 32);
+        let only = rejecting.insert(32, 32).unwrap();
+        assert_eq!(rejecting.insert_or_evict(8, 8), AtlasInsert::default());
+        assert!(rejecting.get(only.id).is_some());
+        assert!(!rejecting.touch(only.id + 1));
+    }
+
+    #[test]
+    fn test_uv_rect() {
+        let atlas = TextureAtlas::new(256, 128);
+        let uv = atlas.uv_rect(&AtlasRect { x: 64, y: 32, width: 64, height: 32 });
+        assert_eq!(uv, [0.25, 0.25, 0.5, 0.5]);
+    }
+}
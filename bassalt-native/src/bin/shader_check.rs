@@ -144,6 +144,8 @@ struct ShaderCheckConfig {
     mc_source_dir: Option<PathBuf>,
     filter: Option<String>,
     verbose: bool,
+    /// GLSL preprocessor defines applied when parsing Minecraft sources
+    defines: Vec<(String, String)>,
 }
 
 struct ShaderFile {
@@ -207,7 +209,7 @@ fn main() {
         // Try to find corresponding GLSL
         let glsl_result = if let Some(ref mc_dir) = config.mc_source_dir {
             if let Some((_glsl_path, glsl_content)) = find_glsl(mc_dir, &shader.name, shader.stage) {
-                Some(parse_glsl(&glsl_content, &shader.name, shader.stage))
+                Some(parse_glsl(&glsl_content, &shader.name, shader.stage, &config.defines))
             } else {
                 None
             }
@@ -251,6 +253,7 @@ fn parse_args() -> ShaderCheckConfig {
     let mut mc_source_dir = None;
     let mut filter = None;
     let mut verbose = false;
+    let mut defines = Vec::new();
 
     let mut i = 1;
     while i < args.len() {
@@ -273,6 +276,13 @@ fn parse_args() -> ShaderCheckConfig {
                     filter = Some(args[i].clone());
                 }
             }
+            "--define" | "-D" => {
+                i += 1;
+                if i < args.len() {
+                    let (name, value) = args[i].split_once('=').unwrap_or((&args[i], ""));
+                    defines.push((name.to_string(), value.to_string()));
+                }
+            }
             "--verbose" | "-v" => verbose = true,
             "--help" | "-h" => {
                 print_usage();
@@ -288,6 +298,7 @@ fn parse_args() -> ShaderCheckConfig {
         mc_source_dir,
         filter,
         verbose,
+        defines,
     }
 }
 
@@ -298,6 +309,7 @@ fn print_usage() {
     println!("  --wgsl, -w <path>       WGSL shaders directory (default: src/main/resources/shaders/wgsl)");
     println!("  --mc-source, -m <path>  Minecraft source directory for GLSL comparison");
     println!("  --filter, -f <pattern>  Only check shaders matching this pattern");
+    println!("  --define, -D <NAME[=V]> Define a GLSL preprocessor macro (repeatable)");
     println!("  --verbose, -v           Show detailed information");
     println!("  --help, -h              Show this help");
     println!();
@@ -377,12 +389,20 @@ fn find_glsl(mc_dir: &Path, base_name: &str, stage: naga::ShaderStage) -> Option
     None
 }
 
-fn parse_wgsl(source: &str, name: &str, _stage: naga::ShaderStage) -> ParseResult {
+fn parse_wgsl(source: &str, name: &str, stage: naga::ShaderStage) -> ParseResult {
     let module = match naga::front::wgsl::parse_str(source) {
         Ok(m) => m,
         Err(e) => return ParseResult::ParseError(format!("{:?}", e)),
     };
 
+    // The file name picks the stage; the entry point attribute has to agree
+    if let Some(entry) = module.entry_points.iter().find(|entry| entry.stage != stage) {
+        return ParseResult::ValidationError(format!(
+            "file name says {:?} but entry point '{}' is {:?}",
+            stage, entry.name, entry.stage
+        ));
+    }
+
     let mut validator = naga::valid::Validator::new(
         naga::valid::ValidationFlags::all(),
         naga::valid::Capabilities::all(),
@@ -398,14 +418,14 @@ fn parse_wgsl(source: &str, name: &str, _stage: naga::ShaderStage) -> ParseResul
     }
 }
 
-fn parse_glsl(source: &str, name: &str, stage: naga::ShaderStage) -> ParseResult {
+fn parse_glsl(source: &str, name: &str, stage: naga::ShaderStage, defines: &[(String, String)]) -> ParseResult {
     // Preprocess GLSL
     let preprocessed = preprocess_glsl(source);
 
     let mut frontend = naga::front::glsl::Frontend::default();
     let options = naga::front::glsl::Options {
         stage,
-        defines: Default::default(),
+        defines: defines.iter().cloned().collect(),
     };
 
     let module = match frontend.parse(&options, &preprocessed) {
@@ -486,7 +506,7 @@ fn reflect_module(module: &Module, module_name: String) -> Result<ShaderReflecti
 
                 info.uniform_structs.push(UniformStructInfo {
                     name: name.clone(),
-                    size: *span as u32,
+                    size: *span,
                     members: field_names,
                 });
             }
@@ -589,7 +609,7 @@ fn generate_report(results: &[ValidationResult], config: &ShaderCheckConfig) {
 
         let icon = match result.wgsl_result {
             ParseResult::Success(_) => {
-                if result.comparison.as_ref().map_or(true, |c| c.issues.is_empty()) {
+                if result.comparison.as_ref().is_none_or(|c| c.issues.is_empty()) {
                     clean += 1;
                     format!("{}+{}", ANSI_GREEN, ANSI_RESET)
                 } else {
@@ -612,11 +632,9 @@ fn generate_report(results: &[ValidationResult], config: &ShaderCheckConfig) {
             }
         }
 
-        if let Some(ref glsl_result) = result.glsl_result {
-            if let ParseResult::ParseError(ref e) | ParseResult::ValidationError(ref e) = glsl_result {
-                glsl_errors += 1;
-                println!("  {}GLSL Error:{} {}", ANSI_YELLOW, ANSI_RESET, e);
-            }
+        if let Some(ParseResult::ParseError(ref e) | ParseResult::ValidationError(ref e)) = result.glsl_result {
+            glsl_errors += 1;
+            println!("  {}GLSL Error:{} {}", ANSI_YELLOW, ANSI_RESET, e);
         }
 
         if let Some(ref comp) = result.comparison {
@@ -628,7 +646,7 @@ fn generate_report(results: &[ValidationResult], config: &ShaderCheckConfig) {
                         IssueSeverity::Error => ANSI_RED,
                         IssueSeverity::Warning => ANSI_YELLOW,
                     };
-                    println!("  {}{}:{} {}", color, format!("{:?}", severity), ANSI_RESET, issue.description());
+                    println!("  {}{:?}:{} {}", color, severity, ANSI_RESET, issue.description());
                 }
             }
         }
@@ -699,7 +717,7 @@ fn print_detailed_analysis(results: &[ValidationResult]) {
             for binding in &info.bindings {
                 all_bindings.insert((binding.group, binding.binding));
                 binding_usage.entry((binding.group, binding.binding))
-                    .or_insert_with(Vec::new)
+                    .or_default()
                     .push(result.shader_name.clone());
             }
         }
@@ -732,7 +750,7 @@ fn print_detailed_analysis(results: &[ValidationResult]) {
             for s in &info.uniform_structs {
                 all_structs.insert(s.name.clone());
                 struct_usage.entry(s.name.clone())
-                    .or_insert_with(Vec::new)
+                    .or_default()
                     .push(result.shader_name.clone());
             }
         }
@@ -1,16 +1,19 @@
 //! Bind group management for wgpu-core 27
 //!
 //! Handles creation of bind groups and bind group layouts for binding
-//! textures, samplers, and uniform buffers to shaders.
+//! textures, samplers, uniform and storage buffers to shaders.
 
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::num::NonZero;
 use std::sync::Arc;
+use parking_lot::Mutex;
 use wgpu_core::{binding_model, id};
 use wgpu_types as wgt;
 
 use crate::context::BasaltContext;
 use crate::error::{BasaltError, Result};
+use crate::resource_handles::{BindingLayoutEntry, BindingLayoutType, TextureViewInfo, HANDLES};
 
 /// A binding entry for a bind group
 #[derive(Debug, Clone)]
@@ -21,12 +24,21 @@ pub enum BindingEntry {
         dimension: wgt::TextureViewDimension,
         /// The underlying texture, used to create views with different dimensions
         texture_id: id::TextureId,
+        /// Array layer count of `texture_id`, all of which a recreated view covers
+        texture_layers: u32,
+        /// Aspect of `view_id`, kept when a view is recreated
+        aspect: wgt::TextureAspect,
     },
     UniformBuffer {
         buffer_id: id::BufferId,
         offset: u64,
         size: NonZero<u64>,
     },
+    StorageBuffer {
+        buffer_id: id::BufferId,
+        offset: u64,
+        size: NonZero<u64>,
+    },
 }
 
 /// Builder for creating bind groups dynamically
@@ -49,18 +61,18 @@ impl BindGroupBuilder {
         }
     }
 
-    /// Add a texture binding with explicit dimension and texture_id for re-view creation
-    pub fn add_texture(
-        mut self,
-        binding: u32,
-        view_id: id::TextureViewId,
-        sampler_id: Option<id::SamplerId>,
-        dimension: wgt::TextureViewDimension,
-        texture_id: id::TextureId,
-    ) -> Self {
+    /// Add a texture binding; the view's texture, layers and aspect are kept for re-view creation
+    pub fn add_texture(mut self, binding: u32, view: &TextureViewInfo, sampler_id: Option<id::SamplerId>) -> Self {
         self.entries.push((
             binding,
-            BindingEntry::Texture { view_id, sampler_id, dimension, texture_id },
+            BindingEntry::Texture {
+                view_id: view.id,
+                sampler_id,
+                dimension: view.dimension,
+                texture_id: view.texture_id,
+                texture_layers: view.texture_layers,
+                aspect: view.aspect,
+            },
         ));
         self
     }
@@ -74,18 +86,51 @@ impl BindGroupBuilder {
         size: u64,
     ) -> Self {
         if let Some(size) = NonZero::new(size) {
-            self.entries.push((
-                binding,
-                BindingEntry::UniformBuffer {
-                    buffer_id,
-                    offset,
-                    size,
-                },
-            ));
+            self.entries.push((binding, BindingEntry::UniformBuffer { buffer_id, offset, size }));
+        }
+        self
+    }
+
+    /// Add a storage buffer binding
+    ///
+    /// Whether the shader may write to it comes from the layout; `build`,
+    /// which has no shader to look at, makes it read-only.
+    pub fn add_storage_buffer(
+        mut self,
+        binding: u32,
+        buffer_id: id::BufferId,
+        offset: u64,
+        size: u64,
+    ) -> Self {
+        if let Some(size) = NonZero::new(size) {
+            self.entries.push((binding, BindingEntry::StorageBuffer { buffer_id, offset, size }));
         }
         self
     }
 
+    /// Key identifying the bind group this builder makes with `layout_id`
+    pub fn cache_key(&self, layout_id: id::BindGroupLayoutId) -> BindGroupKey {
+        let mut entries = Vec::with_capacity(self.entries.len());
+        for (binding, entry) in &self.entries {
+            match entry {
+                BindingEntry::Texture { view_id, sampler_id, texture_id, .. } => {
+                    entries.push((*binding, BoundResource::TextureView(*view_id)));
+                    entries.push((*binding, BoundResource::Texture(*texture_id)));
+                    if let Some(sampler_id) = sampler_id {
+                        entries.push((*binding, BoundResource::Sampler(*sampler_id)));
+                    }
+                }
+                BindingEntry::UniformBuffer { buffer_id, offset, size }
+                | BindingEntry::StorageBuffer { buffer_id, offset, size } => {
+                    entries.push((*binding, BoundResource::Buffer { buffer_id: *buffer_id, offset: *offset, size: size.get() }));
+                }
+            }
+        }
+        // Stable, so resources within a binding keep their order
+        entries.sort_by_key(|(binding, _)| *binding);
+        BindGroupKey { layout_id, entries }
+    }
+
     /// Build the bind group, creating a layout based on actual bindings
     pub fn build(self) -> Result<id::BindGroupId> {
         let global = self.context.inner();
@@ -174,6 +219,26 @@ impl BindGroupBuilder {
                         ),
                     });
                 }
+                BindingEntry::StorageBuffer { buffer_id, offset, size } => {
+                    layout_entries.push(wgt::BindGroupLayoutEntry {
+                        binding: *binding,
+                        visibility: wgt::ShaderStages::VERTEX | wgt::ShaderStages::FRAGMENT,
+                        ty: wgt::BindingType::Buffer {
+                            ty: wgt::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    });
+                    bind_entries.push(binding_model::BindGroupEntry {
+                        binding: *binding,
+                        resource: binding_model::BindingResource::Buffer(binding_model::BufferBinding {
+                            buffer: *buffer_id,
+                            offset: *offset,
+                            size: Some(size.get()),
+                        }),
+                    });
+                }
             }
         }
 
@@ -229,8 +294,8 @@ impl BindGroupBuilder {
         // Collect our available resources by type, including texture_id for re-view creation
         let texture_entries: Vec<_> = self.entries.iter()
             .filter_map(|(binding, e)| match e {
-                BindingEntry::Texture { view_id, dimension, texture_id, .. } => 
-                    Some((*binding, *view_id, *dimension, *texture_id)),
+                BindingEntry::Texture { view_id, dimension, texture_id, texture_layers, aspect, .. } =>
+                    Some((*binding, *view_id, *dimension, *texture_id, *texture_layers, *aspect)),
                 _ => None,
             })
             .collect();
@@ -242,10 +307,11 @@ impl BindGroupBuilder {
             })
             .collect();
         
-        // Collect our available uniform entries WITH binding indices for proper lookup
+        // Collect our available uniform and storage entries WITH binding indices for proper lookup
         let uniform_entries: Vec<_> = self.entries.iter()
             .filter_map(|(binding, e)| match e {
-                BindingEntry::UniformBuffer { buffer_id, offset, size } =>
+                BindingEntry::UniformBuffer { buffer_id, offset, size }
+                | BindingEntry::StorageBuffer { buffer_id, offset, size } =>
                     Some((*binding, *buffer_id, *offset, size.get())),
                 _ => None,
             })
@@ -269,21 +335,41 @@ impl BindGroupBuilder {
             match layout_entry.ty {
                 BindingLayoutType::Texture => {
                     if texture_idx < texture_entries.len() {
-                        let (_, view_id, current_dimension, texture_id) = texture_entries[texture_idx];
-                        
-                        // Check if we need to create a new view with different dimension
+                        let (_, view_id, current_dimension, texture_id, texture_layers, view_aspect) = texture_entries[texture_idx];
+
+                        // Check if we need to create a new view with different dimension.
+                        // Depth textures sampled for comparison also need a depth-only view,
+                        // since a depth-stencil view with both aspects can't be sampled.
+                        // Otherwise a recreated view keeps the aspect the caller chose.
+                        let aspect = if layout_entry.comparison {
+                            wgt::TextureAspect::DepthOnly
+                        } else {
+                            view_aspect
+                        };
+                        if let Some(expected_dim) = layout_entry.expected_dimension {
+                            if !view_dimension_compatible(current_dimension, expected_dim, texture_layers) {
+                                return Err(BasaltError::TextureDimensionMismatch {
+                                    expected: format!("{:?}", expected_dim),
+                                    actual: format!("{:?}", current_dimension),
+                                    binding: layout_entry.binding,
+                                });
+                            }
+                        }
                         let final_view_id = if let Some(expected_dim) = layout_entry.expected_dimension {
-                            if expected_dim != current_dimension {
+                            if expected_dim != current_dimension || aspect != view_aspect {
                                 // Create a new view with the correct dimension
-                                log::debug!("Texture dimension mismatch at binding {}: expected {:?}, got {:?}. Creating new view.",
-                                           layout_entry.binding, expected_dim, current_dimension);
+                                log::debug!("Rebinding texture at binding {}: expected {:?}, got {:?}, depth={}. Creating new view.",
+                                           layout_entry.binding, expected_dim, current_dimension, layout_entry.comparison);
 
                                 let view_desc = wgpu_core::resource::TextureViewDescriptor {
                                     label: Some(Cow::Borrowed("Rebind Texture View")),
                                     format: None,
                                     dimension: Some(expected_dim),
                                     usage: None,
-                                    range: wgt::ImageSubresourceRange::default(),
+                                    range: wgt::ImageSubresourceRange {
+                                        aspect,
+                                        ..Default::default()
+                                    },
                                 };
 
                                 let (new_view_id, error) = global.texture_create_view(
@@ -333,7 +419,7 @@ impl BindGroupBuilder {
                             layout_entry.binding, sampler_idx, sampler_entries.len());
                     }
                 }
-                BindingLayoutType::UniformBuffer | BindingLayoutType::StorageBuffer => {
+                BindingLayoutType::UniformBuffer | BindingLayoutType::StorageBuffer { .. } => {
                     // Look up the entry by binding index (not sequential access)
                     let uniform_entry = uniform_entries.iter()
                         .find(|(binding, _, _, _)| *binding == layout_entry.binding);
@@ -377,7 +463,7 @@ impl BindGroupBuilder {
                             });
                             log::info!(
                                 "Bound {} buffer to slot {} (buffer={:?}, offset={}, size={})",
-                                if layout_entry.ty == BindingLayoutType::StorageBuffer { "storage" } else { "uniform" },
+                                if layout_entry.ty == BindingLayoutType::UniformBuffer { "uniform" } else { "storage" },
                                 layout_entry.binding,
                                 buffer_id,
                                 offset,
@@ -428,3 +514,849 @@ impl BindGroupBuilder {
         Ok(bind_group_id)
     }
 }
+
+/// A resource bound by an explicit bind group entry
+#[derive(Debug, Clone, Copy)]
+pub enum ExplicitResource {
+    TextureView(id::TextureViewId),
+    Sampler(id::SamplerId),
+    Buffer {
+        buffer_id: id::BufferId,
+        offset: u64,
+        /// `None` binds the rest of the buffer
+        size: Option<u64>,
+    },
+}
+
+impl ExplicitResource {
+    fn accepts(&self, ty: BindingLayoutType) -> bool {
+        matches!(
+            (self, ty),
+            (Self::TextureView(_), BindingLayoutType::Texture)
+                | (Self::Sampler(_), BindingLayoutType::Sampler)
+                | (Self::Buffer { .. }, BindingLayoutType::UniformBuffer | BindingLayoutType::StorageBuffer { .. })
+        )
+    }
+}
+
+/// Shader stages a binding of type `ty` is visible to
+///
+/// Bindings are shared by the vertex and fragment stage, except writable
+/// storage buffers: WebGPU doesn't allow them in vertex shaders.
+pub fn binding_visibility(ty: BindingLayoutType) -> wgt::ShaderStages {
+    match ty {
+        BindingLayoutType::StorageBuffer { read_only: false } => wgt::ShaderStages::FRAGMENT,
+        _ => wgt::ShaderStages::VERTEX | wgt::ShaderStages::FRAGMENT,
+    }
+}
+
+/// Layout entries for a caller-provided layout spec
+///
+/// Unlike reflected layouts, nothing is inferred: textures are filterable
+/// float textures of `expected_dimension` (2D if unset) and samplers are
+/// filtering samplers. Only uniform buffers may take a dynamic offset.
+pub fn explicit_layout_entries(spec: &[BindingLayoutEntry]) -> Result<Vec<wgt::BindGroupLayoutEntry>> {
+    let mut seen = std::collections::HashSet::new();
+    spec.iter()
+        .map(|entry| {
+            if !seen.insert(entry.binding) {
+                return Err(BasaltError::invalid_parameter(
+                    "bindings",
+                    format!("binding {} is declared more than once", entry.binding),
+                ));
+            }
+            if entry.has_dynamic_offset && entry.ty != BindingLayoutType::UniformBuffer {
+                return Err(BasaltError::invalid_parameter(
+                    "types",
+                    format!("binding {} is a {:?}; only uniform buffers take dynamic offsets", entry.binding, entry.ty),
+                ));
+            }
+            let ty = match entry.ty {
+                BindingLayoutType::Texture => wgt::BindingType::Texture {
+                    sample_type: wgt::TextureSampleType::Float { filterable: true },
+                    view_dimension: entry.expected_dimension.unwrap_or(wgt::TextureViewDimension::D2),
+                    multisampled: false,
+                },
+                BindingLayoutType::Sampler => wgt::BindingType::Sampler(wgt::SamplerBindingType::Filtering),
+                BindingLayoutType::UniformBuffer | BindingLayoutType::StorageBuffer { .. } => wgt::BindingType::Buffer {
+                    ty: match entry.ty {
+                        BindingLayoutType::StorageBuffer { read_only } => wgt::BufferBindingType::Storage { read_only },
+                        _ => wgt::BufferBindingType::Uniform,
+                    },
+                    has_dynamic_offset: entry.has_dynamic_offset,
+                    min_binding_size: entry.min_binding_size.and_then(NonZero::new),
+                },
+            };
+            Ok(wgt::BindGroupLayoutEntry {
+                binding: entry.binding,
+                visibility: binding_visibility(entry.ty),
+                ty,
+                count: None,
+            })
+        })
+        .collect()
+}
+
+/// Check explicit bind group entries against their layout spec
+///
+/// Every layout binding needs exactly one entry of a matching resource kind.
+/// Dynamic uniform bindings need an explicit size: it is the window each
+/// dynamic offset moves across the buffer.
+pub fn validate_explicit_entries(layout: &[BindingLayoutEntry], entries: &[(u32, ExplicitResource)]) -> Result<()> {
+    for (i, (binding, resource)) in entries.iter().enumerate() {
+        let Some(expected) = layout.iter().find(|l| l.binding == *binding) else {
+            return Err(BasaltError::invalid_parameter(
+                "bindings",
+                format!("binding {} is not declared by the layout", binding),
+            ));
+        };
+        if !resource.accepts(expected.ty) {
+            return Err(BasaltError::invalid_parameter(
+                "resources",
+                format!("binding {} expects {:?} but was given {:?}", binding, expected.ty, resource),
+            ));
+        }
+        if expected.has_dynamic_offset && matches!(resource, ExplicitResource::Buffer { size: None, .. }) {
+            return Err(BasaltError::invalid_parameter(
+                "sizes",
+                format!("dynamic uniform binding {} needs an explicit size", binding),
+            ));
+        }
+        if entries[..i].iter().any(|(other, _)| other == binding) {
+            return Err(BasaltError::invalid_parameter(
+                "bindings",
+                format!("binding {} is bound more than once", binding),
+            ));
+        }
+    }
+    if let Some(missing) = layout.iter().find(|l| !entries.iter().any(|(b, _)| *b == l.binding)) {
+        return Err(BasaltError::invalid_parameter(
+            "bindings",
+            format!("layout binding {} ({:?}) has no entry", missing.binding, missing.ty),
+        ));
+    }
+    Ok(())
+}
+
+/// Create a bind group layout from a caller-provided spec
+pub fn create_explicit_layout(
+    context: &BasaltContext,
+    device_id: id::DeviceId,
+    spec: &[BindingLayoutEntry],
+) -> Result<id::BindGroupLayoutId> {
+    let layout_desc = binding_model::BindGroupLayoutDescriptor {
+        label: Some(Cow::Borrowed("Bassalt Explicit Bind Group Layout")),
+        entries: Cow::Owned(explicit_layout_entries(spec)?),
+    };
+
+    let (layout_id, error) = context.inner().device_create_bind_group_layout(device_id, &layout_desc, None);
+    if let Some(e) = error {
+        return Err(BasaltError::resource_creation("explicit bind group layout", format!("{:?}", e)));
+    }
+    Ok(layout_id)
+}
+
+/// Create a bind group with caller-chosen binding slots, bypassing name matching
+pub fn create_explicit_bind_group(
+    context: &BasaltContext,
+    device_id: id::DeviceId,
+    layout_id: id::BindGroupLayoutId,
+    layout: &[BindingLayoutEntry],
+    entries: &[(u32, ExplicitResource)],
+) -> Result<id::BindGroupId> {
+    validate_explicit_entries(layout, entries)?;
+
+    let bind_entries = entries
+        .iter()
+        .map(|(binding, resource)| binding_model::BindGroupEntry {
+            binding: *binding,
+            resource: match *resource {
+                ExplicitResource::TextureView(view_id) => binding_model::BindingResource::TextureView(view_id),
+                ExplicitResource::Sampler(sampler_id) => binding_model::BindingResource::Sampler(sampler_id),
+                ExplicitResource::Buffer { buffer_id, offset, size } => {
+                    binding_model::BindingResource::Buffer(binding_model::BufferBinding {
+                        buffer: buffer_id,
+                        offset,
+                        size,
+                    })
+                }
+            },
+        })
+        .collect();
+
+    let bind_group_desc = binding_model::BindGroupDescriptor {
+        label: Some(Cow::Borrowed("Bassalt Explicit Bind Group")),
+        layout: layout_id,
+        entries: Cow::Owned(bind_entries),
+    };
+
+    let (bind_group_id, error) = context.inner().device_create_bind_group(device_id, &bind_group_desc, None);
+    if let Some(e) = error {
+        return Err(BasaltError::resource_creation("explicit bind group", format!("{:?}", e)));
+    }
+    Ok(bind_group_id)
+}
+
+/// A resource a bind group entry refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoundResource {
+    Texture(id::TextureId),
+    TextureView(id::TextureViewId),
+    Sampler(id::SamplerId),
+    Buffer { buffer_id: id::BufferId, offset: u64, size: u64 },
+}
+
+/// Bind group identity: its layout plus `(binding, resource)` pairs in binding order
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BindGroupKey {
+    pub layout_id: id::BindGroupLayoutId,
+    pub entries: Vec<(u32, BoundResource)>,
+}
+
+impl BindGroupKey {
+    fn references(&self, resource: impl Fn(&BoundResource) -> bool) -> bool {
+        self.entries.iter().any(|(_, bound)| resource(bound))
+    }
+}
+
+/// A cached bind group and the handle it was registered under
+#[derive(Debug, Clone, Copy)]
+pub struct CachedBindGroup {
+    pub bind_group_id: id::BindGroupId,
+    pub handle: u64,
+}
+
+/// Bind group cache statistics
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BindGroupCacheStats {
+    /// Number of requests served from the cache
+    pub hits: usize,
+    /// Number of requests that created a new bind group
+    pub misses: usize,
+    /// Bind groups dropped because a resource they use was destroyed
+    pub invalidated: usize,
+    /// Total bind groups cached
+    pub total_bind_groups: usize,
+}
+
+/// Bind groups cached at once before the cache is flushed
+///
+/// Keys include uniform offsets, so a stream of distinct offsets would
+/// otherwise grow the cache without bound.
+pub const MAX_CACHED_BIND_GROUPS: usize = 4096;
+
+/// Cache of bind groups keyed by layout and bound resources
+///
+/// Minecraft binds the same texture, sampler and uniform set for thousands
+/// of draws; each distinct set gets one bind group and handle. Destroying a
+/// texture or buffer drops the bind groups that use it, and their handles.
+///
+/// Passes still open, or recorded into the frame but not yet submitted, may
+/// still use an evicted bind group, so evictions are parked until
+/// `release_pending` is called after a frame submit with no pass open.
+pub struct BindGroupCache {
+    bind_groups: Mutex<HashMap<BindGroupKey, CachedBindGroup>>,
+    pending_release: Mutex<Vec<CachedBindGroup>>,
+    stats: Mutex<BindGroupCacheStats>,
+}
+
+impl BindGroupCache {
+    /// Create an empty bind group cache
+    pub fn new() -> Self {
+        Self {
+            bind_groups: Mutex::new(HashMap::new()),
+            pending_release: Mutex::new(Vec::new()),
+            stats: Mutex::new(BindGroupCacheStats::default()),
+        }
+    }
+
+    /// Get the bind group for `key`, creating and registering it on a miss
+    pub fn get_or_create(
+        &self,
+        key: BindGroupKey,
+        create: impl FnOnce() -> Result<id::BindGroupId>,
+    ) -> Result<CachedBindGroup> {
+        let mut bind_groups = self.bind_groups.lock();
+        if let Some(cached) = bind_groups.get(&key) {
+            self.stats.lock().hits += 1;
+            return Ok(*cached);
+        }
+
+        let bind_group_id = create()?;
+        if bind_groups.len() >= MAX_CACHED_BIND_GROUPS {
+            log::debug!("Bind group cache full ({} entries), flushing", bind_groups.len());
+            self.pending_release.lock().extend(bind_groups.drain().map(|(_, cached)| cached));
+        }
+        let cached = CachedBindGroup { bind_group_id, handle: HANDLES.insert_bind_group(bind_group_id) };
+        bind_groups.insert(key, cached);
+
+        let mut stats = self.stats.lock();
+        stats.misses += 1;
+        stats.total_bind_groups = bind_groups.len();
+        Ok(cached)
+    }
+
+    /// Drop cached bind groups that use `texture_id`
+    pub fn invalidate_texture(&self, texture_id: id::TextureId) {
+        self.invalidate(|bound| matches!(bound, BoundResource::Texture(id) if *id == texture_id));
+    }
+
+    /// Drop cached bind groups that use any range of `buffer_id`
+    pub fn invalidate_buffer(&self, buffer_id: id::BufferId) {
+        self.invalidate(|bound| matches!(bound, BoundResource::Buffer { buffer_id: id, .. } if *id == buffer_id));
+    }
+
+    fn invalidate(&self, uses: impl Fn(&BoundResource) -> bool) {
+        let mut bind_groups = self.bind_groups.lock();
+        let mut pending = self.pending_release.lock();
+        let before = bind_groups.len();
+        bind_groups.retain(|key, cached| {
+            let stale = key.references(&uses);
+            if stale {
+                pending.push(*cached);
+            }
+            !stale
+        });
+
+        let mut stats = self.stats.lock();
+        stats.invalidated += before - bind_groups.len();
+        stats.total_bind_groups = bind_groups.len();
+    }
+
+    /// Drop the bind groups evicted since the last call
+    ///
+    /// Call only once every pass that could reference them has ended and been submitted.
+    pub fn release_pending(&self, context: &BasaltContext) {
+        for cached in self.pending_release.lock().drain(..) {
+            release(context, cached);
+        }
+    }
+
+    /// Get cache statistics
+    pub fn stats(&self) -> BindGroupCacheStats {
+        *self.stats.lock()
+    }
+}
+
+impl Default for BindGroupCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Unregister a cached bind group's handle and drop it
+fn release(context: &BasaltContext, cached: CachedBindGroup) {
+    HANDLES.remove_bind_group(cached.handle);
+    context.inner().bind_group_drop(cached.bind_group_id);
+}
+
+/// Convert dynamic offsets from JNI, checking each against the device's
+/// `min_uniform_buffer_offset_alignment`
+pub fn validate_dynamic_offsets(offsets: &[i64], alignment: u32) -> Result<Vec<u32>> {
+    let alignment = alignment.max(1);
+    offsets
+        .iter()
+        .enumerate()
+        .map(|(i, &offset)| {
+            let offset = u32::try_from(offset).map_err(|_| {
+                BasaltError::invalid_parameter("offsets", format!("offset {} ({}) is out of range", i, offset))
+            })?;
+            if !offset.is_multiple_of(alignment) {
+                return Err(BasaltError::invalid_parameter(
+                    "offsets",
+                    format!("offset {} ({}) is not a multiple of {}", i, offset, alignment),
+                ));
+            }
+            Ok(offset)
+        })
+        .collect()
+}
+
+/// Whether a `view` texture view can stand in for an `expected` layout dimension
+///
+/// Views of layered 2D textures are recreated with the layout's dimension over
+/// all `texture_layers` layers, so 2D, 2D array and cube views can stand in for
+/// one another when the layer count fits: one layer for 2D, six for a cube and
+/// a multiple of six for a cube array. 1D and 3D views must match exactly.
+pub fn view_dimension_compatible(
+    view: wgt::TextureViewDimension,
+    expected: wgt::TextureViewDimension,
+    texture_layers: u32,
+) -> bool {
+    use wgt::TextureViewDimension as D;
+    match (view, expected) {
+        _ if view == expected => true,
+        (D::D2 | D::D2Array | D::Cube | D::CubeArray, D::D2) => texture_layers == 1,
+        (D::D2 | D::D2Array | D::Cube | D::CubeArray, D::D2Array) => true,
+        (D::D2 | D::D2Array | D::Cube | D::CubeArray, D::Cube) => texture_layers == 6,
+        (D::D2 | D::D2Array | D::Cube | D::CubeArray, D::CubeArray) => texture_layers > 0 && texture_layers.is_multiple_of(6),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_dynamic_offsets() {
+        assert_eq!(validate_dynamic_offsets(&[0, 256, 1024], 256).unwrap(), vec![0, 256, 1024]);
+        assert!(validate_dynamic_offsets(&[0, 128], 256).is_err());
+        assert!(validate_dynamic_offsets(&[-256], 256).is_err());
+        assert!(validate_dynamic_offsets(&[1 << 32], 256).is_err());
+        assert!(validate_dynamic_offsets(&[], 256).unwrap().is_empty());
+    }
+
+    fn spec(binding: u32, ty: BindingLayoutType) -> BindingLayoutEntry {
+        BindingLayoutEntry {
+            binding,
+            ty,
+            min_binding_size: None,
+            has_dynamic_offset: false,
+            expected_dimension: None,
+            comparison: false,
+            variable_name: None,
+        }
+    }
+
+    fn view_info(id: id::TextureViewId, texture_id: id::TextureId, format: wgt::TextureFormat) -> TextureViewInfo {
+        TextureViewInfo {
+            id,
+            dimension: wgt::TextureViewDimension::D2,
+            texture_id,
+            texture_layers: 1,
+            format,
+            sample_count: 1,
+            aspect: wgt::TextureAspect::All,
+            width: 64,
+            height: 64,
+        }
+    }
+
+    #[test]
+    fn test_explicit_layout_and_bind_group() {
+        let layout = vec![
+            BindingLayoutEntry {
+                expected_dimension: Some(wgt::TextureViewDimension::D2Array),
+                ..spec(0, BindingLayoutType::Texture)
+            },
+            spec(1, BindingLayoutType::Sampler),
+            BindingLayoutEntry { min_binding_size: Some(64), ..spec(4, BindingLayoutType::UniformBuffer) },
+        ];
+
+        let entries = explicit_layout_entries(&layout).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(matches!(entries[0].ty,
+            wgt::BindingType::Texture { view_dimension: wgt::TextureViewDimension::D2Array, .. }));
+        assert!(matches!(entries[1].ty, wgt::BindingType::Sampler(wgt::SamplerBindingType::Filtering)));
+        assert!(matches!(entries[2].ty, wgt::BindingType::Buffer {
+            ty: wgt::BufferBindingType::Uniform, min_binding_size: Some(size), .. } if size.get() == 64));
+        assert!(explicit_layout_entries(&[spec(0, BindingLayoutType::Texture), spec(0, BindingLayoutType::Sampler)]).is_err());
+        let dynamic = BindingLayoutEntry { has_dynamic_offset: true, ..spec(0, BindingLayoutType::UniformBuffer) };
+        assert!(matches!(explicit_layout_entries(&[dynamic]).unwrap()[0].ty,
+            wgt::BindingType::Buffer { has_dynamic_offset: true, .. }));
+        let dynamic_sampler = BindingLayoutEntry { has_dynamic_offset: true, ..spec(0, BindingLayoutType::Sampler) };
+        assert!(explicit_layout_entries(&[dynamic_sampler]).is_err());
+
+        let view = ExplicitResource::TextureView(id::TextureViewId::zip(1, 1));
+        let sampler = ExplicitResource::Sampler(id::SamplerId::zip(1, 1));
+        let buffer = ExplicitResource::Buffer { buffer_id: id::BufferId::zip(1, 1), offset: 256, size: Some(64) };
+
+        // Entries may come in any order
+        assert!(validate_explicit_entries(&layout, &[(4, buffer), (0, view), (1, sampler)]).is_ok());
+
+        let err = validate_explicit_entries(&layout, &[(0, sampler), (1, sampler), (4, buffer)]).unwrap_err();
+        assert!(err.to_string().contains("binding 0 expects Texture"), "{}", err);
+        let err = validate_explicit_entries(&layout, &[(0, view), (1, sampler)]).unwrap_err();
+        assert!(err.to_string().contains("layout binding 4"), "{}", err);
+        assert!(validate_explicit_entries(&layout, &[(0, view), (1, sampler), (4, buffer), (5, buffer)]).is_err());
+        assert!(validate_explicit_entries(&layout, &[(0, view), (0, view), (1, sampler), (4, buffer)]).is_err());
+    }
+
+    #[test]
+    fn test_shadow_sampler_pcf_binding() {
+        const VERTEX: &str = "
+            @vertex
+            fn main(@location(0) pos: vec3<f32>) -> @builtin(position) vec4<f32> {
+                return vec4<f32>(pos, 1.0);
+            }
+        ";
+        const PCF_FRAGMENT: &str = "
+            @group(0) @binding(0) var shadow_map: texture_depth_2d;
+            @group(0) @binding(1) var shadow_sampler: sampler_comparison;
+            @fragment
+            fn main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+                let lit = textureSampleCompare(shadow_map, shadow_sampler, pos.xy / 64.0, pos.z);
+                return vec4<f32>(vec3<f32>(lit), 1.0);
+            }
+        ";
+
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, _queue_id) = context.create_noop_device();
+        let global = context.inner();
+
+        let vertex = crate::shader::parse_wgsl_named(VERTEX, "pcf").unwrap();
+        let fragment = crate::shader::parse_wgsl_named(PCF_FRAGMENT, "pcf").unwrap();
+        let (layout_id, _pipeline_layout_id, binding_layouts, _) =
+            crate::create_layout_from_shaders(&context, device_id, &vertex, &fragment, 0, None).unwrap();
+        assert_eq!(binding_layouts.len(), 2);
+        assert!(binding_layouts.iter().all(|layout| layout.comparison));
+
+        let sampler_desc = crate::sampler::SamplerDescriptor::shadow();
+        assert_eq!(sampler_desc.compare, Some(wgt::CompareFunction::LessEqual));
+        let (sampler_id, error) = global.device_create_sampler(device_id, &sampler_desc.to_wgpu(), None);
+        assert!(error.is_none(), "{:?}", error);
+
+        // A stencil format forces the depth-only view the comparison binding needs
+        let (texture_id, error) = global.device_create_texture(
+            device_id,
+            &wgpu_core::resource::TextureDescriptor {
+                label: Some(Cow::Borrowed("Shadow Map")),
+                size: wgt::Extent3d { width: 64, height: 64, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgt::TextureDimension::D2,
+                format: wgt::TextureFormat::Depth24PlusStencil8,
+                usage: wgt::TextureUsages::RENDER_ATTACHMENT | wgt::TextureUsages::TEXTURE_BINDING,
+                view_formats: vec![],
+            },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+        let (view_id, error) = global.texture_create_view(
+            texture_id,
+            &wgpu_core::resource::TextureViewDescriptor::default(),
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+
+        let bind_group = BindGroupBuilder::new(context.clone(), device_id)
+            .add_texture(0, &view_info(view_id, texture_id, wgt::TextureFormat::Depth24PlusStencil8), Some(sampler_id))
+            .build_with_layout(layout_id, &binding_layouts);
+        assert!(bind_group.is_ok(), "{:?}", bind_group.err());
+    }
+
+    #[test]
+    fn test_view_dimension_mismatch_rejected() {
+        const VERTEX: &str = "
+            @vertex
+            fn main(@location(0) pos: vec3<f32>) -> @builtin(position) vec4<f32> {
+                return vec4<f32>(pos, 1.0);
+            }
+        ";
+        const SKY_FRAGMENT: &str = "
+            @group(0) @binding(0) var sky: texture_cube<f32>;
+            @group(0) @binding(1) var sky_sampler: sampler;
+            @fragment
+            fn main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+                return textureSample(sky, sky_sampler, pos.xyz);
+            }
+        ";
+
+        use wgt::TextureViewDimension as D;
+        assert!(view_dimension_compatible(D::D2Array, D::Cube, 6));
+        assert!(!view_dimension_compatible(D::D2Array, D::Cube, 4));
+        assert!(view_dimension_compatible(D::D2Array, D::CubeArray, 12));
+        assert!(!view_dimension_compatible(D::D2Array, D::CubeArray, 8));
+        assert!(view_dimension_compatible(D::D2Array, D::D2, 1));
+        assert!(!view_dimension_compatible(D::Cube, D::D2, 6));
+        assert!(!view_dimension_compatible(D::D2, D::Cube, 1));
+        assert!(!view_dimension_compatible(D::D3, D::D2, 1));
+
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, _queue_id) = context.create_noop_device();
+        let global = context.inner();
+
+        let vertex = crate::shader::parse_wgsl_named(VERTEX, "sky").unwrap();
+        let fragment = crate::shader::parse_wgsl_named(SKY_FRAGMENT, "sky").unwrap();
+        let (layout_id, _pipeline_layout_id, binding_layouts, _) =
+            crate::create_layout_from_shaders(&context, device_id, &vertex, &fragment, 0, None).unwrap();
+
+        let (sampler_id, error) = global.device_create_sampler(
+            device_id,
+            &crate::sampler::SamplerDescriptor::default().to_wgpu(),
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+        let (texture_id, error) = global.device_create_texture(
+            device_id,
+            &wgpu_core::resource::TextureDescriptor {
+                label: Some(Cow::Borrowed("Flat Sky")),
+                size: wgt::Extent3d { width: 16, height: 16, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgt::TextureDimension::D2,
+                format: wgt::TextureFormat::Rgba8Unorm,
+                usage: wgt::TextureUsages::TEXTURE_BINDING,
+                view_formats: vec![],
+            },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+        let (view_id, error) = global.texture_create_view(
+            texture_id,
+            &wgpu_core::resource::TextureViewDescriptor::default(),
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+
+        let error = BindGroupBuilder::new(context.clone(), device_id)
+            .add_texture(0, &view_info(view_id, texture_id, wgt::TextureFormat::Rgba8Unorm), Some(sampler_id))
+            .build_with_layout(layout_id, &binding_layouts)
+            .unwrap_err();
+        assert!(matches!(error, BasaltError::TextureDimensionMismatch { binding: 0, .. }), "{:?}", error);
+        assert_eq!(
+            error.to_string(),
+            "Texture dimension mismatch at binding 0: the shader expects a Cube view, got D2"
+        );
+    }
+
+    #[test]
+    fn test_storage_buffer_bindings() {
+        const VERTEX: &str = "
+            @group(0) @binding(0) var<storage, read> offsets: array<vec4<f32>>;
+            @vertex
+            fn main(@builtin(vertex_index) index: u32, @location(0) pos: vec3<f32>) -> @builtin(position) vec4<f32> {
+                return vec4<f32>(pos, 1.0) + offsets[index];
+            }
+        ";
+        const FRAGMENT: &str = "
+            @group(0) @binding(1) var<storage, read_write> fragment_count: array<atomic<u32>>;
+            @fragment
+            fn main() -> @location(0) vec4<f32> {
+                atomicAdd(&fragment_count[0], 1u);
+                return vec4<f32>(1.0);
+            }
+        ";
+
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, _queue_id) = context.create_noop_device();
+        let global = context.inner();
+
+        let vertex = crate::shader::parse_wgsl_named(VERTEX, "storage").unwrap();
+        let fragment = crate::shader::parse_wgsl_named(FRAGMENT, "storage").unwrap();
+        let (layout_id, _pipeline_layout_id, binding_layouts, _) =
+            crate::create_layout_from_shaders(&context, device_id, &vertex, &fragment, 0, None).unwrap();
+        let types: Vec<_> = binding_layouts.iter().map(|layout| (layout.binding, layout.ty)).collect();
+        assert_eq!(types, [
+            (0, BindingLayoutType::StorageBuffer { read_only: true }),
+            (1, BindingLayoutType::StorageBuffer { read_only: false }),
+        ]);
+        assert_eq!(binding_visibility(types[1].1), wgt::ShaderStages::FRAGMENT);
+
+        let (buffer_id, error) = global.device_create_buffer(
+            device_id,
+            &wgt::BufferDescriptor {
+                label: None,
+                size: 1024,
+                usage: wgt::BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+
+        let builder = BindGroupBuilder::new(context.clone(), device_id)
+            .add_storage_buffer(0, buffer_id, 0, 512)
+            .add_storage_buffer(1, buffer_id, 512, 256);
+        assert_eq!(builder.cache_key(layout_id).entries.len(), 2);
+        let bind_group = builder.build_with_layout(layout_id, &binding_layouts);
+        assert!(bind_group.is_ok(), "{:?}", bind_group.err());
+
+        let writable = BindingLayoutType::StorageBuffer { read_only: false };
+        let entries = explicit_layout_entries(&[spec(0, writable)]).unwrap();
+        assert!(matches!(entries[0].ty, wgt::BindingType::Buffer {
+            ty: wgt::BufferBindingType::Storage { read_only: false }, .. }));
+    }
+
+    #[test]
+    fn test_stencil_aspect_binding() {
+        const VERTEX: &str = "
+            @vertex
+            fn main(@location(0) pos: vec3<f32>) -> @builtin(position) vec4<f32> {
+                return vec4<f32>(pos, 1.0);
+            }
+        ";
+        const STENCIL_FRAGMENT: &str = "
+            @group(0) @binding(0) var stencil_buffer: texture_2d<u32>;
+            @fragment
+            fn main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+                let stencil = textureLoad(stencil_buffer, vec2<i32>(pos.xy), 0).r;
+                return vec4<f32>(f32(stencil) / 255.0);
+            }
+        ";
+
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, _queue_id) = context.create_noop_device();
+        let global = context.inner();
+
+        let vertex = crate::shader::parse_wgsl_named(VERTEX, "stencil").unwrap();
+        let fragment = crate::shader::parse_wgsl_named(STENCIL_FRAGMENT, "stencil").unwrap();
+        let (layout_id, _pipeline_layout_id, binding_layouts, _) =
+            crate::create_layout_from_shaders(&context, device_id, &vertex, &fragment, 0, None).unwrap();
+
+        let (texture_id, error) = global.device_create_texture(
+            device_id,
+            &wgpu_core::resource::TextureDescriptor {
+                label: Some(Cow::Borrowed("Depth Stencil")),
+                size: wgt::Extent3d { width: 64, height: 64, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgt::TextureDimension::D2,
+                format: wgt::TextureFormat::Depth24PlusStencil8,
+                usage: wgt::TextureUsages::RENDER_ATTACHMENT | wgt::TextureUsages::TEXTURE_BINDING,
+                view_formats: vec![],
+            },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+        let stencil_desc = wgpu_core::resource::TextureViewDescriptor {
+            range: wgt::ImageSubresourceRange { aspect: wgt::TextureAspect::StencilOnly, ..Default::default() },
+            ..Default::default()
+        };
+        let (view_id, error) = global.texture_create_view(texture_id, &stencil_desc, None);
+        assert!(error.is_none(), "{:?}", error);
+        let stencil_view = |dimension| TextureViewInfo {
+            dimension,
+            aspect: wgt::TextureAspect::StencilOnly,
+            ..view_info(view_id, texture_id, wgt::TextureFormat::Stencil8)
+        };
+
+        let bind_group = BindGroupBuilder::new(context.clone(), device_id)
+            .add_texture(0, &stencil_view(wgt::TextureViewDimension::D2), None)
+            .build_with_layout(layout_id, &binding_layouts);
+        assert!(bind_group.is_ok(), "{:?}", bind_group.err());
+
+        // Recreating the view for a dimension mismatch keeps the stencil aspect
+        let bind_group = BindGroupBuilder::new(context.clone(), device_id)
+            .add_texture(0, &stencil_view(wgt::TextureViewDimension::D2Array), None)
+            .build_with_layout(layout_id, &binding_layouts);
+        assert!(bind_group.is_ok(), "{:?}", bind_group.err());
+    }
+
+    #[test]
+    fn test_bind_group_cache_hits_and_invalidation() {
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, _queue_id) = context.create_noop_device();
+        let global = context.inner();
+
+        let (layout_id, error) = global.device_create_bind_group_layout(
+            device_id,
+            &wgpu_core::binding_model::BindGroupLayoutDescriptor { label: None, entries: Cow::Borrowed(&[]) },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+        let create = || {
+            let (bind_group_id, error) = global.device_create_bind_group(
+                device_id,
+                &wgpu_core::binding_model::BindGroupDescriptor { label: None, layout: layout_id, entries: Cow::Borrowed(&[]) },
+                None,
+            );
+            assert!(error.is_none(), "{:?}", error);
+            Ok(bind_group_id)
+        };
+
+        // Keys are plain data; the ids only need to be distinct
+        let texture_id = id::TextureId::zip(1, 1);
+        let buffer_id = id::BufferId::zip(1, 1);
+        let textured = BindGroupKey {
+            layout_id,
+            entries: vec![
+                (0, BoundResource::TextureView(id::TextureViewId::zip(1, 1))),
+                (0, BoundResource::Texture(texture_id)),
+                (0, BoundResource::Sampler(id::SamplerId::zip(1, 1))),
+            ],
+        };
+        let uniform = |offset| BindGroupKey {
+            layout_id,
+            entries: vec![(1, BoundResource::Buffer { buffer_id, offset, size: 64 })],
+        };
+
+        let cache = BindGroupCache::new();
+        let first = cache.get_or_create(textured.clone(), create).unwrap();
+        let again = cache.get_or_create(textured.clone(), || panic!("cached bind group was rebuilt")).unwrap();
+        assert_eq!(first.handle, again.handle);
+        let low = cache.get_or_create(uniform(0), create).unwrap();
+        let high = cache.get_or_create(uniform(256), create).unwrap();
+        assert_ne!(low.handle, high.handle);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.total_bind_groups), (1, 3, 3));
+
+        cache.invalidate_buffer(buffer_id);
+        // Evicted bind groups stay alive until the frame that may use them is submitted
+        assert_eq!(HANDLES.get_bind_group(low.handle), Some(low.bind_group_id));
+        let rebuilt = cache.get_or_create(uniform(0), create).unwrap();
+        assert_ne!(rebuilt.handle, low.handle);
+        cache.release_pending(&context);
+        assert!(HANDLES.get_bind_group(low.handle).is_none());
+        assert!(HANDLES.get_bind_group(high.handle).is_none());
+        assert_eq!(HANDLES.get_bind_group(first.handle), Some(first.bind_group_id));
+
+        cache.invalidate_buffer(buffer_id);
+        cache.invalidate_texture(texture_id);
+        cache.release_pending(&context);
+        assert!(HANDLES.get_bind_group(rebuilt.handle).is_none());
+        assert!(HANDLES.get_bind_group(first.handle).is_none());
+        let stats = cache.stats();
+        assert_eq!((stats.invalidated, stats.total_bind_groups), (4, 0));
+    }
+
+    #[test]
+    fn test_layouts_for_groups_after_zero() {
+        const VERTEX: &str = "
+            @group(0) @binding(0) var<uniform> transform: mat4x4<f32>;
+            @vertex
+            fn main(@location(0) pos: vec3<f32>) -> @builtin(position) vec4<f32> {
+                return transform * vec4<f32>(pos, 1.0);
+            }
+        ";
+        const FRAGMENT: &str = "
+            @group(2) @binding(0) var albedo: texture_2d<f32>;
+            @group(2) @binding(1) var albedo_sampler: sampler;
+            @fragment
+            fn main() -> @location(0) vec4<f32> {
+                return textureSample(albedo, albedo_sampler, vec2<f32>(0.5));
+            }
+        ";
+        const STORAGE_TEXTURE_FRAGMENT: &str = "
+            @group(1) @binding(3) var output: texture_storage_2d<rgba8unorm, write>;
+            @fragment
+            fn main() -> @location(0) vec4<f32> {
+                textureStore(output, vec2<i32>(0), vec4<f32>(1.0));
+                return vec4<f32>(1.0);
+            }
+        ";
+
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, _queue_id) = context.create_noop_device();
+
+        let vertex = crate::shader::parse_wgsl_named(VERTEX, "groups").unwrap();
+        let fragment = crate::shader::parse_wgsl_named(FRAGMENT, "groups").unwrap();
+        let (_layout_id, _pipeline_layout_id, binding_layouts, extra_groups) =
+            crate::create_layout_from_shaders(&context, device_id, &vertex, &fragment, 0, None).unwrap();
+        assert_eq!(binding_layouts.len(), 1);
+        assert_eq!(binding_layouts[0].variable_name.as_deref(), Some("transform"));
+
+        // Group 1 is a gap and gets an empty layout so group 2 stays at its index
+        let groups: Vec<_> = extra_groups.iter().map(|extra| extra.group).collect();
+        assert_eq!(groups, [1, 2]);
+        assert!(extra_groups[0].entries.is_empty());
+        let types: Vec<_> = extra_groups[1].entries.iter().map(|entry| (entry.binding, entry.ty)).collect();
+        assert_eq!(types, [(0, BindingLayoutType::Texture), (1, BindingLayoutType::Sampler)]);
+
+        // Handles are registered once per pipeline; a cache hit gets the same ones back
+        let cache = crate::pipeline_registry::PipelineCache::new();
+        let pipeline_id = wgpu_core::id::RenderPipelineId::zip(0, 1);
+        let registered = cache.extra_group_layouts(&context, pipeline_id, extra_groups);
+        let textured = HANDLES.get_bind_group_layout_info(registered[1].layout_handle).unwrap();
+        assert_eq!(textured.entries.len(), 2);
+        let (_, _, _, again) =
+            crate::create_layout_from_shaders(&context, device_id, &vertex, &fragment, 0, None).unwrap();
+        assert_eq!(cache.extra_group_layouts(&context, pipeline_id, again), registered);
+
+        let storage = crate::shader::parse_wgsl_named(STORAGE_TEXTURE_FRAGMENT, "storage_texture").unwrap();
+        let error = crate::create_layout_from_shaders(&context, device_id, &vertex, &storage, 0, None).unwrap_err();
+        assert!(error.to_string().contains("output"), "{}", error);
+    }
+}
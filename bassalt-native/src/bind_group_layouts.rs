@@ -184,7 +184,7 @@ impl SharedLayoutCache {
         if let Some(e) = error {
             log::error!("Failed to create bind group layout '{}': {:?}", label, e);
             // Return invalid ID - caller should handle this
-            return unsafe { std::mem::transmute(1u64) };
+            return unsafe { std::mem::transmute::<u64, id::BindGroupLayoutId>(1u64) };
         }
 
         // Cache the new layout
@@ -458,7 +458,7 @@ impl<'a> BindGroupLayoutBuilder<'a> {
             ty: wgt::BindingType::Buffer {
                 ty: wgt::BufferBindingType::Uniform,
                 has_dynamic_offset: false,
-                min_binding_size: min_binding_size.map(std::num::NonZero::new).flatten(),
+                min_binding_size: min_binding_size.and_then(std::num::NonZero::new),
             },
             count: None,
         });
@@ -510,7 +510,7 @@ impl<'a> BindGroupLayoutBuilder<'a> {
                 log::error!("Failed to create bind group layout '{}': {:?}", self.label, e);
                 // Use transmute to create an invalid ID - this is only for error recovery
                 // The caller will handle the actual error, this just prevents a crash here
-                unsafe { std::mem::transmute(1u64) }
+                unsafe { std::mem::transmute::<u64, id::BindGroupLayoutId>(1u64) }
             } else {
                 log::debug!("Created bind group layout '{}' with {} entries", self.label, descriptor.entries.len());
                 layout_id
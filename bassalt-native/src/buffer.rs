@@ -2,6 +2,8 @@
 
 use wgpu_types as wgt;
 
+use crate::error::{BasaltError, Result};
+
 /// Buffer descriptor for creating buffers
 #[derive(Debug, Clone)]
 pub struct BufferDescriptor {
@@ -21,3 +23,30 @@ impl Default for BufferDescriptor {
         }
     }
 }
+
+/// Check a `clear_buffer` range and resolve its size
+///
+/// `size` of `None` clears from `offset` to the end of the buffer. WebGPU
+/// clears whole words, so `offset` and the size must be multiples of 4.
+pub fn clear_range(buffer_size: u64, offset: u64, size: Option<u64>) -> Result<u64> {
+    if offset > buffer_size {
+        return Err(BasaltError::invalid_parameter(
+            "offset",
+            format!("offset {} is past the end of the {} byte buffer", offset, buffer_size),
+        ));
+    }
+    let size = size.unwrap_or(buffer_size - offset);
+    if !offset.is_multiple_of(4) || !size.is_multiple_of(4) {
+        return Err(BasaltError::invalid_parameter(
+            "size",
+            format!("clear range {}+{} is not 4-byte aligned", offset, size),
+        ));
+    }
+    if size > buffer_size - offset {
+        return Err(BasaltError::invalid_parameter(
+            "size",
+            format!("clear range {}+{} exceeds the {} byte buffer", offset, size, buffer_size),
+        ));
+    }
+    Ok(size)
+}
@@ -1,12 +1,62 @@
 //! Global WebGPU context wrapper
 
 use std::sync::Arc;
-use parking_lot::RwLock;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use parking_lot::{ReentrantMutex, ReentrantMutexGuard, RwLock};
 use std::collections::HashMap;
 use wgpu_core::global::Global;
 use wgpu_core::id;
 use wgpu_types as wgt;
 
+/// Environment variable limiting the backends, e.g. `vulkan`, `dx12`, `metal`, `gl` or `vulkan,gl`
+pub const BACKEND_ENV_VAR: &str = "BASALT_BACKEND";
+
+/// Debug switch that serializes command recording and queue submission
+///
+/// When enabled, every path that records and submits commands holds one
+/// process-wide lock, so threading can be ruled out when isolating a bug.
+/// Reentrant, because some submit paths call others.
+pub struct SubmissionSerializer {
+    enabled: AtomicBool,
+    lock: ReentrantMutex<()>,
+    /// Times a thread had to wait for another's submission
+    contended: AtomicU64,
+}
+
+impl SubmissionSerializer {
+    fn new() -> Self {
+        Self { enabled: AtomicBool::new(false), lock: ReentrantMutex::new(()), contended: AtomicU64::new(0) }
+    }
+
+    /// Hold the submission lock if serialization is on
+    pub fn guard(&self) -> Option<ReentrantMutexGuard<'_, ()>> {
+        if !self.enabled.load(Ordering::Acquire) {
+            return None;
+        }
+        if let Some(guard) = self.lock.try_lock() {
+            return Some(guard);
+        }
+        self.contended.fetch_add(1, Ordering::Relaxed);
+        Some(self.lock.lock())
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Release);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Acquire)
+    }
+
+    /// Times a submission waited on another thread's since startup
+    pub fn contention_count(&self) -> u64 {
+        self.contended.load(Ordering::Relaxed)
+    }
+}
+
+/// Adapter as listed by `BasaltContext::enumerate_adapters`: (name, backend, device type)
+pub type AdapterSummary = (String, wgt::Backend, wgt::DeviceType);
+
 /// Wrapper around the global WebGPU context
 pub struct BasaltContext {
     inner: Arc<Global>,
@@ -15,12 +65,24 @@ pub struct BasaltContext {
     /// This is maintained separately from wgpu-core's internal structures
     /// because we can't reliably query parent texture from a view ID
     view_to_texture_map: RwLock<HashMap<id::TextureViewId, id::TextureId>>,
+    /// Adapter the device was created on, reported by `get_adapter_info`
+    selected_adapter: RwLock<Option<wgt::AdapterInfo>>,
+    /// Optional lock around command recording and submission (off by default)
+    submission: SubmissionSerializer,
 }
 
 impl BasaltContext {
-    /// Create a new Basalt context
+    /// Create a new Basalt context on every backend
     pub fn new() -> Self {
-        log::debug!("Initializing Basalt context");
+        Self::new_with_backends(wgt::Backends::all())
+    }
+
+    /// Create a new Basalt context limited to `backends`
+    ///
+    /// Adapter enumeration and selection only see these backends, which lets
+    /// users force e.g. DX12 over Vulkan to work around driver bugs.
+    pub fn new_with_backends(backends: wgt::Backends) -> Self {
+        log::debug!("Initializing Basalt context (backends: {:?})", backends);
 
         // Enable comprehensive validation and debugging in debug builds
         let flags = if cfg!(debug_assertions) {
@@ -32,7 +94,7 @@ impl BasaltContext {
         };
 
         let instance_desc = wgt::InstanceDescriptor {
-            backends: wgt::Backends::all(),
+            backends,
             flags,
             ..Default::default()
         };
@@ -44,9 +106,47 @@ impl BasaltContext {
             inner: Arc::new(global),
             instance_desc,
             view_to_texture_map: RwLock::new(HashMap::new()),
+            selected_adapter: RwLock::new(None),
+            submission: SubmissionSerializer::new(),
         }
     }
 
+    /// Context on the noop backend, for tests that need real wgpu-core
+    /// objects without a GPU
+    #[cfg(test)]
+    pub fn new_noop() -> Self {
+        let instance_desc = wgt::InstanceDescriptor {
+            backends: wgt::Backends::NOOP,
+            flags: wgt::InstanceFlags::VALIDATION,
+            backend_options: wgt::BackendOptions {
+                noop: wgt::NoopBackendOptions { enable: true },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let global = Global::new("basalt-test", &instance_desc, None);
+
+        Self {
+            inner: Arc::new(global),
+            instance_desc,
+            view_to_texture_map: RwLock::new(HashMap::new()),
+            selected_adapter: RwLock::new(None),
+            submission: SubmissionSerializer::new(),
+        }
+    }
+
+    /// Create a device on a `new_noop` context
+    #[cfg(test)]
+    pub fn create_noop_device(&self) -> (id::DeviceId, id::QueueId) {
+        let adapter_id = self
+            .inner
+            .request_adapter(&wgpu_core::instance::RequestAdapterOptions::default(), wgt::Backends::NOOP, None)
+            .expect("noop adapter");
+        self.inner
+            .adapter_request_device(adapter_id, &wgt::DeviceDescriptor::default(), None, None)
+            .expect("noop device")
+    }
+
     /// Get the inner global context
     pub fn inner(&self) -> &Arc<Global> {
         &self.inner
@@ -73,10 +173,119 @@ impl BasaltContext {
 
     /// Get adapter information as a string
     pub fn get_adapter_info(&self) -> String {
-        format!(
-            "Basalt Renderer (WebGPU)\nAvailable backends: Vulkan, Metal, DX12, OpenGL"
-        )
+        let mut info = format!("Basalt Renderer (WebGPU)\nEnabled backends: {:?}", self.instance_desc.backends);
+        if let Some(adapter) = &*self.selected_adapter.read() {
+            info.push_str(&format!(
+                "\nAdapter: {} ({:?}, {:?}, vendor {}, driver {})",
+                adapter.name,
+                adapter.backend,
+                adapter.device_type,
+                vendor_name(adapter.vendor).map_or_else(|| format!("0x{:04X}", adapter.vendor), str::to_string),
+                driver_version(adapter)
+            ));
+        }
+        log::info!("{}", info.replace('\n', "; "));
+        info
+    }
+
+    /// Serialization switch for recording and submission paths
+    pub fn submission(&self) -> &SubmissionSerializer {
+        &self.submission
+    }
+
+    /// Hold the submission lock for the rest of the scope, when serialization is on
+    pub fn serialize_submission(&self) -> Option<ReentrantMutexGuard<'_, ()>> {
+        self.submission.guard()
     }
+
+    /// Backends adapters are requested from
+    pub fn backends(&self) -> wgt::Backends {
+        self.instance_desc.backends
+    }
+
+    /// Remember the adapter a device was created on, for `get_adapter_info`
+    pub fn set_selected_adapter(&self, adapter_id: id::AdapterId) {
+        *self.selected_adapter.write() = Some(self.inner.adapter_get_info(adapter_id));
+    }
+
+    /// List the adapters on every enabled backend
+    ///
+    /// The same GPU shows up once per backend (e.g. Vulkan and GL). Indices
+    /// select the adapter in `adapter_at`.
+    pub fn enumerate_adapters(&self) -> Vec<AdapterSummary> {
+        self.inner
+            .enumerate_adapters(self.instance_desc.backends)
+            .into_iter()
+            .map(|adapter_id| {
+                let info = self.inner.adapter_get_info(adapter_id);
+                self.inner.adapter_drop(adapter_id);
+                (info.name, info.backend, info.device_type)
+            })
+            .collect()
+    }
+
+    /// Adapter at `index` in `enumerate_adapters` order, or None if out of range
+    pub fn adapter_at(&self, index: usize) -> Option<id::AdapterId> {
+        let mut selected = None;
+        for (i, adapter_id) in self.inner.enumerate_adapters(self.instance_desc.backends).into_iter().enumerate() {
+            if i == index {
+                selected = Some(adapter_id);
+            } else {
+                self.inner.adapter_drop(adapter_id);
+            }
+        }
+        selected
+    }
+}
+
+/// Vendor name for a PCI vendor id
+pub(crate) fn vendor_name(vendor_id: u32) -> Option<&'static str> {
+    match vendor_id {
+        0x1002 | 0x1022 => Some("AMD"),
+        0x10DE => Some("NVIDIA"),
+        0x8086 => Some("Intel"),
+        0x106B => Some("Apple"),
+        0x13B5 => Some("ARM"),
+        0x5143 => Some("Qualcomm"),
+        0x1010 => Some("Imagination Technologies"),
+        0x14E4 => Some("Broadcom"),
+        0x15AD => Some("VMware"),
+        0x1414 => Some("Microsoft"),
+        0x10005 => Some("Mesa"),
+        _ => None,
+    }
+}
+
+/// Driver name and version, e.g. "NVIDIA 550.54.14"
+///
+/// Backends that report neither get "Basalt <crate version>".
+pub(crate) fn driver_version(info: &wgt::AdapterInfo) -> String {
+    let version = format!("{} {}", info.driver, info.driver_info).trim().to_string();
+    if version.is_empty() {
+        format!("Basalt {}", env!("CARGO_PKG_VERSION"))
+    } else {
+        version
+    }
+}
+
+/// Backends selected by `BASALT_BACKEND`, or all of them when it's unset
+pub fn backends_from_env() -> wgt::Backends {
+    parse_backends(std::env::var(BACKEND_ENV_VAR).ok().as_deref())
+}
+
+/// Parse a `BASALT_BACKEND` value; unset or unrecognized values select every backend
+fn parse_backends(value: Option<&str>) -> wgt::Backends {
+    let Some(value) = value.filter(|v| !v.trim().is_empty()) else {
+        return wgt::Backends::all();
+    };
+
+    let backends = wgt::Backends::from_comma_list(value);
+    if backends.is_empty() {
+        log::warn!("{}={} names no known backend, using all backends", BACKEND_ENV_VAR, value);
+        return wgt::Backends::all();
+    }
+    log::info!("{}={} limits adapters to {:?}", BACKEND_ENV_VAR, value, backends);
+    backends
 }
 
 impl Default for BasaltContext {
@@ -84,3 +293,70 @@ impl Default for BasaltContext {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialized_submission() {
+        use std::sync::atomic::AtomicUsize;
+
+        let context = BasaltContext::new_noop();
+        assert!(context.serialize_submission().is_none(), "off by default");
+
+        context.submission().set_enabled(true);
+        let inside = AtomicUsize::new(0);
+        let max_inside = AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..8 {
+                        let _serialized = context.serialize_submission().expect("serialization on");
+                        // Nested submit paths take the lock again without deadlocking
+                        let _nested = context.serialize_submission();
+                        let now = inside.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_inside.fetch_max(now, Ordering::SeqCst);
+                        std::thread::sleep(std::time::Duration::from_millis(1));
+                        inside.fetch_sub(1, Ordering::SeqCst);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(max_inside.load(Ordering::SeqCst), 1, "submissions overlapped");
+        assert!(context.submission().contention_count() > 0, "threads should have waited on each other");
+    }
+
+    #[test]
+    fn test_parse_backends() {
+        assert_eq!(parse_backends(None), wgt::Backends::all());
+        assert_eq!(parse_backends(Some("")), wgt::Backends::all());
+        assert_eq!(parse_backends(Some("vulkan")), wgt::Backends::VULKAN);
+        assert_eq!(parse_backends(Some("DX12")), wgt::Backends::DX12);
+        assert_eq!(parse_backends(Some("metal")), wgt::Backends::METAL);
+        assert_eq!(parse_backends(Some("vulkan, gl")), wgt::Backends::VULKAN | wgt::Backends::GL);
+        assert_eq!(parse_backends(Some("glide")), wgt::Backends::all(), "unknown names fall back to all");
+    }
+
+    #[test]
+    fn test_enumerate_and_select_adapters() {
+        let context = BasaltContext::new_noop();
+
+        let adapters = context.enumerate_adapters();
+        assert_eq!(adapters.len(), 1);
+        assert_eq!(adapters[0].1, wgt::Backend::Noop);
+
+        let adapter_id = context.adapter_at(0).expect("the noop adapter");
+        assert_eq!(context.inner().adapter_get_info(adapter_id).name, adapters[0].0);
+        assert!(context.adapter_at(adapters.len()).is_none());
+
+        assert!(!context.get_adapter_info().contains("Adapter:"));
+        context.set_selected_adapter(adapter_id);
+        assert!(context.get_adapter_info().contains("Noop"));
+
+        // A selected adapter can still create a device
+        let desc = wgt::DeviceDescriptor::default();
+        assert!(context.inner().adapter_request_device(adapter_id, &desc, None, None).is_ok());
+    }
+}
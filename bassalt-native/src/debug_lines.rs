@@ -0,0 +1,359 @@
+//! Debug line rendering - hitbox outlines, chunk borders and other overlays
+//!
+//! Lines are drawn as a `LineList` with culling disabled, depth tested
+//! against the scene but never written. wgpu has no line width: every line
+//! rasterizes one pixel wide, so wider strokes have to be expanded into quads
+//! and drawn through a regular triangle pipeline.
+//!
+//! # Vertex layout
+//!
+//! Each vertex is [`DEBUG_LINE_VERTEX_STRIDE`] bytes: a `Float32x3` world
+//! position followed by an RGBA8 (`Unorm8x4`) color, two vertices per line.
+
+use std::borrow::Cow;
+use std::ops::Range;
+use wgpu_core::command;
+use wgpu_core::id;
+use wgpu_types as wgt;
+
+use crate::context::BasaltContext;
+use crate::error::{BasaltError, Result};
+use crate::resource_handles::TextureInfo;
+
+/// Bytes per debug line vertex: position (3 x f32) + color (4 x u8)
+pub const DEBUG_LINE_VERTEX_STRIDE: u64 = 16;
+
+/// Size of the view-projection uniform (a column-major mat4x4<f32>)
+const VIEW_PROJ_SIZE: u64 = 64;
+
+/// Line pipeline for one color/depth format pair, with its own matrix uniform
+#[derive(Debug, Clone, Copy)]
+pub struct DebugLinePipeline {
+    pub pipeline_id: id::RenderPipelineId,
+    pub bind_group_layout_id: id::BindGroupLayoutId,
+    pub uniform_buffer_id: id::BufferId,
+    pub bind_group_id: id::BindGroupId,
+    pub color_format: wgt::TextureFormat,
+    pub depth_format: Option<wgt::TextureFormat>,
+}
+
+impl DebugLinePipeline {
+    pub fn new(
+        context: &BasaltContext,
+        device_id: id::DeviceId,
+        color_format: wgt::TextureFormat,
+        depth_format: Option<wgt::TextureFormat>,
+        driver_cache: Option<id::PipelineCacheId>,
+    ) -> Result<Self> {
+        if let Some(format) = depth_format {
+            if !format.has_depth_aspect() {
+                return Err(BasaltError::invalid_parameter("depth_format", format!("{:?} has no depth aspect", format)));
+            }
+        }
+
+        let global = context.inner();
+        let bgl_entries = [wgt::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgt::ShaderStages::VERTEX,
+            ty: wgt::BindingType::Buffer {
+                ty: wgt::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: wgt::BufferSize::new(VIEW_PROJ_SIZE),
+            },
+            count: None,
+        }];
+        let (bind_group_layout_id, error) = global.device_create_bind_group_layout(
+            device_id,
+            &wgpu_core::binding_model::BindGroupLayoutDescriptor {
+                label: Some(Cow::Borrowed("Debug Line BGL")),
+                entries: Cow::Borrowed(&bgl_entries),
+            },
+            None,
+        );
+        if let Some(e) = error {
+            return Err(BasaltError::resource_creation("debug line bind group layout", format!("{:?}", e)));
+        }
+
+        let (uniform_buffer_id, error) = global.device_create_buffer(
+            device_id,
+            &wgt::BufferDescriptor {
+                label: Some(Cow::Borrowed("Debug Line View Projection")),
+                size: VIEW_PROJ_SIZE,
+                usage: wgt::BufferUsages::UNIFORM | wgt::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            },
+            None,
+        );
+        if let Some(e) = error {
+            global.bind_group_layout_drop(bind_group_layout_id);
+            return Err(BasaltError::resource_creation("debug line uniform buffer", format!("{:?}", e)));
+        }
+
+        let bg_entries = [wgpu_core::binding_model::BindGroupEntry {
+            binding: 0,
+            resource: wgpu_core::binding_model::BindingResource::Buffer(wgpu_core::binding_model::BufferBinding {
+                buffer: uniform_buffer_id,
+                offset: 0,
+                size: Some(VIEW_PROJ_SIZE),
+            }),
+        }];
+        let (bind_group_id, error) = global.device_create_bind_group(
+            device_id,
+            &wgpu_core::binding_model::BindGroupDescriptor {
+                label: Some(Cow::Borrowed("Debug Line Bind Group")),
+                layout: bind_group_layout_id,
+                entries: Cow::Borrowed(&bg_entries),
+            },
+            None,
+        );
+
+        let fail = |resource: &str, e: String| {
+            global.bind_group_drop(bind_group_id);
+            global.buffer_drop(uniform_buffer_id);
+            global.bind_group_layout_drop(bind_group_layout_id);
+            Err(BasaltError::resource_creation(resource, e))
+        };
+        if let Some(e) = error {
+            return fail("debug line bind group", format!("{:?}", e));
+        }
+
+        let (pipeline_layout_id, error) = global.device_create_pipeline_layout(
+            device_id,
+            &wgpu_core::binding_model::PipelineLayoutDescriptor {
+                label: Some(Cow::Borrowed("Debug Line Pipeline Layout")),
+                bind_group_layouts: Cow::Borrowed(&[bind_group_layout_id]),
+                immediate_size: 0,
+            },
+            None,
+        );
+        if let Some(e) = error {
+            return fail("debug line pipeline layout", format!("{:?}", e));
+        }
+
+        let module = match crate::shader::parse_wgsl_named(include_str!("shaders/debug_lines.wgsl"), "debug_lines") {
+            Ok(module) => module,
+            Err(e) => {
+                global.pipeline_layout_drop(pipeline_layout_id);
+                return fail("debug line shader", e.to_string());
+            }
+        };
+        let (module_id, error) = global.device_create_shader_module(
+            device_id,
+            &wgpu_core::pipeline::ShaderModuleDescriptor {
+                label: Some(Cow::Borrowed("Debug Line Shader")),
+                runtime_checks: wgt::ShaderRuntimeChecks::default(),
+            },
+            wgpu_core::pipeline::ShaderModuleSource::Naga(Cow::Owned(module)),
+            None,
+        );
+        if let Some(e) = error {
+            global.pipeline_layout_drop(pipeline_layout_id);
+            return fail("debug line shader", format!("{:?}", e));
+        }
+
+        let attributes = [
+            wgt::VertexAttribute { format: wgt::VertexFormat::Float32x3, offset: 0, shader_location: 0 },
+            wgt::VertexAttribute { format: wgt::VertexFormat::Unorm8x4, offset: 12, shader_location: 1 },
+        ];
+        let vertex_buffers = [wgpu_core::pipeline::VertexBufferLayout {
+            array_stride: DEBUG_LINE_VERTEX_STRIDE,
+            step_mode: wgt::VertexStepMode::Vertex,
+            attributes: Cow::Borrowed(&attributes),
+        }];
+        let stage = |entry_point: &'static str| wgpu_core::pipeline::ProgrammableStageDescriptor {
+            module: module_id,
+            entry_point: Some(Cow::Borrowed(entry_point)),
+            constants: Default::default(),
+            zero_initialize_workgroup_memory: true,
+        };
+        let (pipeline_id, error) = global.device_create_render_pipeline(
+            device_id,
+            &wgpu_core::pipeline::RenderPipelineDescriptor {
+                label: Some(Cow::Borrowed("Debug Line Pipeline")),
+                layout: Some(pipeline_layout_id),
+                vertex: wgpu_core::pipeline::VertexState {
+                    stage: stage("vs_main"),
+                    buffers: Cow::Borrowed(&vertex_buffers),
+                },
+                primitive: wgt::PrimitiveState {
+                    topology: wgt::PrimitiveTopology::LineList,
+                    cull_mode: None,
+                    ..Default::default()
+                },
+                // Lines sit on top of the geometry they outline, so test but never write depth
+                depth_stencil: depth_format.map(|format| wgt::DepthStencilState {
+                    format,
+                    depth_write_enabled: false,
+                    depth_compare: wgt::CompareFunction::LessEqual,
+                    stencil: wgt::StencilState::default(),
+                    bias: wgt::DepthBiasState::default(),
+                }),
+                multisample: wgt::MultisampleState::default(),
+                fragment: Some(wgpu_core::pipeline::FragmentState {
+                    stage: stage("fs_main"),
+                    targets: Cow::Owned(vec![Some(wgt::ColorTargetState {
+                        format: color_format,
+                        blend: Some(wgt::BlendState::ALPHA_BLENDING),
+                        write_mask: wgt::ColorWrites::ALL,
+                    })]),
+                }),
+                multiview_mask: None,
+                cache: driver_cache,
+            },
+            None,
+        );
+        global.pipeline_layout_drop(pipeline_layout_id);
+        global.shader_module_drop(module_id);
+        if let Some(e) = error {
+            return fail("debug line pipeline", format!("{:?}", e));
+        }
+
+        log::info!("Created debug line pipeline for {:?} / {:?}", color_format, depth_format);
+        Ok(Self {
+            pipeline_id,
+            bind_group_layout_id,
+            uniform_buffer_id,
+            bind_group_id,
+            color_format,
+            depth_format,
+        })
+    }
+
+    /// Record a pass drawing the `vertices` of `vertex_buffer` as lines
+    ///
+    /// Both attachments are loaded and kept; depth is attached read-only. The
+    /// view-projection matrix must already be written to `uniform_buffer_id`.
+    pub fn record(
+        &self,
+        context: &BasaltContext,
+        encoder_id: id::CommandEncoderId,
+        color_view: id::TextureViewId,
+        depth_view: Option<id::TextureViewId>,
+        vertex_buffer: id::BufferId,
+        vertices: Range<u32>,
+    ) -> Result<()> {
+        let global = context.inner();
+        let color_attachments = [Some(command::RenderPassColorAttachment {
+            view: color_view,
+            depth_slice: None,
+            resolve_target: None,
+            load_op: wgt::LoadOp::Load,
+            store_op: wgt::StoreOp::Store,
+        })];
+        let depth_stencil_attachment = depth_view.map(|view| command::RenderPassDepthStencilAttachment {
+            view,
+            depth: command::PassChannel { load_op: None, store_op: None, read_only: true },
+            stencil: command::PassChannel { load_op: None, store_op: None, read_only: true },
+        });
+        let (mut pass, error) = global.command_encoder_begin_render_pass(
+            encoder_id,
+            &command::RenderPassDescriptor {
+                label: Some(Cow::Borrowed("Debug Line Pass")),
+                color_attachments: Cow::Borrowed(&color_attachments),
+                depth_stencil_attachment: depth_stencil_attachment.as_ref(),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+                multiview_mask: None,
+            },
+        );
+        match error {
+            Some(e) => Err(BasaltError::RenderPass(format!("{:?}", e))),
+            None => {
+                let recorded = global
+                    .render_pass_set_pipeline(&mut pass, self.pipeline_id)
+                    .and_then(|_| global.render_pass_set_bind_group(&mut pass, 0, Some(self.bind_group_id), &[]))
+                    .and_then(|_| global.render_pass_set_vertex_buffer(&mut pass, 0, vertex_buffer, 0, None))
+                    .and_then(|_| global.render_pass_draw(&mut pass, vertices.len() as u32, 1, vertices.start, 0))
+                    .map_err(|e| BasaltError::RenderPass(format!("{:?}", e)));
+                // The pass is ended even after a failed command so the encoder stays usable
+                let ended = global.render_pass_end(&mut pass).map_err(|e| BasaltError::RenderPass(format!("{:?}", e)));
+                recorded.and(ended)
+            }
+        }
+    }
+
+    pub fn destroy(&self, context: &BasaltContext) {
+        let global = context.inner();
+        global.render_pipeline_drop(self.pipeline_id);
+        global.bind_group_drop(self.bind_group_id);
+        global.buffer_drop(self.uniform_buffer_id);
+        global.bind_group_layout_drop(self.bind_group_layout_id);
+    }
+}
+
+/// Check that `first_vertex..first_vertex + vertex_count` forms whole lines inside a buffer of `buffer_size` bytes
+pub fn validate_line_vertices(buffer_size: u64, first_vertex: u32, vertex_count: u32) -> Result<()> {
+    if !vertex_count.is_multiple_of(2) {
+        return Err(BasaltError::invalid_parameter(
+            "vertex_count",
+            format!("{} is odd; a line list needs two vertices per line", vertex_count),
+        ));
+    }
+    let end = (first_vertex as u64 + vertex_count as u64) * DEBUG_LINE_VERTEX_STRIDE;
+    if end > buffer_size {
+        return Err(BasaltError::invalid_parameter(
+            "vertex_count",
+            format!("vertices {}..{} end at byte {} past the {}-byte buffer", first_vertex, first_vertex as u64 + vertex_count as u64, end, buffer_size),
+        ));
+    }
+    Ok(())
+}
+
+/// Check that `color` and `depth` are single-sampled, single-layer attachments
+///
+/// The line pipeline is created with one sample and draws through a plain 2D
+/// view, so multisampled or layered targets would only fail inside wgpu.
+pub fn validate_line_targets(color: &TextureInfo, depth: Option<&TextureInfo>) -> Result<()> {
+    for (target, name) in std::iter::once((color, "color")).chain(depth.map(|depth| (depth, "depth"))) {
+        if target.sample_count != 1 {
+            return Err(BasaltError::invalid_parameter(
+                "sample_count",
+                format!("debug lines need a single-sampled {} target, got {} samples", name, target.sample_count),
+            ));
+        }
+        if target.array_layer_count() != 1 || target.dimension != wgt::TextureDimension::D2 {
+            return Err(BasaltError::invalid_parameter(
+                "texture",
+                format!("debug lines need a single-layer 2D {} target", name),
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_line_vertices() {
+        assert!(validate_line_vertices(64, 0, 4).is_ok());
+        assert!(validate_line_vertices(64, 2, 2).is_ok());
+        assert!(validate_line_vertices(64, 0, 0).is_ok());
+        // Half a line, and vertices running past the buffer
+        assert!(validate_line_vertices(64, 0, 3).is_err());
+        assert!(validate_line_vertices(64, 2, 4).is_err());
+        assert!(validate_line_vertices(64, u32::MAX, 2).is_err());
+    }
+
+    #[test]
+    fn test_validate_line_targets() {
+        let target = TextureInfo {
+            id: id::TextureId::zip(1, 1),
+            width: 64,
+            height: 64,
+            depth_or_array_layers: 1,
+            mip_level_count: 1,
+            dimension: wgt::TextureDimension::D2,
+            format: wgt::TextureFormat::Rgba8Unorm,
+            sample_count: 1,
+            is_cube: false,
+        };
+        assert!(validate_line_targets(&target, Some(&target)).is_ok());
+        let multisampled = TextureInfo { sample_count: 4, ..target };
+        assert!(validate_line_targets(&multisampled, None).is_err());
+        assert!(validate_line_targets(&target, Some(&multisampled)).is_err());
+        let layered = TextureInfo { depth_or_array_layers: 6, ..target };
+        assert!(validate_line_targets(&layered, None).is_err());
+    }
+}
@@ -1,28 +1,92 @@
 //! GPU device wrapper - main interface for rendering operations
 
 use std::borrow::Cow;
+use std::path::Path;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use parking_lot::ReentrantMutexGuard;
 use wgpu_core::id;
 use wgpu_types as wgt;
 
 // wgpu 28.0: Import LoadOpDontCare token for DontCare load op
 use wgt::LoadOpDontCare;
 
-use crate::context::BasaltContext;
-use crate::surface::BasaltSurface;
+use crate::context::{driver_version, vendor_name, BasaltContext};
+use crate::surface::{BasaltSurface, SurfaceCapabilities};
 use crate::pipeline_registry::PipelineCache;
-use crate::error::{BasaltError, Result};
+use crate::error::{BasaltError, ErrorFilter, Result};
 use crate::bind_group_layouts::{BindGroupLayouts, SharedLayoutCache};
+use crate::frame_timing::{CpuFrameTimings, CpuTimingKind};
+use crate::texture::{
+    full_view_dimension, resolve_view_range, upload_row_layout, validate_mip_region, LayerTarget, TextureReadback,
+    TextureWrite, ViewRangeRequest,
+};
+use crate::resource_handles::{BufferInfo, TextureInfo};
+use crate::atlas::{AtlasEntryId, AtlasEvictionPolicy, AtlasInsert, AtlasRect, AtlasRepack, TextureAtlas};
+use crate::range_allocator::{RangeAllocator, RangeAllocatorStats, RangeHandle, RangeMove};
+use crate::msaa::{validate_partial_resolve, PartialResolvePipeline, ResolveRect};
+use crate::debug_lines::{validate_line_targets, validate_line_vertices, DebugLinePipeline};
+use crate::render_bundle::RenderBundleRecorder;
+use crate::occlusion_queries::OcclusionQuerySet;
+use crate::pipeline_statistics::PipelineStatisticsQuerySet;
+use crate::timestamp_queries::TimestampQuerySet;
+use crate::staging::{StagingBeltPool, StagingPoolStats};
+use crate::write_batch::{WriteBatch, WriteBatchStats, MAX_BATCHED_BYTES, MAX_BATCHED_WRITE};
+use crate::sampler::{CachedSampler, SamplerCache, SamplerConfig, SamplerDescriptor};
+use crate::bind_group::BindGroupCache;
+use crate::render_pass::{
+    ImmediatesFallback, ImmediatesRing, RenderPassState, IMMEDIATES_FALLBACK_RING_SLOTS, IMMEDIATES_FALLBACK_SLOT_SIZE,
+    LIVE_RENDER_PASSES, MAX_IMMEDIATE_SIZE,
+};
+
+/// Buffer uploads at least this large go through the staging pool
+pub const STAGED_UPLOAD_THRESHOLD: u64 = 256 * 1024;
+
+/// Format of the depth attachment render passes create when Java provides none
+///
+/// Depth-tested pipelines without an explicit depth format target this, so
+/// they match the pass they are drawn in.
+pub const DEFAULT_DEPTH_FORMAT: wgt::TextureFormat = wgt::TextureFormat::Depth32Float;
 
 /// Current swapchain state (for lock-free updates)
 #[derive(Debug, Clone)]
 struct SwapchainState {
     main_framebuffer: Option<id::TextureId>,
+    /// Format and size of `main_framebuffer`, when it's a texture we created
+    main_framebuffer_info: Option<TextureInfo>,
     width: u32,
     height: u32,
 }
 
+/// Outcome of `present_frame`, passed to Java as its discriminant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresentStatus {
+    /// The frame was presented
+    Presented = 0,
+    /// Nothing was presented: there is no surface or no swapchain texture was available
+    Skipped = 1,
+    /// The swapchain went outdated or lost and the surface was reconfigured
+    /// from its stored configuration; the frame is presented if reacquiring worked
+    Reconfigured = 2,
+}
+
+/// Why `record_render_pass` failed
+#[derive(Debug)]
+pub enum RecordPassError {
+    /// Submitting the passes recorded before this one failed
+    Submit(BasaltError),
+    /// The pass, or the data it uploads, was rejected before reaching a submit
+    Recording(BasaltError),
+}
+
+/// Whether a swapchain status means the surface has to be reconfigured
+///
+/// Outdated and lost swapchains are routine after window resizes and display
+/// changes; the surface stays unusable until it's configured again.
+pub fn swapchain_needs_reconfigure(status: &wgt::SurfaceStatus) -> bool {
+    matches!(status, wgt::SurfaceStatus::Outdated | wgt::SurfaceStatus::Lost)
+}
+
 /// Frame-in-flight tracking for proper frame synchronization
 ///
 /// **CRITICAL FIX #2:** Actual GPU synchronization using device.poll()
@@ -93,6 +157,24 @@ impl FrameTracker {
     }
 }
 
+/// Depth texture cache key: (width, height, sample_count)
+type DepthCacheKey = (u32, u32, u32);
+
+/// Source view and bind group cached by the present blit
+struct BlitSource {
+    texture: id::TextureId,
+    layer: LayerTarget,
+    view: id::TextureViewId,
+    bind_group: id::BindGroupId,
+}
+
+impl BlitSource {
+    fn release(self, global: &wgpu_core::global::Global) {
+        global.bind_group_drop(self.bind_group);
+        let _ = global.texture_view_drop(self.view);
+    }
+}
+
 /// Main device wrapper
 pub struct BasaltDevice {
     context: Arc<BasaltContext>,
@@ -101,6 +183,9 @@ pub struct BasaltDevice {
     queue_id: id::QueueId,
     surface: Option<BasaltSurface>,
     limits: wgt::Limits,
+    // 16 where the backend filters anisotropically, otherwise 1
+    max_anisotropy: u16,
+    adapter_info: wgt::AdapterInfo,
     info: String,
     // **CRITICAL FIX #3:** Proper mutex for swapchain state (not lock-free)
     // Lock-free ArcSwap doesn't provide ordering guarantees between:
@@ -108,29 +193,84 @@ pub struct BasaltDevice {
     // - present_frame() reads (getting main framebuffer)
     // Using Mutex ensures proper synchronization and prevents race conditions
     swapchain_state: parking_lot::Mutex<SwapchainState>,
-    swapchain_format: wgt::TextureFormat,
+    // Current surface format, `HDR_SURFACE_FORMAT` while HDR is on
+    swapchain_format: parking_lot::Mutex<wgt::TextureFormat>,
+    // Surface format chosen at creation, presented while HDR is off
+    sdr_swapchain_format: wgt::TextureFormat,
+    hdr_enabled: AtomicBool,
     // Frame-in-flight tracking for synchronization
     frame_tracker: FrameTracker,
     // Cached blit pipeline for format conversion
     blit_bind_group_layout: parking_lot::Mutex<Option<id::BindGroupLayoutId>>,
     blit_pipeline: parking_lot::Mutex<Option<id::RenderPipelineId>>,
+    // View and bind group of the last blit source, reused while the source stays the same
+    blit_source: parking_lot::Mutex<Option<BlitSource>>,
+    // Scissored MSAA resolve pipelines, one per target format
+    partial_resolve_pipelines: parking_lot::Mutex<std::collections::HashMap<wgt::TextureFormat, PartialResolvePipeline>>,
+    // Render bundles destroyed while unsubmitted passes may still execute them
+    pending_bundle_drops: parking_lot::Mutex<Vec<id::RenderBundleId>>,
+    // Debug line pipelines keyed by (color format, depth format)
+    line_pipelines: parking_lot::Mutex<std::collections::HashMap<(wgt::TextureFormat, Option<wgt::TextureFormat>), DebugLinePipeline>>,
+    // Whether the present blit writes alpha 1.0 (see `forces_opaque_present`)
+    force_opaque_present: AtomicBool,
+    // Whether clear colors are decoded from sRGB for sRGB attachments
+    srgb_clear_colors: AtomicBool,
+    // Uniform buffer replacing immediates, created on first use without push constants
+    immediates_fallback: parking_lot::Mutex<Option<ImmediatesFallback>>,
+    // Fallback slots taken by the passes waiting in the frame
+    immediates_ring: parking_lot::Mutex<ImmediatesRing>,
     // Shared bind group layout and pipeline layout for Minecraft rendering
     shared_bind_group_layout: id::BindGroupLayoutId,
     shared_pipeline_layout: id::PipelineLayoutId,
     // Pre-defined bind group layouts (wgpu-mc style)
     pub bind_group_layouts: BindGroupLayouts,
-    // Depth texture cache by dimensions (width, height) -> (texture_id, view_id)
-    depth_texture_cache: parking_lot::Mutex<std::collections::HashMap<(u32, u32), (id::TextureId, id::TextureViewId)>>,
+    // Depth texture cache by (width, height, sample_count) -> (texture_id, view_id)
+    depth_texture_cache: parking_lot::Mutex<std::collections::HashMap<DepthCacheKey, (id::TextureId, id::TextureViewId)>>,
     // Pipeline cache for fast shader compilation
     pub pipeline_cache: Arc<PipelineCache>,
     // Shared layout cache for deduplicating bind group layouts
     pub layout_cache: Arc<SharedLayoutCache>,
+    // Sampler cache for deduplicating identical samplers
+    pub sampler_cache: SamplerCache,
+    // Bind group cache for sharing bind groups between identical resource sets
+    pub bind_group_cache: BindGroupCache,
     // Track which textures have been rendered to (for automatic first-use clearing)
     pub(crate) initialized_textures: parking_lot::Mutex<std::collections::HashSet<id::TextureId>>,
+    // CPU-side submit/present/pipeline timings for the current frame
+    cpu_timings: parking_lot::Mutex<CpuFrameTimings>,
+    // Set once a submit reports device loss; the device must be recreated
+    lost: AtomicBool,
+    // Recycled staging buffers for large buffer uploads
+    staging_pool: StagingBeltPool,
+    // Small uniform writes waiting for the next submit
+    write_batch: WriteBatch,
+    // Command buffers of this frame's render passes, until `submit_frame`
+    frame_commands: parking_lot::Mutex<FrameCommands>,
+    // Allocators of buffers used as suballocation pools, created on first use
+    suballocation_pools: parking_lot::Mutex<std::collections::HashMap<id::BufferId, SuballocationPool>>,
+    // Rectangle allocators for atlas textures Java builds, by `atlas_create` id
+    atlases: parking_lot::Mutex<std::collections::HashMap<u64, TextureAtlas>>,
+    next_atlas_id: AtomicU64,
+}
+
+/// Range allocator of a buffer that meshes are suballocated from
+struct SuballocationPool {
+    allocator: RangeAllocator,
+    /// Live allocations by offset, which is all Java keeps
+    handles: std::collections::HashMap<u64, RangeHandle>,
+}
+
+/// Finished command buffers of a frame, submitted together
+#[derive(Default)]
+struct FrameCommands {
+    /// Render passes and the batched writes between them, in submission order
+    command_buffers: Vec<id::CommandBufferId>,
+    passes: u32,
 }
 
 impl BasaltDevice {
     /// Create a new device
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         context: Arc<BasaltContext>,
         device_id: id::DeviceId,
@@ -145,9 +285,22 @@ impl BasaltDevice {
             .inner()
             .device_limits(device_id);
 
+        // wgpu only reports whether anisotropic filtering works; where it does, hal supports up to 16x
+        let max_anisotropy = if context
+            .inner()
+            .device_downlevel_properties(device_id)
+            .flags
+            .contains(wgt::DownlevelFlags::ANISOTROPIC_FILTERING)
+        {
+            16
+        } else {
+            1
+        };
+
+        let adapter_info = context.inner().adapter_get_info(adapter_id);
         let info = format!(
-            "Basalt Renderer (wgpu-core)\nAdapter: {}",
-            "Unknown"
+            "Basalt Renderer (wgpu-core)\nAdapter: {} ({:?}, {:?}, driver {})",
+            adapter_info.name, adapter_info.backend, adapter_info.device_type, driver_version(&adapter_info)
         );
 
         // Create shared bind group layout and pipeline layout
@@ -170,15 +323,27 @@ impl BasaltDevice {
         // Initial swapchain state
         let initial_state = SwapchainState {
             main_framebuffer: None,
+            main_framebuffer_info: None,
             width,
             height,
         };
 
+        // Surfaces that composite alpha get an opaque present by default
+        let force_opaque_present = surface
+            .as_ref()
+            .and_then(|s| s.get_configuration())
+            .is_some_and(|config| forces_opaque_present(config.alpha_mode));
+
         // Create frame tracker for triple buffering (2 frames max)
         let frame_tracker = FrameTracker::new();
         log::info!("Initialized frame tracker (max {} frames in flight for triple buffering)",
             frame_tracker.max_frames_in_flight);
 
+        let sampler_cache = SamplerCache::new();
+        if let Err(e) = sampler_cache.prewarm(&context, device_id) {
+            log::warn!("Failed to create preset samplers: {}", e);
+        }
+
         Ok(Self {
             context,
             device_id,
@@ -186,19 +351,41 @@ impl BasaltDevice {
             queue_id,
             surface,
             limits,
+            max_anisotropy,
+            adapter_info,
             info,
             swapchain_state: parking_lot::Mutex::new(initial_state),
-            swapchain_format,
+            swapchain_format: parking_lot::Mutex::new(swapchain_format),
+            sdr_swapchain_format: swapchain_format,
+            hdr_enabled: AtomicBool::new(false),
             frame_tracker,
             blit_bind_group_layout: parking_lot::Mutex::new(None),
             blit_pipeline: parking_lot::Mutex::new(None),
+            blit_source: parking_lot::Mutex::new(None),
+            partial_resolve_pipelines: parking_lot::Mutex::new(std::collections::HashMap::new()),
+            line_pipelines: parking_lot::Mutex::new(std::collections::HashMap::new()),
+            pending_bundle_drops: parking_lot::Mutex::new(Vec::new()),
+            force_opaque_present: AtomicBool::new(force_opaque_present),
+            srgb_clear_colors: AtomicBool::new(true),
+            immediates_fallback: parking_lot::Mutex::new(None),
+            immediates_ring: parking_lot::Mutex::new(ImmediatesRing::new()),
             shared_bind_group_layout,
             shared_pipeline_layout,
             bind_group_layouts,
             depth_texture_cache: parking_lot::Mutex::new(std::collections::HashMap::new()),
             pipeline_cache,
             layout_cache,
+            sampler_cache,
+            bind_group_cache: BindGroupCache::new(),
             initialized_textures: parking_lot::Mutex::new(std::collections::HashSet::new()),
+            cpu_timings: parking_lot::Mutex::new(CpuFrameTimings::new()),
+            lost: AtomicBool::new(false),
+            staging_pool: StagingBeltPool::new(),
+            write_batch: WriteBatch::new(),
+            frame_commands: parking_lot::Mutex::new(FrameCommands::default()),
+            suballocation_pools: parking_lot::Mutex::new(std::collections::HashMap::new()),
+            atlases: parking_lot::Mutex::new(std::collections::HashMap::new()),
+            next_atlas_id: AtomicU64::new(1),
         })
     }
 
@@ -304,8 +491,18 @@ impl BasaltDevice {
     }
 
     /// Get the swapchain format (color attachment format)
+    ///
+    /// Follows `set_hdr_enabled`, so it's `HDR_SURFACE_FORMAT` while HDR is on.
     pub fn swapchain_format(&self) -> wgt::TextureFormat {
-        self.swapchain_format
+        *self.swapchain_format.lock()
+    }
+
+    /// Swapchain format with HDR off
+    ///
+    /// The game renders into 8-bit targets either way, so pipelines default to
+    /// this format rather than the current `swapchain_format`.
+    pub fn sdr_swapchain_format(&self) -> wgt::TextureFormat {
+        self.sdr_swapchain_format
     }
 
     /// Get the context
@@ -323,10 +520,51 @@ impl BasaltDevice {
         self.shared_pipeline_layout
     }
 
+    /// Add a CPU timing measurement to the current frame
+    pub fn record_cpu_timing(&self, kind: CpuTimingKind, elapsed: std::time::Duration) {
+        self.cpu_timings.lock().record(kind, elapsed);
+    }
+
+    /// Add a render pass's elided state changes to the frame stats
+    pub fn record_elided_state_changes(&self, count: u32) {
+        self.cpu_timings.lock().record_elided_state_changes(count);
+    }
+
+    /// JSON summary of CPU submit/present/pipeline timings
+    pub fn cpu_timings_json(&self) -> String {
+        self.cpu_timings.lock().to_json()
+    }
+
+    /// Whether a submit has reported device loss
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Acquire)
+    }
+
+    /// Record that the device is lost so callers stop submitting work to it
+    pub fn mark_lost(&self) {
+        if !self.lost.swap(true, Ordering::AcqRel) {
+            log::error!("Device marked as lost; it must be recreated before rendering can continue");
+        }
+    }
+
+    /// Classify a failed queue submit, marking the device lost when that's the cause
+    pub(crate) fn submit_error(
+        &self,
+        context: &str,
+        error: &wgpu_core::device::queue::QueueSubmitError,
+    ) -> BasaltError {
+        let error = BasaltError::from_webgpu_error(context, error);
+        if error.is_device_lost() {
+            self.mark_lost();
+        }
+        error
+    }
+
     /// Get or create a depth texture view for the given dimensions
-    /// Used when MC doesn't provide depth texture but pipeline requires it
-    pub fn get_or_create_depth_view(&self, width: u32, height: u32) -> Result<id::TextureViewId> {
-        let key = (width, height);
+    /// Used when MC doesn't provide depth texture but pipeline requires it.
+    /// `sample_count` must match the color attachment of the pass (1 = no MSAA).
+    pub fn get_or_create_depth_view(&self, width: u32, height: u32, sample_count: u32) -> Result<id::TextureViewId> {
+        let key = (width, height, sample_count);
 
         // Check cache first
         {
@@ -338,7 +576,7 @@ impl BasaltDevice {
         }
 
         // Create new depth texture
-        log::info!("Creating depth texture for {}x{}", width, height);
+        log::info!("Creating depth texture for {}x{} ({}x MSAA)", width, height, sample_count);
 
         // wgpu 28.0: Use MemoryUsage hint for depth buffers (reduces memory footprint)
         // Depth buffers are only used during rendering and don't need to persist
@@ -351,9 +589,9 @@ impl BasaltDevice {
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: wgt::TextureDimension::D2,
-            format: wgt::TextureFormat::Depth32Float,
+            format: DEFAULT_DEPTH_FORMAT,
             usage: wgt::TextureUsages::RENDER_ATTACHMENT,
             view_formats: vec![],
         };
@@ -370,7 +608,7 @@ impl BasaltDevice {
 
         let view_desc = wgpu_core::resource::TextureViewDescriptor {
             label: Some(Cow::Borrowed("Cached Depth View")),
-            format: Some(wgt::TextureFormat::Depth32Float),
+            format: Some(DEFAULT_DEPTH_FORMAT),
             dimension: Some(wgt::TextureViewDimension::D2),
             usage: None,
             range: wgt::ImageSubresourceRange::default(),
@@ -425,7 +663,7 @@ impl BasaltDevice {
 
             // Signal wgpu-core to release resources
             let _ = self.context.inner().texture_view_drop(view_id);
-            let _ = self.context.inner().texture_drop(texture_id);
+            self.context.inner().texture_drop(texture_id);
         }
 
         log::info!("Cleared {} depth textures from cache", count);
@@ -435,10 +673,14 @@ impl BasaltDevice {
     ///
     /// Useful for cleaning up when resizing the window.
     pub fn clear_depth_cache_for_size(&self, width: u32, height: u32) {
-        let key = (width, height);
         let mut cache = self.depth_texture_cache.lock();
+        let keys: Vec<_> = cache
+            .keys()
+            .filter(|(w, h, _)| *w == width && *h == height)
+            .copied()
+            .collect();
 
-        if let Some((texture_id, view_id)) = cache.remove(&key) {
+        for (texture_id, view_id) in keys.iter().filter_map(|key| cache.remove(key)) {
             log::info!("Clearing depth texture for size {}x{}", width, height);
 
             // Unregister the view-to-texture mapping
@@ -446,7 +688,7 @@ impl BasaltDevice {
 
             // Signal wgpu-core to release resources
             let _ = self.context.inner().texture_view_drop(view_id);
-            let _ = self.context.inner().texture_drop(texture_id);
+            self.context.inner().texture_drop(texture_id);
         }
     }
 
@@ -459,60 +701,96 @@ impl BasaltDevice {
     ///
     /// Always acquires a fresh swapchain texture each frame.
     /// Swapchain textures cannot be reused - once presented, a new one must be acquired.
-    pub fn acquire_swapchain_texture(&self) -> Result<id::TextureId> {
+    ///
+    /// An outdated or lost swapchain is reconfigured from the stored
+    /// configuration and acquired once more. Returns None when no texture is
+    /// available this frame, along with whether the surface was reconfigured.
+    pub fn acquire_swapchain_texture(&self) -> Result<(Option<id::TextureId>, bool)> {
         let surface = self.surface.as_ref()
             .ok_or_else(|| BasaltError::surface("No surface available"))?;
 
-        // Always get a fresh swapchain texture - swapchain textures must be acquired each frame
-        let output = self.context.inner().surface_get_current_texture(
-            surface.id(),
-            None,
-        ).map_err(|e| BasaltError::surface(format!("Failed to acquire swapchain texture: {:?}", e)))?;
-
-        let texture_id = output.texture
-            .ok_or_else(|| BasaltError::surface("Swapchain texture not available"))?;
-
-        log::info!("Acquired swapchain texture: {:?}", texture_id);
-        Ok(texture_id)
+        let mut reconfigured = false;
+        loop {
+            // Always get a fresh swapchain texture - swapchain textures must be acquired each frame
+            let output = self.context.inner().surface_get_current_texture(
+                surface.id(),
+                None,
+            ).map_err(|e| BasaltError::surface(format!("Failed to acquire swapchain texture: {:?}", e)))?;
+
+            if let Some(texture_id) = output.texture {
+                log::info!("Acquired swapchain texture: {:?}", texture_id);
+                return Ok((Some(texture_id), reconfigured));
+            }
+            if reconfigured || !swapchain_needs_reconfigure(&output.status) {
+                log::warn!("Swapchain texture not available ({:?})", output.status);
+                return Ok((None, reconfigured));
+            }
+            log::warn!("Swapchain is {:?}, reconfiguring the surface", output.status);
+            surface.reconfigure()?;
+            reconfigured = true;
+        }
     }
 
     /// Blit from source texture to swapchain using a render pass
     /// This handles format conversion (e.g., RGBA -> BGRA)
-    fn blit_to_swapchain(
+    ///
+    /// When `src_info` shows the source already matches the swapchain, the
+    /// texels are copied instead, skipping the fullscreen pass.
+    pub(crate) fn blit_to_swapchain(
         &self,
         src_texture: id::TextureId,
+        src_info: Option<&TextureInfo>,
         dst_texture: id::TextureId,
     ) -> Result<()> {
+        let config = self.surface.as_ref().and_then(|s| s.get_configuration());
+        if let (Some(src), Some(config)) = (src_info, config) {
+            if present_by_copy(src, &config, self.force_opaque_present()) {
+                let dst = TextureInfo {
+                    id: dst_texture,
+                    width: config.width,
+                    height: config.height,
+                    depth_or_array_layers: 1,
+                    mip_level_count: 1,
+                    dimension: wgt::TextureDimension::D2,
+                    format: config.format,
+                    sample_count: 1,
+                    is_cube: false,
+                };
+                return self.copy_texture_to_texture(src, &dst, 0, 0, 0, 0, 0, 0, src.width, src.height);
+            }
+        }
+
         // Use render-based blit for format conversion
         // The blit shader samples from the source texture and renders to the swapchain,
         // handling RGBA -> BGRA conversion automatically
-        self.render_blit(src_texture, dst_texture)
+        let src_layer = crate::texture::layer_target(wgt::TextureDimension::D2, 1, 0)?;
+        self.render_blit(src_texture, src_layer, dst_texture)
     }
 
     /// Render-based blit for format conversion
+    ///
+    /// Samples a single layer of the source; array and cube textures are read
+    /// through a 2D view of `src_layer`.
     fn render_blit(
         &self,
         src_texture: id::TextureId,
+        src_layer: LayerTarget,
         dst_texture: id::TextureId,
     ) -> Result<()> {
-        // Create blit shader and pipeline (cached in device)
-        let blit_pipeline = self.get_or_create_blit_pipeline()?;
+        let _serialized = self.begin_submission()?;
+        // The blit shader samples a texture_2d, which a 3D slice can't be viewed as
+        if src_layer.view_dimension != wgt::TextureViewDimension::D2 {
+            return Err(BasaltError::invalid_parameter(
+                "src_layer",
+                format!("blit source must be a 2D layer, got {:?}", src_layer.view_dimension),
+            ));
+        }
 
-        // Create texture views
-        let src_view_desc = wgpu_core::resource::TextureViewDescriptor {
-            label: Some(Cow::Borrowed("Blit Source View")),
-            format: None,
-            dimension: None,
-            usage: Some(wgt::TextureUsages::TEXTURE_BINDING),
-            range: wgt::ImageSubresourceRange {
-                aspect: wgt::TextureAspect::All,
-                base_mip_level: 0,
-                mip_level_count: None,
-                base_array_layer: 0,
-                array_layer_count: None,
-            },
-        };
+        // Pipeline, sampler and the source's bind group are cached across frames
+        let (bind_group_layout_id, pipeline_id, sampler_id) = self.get_or_create_blit_pipeline()?;
+        let bind_group_id = self.blit_bind_group(bind_group_layout_id, sampler_id, src_texture, src_layer)?;
 
+        // The swapchain texture changes every frame, so its view can't be cached
         let dst_view_desc = wgpu_core::resource::TextureViewDescriptor {
             label: Some(Cow::Borrowed("Blit Dest View")),
             format: None,
@@ -527,16 +805,6 @@ impl BasaltDevice {
             },
         };
 
-        let (src_view, error) = self.context.inner().texture_create_view(
-            src_texture,
-            &src_view_desc,
-            None,
-        );
-
-        if let Some(e) = error {
-            return Err(BasaltError::Wgpu(format!("Failed to create source view: {:?}", e)));
-        }
-
         let (dst_view, error) = self.context.inner().texture_create_view(
             dst_texture,
             &dst_view_desc,
@@ -546,63 +814,18 @@ impl BasaltDevice {
         if let Some(e) = error {
             return Err(BasaltError::Wgpu(format!("Failed to create dest view: {:?}", e)));
         }
+        let result = self.submit_blit(pipeline_id, bind_group_id, dst_view);
+        let _ = self.context.inner().texture_view_drop(dst_view);
+        result
+    }
 
-        // Create sampler
-        let sampler_desc = wgpu_core::resource::SamplerDescriptor {
-            label: Some(Cow::Borrowed("Blit Sampler")),
-            address_modes: [
-                wgt::AddressMode::ClampToEdge,
-                wgt::AddressMode::ClampToEdge,
-                wgt::AddressMode::ClampToEdge,
-            ],
-            mag_filter: wgt::FilterMode::Linear,
-            min_filter: wgt::FilterMode::Linear,
-            mipmap_filter: wgt::MipmapFilterMode::Nearest,
-            lod_min_clamp: 0.0,
-            lod_max_clamp: 0.0,
-            compare: None,
-            anisotropy_clamp: 1,
-            border_color: None,
-        };
-
-        let (sampler_id, error) = self.context.inner().device_create_sampler(
-            self.device_id,
-            &sampler_desc,
-            None,
-        );
-
-        if let Some(e) = error {
-            return Err(BasaltError::Wgpu(format!("Failed to create sampler: {:?}", e)));
-        }
-
-        // Create bind group
-        let bind_group_entries = vec![
-            wgpu_core::binding_model::BindGroupEntry {
-                binding: 0,
-                resource: wgpu_core::binding_model::BindingResource::TextureView(src_view),
-            },
-            wgpu_core::binding_model::BindGroupEntry {
-                binding: 1,
-                resource: wgpu_core::binding_model::BindingResource::Sampler(sampler_id),
-            },
-        ];
-
-        let bind_group_desc = wgpu_core::binding_model::BindGroupDescriptor {
-            label: Some(Cow::Borrowed("Blit Bind Group")),
-            layout: blit_pipeline.0, // bind group layout
-            entries: Cow::Borrowed(&bind_group_entries),
-        };
-
-        let (bind_group_id, error) = self.context.inner().device_create_bind_group(
-            self.device_id,
-            &bind_group_desc,
-            None,
-        );
-
-        if let Some(e) = error {
-            return Err(BasaltError::Wgpu(format!("Failed to create bind group: {:?}", e)));
-        }
-
+    /// Record and submit the fullscreen blit pass into `dst_view`
+    fn submit_blit(
+        &self,
+        pipeline_id: id::RenderPipelineId,
+        bind_group_id: id::BindGroupId,
+        dst_view: id::TextureViewId,
+    ) -> Result<()> {
         // Create command encoder
         let encoder_desc = wgt::CommandEncoderDescriptor {
             label: Some(Cow::Borrowed("Blit Encoder")),
@@ -648,7 +871,7 @@ impl BasaltDevice {
         }
 
         // Set pipeline and bind group
-        if let Err(e) = self.context.inner().render_pass_set_pipeline(&mut render_pass, blit_pipeline.1) {
+        if let Err(e) = self.context.inner().render_pass_set_pipeline(&mut render_pass, pipeline_id) {
             return Err(BasaltError::Wgpu(format!("Failed to set pipeline: {:?}", e)));
         }
 
@@ -691,20 +914,22 @@ impl BasaltDevice {
         self.context
             .inner()
             .queue_submit(self.queue_id, &[command_buffer])
-            .map_err(|e| BasaltError::Wgpu(format!("Failed to submit: {:?}", e)))?;
+            .map_err(|(_, e)| self.submit_error("blit submit", &e))?;
 
         log::debug!("Render-based blit completed with shader sampling");
         Ok(())
     }
 
-    /// Get or create the blit pipeline (cached)
-    fn get_or_create_blit_pipeline(&self) -> Result<(id::BindGroupLayoutId, id::RenderPipelineId)> {
+    /// Get or create the blit pipeline and its sampler (cached)
+    fn get_or_create_blit_pipeline(&self) -> Result<(id::BindGroupLayoutId, id::RenderPipelineId, id::SamplerId)> {
+        let sampler_id = self.sampler_cache.get_or_create(&self.context, self.device_id, &SamplerDescriptor::blit())?.sampler_id;
+
         // Check if we already have a cached pipeline
         {
             let bgl_lock = self.blit_bind_group_layout.lock();
             let pipeline_lock = self.blit_pipeline.lock();
             if let (Some(bgl_id), Some(pipeline_id)) = (*bgl_lock, *pipeline_lock) {
-                return Ok((bgl_id, pipeline_id));
+                return Ok((bgl_id, pipeline_id, sampler_id));
             }
         }
 
@@ -816,17 +1041,20 @@ impl BasaltDevice {
                 stage: wgpu_core::pipeline::ProgrammableStageDescriptor {
                     module: shader_module_id,
                     entry_point: Some(Cow::Borrowed("fs_main")),
-                    constants: HashMap::<String, f64>::new(),
+                    constants: HashMap::<String, f64>::from([
+                        ("force_opaque_alpha".to_string(), if self.force_opaque_present() { 1.0 } else { 0.0 }),
+                        ("decode_srgb".to_string(), if self.hdr_enabled() { 1.0 } else { 0.0 }),
+                    ]),
                     zero_initialize_workgroup_memory: true,
                 },
                 targets: Cow::Borrowed(&[Some(wgt::ColorTargetState {
-                    format: self.swapchain_format, // Use actual swapchain format
+                    format: self.swapchain_format(), // Use actual swapchain format
                     blend: None,
                     write_mask: wgt::ColorWrites::ALL,
                 })]),
             }),
             multiview_mask: None,
-            cache: None,
+            cache: self.pipeline_cache.driver_cache(),
         };
 
         let (pipeline_id, error) = self.context.inner().device_create_render_pipeline(
@@ -844,7 +1072,278 @@ impl BasaltDevice {
         *self.blit_pipeline.lock() = Some(pipeline_id);
 
         log::info!("Created blit pipeline (cached for future frames)");
-        Ok((bgl_id, pipeline_id))
+        Ok((bgl_id, pipeline_id, sampler_id))
+    }
+
+    /// Bind group sampling `src_layer` of `src_texture` for the blit
+    ///
+    /// The main framebuffer rarely changes, so the last source's view and bind
+    /// group are kept and only rebuilt when the source does. They keep that
+    /// source alive until then.
+    fn blit_bind_group(
+        &self,
+        bind_group_layout_id: id::BindGroupLayoutId,
+        sampler_id: id::SamplerId,
+        src_texture: id::TextureId,
+        src_layer: LayerTarget,
+    ) -> Result<id::BindGroupId> {
+        let mut cached = self.blit_source.lock();
+        if let Some(source) = cached.as_ref() {
+            if source.texture == src_texture && source.layer == src_layer {
+                return Ok(source.bind_group);
+            }
+        }
+
+        let global = self.context.inner();
+        let src_view_desc = wgpu_core::resource::TextureViewDescriptor {
+            label: Some(Cow::Borrowed("Blit Source View")),
+            format: None,
+            dimension: Some(src_layer.view_dimension),
+            usage: Some(wgt::TextureUsages::TEXTURE_BINDING),
+            range: src_layer.range(wgt::TextureAspect::All),
+        };
+        let (view, error) = global.texture_create_view(src_texture, &src_view_desc, None);
+        if let Some(e) = error {
+            return Err(BasaltError::Wgpu(format!("Failed to create source view: {:?}", e)));
+        }
+
+        let bind_group_entries = [
+            wgpu_core::binding_model::BindGroupEntry {
+                binding: 0,
+                resource: wgpu_core::binding_model::BindingResource::TextureView(view),
+            },
+            wgpu_core::binding_model::BindGroupEntry {
+                binding: 1,
+                resource: wgpu_core::binding_model::BindingResource::Sampler(sampler_id),
+            },
+        ];
+        let bind_group_desc = wgpu_core::binding_model::BindGroupDescriptor {
+            label: Some(Cow::Borrowed("Blit Bind Group")),
+            layout: bind_group_layout_id,
+            entries: Cow::Borrowed(&bind_group_entries),
+        };
+        let (bind_group, error) = global.device_create_bind_group(self.device_id, &bind_group_desc, None);
+        if let Some(e) = error {
+            let _ = global.texture_view_drop(view);
+            return Err(BasaltError::Wgpu(format!("Failed to create bind group: {:?}", e)));
+        }
+
+        if let Some(old) = cached.replace(BlitSource { texture: src_texture, layer: src_layer, view, bind_group }) {
+            old.release(global);
+        }
+        Ok(bind_group)
+    }
+
+    /// Start recording a render bundle for passes with these attachments
+    ///
+    /// Static sequences such as the HUD can be recorded once and replayed each
+    /// frame with `RenderPassState::record_execute_bundles`, skipping the
+    /// per-draw recording overhead. `sample_count` must match the passes the
+    /// bundle runs in.
+    pub fn create_render_bundle_encoder(
+        &self,
+        color_formats: &[wgt::TextureFormat],
+        depth_format: Option<wgt::TextureFormat>,
+        sample_count: u32,
+    ) -> Result<RenderBundleRecorder> {
+        RenderBundleRecorder::new(
+            &self.context,
+            self.device_id,
+            color_formats,
+            depth_format,
+            sample_count,
+            self.immediates_fallback()?,
+        )
+    }
+
+    /// Drop a finished render bundle once no pass can still execute it
+    ///
+    /// Passes replay their commands when they end, and ended passes wait for
+    /// `submit_frame`, so the bundle is dropped at the first frame submit
+    /// with no render pass open.
+    pub fn destroy_render_bundle(&self, bundle_id: id::RenderBundleId) {
+        self.pending_bundle_drops.lock().push(bundle_id);
+    }
+
+    /// Drop the evicted bind groups and destroyed render bundles, unless a render pass is still open
+    ///
+    /// An open pass may have recorded either already and replays it when it ends.
+    fn release_pending(&self) {
+        if LIVE_RENDER_PASSES.live() > 0 {
+            return;
+        }
+        self.bind_group_cache.release_pending(&self.context);
+        let global = self.context.inner();
+        for bundle_id in self.pending_bundle_drops.lock().drain(..) {
+            global.render_bundle_drop(bundle_id);
+        }
+    }
+
+    /// Resolve only `rect` of a multisampled texture into its resolve target
+    ///
+    /// A scissored shader pass averages the samples inside `rect`; the rest of
+    /// `dst` keeps its contents. Cheaper than resolving the whole attachment
+    /// when only part of the frame changed. `src` needs TEXTURE_BINDING usage.
+    pub fn resolve_msaa_region(&self, src: &TextureInfo, dst: &TextureInfo, rect: ResolveRect) -> Result<()> {
+        validate_partial_resolve(src, dst, rect)?;
+        let _serialized = self.begin_submission()?;
+
+        let pipeline = {
+            let mut pipelines = self.partial_resolve_pipelines.lock();
+            match pipelines.get(&dst.format) {
+                Some(pipeline) => *pipeline,
+                None => {
+                    let pipeline = PartialResolvePipeline::new(
+                        &self.context,
+                        self.device_id,
+                        dst.format,
+                        self.pipeline_cache.driver_cache(),
+                    )?;
+                    *pipelines.entry(dst.format).or_insert(pipeline)
+                }
+            }
+        };
+
+        let global = self.context.inner();
+        let view = |texture_id, usage, label| {
+            let desc = wgpu_core::resource::TextureViewDescriptor {
+                label: Some(Cow::Borrowed(label)),
+                format: None,
+                dimension: Some(wgt::TextureViewDimension::D2),
+                usage: Some(usage),
+                range: wgt::ImageSubresourceRange::default(),
+            };
+            match global.texture_create_view(texture_id, &desc, None) {
+                (view_id, None) => Ok(view_id),
+                (_, Some(e)) => Err(BasaltError::resource_creation(label, format!("{:?}", e))),
+            }
+        };
+        let src_view = view(src.id, wgt::TextureUsages::TEXTURE_BINDING, "Partial Resolve Source View")?;
+        let dst_view = match view(dst.id, wgt::TextureUsages::RENDER_ATTACHMENT, "Partial Resolve Target View") {
+            Ok(view_id) => view_id,
+            Err(e) => {
+                let _ = global.texture_view_drop(src_view);
+                return Err(e);
+            }
+        };
+
+        let (encoder_id, error) = global.device_create_command_encoder(
+            self.device_id,
+            &wgt::CommandEncoderDescriptor { label: Some(Cow::Borrowed("Partial Resolve Encoder")) },
+            None,
+        );
+        let result = match error {
+            Some(e) => Err(BasaltError::resource_creation("partial resolve encoder", format!("{:?}", e))),
+            None => pipeline
+                .record(&self.context, self.device_id, encoder_id, src_view, dst_view, rect)
+                .and_then(|_| match global.command_encoder_finish(encoder_id, &wgt::CommandBufferDescriptor::default(), None) {
+                    (command_buffer, None) => global
+                        .queue_submit(self.queue_id, &[command_buffer])
+                        .map(|_| ())
+                        .map_err(|(_, e)| self.submit_error("partial resolve submit", &e)),
+                    (_, Some((_, e))) => Err(BasaltError::from_webgpu_error("partial resolve finish", &e)),
+                }),
+        };
+        global.command_encoder_drop(encoder_id);
+        let _ = global.texture_view_drop(src_view);
+        let _ = global.texture_view_drop(dst_view);
+        result
+    }
+
+    /// Get or create the debug line pipeline for a color/depth format pair
+    ///
+    /// The pipeline draws `LineList` topology with culling off and depth tested
+    /// but not written. Lines are always one pixel wide since wgpu has no line
+    /// width; wider strokes need quads drawn through a triangle pipeline.
+    pub fn create_line_pipeline(
+        &self,
+        color_format: wgt::TextureFormat,
+        depth_format: Option<wgt::TextureFormat>,
+    ) -> Result<DebugLinePipeline> {
+        let mut pipelines = self.line_pipelines.lock();
+        if let Some(pipeline) = pipelines.get(&(color_format, depth_format)) {
+            return Ok(*pipeline);
+        }
+        let pipeline = DebugLinePipeline::new(
+            &self.context,
+            self.device_id,
+            color_format,
+            depth_format,
+            self.pipeline_cache.driver_cache(),
+        )?;
+        Ok(*pipelines.entry((color_format, depth_format)).or_insert(pipeline))
+    }
+
+    /// Draw `vertex_count` debug line vertices from `vertex_buffer` onto `color`
+    ///
+    /// Vertices use the layout in [`crate::debug_lines`], two per line, and are
+    /// transformed by the column-major `view_proj` matrix. Existing contents of
+    /// `color` and `depth` are kept; `depth` is only tested against.
+    pub fn draw_lines(
+        &self,
+        color: &TextureInfo,
+        depth: Option<&TextureInfo>,
+        vertex_buffer: &BufferInfo,
+        first_vertex: u32,
+        vertex_count: u32,
+        view_proj: &[f32; 16],
+    ) -> Result<()> {
+        validate_line_targets(color, depth)?;
+        validate_line_vertices(vertex_buffer.size, first_vertex, vertex_count)?;
+        let _serialized = self.begin_submission()?;
+        let pipeline = self.create_line_pipeline(color.format, depth.map(|info| info.format))?;
+
+        let global = self.context.inner();
+        let uniform: Vec<u8> = view_proj.iter().flat_map(|value| value.to_le_bytes()).collect();
+        global
+            .queue_write_buffer(self.queue_id, pipeline.uniform_buffer_id, 0, &uniform)
+            .map_err(|e| BasaltError::from_webgpu_error("debug line matrix write", &e))?;
+
+        let view = |texture_id, label| {
+            let desc = wgpu_core::resource::TextureViewDescriptor {
+                label: Some(Cow::Borrowed(label)),
+                format: None,
+                dimension: Some(wgt::TextureViewDimension::D2),
+                usage: Some(wgt::TextureUsages::RENDER_ATTACHMENT),
+                range: wgt::ImageSubresourceRange::default(),
+            };
+            match global.texture_create_view(texture_id, &desc, None) {
+                (view_id, None) => Ok(view_id),
+                (_, Some(e)) => Err(BasaltError::resource_creation(label, format!("{:?}", e))),
+            }
+        };
+        let color_view = view(color.id, "Debug Line Color View")?;
+        let depth_view = match depth.map(|info| view(info.id, "Debug Line Depth View")).transpose() {
+            Ok(view_id) => view_id,
+            Err(e) => {
+                let _ = global.texture_view_drop(color_view);
+                return Err(e);
+            }
+        };
+
+        let (encoder_id, error) = global.device_create_command_encoder(
+            self.device_id,
+            &wgt::CommandEncoderDescriptor { label: Some(Cow::Borrowed("Debug Line Encoder")) },
+            None,
+        );
+        let result = match error {
+            Some(e) => Err(BasaltError::resource_creation("debug line encoder", format!("{:?}", e))),
+            None => pipeline
+                .record(&self.context, encoder_id, color_view, depth_view, vertex_buffer.id, first_vertex..first_vertex + vertex_count)
+                .and_then(|_| match global.command_encoder_finish(encoder_id, &wgt::CommandBufferDescriptor::default(), None) {
+                    (command_buffer, None) => global
+                        .queue_submit(self.queue_id, &[command_buffer])
+                        .map(|_| ())
+                        .map_err(|(_, e)| self.submit_error("debug line submit", &e)),
+                    (_, Some((_, e))) => Err(BasaltError::from_webgpu_error("debug line finish", &e)),
+                }),
+        };
+        global.command_encoder_drop(encoder_id);
+        let _ = global.texture_view_drop(color_view);
+        if let Some(depth_view) = depth_view {
+            let _ = global.texture_view_drop(depth_view);
+        }
+        result
     }
 
     /// Present the current frame
@@ -856,12 +1355,26 @@ impl BasaltDevice {
     /// - Use device.poll() to check ACTUAL GPU completion (not just counters)
     /// - Reset frame counter when GPU queue is empty (prevents drift)
     /// - Increment on work submission, reset when GPU confirms completion
-    pub fn present_frame(&self) -> Result<()> {
+    ///
+    /// An outdated or lost swapchain is reconfigured rather than failing the
+    /// frame; the returned status says so.
+    pub fn present_frame(&self) -> Result<PresentStatus> {
+        let start = std::time::Instant::now();
+        let result = self.present_frame_inner();
+
+        let mut timings = self.cpu_timings.lock();
+        timings.record(CpuTimingKind::Present, start.elapsed());
+        timings.end_frame();
+
+        result
+    }
+
+    fn present_frame_inner(&self) -> Result<PresentStatus> {
         let surface = match &self.surface {
             Some(s) => s,
             None => {
                 log::debug!("No surface, skipping present");
-                return Ok(());
+                return Ok(PresentStatus::Skipped);
             }
         };
 
@@ -894,12 +1407,19 @@ impl BasaltDevice {
             }
         }
 
+        // The frame's passes go to the GPU together, then anything batched after them
+        if let Err(e) = self.submit_frame().and_then(|_| self.flush_buffer_writes()) {
+            log::warn!("Failed to submit the frame's render passes: {}", e);
+        }
+
         // Acquire the swapchain texture
-        let swapchain_texture = match self.acquire_swapchain_texture() {
-            Ok(t) => t,
+        let (swapchain_texture, reconfigured) = match self.acquire_swapchain_texture() {
+            Ok((Some(t), reconfigured)) => (t, reconfigured),
+            Ok((None, true)) => return Ok(PresentStatus::Reconfigured),
+            Ok((None, false)) => return Ok(PresentStatus::Skipped),
             Err(e) => {
                 log::warn!("Failed to acquire swapchain texture: {}", e);
-                return Ok(()); // Don't fail, just skip this frame
+                return Ok(PresentStatus::Skipped); // Don't fail, just skip this frame
             }
         };
 
@@ -910,7 +1430,7 @@ impl BasaltDevice {
             log::info!("Blitting main framebuffer {:?} to swapchain {:?}", main_fb, swapchain_texture);
 
             // Blit using a render pass (handles format conversion)
-            if let Err(e) = self.blit_to_swapchain(main_fb, swapchain_texture) {
+            if let Err(e) = self.blit_to_swapchain(main_fb, state.main_framebuffer_info.as_ref(), swapchain_texture) {
                 log::error!("Failed to blit to swapchain: {}", e);
                 // Continue anyway and try to present
             } else {
@@ -935,9 +1455,15 @@ impl BasaltDevice {
 
         // Present the surface
         match surface.present(self.queue_id) {
+            Ok(status) if swapchain_needs_reconfigure(&status) => {
+                // The frame is gone, but the next one can acquire again
+                log::warn!("Swapchain is {:?} after present, reconfiguring the surface", status);
+                surface.reconfigure()?;
+                Ok(PresentStatus::Reconfigured)
+            }
             Ok(status) => {
                 log::info!("Presented frame with status: {:?}", status);
-                Ok(())
+                Ok(if reconfigured { PresentStatus::Reconfigured } else { PresentStatus::Presented })
             }
             Err(e) => {
                 log::error!("Failed to present frame: {:?}", e);
@@ -948,6 +1474,7 @@ impl BasaltDevice {
 
     /// Clear the swapchain texture to black (fallback when no main framebuffer)
     fn clear_swapchain(&self, swapchain_texture: id::TextureId) -> Result<()> {
+        let _serialized = self.begin_submission()?;
         // Create texture view for the swapchain
         let view_desc = wgpu_core::resource::TextureViewDescriptor {
             label: Some(Cow::Borrowed("Swapchain Clear View")),
@@ -1034,7 +1561,7 @@ impl BasaltDevice {
         self.context
             .inner()
             .queue_submit(self.queue_id, &[command_buffer])
-            .map_err(|e| BasaltError::Wgpu(format!("Failed to submit clear: {:?}", e)))?;
+            .map_err(|(_, e)| self.submit_error("swapchain clear submit", &e))?;
 
         log::debug!("Cleared swapchain to black");
         Ok(())
@@ -1053,42 +1580,278 @@ impl BasaltDevice {
         Ok(())
     }
 
-    /// Explicitly set the main framebuffer texture for presentation
-    /// This should be called when a render pass targets a texture that will be presented
-    pub fn set_main_framebuffer(&self, texture_id: id::TextureId) {
-        log::info!("Explicitly setting main framebuffer to {:?}", texture_id);
-        // **CRITICAL FIX #3:** Use mutex lock for proper synchronization
-        let mut state = self.swapchain_state.lock();
-        state.main_framebuffer = Some(texture_id);
-        // Mutex guard is released here automatically
-    }
+    /// Force the presented image to alpha 1.0
+    ///
+    /// Defaults to on when the surface's composite alpha mode isn't `Opaque`.
+    /// The cached blit pipeline is rebuilt on the next present.
+    pub fn set_force_opaque_present(&self, enabled: bool) {
+        if self.force_opaque_present.swap(enabled, Ordering::AcqRel) == enabled {
+            return;
+        }
 
-    /// Set the main framebuffer from a texture view ID
-    /// Looks up the parent texture of the view and sets it as the main framebuffer
-    pub fn set_main_framebuffer_from_view(&self, view_id: id::TextureViewId) {
-        // For now, we can't easily get the parent texture from a view in wgpu-core
-        // So we'll need to track this separately or use a different approach
-        log::debug!("set_main_framebuffer_from_view called with view {:?}", view_id);
+        self.drop_blit_pipeline();
+        log::debug!("Opaque present {}", if enabled { "enabled" } else { "disabled" });
     }
 
-    /// Get implementation information
-    pub fn get_implementation_info(&self) -> String {
-        self.info.clone()
+    /// Drop the cached blit pipeline so the next present rebuilds it
+    fn drop_blit_pipeline(&self) {
+        let global = self.context.inner();
+        if let Some(pipeline_id) = self.blit_pipeline.lock().take() {
+            global.render_pipeline_drop(pipeline_id);
+        }
+        if let Some(bgl_id) = self.blit_bind_group_layout.lock().take() {
+            global.bind_group_layout_drop(bgl_id);
+        }
+        // Bind groups belong to the layout just dropped
+        if let Some(source) = self.blit_source.lock().take() {
+            source.release(global);
+        }
     }
 
-    /// Get vendor name
-    pub fn get_vendor(&self) -> String {
-        "Unknown".to_string()
-    }
+    /// Present in HDR through an extended-range `HDR_SURFACE_FORMAT` surface
+    ///
+    /// Reconfigures the surface and rebuilds the present blit for the new
+    /// format; the blit decodes the game's sRGB-encoded output to the linear
+    /// values the extended color space expects. Falls back to SDR when the
+    /// surface doesn't offer the format, or there is no surface. Returns
+    /// whether HDR is on afterwards.
+    pub fn set_hdr_enabled(&self, enabled: bool) -> Result<bool> {
+        let Some(surface) = &self.surface else {
+            return Ok(false);
+        };
+        let (Some(mut config), Some(caps)) = (surface.get_configuration(), surface.capabilities()) else {
+            return Ok(false);
+        };
 
-    /// Get renderer name
-    pub fn get_renderer(&self) -> String {
-        "Basalt WebGPU Renderer".to_string()
-    }
+        let format = if enabled { choose_surface_format(&caps.formats, true) } else { self.sdr_swapchain_format };
+        let hdr = format == HDR_SURFACE_FORMAT;
+        if enabled && !hdr {
+            log::warn!("Surface doesn't offer {:?} (available: {:?}), staying in SDR", HDR_SURFACE_FORMAT, caps.formats);
+        }
+        if format == config.format {
+            return Ok(hdr);
+        }
+
+        let _serialized = self.begin_submission()?;
+        config.format = format;
+        config.view_formats = build_view_formats(&format, &caps.formats);
+        config.usage = wgt::TextureUsages::RENDER_ATTACHMENT | (caps.usages & wgt::TextureUsages::COPY_DST);
+        if !caps.alpha_modes.contains(&config.alpha_mode) {
+            config.alpha_mode = caps.alpha_modes[0];
+        }
+        surface.update_configuration(config)?;
+
+        *self.swapchain_format.lock() = format;
+        self.hdr_enabled.store(hdr, Ordering::Release);
+        self.drop_blit_pipeline();
+        log::info!("Presenting in {} ({:?})", if hdr { "HDR" } else { "SDR" }, format);
+        Ok(hdr)
+    }
+
+    /// Whether the surface is presenting in HDR
+    pub fn hdr_enabled(&self) -> bool {
+        self.hdr_enabled.load(Ordering::Acquire)
+    }
+
+    /// Formats, present modes and alpha modes the window surface supports
+    ///
+    /// Queried once when the device is created. None for headless devices.
+    pub fn get_surface_capabilities(&self) -> Option<SurfaceCapabilities> {
+        self.surface.as_ref().and_then(|surface| surface.capabilities().cloned())
+    }
+
+    /// Whether the present blit writes alpha 1.0
+    pub fn force_opaque_present(&self) -> bool {
+        self.force_opaque_present.load(Ordering::Acquire)
+    }
+
+    /// Decode Minecraft's sRGB-encoded clear colors for sRGB attachments
+    ///
+    /// On by default, so a clear stores the packed color as given whatever the
+    /// attachment format; see `render_pass::linear_clear_color`.
+    pub fn set_srgb_clear_colors(&self, enabled: bool) {
+        self.srgb_clear_colors.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Whether clear colors are decoded for sRGB attachments
+    pub fn srgb_clear_colors(&self) -> bool {
+        self.srgb_clear_colors.load(Ordering::Relaxed)
+    }
+
+    /// Whether pipelines can use immediates (push constants)
+    ///
+    /// False on GL and other backends without `IMMEDIATES`; those route
+    /// immediates through `immediates_fallback` instead.
+    pub fn supports_push_constants(&self) -> bool {
+        self.limits.max_immediate_size > 0
+    }
+
+    /// Serialize command recording and queue submission behind one lock
+    ///
+    /// A debugging aid for races between render threads: slower, but makes
+    /// submission order deterministic. Off by default.
+    pub fn set_serialized_submission(&self, enabled: bool) {
+        self.context.submission().set_enabled(enabled);
+        log::info!("Serialized submission {}", if enabled { "enabled" } else { "disabled" });
+    }
+
+    /// Whether command recording and submission are serialized
+    pub fn serialized_submission(&self) -> bool {
+        self.context.submission().is_enabled()
+    }
+
+    /// Open an error scope on the calling thread
+    ///
+    /// Until the matching `pop_error_scope`, recoverable errors matching `filter`
+    /// (the per-draw errors otherwise logged and kept as the last error) are
+    /// held by the scope instead. Scopes nest; the innermost matching one wins.
+    pub fn push_error_scope(&self, filter: ErrorFilter) {
+        crate::error::push_error_scope(filter);
+    }
+
+    /// Close the calling thread's innermost error scope, returning its first error
+    pub fn pop_error_scope(&self) -> Result<Option<String>> {
+        crate::error::pop_error_scope()
+    }
+
+    /// Uniform fallback for immediates, or None when push constants are supported
+    pub fn immediates_fallback(&self) -> Result<Option<ImmediatesFallback>> {
+        if self.supports_push_constants() {
+            return Ok(None);
+        }
+
+        let mut cached = self.immediates_fallback.lock();
+        if let Some(fallback) = *cached {
+            return Ok(Some(fallback));
+        }
+
+        let global = self.context.inner();
+        let (buffer_id, error) = global.device_create_buffer(
+            self.device_id,
+            &wgt::BufferDescriptor {
+                label: Some(Cow::Borrowed("Bassalt Immediates Fallback")),
+                size: (IMMEDIATES_FALLBACK_RING_SLOTS * IMMEDIATES_FALLBACK_SLOT_SIZE) as u64,
+                usage: wgt::BufferUsages::UNIFORM | wgt::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            },
+            None,
+        );
+        if let Some(e) = error {
+            return Err(BasaltError::resource_creation("immediates fallback buffer", format!("{:?}", e)));
+        }
+
+        let block_size = std::num::NonZeroU64::new(MAX_IMMEDIATE_SIZE as u64);
+        let (bind_group_layout_id, error) = global.device_create_bind_group_layout(
+            self.device_id,
+            &wgpu_core::binding_model::BindGroupLayoutDescriptor {
+                label: Some(Cow::Borrowed("Bassalt Immediates Fallback Layout")),
+                entries: Cow::Owned(vec![wgt::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgt::ShaderStages::VERTEX | wgt::ShaderStages::FRAGMENT,
+                    ty: wgt::BindingType::Buffer {
+                        ty: wgt::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: block_size,
+                    },
+                    count: None,
+                }]),
+            },
+            None,
+        );
+        if let Some(e) = error {
+            return Err(BasaltError::resource_creation("immediates fallback layout", format!("{:?}", e)));
+        }
+
+        let (bind_group_id, error) = global.device_create_bind_group(
+            self.device_id,
+            &wgpu_core::binding_model::BindGroupDescriptor {
+                label: Some(Cow::Borrowed("Bassalt Immediates Fallback Bind Group")),
+                layout: bind_group_layout_id,
+                entries: Cow::Owned(vec![wgpu_core::binding_model::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu_core::binding_model::BindingResource::Buffer(
+                        wgpu_core::binding_model::BufferBinding { buffer: buffer_id, offset: 0, size: Some(MAX_IMMEDIATE_SIZE as u64) },
+                    ),
+                }]),
+            },
+            None,
+        );
+        if let Some(e) = error {
+            return Err(BasaltError::resource_creation("immediates fallback bind group", format!("{:?}", e)));
+        }
+
+        log::info!("Created uniform fallback for immediates ({} slots)", IMMEDIATES_FALLBACK_RING_SLOTS);
+        let fallback = ImmediatesFallback { buffer_id, bind_group_layout_id, bind_group_id };
+        *cached = Some(fallback);
+        Ok(Some(fallback))
+    }
+
+    /// Explicitly set the main framebuffer texture for presentation
+    /// This should be called when a render pass targets a texture that will be presented
+    ///
+    /// `info` lets present copy the texture instead of blitting it when its format
+    /// and size already match the swapchain; without it present always blits.
+    pub fn set_main_framebuffer(&self, texture_id: id::TextureId, info: Option<TextureInfo>) {
+        log::info!("Explicitly setting main framebuffer to {:?}", texture_id);
+        // **CRITICAL FIX #3:** Use mutex lock for proper synchronization
+        let mut state = self.swapchain_state.lock();
+        state.main_framebuffer = Some(texture_id);
+        state.main_framebuffer_info = info;
+        // Mutex guard is released here automatically
+    }
+
+    /// Texture presented by the next `present_frame`, if any
+    pub fn main_framebuffer(&self) -> Option<id::TextureId> {
+        self.swapchain_state.lock().main_framebuffer
+    }
+
+    /// Set the main framebuffer from a texture view ID
+    /// Looks up the parent texture of the view and sets it as the main framebuffer
+    pub fn set_main_framebuffer_from_view(&self, view_id: id::TextureViewId) {
+        // For now, we can't easily get the parent texture from a view in wgpu-core
+        // So we'll need to track this separately or use a different approach
+        log::debug!("set_main_framebuffer_from_view called with view {:?}", view_id);
+    }
+
+    /// Get implementation information
+    pub fn get_implementation_info(&self) -> String {
+        self.info.clone()
+    }
+
+    /// Highest sampler anisotropy the backend supports, 1 if it can't filter anisotropically
+    pub fn max_anisotropy(&self) -> u16 {
+        self.max_anisotropy
+    }
+
+    /// Adapter the device was created on, as reported by the driver
+    pub fn adapter_info(&self) -> &wgt::AdapterInfo {
+        &self.adapter_info
+    }
+
+    /// Get vendor name
+    ///
+    /// Known PCI vendor ids map to the vendor's name; others are shown as hex.
+    /// GL adapters often don't report an id, so that falls back to the driver name.
+    pub fn get_vendor(&self) -> String {
+        let info = &self.adapter_info;
+        match vendor_name(info.vendor) {
+            Some(name) => name.to_string(),
+            None if info.vendor != 0 => format!("0x{:04X}", info.vendor),
+            None if !info.driver.is_empty() => info.driver.clone(),
+            None => "Unknown".to_string(),
+        }
+    }
+
+    /// Get renderer name: the adapter name and the backend driving it
+    pub fn get_renderer(&self) -> String {
+        let info = &self.adapter_info;
+        let name = if info.name.is_empty() { "Unknown GPU" } else { info.name.as_str() };
+        format!("{} ({})", name, info.backend)
+    }
 
     /// Get driver version
     pub fn get_version(&self) -> String {
-        env!("CARGO_PKG_VERSION").to_string()
+        driver_version(&self.adapter_info)
     }
 
     /// Get device limits
@@ -1096,6 +1859,85 @@ impl BasaltDevice {
         &self.limits
     }
 
+    /// Device limits as a JSON object keyed by `wgt::Limits` field name
+    pub fn limits_json(&self) -> String {
+        limits_to_json(&self.limits)
+    }
+
+    /// Best-effort VRAM budget of the adapter, `None` where the backend doesn't report it
+    pub fn vram_budget(&self) -> Option<crate::memory_budget::VramBudget> {
+        crate::memory_budget::query(&self.context, self.adapter_id)
+    }
+
+    /// Load the driver pipeline cache saved at `path` for pipelines created from now on
+    ///
+    /// A missing, unreadable or stale file (e.g. after a driver update) starts an
+    /// empty cache. Returns false without doing anything when the device lacks
+    /// `PIPELINE_CACHE`.
+    pub fn create_pipeline_cache_from_file(&self, path: &Path) -> Result<bool> {
+        let global = self.context.inner();
+        if !global.device_features(self.device_id).contains(wgt::Features::PIPELINE_CACHE) {
+            log::debug!("Device lacks PIPELINE_CACHE, not loading {}", path.display());
+            return Ok(false);
+        }
+
+        let data = match std::fs::read(path) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    log::warn!("Failed to read pipeline cache {}: {}", path.display(), e);
+                }
+                None
+            }
+        };
+        let loaded = data.as_ref().map_or(0, Vec::len);
+
+        // SAFETY: the data was written by `save_pipeline_cache`; wgpu checks its
+        // header against this adapter and `fallback` discards data that doesn't match
+        let (cache_id, error) = unsafe {
+            global.device_create_pipeline_cache(
+                self.device_id,
+                &wgpu_core::pipeline::PipelineCacheDescriptor {
+                    label: Some(Cow::Borrowed("Basalt Pipeline Cache")),
+                    data: data.map(Cow::Owned),
+                    fallback: true,
+                },
+                None,
+            )
+        };
+        if let Some(e) = error {
+            global.pipeline_cache_drop(cache_id);
+            return Err(BasaltError::from_webgpu_error("create pipeline cache", &e));
+        }
+
+        if let Some(previous) = self.pipeline_cache.set_driver_cache(Some(cache_id)) {
+            global.pipeline_cache_drop(previous);
+        }
+        log::info!("Loaded pipeline cache from {} ({} bytes)", path.display(), loaded);
+        Ok(true)
+    }
+
+    /// Write the driver pipeline cache to `path`
+    ///
+    /// Returns false when no cache was loaded or the backend has no data to
+    /// save. The file is replaced atomically, so a crash mid-write leaves the
+    /// previous cache intact.
+    pub fn save_pipeline_cache(&self, path: &Path) -> Result<bool> {
+        let Some(cache_id) = self.pipeline_cache.driver_cache() else {
+            return Ok(false);
+        };
+        let Some(data) = self.context.inner().pipeline_cache_get_data(cache_id) else {
+            return Ok(false);
+        };
+
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        std::fs::write(&temp_path, &data)?;
+        std::fs::rename(&temp_path, path)?;
+        log::info!("Saved pipeline cache to {} ({} bytes)", path.display(), data.len());
+        Ok(true)
+    }
+
     /// Get the device context
     pub fn get_context(&self) -> &Arc<BasaltContext> {
         &self.context
@@ -1111,6 +1953,8 @@ impl BasaltDevice {
     /// This processes any pending GPU operations and returns the device status.
     /// Based on wgpu's pattern of polling to process async operations.
     /// Use this to synchronize with the GPU and ensure operations have completed.
+    /// Polling is also what runs pending buffer map callbacks and frees
+    /// resources whose last submission has finished.
     ///
     /// # Arguments
     /// * `wait` - If true, wait until all operations complete. If false, just check status.
@@ -1135,16 +1979,72 @@ impl BasaltDevice {
                 }
                 Ok(status.is_queue_empty())
             }
-            Err(e) => {
-                log::error!("Device poll error: {:?}", e);
-                Err(BasaltError::device_creation(format!("Device poll failed: {:?}", e)))
+            Err(wgpu_core::device::WaitIdleError::Device(e)) => {
+                let error = BasaltError::from_webgpu_error("device poll", &e);
+                if error.is_device_lost() {
+                    self.mark_lost();
+                }
+                Err(error)
+            }
+            Err(e) => Err(BasaltError::Wgpu(format!("Device poll failed: {}", e))),
+        }
+    }
+
+    /// Submit everything recorded so far and return an index covering it
+    ///
+    /// Indices increase with every call. Once `is_submission_complete` reports
+    /// an index done, the GPU has finished all work submitted before it, so
+    /// buffers it read can be rewritten without waiting for the whole queue.
+    pub fn submit_and_get_submission_index(&self) -> Result<u64> {
+        let _serialized = self.begin_submission()?;
+        self.flush_buffer_writes()?;
+        // An empty submit still gets an index, ordered after every earlier one
+        self.context
+            .inner()
+            .queue_submit(self.queue_id, &[])
+            .map_err(|(_, e)| self.submit_error("fence submit", &e))
+    }
+
+    /// Whether the GPU finished the work covered by `index`, without blocking
+    pub fn is_submission_complete(&self, index: u64) -> Result<bool> {
+        let poll_type = wgt::PollType::Wait {
+            submission_index: Some(index),
+            timeout: Some(std::time::Duration::ZERO),
+        };
+        match self.context.inner().device_poll(self.device_id, poll_type) {
+            Ok(_) => Ok(true),
+            Err(wgpu_core::device::WaitIdleError::Timeout) => Ok(false),
+            Err(wgpu_core::device::WaitIdleError::Device(e)) => {
+                let error = BasaltError::from_webgpu_error("submission poll", &e);
+                if error.is_device_lost() {
+                    self.mark_lost();
+                }
+                Err(error)
             }
+            Err(e) => Err(BasaltError::invalid_parameter("index", e.to_string())),
         }
     }
 
     /// Create a buffer with a descriptive debug label based on usage
     pub fn create_buffer(&self, size: u64, usage: u32) -> Result<id::BufferId> {
-        let mut wgpu_usage = self.map_buffer_usage(usage);
+        self.create_buffer_with_hint(size, usage, false, None)
+    }
+
+    /// Create a buffer, hinting whether the CPU rewrites it often
+    ///
+    /// wgpu picks the memory heap itself, so the hint works through usage
+    /// flags: frequently updated buffers always get COPY_DST so per-frame
+    /// `write_buffer` calls are valid, and are labelled dynamic. Buffers written
+    /// once and then only read by the GPU should pass false; wgpu keeps them in
+    /// device-local memory. `label` replaces the usage-derived debug label.
+    pub fn create_buffer_with_hint(
+        &self,
+        size: u64,
+        usage: u32,
+        frequently_updated: bool,
+        label: Option<&str>,
+    ) -> Result<id::BufferId> {
+        let mut wgpu_usage = buffer_usage_with_hint(self.map_buffer_usage(usage), frequently_updated);
 
         // WebGPU has a 64KB limit for uniform buffers
         // For larger buffers with UNIFORM usage, also add STORAGE usage
@@ -1158,8 +2058,17 @@ impl BasaltDevice {
             );
         }
 
-        // Create a descriptive label based on usage
-        let label = self.buffer_usage_to_label(wgpu_usage, size);
+        // Create a descriptive label based on usage, unless the caller named the buffer
+        let label = match label {
+            Some(label) => label.to_string(),
+            None => {
+                let mut label = self.buffer_usage_to_label(wgpu_usage, size);
+                if frequently_updated {
+                    label.push_str(" [dynamic]");
+                }
+                label
+            }
+        };
 
         let desc = wgt::BufferDescriptor {
             label: Some(Cow::Owned(label)),
@@ -1182,6 +2091,9 @@ impl BasaltDevice {
 
     /// Write data to a buffer
     pub fn write_buffer(&self, buffer_id: id::BufferId, offset: u64, data: &[u8]) -> Result<()> {
+        let _serialized = self.begin_submission()?;
+        // Batched writes were issued first, so they must land first
+        self.flush_buffer_writes()?;
         self.context
             .inner()
             .queue_write_buffer(self.queue_id, buffer_id, offset, data)
@@ -1190,12 +2102,337 @@ impl BasaltDevice {
         Ok(())
     }
 
+    /// Write data to a buffer, staging large aligned payloads through the pool
+    ///
+    /// Payloads below `STAGED_UPLOAD_THRESHOLD` or not 4-byte aligned go
+    /// through `write_buffer`, which is cheaper for small updates.
+    pub fn write_buffer_staged(&self, buffer_id: id::BufferId, offset: u64, data: &[u8]) -> Result<()> {
+        let len = data.len() as u64;
+        if len < STAGED_UPLOAD_THRESHOLD
+            || !len.is_multiple_of(wgt::COPY_BUFFER_ALIGNMENT)
+            || !offset.is_multiple_of(wgt::COPY_BUFFER_ALIGNMENT)
+        {
+            return self.write_buffer(buffer_id, offset, data);
+        }
+
+        let _serialized = self.begin_submission()?;
+        self.flush_buffer_writes()?;
+        self.staging_pool
+            .write_buffer(&self.context, self.device_id, self.queue_id, buffer_id, offset, data)
+            .inspect_err(|e| {
+                if e.is_device_lost() {
+                    self.mark_lost();
+                }
+            })
+    }
+
+    /// Staging pool counters
+    pub fn staging_stats(&self) -> StagingPoolStats {
+        self.staging_pool.stats()
+    }
+
+    /// Write a small block of CPU data, such as a uniform update, batched until the next submit
+    ///
+    /// Cheaper than `write_buffer` for the many tiny per-draw updates, since
+    /// writes to neighbouring ranges of a buffer merge into one upload. Writes
+    /// over `MAX_BATCHED_WRITE` bytes skip the batch. Offset and length must be
+    /// multiples of 4. Use `copy_buffer_to_buffer` when the data is already on the GPU.
+    pub fn update_uniform(&self, buffer_id: id::BufferId, offset: u64, data: &[u8]) -> Result<()> {
+        if data.len() as u64 > MAX_BATCHED_WRITE {
+            return self.write_buffer_staged(buffer_id, offset, data);
+        }
+        if self.write_batch.pending_bytes() >= MAX_BATCHED_BYTES {
+            self.flush_buffer_writes()?;
+        }
+        self.write_batch.push(buffer_id, offset, data)
+    }
+
+    /// Issue the writes batched by `update_uniform`
+    ///
+    /// Called before every submit that can read them, including before each
+    /// render pass is recorded. With passes pending in the frame, the writes
+    /// are recorded as copies after them instead, so they can't land ahead of
+    /// those passes and the frame still reaches the GPU in one submit.
+    pub fn flush_buffer_writes(&self) -> Result<()> {
+        if self.write_batch.is_empty() {
+            return Ok(());
+        }
+        let _serialized = self.context.serialize_submission();
+        let mut frame = self.frame_commands.lock();
+        if frame.command_buffers.is_empty() {
+            return self.write_batch.flush(&self.context, self.queue_id);
+        }
+        if let Some(command_buffer) = self.write_batch.record(&self.context, self.device_id)? {
+            frame.command_buffers.push(command_buffer);
+        }
+        Ok(())
+    }
+
+    /// Serialize a submission, submitting the frame's pending render passes first
+    ///
+    /// Queue writes and submits run before anything still waiting in the
+    /// frame, so work that must follow the passes ended so far starts here
+    /// rather than with `BasaltContext::serialize_submission`.
+    fn begin_submission(&self) -> Result<Option<ReentrantMutexGuard<'_, ()>>> {
+        let serialized = self.context.serialize_submission();
+        self.submit_frame()?;
+        Ok(serialized)
+    }
+
+    /// Record an ended render pass for the frame's submit
+    ///
+    /// Each pass gets its own command buffer, and the frame's buffers reach
+    /// the GPU together in `submit_frame`, so a frame with many passes costs
+    /// one submit. A pass that fails to record or validate is dropped on its
+    /// own; passes recorded before it still run. On devices without push
+    /// constants, the pass's immediates are written to fallback slots of its
+    /// own, so they can't change what the frame's earlier passes read; only a
+    /// frame whose passes fill the whole fallback ring is submitted early.
+    pub fn record_render_pass(
+        &self,
+        state: &mut RenderPassState,
+    ) -> std::result::Result<Option<id::TextureId>, RecordPassError> {
+        let _serialized = self.context.serialize_submission();
+        self.flush_buffer_writes().map_err(RecordPassError::Recording)?;
+        let slots = state.immediates_slot_count();
+        if slots > 0 {
+            let reserved = self.immediates_ring.lock().reserve(slots);
+            let base_slot = match reserved {
+                Some(base_slot) => base_slot,
+                None => {
+                    self.submit_frame().map_err(RecordPassError::Submit)?;
+                    self.immediates_ring.lock().reserve(slots).ok_or_else(|| {
+                        RecordPassError::Recording(BasaltError::RenderPass(format!(
+                            "{} immediates fallback slots don't fit the ring",
+                            slots
+                        )))
+                    })?
+                }
+            };
+            state.upload_immediates(&self.context, self.queue_id, base_slot).map_err(RecordPassError::Recording)?;
+        }
+
+        let global = self.context.inner();
+        let encoder_desc = wgt::CommandEncoderDescriptor {
+            label: Some(Cow::Borrowed("Basalt Pass Encoder")),
+        };
+        let (encoder_id, error) = global.device_create_command_encoder(self.device_id, &encoder_desc, None);
+        if let Some(e) = error {
+            return Err(RecordPassError::Recording(BasaltError::Wgpu(format!(
+                "Failed to create pass encoder: {:?}",
+                e
+            ))));
+        }
+
+        let output = match state.finish(&self.context, encoder_id) {
+            Ok(output) => output,
+            Err(e) => {
+                global.command_encoder_drop(encoder_id);
+                return Err(RecordPassError::Recording(e));
+            }
+        };
+        // wgpu reports most pass validation errors only when the encoder finishes
+        let (command_buffer, error) =
+            global.command_encoder_finish(encoder_id, &wgt::CommandBufferDescriptor::default(), None);
+        if let Some(e) = error {
+            return Err(RecordPassError::Recording(BasaltError::RenderPass(format!(
+                "Render pass failed validation: {:?}",
+                e
+            ))));
+        }
+
+        let mut frame = self.frame_commands.lock();
+        frame.command_buffers.push(command_buffer);
+        frame.passes += 1;
+        Ok(output)
+    }
+
+    /// Submit the render passes recorded since the last submit, if any
+    pub fn submit_frame(&self) -> Result<()> {
+        let _serialized = self.context.serialize_submission();
+        let frame = std::mem::take(&mut *self.frame_commands.lock());
+        if frame.command_buffers.is_empty() {
+            self.immediates_ring.lock().frame_submitted();
+            self.release_pending();
+            return Ok(());
+        }
+
+        let global = self.context.inner();
+        global
+            .queue_submit(self.queue_id, &frame.command_buffers)
+            .map_err(|(_, e)| self.submit_error("frame submit", &e))?;
+        // Nothing unsubmitted can reference bind groups or bundles released before this point
+        self.immediates_ring.lock().frame_submitted();
+        self.release_pending();
+
+        // Drive GPU progress without waiting; frame limiting happens at present
+        let _ = global.device_poll(self.device_id, wgt::PollType::Poll);
+        log::debug!(
+            "Submitted {} render passes in {} command buffers",
+            frame.passes,
+            frame.command_buffers.len()
+        );
+        Ok(())
+    }
+
+    /// Render passes recorded for the frame and not yet submitted
+    pub fn pending_frame_passes(&self) -> u32 {
+        self.frame_commands.lock().passes
+    }
+
+    /// Uniform write batch counters
+    pub fn write_batch_stats(&self) -> WriteBatchStats {
+        self.write_batch.stats()
+    }
+
+    /// Suballocate `size` bytes aligned to `align` from `pool`, returning the offset
+    ///
+    /// Lets many small meshes share a few large buffers instead of each getting
+    /// its own. The pool's allocator is created on first use and coalesces
+    /// freed neighbours; fails with `OutOfMemory` when no free range fits.
+    pub fn suballocate(&self, pool: &BufferInfo, size: u64, align: u64) -> Result<u64> {
+        let mut pools = self.suballocation_pools.lock();
+        let pool = pools.entry(pool.id).or_insert_with(|| SuballocationPool {
+            allocator: RangeAllocator::new(pool.size),
+            handles: std::collections::HashMap::new(),
+        });
+        let allocation = pool.allocator.allocate(size, align)?;
+        pool.handles.insert(allocation.offset, allocation.handle);
+        Ok(allocation.offset)
+    }
+
+    /// Return the range `suballocate` handed out at `offset` to `pool`
+    pub fn free_suballocation(&self, pool: id::BufferId, offset: u64) -> Result<()> {
+        let mut pools = self.suballocation_pools.lock();
+        let freed = pools.get_mut(&pool).and_then(|pool| {
+            let handle = pool.handles.remove(&offset)?;
+            pool.allocator.free(handle)
+        });
+        freed.map(|_| ()).ok_or_else(|| {
+            BasaltError::invalid_parameter("offset", format!("no suballocation at offset {} of buffer {:?}", offset, pool))
+        })
+    }
+
+    /// Compact `pool`'s live suballocations towards its start
+    ///
+    /// Moves at most `max_bytes` (at least one allocation if any can move) and
+    /// records the matching copies, so holes left by freed meshes merge into
+    /// one free range. Returns the moves; Java must rewrite every cached offset
+    /// from `old_offset` to `new_offset`. If the copies fail the pool keeps its
+    /// old layout. The pool buffer needs COPY_SRC | COPY_DST usage.
+    pub fn compact_pool(&self, pool: id::BufferId, max_bytes: u64) -> Result<Vec<RangeMove>> {
+        let mut pools = self.suballocation_pools.lock();
+        let Some(pool_state) = pools.get_mut(&pool) else {
+            return Ok(Vec::new());
+        };
+
+        let previous = pool_state.allocator.clone();
+        let moves = pool_state.allocator.defragment(max_bytes);
+        if let Err(e) = self.apply_range_moves(pool, &moves) {
+            pool_state.allocator = previous;
+            return Err(e);
+        }
+
+        let allocator = &pool_state.allocator;
+        pool_state.handles = pool_state
+            .handles
+            .values()
+            .filter_map(|&handle| allocator.get(handle).map(|allocation| (allocation.offset, handle)))
+            .collect();
+        Ok(moves)
+    }
+
+    /// Occupancy of `pool`, or `None` if nothing was ever suballocated from it
+    ///
+    /// `RangeAllocatorStats::needs_compaction` means `compact_pool` is worth running.
+    pub fn pool_stats(&self, pool: id::BufferId) -> Option<RangeAllocatorStats> {
+        self.suballocation_pools.lock().get(&pool).map(|pool| pool.allocator.stats())
+    }
+
+    /// Create an empty `width` x `height` atlas allocator, returning its id
+    ///
+    /// The allocator only places rectangles; the caller creates the texture
+    /// and uploads sprites at the positions `atlas_insert` returns. `policy`
+    /// decides what `atlas_insert` does when a sprite doesn't fit.
+    pub fn atlas_create(&self, width: u32, height: u32, policy: AtlasEvictionPolicy) -> Result<u64> {
+        let max = self.limits.max_texture_dimension_2d;
+        if width == 0 || height == 0 || width > max || height > max {
+            return Err(BasaltError::invalid_parameter(
+                "size",
+                format!("atlas of {}x{} must be between 1 and {} texels per side", width, height, max),
+            ));
+        }
+        let atlas_id = self.next_atlas_id.fetch_add(1, Ordering::Relaxed);
+        self.atlases.lock().insert(atlas_id, TextureAtlas::with_policy(width, height, policy));
+        Ok(atlas_id)
+    }
+
+    /// Place a `width` x `height` sprite in an atlas
+    ///
+    /// An LRU atlas evicts its least recently used sprites until the new one
+    /// fits and reports them, so the caller can drop their UVs. The entry is
+    /// `None` when the sprite still doesn't fit, so the caller can grow the
+    /// atlas or reset it and insert again.
+    pub fn atlas_insert(&self, atlas_id: u64, width: u32, height: u32) -> Result<AtlasInsert> {
+        self.with_atlas(atlas_id, |atlas| atlas.insert_or_evict(width, height))
+    }
+
+    /// Mark a sprite as used this frame; false if it was evicted
+    pub fn atlas_touch(&self, atlas_id: u64, entry_id: u64) -> Result<bool> {
+        self.with_atlas(atlas_id, |atlas| atlas.touch(entry_id))
+    }
+
+    /// Free the sprite occupying exactly `rect`, returning its id
+    pub fn atlas_evict_rect(&self, atlas_id: u64, rect: AtlasRect) -> Result<Option<AtlasEntryId>> {
+        self.with_atlas(atlas_id, |atlas| atlas.evict_rect(rect))
+    }
+
+    /// Compact an atlas and move its sprites inside `texture`
+    ///
+    /// `texture` is the atlas texture the caller uploads sprites into; see
+    /// `apply_atlas_relocations` for its usage requirements. If the copies
+    /// fail the atlas keeps its old layout, matching the texture's texels.
+    pub fn atlas_repack(&self, atlas_id: u64, texture: id::TextureId, format: wgt::TextureFormat) -> Result<AtlasRepack> {
+        let mut atlases = self.atlases.lock();
+        let atlas = atlases
+            .get_mut(&atlas_id)
+            .ok_or_else(|| BasaltError::invalid_parameter("atlas", format!("no atlas with id {}", atlas_id)))?;
+        let previous = atlas.clone();
+        let repack = atlas.repack();
+        if let Err(e) = self.apply_atlas_relocations(texture, format, atlas.width(), atlas.height(), &repack.relocations) {
+            *atlas = previous;
+            return Err(e);
+        }
+        Ok(repack)
+    }
+
+    /// Free every sprite of an atlas
+    pub fn atlas_reset(&self, atlas_id: u64) -> Result<()> {
+        self.with_atlas(atlas_id, TextureAtlas::clear)
+    }
+
+    /// Forget an atlas allocator
+    pub fn atlas_destroy(&self, atlas_id: u64) {
+        self.atlases.lock().remove(&atlas_id);
+    }
+
+    fn with_atlas<R>(&self, atlas_id: u64, f: impl FnOnce(&mut TextureAtlas) -> R) -> Result<R> {
+        let mut atlases = self.atlases.lock();
+        let atlas = atlases
+            .get_mut(&atlas_id)
+            .ok_or_else(|| BasaltError::invalid_parameter("atlas", format!("no atlas with id {}", atlas_id)))?;
+        Ok(f(atlas))
+    }
+
     /// Destroy a buffer
     pub fn destroy_buffer(&self, buffer_id: id::BufferId) {
+        self.write_batch.discard(buffer_id);
+        self.suballocation_pools.lock().remove(&buffer_id);
+        self.bind_group_cache.invalidate_buffer(buffer_id);
         self.context.inner().buffer_drop(buffer_id);
     }
 
-    /// Create a texture
+    /// Create a single-sampled 2D texture
     pub fn create_texture(
         &self,
         width: u32,
@@ -1205,16 +2442,56 @@ impl BasaltDevice {
         format: u32,
         usage: u32,
     ) -> Result<id::TextureId> {
+        self.create_texture_from_request(TextureRequest::new(width, height, depth, mip_levels, format, usage))
+    }
+
+    /// Create a texture with the sample count, dimension, view formats and label of `request`
+    ///
+    /// `sample_count` > 1 creates a multisampled render target. Multisampled
+    /// textures can't have mips or storage usage, so those are dropped.
+    pub fn create_texture_from_request(&self, request: TextureRequest) -> Result<id::TextureId> {
+        let TextureRequest { width, height, depth, mip_levels, format, usage, sample_count, dimension, view_formats, label } =
+            request;
+        if !matches!(sample_count, 1 | 2 | 4 | 8 | 16) {
+            return Err(BasaltError::invalid_parameter(
+                "sample_count",
+                format!("unsupported sample count {}", sample_count),
+            ));
+        }
+
         let texture_format = self.map_texture_format_public(format)?;
+
+        if dimension == wgt::TextureDimension::D3 {
+            if sample_count > 1 {
+                return Err(BasaltError::invalid_parameter("sample_count", "3D textures can't be multisampled"));
+            }
+            if texture_format.is_depth_stencil_format() {
+                return Err(BasaltError::invalid_parameter(
+                    "format",
+                    format!("3D textures can't use depth-stencil format {:?}", texture_format),
+                ));
+            }
+        }
         let texture_usage = self.map_texture_usage(usage);
 
+        // Block-compressed formats need their compression feature on the device
+        let missing = texture_format.required_features() - self.context.inner().device_features(self.device_id);
+        if !missing.is_empty() {
+            return Err(BasaltError::invalid_parameter(
+                "format",
+                format!("{:?} requires {:?}, which isn't enabled on this device", texture_format, missing),
+            ));
+        }
+
         // For all render target textures (RENDER_ATTACHMENT), also add TEXTURE_BINDING
         // so they can be sampled as inputs in subsequent render passes (compositing, post-processing, etc.)
         // This is essential for multi-pass rendering where intermediate textures need to be sampled.
+        // Single-sampled targets also get COPY_SRC so present can copy them into the swapchain.
         let texture_usage = if texture_usage.contains(wgt::TextureUsages::RENDER_ATTACHMENT) {
             log::info!("Adding TEXTURE_BINDING to render target {}x{} (format={:?}) for shader sampling",
                 width, height, texture_format);
-            texture_usage | wgt::TextureUsages::TEXTURE_BINDING
+            let copy_src = if sample_count == 1 { wgt::TextureUsages::COPY_SRC } else { wgt::TextureUsages::empty() };
+            texture_usage | wgt::TextureUsages::TEXTURE_BINDING | copy_src
         } else {
             texture_usage
         };
@@ -1239,6 +2516,7 @@ impl BasaltDevice {
             | wgt::TextureFormat::Rg8Sint
             | wgt::TextureFormat::R8Sint
             // Depth/stencil formats (none support storage binding)
+            | wgt::TextureFormat::Depth16Unorm
             | wgt::TextureFormat::Depth24Plus
             | wgt::TextureFormat::Depth32Float
             | wgt::TextureFormat::Depth24PlusStencil8
@@ -1246,26 +2524,22 @@ impl BasaltDevice {
             | wgt::TextureFormat::Depth32FloatStencil8 => {
                 texture_usage - wgt::TextureUsages::STORAGE_BINDING
             }
+            // Compressed formats can only be sampled and copied into
+            _ if texture_format.is_compressed() => {
+                texture_usage - wgt::TextureUsages::STORAGE_BINDING - wgt::TextureUsages::RENDER_ATTACHMENT
+            }
             _ => texture_usage,
         };
-
-        // Calculate maximum allowed mip levels for this texture size
-        // Max mip levels = floor(log2(max(width, height))) + 1
-        let max_dimension = width.max(height);
-        let max_mip_levels = (max_dimension as f32).log2().floor() as u32 + 1;
-        
-        // Clamp requested mip levels to the valid range
-        let actual_mip_levels = if mip_levels > max_mip_levels {
-            log::debug!(
-                "Clamping mip levels from {} to {} for {}x{} texture",
-                mip_levels, max_mip_levels, width, height
-            );
-            max_mip_levels
-        } else if mip_levels == 0 {
-            1 // Minimum 1 mip level
+        let filtered_usage = if sample_count > 1 {
+            filtered_usage - wgt::TextureUsages::STORAGE_BINDING
         } else {
-            mip_levels
+            filtered_usage
         };
+        if texture_format.is_depth_stencil_format() {
+            check_format_usages(&self.context, self.adapter_id, self.device_id, texture_format, filtered_usage)?;
+        }
+
+        let actual_mip_levels = Self::texture_mip_level_count(dimension, width, height, depth, mip_levels, sample_count);
 
         let extent = wgt::Extent3d {
             width,
@@ -1273,18 +2547,36 @@ impl BasaltDevice {
             depth_or_array_layers: depth,
         };
 
-        // Create a descriptive label based on texture usage
-        let label = self.texture_usage_to_label(filtered_usage, width, height, texture_format);
+        // Create a descriptive label based on texture usage, unless the caller named the texture
+        let label = match label {
+            Some(label) => label,
+            None => self.texture_usage_to_label(filtered_usage, width, height, texture_format),
+        };
+
+        // Allow sRGB <-> linear reinterpretation through ranged views unless told otherwise
+        let compatible = crate::texture::compatible_view_formats(texture_format);
+        let view_formats = match view_formats {
+            Some(view_formats) => {
+                if let Some(f) = view_formats.iter().find(|f| !compatible.contains(f)) {
+                    return Err(BasaltError::invalid_parameter(
+                        "view_formats",
+                        format!("{:?} is not a compatible view format for {:?}", f, texture_format),
+                    ));
+                }
+                view_formats
+            }
+            None => compatible.into_iter().filter(|&f| f != texture_format).collect(),
+        };
 
         let desc = wgt::TextureDescriptor {
             label: Some(Cow::Owned(label)),
             size: extent,
             mip_level_count: actual_mip_levels,
-            sample_count: 1,
-            dimension: wgt::TextureDimension::D2,
+            sample_count,
+            dimension,
             format: texture_format,
             usage: filtered_usage,
-            view_formats: vec![],
+            view_formats,
         };
 
         let (texture_id, error) = self
@@ -1296,8 +2588,8 @@ impl BasaltDevice {
             return Err(BasaltError::Wgpu(format!("{:?}", e)));
         }
 
-        log::info!("Created texture {:?}: {}x{}x{} format={:?}",
-            texture_id, width, height, depth, texture_format);
+        log::info!("Created {:?} texture {:?}: {}x{}x{} format={:?} samples={}",
+            dimension, texture_id, width, height, depth, texture_format, sample_count);
 
         // NOTE: main_framebuffer is now ONLY set by set_main_framebuffer() which is called
         // from endRenderPass() after a render pass completes. We no longer auto-detect it here
@@ -1307,39 +2599,69 @@ impl BasaltDevice {
         Ok(texture_id)
     }
 
+    /// Mip count `create_texture` actually allocates for a requested count
+    ///
+    /// Clamped to the size's full mip chain; multisampled textures always get one.
+    pub fn mip_level_count(width: u32, height: u32, mip_levels: u32, sample_count: u32) -> u32 {
+        // Max mip levels = floor(log2(max(width, height))) + 1
+        let max_dimension = width.max(height);
+        let max_mip_levels = (max_dimension as f32).log2().floor() as u32 + 1;
+
+        if sample_count > 1 {
+            1 // Multisampled textures must have a single mip level
+        } else if mip_levels > max_mip_levels {
+            log::debug!(
+                "Clamping mip levels from {} to {} for {}x{} texture",
+                mip_levels, max_mip_levels, width, height
+            );
+            max_mip_levels
+        } else if mip_levels == 0 {
+            1 // Minimum 1 mip level
+        } else {
+            mip_levels
+        }
+    }
+
+    /// `mip_level_count` for a texture of `dimension`; 3D mips also halve the depth
+    pub fn texture_mip_level_count(
+        dimension: wgt::TextureDimension,
+        width: u32,
+        height: u32,
+        depth: u32,
+        mip_levels: u32,
+        sample_count: u32,
+    ) -> u32 {
+        let height = if dimension == wgt::TextureDimension::D3 { height.max(depth) } else { height };
+        Self::mip_level_count(width, height, mip_levels, sample_count)
+    }
+
     /// Destroy a texture
     pub fn destroy_texture(&self, texture_id: id::TextureId) {
+        self.bind_group_cache.invalidate_texture(texture_id);
         self.context.inner().texture_drop(texture_id);
     }
 
     /// Create a texture view with descriptive debug label, returns (view_id, dimension)
-    /// array_layers is used to determine if this is a D2 or D2Array texture
+    /// `dimension`, `depth_or_array_layers` and `is_cube` pick a D2, D2Array, Cube or D3 view
+    /// (see `full_view_dimension`)
     pub fn create_texture_view(
         &self,
         texture_id: id::TextureId,
-        array_layers: u32,
+        dimension: wgt::TextureDimension,
+        depth_or_array_layers: u32,
+        is_cube: bool,
     ) -> Result<(id::TextureViewId, wgt::TextureViewDimension)> {
-        // Determine the view dimension based on array layers
-        // - 1 layer = D2 (regular 2D texture)
-        // - 6 layers = Cube (cubemap for panorama)
-        // - >1 layers (not 6) = D2Array
-        let view_dimension = if array_layers == 6 {
-            log::info!("Creating Cube texture view for 6-layer texture (panorama cubemap)");
-            wgt::TextureViewDimension::Cube
-        } else if array_layers > 1 {
-            wgt::TextureViewDimension::D2Array
-        } else {
-            wgt::TextureViewDimension::D2
-        };
+        let view_dimension = full_view_dimension(dimension, depth_or_array_layers, is_cube);
 
         // Create a descriptive label based on dimension
         let dim_name = match view_dimension {
             wgt::TextureViewDimension::D2 => "D2",
             wgt::TextureViewDimension::D2Array => "D2Array",
             wgt::TextureViewDimension::Cube => "Cube",
+            wgt::TextureViewDimension::D3 => "D3",
             _ => "Unknown",
         };
-        let label = format!("Bassalt Texture View: {} ({} layers)", dim_name, array_layers);
+        let label = format!("Bassalt Texture View: {} ({} layers)", dim_name, depth_or_array_layers);
 
         let desc = wgpu_core::resource::TextureViewDescriptor {
             label: Some(Cow::Owned(label)),
@@ -1369,62 +2691,147 @@ impl BasaltDevice {
 
         log::debug!(
             "Created texture view for texture {:?} with {} layers -> dimension {:?}",
-            texture_id, array_layers, view_dimension
+            texture_id, depth_or_array_layers, view_dimension
         );
 
         Ok((view_id, view_dimension))
     }
 
-    /// Create a sampler
-    pub fn create_sampler(
+    /// Create a view of a mip/layer range of a texture, optionally reinterpreting its format
+    ///
+    /// Used to bind a single cubemap face, a single mip for manual mip generation,
+    /// an sRGB texture as linear, or one aspect of a depth-stencil texture.
+    /// Ranges are validated against the texture's recorded mip and layer counts.
+    /// Returns the view, its dimension and the aspect it covers.
+    pub fn create_texture_view_ranged(
         &self,
-        address_mode_u: u32,
-        address_mode_v: u32,
-        address_mode_w: u32,
-        min_filter: u32,
-        mag_filter: u32,
-        mipmap_filter: u32,
-        lod_min_clamp: f32,
-        lod_max_clamp: f32,
-        max_anisotropy: u32,
-    ) -> Result<id::SamplerId> {
-        let desc = wgpu_core::resource::SamplerDescriptor {
-            label: Some(Cow::Borrowed("Basalt Sampler")),
-            address_modes: [
-                self.map_address_mode(address_mode_u)?,
-                self.map_address_mode(address_mode_v)?,
-                self.map_address_mode(address_mode_w)?,
-            ],
-            mag_filter: self.map_filter_mode(mag_filter)?,
-            min_filter: self.map_filter_mode(min_filter)?,
-            mipmap_filter: self.map_mipmap_filter(mipmap_filter)?,
-            lod_min_clamp,
-            lod_max_clamp,
-            compare: None,
-            anisotropy_clamp: max_anisotropy.min(16) as u16,
-            border_color: None,
+        texture: &TextureInfo,
+        request: ViewRangeRequest,
+    ) -> Result<(id::TextureViewId, wgt::TextureViewDimension, wgt::TextureAspect)> {
+        let view = resolve_view_range(texture, request)?;
+
+        let label = format!(
+            "Bassalt Texture View: {:?} (mips {}+{}, layers {}+{})",
+            view.dimension,
+            view.range.base_mip_level,
+            view.range.mip_level_count.unwrap_or(1),
+            view.range.base_array_layer,
+            view.range.array_layer_count.unwrap_or(1),
+        );
+        let desc = wgpu_core::resource::TextureViewDescriptor {
+            label: Some(Cow::Owned(label)),
+            format: view.format,
+            dimension: Some(view.dimension),
+            usage: None,
+            range: view.range,
         };
 
-        let (sampler_id, error) = self
+        let (view_id, error) = self
             .context
             .inner()
-            .device_create_sampler(self.device_id, &desc, None);
+            .texture_create_view(texture.id, &desc, None);
 
         if let Some(e) = error {
-            return Err(BasaltError::Wgpu(format!("{:?}", e)));
+            return Err(BasaltError::wgpu_context("ranged texture view creation", e));
+        }
+
+        self.context.register_texture_view(view_id, texture.id);
+
+        log::debug!("Created ranged texture view for texture {:?}: {:?}", texture.id, view);
+
+        Ok((view_id, view.dimension, view.range.aspect))
+    }
+
+    /// Create a sampler, or reuse a cached one with the same parameters
+    ///
+    /// Comparison samplers are used for depth textures bound as
+    /// `sampler_comparison` (shadow maps). `max_anisotropy` is clamped to
+    /// `max_anisotropy()`, and anisotropy above 1 makes all filters linear, see
+    /// `SamplerDescriptor::with_anisotropy_limit`. Labels aren't part of the
+    /// cache key, so `label` only names a newly created sampler; `None` keeps
+    /// the default "Basalt Sampler".
+    pub fn create_sampler(&self, request: SamplerRequest) -> Result<CachedSampler> {
+        let compare = if request.compare_function < 0 {
+            None
+        } else {
+            Some(self.map_compare_function(request.compare_function as u32)?)
+        };
+
+        let desc = SamplerDescriptor {
+            label: Some(request.label.unwrap_or_else(|| "Basalt Sampler".to_string())),
+            address_mode_u: self.map_address_mode(request.address_mode_u)?,
+            address_mode_v: self.map_address_mode(request.address_mode_v)?,
+            address_mode_w: self.map_address_mode(request.address_mode_w)?,
+            mag_filter: self.map_filter_mode(request.mag_filter)?,
+            min_filter: self.map_filter_mode(request.min_filter)?,
+            mipmap_filter: self.map_mipmap_filter(request.mipmap_filter)?,
+            lod_min_clamp: request.lod_min_clamp,
+            lod_max_clamp: request.lod_max_clamp,
+            compare,
+            anisotropy_clamp: request.max_anisotropy.min(u16::MAX as u32) as u16,
+            border_color: None,
+        }
+        .with_anisotropy_limit(self.max_anisotropy);
+
+        self.sampler_cache.get_or_create(&self.context, self.device_id, &desc)
+    }
+
+    /// Get the cached sampler for a filtering setup
+    ///
+    /// Common setups are created with the device (`SamplerConfig::presets`),
+    /// so switching between them per draw doesn't create sampler objects.
+    pub fn get_sampler(&self, config: &SamplerConfig) -> Result<CachedSampler> {
+        self.sampler_cache.get_config(&self.context, self.device_id, config)
+    }
+
+    /// Create a comparison sampler for PCF shadow maps
+    ///
+    /// See `SamplerDescriptor::shadow`. Bind it next to a `texture_depth_2d`
+    /// declared as `sampler_comparison` in the shader.
+    pub fn create_shadow_sampler(&self) -> Result<CachedSampler> {
+        self.sampler_cache.get_or_create(&self.context, self.device_id, &SamplerDescriptor::shadow())
+    }
+
+    /// Create a shader module from a SPIR-V binary
+    ///
+    /// The words are parsed and validated with naga first, so malformed
+    /// binaries fail here with a readable error instead of inside wgpu-core.
+    pub fn create_shader_module_spirv(&self, words: &[u32], label: &str) -> Result<id::ShaderModuleId> {
+        let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+        let module = crate::shader::parse_spirv_named(&bytes, label)?;
+
+        let desc = wgpu_core::pipeline::ShaderModuleDescriptor {
+            label: Some(Cow::Owned(format!("Shader: {}", label))),
+            runtime_checks: wgt::ShaderRuntimeChecks::default(),
+        };
+
+        let (module_id, error) = self.context.inner().device_create_shader_module(
+            self.device_id,
+            &desc,
+            wgpu_core::pipeline::ShaderModuleSource::Naga(Cow::Owned(module)),
+            None,
+        );
+
+        if let Some(e) = error {
+            return Err(BasaltError::shader_compilation(label, format!("{:?}", e), "spirv"));
         }
 
-        Ok(sampler_id)
+        Ok(module_id)
     }
 
     /// Write data to texture using queue
     ///
+    /// Writes one image: `origin_z` picks the array layer, or the depth slice of
+    /// a 3D texture, so `rows_per_image` is always the image's own row count.
+    ///
     /// **CRITICAL:** WebGPU requires bytes_per_row to be 256-byte aligned for buffer-to-texture copies.
     /// This function automatically pads the data to meet this requirement.
     /// See checklist Step 214 for details.
+    #[allow(clippy::too_many_arguments)]
     pub fn write_texture(
         &self,
         texture_id: id::TextureId,
+        format: wgt::TextureFormat,
         data: &[u8],
         mip_level: u32,
         origin_x: u32,
@@ -1433,6 +2840,7 @@ impl BasaltDevice {
         width: u32,
         height: u32,
     ) -> Result<()> {
+        let _serialized = self.begin_submission()?;
         let texture_copy = wgt::TexelCopyTextureInfo {
             texture: texture_id,
             mip_level,
@@ -1446,8 +2854,17 @@ impl BasaltDevice {
 
         // CRITICAL FIX: Align bytes_per_row to 256 bytes (WebGPU requirement)
         // See checklist Step 214: bytes_per_row must be multiple of 256 for buffer copies
-        let original_bytes_per_row = width * 4; // RGBA8 = 4 bytes per pixel
-        let aligned_bytes_per_row = ((original_bytes_per_row + 255) & !255) as u32;
+        // Compressed formats count rows of blocks rather than rows of texels.
+        let (original_bytes_per_row, rows) = upload_row_layout(format, width, height)?;
+        let aligned_bytes_per_row = (original_bytes_per_row + 255) & !255;
+
+        let required = original_bytes_per_row as usize * rows as usize;
+        if data.len() < required {
+            return Err(BasaltError::invalid_parameter(
+                "data",
+                format!("{}x{} {:?} upload needs {} bytes, got {}", width, height, format, required, data.len()),
+            ));
+        }
 
         // If data is already aligned, use it directly
         let (data_to_upload, data_layout) = if original_bytes_per_row == aligned_bytes_per_row {
@@ -1455,7 +2872,7 @@ impl BasaltDevice {
             (Cow::Borrowed(data), wgt::TexelCopyBufferLayout {
                 offset: 0,
                 bytes_per_row: Some(original_bytes_per_row),
-                rows_per_image: Some(height),
+                rows_per_image: Some(rows),
             })
         } else {
             // Data is not aligned - need to pad each row to 256-byte boundary
@@ -1463,10 +2880,10 @@ impl BasaltDevice {
                        original_bytes_per_row, aligned_bytes_per_row);
 
             let padding_bytes = (aligned_bytes_per_row - original_bytes_per_row) as usize;
-            let padded_size = (aligned_bytes_per_row * height) as usize;
+            let padded_size = (aligned_bytes_per_row * rows) as usize;
             let mut padded_data = Vec::with_capacity(padded_size);
 
-            for row in 0..height {
+            for row in 0..rows {
                 let row_start = (row * original_bytes_per_row) as usize;
                 let row_end = row_start + original_bytes_per_row as usize;
                 let row_data = &data[row_start..row_end];
@@ -1476,8 +2893,8 @@ impl BasaltDevice {
 
                 // Add padding to reach 256-byte boundary
                 // Note: Last row doesn't need padding since there's no next row to stride to
-                if row < height - 1 {
-                    padded_data.extend(std::iter::repeat(0).take(padding_bytes));
+                if row < rows - 1 {
+                    padded_data.extend(std::iter::repeat_n(0, padding_bytes));
                 }
             }
 
@@ -1487,13 +2904,15 @@ impl BasaltDevice {
             (Cow::Owned(padded_data), wgt::TexelCopyBufferLayout {
                 offset: 0,
                 bytes_per_row: Some(aligned_bytes_per_row),
-                rows_per_image: Some(height),
+                rows_per_image: Some(rows),
             })
         };
 
+        // Compressed copies cover whole blocks, even past the edge of a small mip
+        let (block_width, block_height) = format.block_dimensions();
         let size = wgt::Extent3d {
-            width,
-            height,
+            width: width.next_multiple_of(block_width),
+            height: height.next_multiple_of(block_height),
             depth_or_array_layers: 1,
         };
 
@@ -1505,6 +2924,123 @@ impl BasaltDevice {
         Ok(())
     }
 
+    /// Upload many small texture regions through one staging buffer
+    ///
+    /// Every region's rows are repacked at 256-byte aligned pitch into a single
+    /// staging buffer, which is copied to the textures with one
+    /// `copy_buffer_to_texture` per region and a single submit. This amortizes the
+    /// per-call staging allocation of `write_texture` across e.g. atlas sprites.
+    pub fn write_textures_batch(&self, writes: &[TextureWrite], data: &[u8]) -> Result<()> {
+        let _serialized = self.begin_submission()?;
+        if writes.is_empty() {
+            return Ok(());
+        }
+
+        const ALIGN: u32 = wgt::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        // Lay out every region before touching the GPU so a bad entry fails the whole batch
+        let mut layouts = Vec::with_capacity(writes.len());
+        let mut staging_size = 0u64;
+        for (i, write) in writes.iter().enumerate() {
+            let (bytes_per_row, rows) = write.validate(data.len()).map_err(|e| {
+                BasaltError::invalid_parameter(format!("writes[{}]", i), e)
+            })?;
+            let aligned_bytes_per_row = bytes_per_row.next_multiple_of(ALIGN);
+            layouts.push((staging_size, bytes_per_row, aligned_bytes_per_row, rows));
+            staging_size += (aligned_bytes_per_row as u64 * rows as u64).next_multiple_of(ALIGN as u64);
+        }
+
+        let mut staging = vec![0u8; staging_size as usize];
+        for (write, &(staging_offset, bytes_per_row, aligned_bytes_per_row, rows)) in writes.iter().zip(&layouts) {
+            for row in 0..rows as usize {
+                let src = write.data_offset as usize + row * bytes_per_row as usize;
+                let dst = staging_offset as usize + row * aligned_bytes_per_row as usize;
+                staging[dst..dst + bytes_per_row as usize].copy_from_slice(&data[src..src + bytes_per_row as usize]);
+            }
+        }
+
+        let staging_desc = wgt::BufferDescriptor {
+            label: Some(Cow::Borrowed("Bassalt Texture Upload Staging")),
+            size: staging_size,
+            usage: wgt::BufferUsages::COPY_SRC | wgt::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        };
+
+        let global = self.context.inner();
+        let (staging_id, error) = global.device_create_buffer(self.device_id, &staging_desc, None);
+
+        if let Some(e) = error {
+            return Err(BasaltError::Wgpu(format!("{:?}", e)));
+        }
+
+        let record = || -> Result<()> {
+            global
+                .queue_write_buffer(self.queue_id, staging_id, 0, &staging)
+                .map_err(|e| BasaltError::Wgpu(format!("{:?}", e)))?;
+
+            let encoder_desc = wgt::CommandEncoderDescriptor {
+                label: Some(Cow::Borrowed("Texture Upload Batch Encoder")),
+            };
+            let (encoder_id, error) = global.device_create_command_encoder(self.device_id, &encoder_desc, None);
+
+            if let Some(e) = error {
+                return Err(BasaltError::Wgpu(format!("{:?}", e)));
+            }
+
+            for (i, (write, &(staging_offset, _, aligned_bytes_per_row, rows))) in writes.iter().zip(&layouts).enumerate() {
+                let (block_width, block_height) = write.format.block_dimensions();
+                let source = wgt::TexelCopyBufferInfo {
+                    buffer: staging_id,
+                    layout: wgt::TexelCopyBufferLayout {
+                        offset: staging_offset,
+                        bytes_per_row: Some(aligned_bytes_per_row),
+                        rows_per_image: Some(rows),
+                    },
+                };
+                let destination = wgt::TexelCopyTextureInfo {
+                    texture: write.texture_id,
+                    mip_level: write.mip_level,
+                    origin: wgt::Origin3d { x: write.x, y: write.y, z: write.layer },
+                    aspect: wgt::TextureAspect::All,
+                };
+                let size = wgt::Extent3d {
+                    width: write.width.next_multiple_of(block_width),
+                    height: write.height.next_multiple_of(block_height),
+                    depth_or_array_layers: 1,
+                };
+
+                global
+                    .command_encoder_copy_buffer_to_texture(encoder_id, &source, &destination, &size)
+                    .map_err(|e| BasaltError::wgpu_context(format!("texture upload batch writes[{}]", i), e))?;
+            }
+
+            let (command_buffer, error) = global.command_encoder_finish(
+                encoder_id,
+                &wgt::CommandBufferDescriptor::default(),
+                None,
+            );
+
+            if let Some(e) = error {
+                return Err(BasaltError::wgpu_context("texture upload batch", e.1));
+            }
+
+            global
+                .queue_submit(self.queue_id, &[command_buffer])
+                .map_err(|(_, e)| self.submit_error("texture upload batch submit", &e))?;
+
+            Ok(())
+        };
+
+        let result = record();
+
+        // The submission keeps the staging buffer alive until the copies complete
+        global.buffer_drop(staging_id);
+
+        log::debug!("Uploaded {} texture regions through {} staging bytes", writes.len(), staging_size);
+
+        result
+    }
+
     /// Copy buffer to buffer
     pub fn copy_buffer_to_buffer(
         &self,
@@ -1514,6 +3050,8 @@ impl BasaltDevice {
         dst_offset: u64,
         size: u64,
     ) -> Result<()> {
+        let _serialized = self.begin_submission()?;
+        self.flush_buffer_writes()?;
         // Create a command encoder for the copy operation
         let encoder_desc = wgt::CommandEncoderDescriptor {
             label: Some(Cow::Borrowed("Copy Command Encoder")),
@@ -1554,24 +3092,82 @@ impl BasaltDevice {
         self.context
             .inner()
             .queue_submit(self.queue_id, &[command_buffer])
-            .map_err(|e| BasaltError::Wgpu(format!("{:?}", e)))?;
+            .map_err(|(_, e)| self.submit_error("buffer copy submit", &e))?;
 
         Ok(())
     }
 
-    /// Copy texture to buffer (readback)
-    pub fn copy_texture_to_buffer(
+    /// Zero `size` bytes of a buffer starting at `offset`
+    ///
+    /// Both must be multiples of 4 (see `buffer::clear_range`), and the
+    /// buffer needs COPY_DST usage. Useful for resetting indirect draw
+    /// counts and atomic counters between frames.
+    pub fn clear_buffer(&self, buffer_id: id::BufferId, offset: u64, size: u64) -> Result<()> {
+        let _serialized = self.begin_submission()?;
+        self.flush_buffer_writes()?;
+        let global = self.context.inner();
+        let encoder_desc = wgt::CommandEncoderDescriptor {
+            label: Some(Cow::Borrowed("Clear Buffer Encoder")),
+        };
+        let (encoder_id, error) = global.device_create_command_encoder(self.device_id, &encoder_desc, None);
+        if let Some(e) = error {
+            return Err(BasaltError::Wgpu(format!("{:?}", e)));
+        }
+
+        if let Err(e) = global.command_encoder_clear_buffer(encoder_id, buffer_id, offset, Some(size)) {
+            return Err(BasaltError::Wgpu(format!("{:?}", e)));
+        }
+
+        let (command_buffer, error) =
+            global.command_encoder_finish(encoder_id, &wgt::CommandBufferDescriptor::default(), None);
+        if let Some(e) = error {
+            return Err(BasaltError::Wgpu(format!("{:?}", e)));
+        }
+
+        global
+            .queue_submit(self.queue_id, &[command_buffer])
+            .map_err(|(_, e)| self.submit_error("buffer clear submit", &e))?;
+
+        Ok(())
+    }
+
+    /// Apply moves produced by `RangeAllocator::defragment` to an arena buffer
+    ///
+    /// WebGPU forbids copies where source and destination are the same buffer,
+    /// and a move may overlap its own old range, so every move is staged through
+    /// a scratch buffer: all sources are copied out first, then written back at
+    /// their new offsets. The arena buffer needs COPY_SRC | COPY_DST usage.
+    pub fn apply_range_moves(
         &self,
-        texture_id: id::TextureId,
-        buffer_id: id::BufferId,
-        buffer_offset: u64,
-        mip_level: u32,
-        width: u32,
-        height: u32,
+        arena_buffer: id::BufferId,
+        moves: &[RangeMove],
     ) -> Result<()> {
-        // Create a command encoder for the copy operation
+        let _serialized = self.begin_submission()?;
+        // Batched writes target the old offsets, so they must land before the moves
+        self.flush_buffer_writes()?;
+        if moves.is_empty() {
+            return Ok(());
+        }
+
+        let scratch_size: u64 = moves.iter().map(|m| m.size).sum();
+        let scratch_desc = wgt::BufferDescriptor {
+            label: Some(Cow::Borrowed("Bassalt Defragment Scratch")),
+            size: scratch_size,
+            usage: wgt::BufferUsages::COPY_SRC | wgt::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        };
+
+        let (scratch_id, error) = self
+            .context
+            .inner()
+            .device_create_buffer(self.device_id, &scratch_desc, None);
+
+        if let Some(e) = error {
+            return Err(BasaltError::Wgpu(format!("{:?}", e)));
+        }
+
         let encoder_desc = wgt::CommandEncoderDescriptor {
-            label: Some(Cow::Borrowed("Readback Command Encoder")),
+            label: Some(Cow::Borrowed("Defragment Command Encoder")),
         };
 
         let (encoder_id, error) = self
@@ -1580,68 +3176,183 @@ impl BasaltDevice {
             .device_create_command_encoder(self.device_id, &encoder_desc, None);
 
         if let Some(e) = error {
+            self.context.inner().buffer_drop(scratch_id);
             return Err(BasaltError::Wgpu(format!("{:?}", e)));
         }
 
-        let texture_copy = wgt::TexelCopyTextureInfo {
-            texture: texture_id,
-            mip_level,
-            origin: wgt::Origin3d::ZERO,
-            aspect: wgt::TextureAspect::All,
-        };
+        let record = || -> Result<()> {
+            let global = self.context.inner();
 
-        let bytes_per_row = width * 4; // Assuming RGBA8
-        let buffer_copy = wgt::TexelCopyBufferInfo {
-            buffer: buffer_id,
-            layout: wgt::TexelCopyBufferLayout {
-                offset: buffer_offset,
-                bytes_per_row: Some(bytes_per_row),
-                rows_per_image: Some(height),
-            },
-        };
+            let mut scratch_offset = 0;
+            for m in moves {
+                global
+                    .command_encoder_copy_buffer_to_buffer(
+                        encoder_id, arena_buffer, m.old_offset, scratch_id, scratch_offset, Some(m.size),
+                    )
+                    .map_err(|e| BasaltError::Wgpu(format!("{:?}", e)))?;
+                scratch_offset += m.size;
+            }
 
-        let size = wgt::Extent3d {
-            width,
-            height,
-            depth_or_array_layers: 1,
+            let mut scratch_offset = 0;
+            for m in moves {
+                global
+                    .command_encoder_copy_buffer_to_buffer(
+                        encoder_id, scratch_id, scratch_offset, arena_buffer, m.new_offset, Some(m.size),
+                    )
+                    .map_err(|e| BasaltError::Wgpu(format!("{:?}", e)))?;
+                scratch_offset += m.size;
+            }
+
+            let (command_buffer, error) = global.command_encoder_finish(
+                encoder_id,
+                &wgt::CommandBufferDescriptor::default(),
+                None,
+            );
+
+            if let Some(e) = error {
+                return Err(BasaltError::Wgpu(format!("{:?}", e)));
+            }
+
+            global
+                .queue_submit(self.queue_id, &[command_buffer])
+                .map_err(|(_, e)| self.submit_error("range move submit", &e))?;
+
+            Ok(())
         };
 
-        // Record copy command
-        if let Err(e) = self.context.inner().command_encoder_copy_texture_to_buffer(
-            encoder_id,
-            &texture_copy,
-            &buffer_copy,
-            &size,
-        ) {
-            return Err(BasaltError::Wgpu(format!("{:?}", e)));
-        }
+        let result = record();
 
-        // Finish and submit
-        let (command_buffer, error) = self.context.inner().command_encoder_finish(
-            encoder_id,
-            &wgt::CommandBufferDescriptor::default(),
-            None,
+        // The submission keeps the scratch buffer alive until the copies complete
+        self.context.inner().buffer_drop(scratch_id);
+
+        log::debug!(
+            "Applied {} range moves ({} bytes) to arena buffer {:?}",
+            moves.len(), scratch_size, arena_buffer
         );
 
-        if let Some(e) = error {
-            return Err(BasaltError::Wgpu(format!("{:?}", e)));
-        }
+        result
+    }
 
-        self.context
-            .inner()
-            .queue_submit(self.queue_id, &[command_buffer])
-            .map_err(|e| BasaltError::Wgpu(format!("{:?}", e)))?;
+    /// Copy texture to buffer (readback)
+    ///
+    /// Rows are tightly packed at the size of one texel of the readback's
+    /// aspect, e.g. 4 bytes for the depth of `Depth32Float`. WebGPU copies
+    /// texture rows at a 256-byte pitch, so narrower regions are copied into a
+    /// padded scratch buffer first and then moved into `buffer_id` row by row.
+    pub fn copy_texture_to_buffer(
+        &self,
+        readback: TextureReadback,
+        buffer_id: id::BufferId,
+        buffer_offset: u64,
+    ) -> Result<()> {
+        let TextureReadback { texture_id, format, aspect, mip_level, width, height } = readback;
+        let layout = crate::texture::readback_row_layout(format, aspect, width, height)?;
+        let _serialized = self.begin_submission()?;
+        self.flush_buffer_writes()?;
+        let global = self.context.inner();
+
+        // Padded rows land in a scratch buffer; aligned rows go straight to the destination
+        let scratch_id = if layout.needs_unpadding() {
+            let scratch_desc = wgt::BufferDescriptor {
+                label: Some(Cow::Borrowed("Bassalt Readback Scratch")),
+                size: layout.padded_bytes_per_row as u64 * layout.rows as u64,
+                usage: wgt::BufferUsages::COPY_SRC | wgt::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            };
+            let (scratch_id, error) = global.device_create_buffer(self.device_id, &scratch_desc, None);
+            if let Some(e) = error {
+                return Err(BasaltError::Wgpu(format!("{:?}", e)));
+            }
+            Some(scratch_id)
+        } else {
+            None
+        };
 
-        Ok(())
+        let record = || -> Result<()> {
+            let encoder_desc = wgt::CommandEncoderDescriptor {
+                label: Some(Cow::Borrowed("Readback Command Encoder")),
+            };
+            let (encoder_id, error) = global.device_create_command_encoder(self.device_id, &encoder_desc, None);
+            if let Some(e) = error {
+                return Err(BasaltError::Wgpu(format!("{:?}", e)));
+            }
+
+            let texture_copy = wgt::TexelCopyTextureInfo {
+                texture: texture_id,
+                mip_level,
+                origin: wgt::Origin3d::ZERO,
+                aspect,
+            };
+
+            let buffer_copy = wgt::TexelCopyBufferInfo {
+                buffer: scratch_id.unwrap_or(buffer_id),
+                layout: wgt::TexelCopyBufferLayout {
+                    offset: if scratch_id.is_some() { 0 } else { buffer_offset },
+                    bytes_per_row: Some(layout.padded_bytes_per_row),
+                    rows_per_image: Some(layout.rows),
+                },
+            };
+
+            let size = wgt::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            };
+
+            global
+                .command_encoder_copy_texture_to_buffer(encoder_id, &texture_copy, &buffer_copy, &size)
+                .map_err(|e| BasaltError::Wgpu(format!("{:?}", e)))?;
+
+            // Drop the row padding on the way into the destination
+            if let Some(scratch_id) = scratch_id {
+                for row in 0..layout.rows as u64 {
+                    global
+                        .command_encoder_copy_buffer_to_buffer(
+                            encoder_id,
+                            scratch_id,
+                            row * layout.padded_bytes_per_row as u64,
+                            buffer_id,
+                            buffer_offset + row * layout.bytes_per_row as u64,
+                            Some(layout.bytes_per_row as u64),
+                        )
+                        .map_err(|e| BasaltError::Wgpu(format!("{:?}", e)))?;
+                }
+            }
+
+            let (command_buffer, error) =
+                global.command_encoder_finish(encoder_id, &wgt::CommandBufferDescriptor::default(), None);
+            if let Some(e) = error {
+                return Err(BasaltError::Wgpu(format!("{:?}", e)));
+            }
+
+            global
+                .queue_submit(self.queue_id, &[command_buffer])
+                .map_err(|(_, e)| self.submit_error("texture-to-buffer copy submit", &e))?;
+            Ok(())
+        };
+
+        let result = record();
+
+        // The submission keeps the scratch buffer alive until the copies complete
+        if let Some(scratch_id) = scratch_id {
+            global.buffer_drop(scratch_id);
+        }
+
+        result
     }
 
-    /// Clear a texture with color and/or depth values
+    /// Clear layers of a texture with color and/or depth values
+    ///
+    /// Each target (see `texture::layer_targets`) is cleared by its own render
+    /// pass; layers not listed are left untouched.
     pub fn clear_texture(
         &self,
         texture_id: id::TextureId,
+        targets: &[LayerTarget],
         clear_color: Option<wgt::Color>,
         clear_depth: Option<f32>,
     ) -> Result<()> {
+        let _serialized = self.begin_submission()?;
         // Create command encoder
         let encoder_desc = wgt::CommandEncoderDescriptor {
             label: Some(Cow::Borrowed("Clear Command Encoder")),
@@ -1656,82 +3367,7 @@ impl BasaltDevice {
             return Err(BasaltError::Wgpu(format!("{:?}", e)));
         }
 
-        // Create a texture view for the whole texture
-        // In wgpu-core 27, texture view descriptor uses ImageSubresourceRange
-        let view_desc = wgpu_core::resource::TextureViewDescriptor {
-            label: Some(Cow::Borrowed("Clear Texture View")),
-            format: None,
-            dimension: None,
-            usage: Some(wgt::TextureUsages::RENDER_ATTACHMENT),
-            range: wgt::ImageSubresourceRange {
-                aspect: wgt::TextureAspect::All,
-                base_mip_level: 0,
-                mip_level_count: None,
-                base_array_layer: 0,
-                array_layer_count: None,
-            },
-        };
-
-        let (view_id, error) = self.context.inner().texture_create_view(
-            texture_id,
-            &view_desc,
-            None,
-        );
-
-        if let Some(e) = error {
-            return Err(BasaltError::Wgpu(format!("{:?}", e)));
-        }
-
-        // Create a render pass that clears the texture
-        let mut color_attachments = Vec::new();
-        if clear_color.is_some() {
-            color_attachments.push(Some(wgpu_core::command::RenderPassColorAttachment {
-                view: view_id,
-                resolve_target: None,
-                load_op: wgpu_core::command::LoadOp::Clear(clear_color.unwrap()),
-                store_op: wgpu_core::command::StoreOp::Store,
-                depth_slice: None,
-            }));
-        }
-
-        let depth_stencil_attachment = clear_depth.map(|depth| {
-            wgpu_core::command::RenderPassDepthStencilAttachment {
-                view: view_id,
-                depth: wgpu_core::command::PassChannel {
-                    load_op: Some(wgpu_core::command::LoadOp::Clear(Some(depth))),
-                    store_op: Some(wgpu_core::command::StoreOp::Store),
-                    read_only: false,
-                },
-                stencil: wgpu_core::command::PassChannel {
-                    load_op: Some(wgpu_core::command::LoadOp::Clear(Some(0))),
-                    store_op: Some(wgpu_core::command::StoreOp::Store),
-                    read_only: false,
-                },
-            }
-        });
-
-        let pass_desc = wgpu_core::command::RenderPassDescriptor {
-            label: Some(Cow::Borrowed("Clear Render Pass")),
-            color_attachments: Cow::Borrowed(&color_attachments),
-            depth_stencil_attachment: depth_stencil_attachment.as_ref(),
-            timestamp_writes: None,
-            occlusion_query_set: None,
-            multiview_mask: None,  // No multiview (wgpu 28.0+)
-        };
-
-        // Begin and immediately end the render pass (clears happen on load)
-        let (mut render_pass, error) = self.context.inner().command_encoder_begin_render_pass(
-            encoder_id,
-            &pass_desc,
-        );
-
-        if let Some(e) = error {
-            return Err(BasaltError::Wgpu(format!("{:?}", e)));
-        }
-
-        if let Err(e) = self.context.inner().render_pass_end(&mut render_pass) {
-            return Err(BasaltError::Wgpu(format!("{:?}", e)));
-        }
+        encode_layer_clears(&self.context, encoder_id, texture_id, targets, clear_color, clear_depth)?;
 
         // Finish and submit
         let (command_buffer, error) = self.context.inner().command_encoder_finish(
@@ -1747,17 +3383,93 @@ impl BasaltDevice {
         self.context
             .inner()
             .queue_submit(self.queue_id, &[command_buffer])
-            .map_err(|e| BasaltError::Wgpu(format!("{:?}", e)))?;
+            .map_err(|(_, e)| self.submit_error("texture clear submit", &e))?;
 
         Ok(())
     }
 
-    /// Copy texture to texture
+    /// Create a set of `count` occlusion queries for use in render passes
+    pub fn create_occlusion_query_set(&self, count: u32) -> Result<OcclusionQuerySet> {
+        OcclusionQuerySet::new(&self.context, self.device_id, count)
+    }
+
+    /// Read back the sample counts of `count` queries starting at `first`
+    ///
+    /// Blocks until the GPU has finished the passes that wrote the queries.
+    pub fn resolve_occlusion_results(
+        &self,
+        queries: &OcclusionQuerySet,
+        first: u32,
+        count: u32,
+    ) -> Result<Vec<u64>> {
+        self.submit_frame()?;
+        let range = queries.validate_range(first, count)?;
+        queries
+            .resolve(&self.context, self.device_id, self.queue_id, range)
+            .inspect_err(|e| {
+                if e.is_device_lost() {
+                    self.mark_lost();
+                }
+            })
+    }
+
+    /// Create a set of `count` pipeline statistics queries
+    ///
+    /// Fails when the device lacks `PIPELINE_STATISTICS_QUERY`.
+    pub fn create_pipeline_statistics_query_set(&self, count: u32) -> Result<PipelineStatisticsQuerySet> {
+        PipelineStatisticsQuerySet::new(&self.context, self.device_id, count)
+    }
+
+    /// Read back the counters of `count` pipeline statistics queries starting at `first`
+    ///
+    /// Returns `STATISTICS_PER_QUERY` values per query. Blocks until the GPU
+    /// has finished the passes that wrote the queries.
+    pub fn resolve_pipeline_statistics(
+        &self,
+        queries: &PipelineStatisticsQuerySet,
+        first: u32,
+        count: u32,
+    ) -> Result<Vec<u64>> {
+        self.submit_frame()?;
+        let range = queries.validate_range(first, count)?;
+        queries
+            .resolve(&self.context, self.device_id, self.queue_id, range)
+            .inspect_err(|e| {
+                if e.is_device_lost() {
+                    self.mark_lost();
+                }
+            })
+    }
+
+    /// Resolve `count` timestamp queries starting at `first` and read them back in nanoseconds
+    pub fn resolve_timestamp_queries(
+        &self,
+        queries: &TimestampQuerySet,
+        first: u32,
+        count: u32,
+    ) -> Result<Vec<u64>> {
+        self.submit_frame()?;
+        queries
+            .resolve_and_read(&self.context, self.device_id, self.queue_id, first..first.saturating_add(count))
+            .inspect_err(|e| {
+                if e.is_device_lost() {
+                    self.mark_lost();
+                }
+            })
+    }
+
+    /// Copy a region of `src_mip_level` in one texture into `dst_mip_level` of another
+    ///
+    /// Each mip level is checked against its own texture, and the region must fit
+    /// both the source and destination mip, so base-level images can be copied
+    /// into the lower levels of a custom mip chain.
+    #[allow(clippy::too_many_arguments)]
     pub fn copy_texture_to_texture(
         &self,
-        src_texture: id::TextureId,
-        dst_texture: id::TextureId,
-        mip_level: u32,
+        src_texture: &TextureInfo,
+        dst_texture: &TextureInfo,
+        src_mip_level: u32,
+        dst_mip_level: u32,
         dest_x: u32,
         dest_y: u32,
         source_x: u32,
@@ -1765,6 +3477,10 @@ impl BasaltDevice {
         width: u32,
         height: u32,
     ) -> Result<()> {
+        validate_mip_region(src_texture, "src_mip_level", src_mip_level, source_x, source_y, width, height)?;
+        validate_mip_region(dst_texture, "dst_mip_level", dst_mip_level, dest_x, dest_y, width, height)?;
+
+        let _serialized = self.begin_submission()?;
         // Create command encoder
         let encoder_desc = wgt::CommandEncoderDescriptor {
             label: Some(Cow::Borrowed("Texture Copy Command Encoder")),
@@ -1780,8 +3496,8 @@ impl BasaltDevice {
         }
 
         let src_copy = wgt::TexelCopyTextureInfo {
-            texture: src_texture,
-            mip_level,
+            texture: src_texture.id,
+            mip_level: src_mip_level,
             origin: wgt::Origin3d {
                 x: source_x,
                 y: source_y,
@@ -1791,8 +3507,8 @@ impl BasaltDevice {
         };
 
         let dst_copy = wgt::TexelCopyTextureInfo {
-            texture: dst_texture,
-            mip_level,
+            texture: dst_texture.id,
+            mip_level: dst_mip_level,
             origin: wgt::Origin3d {
                 x: dest_x,
                 y: dest_y,
@@ -1831,11 +3547,131 @@ impl BasaltDevice {
         self.context
             .inner()
             .queue_submit(self.queue_id, &[command_buffer])
-            .map_err(|e| BasaltError::Wgpu(format!("{:?}", e)))?;
+            .map_err(|(_, e)| self.submit_error("texture copy submit", &e))?;
 
         Ok(())
     }
 
+    /// Move sprites inside an atlas texture after `TextureAtlas::repack`
+    ///
+    /// Old and new rectangles may overlap, and WebGPU forbids overlapping copies
+    /// within one texture, so the atlas is snapshotted into a scratch texture and
+    /// each sprite is copied back from the snapshot at its new position. The
+    /// atlas texture needs COPY_SRC | COPY_DST usage.
+    pub fn apply_atlas_relocations(
+        &self,
+        atlas_texture: id::TextureId,
+        format: wgt::TextureFormat,
+        width: u32,
+        height: u32,
+        relocations: &[crate::atlas::AtlasRelocation],
+    ) -> Result<()> {
+        let _serialized = self.begin_submission()?;
+        if relocations.is_empty() {
+            return Ok(());
+        }
+
+        let extent = wgt::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let scratch_desc = wgt::TextureDescriptor {
+            label: Some(Cow::Borrowed("Bassalt Atlas Repack Scratch")),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgt::TextureDimension::D2,
+            format,
+            usage: wgt::TextureUsages::COPY_SRC | wgt::TextureUsages::COPY_DST,
+            view_formats: vec![],
+        };
+
+        let (scratch_id, error) = self
+            .context
+            .inner()
+            .device_create_texture(self.device_id, &scratch_desc, None);
+
+        if let Some(e) = error {
+            return Err(BasaltError::Wgpu(format!("{:?}", e)));
+        }
+
+        let encoder_desc = wgt::CommandEncoderDescriptor {
+            label: Some(Cow::Borrowed("Atlas Repack Command Encoder")),
+        };
+
+        let (encoder_id, error) = self
+            .context
+            .inner()
+            .device_create_command_encoder(self.device_id, &encoder_desc, None);
+
+        if let Some(e) = error {
+            self.context.inner().texture_drop(scratch_id);
+            return Err(BasaltError::Wgpu(format!("{:?}", e)));
+        }
+
+        let copy_info = |texture, x, y| wgt::TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: wgt::Origin3d { x, y, z: 0 },
+            aspect: wgt::TextureAspect::All,
+        };
+
+        let record = || -> Result<()> {
+            let global = self.context.inner();
+
+            global
+                .command_encoder_copy_texture_to_texture(
+                    encoder_id,
+                    &copy_info(atlas_texture, 0, 0),
+                    &copy_info(scratch_id, 0, 0),
+                    &extent,
+                )
+                .map_err(|e| BasaltError::Wgpu(format!("{:?}", e)))?;
+
+            for r in relocations {
+                global
+                    .command_encoder_copy_texture_to_texture(
+                        encoder_id,
+                        &copy_info(scratch_id, r.old.x, r.old.y),
+                        &copy_info(atlas_texture, r.new.x, r.new.y),
+                        &wgt::Extent3d {
+                            width: r.new.width,
+                            height: r.new.height,
+                            depth_or_array_layers: 1,
+                        },
+                    )
+                    .map_err(|e| BasaltError::Wgpu(format!("{:?}", e)))?;
+            }
+
+            let (command_buffer, error) = global.command_encoder_finish(
+                encoder_id,
+                &wgt::CommandBufferDescriptor::default(),
+                None,
+            );
+
+            if let Some(e) = error {
+                return Err(BasaltError::Wgpu(format!("{:?}", e)));
+            }
+
+            global
+                .queue_submit(self.queue_id, &[command_buffer])
+                .map_err(|(_, e)| self.submit_error("atlas relocation submit", &e))?;
+
+            Ok(())
+        };
+
+        let result = record();
+
+        // The submission keeps the scratch texture alive until the copies complete
+        self.context.inner().texture_drop(scratch_id);
+
+        log::debug!("Applied {} atlas relocations to texture {:?}", relocations.len(), atlas_texture);
+
+        result
+    }
+
     // Helper functions for type mapping
 
     fn map_buffer_usage(&self, usage: u32) -> wgt::BufferUsages {
@@ -2022,7 +3858,7 @@ impl BasaltDevice {
             // Default to 4 bytes
             _ => 4,
         };
-        let estimated_bytes = (width as u64 * height as u64 * bytes_per_pixel) as u64;
+        let estimated_bytes = width as u64 * height as u64 * bytes_per_pixel;
 
         // Format size nicely (KB, MB)
         let size_str = if estimated_bytes >= 1024 * 1024 {
@@ -2062,6 +3898,26 @@ impl BasaltDevice {
         const DEPTH24: u32 = 7;
         const DEPTH32F: u32 = 8;
         const DEPTH24_STENCIL8: u32 = 9;
+        const DEPTH16: u32 = 10;
+        const RGBA8_SRGB: u32 = 11;
+        const BGRA8_SRGB: u32 = 12;
+        // Block-compressed formats (need the matching TEXTURE_COMPRESSION_* feature)
+        const BC1_RGBA: u32 = 13;
+        const BC1_RGBA_SRGB: u32 = 14;
+        const BC3_RGBA: u32 = 15;
+        const BC3_RGBA_SRGB: u32 = 16;
+        const BC4_R: u32 = 17;
+        const BC5_RG: u32 = 18;
+        const BC7_RGBA: u32 = 19;
+        const BC7_RGBA_SRGB: u32 = 20;
+        const ETC2_RGB8: u32 = 21;
+        const ETC2_RGB8_SRGB: u32 = 22;
+        const ETC2_RGBA8: u32 = 23;
+        const ETC2_RGBA8_SRGB: u32 = 24;
+        const ASTC_4X4: u32 = 25;
+        const ASTC_4X4_SRGB: u32 = 26;
+        const ASTC_8X8: u32 = 27;
+        const ASTC_8X8_SRGB: u32 = 28;
 
         // NOTE: RGBA/BGRA channel swapping for correct texture rendering
         // =============================================================
@@ -2090,11 +3946,31 @@ impl BasaltDevice {
             DEPTH24 => wgt::TextureFormat::Depth24Plus,
             DEPTH32F => wgt::TextureFormat::Depth32Float,
             DEPTH24_STENCIL8 => wgt::TextureFormat::Depth24PlusStencil8,
+            DEPTH16 => wgt::TextureFormat::Depth16Unorm,
+            RGBA8_SRGB => wgt::TextureFormat::Bgra8UnormSrgb, // Same channel swap as RGBA8
+            BGRA8_SRGB => wgt::TextureFormat::Rgba8UnormSrgb,
+            // Compressed data is uploaded as-is, so no channel swap here
+            BC1_RGBA => wgt::TextureFormat::Bc1RgbaUnorm,
+            BC1_RGBA_SRGB => wgt::TextureFormat::Bc1RgbaUnormSrgb,
+            BC3_RGBA => wgt::TextureFormat::Bc3RgbaUnorm,
+            BC3_RGBA_SRGB => wgt::TextureFormat::Bc3RgbaUnormSrgb,
+            BC4_R => wgt::TextureFormat::Bc4RUnorm,
+            BC5_RG => wgt::TextureFormat::Bc5RgUnorm,
+            BC7_RGBA => wgt::TextureFormat::Bc7RgbaUnorm,
+            BC7_RGBA_SRGB => wgt::TextureFormat::Bc7RgbaUnormSrgb,
+            ETC2_RGB8 => wgt::TextureFormat::Etc2Rgb8Unorm,
+            ETC2_RGB8_SRGB => wgt::TextureFormat::Etc2Rgb8UnormSrgb,
+            ETC2_RGBA8 => wgt::TextureFormat::Etc2Rgba8Unorm,
+            ETC2_RGBA8_SRGB => wgt::TextureFormat::Etc2Rgba8UnormSrgb,
+            ASTC_4X4 => wgt::TextureFormat::Astc { block: wgt::AstcBlock::B4x4, channel: wgt::AstcChannel::Unorm },
+            ASTC_4X4_SRGB => wgt::TextureFormat::Astc { block: wgt::AstcBlock::B4x4, channel: wgt::AstcChannel::UnormSrgb },
+            ASTC_8X8 => wgt::TextureFormat::Astc { block: wgt::AstcBlock::B8x8, channel: wgt::AstcChannel::Unorm },
+            ASTC_8X8_SRGB => wgt::TextureFormat::Astc { block: wgt::AstcBlock::B8x8, channel: wgt::AstcChannel::UnormSrgb },
             _ => return Err(BasaltError::invalid_parameter("format", format!("Unknown texture format: {}", format))),
         })
     }
 
-    fn map_address_mode(&self, mode: u32) -> Result<wgt::AddressMode> {
+    pub(crate) fn map_address_mode(&self, mode: u32) -> Result<wgt::AddressMode> {
         Ok(match mode {
             0 => wgt::AddressMode::Repeat,
             1 => wgt::AddressMode::MirrorRepeat,
@@ -2167,6 +4043,21 @@ impl BasaltDevice {
     }
 }
 
+impl Drop for BasaltDevice {
+    fn drop(&mut self) {
+        // Cached helper pipelines hold layouts, bind groups and uniform buffers of their own
+        for (_, pipeline) in self.line_pipelines.get_mut().drain() {
+            pipeline.destroy(&self.context);
+        }
+        for (_, pipeline) in self.partial_resolve_pipelines.get_mut().drain() {
+            pipeline.destroy(&self.context);
+        }
+        for bundle_id in self.pending_bundle_drops.get_mut().drain(..) {
+            self.context.inner().render_bundle_drop(bundle_id);
+        }
+    }
+}
+
 /// Build view_formats for surface configuration (wgpu 27.0 best practice)
 ///
 /// wgpu 27.0 recommends including both the base format and its sRGB variant
@@ -2278,11 +4169,220 @@ fn align_to(value: u64, alignment: u64) -> u64 {
     (value + alignment - 1) & !(alignment - 1)
 }
 
+/// Optional features Java can request, indexed by bit (`BassaltBackend.FEATURE_*`)
+const REQUESTABLE_FEATURES: [wgt::Features; 9] = [
+    wgt::Features::IMMEDIATES,
+    wgt::Features::DEPTH_CLIP_CONTROL,
+    wgt::Features::TIMESTAMP_QUERY,
+    wgt::Features::TEXTURE_COMPRESSION_BC,
+    wgt::Features::POLYGON_MODE_LINE,
+    wgt::Features::DUAL_SOURCE_BLENDING,
+    wgt::Features::FLOAT32_FILTERABLE,
+    wgt::Features::SHADER_F16,
+    wgt::Features::INDIRECT_FIRST_INSTANCE,
+];
+
+/// Limits Java can raise, indexed like `BassaltBackend.LIMIT_*`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceLimit {
+    MaxImmediateSize,
+    MaxBindGroups,
+    MaxTextureDimension2d,
+    MaxStorageBufferBindingSize,
+    MaxBufferSize,
+}
+
+impl DeviceLimit {
+    pub fn from_id(id: u32) -> Result<Self> {
+        Ok(match id {
+            0 => Self::MaxImmediateSize,
+            1 => Self::MaxBindGroups,
+            2 => Self::MaxTextureDimension2d,
+            3 => Self::MaxStorageBufferBindingSize,
+            4 => Self::MaxBufferSize,
+            _ => return Err(BasaltError::invalid_parameter("limit", format!("unknown device limit id {}", id))),
+        })
+    }
+
+    fn apply(self, limits: &mut wgt::Limits, value: u64) {
+        let value32 = value.min(u32::MAX as u64) as u32;
+        match self {
+            Self::MaxImmediateSize => limits.max_immediate_size = value32,
+            Self::MaxBindGroups => limits.max_bind_groups = value32,
+            Self::MaxTextureDimension2d => limits.max_texture_dimension_2d = value32,
+            Self::MaxStorageBufferBindingSize => limits.max_storage_buffer_binding_size = value32,
+            Self::MaxBufferSize => limits.max_buffer_size = value,
+        }
+    }
+}
+
+/// Texture to create with `BasaltDevice::create_texture_from_request`
+///
+/// `format` and `usage` use the encodings of `create_texture`. `depth` is the
+/// layer count of a 2D texture or the depth of a 3D one.
+#[derive(Debug, Clone)]
+pub struct TextureRequest {
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    pub mip_levels: u32,
+    pub format: u32,
+    pub usage: u32,
+    pub sample_count: u32,
+    pub dimension: wgt::TextureDimension,
+    /// Formats views may reinterpret the texture as, from
+    /// `texture::compatible_view_formats`; `None` allows the sRGB/linear
+    /// counterpart of the format, if it has one
+    pub view_formats: Option<Vec<wgt::TextureFormat>>,
+    /// Replaces the usage-derived debug label
+    pub label: Option<String>,
+}
+
+impl TextureRequest {
+    /// A single-sampled 2D texture with the default view formats and label
+    pub fn new(width: u32, height: u32, depth: u32, mip_levels: u32, format: u32, usage: u32) -> Self {
+        Self {
+            width,
+            height,
+            depth,
+            mip_levels,
+            format,
+            usage,
+            sample_count: 1,
+            dimension: wgt::TextureDimension::D2,
+            view_formats: None,
+            label: None,
+        }
+    }
+}
+
+/// Sampler to create with `BasaltDevice::create_sampler`
+///
+/// Address modes and filters use the encodings of `map_address_mode`,
+/// `map_filter_mode` and `map_mipmap_filter`.
+#[derive(Debug, Clone)]
+pub struct SamplerRequest {
+    pub address_mode_u: u32,
+    pub address_mode_v: u32,
+    pub address_mode_w: u32,
+    pub min_filter: u32,
+    pub mag_filter: u32,
+    pub mipmap_filter: u32,
+    pub lod_min_clamp: f32,
+    pub lod_max_clamp: f32,
+    pub max_anisotropy: u32,
+    /// Encoded as for `map_compare_function`; negative for a regular
+    /// (non-comparison) sampler
+    pub compare_function: i32,
+    pub label: Option<String>,
+}
+
+/// Features, limits and surface preferences requested on top of Bassalt's defaults
+///
+/// Requested features are required: device creation fails if the adapter
+/// lacks one, rather than rendering without it. `prefer_hdr` is only a
+/// preference and falls back to SDR, see `BasaltDevice::set_hdr_enabled`.
+#[derive(Debug, Clone, Default)]
+pub struct DeviceRequest {
+    pub features: wgt::Features,
+    pub limits: Vec<(DeviceLimit, u64)>,
+    pub prefer_hdr: bool,
+}
+
+impl DeviceRequest {
+    /// Build a request from a `FEATURE_*` bitmask and (`LIMIT_*`, value) pairs
+    pub fn from_flags(feature_flags: u64, limit_pairs: &[i64]) -> Result<Self> {
+        let mut features = wgt::Features::empty();
+        for (bit, feature) in REQUESTABLE_FEATURES.iter().enumerate() {
+            if feature_flags & (1 << bit) != 0 {
+                features |= *feature;
+            }
+        }
+        let unknown = feature_flags & !((1u64 << REQUESTABLE_FEATURES.len()) - 1);
+        if unknown != 0 {
+            return Err(BasaltError::invalid_parameter("features", format!("unknown feature bits {:#x}", unknown)));
+        }
+
+        if !limit_pairs.len().is_multiple_of(2) {
+            return Err(BasaltError::invalid_parameter("limits", "limit overrides must be (id, value) pairs"));
+        }
+        let limits = limit_pairs
+            .chunks_exact(2)
+            .map(|pair| {
+                let value = u64::try_from(pair[1])
+                    .map_err(|_| BasaltError::invalid_parameter("limits", format!("negative limit value {}", pair[1])))?;
+                Ok((DeviceLimit::from_id(pair[0] as u32)?, value))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { features, limits, prefer_hdr: false })
+    }
+
+    /// Merge this request into Bassalt's defaults, checking it against the adapter
+    pub fn apply(
+        &self,
+        features: wgt::Features,
+        limits: wgt::Limits,
+        adapter_features: wgt::Features,
+        adapter_limits: &wgt::Limits,
+    ) -> Result<(wgt::Features, wgt::Limits)> {
+        let missing = self.features - adapter_features;
+        if !missing.is_empty() {
+            return Err(BasaltError::device_creation(format!("Adapter does not support requested features {:?}", missing)));
+        }
+
+        let mut limits = limits;
+        for (limit, value) in &self.limits {
+            limit.apply(&mut limits, *value);
+        }
+        // Immediates need a range to be useful; default it like the automatic path
+        if self.features.contains(wgt::Features::IMMEDIATES) && limits.max_immediate_size == 0 {
+            limits.max_immediate_size = adapter_limits.max_immediate_size.min(MAX_IMMEDIATE_SIZE);
+        }
+
+        let mut exceeded = Vec::new();
+        limits.check_limits_with_fail_fn(adapter_limits, false, |name, requested, allowed| {
+            exceeded.push(format!("{} {} (adapter allows {})", name, requested, allowed));
+        });
+        if !exceeded.is_empty() {
+            return Err(BasaltError::device_creation(format!("Requested limits exceed the adapter: {}", exceeded.join(", "))));
+        }
+
+        Ok((features | self.features, limits))
+    }
+}
+
+/// Usage flags for a buffer created with the `frequently_updated` hint
+pub fn buffer_usage_with_hint(usage: wgt::BufferUsages, frequently_updated: bool) -> wgt::BufferUsages {
+    if frequently_updated {
+        usage | wgt::BufferUsages::COPY_DST
+    } else {
+        usage
+    }
+}
+
 /// Helper function to create a device from a GLFW window handle
 pub fn create_device_from_window(
     context: Arc<BasaltContext>,
     window_ptr: u64,
     display_ptr: u64,
+    width: u32,
+    height: u32,
+) -> Result<BasaltDevice> {
+    create_device_from_window_with_adapter(context, None, &DeviceRequest::default(), window_ptr, display_ptr, width, height)
+}
+
+/// Create a device from a GLFW window handle on a chosen adapter
+///
+/// `adapter_index` indexes `BasaltContext::enumerate_adapters`; None picks the
+/// high-performance adapter that can present to the window. `request` adds
+/// features and limits beyond the ones Bassalt enables on its own.
+pub fn create_device_from_window_with_adapter(
+    context: Arc<BasaltContext>,
+    adapter_index: Option<usize>,
+    request: &DeviceRequest,
+    window_ptr: u64,
+    display_ptr: u64,
     _width: u32,
     _height: u32,
 ) -> Result<BasaltDevice> {
@@ -2435,17 +4535,37 @@ pub fn create_device_from_window(
         )
     }.map_err(|e| BasaltError::wgpu_context("surface creation", format!("{:?}", e)))?;
 
-    // Request adapter compatible with the surface
-    let adapter_opts = wgpu_core::instance::RequestAdapterOptions {
-        power_preference: wgt::PowerPreference::HighPerformance,
-        compatible_surface: Some(surface_id),
-        force_fallback_adapter: false,
-    };
+    let adapter_id = match adapter_index {
+        Some(index) => {
+            let adapter_id = context
+                .adapter_at(index)
+                .ok_or_else(|| BasaltError::device_creation(format!("No adapter at index {}", index)))?;
+            let info = context.inner().adapter_get_info(adapter_id);
+            if context.inner().surface_get_capabilities(surface_id, adapter_id).is_err() {
+                context.inner().adapter_drop(adapter_id);
+                return Err(BasaltError::device_creation(format!(
+                    "Adapter {} ({}, {:?}) can't present to this window",
+                    index, info.name, info.backend
+                )));
+            }
+            log::info!("Using adapter {}: {} ({:?}, {:?})", index, info.name, info.backend, info.device_type);
+            adapter_id
+        }
+        None => {
+            // Request adapter compatible with the surface
+            let adapter_opts = wgpu_core::instance::RequestAdapterOptions {
+                power_preference: wgt::PowerPreference::HighPerformance,
+                compatible_surface: Some(surface_id),
+                force_fallback_adapter: false,
+            };
 
-    let adapter_id = context
-        .inner()
-        .request_adapter(&adapter_opts, wgt::Backends::all(), None)
-        .map_err(|e| BasaltError::device_creation(format!("Failed to find adapter: {:?}", e)))?;
+            context
+                .inner()
+                .request_adapter(&adapter_opts, context.backends(), None)
+                .map_err(|e| BasaltError::device_creation(format!("Failed to find adapter: {:?}", e)))?
+        }
+    };
+    context.set_selected_adapter(adapter_id);
 
     // Query adapter for available features to enable advanced capabilities
     let adapter_features = context
@@ -2481,7 +4601,17 @@ pub fn create_device_from_window(
 
     // Build required features with advanced capabilities if available
     // Start with base features required by Bassalt
-    let mut required_features = wgt::Features::DEPTH_CLIP_CONTROL | wgt::Features::IMMEDIATES;
+    let mut required_features = wgt::Features::DEPTH_CLIP_CONTROL;
+
+    // Immediates (push constants) are missing on GL and some mobile drivers; without them
+    // pipelines fall back to a dynamic uniform buffer (see `ImmediatesFallback`)
+    let max_immediate_size = if adapter_features.contains(wgt::Features::IMMEDIATES) {
+        required_features |= wgt::Features::IMMEDIATES;
+        context.inner().adapter_limits(adapter_id).max_immediate_size.min(MAX_IMMEDIATE_SIZE)
+    } else {
+        log::info!("Adapter does not support IMMEDIATES - push constants use the uniform buffer fallback");
+        0
+    };
 
     // Enable timestamp queries if available (for GPU profiling)
     if adapter_features.contains(wgt::Features::TIMESTAMP_QUERY) {
@@ -2523,6 +4653,26 @@ pub fn create_device_from_window(
         required_features |= wgt::Features::SUBGROUP_BARRIER;
     }
 
+    // Pipeline statistics queries for per-pass rendering cost (see `pipeline_statistics`)
+    if adapter_features.contains(wgt::Features::PIPELINE_STATISTICS_QUERY) {
+        log::info!("Adapter supports PIPELINE_STATISTICS_QUERY - per-pass draw statistics available");
+        required_features |= wgt::Features::PIPELINE_STATISTICS_QUERY;
+    }
+
+    // Driver pipeline caches persisted between launches (see `create_pipeline_cache_from_file`)
+    if adapter_features.contains(wgt::Features::PIPELINE_CACHE) {
+        log::info!("Adapter supports PIPELINE_CACHE - compiled pipelines can be saved to disk");
+        required_features |= wgt::Features::PIPELINE_CACHE;
+    }
+
+    // Wireframe / point rasterization for debugging meshes (see `pipeline_registry::polygon_mode`)
+    if adapter_features.contains(wgt::Features::POLYGON_MODE_LINE) {
+        required_features |= wgt::Features::POLYGON_MODE_LINE;
+    }
+    if adapter_features.contains(wgt::Features::POLYGON_MODE_POINT) {
+        required_features |= wgt::Features::POLYGON_MODE_POINT;
+    }
+
     // Multisample array textures for advanced anti-aliasing
     if adapter_features.contains(wgt::Features::MULTISAMPLE_ARRAY) {
         log::info!("Adapter supports MULTISAMPLE_ARRAY - multisampled array textures available");
@@ -2530,17 +4680,30 @@ pub fn create_device_from_window(
     }
 
 
+    let required_limits = wgt::Limits {
+        // Immediates (push constants) require max_immediate_size to be set
+        max_immediate_size,
+        max_bind_groups: 8,
+        ..wgt::Limits::default()
+    };
+
+    // Features and limits Java asked for explicitly
+    let (required_features, required_limits) = request.apply(
+        required_features,
+        required_limits,
+        adapter_features,
+        &context.inner().adapter_limits(adapter_id),
+    )?;
+    if !request.features.is_empty() || !request.limits.is_empty() {
+        log::info!("Requested device features {:?}, limit overrides {:?}", request.features, request.limits);
+    }
+
     // Request device with required features (matching wgpu-mc)
     // wgpu 27.0 requires explicit memory_hints and experimental_features
     let device_desc = wgt::DeviceDescriptor {
         label: Some(Cow::Borrowed("Bassalt Device")),
         required_features,
-        required_limits: wgt::Limits {
-            // Immediates (push constants) require max_immediate_size to be set
-            max_immediate_size: 128,
-            max_bind_groups: 8,
-            ..wgt::Limits::default()
-        },
+        required_limits,
         // wgpu 27.0: Explicit memory hints for better allocation strategy
         memory_hints: wgt::MemoryHints::Performance,
         // wgpu 27.0: Experimental features controlled by BASALT_EXPERIMENTAL env var
@@ -2562,44 +4725,23 @@ pub fn create_device_from_window(
         .inner()
         .surface_get_capabilities(surface_id, adapter_id)
         .map_err(|e| BasaltError::surface(format!("Failed to get surface capabilities: {:?}", e)))?;
+    bassalt_surface.set_capabilities(SurfaceCapabilities::from(&surface_caps));
 
-    // Prefer Bgra8Unorm (standard for most displays, what wgpu-mc uses)
-    // Fall back to Bgra8UnormSrgb, then Rgba variants, then first available
-    let surface_format = surface_caps
-        .formats
+    let surface_format = choose_surface_format(&surface_caps.formats, false);
+    log::info!("Selected surface format: {:?} (available: {:?})", surface_format, surface_caps.formats);
+
+    // Select present mode - prefer AutoNoVsync for lower latency (like wgpu-mc)
+    let present_mode = surface_caps
+        .present_modes
         .iter()
         .copied()
-        .find(|f| matches!(f, wgt::TextureFormat::Bgra8Unorm))
+        .find(|m| matches!(m, wgt::PresentMode::Mailbox))
         .or_else(|| {
             surface_caps
-                .formats
+                .present_modes
                 .iter()
                 .copied()
-                .find(|f| matches!(f, wgt::TextureFormat::Bgra8UnormSrgb))
-        })
-        .or_else(|| {
-            surface_caps
-                .formats
-                .iter()
-                .copied()
-                .find(|f| matches!(f, wgt::TextureFormat::Rgba8Unorm | wgt::TextureFormat::Rgba8UnormSrgb))
-        })
-        .unwrap_or(surface_caps.formats[0]);
-
-    log::info!("Selected surface format: {:?} (available: {:?})", surface_format, surface_caps.formats);
-
-    // Select present mode - prefer AutoNoVsync for lower latency (like wgpu-mc)
-    let present_mode = surface_caps
-        .present_modes
-        .iter()
-        .copied()
-        .find(|m| matches!(m, wgt::PresentMode::Mailbox))
-        .or_else(|| {
-            surface_caps
-                .present_modes
-                .iter()
-                .copied()
-                .find(|m| matches!(m, wgt::PresentMode::Immediate))
+                .find(|m| matches!(m, wgt::PresentMode::Immediate))
         })
         .unwrap_or(wgt::PresentMode::Fifo);
 
@@ -2609,15 +4751,28 @@ pub fn create_device_from_window(
     // This allows render passes to use either the base format or its sRGB variant
     let view_formats = build_view_formats(&surface_format, &surface_caps.formats);
 
+    // Prefer an opaque surface; otherwise the present blit forces alpha to 1.0
+    let alpha_mode = surface_caps
+        .alpha_modes
+        .iter()
+        .copied()
+        .find(|m| matches!(m, wgt::CompositeAlphaMode::Opaque))
+        .unwrap_or(surface_caps.alpha_modes[0]);
+
+    log::info!("Selected composite alpha mode: {:?} (available: {:?})", alpha_mode, surface_caps.alpha_modes);
+
+    // COPY_DST lets present copy a framebuffer that already matches instead of blitting it
+    let usage = wgt::TextureUsages::RENDER_ATTACHMENT | (surface_caps.usages & wgt::TextureUsages::COPY_DST);
+
     // Configure the surface
     let surface_config = wgt::SurfaceConfiguration {
-        usage: wgt::TextureUsages::RENDER_ATTACHMENT,
+        usage,
         format: surface_format,
         width: _width,
         height: _height,
         present_mode,
         desired_maximum_frame_latency: 2,
-        alpha_mode: wgt::CompositeAlphaMode::Auto,
+        alpha_mode,
         view_formats,
     };
 
@@ -2625,5 +4780,1632 @@ pub fn create_device_from_window(
 
     bassalt_surface.configure(device_id, surface_config)?;
 
-    BasaltDevice::new(context, device_id, adapter_id, queue_id, Some(bassalt_surface), _width, _height, surface_format)
+    let device = BasaltDevice::new(context, device_id, adapter_id, queue_id, Some(bassalt_surface), _width, _height, surface_format)?;
+    if request.prefer_hdr {
+        device.set_hdr_enabled(true)?;
+    }
+    Ok(device)
+}
+
+/// Surface format presented when HDR is on; wgpu pairs it with an extended-range linear color space
+pub const HDR_SURFACE_FORMAT: wgt::TextureFormat = wgt::TextureFormat::Rgba16Float;
+
+/// Pick the surface format from the ones the surface supports
+///
+/// With `hdr`, `HDR_SURFACE_FORMAT` wins when it's offered. Otherwise
+/// Bgra8Unorm is preferred (standard for most displays, what wgpu-mc uses),
+/// then Bgra8UnormSrgb, then the Rgba8 variants, then the first available.
+pub fn choose_surface_format(formats: &[wgt::TextureFormat], hdr: bool) -> wgt::TextureFormat {
+    let find = |matches: fn(&wgt::TextureFormat) -> bool| formats.iter().copied().find(matches);
+    (if hdr { find(|f| *f == HDR_SURFACE_FORMAT) } else { None })
+        .or_else(|| find(|f| matches!(f, wgt::TextureFormat::Bgra8Unorm)))
+        .or_else(|| find(|f| matches!(f, wgt::TextureFormat::Bgra8UnormSrgb)))
+        .or_else(|| find(|f| matches!(f, wgt::TextureFormat::Rgba8Unorm | wgt::TextureFormat::Rgba8UnormSrgb)))
+        .unwrap_or(formats[0])
+}
+
+/// Whether presenting with `alpha_mode` needs the blit to force alpha to 1.0
+///
+/// Only `Opaque` surfaces ignore the presented alpha; with any other mode the
+/// compositor blends the window by the framebuffer's alpha channel.
+pub fn forces_opaque_present(alpha_mode: wgt::CompositeAlphaMode) -> bool {
+    alpha_mode != wgt::CompositeAlphaMode::Opaque
+}
+
+/// Whether presenting `src` can copy it into a swapchain configured as `config`
+///
+/// A copy keeps the texels as they are, so it only stands in for the blit when
+/// format and size match exactly, the swapchain accepts copies, and alpha
+/// doesn't need forcing to 1.0.
+pub fn present_by_copy(
+    src: &TextureInfo,
+    config: &wgt::SurfaceConfiguration<Vec<wgt::TextureFormat>>,
+    force_opaque: bool,
+) -> bool {
+    !force_opaque
+        && config.usage.contains(wgt::TextureUsages::COPY_DST)
+        && src.format == config.format
+        && (src.width, src.height) == (config.width, config.height)
+        && src.dimension == wgt::TextureDimension::D2
+        && src.sample_count == 1
+}
+
+/// Check that the device allows `usage` for `format`
+///
+/// Mirrors wgpu-core: adapter-reported format features only apply when
+/// `TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES` is enabled, otherwise the WebGPU
+/// guaranteed features do. Failing here gives a clearer error than texture or
+/// pipeline creation failing inside wgpu-core.
+pub(crate) fn check_format_usages(
+    context: &BasaltContext,
+    adapter_id: id::AdapterId,
+    device_id: id::DeviceId,
+    format: wgt::TextureFormat,
+    usage: wgt::TextureUsages,
+) -> Result<()> {
+    let device_features = context.inner().device_features(device_id);
+    let features = if device_features.contains(wgt::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+        context.inner().adapter_get_texture_format_features(adapter_id, format)
+    } else {
+        format.guaranteed_format_features(device_features)
+    };
+    let missing = usage - features.allowed_usages;
+    if !missing.is_empty() {
+        return Err(BasaltError::invalid_parameter(
+            "format",
+            format!("{:?} does not support {:?} on this adapter", format, missing),
+        ));
+    }
+    Ok(())
+}
+
+/// Record one clearing render pass per target into `encoder_id`
+pub(crate) fn encode_layer_clears(
+    context: &BasaltContext,
+    encoder_id: id::CommandEncoderId,
+    texture_id: id::TextureId,
+    targets: &[LayerTarget],
+    clear_color: Option<wgt::Color>,
+    clear_depth: Option<f32>,
+) -> Result<()> {
+    for target in targets {
+        let view_desc = wgpu_core::resource::TextureViewDescriptor {
+            label: Some(Cow::Borrowed("Clear Texture View")),
+            format: None,
+            dimension: Some(target.view_dimension),
+            usage: Some(wgt::TextureUsages::RENDER_ATTACHMENT),
+            range: target.range(wgt::TextureAspect::All),
+        };
+
+        let (view_id, error) = context.inner().texture_create_view(texture_id, &view_desc, None);
+
+        if let Some(e) = error {
+            return Err(BasaltError::Wgpu(format!("{:?}", e)));
+        }
+
+        // Create a render pass that clears the layer
+        let mut color_attachments = Vec::new();
+        if let Some(color) = clear_color {
+            color_attachments.push(Some(wgpu_core::command::RenderPassColorAttachment {
+                view: view_id,
+                resolve_target: None,
+                load_op: wgpu_core::command::LoadOp::Clear(color),
+                store_op: wgpu_core::command::StoreOp::Store,
+                depth_slice: target.depth_slice,
+            }));
+        }
+
+        let depth_stencil_attachment = clear_depth.map(|depth| {
+            wgpu_core::command::RenderPassDepthStencilAttachment {
+                view: view_id,
+                depth: wgpu_core::command::PassChannel {
+                    load_op: Some(wgpu_core::command::LoadOp::Clear(Some(depth))),
+                    store_op: Some(wgpu_core::command::StoreOp::Store),
+                    read_only: false,
+                },
+                stencil: wgpu_core::command::PassChannel {
+                    load_op: Some(wgpu_core::command::LoadOp::Clear(Some(0))),
+                    store_op: Some(wgpu_core::command::StoreOp::Store),
+                    read_only: false,
+                },
+            }
+        });
+
+        let pass_desc = wgpu_core::command::RenderPassDescriptor {
+            label: Some(Cow::Borrowed("Clear Render Pass")),
+            color_attachments: Cow::Borrowed(&color_attachments),
+            depth_stencil_attachment: depth_stencil_attachment.as_ref(),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            multiview_mask: None,  // No multiview (wgpu 28.0+)
+        };
+
+        // Begin and immediately end the render pass (clears happen on load)
+        let (mut render_pass, error) = context.inner().command_encoder_begin_render_pass(
+            encoder_id,
+            &pass_desc,
+        );
+
+        if let Some(e) = error {
+            return Err(BasaltError::Wgpu(format!("{:?}", e)));
+        }
+
+        if let Err(e) = context.inner().render_pass_end(&mut render_pass) {
+            return Err(BasaltError::Wgpu(format!("{:?}", e)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Serialize every field of `limits`, so Java can configure itself from the real values
+fn limits_to_json(limits: &wgt::Limits) -> String {
+    macro_rules! fields {
+        ($($name:ident,)*) => {
+            [$(format!("\"{}\":{}", stringify!($name), limits.$name)),*]
+        };
+    }
+    let fields = fields!(
+        max_texture_dimension_1d,
+        max_texture_dimension_2d,
+        max_texture_dimension_3d,
+        max_texture_array_layers,
+        max_bind_groups,
+        max_bindings_per_bind_group,
+        max_dynamic_uniform_buffers_per_pipeline_layout,
+        max_dynamic_storage_buffers_per_pipeline_layout,
+        max_sampled_textures_per_shader_stage,
+        max_samplers_per_shader_stage,
+        max_storage_buffers_per_shader_stage,
+        max_storage_textures_per_shader_stage,
+        max_uniform_buffers_per_shader_stage,
+        max_binding_array_elements_per_shader_stage,
+        max_binding_array_sampler_elements_per_shader_stage,
+        max_uniform_buffer_binding_size,
+        max_storage_buffer_binding_size,
+        max_vertex_buffers,
+        max_buffer_size,
+        max_vertex_attributes,
+        max_vertex_buffer_array_stride,
+        min_uniform_buffer_offset_alignment,
+        min_storage_buffer_offset_alignment,
+        max_inter_stage_shader_components,
+        max_color_attachments,
+        max_color_attachment_bytes_per_sample,
+        max_compute_workgroup_storage_size,
+        max_compute_invocations_per_workgroup,
+        max_compute_workgroup_size_x,
+        max_compute_workgroup_size_y,
+        max_compute_workgroup_size_z,
+        max_compute_workgroups_per_dimension,
+        max_immediate_size,
+        max_non_sampler_bindings,
+        max_task_mesh_workgroup_total_count,
+        max_task_mesh_workgroups_per_dimension,
+        max_task_invocations_per_workgroup,
+        max_task_invocations_per_dimension,
+        max_mesh_invocations_per_workgroup,
+        max_mesh_invocations_per_dimension,
+        max_task_payload_size,
+        max_mesh_output_vertices,
+        max_mesh_output_primitives,
+        max_mesh_output_layers,
+        max_mesh_multiview_view_count,
+        max_blas_primitive_count,
+        max_blas_geometry_count,
+        max_tlas_instance_count,
+        max_acceleration_structures_per_shader_stage,
+        max_multiview_view_count,
+    );
+    format!("{{{}}}", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture::layer_targets;
+    use crate::render_pass::PipelineBinding;
+
+    #[test]
+    fn test_clear_single_array_layer() {
+        let context = BasaltContext::new_noop();
+        let (device_id, queue_id) = context.create_noop_device();
+
+        let texture_desc = wgpu_core::resource::TextureDescriptor {
+            label: Some(Cow::Borrowed("Layered Clear Target")),
+            size: wgt::Extent3d { width: 4, height: 4, depth_or_array_layers: 4 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgt::TextureDimension::D2,
+            format: wgt::TextureFormat::Rgba8Unorm,
+            usage: wgt::TextureUsages::RENDER_ATTACHMENT | wgt::TextureUsages::TEXTURE_BINDING,
+            view_formats: vec![],
+        };
+        let (texture_id, error) = context.inner().device_create_texture(device_id, &texture_desc, None);
+        assert!(error.is_none(), "{:?}", error);
+
+        // Only layer 2 is covered; the other layers are outside every view
+        let targets = layer_targets(wgt::TextureDimension::D2, 4, Some(2)).unwrap();
+        assert_eq!(targets.len(), 1);
+        let range = targets[0].range(wgt::TextureAspect::All);
+        assert_eq!((range.base_array_layer, range.array_layer_count), (2, Some(1)));
+        assert!(layer_targets(wgt::TextureDimension::D2, 4, Some(4)).is_err());
+
+        let (encoder_id, error) = context.inner().device_create_command_encoder(
+            device_id,
+            &wgt::CommandEncoderDescriptor { label: None },
+            None,
+        );
+        assert!(error.is_none());
+        encode_layer_clears(&context, encoder_id, texture_id, &targets, Some(wgt::Color::RED), None).unwrap();
+
+        let (command_buffer, error) = context.inner().command_encoder_finish(
+            encoder_id,
+            &wgt::CommandBufferDescriptor::default(),
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+        context.inner().queue_submit(queue_id, &[command_buffer]).unwrap();
+
+        // The full-texture view a layered clear used to create can't be an attachment
+        let whole = wgpu_core::resource::TextureViewDescriptor {
+            label: None,
+            format: None,
+            dimension: None,
+            usage: Some(wgt::TextureUsages::RENDER_ATTACHMENT),
+            range: wgt::ImageSubresourceRange::default(),
+        };
+        let (view_id, error) = context.inner().texture_create_view(texture_id, &whole, None);
+        assert!(error.is_none());
+        let (encoder_id, _) = context.inner().device_create_command_encoder(
+            device_id,
+            &wgt::CommandEncoderDescriptor { label: None },
+            None,
+        );
+        let attachments = [Some(wgpu_core::command::RenderPassColorAttachment {
+            view: view_id,
+            resolve_target: None,
+            load_op: wgpu_core::command::LoadOp::Clear(wgt::Color::RED),
+            store_op: wgpu_core::command::StoreOp::Store,
+            depth_slice: None,
+        })];
+        let pass_desc = wgpu_core::command::RenderPassDescriptor {
+            label: None,
+            color_attachments: Cow::Borrowed(&attachments),
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            multiview_mask: None,
+        };
+        let (mut pass, error) = context.inner().command_encoder_begin_render_pass(encoder_id, &pass_desc);
+        let ended = context.inner().render_pass_end(&mut pass);
+        let (_, finish_error) = context.inner().command_encoder_finish(
+            encoder_id,
+            &wgt::CommandBufferDescriptor::default(),
+            None,
+        );
+        assert!(error.is_some() || ended.is_err() || finish_error.is_some());
+    }
+
+    /// Full `BasaltDevice` on the noop backend
+    fn noop_device() -> BasaltDevice {
+        noop_device_with_features(wgt::Features::empty())
+    }
+
+    /// Range of a single mip and layer
+    fn subresource(base_mip: u32, base_layer: u32) -> ViewRangeRequest {
+        ViewRangeRequest { base_mip, mip_count: 1, base_layer, layer_count: 1, ..Default::default() }
+    }
+
+    fn noop_device_with_features(required_features: wgt::Features) -> BasaltDevice {
+        let context = BasaltContext::new_noop();
+        let adapter_id = context
+            .inner()
+            .request_adapter(&wgpu_core::instance::RequestAdapterOptions::default(), wgt::Backends::NOOP, None)
+            .expect("noop adapter");
+        let (device_id, queue_id) = context
+            .inner()
+            .adapter_request_device(
+                adapter_id,
+                &wgt::DeviceDescriptor { required_features, ..Default::default() },
+                None,
+                None,
+            )
+            .expect("noop device");
+        BasaltDevice::new(
+            Arc::new(context),
+            device_id,
+            adapter_id,
+            queue_id,
+            None,
+            4,
+            4,
+            wgt::TextureFormat::Rgba8Unorm,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_large_buffer_writes_use_staging_pool() {
+        let device = noop_device();
+        let buffer_id = device
+            .create_buffer(1024 * 1024, 0b110) // COPY_DST | VERTEX
+            .unwrap();
+
+        device.write_buffer_staged(buffer_id, 0, &[1; 1024]).unwrap();
+        assert_eq!(device.staging_stats().created, 0);
+
+        let data = vec![3; STAGED_UPLOAD_THRESHOLD as usize];
+        device.write_buffer_staged(buffer_id, 0, &data).unwrap();
+        device.poll_device(true).unwrap();
+        device.write_buffer_staged(buffer_id, 4096, &data).unwrap();
+        let stats = device.staging_stats();
+        assert_eq!((stats.created, stats.reused), (1, 1), "{:?}", stats);
+    }
+
+    #[test]
+    fn test_immediates_fall_back_without_push_constants() {
+        use crate::pipeline_registry::pipeline_immediate_size;
+        use crate::render_pass::RenderPassState;
+
+        assert_eq!(pipeline_immediate_size(0, 0).unwrap(), 0);
+        assert_eq!(pipeline_immediate_size(62, 128).unwrap(), 64);
+        assert!(pipeline_immediate_size(16, 0).is_err());
+
+        // The noop device is created without IMMEDIATES, so its limit is zero
+        let device = noop_device();
+        assert!(!device.supports_push_constants());
+        let fallback = device.immediates_fallback().unwrap().expect("fallback without push constants");
+
+        const VERTEX: &str = "
+            var<immediate> offset: vec4<f32>;
+            @vertex
+            fn main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+                return vec4<f32>(f32(i), 0.0, 0.0, 1.0) + offset;
+            }
+        ";
+        const FRAGMENT: &str = "@fragment fn main() -> @location(0) vec4<f32> { return vec4<f32>(1.0); }";
+        let fragment = crate::shader::parse_wgsl(FRAGMENT).unwrap();
+
+        // An immediate range can't be declared here...
+        let immediate_vertex = crate::shader::parse_wgsl(VERTEX).unwrap();
+        let limit = device.get_limits().max_immediate_size;
+        assert!(crate::create_layout_from_shaders(device.context(), device.id(), &immediate_vertex, &fragment, limit, None).is_err());
+
+        // ...so the block is lowered to the fallback uniform and the layout omits the range
+        let lowered = crate::shader::lower_immediates_to_uniform(VERTEX).unwrap();
+        assert!(crate::shader::lower_immediates_to_uniform(FRAGMENT).is_none());
+        let vertex = crate::shader::parse_wgsl(&lowered).unwrap();
+        let (bgl_id, pipeline_layout_id, ..) = crate::create_layout_from_shaders(
+            device.context(),
+            device.id(),
+            &vertex,
+            &fragment,
+            limit,
+            Some(fallback.bind_group_layout_id),
+        )
+        .unwrap();
+
+        let global = device.context().inner();
+        let create_module = |module| {
+            let (module_id, error) = global.device_create_shader_module(
+                device.id(),
+                &wgpu_core::pipeline::ShaderModuleDescriptor { label: None, runtime_checks: Default::default() },
+                wgpu_core::pipeline::ShaderModuleSource::Naga(Cow::Owned(module)),
+                None,
+            );
+            assert!(error.is_none(), "{:?}", error);
+            wgpu_core::pipeline::ProgrammableStageDescriptor {
+                module: module_id,
+                entry_point: Some(Cow::Borrowed("main")),
+                constants: Default::default(),
+                zero_initialize_workgroup_memory: true,
+            }
+        };
+        let (pipeline_id, error) = global.device_create_render_pipeline(
+            device.id(),
+            &wgpu_core::pipeline::RenderPipelineDescriptor {
+                label: None,
+                layout: Some(pipeline_layout_id),
+                vertex: wgpu_core::pipeline::VertexState { stage: create_module(vertex), buffers: Cow::Borrowed(&[]) },
+                primitive: wgt::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgt::MultisampleState::default(),
+                fragment: Some(wgpu_core::pipeline::FragmentState {
+                    stage: create_module(fragment),
+                    targets: Cow::Borrowed(&[Some(wgt::TextureFormat::Rgba8Unorm.into())]),
+                }),
+                multiview_mask: None,
+                cache: None,
+            },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+        let (bind_group_id, error) = global.device_create_bind_group(
+            device.id(),
+            &wgpu_core::binding_model::BindGroupDescriptor { label: None, layout: bgl_id, entries: Cow::Borrowed(&[]) },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+
+        // Per-draw immediates land in the fallback buffer and the pass validates
+        let target = device.create_texture(4, 4, 1, 1, 1, 0b10000).unwrap(); // BGRA8 -> Rgba8Unorm, RENDER_ATTACHMENT
+        let (view, _) = device.create_texture_view(target, wgt::TextureDimension::D2, 1, false).unwrap();
+        let mut state = RenderPassState::new(
+            device.context().clone(), device.id(), device.queue_id(), Some(view), Vec::new(), None, None, None,
+            1, None, false, 0, false, 1.0, 0, 4, 4,
+        )
+        .unwrap();
+        state.set_immediates_fallback(fallback);
+        state.record_set_pipeline(PipelineBinding::new(pipeline_id)).unwrap();
+        state.record_set_bind_group(0, Some(bind_group_id), Vec::new());
+        state.record_draw(3, 1, 0, 0);
+        state.record_set_immediates(wgt::ShaderStages::VERTEX_FRAGMENT, 0, vec![0; 16]).unwrap();
+        state.record_draw(3, 1, 0, 0);
+        assert!(state.record_set_immediates(wgt::ShaderStages::VERTEX_FRAGMENT, MAX_IMMEDIATE_SIZE - 4, vec![0; 8]).is_err());
+        state.finish_and_submit(device.context(), device.queue_id()).unwrap();
+
+        // Frame passes write their immediates to slots of their own, so none of them forces a submit
+        for expected in 1..=3 {
+            let mut pass = RenderPassState::new(
+                device.context().clone(), device.id(), device.queue_id(), Some(view), Vec::new(), None, None, None,
+                1, None, false, 0, false, 1.0, 0, 4, 4,
+            )
+            .unwrap();
+            pass.set_immediates_fallback(fallback);
+            pass.record_set_pipeline(PipelineBinding::new(pipeline_id)).unwrap();
+            pass.record_set_bind_group(0, Some(bind_group_id), Vec::new());
+            pass.record_set_immediates(wgt::ShaderStages::VERTEX_FRAGMENT, 0, vec![expected as u8; 16]).unwrap();
+            pass.record_draw(3, 1, 0, 0);
+            device.record_render_pass(&mut pass).unwrap();
+            assert_eq!(device.pending_frame_passes(), expected);
+        }
+        device.submit_frame().unwrap();
+    }
+
+    #[test]
+    fn test_dynamic_uniform_offsets_reach_the_pass() {
+        use crate::resource_handles::{BindingLayoutEntry, BindingLayoutType};
+
+        const VERTEX: &str = "
+            @group(0) @binding(0) var<uniform> offset: vec4<f32>;
+            @vertex
+            fn main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+                return vec4<f32>(f32(i), 0.0, 0.0, 1.0) + offset;
+            }
+        ";
+        const FRAGMENT: &str = "@fragment fn main() -> @location(0) vec4<f32> { return vec4<f32>(1.0); }";
+
+        let device = noop_device();
+        let global = device.context().inner();
+        let layout = [BindingLayoutEntry {
+            binding: 0,
+            ty: BindingLayoutType::UniformBuffer,
+            min_binding_size: Some(16),
+            has_dynamic_offset: true,
+            expected_dimension: None,
+            comparison: false,
+            variable_name: None,
+        }];
+        let bgl_id = crate::bind_group::create_explicit_layout(device.context(), device.id(), &layout).unwrap();
+        let (pipeline_layout_id, error) = global.device_create_pipeline_layout(
+            device.id(),
+            &wgpu_core::binding_model::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: Cow::Borrowed(&[bgl_id]),
+                immediate_size: 0,
+            },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+        let create_module = |source| {
+            let (module_id, error) = global.device_create_shader_module(
+                device.id(),
+                &wgpu_core::pipeline::ShaderModuleDescriptor { label: None, runtime_checks: Default::default() },
+                wgpu_core::pipeline::ShaderModuleSource::Naga(Cow::Owned(crate::shader::parse_wgsl(source).unwrap())),
+                None,
+            );
+            assert!(error.is_none(), "{:?}", error);
+            wgpu_core::pipeline::ProgrammableStageDescriptor {
+                module: module_id,
+                entry_point: Some(Cow::Borrowed("main")),
+                constants: Default::default(),
+                zero_initialize_workgroup_memory: true,
+            }
+        };
+        let (pipeline_id, error) = global.device_create_render_pipeline(
+            device.id(),
+            &wgpu_core::pipeline::RenderPipelineDescriptor {
+                label: None,
+                layout: Some(pipeline_layout_id),
+                vertex: wgpu_core::pipeline::VertexState { stage: create_module(VERTEX), buffers: Cow::Borrowed(&[]) },
+                primitive: wgt::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgt::MultisampleState::default(),
+                fragment: Some(wgpu_core::pipeline::FragmentState {
+                    stage: create_module(FRAGMENT),
+                    targets: Cow::Borrowed(&[Some(wgt::TextureFormat::Rgba8Unorm.into())]),
+                }),
+                multiview_mask: None,
+                cache: None,
+            },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+
+        // One 16-byte window that each draw moves across the 512-byte buffer
+        let alignment = device.get_limits().min_uniform_buffer_offset_alignment;
+        assert!(alignment + 16 <= 512);
+        let buffer_id = device.create_buffer(512, 16).unwrap(); // UNIFORM
+        let window = crate::bind_group::ExplicitResource::Buffer { buffer_id, offset: 0, size: Some(16) };
+        let whole = crate::bind_group::ExplicitResource::Buffer { buffer_id, offset: 0, size: None };
+        assert!(crate::bind_group::create_explicit_bind_group(device.context(), device.id(), bgl_id, &layout, &[(0, whole)]).is_err());
+        let bind_group_id =
+            crate::bind_group::create_explicit_bind_group(device.context(), device.id(), bgl_id, &layout, &[(0, window)]).unwrap();
+
+        let target = device.create_texture(4, 4, 1, 1, 1, 0b10000).unwrap(); // BGRA8 -> Rgba8Unorm, RENDER_ATTACHMENT
+        let (view, _) = device.create_texture_view(target, wgt::TextureDimension::D2, 1, false).unwrap();
+        let record = |offsets: &[u32]| {
+            let mut state = RenderPassState::new(
+                device.context().clone(), device.id(), device.queue_id(), Some(view), Vec::new(), None, None, None,
+                1, None, false, 0, false, 1.0, 0, 4, 4,
+            )
+            .unwrap();
+            state.record_set_pipeline(PipelineBinding::new(pipeline_id)).unwrap();
+            for &offset in offsets {
+                state.record_set_bind_group(0, Some(bind_group_id), vec![offset]);
+                state.record_draw(3, 1, 0, 0);
+            }
+            state.finish_and_submit(device.context(), device.queue_id())
+        };
+        let recorded = record(&[0, alignment]);
+        assert!(recorded.is_ok(), "{:?}", recorded);
+        // Past the end of the buffer: the offset reaches wgpu and is rejected there
+        assert!(record(&[512]).is_err());
+    }
+
+    #[test]
+    fn test_suballocate_from_pool_buffer() {
+        let device = noop_device();
+        let pool = BufferInfo { id: device.create_buffer(1024, 0b100).unwrap(), size: 1024 }; // VERTEX
+
+        let first = device.suballocate(&pool, 300, 0).unwrap();
+        let second = device.suballocate(&pool, 256, 256).unwrap();
+        let third = device.suballocate(&pool, 200, 0).unwrap();
+        assert_eq!((first, second, third), (0, 512, 300));
+        let error = device.suballocate(&pool, 512, 0).unwrap_err();
+        assert!(matches!(error, BasaltError::OutOfMemory { .. }), "{:?}", error);
+
+        // Freed neighbours coalesce into one range big enough for the request
+        device.free_suballocation(pool.id, third).unwrap();
+        device.free_suballocation(pool.id, second).unwrap();
+        assert_eq!(device.suballocate(&pool, 700, 0).unwrap(), 300);
+        assert!(device.free_suballocation(pool.id, second).is_err());
+
+        // Destroying the buffer forgets its pool
+        device.destroy_buffer(pool.id);
+        assert!(device.free_suballocation(pool.id, first).is_err());
+    }
+
+    #[test]
+    fn test_compact_pool_moves_live_ranges_down() {
+        let device = noop_device();
+        let pool = BufferInfo { id: device.create_buffer(1024, 0b11).unwrap(), size: 1024 }; // COPY_SRC | COPY_DST
+        assert!(device.pool_stats(pool.id).is_none());
+
+        let offsets: Vec<u64> = (0..4).map(|_| device.suballocate(&pool, 256, 0).unwrap()).collect();
+        device.free_suballocation(pool.id, offsets[0]).unwrap();
+        device.free_suballocation(pool.id, offsets[2]).unwrap();
+        let stats = device.pool_stats(pool.id).unwrap();
+        assert_eq!((stats.used_bytes, stats.largest_free_range, stats.allocations), (512, 256, 2));
+        assert!(stats.needs_compaction());
+
+        let moves = device.compact_pool(pool.id, u64::MAX).unwrap();
+        let moved: Vec<(u64, u64)> = moves.iter().map(|m| (m.old_offset, m.new_offset)).collect();
+        assert_eq!(moved, [(256, 0), (768, 256)]);
+        let stats = device.pool_stats(pool.id).unwrap();
+        assert_eq!((stats.largest_free_range, stats.fragmentation), (512, 0.0));
+
+        // Allocations are known by their new offsets only
+        assert!(device.free_suballocation(pool.id, 768).is_err());
+        device.free_suballocation(pool.id, 256).unwrap();
+        assert!(device.compact_pool(pool.id, u64::MAX).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_compact_pool_carries_batched_writes() {
+        let device = noop_device();
+        let global = device.context().inner();
+        let pool = BufferInfo { id: device.create_buffer(1024, 0b11).unwrap(), size: 1024 }; // COPY_SRC | COPY_DST
+        let offsets: Vec<u64> = (0..2).map(|_| device.suballocate(&pool, 256, 0).unwrap()).collect();
+        device.free_suballocation(pool.id, offsets[0]).unwrap();
+
+        // Still batched when the pool compacts, so it must land before the move
+        device.update_uniform(pool.id, offsets[1], &[7; 16]).unwrap();
+        let moves = device.compact_pool(pool.id, u64::MAX).unwrap();
+        assert_eq!(moves.len(), 1);
+        let new_offset = moves[0].new_offset;
+        assert_eq!(new_offset, 0);
+
+        let (readback, error) = global.device_create_buffer(
+            device.id(),
+            &wgt::BufferDescriptor {
+                label: None,
+                size: 16,
+                usage: wgt::BufferUsages::MAP_READ | wgt::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+        device.copy_buffer_to_buffer(pool.id, new_offset, readback, 0, 16).unwrap();
+        let map_op = wgpu_core::resource::BufferMapOperation { host: wgpu_core::device::HostMap::Read, callback: None };
+        global.buffer_map_async(readback, 0, Some(16), map_op).unwrap();
+        device.poll_device(true).unwrap();
+        let (ptr, _) = global.buffer_get_mapped_range(readback, 0, Some(16)).unwrap();
+        // SAFETY: the buffer stays mapped for 16 bytes while the slice is used
+        let contents = unsafe { std::slice::from_raw_parts(ptr.as_ptr(), 16) }.to_vec();
+        global.buffer_unmap(readback).unwrap();
+        assert_eq!(contents, [7; 16]);
+    }
+
+    #[test]
+    fn test_atlas_packs_without_overlap() {
+        let device = noop_device();
+        let atlas = device.atlas_create(64, 64, AtlasEvictionPolicy::Reject).unwrap();
+        assert!(device.atlas_create(0, 64, AtlasEvictionPolicy::Reject).is_err());
+
+        let mut placed: Vec<crate::atlas::AtlasEntry> = Vec::new();
+        for (width, height) in [(32, 32), (16, 16), (16, 32), (32, 16), (8, 8), (24, 8)] {
+            let entry = device.atlas_insert(atlas, width, height).unwrap().entry.unwrap();
+            assert!(entry.rect.x + width <= 64 && entry.rect.y + height <= 64);
+            for other in &placed {
+                assert!(!entry.rect.overlaps(&other.rect), "{:?} overlaps {:?}", entry.rect, other.rect);
+            }
+            placed.push(entry);
+        }
+
+        // A full atlas reports no room rather than an error, until it's reset
+        assert_eq!(device.atlas_insert(atlas, 64, 64).unwrap(), AtlasInsert::default());
+        device.atlas_reset(atlas).unwrap();
+        assert!(device.atlas_insert(atlas, 64, 64).unwrap().entry.is_some());
+
+        // Evicting by rectangle goes through the same device-owned atlas
+        let full = device.atlas_insert(atlas, 1, 1).unwrap();
+        assert!(full.entry.is_none());
+        let whole = AtlasRect { x: 0, y: 0, width: 64, height: 64 };
+        assert!(device.atlas_evict_rect(atlas, whole).unwrap().is_some());
+        assert_eq!(device.atlas_evict_rect(atlas, whole).unwrap(), None);
+
+        // A repack whose copies fail leaves the sprites where the texture still has them
+        let first = device.atlas_insert(atlas, 32, 32).unwrap().entry.unwrap();
+        let second = device.atlas_insert(atlas, 32, 32).unwrap().entry.unwrap();
+        device.atlas_evict_rect(atlas, first.rect).unwrap();
+        let no_copy = device.create_texture(64, 64, 1, 1, 0, 0b10000).unwrap(); // RGBA8, RENDER_ATTACHMENT only
+        assert!(device.atlas_repack(atlas, no_copy, wgt::TextureFormat::Rgba8Unorm).is_err());
+        assert_eq!(device.atlas_evict_rect(atlas, second.rect).unwrap(), Some(second.id));
+
+        device.atlas_destroy(atlas);
+        assert!(device.atlas_insert(atlas, 1, 1).is_err());
+        assert!(device.atlas_evict_rect(atlas, whole).is_err());
+
+        // An LRU atlas reports what it evicted and where, so the caller can drop those UVs
+        let lru = device.atlas_create(32, 32, AtlasEvictionPolicy::Lru).unwrap();
+        let old = device.atlas_insert(lru, 32, 32).unwrap().entry.unwrap();
+        let insert = device.atlas_insert(lru, 16, 16).unwrap();
+        assert_eq!(insert.evicted, [old]);
+        assert!(!device.atlas_touch(lru, old.id).unwrap());
+        assert!(insert.entry.is_some());
+    }
+
+    #[test]
+    fn test_line_pipeline_is_cached_and_draws() {
+        const RGBA8: u32 = 0;
+        const DEPTH32F: u32 = 8;
+        const RENDER_ATTACHMENT: u32 = 0b10000;
+
+        let device = noop_device();
+        let color_format = wgt::TextureFormat::Bgra8Unorm;
+        let depth_format = Some(wgt::TextureFormat::Depth32Float);
+        let with_depth = device.create_line_pipeline(color_format, depth_format).unwrap();
+        let cached = device.create_line_pipeline(color_format, depth_format).unwrap();
+        assert_eq!(with_depth.pipeline_id, cached.pipeline_id);
+        let without_depth = device.create_line_pipeline(color_format, None).unwrap();
+        assert_ne!(with_depth.pipeline_id, without_depth.pipeline_id);
+        assert!(device.create_line_pipeline(color_format, Some(wgt::TextureFormat::Stencil8)).is_err());
+
+        let info = |id, format| TextureInfo {
+            id,
+            width: 16,
+            height: 8,
+            depth_or_array_layers: 1,
+            mip_level_count: 1,
+            dimension: wgt::TextureDimension::D2,
+            format,
+            sample_count: 1,
+            is_cube: false,
+        };
+        let color = info(device.create_texture(16, 8, 1, 1, RGBA8, RENDER_ATTACHMENT).unwrap(), color_format);
+        let depth = info(device.create_texture(16, 8, 1, 1, DEPTH32F, RENDER_ATTACHMENT).unwrap(), wgt::TextureFormat::Depth32Float);
+        let vertices = BufferInfo { id: device.create_buffer(64, 0b00110).unwrap(), size: 64 }; // VERTEX | COPY_DST
+        let identity = [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0];
+
+        device.draw_lines(&color, Some(&depth), &vertices, 0, 4, &identity).unwrap();
+        device.draw_lines(&color, None, &vertices, 2, 2, &identity).unwrap();
+        assert!(device.draw_lines(&color, None, &vertices, 2, 4, &identity).is_err());
+    }
+
+    #[test]
+    fn test_submission_indices_increase_and_complete() {
+        let device = noop_device();
+        let buffer = device.create_buffer(16, 0b10010).unwrap(); // UNIFORM | COPY_DST
+        device.update_uniform(buffer, 0, &[1; 16]).unwrap();
+
+        let first = device.submit_and_get_submission_index().unwrap();
+        assert_eq!(device.write_batch_stats().flushed_runs, 1);
+        let second = device.submit_and_get_submission_index().unwrap();
+        assert!(second > first);
+        assert!(device.is_submission_complete(first).unwrap());
+        assert!(device.is_submission_complete(second).unwrap());
+        // An index that was never handed out is a caller bug, not "still running"
+        assert!(device.is_submission_complete(second + 100).is_err());
+    }
+
+    #[test]
+    fn test_render_passes_share_frame_submit() {
+        let device = noop_device();
+        let target = device.create_texture(4, 4, 1, 1, 0, 0b10000).unwrap(); // RGBA8, RENDER_ATTACHMENT
+        let (view, _) = device.create_texture_view(target, wgt::TextureDimension::D2, 1, false).unwrap();
+        let clear_pass = || {
+            RenderPassState::new(
+                device.context().clone(), device.id(), device.queue_id(), Some(view), Vec::new(), None, Some(target),
+                None, 1, None, true, 0xFF000000, false, 1.0, 0, 4, 4,
+            )
+            .unwrap()
+        };
+
+        // Passes wait for the frame's submit, each one still reporting its output
+        for expected in 1..=3 {
+            assert_eq!(device.record_render_pass(&mut clear_pass()).unwrap(), Some(target));
+            assert_eq!(device.pending_frame_passes(), expected);
+        }
+        device.submit_frame().unwrap();
+        assert_eq!(device.pending_frame_passes(), 0);
+
+        // Queue work submits the passes ahead of it, so it can't overtake them
+        device.record_render_pass(&mut clear_pass()).unwrap();
+        let buffer = device.create_buffer(16, 0b10).unwrap(); // COPY_DST
+        device.write_buffer(buffer, 0, &[0; 16]).unwrap();
+        assert_eq!(device.pending_frame_passes(), 0);
+
+        // Batched uniform writes between passes are recorded after them instead
+        device.record_render_pass(&mut clear_pass()).unwrap();
+        device.update_uniform(buffer, 0, &[1; 16]).unwrap();
+        assert_eq!(device.pending_frame_passes(), 1);
+        device.record_render_pass(&mut clear_pass()).unwrap();
+        assert_eq!(device.pending_frame_passes(), 2);
+        assert_eq!(device.write_batch_stats().flushed_runs, 1);
+        device.submit_frame().unwrap();
+        assert_eq!(device.pending_frame_passes(), 0);
+
+        // A pass wgpu rejects is dropped alone; the passes before it still submit
+        const DEPTH32F: u32 = 8;
+        let depth = device.create_texture(8, 8, 1, 1, DEPTH32F, 0b10000).unwrap();
+        let (depth_view, _) = device.create_texture_view(depth, wgt::TextureDimension::D2, 1, false).unwrap();
+        let mut mismatched = RenderPassState::new(
+            device.context().clone(), device.id(), device.queue_id(), Some(view), Vec::new(), Some(depth_view),
+            Some(target), None, 1, None, true, 0, true, 1.0, 0, 4, 4,
+        )
+        .unwrap();
+        device.record_render_pass(&mut clear_pass()).unwrap();
+        assert!(matches!(device.record_render_pass(&mut mismatched), Err(RecordPassError::Recording(_))));
+        assert_eq!(device.pending_frame_passes(), 1);
+        device.submit_frame().unwrap();
+    }
+
+    #[test]
+    fn test_rejected_pass_is_a_recording_error() {
+        // A pass with nothing to render into never reaches a submit
+        let device = noop_device();
+        let mut pass = RenderPassState::new(
+            device.context().clone(), device.id(), device.queue_id(), None, Vec::new(), None, None, None, 1, None,
+            false, 0, false, 1.0, 0, 4, 4,
+        )
+        .unwrap();
+
+        match device.record_render_pass(&mut pass) {
+            Err(RecordPassError::Recording(_)) => {}
+            other => panic!("expected a recording error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_open_pass_keeps_evicted_bind_groups() {
+        use crate::bind_group::{BindGroupKey, BoundResource, MAX_CACHED_BIND_GROUPS};
+
+        let device = noop_device();
+        let global = device.context().inner();
+        let layout_id = crate::bind_group::create_explicit_layout(device.context(), device.id(), &[]).unwrap();
+        let create = || {
+            let (bind_group_id, error) = global.device_create_bind_group(
+                device.id(),
+                &wgpu_core::binding_model::BindGroupDescriptor { label: None, layout: layout_id, entries: Cow::Borrowed(&[]) },
+                None,
+            );
+            assert!(error.is_none(), "{:?}", error);
+            Ok(bind_group_id)
+        };
+        let uniform = |offset| BindGroupKey {
+            layout_id,
+            entries: vec![(0, BoundResource::Buffer { buffer_id: id::BufferId::zip(1, 1), offset, size: 64 })],
+        };
+
+        let target = device.create_texture(4, 4, 1, 1, 0, 0b10000).unwrap(); // RGBA8, RENDER_ATTACHMENT
+        let (view, _) = device.create_texture_view(target, wgt::TextureDimension::D2, 1, false).unwrap();
+        let mut pass = RenderPassState::new(
+            device.context().clone(), device.id(), device.queue_id(), Some(view), Vec::new(), None, Some(target),
+            None, 1, None, true, 0xFF000000, false, 1.0, 0, 4, 4,
+        )
+        .unwrap();
+        let recorded = device.bind_group_cache.get_or_create(uniform(0), create).unwrap();
+        pass.record_set_bind_group(0, Some(recorded.bind_group_id), Vec::new());
+
+        // Filling the cache flushes it, evicting the bind group the open pass recorded
+        for offset in 1..=MAX_CACHED_BIND_GROUPS as u64 {
+            device.bind_group_cache.get_or_create(uniform(offset * 256), create).unwrap();
+        }
+        assert_eq!(device.bind_group_cache.stats().total_bind_groups, 1);
+
+        // A queue write mid-pass submits the frame, but the evicted bind group survives it
+        let buffer = device.create_buffer(16, 0b10).unwrap(); // COPY_DST
+        device.write_buffer(buffer, 0, &[0; 16]).unwrap();
+        assert_eq!(crate::resource_handles::HANDLES.get_bind_group(recorded.handle), Some(recorded.bind_group_id));
+        assert_eq!(device.record_render_pass(&mut pass).unwrap(), Some(target));
+        device.submit_frame().unwrap();
+    }
+
+    #[test]
+    fn test_force_opaque_present_blit() {
+        const RGBA8: u32 = 0;
+        const BGRA8: u32 = 1;
+
+        assert!(!forces_opaque_present(wgt::CompositeAlphaMode::Opaque));
+        assert!(forces_opaque_present(wgt::CompositeAlphaMode::PreMultiplied));
+        assert!(forces_opaque_present(wgt::CompositeAlphaMode::Inherit));
+
+        // The blit shader exposes the override the pipeline sets
+        let module = crate::shader::parse_wgsl(include_str!("shaders/blit.wgsl")).unwrap();
+        assert!(module
+            .overrides
+            .iter()
+            .any(|(_, o)| o.name.as_deref() == Some("force_opaque_alpha")));
+
+        let device = noop_device();
+        assert!(!device.force_opaque_present(), "no surface, nothing to composite");
+
+        // Source with a translucent framebuffer, destination in the swapchain format
+        let src = device.create_texture(4, 4, 1, 1, RGBA8, 0b110).unwrap(); // COPY_DST | TEXTURE_BINDING
+        let dst = device.create_texture(4, 4, 1, 1, BGRA8, 0b10000).unwrap(); // RENDER_ATTACHMENT
+        device.write_texture(src, wgt::TextureFormat::Bgra8Unorm, &[0x80; 4 * 4 * 4], 0, 0, 0, 0, 4, 4).unwrap();
+
+        device.blit_to_swapchain(src, None, dst).unwrap();
+        let unforced = device.blit_pipeline.lock().unwrap();
+
+        // Forcing rebuilds the pipeline with the override set, and the blit still validates
+        device.set_force_opaque_present(true);
+        assert!(device.blit_pipeline.lock().is_none());
+        device.blit_to_swapchain(src, None, dst).unwrap();
+        let forced = device.blit_pipeline.lock().unwrap();
+        assert_ne!(forced, unforced);
+
+        // Setting the same value keeps the cached pipeline
+        device.set_force_opaque_present(true);
+        assert_eq!(*device.blit_pipeline.lock(), Some(forced));
+    }
+
+    #[test]
+    fn test_blit_reuses_sampler_and_bind_group() {
+        const RGBA8: u32 = 0;
+        const BGRA8: u32 = 1;
+
+        let device = noop_device();
+        let src = device.create_texture(4, 4, 1, 1, RGBA8, 0b110).unwrap(); // COPY_DST | TEXTURE_BINDING
+        let other_src = device.create_texture(4, 4, 1, 1, RGBA8, 0b110).unwrap();
+        let dst = device.create_texture(4, 4, 1, 1, BGRA8, 0b10000).unwrap(); // RENDER_ATTACHMENT
+        let cached_bind_group = || device.blit_source.lock().as_ref().map(|source| source.bind_group);
+
+        device.blit_to_swapchain(src, None, dst).unwrap();
+        let samplers_created = device.sampler_cache.stats().misses;
+        let bind_group = cached_bind_group().unwrap();
+
+        // Steady state: same source, nothing new created
+        device.blit_to_swapchain(src, None, dst).unwrap();
+        device.blit_to_swapchain(src, None, dst).unwrap();
+        assert_eq!(device.sampler_cache.stats().misses, samplers_created);
+        assert_eq!(cached_bind_group(), Some(bind_group));
+
+        // A new source replaces the cached bind group
+        device.blit_to_swapchain(other_src, None, dst).unwrap();
+        assert_ne!(cached_bind_group(), Some(bind_group));
+        assert_eq!(device.sampler_cache.stats().misses, samplers_created);
+
+        // Rebuilding the pipeline drops the bind group made for the old layout
+        device.set_force_opaque_present(true);
+        assert!(cached_bind_group().is_none());
+        device.blit_to_swapchain(other_src, None, dst).unwrap();
+    }
+
+    #[test]
+    fn test_hdr_surface_format_falls_back_to_sdr() {
+        use wgt::TextureFormat::*;
+
+        let hdr_capable = [Rgba8UnormSrgb, Bgra8Unorm, Rgba16Float];
+        assert_eq!(choose_surface_format(&hdr_capable, true), HDR_SURFACE_FORMAT);
+        assert_eq!(choose_surface_format(&hdr_capable, false), Bgra8Unorm);
+        // Without the HDR format the usual SDR preference applies
+        assert_eq!(choose_surface_format(&[Rgba8UnormSrgb, Bgra8UnormSrgb], true), Bgra8UnormSrgb);
+        assert_eq!(choose_surface_format(&[Rgb10a2Unorm], true), Rgb10a2Unorm);
+
+        // A device without a surface stays in SDR
+        let device = noop_device();
+        assert!(!device.set_hdr_enabled(true).unwrap());
+        assert!(!device.hdr_enabled());
+        assert_eq!(device.swapchain_format(), device.sdr_swapchain_format());
+    }
+
+    #[test]
+    fn test_surface_capabilities_are_stored() {
+        let caps = wgt::SurfaceCapabilities {
+            formats: vec![wgt::TextureFormat::Bgra8Unorm, wgt::TextureFormat::Rgba16Float],
+            present_modes: vec![wgt::PresentMode::Fifo, wgt::PresentMode::Mailbox],
+            alpha_modes: vec![wgt::CompositeAlphaMode::Opaque],
+            usages: wgt::TextureUsages::RENDER_ATTACHMENT,
+        };
+        let stored = SurfaceCapabilities::from(&caps);
+        assert_eq!(stored.formats, caps.formats);
+        assert_eq!(stored.present_modes, caps.present_modes);
+        assert_eq!(stored.alpha_modes, caps.alpha_modes);
+
+        // Headless devices have no surface to report on
+        assert_eq!(noop_device().get_surface_capabilities(), None);
+    }
+
+    #[test]
+    fn test_outdated_swapchain_is_reconfigured() {
+        assert!(swapchain_needs_reconfigure(&wgt::SurfaceStatus::Outdated));
+        assert!(swapchain_needs_reconfigure(&wgt::SurfaceStatus::Lost));
+        assert!(!swapchain_needs_reconfigure(&wgt::SurfaceStatus::Good));
+        assert!(!swapchain_needs_reconfigure(&wgt::SurfaceStatus::Suboptimal));
+        assert!(!swapchain_needs_reconfigure(&wgt::SurfaceStatus::Timeout));
+
+        // Java matches on the discriminants
+        assert_eq!(PresentStatus::Presented as i32, 0);
+        assert_eq!(PresentStatus::Skipped as i32, 1);
+        assert_eq!(PresentStatus::Reconfigured as i32, 2);
+        assert_eq!(noop_device().present_frame().unwrap(), PresentStatus::Skipped);
+    }
+
+    #[test]
+    fn test_matching_framebuffer_presents_by_copy() {
+        const RGBA8: u32 = 0;
+        const RENDER_ATTACHMENT: u32 = 0b10000;
+
+        let device = noop_device();
+        let info = |id, format| TextureInfo {
+            id,
+            width: 16,
+            height: 8,
+            depth_or_array_layers: 1,
+            mip_level_count: 1,
+            dimension: wgt::TextureDimension::D2,
+            format,
+            sample_count: 1,
+            is_cube: false,
+        };
+        let config = |format, usage| wgt::SurfaceConfiguration {
+            usage,
+            format,
+            width: 16,
+            height: 8,
+            present_mode: wgt::PresentMode::Fifo,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgt::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+        };
+        let copyable = wgt::TextureUsages::RENDER_ATTACHMENT | wgt::TextureUsages::COPY_DST;
+
+        // Render targets can be copied from even when only asked for as attachments
+        let framebuffer = device.create_texture(16, 8, 1, 1, RGBA8, RENDER_ATTACHMENT).unwrap();
+        let swapchain = device.create_texture(16, 8, 1, 1, RGBA8, RENDER_ATTACHMENT | 0b10).unwrap(); // | COPY_DST
+        let src = info(framebuffer, wgt::TextureFormat::Rgba8Unorm);
+        device
+            .copy_texture_to_texture(&src, &info(swapchain, wgt::TextureFormat::Rgba8Unorm), 0, 0, 0, 0, 0, 0, 16, 8)
+            .unwrap();
+
+        assert!(present_by_copy(&src, &config(wgt::TextureFormat::Rgba8Unorm, copyable), false));
+        // A genuine format or size mismatch still needs the blit
+        assert!(!present_by_copy(&src, &config(wgt::TextureFormat::Bgra8Unorm, copyable), false));
+        assert!(!present_by_copy(&src, &config(wgt::TextureFormat::Rgba8UnormSrgb, copyable), false));
+        let mut resized = config(wgt::TextureFormat::Rgba8Unorm, copyable);
+        resized.width = 32;
+        assert!(!present_by_copy(&src, &resized, false));
+        // So does forcing alpha, or a swapchain that can't be copied into
+        assert!(!present_by_copy(&src, &config(wgt::TextureFormat::Rgba8Unorm, copyable), true));
+        let attachment_only = config(wgt::TextureFormat::Rgba8Unorm, wgt::TextureUsages::RENDER_ATTACHMENT);
+        assert!(!present_by_copy(&src, &attachment_only, false));
+    }
+
+    #[test]
+    fn test_submit_errors_are_classified() {
+        use wgpu_core::device::{queue::QueueSubmitError, DeviceError};
+
+        let device = noop_device();
+        let (device_id, queue_id) = (device.device_id, device.queue_id);
+        let global = device.context().inner();
+
+        let (encoder_id, error) = global.device_create_command_encoder(
+            device_id,
+            &wgt::CommandEncoderDescriptor { label: None },
+            None,
+        );
+        assert!(error.is_none());
+        let (command_buffer, error) = global.command_encoder_finish(
+            encoder_id,
+            &wgt::CommandBufferDescriptor::default(),
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+        global.queue_submit(queue_id, &[command_buffer]).unwrap();
+
+        // Submitting a consumed command buffer is a validation error, not device loss
+        let (_, e) = global.queue_submit(queue_id, &[command_buffer]).unwrap_err();
+        let error = device.submit_error("resubmit", &e);
+        assert!(matches!(error, BasaltError::Validation(_)), "{:?}", error);
+        assert!(!device.is_lost());
+
+        let error = device.submit_error("frame submit", &QueueSubmitError::Queue(DeviceError::OutOfMemory));
+        assert!(matches!(error, BasaltError::OutOfMemory { .. }), "{:?}", error);
+        assert!(!device.is_lost());
+
+        let error = device.submit_error("frame submit", &QueueSubmitError::Queue(DeviceError::Lost));
+        assert!(error.is_device_lost(), "{:?}", error);
+        assert!(device.is_lost());
+    }
+
+    #[test]
+    fn test_3d_textures() {
+        const RGBA8: u32 = 0;
+        const DEPTH32F: u32 = 8;
+        const COPY_DST: u32 = 2;
+        const TEXTURE_BINDING: u32 = 4;
+
+        let device = noop_device();
+        let format = device.map_texture_format_public(RGBA8).unwrap();
+        let d3 = wgt::TextureDimension::D3;
+
+        // The mip chain follows the largest of width, height and depth
+        let mip_level_count = BasaltDevice::texture_mip_level_count(d3, 4, 4, 16, 10, 1);
+        assert_eq!(mip_level_count, 5);
+        let volume_request = |depth, mip_levels, format, usage| TextureRequest {
+            dimension: d3,
+            ..TextureRequest::new(4, 4, depth, mip_levels, format, usage)
+        };
+        let texture_id = device.create_texture_from_request(volume_request(16, 10, RGBA8, TEXTURE_BINDING | COPY_DST)).unwrap();
+        let volume = TextureInfo {
+            id: texture_id,
+            width: 4,
+            height: 4,
+            depth_or_array_layers: 16,
+            mip_level_count,
+            dimension: d3,
+            format,
+            sample_count: 1,
+            is_cube: false,
+        };
+        assert_eq!((volume.mip_depth_or_layers(0), volume.mip_depth_or_layers(2)), (16, 4));
+
+        let (_, dimension) = device.create_texture_view(texture_id, d3, 16, false).unwrap();
+        assert_eq!(dimension, wgt::TextureViewDimension::D3);
+        let (_, dimension, _) = device.create_texture_view_ranged(&volume, ViewRangeRequest { base_mip: 1, ..Default::default() }).unwrap();
+        assert_eq!(dimension, wgt::TextureViewDimension::D3);
+
+        // Slices are written one image at a time through origin_z
+        device.write_texture(texture_id, format, &[0x7F; 4 * 4 * 4], 0, 0, 0, 15, 4, 4).unwrap();
+        device.write_texture(texture_id, format, &[0x7F; 2 * 2 * 4], 1, 0, 0, 7, 2, 2).unwrap();
+
+        let error = device.create_texture_from_request(volume_request(4, 1, DEPTH32F, TEXTURE_BINDING)).unwrap_err();
+        assert!(matches!(error, BasaltError::InvalidParameter { .. }), "{:?}", error);
+        let multisampled = TextureRequest { sample_count: 4, ..volume_request(4, 1, RGBA8, TEXTURE_BINDING) };
+        let error = device.create_texture_from_request(multisampled).unwrap_err();
+        assert!(matches!(error, BasaltError::InvalidParameter { .. }), "{:?}", error);
+    }
+
+    #[test]
+    fn test_ranged_texture_views() {
+        const RGBA8: u32 = 0;
+        const RGBA8_SRGB: u32 = 11;
+        const DEPTH24_STENCIL8: u32 = 9;
+        const TEXTURE_BINDING: u32 = 4;
+        const RENDER_ATTACHMENT: u32 = 16;
+
+        let device = noop_device();
+
+        // 6-layer cubemap with a full mip chain
+        let texture_id = device.create_texture(16, 16, 6, 5, RGBA8, TEXTURE_BINDING).unwrap();
+        let cube = TextureInfo {
+            id: texture_id,
+            width: 16,
+            height: 16,
+            depth_or_array_layers: 6,
+            mip_level_count: BasaltDevice::mip_level_count(16, 16, 5, 1),
+            dimension: wgt::TextureDimension::D2,
+            format: wgt::TextureFormat::Bgra8Unorm,
+            sample_count: 1,
+            is_cube: true,
+        };
+
+        // Every face defaults to a Cube view, a 6-layer array texture to D2Array
+        let (_, dimension, _) = device.create_texture_view_ranged(&cube, ViewRangeRequest::default()).unwrap();
+        assert_eq!(dimension, wgt::TextureViewDimension::Cube);
+        let (_, dimension) = device.create_texture_view(texture_id, wgt::TextureDimension::D2, 6, true).unwrap();
+        assert_eq!(dimension, wgt::TextureViewDimension::Cube);
+        let (_, dimension) = device.create_texture_view(texture_id, wgt::TextureDimension::D2, 6, false).unwrap();
+        assert_eq!(dimension, wgt::TextureViewDimension::D2Array);
+        assert!(crate::texture::validate_cube(16, 16, 6).is_ok());
+        assert!(crate::texture::validate_cube(16, 8, 6).is_err());
+        assert!(crate::texture::validate_cube(16, 16, 4).is_err());
+
+        // Single face, single mip
+        let (_, dimension, _) = device.create_texture_view_ranged(&cube, subresource(3, 4)).unwrap();
+        assert_eq!(dimension, wgt::TextureViewDimension::D2);
+        // Whole cube, remaining mips
+        let (_, dimension, _) = device
+            .create_texture_view_ranged(
+                &cube,
+                ViewRangeRequest { base_mip: 1, dimension: Some(wgt::TextureViewDimension::Cube), ..Default::default() },
+            )
+            .unwrap();
+        assert_eq!(dimension, wgt::TextureViewDimension::Cube);
+        // Linear texture sampled through its sRGB counterpart
+        let srgb = device.map_texture_format_public(RGBA8_SRGB).unwrap();
+        assert!(device.create_texture_view_ranged(&cube, ViewRangeRequest { format: Some(srgb), ..subresource(0, 0) }).is_ok());
+
+        // Ranges past the texture's recorded mips/layers are rejected before reaching wgpu
+        let error = device.create_texture_view_ranged(&cube, subresource(5, 0)).unwrap_err();
+        assert!(matches!(error, BasaltError::InvalidParameter { .. }), "{:?}", error);
+        let error = device
+            .create_texture_view_ranged(&cube, ViewRangeRequest { base_layer: 4, layer_count: 3, ..subresource(0, 0) })
+            .unwrap_err();
+        assert!(matches!(error, BasaltError::InvalidParameter { .. }), "{:?}", error);
+        let error = device
+            .create_texture_view_ranged(
+                &cube,
+                ViewRangeRequest { layer_count: 4, dimension: Some(wgt::TextureViewDimension::Cube), ..subresource(0, 0) },
+            )
+            .unwrap_err();
+        assert!(matches!(error, BasaltError::InvalidParameter { .. }), "{:?}", error);
+
+        let depth_id = device.create_texture(16, 16, 1, 1, DEPTH24_STENCIL8, RENDER_ATTACHMENT).unwrap();
+        let depth = TextureInfo {
+            id: depth_id,
+            width: 16,
+            height: 16,
+            depth_or_array_layers: 1,
+            mip_level_count: 1,
+            dimension: wgt::TextureDimension::D2,
+            format: wgt::TextureFormat::Depth24PlusStencil8,
+            sample_count: 1,
+            is_cube: false,
+        };
+        // Depth aspect of a depth-stencil texture
+        assert!(device
+            .create_texture_view_ranged(&depth, ViewRangeRequest { format: Some(wgt::TextureFormat::Depth24Plus), ..subresource(0, 0) })
+            .is_ok());
+    }
+
+    #[test]
+    fn test_depth_stencil_aspect_views() {
+        const DEPTH24_STENCIL8: u32 = 9;
+        const DEPTH32F: u32 = 8;
+        const TEXTURE_BINDING: u32 = 4;
+        const RENDER_ATTACHMENT: u32 = 16;
+
+        let device = noop_device();
+        let info = |id, format| TextureInfo {
+            id,
+            width: 16,
+            height: 16,
+            depth_or_array_layers: 1,
+            mip_level_count: 1,
+            dimension: wgt::TextureDimension::D2,
+            format,
+            sample_count: 1,
+            is_cube: false,
+        };
+
+        let texture_id = device
+            .create_texture(16, 16, 1, 1, DEPTH24_STENCIL8, TEXTURE_BINDING | RENDER_ATTACHMENT)
+            .unwrap();
+        let texture = info(texture_id, wgt::TextureFormat::Depth24PlusStencil8);
+
+        // One texture, one view per aspect
+        let depth = ViewRangeRequest { aspect: Some(wgt::TextureAspect::DepthOnly), ..subresource(0, 0) };
+        let stencil = ViewRangeRequest { aspect: Some(wgt::TextureAspect::StencilOnly), ..subresource(0, 0) };
+        let (depth_view, _, aspect) = device.create_texture_view_ranged(&texture, depth).unwrap();
+        assert_eq!(aspect, wgt::TextureAspect::DepthOnly);
+        let (stencil_view, _, aspect) = device.create_texture_view_ranged(&texture, stencil).unwrap();
+        assert_eq!(aspect, wgt::TextureAspect::StencilOnly);
+        assert_ne!(depth_view, stencil_view);
+
+        // Stencil view format must be the stencil aspect's format
+        assert!(device
+            .create_texture_view_ranged(&texture, ViewRangeRequest { format: Some(wgt::TextureFormat::Stencil8), ..stencil })
+            .is_ok());
+        let error = device
+            .create_texture_view_ranged(&texture, ViewRangeRequest { format: Some(wgt::TextureFormat::Depth24Plus), ..stencil })
+            .unwrap_err();
+        assert!(matches!(error, BasaltError::InvalidParameter { .. }), "{:?}", error);
+
+        // Depth32Float has no stencil aspect
+        let depth_only_id = device
+            .create_texture(16, 16, 1, 1, DEPTH32F, TEXTURE_BINDING | RENDER_ATTACHMENT)
+            .unwrap();
+        let depth_only = info(depth_only_id, wgt::TextureFormat::Depth32Float);
+        let error = device.create_texture_view_ranged(&depth_only, stencil).unwrap_err();
+        assert!(matches!(error, BasaltError::InvalidParameter { .. }), "{:?}", error);
+    }
+
+    #[test]
+    fn test_compressed_texture_upload() {
+        const BC1_RGBA_SRGB: u32 = 14;
+        const COPY_DST: u32 = 2;
+        const TEXTURE_BINDING: u32 = 4;
+
+        // Rejected up front when the compression feature isn't enabled
+        let error = noop_device().create_texture(8, 8, 1, 1, BC1_RGBA_SRGB, TEXTURE_BINDING | COPY_DST).unwrap_err();
+        assert!(error.to_string().contains("TEXTURE_COMPRESSION_BC"), "{}", error);
+
+        let device = noop_device_with_features(wgt::Features::TEXTURE_COMPRESSION_BC);
+        let format = device.map_texture_format_public(BC1_RGBA_SRGB).unwrap();
+        let texture_id = device.create_texture(12, 12, 1, 2, BC1_RGBA_SRGB, TEXTURE_BINDING | COPY_DST).unwrap();
+
+        // 12x12 BC1 is 3x3 blocks of 8 bytes
+        assert_eq!(upload_row_layout(format, 12, 12).unwrap(), (24, 3));
+        device.write_texture(texture_id, format, &[0xAB; 72], 0, 0, 0, 0, 12, 12).unwrap();
+        // Mip 1 is 6x6, uploaded as 2x2 whole blocks
+        assert_eq!(upload_row_layout(format, 6, 6).unwrap(), (16, 2));
+        device.write_texture(texture_id, format, &[0xAB; 32], 1, 0, 0, 0, 6, 6).unwrap();
+
+        let error = device.write_texture(texture_id, format, &[0xAB; 64], 0, 0, 0, 0, 12, 12).unwrap_err();
+        assert!(matches!(error, BasaltError::InvalidParameter { .. }), "{:?}", error);
+    }
+
+    #[test]
+    fn test_texture_upload_batch() {
+        const RGBA8: u32 = 0;
+        const R8: u32 = 4;
+        const COPY_DST: u32 = 2;
+        const TEXTURE_BINDING: u32 = 4;
+
+        let device = noop_device();
+        let atlas = device.create_texture(64, 64, 1, 1, RGBA8, TEXTURE_BINDING | COPY_DST).unwrap();
+        let mask = device.create_texture(16, 16, 2, 1, R8, TEXTURE_BINDING | COPY_DST).unwrap();
+        let rgba = device.map_texture_format_public(RGBA8).unwrap();
+        let r8 = device.map_texture_format_public(R8).unwrap();
+
+        let write = |texture_id, format, layer, x, y, width, height, data_offset| TextureWrite {
+            texture_id,
+            format,
+            mip_level: 0,
+            layer,
+            x,
+            y,
+            width,
+            height,
+            data_offset,
+        };
+        // Two 8x8 sprites and a 5x3 mask region on layer 1, packed back to back
+        let writes = [
+            write(atlas, rgba, 0, 0, 0, 8, 8, 0),
+            write(atlas, rgba, 0, 8, 0, 8, 8, 256),
+            write(mask, r8, 1, 3, 2, 5, 3, 512),
+        ];
+        let data = vec![0x7F; 512 + 15];
+        device.write_textures_batch(&writes, &data).unwrap();
+
+        // Any bad entry fails the batch before anything is uploaded
+        let error = device.write_textures_batch(&writes, &data[..520]).unwrap_err();
+        assert!(error.to_string().contains("writes[2]"), "{}", error);
+        let empty = [write(atlas, rgba, 0, 0, 0, 0, 8, 0)];
+        assert!(device.write_textures_batch(&empty, &data).is_err());
+        // Regions outside the texture are caught by the copy itself
+        let outside = [write(atlas, rgba, 0, 60, 60, 8, 8, 0)];
+        assert!(device.write_textures_batch(&outside, &data).is_err());
+    }
+
+    #[test]
+    fn test_copy_texture_into_higher_mip() {
+        const RGBA8: u32 = 0;
+        const COPY_SRC: u32 = 1;
+        const COPY_DST: u32 = 2;
+        const TEXTURE_BINDING: u32 = 4;
+
+        let device = noop_device();
+        let format = device.map_texture_format_public(RGBA8).unwrap();
+        let info = |id, width, height, mips| TextureInfo {
+            id,
+            width,
+            height,
+            depth_or_array_layers: 1,
+            mip_level_count: BasaltDevice::mip_level_count(width, height, mips, 1),
+            dimension: wgt::TextureDimension::D2,
+            format,
+            sample_count: 1,
+            is_cube: false,
+        };
+
+        // 8x8 base-level image copied into mip 2 (8x8) of a 32x32 chain
+        let image_id = device.create_texture(8, 8, 1, 1, RGBA8, COPY_SRC | COPY_DST).unwrap();
+        device.write_texture(image_id, format, &[0x40; 8 * 8 * 4], 0, 0, 0, 0, 8, 8).unwrap();
+        let image = info(image_id, 8, 8, 1);
+        let chain_id = device.create_texture(32, 32, 1, 6, RGBA8, TEXTURE_BINDING | COPY_DST).unwrap();
+        let chain = info(chain_id, 32, 32, 6);
+        device.copy_texture_to_texture(&image, &chain, 0, 2, 0, 0, 0, 0, 8, 8).unwrap();
+        // Smallest level
+        device.copy_texture_to_texture(&image, &chain, 0, 5, 0, 0, 0, 0, 1, 1).unwrap();
+
+        // Region larger than the destination mip
+        let error = device.copy_texture_to_texture(&image, &chain, 0, 3, 0, 0, 0, 0, 8, 8).unwrap_err();
+        assert!(error.to_string().contains("dst_mip_level"), "{}", error);
+        // Mip levels past either texture's chain
+        let error = device.copy_texture_to_texture(&image, &chain, 0, 6, 0, 0, 0, 0, 1, 1).unwrap_err();
+        assert!(matches!(error, BasaltError::InvalidParameter { .. }), "{:?}", error);
+        let error = device.copy_texture_to_texture(&image, &chain, 1, 2, 0, 0, 0, 0, 1, 1).unwrap_err();
+        assert!(error.to_string().contains("src_mip_level"), "{}", error);
+    }
+
+    #[test]
+    fn test_error_scopes_capture_recoverable_errors() {
+        let _strict = crate::error::STRICT_VALIDATION_TEST_LOCK.lock();
+        let device = noop_device();
+        let _ = crate::error::take_last_error();
+
+        device.push_error_scope(ErrorFilter::Validation);
+        device.push_error_scope(ErrorFilter::OutOfMemory);
+        BasaltError::invalid_parameter("first", "bad").log_or_escalate().unwrap();
+        BasaltError::invalid_parameter("second", "bad").log_or_escalate().unwrap();
+        BasaltError::OutOfMemory { context: "staging".to_string() }.log_or_escalate().unwrap();
+
+        // Each error went to the innermost scope with its filter, first one kept
+        let oom = device.pop_error_scope().unwrap().expect("out of memory captured");
+        assert!(oom.contains("staging"), "{}", oom);
+        let validation = device.pop_error_scope().unwrap().expect("validation captured");
+        assert!(validation.contains("first"), "{}", validation);
+        assert_eq!(crate::error::take_last_error(), None);
+
+        // Without scopes errors go back to the last-error slot
+        assert!(device.pop_error_scope().is_err());
+        BasaltError::Internal("third".to_string()).log_or_escalate().unwrap();
+        assert!(crate::error::take_last_error().unwrap().contains("third"));
+        device.push_error_scope(ErrorFilter::Internal);
+        assert_eq!(device.pop_error_scope().unwrap(), None);
+    }
+
+    #[test]
+    fn test_poll_retires_submitted_work() {
+        const COPY_SRC: u32 = 1;
+        const COPY_DST: u32 = 2;
+
+        let device = noop_device();
+        let src = device.create_buffer(256, COPY_SRC | COPY_DST).unwrap();
+        let dst = device.create_buffer(256, COPY_DST).unwrap();
+        device.write_buffer(src, 0, &[9; 256]).unwrap();
+        device.copy_buffer_to_buffer(src, 0, dst, 0, 256).unwrap();
+
+        assert!(device.poll_device(true).unwrap());
+        // Nothing left to retire, so a non-blocking poll agrees
+        assert!(device.poll_device(false).unwrap());
+        assert!(!device.is_lost());
+    }
+
+    #[test]
+    fn test_pipeline_cache_falls_back_without_support() {
+        let path = std::env::temp_dir().join(format!("bassalt-pipeline-cache-{}.bin", std::process::id()));
+        std::fs::write(&path, b"not a pipeline cache").unwrap();
+
+        // Without PIPELINE_CACHE nothing is loaded and pipelines are made without a cache
+        let device = noop_device();
+        assert!(!device.create_pipeline_cache_from_file(&path).unwrap());
+        assert_eq!(device.pipeline_cache.driver_cache(), None);
+        assert!(!device.save_pipeline_cache(&path).unwrap());
+        assert_eq!(std::fs::read(&path).unwrap(), b"not a pipeline cache");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_frequently_updated_buffers_are_writable() {
+        const VERTEX: u32 = 4;
+        const UNIFORM: u32 = 16;
+
+        let vertex = wgt::BufferUsages::VERTEX;
+        assert_eq!(buffer_usage_with_hint(vertex, false), vertex);
+        assert_eq!(buffer_usage_with_hint(vertex, true), vertex | wgt::BufferUsages::COPY_DST);
+        let copy_dst = wgt::BufferUsages::UNIFORM | wgt::BufferUsages::COPY_DST;
+        assert_eq!(buffer_usage_with_hint(copy_dst, true), copy_dst);
+
+        // A hinted buffer without COPY_DST in its usage still accepts writes
+        let device = noop_device();
+        let dynamic = device.create_buffer_with_hint(256, UNIFORM, true, None).unwrap();
+        device.write_buffer(dynamic, 0, &[1; 256]).unwrap();
+        let static_buffer = device.create_buffer_with_hint(256, VERTEX, false, None).unwrap();
+        assert!(device.write_buffer(static_buffer, 0, &[1; 256]).is_err());
+    }
+
+    #[test]
+    fn test_read_back_pads_unaligned_rows() {
+        const COPY_SRC: u32 = 1;
+        const COPY_DST: u32 = 2;
+        const DEPTH32F: u32 = 8;
+
+        // 1366 Depth32Float texels are 5464 bytes a row, not a multiple of 256
+        let device = noop_device();
+        let depth = device.create_texture(1366, 2, 1, 1, DEPTH32F, COPY_SRC).unwrap();
+        let region = TextureReadback {
+            texture_id: depth,
+            format: wgt::TextureFormat::Depth32Float,
+            aspect: wgt::TextureAspect::DepthOnly,
+            mip_level: 0,
+            width: 1366,
+            height: 2,
+        };
+        let readback = device.create_buffer(1366 * 2 * 4, COPY_DST).unwrap();
+        device.copy_texture_to_buffer(region, readback, 0).unwrap();
+
+        // The tightly packed rows must fit the destination
+        let small = device.create_buffer(1366 * 4, COPY_DST).unwrap();
+        assert!(device.copy_texture_to_buffer(region, small, 0).is_err());
+    }
+
+    #[test]
+    fn test_resources_accept_debug_labels() {
+        const UNIFORM: u32 = 16;
+        const RENDER_ATTACHMENT: u32 = 16;
+
+        let device = noop_device();
+        device.create_buffer_with_hint(256, UNIFORM, false, Some("Sky Uniforms")).unwrap();
+        device
+            .create_texture_from_request(TextureRequest {
+                label: Some("Main Target".to_string()),
+                ..TextureRequest::new(16, 16, 1, 1, 0, RENDER_ATTACHMENT)
+            })
+            .unwrap();
+
+        // Labels aren't part of the sampler cache key
+        let unnamed = SamplerRequest {
+            address_mode_u: 2,
+            address_mode_v: 2,
+            address_mode_w: 2,
+            min_filter: 1,
+            mag_filter: 1,
+            mipmap_filter: 0,
+            lod_min_clamp: 0.0,
+            lod_max_clamp: 32.0,
+            max_anisotropy: 1,
+            compare_function: -1,
+            label: None,
+        };
+        let named = SamplerRequest { label: Some("Atlas Sampler".to_string()), ..unnamed.clone() };
+        let named = device.create_sampler(named).unwrap();
+        let unnamed = device.create_sampler(unnamed).unwrap();
+        assert_eq!(named.handle, unnamed.handle);
+    }
+
+    #[test]
+    fn test_clear_buffer_range() {
+        const COPY_SRC: u32 = 1;
+        const COPY_DST: u32 = 2;
+
+        assert_eq!(crate::buffer::clear_range(64, 16, None).unwrap(), 48);
+        assert_eq!(crate::buffer::clear_range(64, 0, Some(8)).unwrap(), 8);
+        assert!(crate::buffer::clear_range(64, 2, Some(8)).is_err());
+        assert!(crate::buffer::clear_range(64, 0, Some(6)).is_err());
+        assert!(crate::buffer::clear_range(64, 32, Some(64)).is_err());
+        assert!(crate::buffer::clear_range(64, 68, None).is_err());
+
+        let device = noop_device();
+        let global = device.context().inner();
+        let buffer = device.create_buffer(64, COPY_SRC | COPY_DST).unwrap();
+        device.write_buffer(buffer, 0, &[0xAB; 64]).unwrap();
+        device.clear_buffer(buffer, 16, 32).unwrap();
+
+        let (readback, error) = global.device_create_buffer(
+            device.id(),
+            &wgt::BufferDescriptor {
+                label: None,
+                size: 64,
+                usage: wgt::BufferUsages::MAP_READ | wgt::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+        device.copy_buffer_to_buffer(buffer, 0, readback, 0, 64).unwrap();
+        let map_op = wgpu_core::resource::BufferMapOperation { host: wgpu_core::device::HostMap::Read, callback: None };
+        global.buffer_map_async(readback, 0, Some(64), map_op).unwrap();
+        device.poll_device(true).unwrap();
+        let (ptr, _) = global.buffer_get_mapped_range(readback, 0, Some(64)).unwrap();
+        // SAFETY: the buffer stays mapped for 64 bytes while the slice is used
+        let contents = unsafe { std::slice::from_raw_parts(ptr.as_ptr(), 64) }.to_vec();
+        global.buffer_unmap(readback).unwrap();
+
+        assert!(contents[..16].iter().all(|&b| b == 0xAB));
+        assert!(contents[16..48].iter().all(|&b| b == 0));
+        assert!(contents[48..].iter().all(|&b| b == 0xAB));
+    }
+
+    #[test]
+    fn test_device_request_from_flags() {
+        let request = DeviceRequest::from_flags(0b101, &[0, 128, 1, 6]).unwrap();
+        assert_eq!(request.features, wgt::Features::IMMEDIATES | wgt::Features::TIMESTAMP_QUERY);
+        assert_eq!(request.limits, vec![(DeviceLimit::MaxImmediateSize, 128), (DeviceLimit::MaxBindGroups, 6)]);
+
+        assert!(DeviceRequest::from_flags(1 << 40, &[]).is_err());
+        assert!(DeviceRequest::from_flags(0, &[0]).is_err());
+        assert!(DeviceRequest::from_flags(0, &[99, 1]).is_err());
+        assert!(DeviceRequest::from_flags(0, &[0, -1]).is_err());
+
+        let adapter_limits = wgt::Limits { max_immediate_size: 256, ..wgt::Limits::default() };
+        let defaults = wgt::Limits::default();
+
+        // Immediates without an explicit size get Bassalt's range
+        let request = DeviceRequest::from_flags(1, &[]).unwrap();
+        let (features, limits) = request
+            .apply(wgt::Features::DEPTH_CLIP_CONTROL, defaults.clone(), wgt::Features::all(), &adapter_limits)
+            .unwrap();
+        assert_eq!(features, wgt::Features::DEPTH_CLIP_CONTROL | wgt::Features::IMMEDIATES);
+        assert_eq!(limits.max_immediate_size, MAX_IMMEDIATE_SIZE);
+
+        // Missing features and limits above the adapter are rejected
+        assert!(request.apply(wgt::Features::empty(), defaults.clone(), wgt::Features::empty(), &adapter_limits).is_err());
+        let too_big = DeviceRequest::from_flags(0, &[0, 512]).unwrap();
+        assert!(too_big.apply(wgt::Features::empty(), defaults, wgt::Features::all(), &adapter_limits).is_err());
+    }
+
+    #[test]
+    fn test_limits_json_lists_every_limit() {
+        let device = noop_device();
+        let json = device.limits_json();
+        assert!(json.starts_with('{') && json.ends_with('}'));
+
+        let limits = device.get_limits();
+        assert!(json.contains(&format!("\"max_texture_dimension_2d\":{}", limits.max_texture_dimension_2d)));
+        assert!(json.contains(&format!("\"max_buffer_size\":{}", limits.max_buffer_size)));
+        assert!(json.contains(&format!("\"min_uniform_buffer_offset_alignment\":{}", limits.min_uniform_buffer_offset_alignment)));
+        assert!(json.contains("\"max_compute_workgroup_size_z\":"));
+        assert_eq!(json.matches(':').count(), 50);
+    }
+
+    #[test]
+    fn test_reports_real_adapter_info() {
+        let device = noop_device();
+        let info = device.adapter_info().clone();
+        assert_eq!(info.backend, wgt::Backend::Noop);
+        assert!(device.get_renderer().starts_with(&info.name));
+        assert!(device.get_renderer().contains("noop"), "{}", device.get_renderer());
+        assert_eq!(device.get_version(), driver_version(&info));
+        assert!(device.get_implementation_info().contains(&info.name));
+
+        // PCI ids map to names, drivers without a version fall back to ours
+        assert_eq!(vendor_name(0x10DE), Some("NVIDIA"));
+        assert_eq!(vendor_name(0x1234), None);
+        let nvidia = wgt::AdapterInfo {
+            vendor: 0x10DE,
+            driver: "NVIDIA".to_string(),
+            driver_info: "550.54.14".to_string(),
+            ..info.clone()
+        };
+        assert_eq!(driver_version(&nvidia), "NVIDIA 550.54.14");
+        let silent = wgt::AdapterInfo { driver: String::new(), driver_info: String::new(), ..info };
+        assert_eq!(driver_version(&silent), format!("Basalt {}", env!("CARGO_PKG_VERSION")));
+    }
 }
@@ -3,12 +3,122 @@
 //! Provides comprehensive error handling with detailed context for debugging.
 //! Inspired by wgpu's error reporting patterns with additional Bassalt-specific errors.
 
+use std::cell::RefCell;
 use std::fmt;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, Ordering};
 use thiserror::Error;
 
 /// Result type alias for Basalt operations
 pub type Result<T> = std::result::Result<T, BasaltError>;
 
+/// Whether recoverable validation errors are raised instead of logged
+///
+/// Starts from `BASALT_STRICT=1`; Java can toggle it with `setStrictValidation`.
+static STRICT_VALIDATION: LazyLock<AtomicBool> = LazyLock::new(|| {
+    AtomicBool::new(matches!(std::env::var("BASALT_STRICT").as_deref(), Ok("1") | Ok("true")))
+});
+
+/// Whether strict validation is enabled
+pub fn strict_validation() -> bool {
+    STRICT_VALIDATION.load(Ordering::Relaxed)
+}
+
+/// Enable or disable strict validation
+pub fn set_strict_validation(enabled: bool) {
+    STRICT_VALIDATION.store(enabled, Ordering::Relaxed);
+}
+
+/// Held by tests that toggle strict validation or depend on it staying off
+#[cfg(test)]
+pub(crate) static STRICT_VALIDATION_TEST_LOCK: parking_lot::Mutex<()> = parking_lot::Mutex::new(());
+
+thread_local! {
+    /// Most recent error from a per-draw call on this thread
+    ///
+    /// Render pass record calls (set pipeline, bind buffers, draw) report bad
+    /// input here instead of throwing, so the render thread pays no exception
+    /// cost per call; Java polls it with `BassaltDevice.getLastError`.
+    static LAST_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Store `error` as this thread's last error, replacing any previous one
+pub fn set_last_error(error: &BasaltError) {
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = Some(error.to_string()));
+}
+
+/// Take this thread's last error, clearing the slot
+pub fn take_last_error() -> Option<String> {
+    LAST_ERROR.with(|slot| slot.borrow_mut().take())
+}
+
+/// Kind of error an error scope captures, mirroring WebGPU's `GPUErrorFilter`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFilter {
+    Validation,
+    OutOfMemory,
+    Internal,
+}
+
+impl ErrorFilter {
+    /// Filter for a Java `ERROR_FILTER_*` constant
+    pub fn from_id(id: u32) -> Result<Self> {
+        match id {
+            0 => Ok(Self::Validation),
+            1 => Ok(Self::OutOfMemory),
+            2 => Ok(Self::Internal),
+            _ => Err(BasaltError::invalid_parameter("filter", format!("unknown error filter {}", id))),
+        }
+    }
+}
+
+/// An open error scope and the first error it caught
+struct ErrorScope {
+    filter: ErrorFilter,
+    error: Option<String>,
+}
+
+thread_local! {
+    /// Open error scopes on this thread, innermost last
+    ///
+    /// Thread-local like wgpu's own error scopes, so a scope opened around a
+    /// frame on the render thread only sees that thread's errors.
+    static ERROR_SCOPES: RefCell<Vec<ErrorScope>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Open an error scope capturing errors that match `filter`
+pub fn push_error_scope(filter: ErrorFilter) {
+    ERROR_SCOPES.with(|scopes| scopes.borrow_mut().push(ErrorScope { filter, error: None }));
+}
+
+/// Close the innermost error scope, returning the first error it caught
+pub fn pop_error_scope() -> Result<Option<String>> {
+    ERROR_SCOPES
+        .with(|scopes| scopes.borrow_mut().pop())
+        .map(|scope| scope.error)
+        .ok_or_else(|| BasaltError::Validation("pop_error_scope with no open error scope".to_string()))
+}
+
+/// Record `error` in the innermost open scope whose filter matches it
+///
+/// Returns whether a scope took the error. Later errors in a scope that
+/// already holds one are still consumed, as in WebGPU.
+pub fn capture_in_error_scope(error: &BasaltError) -> bool {
+    let Some(filter) = error.error_filter() else {
+        return false;
+    };
+    ERROR_SCOPES.with(|scopes| {
+        let mut scopes = scopes.borrow_mut();
+        match scopes.iter_mut().rev().find(|scope| scope.filter == filter) {
+            Some(scope) => {
+                scope.error.get_or_insert_with(|| error.to_string());
+                true
+            }
+            None => false,
+        }
+    })
+}
+
 /// Main error type for the Basalt renderer
 ///
 /// Each variant provides specific context about what went wrong,
@@ -78,11 +188,14 @@ pub enum BasaltError {
     #[error("Shader validation failed: {shader_name}: {error}")]
     ShaderValidation { shader_name: String, error: String },
 
-    #[error("Shader parse error: {error}")]
+    #[error("{}", format_shader_parse(.shader_name, .error, *.line, *.column, .source_line.as_deref()))]
     ShaderParse {
+        shader_name: String,
         error: String,
         line: Option<usize>,
         column: Option<usize>,
+        /// The offending source line, for showing a snippet alongside the error
+        source_line: Option<String>,
     },
 
     // === Pipeline errors ===
@@ -119,7 +232,7 @@ pub enum BasaltError {
     #[error("Binding size too small: shader requires {shader_size} bytes, bound {bound_size} bytes at binding {binding}")]
     BindingSizeTooSmall { shader_size: u64, bound_size: u64, binding: u32 },
 
-    #[error("Texture dimension mismatch: expected {expected:?}, got {actual:?} at binding {binding}")]
+    #[error("Texture dimension mismatch at binding {binding}: the shader expects a {expected} view, got {actual}")]
     TextureDimensionMismatch {
         expected: String,
         actual: String,
@@ -172,6 +285,38 @@ pub enum BasaltError {
     Internal(String),
 }
 
+/// Format a shader parse error as `name:line:column: error` followed by the
+/// offending source line and a caret under the reported column
+fn format_shader_parse(
+    shader_name: &str,
+    error: &str,
+    line: Option<usize>,
+    column: Option<usize>,
+    source_line: Option<&str>,
+) -> String {
+    let mut msg = match (line, column) {
+        (Some(line), Some(column)) => format!("Shader parse error in '{}' at {}:{}: {}", shader_name, line, column, error),
+        (Some(line), None) => format!("Shader parse error in '{}' at line {}: {}", shader_name, line, error),
+        _ => format!("Shader parse error in '{}': {}", shader_name, error),
+    };
+
+    if let (Some(line), Some(source_line)) = (line, source_line) {
+        let gutter = line.to_string();
+        msg.push_str(&format!("\n {} | {}", gutter, source_line));
+        if let Some(column) = column {
+            // Columns are 1-based byte offsets; count chars so the caret lines up
+            let prefix = source_line
+                .char_indices()
+                .take_while(|(i, _)| *i + 1 < column)
+                .map(|(_, c)| if c == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+            msg.push_str(&format!("\n {} | {}^", " ".repeat(gutter.len()), prefix));
+        }
+    }
+
+    msg
+}
+
 /// Error type categories for wgpu-core errors
 ///
 /// These categories allow filtering and handling of specific error types
@@ -342,11 +487,90 @@ impl BasaltError {
             context: context.to_string(),
         }
     }
+
+    /// Classify a wgpu-core error by its WebGPU error type
+    ///
+    /// Device loss and OOM get their own variants so callers can react to them
+    /// (recover, warn the user) instead of treating every failure as fatal noise.
+    pub fn from_webgpu_error<E>(context: impl fmt::Display, error: &E) -> Self
+    where
+        E: wgpu_types::error::WebGpuError + fmt::Display,
+    {
+        use wgpu_types::error::ErrorType;
+
+        match error.webgpu_error_type() {
+            ErrorType::DeviceLost => Self::DeviceLost {
+                reason: format!("{}: {}", context, error),
+            },
+            ErrorType::OutOfMemory => Self::out_of_memory(format!("{}: {}", context, error)),
+            ErrorType::Validation => Self::Validation(format!("{}: {}", context, error)),
+            ErrorType::Internal => Self::wgpu_context(context, error),
+        }
+    }
+
+    /// Whether this error means the device is gone and must be recreated
+    pub fn is_device_lost(&self) -> bool {
+        matches!(self, Self::DeviceLost { .. })
+    }
+
+    /// Error scope filter that captures this error; None for device loss,
+    /// which is reported through the device's lost state instead
+    pub fn error_filter(&self) -> Option<ErrorFilter> {
+        match self {
+            Self::DeviceLost { .. } => None,
+            Self::OutOfMemory { .. } | Self::BufferAllocationFailed { .. } => Some(ErrorFilter::OutOfMemory),
+            Self::WgpuWithContext { error_type: WgpuErrorType::Memory, .. } => Some(ErrorFilter::OutOfMemory),
+            _ if self.is_validation() => Some(ErrorFilter::Validation),
+            _ => Some(ErrorFilter::Internal),
+        }
+    }
+
+    /// Whether this error comes from a bad API call rather than the device or system
+    pub fn is_validation(&self) -> bool {
+        match self {
+            Self::WgpuWithContext { error_type, .. } => {
+                !matches!(error_type, WgpuErrorType::Device | WgpuErrorType::Memory)
+            }
+            Self::Wgpu(_)
+            | Self::Validation(_)
+            | Self::ShaderValidation { .. }
+            | Self::PipelineCreation { .. }
+            | Self::BindGroupLayoutMismatch { .. }
+            | Self::BufferSizeTooSmall { .. }
+            | Self::BindingSizeTooSmall { .. }
+            | Self::TextureDimensionMismatch { .. }
+            | Self::InvalidParameter { .. }
+            | Self::InvalidHandle { .. }
+            | Self::NullPointer { .. }
+            | Self::RenderPass(_)
+            | Self::DepthStencilMismatch { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Log an error the caller recovers from by skipping the bad call
+    ///
+    /// The error is also kept as this thread's last error. In strict mode
+    /// validation errors are returned instead, so the caller fails at the
+    /// first bad call rather than rendering on with it skipped. An open error
+    /// scope with a matching filter takes the error before either happens.
+    pub fn log_or_escalate(self) -> Result<()> {
+        if capture_in_error_scope(&self) {
+            log::debug!("Captured by error scope: {}", self);
+            return Ok(());
+        }
+        if strict_validation() && self.is_validation() {
+            return Err(self);
+        }
+        log::error!("{}", self);
+        set_last_error(&self);
+        Ok(())
+    }
 }
 
-/// ============================================================================
-/// Shader Compilation Info Types (wgpu 28.0+)
-/// ============================================================================
+// ============================================================================
+// Shader Compilation Info Types (wgpu 28.0+)
+// ============================================================================
 
 /// Compilation information for a shader module.
 ///
@@ -439,6 +663,7 @@ impl CompilationInfo {
     }
 
     /// Format all messages as a human-readable string
+    #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
         if self.messages.is_empty() {
             return "No compilation messages".to_string();
@@ -592,6 +817,19 @@ impl From<naga::error::ShaderError<naga::front::glsl::ParseErrors>> for Compilat
     }
 }
 
+/// Convert from naga SPIR-V parse errors (binary input, so no source location)
+impl From<naga::error::ShaderError<naga::front::spv::Error>> for CompilationInfo {
+    fn from(value: naga::error::ShaderError<naga::front::spv::Error>) -> Self {
+        Self {
+            messages: vec![CompilationMessage {
+                message: value.inner.to_string(),
+                message_type: CompilationMessageType::Error,
+                location: None,
+            }],
+        }
+    }
+}
+
 /// Convert from naga validation errors
 impl From<naga::error::ShaderError<naga::WithSpan<naga::valid::ValidationError>>> for CompilationInfo {
     fn from(value: naga::error::ShaderError<naga::WithSpan<naga::valid::ValidationError>>) -> Self {
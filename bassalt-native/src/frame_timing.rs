@@ -0,0 +1,172 @@
+//! CPU-side frame pacing instrumentation
+//!
+//! GPU timestamps tell how long the GPU spent on a frame, but not how much
+//! time the render thread spent recording and submitting work. These timers
+//! wrap the CPU side of submission, presentation and pipeline creation so a
+//! CPU-bound frame can be told apart from a GPU-bound one.
+//!
+//! Timings accumulate over a frame and are rolled over on every present.
+//! Redundant render state changes skipped while recording are counted alongside.
+
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// CPU operation being timed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuTimingKind {
+    /// `BasaltDevice::record_render_pass`
+    Submit,
+    /// `BasaltDevice::present_frame`
+    Present,
+    /// Pipeline cache lookup and (on a miss) creation
+    PipelineCreation,
+}
+
+impl CpuTimingKind {
+    const ALL: [CpuTimingKind; 3] = [Self::Submit, Self::Present, Self::PipelineCreation];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Submit => "submit",
+            Self::Present => "present",
+            Self::PipelineCreation => "pipeline_creation",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Accumulated timings for one kind of operation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimingStat {
+    pub count: u32,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl TimingStat {
+    fn add(&mut self, elapsed: Duration) {
+        self.count += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+    }
+}
+
+/// Per-frame CPU timings, reset on every present
+#[derive(Debug, Default)]
+pub struct CpuFrameTimings {
+    current: [TimingStat; 3],
+    last_frame: [TimingStat; 3],
+    frame_start: Option<Instant>,
+    last_frame_time: Duration,
+    frames: u64,
+    // Redundant state changes elided by render passes (current frame, last frame)
+    elided_state_changes: (u64, u64),
+}
+
+impl CpuFrameTimings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a measurement to the frame in progress
+    pub fn record(&mut self, kind: CpuTimingKind, elapsed: Duration) {
+        self.frame_start.get_or_insert_with(Instant::now);
+        self.current[kind.index()].add(elapsed);
+    }
+
+    /// Count redundant state changes a render pass skipped
+    pub fn record_elided_state_changes(&mut self, count: u32) {
+        self.elided_state_changes.0 += count as u64;
+    }
+
+    /// Redundant state changes elided in the last completed frame
+    pub fn last_frame_elided_state_changes(&self) -> u64 {
+        self.elided_state_changes.1
+    }
+
+    /// Timings accumulated so far in the frame in progress
+    pub fn current(&self, kind: CpuTimingKind) -> TimingStat {
+        self.current[kind.index()]
+    }
+
+    /// Timings of the last completed frame
+    pub fn last_frame(&self, kind: CpuTimingKind) -> TimingStat {
+        self.last_frame[kind.index()]
+    }
+
+    /// Close the frame in progress; called after present
+    pub fn end_frame(&mut self) {
+        let now = Instant::now();
+        self.last_frame_time = self.frame_start.map_or(Duration::ZERO, |start| now - start);
+        self.last_frame = std::mem::take(&mut self.current);
+        self.elided_state_changes = (0, self.elided_state_changes.0);
+        self.frame_start = Some(now);
+        self.frames += 1;
+    }
+
+    /// JSON summary of the last completed frame and the frame in progress
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        let _ = write!(
+            json,
+            "{{\"frames\":{},\"last_frame_ms\":{:.3},\"last_frame\":{},\"current_frame\":{},\
+             \"elided_state_changes\":{{\"last_frame\":{},\"current_frame\":{}}}}}",
+            self.frames,
+            self.last_frame_time.as_secs_f64() * 1000.0,
+            Self::stats_json(&self.last_frame),
+            Self::stats_json(&self.current),
+            self.elided_state_changes.1,
+            self.elided_state_changes.0,
+        );
+        json
+    }
+
+    fn stats_json(stats: &[TimingStat; 3]) -> String {
+        let fields: Vec<String> = CpuTimingKind::ALL
+            .iter()
+            .map(|kind| {
+                let stat = stats[kind.index()];
+                format!(
+                    "\"{}\":{{\"count\":{},\"total_ms\":{:.3},\"max_ms\":{:.3}}}",
+                    kind.name(),
+                    stat.count,
+                    stat.total.as_secs_f64() * 1000.0,
+                    stat.max.as_secs_f64() * 1000.0,
+                )
+            })
+            .collect();
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timings_populated_after_submit() {
+        let mut timings = CpuFrameTimings::new();
+        timings.record(CpuTimingKind::Submit, Duration::from_micros(300));
+        timings.record(CpuTimingKind::Submit, Duration::from_micros(500));
+
+        let submit = timings.current(CpuTimingKind::Submit);
+        assert_eq!(submit.count, 2);
+        assert_eq!(submit.total, Duration::from_micros(800));
+        assert_eq!(submit.max, Duration::from_micros(500));
+        assert!(timings.to_json().contains("\"current_frame\":{\"submit\":{\"count\":2,\"total_ms\":0.800"));
+
+        // Present rolls the frame over
+        timings.record(CpuTimingKind::Present, Duration::from_micros(100));
+        timings.record_elided_state_changes(7);
+        timings.end_frame();
+        assert_eq!(timings.last_frame_elided_state_changes(), 7);
+        assert!(timings.to_json().ends_with("\"elided_state_changes\":{\"last_frame\":7,\"current_frame\":0}}"));
+        assert_eq!(timings.current(CpuTimingKind::Submit), TimingStat::default());
+        assert_eq!(timings.last_frame(CpuTimingKind::Submit).count, 2);
+        assert_eq!(timings.last_frame(CpuTimingKind::Present).count, 1);
+        assert!(timings.to_json().starts_with("{\"frames\":1,"));
+    }
+}
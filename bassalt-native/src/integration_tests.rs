@@ -0,0 +1,418 @@
+//! End-to-end rendering tests on a real adapter
+//!
+//! These drive the same Rust paths the JNI entry points use (buffer and
+//! texture creation, uploads, the pipeline cache, render passes, readback)
+//! without a window. Machines without a usable GPU skip them.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use wgpu_core::id;
+use wgpu_types as wgt;
+
+use crate::context::BasaltContext;
+use crate::device::{BasaltDevice, TextureRequest};
+use crate::pipeline_registry::{OverrideConstants, PipelineCache, RenderPipelineKey, RenderPipelineRequest, VertexBufferDesc, VertexLayout};
+use crate::render_pass::{PipelineBinding, RenderPassState};
+use crate::resource_handles::PipelineDepthFormat;
+use crate::texture::TextureReadback;
+
+const RGBA8: u32 = 0;
+const COPY_SRC: u32 = 1;
+const COPY_DST: u32 = 2;
+const VERTEX: u32 = 4;
+const RENDER_ATTACHMENT: u32 = 16;
+
+/// Target size; 64 RGBA8 texels fill the 256 byte row alignment of texture copies
+const SIZE: u32 = 64;
+
+/// Device on the first adapter any enabled backend offers, with no surface
+///
+/// Returns None, after saying why, when the machine has no usable adapter.
+fn headless_device() -> Option<BasaltDevice> {
+    headless_device_with_features(wgt::Features::empty())
+}
+
+/// Like `headless_device`, with `required_features` enabled
+///
+/// Returns None, after saying why, when the adapter lacks any of them.
+fn headless_device_with_features(required_features: wgt::Features) -> Option<BasaltDevice> {
+    let context = BasaltContext::new_with_backends(wgt::Backends::all());
+    let adapter_id = match context.inner().request_adapter(
+        &wgpu_core::instance::RequestAdapterOptions::default(),
+        context.backends(),
+        None,
+    ) {
+        Ok(adapter_id) => adapter_id,
+        Err(e) => {
+            eprintln!("skipping GPU test: no adapter available ({})", e);
+            return None;
+        }
+    };
+    let missing = required_features - context.inner().adapter_features(adapter_id);
+    if !missing.is_empty() {
+        eprintln!("skipping GPU test: adapter lacks {:?}", missing);
+        return None;
+    }
+    let (device_id, queue_id) = match context.inner().adapter_request_device(
+        adapter_id,
+        &wgt::DeviceDescriptor { required_features, ..Default::default() },
+        None,
+        None,
+    ) {
+        Ok(ids) => ids,
+        Err(e) => {
+            eprintln!("skipping GPU test: device request failed ({})", e);
+            return None;
+        }
+    };
+    context.set_selected_adapter(adapter_id);
+    let format = wgt::TextureFormat::Bgra8Unorm;
+    BasaltDevice::new(Arc::new(context), device_id, adapter_id, queue_id, None, SIZE, SIZE, format).ok()
+}
+
+/// Build a pipeline through the cache the way `createNativePipelineFromWgsl` does
+///
+/// Returns the pipeline and a bind group for its (binding-free) group 0.
+fn create_pipeline(
+    device: &BasaltDevice,
+    vertex_wgsl: &str,
+    fragment_wgsl: &str,
+    vertex_layout: VertexLayout,
+    target_format: wgt::TextureFormat,
+) -> (id::RenderPipelineId, id::BindGroupId) {
+    let vertex_module = crate::shader::parse_wgsl(vertex_wgsl).unwrap();
+    let fragment_module = crate::shader::parse_wgsl(fragment_wgsl).unwrap();
+    let (bind_group_layout_id, pipeline_layout_id, binding_layouts, _) = crate::create_layout_from_shaders(
+        device.context(),
+        device.id(),
+        &vertex_module,
+        &fragment_module,
+        device.get_limits().max_immediate_size,
+        None,
+    )
+    .unwrap();
+
+    assert!(binding_layouts.is_empty());
+    let (bind_group_id, error) = device.context().inner().device_create_bind_group(
+        device.id(),
+        &wgpu_core::binding_model::BindGroupDescriptor {
+            label: None,
+            layout: bind_group_layout_id,
+            entries: Cow::Borrowed(&[]),
+        },
+        None,
+    );
+    assert!(error.is_none(), "{:?}", error);
+
+    let key = RenderPipelineKey {
+        vertex_shader_hash: PipelineCache::hash_wgsl(vertex_wgsl),
+        fragment_shader_hash: PipelineCache::hash_wgsl(fragment_wgsl),
+        topology: wgt::PrimitiveTopology::TriangleList,
+        strip_index_format: None,
+        polygon_mode: wgt::PolygonMode::Fill,
+        depth_test_enabled: false,
+        depth_write_enabled: false,
+        depth_compare: wgt::CompareFunction::Always,
+        blend_enabled: false,
+        blend_src_color_factor: None,
+        blend_dst_color_factor: None,
+        blend_src_alpha_factor: None,
+        blend_dst_alpha_factor: None,
+        target_format,
+        extra_color_targets: Vec::new(),
+        depth_format: PipelineDepthFormat::None,
+        depth_bias_constant: 0,
+        depth_bias_slope_scale: 0,
+        sample_count: 1,
+        stencil: wgt::StencilState::default(),
+        depth_only: false,
+        override_constants: OverrideConstants::default(),
+        vertex_layout,
+    };
+    let pipeline = device
+        .pipeline_cache
+        .get_or_create_render_pipeline(
+            device.context(),
+            device.id(),
+            vertex_wgsl,
+            fragment_wgsl,
+            RenderPipelineRequest {
+                key,
+                bind_group_layout_id,
+                pipeline_layout_id,
+                binding_layouts,
+                label: "IntegrationTest".to_string(),
+            },
+        )
+        .unwrap();
+    (pipeline.pipeline_id, bind_group_id)
+}
+
+/// Copy mip 0 of an RGBA8-sized `SIZE`x`SIZE` texture to a mappable buffer and read it back
+fn read_texture(device: &BasaltDevice, texture_id: id::TextureId) -> Vec<u8> {
+    read_texture_region(device, texture_id, SIZE, SIZE)
+}
+
+/// Read back the top-left `width`x`height` texels of mip 0, tightly packed
+fn read_texture_region(device: &BasaltDevice, texture_id: id::TextureId, width: u32, height: u32) -> Vec<u8> {
+    let global = device.context().inner();
+    let size = (width * height * 4) as u64;
+    let (readback, error) = global.device_create_buffer(
+        device.id(),
+        &wgt::BufferDescriptor {
+            label: None,
+            size,
+            usage: wgt::BufferUsages::MAP_READ | wgt::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        },
+        None,
+    );
+    assert!(error.is_none(), "{:?}", error);
+    let region = TextureReadback {
+        texture_id,
+        format: wgt::TextureFormat::Rgba8Unorm,
+        aspect: wgt::TextureAspect::All,
+        mip_level: 0,
+        width,
+        height,
+    };
+    device.copy_texture_to_buffer(region, readback, 0).unwrap();
+
+    let map_op = wgpu_core::resource::BufferMapOperation { host: wgpu_core::device::HostMap::Read, callback: None };
+    global.buffer_map_async(readback, 0, Some(size), map_op).unwrap();
+    device.poll_device(true).unwrap();
+    let (ptr, _) = global.buffer_get_mapped_range(readback, 0, Some(size)).unwrap();
+    // SAFETY: the buffer stays mapped for `size` bytes until unmapped below
+    let pixels = unsafe { std::slice::from_raw_parts(ptr.as_ptr(), size as usize) }.to_vec();
+    global.buffer_unmap(readback).unwrap();
+    global.buffer_drop(readback);
+    pixels
+}
+
+#[test]
+fn test_upload_draw_and_read_back() {
+    let Some(device) = headless_device() else {
+        return;
+    };
+    let context = device.context().clone();
+    let format = device.map_texture_format_public(RGBA8).unwrap();
+    assert_eq!(format, wgt::TextureFormat::Bgra8Unorm);
+
+    // Two triangles covering the left half of the target
+    let vertices: [[f32; 2]; 6] = [[-1.0, -1.0], [0.0, -1.0], [0.0, 1.0], [-1.0, -1.0], [0.0, 1.0], [-1.0, 1.0]];
+    let vertex_data: Vec<u8> = vertices.iter().flatten().flat_map(|v| v.to_le_bytes()).collect();
+    let vertex_buffer = device.create_buffer(vertex_data.len() as u64, VERTEX | COPY_DST).unwrap();
+    device.write_buffer_staged(vertex_buffer, 0, &vertex_data).unwrap();
+
+    // Target starts out solid blue through a texture upload (BGRA byte order)
+    let texture_id = device.create_texture(SIZE, SIZE, 1, 1, RGBA8, RENDER_ATTACHMENT | COPY_SRC | COPY_DST).unwrap();
+    let blue = [255u8, 0, 0, 255];
+    device.write_texture(texture_id, format, &blue.repeat((SIZE * SIZE) as usize), 0, 0, 0, 0, SIZE, SIZE).unwrap();
+    let (view_id, _) = device.create_texture_view(texture_id, wgt::TextureDimension::D2, 1, false).unwrap();
+
+    let vertex_layout = VertexLayout::custom(vec![VertexBufferDesc {
+        array_stride: 8,
+        step_mode: wgt::VertexStepMode::Vertex,
+        attributes: vec![wgt::VertexAttribute { format: wgt::VertexFormat::Float32x2, offset: 0, shader_location: 0 }],
+    }])
+    .unwrap();
+    let (pipeline_id, bind_group_id) = create_pipeline(
+        &device,
+        "@vertex fn main(@location(0) position: vec2<f32>) -> @builtin(position) vec4<f32> {
+            return vec4<f32>(position, 0.0, 1.0);
+        }",
+        "@fragment fn main() -> @location(0) vec4<f32> { return vec4<f32>(1.0, 0.0, 0.0, 1.0); }",
+        vertex_layout,
+        format,
+    );
+
+    // Load the uploaded contents and draw red over the left half
+    let mut state = RenderPassState::new(
+        context.clone(), device.id(), device.queue_id(), Some(view_id), Vec::new(), None, Some(texture_id), None, 1,
+        None, false, 0, false, 1.0, 0, SIZE, SIZE,
+    )
+    .unwrap();
+    state.record_set_pipeline(PipelineBinding { vertex_buffer_count: 1, ..PipelineBinding::new(pipeline_id) }).unwrap();
+    state.record_set_bind_group(0, Some(bind_group_id), Vec::new());
+    state.record_set_vertex_buffer(0, vertex_buffer, 0, None);
+    state.record_draw(vertices.len() as u32, 1, 0, 0);
+    state.finish_and_submit(&context, device.queue_id()).unwrap();
+
+    let pixels = read_texture(&device, texture_id);
+    let pixel = |x: u32, y: u32| &pixels[((y * SIZE + x) * 4) as usize..][..4];
+    let red = [0u8, 0, 255, 255];
+    for y in [0, SIZE / 2, SIZE - 1] {
+        assert_eq!(pixel(0, y), red, "left edge, row {}", y);
+        assert_eq!(pixel(SIZE / 2 - 2, y), red, "left of center, row {}", y);
+        assert_eq!(pixel(SIZE / 2 + 1, y), blue, "right of center, row {}", y);
+        assert_eq!(pixel(SIZE - 1, y), blue, "right edge, row {}", y);
+    }
+}
+
+#[test]
+fn test_blit_keeps_orientation() {
+    const TEXTURE_BINDING: u32 = 4;
+
+    let Some(device) = headless_device() else {
+        return;
+    };
+    let format = device.map_texture_format_public(RGBA8).unwrap();
+
+    // Source red in its top-left quadrant only (BGRA byte order)
+    let red = [0u8, 0, 255, 255];
+    let blue = [255u8, 0, 0, 255];
+    let top_left = |x: u32, y: u32| x < SIZE / 2 && y < SIZE / 2;
+    let source: Vec<u8> = (0..SIZE * SIZE)
+        .flat_map(|i| if top_left(i % SIZE, i / SIZE) { red } else { blue })
+        .collect();
+    let src_id = device.create_texture(SIZE, SIZE, 1, 1, RGBA8, TEXTURE_BINDING | COPY_DST).unwrap();
+    device.write_texture(src_id, format, &source, 0, 0, 0, 0, SIZE, SIZE).unwrap();
+    let dst_id = device.create_texture(SIZE, SIZE, 1, 1, RGBA8, RENDER_ATTACHMENT | COPY_SRC).unwrap();
+
+    // Without the source's info the blit can't present by copy, so the shader runs
+    device.blit_to_swapchain(src_id, None, dst_id).unwrap();
+
+    let pixels = read_texture(&device, dst_id);
+    for (x, y) in [(0, 0), (SIZE / 2 - 1, SIZE / 2 - 1), (SIZE - 1, 0), (0, SIZE - 1), (SIZE - 1, SIZE - 1)] {
+        let expected = if top_left(x, y) { red } else { blue };
+        assert_eq!(&pixels[((y * SIZE + x) * 4) as usize..][..4], expected, "pixel ({}, {})", x, y);
+    }
+}
+
+#[test]
+fn test_report_vram_budget() {
+    let Some(device) = headless_device() else {
+        return;
+    };
+    let info = device.context().inner().adapter_get_info(device.adapter_id());
+    match device.vram_budget() {
+        Some(budget) => {
+            eprintln!("{} ({:?}): {} of {} bytes of VRAM available", info.name, info.backend, budget.available, budget.total);
+            assert!(budget.total > 0);
+            assert!(budget.available <= budget.total);
+        }
+        None => eprintln!("{} ({:?}) doesn't report a VRAM budget", info.name, info.backend),
+    }
+}
+
+#[test]
+fn test_pipeline_statistics_around_draw() {
+    use crate::pipeline_statistics::{PipelineStatistic, STATISTICS_PER_QUERY};
+
+    let Some(device) = headless_device_with_features(wgt::Features::PIPELINE_STATISTICS_QUERY) else {
+        return;
+    };
+    let context = device.context().clone();
+    let format = device.map_texture_format_public(RGBA8).unwrap();
+    let texture_id = device.create_texture(SIZE, SIZE, 1, 1, RGBA8, RENDER_ATTACHMENT | COPY_SRC).unwrap();
+    let (view_id, _) = device.create_texture_view(texture_id, wgt::TextureDimension::D2, 1, false).unwrap();
+
+    // One full-screen triangle from vertex_index, no vertex buffers
+    let (pipeline_id, bind_group_id) = create_pipeline(
+        &device,
+        "@vertex fn main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+            let uv = vec2<f32>(f32((i << 1u) & 2u), f32(i & 2u));
+            return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+        }",
+        "@fragment fn main() -> @location(0) vec4<f32> { return vec4<f32>(0.0, 1.0, 0.0, 1.0); }",
+        VertexLayout::Fixed(255),
+        format,
+    );
+
+    let queries = device.create_pipeline_statistics_query_set(2).unwrap();
+    let mut state = RenderPassState::new(
+        context.clone(), device.id(), device.queue_id(), Some(view_id), Vec::new(), None, None, None, 1,
+        None, false, 0, false, 1.0, 0, SIZE, SIZE,
+    )
+    .unwrap();
+    state.record_set_pipeline(PipelineBinding::new(pipeline_id)).unwrap();
+    state.record_set_bind_group(0, Some(bind_group_id), Vec::new());
+    state.record_begin_pipeline_statistics_query(&queries, 0).unwrap();
+    state.record_draw(3, 1, 0, 0);
+    state.record_end_pipeline_statistics_query().unwrap();
+    // An empty query counts nothing
+    state.record_begin_pipeline_statistics_query(&queries, 1).unwrap();
+    state.record_end_pipeline_statistics_query().unwrap();
+    state.finish_and_submit(&context, device.queue_id()).unwrap();
+
+    let stats = device.resolve_pipeline_statistics(&queries, 0, 2).unwrap();
+    assert_eq!(stats.len(), 2 * STATISTICS_PER_QUERY as usize);
+    let (drawn, empty) = stats.split_at(STATISTICS_PER_QUERY as usize);
+    assert!(drawn[PipelineStatistic::VertexShaderInvocations as usize] >= 3, "{:?}", drawn);
+    assert!(drawn[PipelineStatistic::ClipperInvocations as usize] >= 1, "{:?}", drawn);
+    // Every texel of the target is covered once
+    assert!(drawn[PipelineStatistic::FragmentShaderInvocations as usize] >= (SIZE * SIZE) as u64, "{:?}", drawn);
+    assert!(empty.iter().all(|&n| n == 0), "{:?}", empty);
+    queries.destroy(&context);
+}
+
+#[test]
+fn test_partial_msaa_resolve_updates_only_rect() {
+    use crate::msaa::ResolveRect;
+    use crate::resource_handles::TextureInfo;
+    use crate::texture::layer_targets;
+
+    const TEXTURE_BINDING: u32 = 4;
+
+    let Some(device) = headless_device() else {
+        return;
+    };
+    let format = device.map_texture_format_public(RGBA8).unwrap();
+    let info = |id, sample_count| TextureInfo {
+        id,
+        width: SIZE,
+        height: SIZE,
+        depth_or_array_layers: 1,
+        mip_level_count: 1,
+        dimension: wgt::TextureDimension::D2,
+        format,
+        sample_count,
+        is_cube: false,
+    };
+
+    // Multisampled source cleared red, resolve target uploaded blue (BGRA byte order)
+    let msaa_id = device
+        .create_texture_from_request(TextureRequest {
+            sample_count: 4,
+            ..TextureRequest::new(SIZE, SIZE, 1, 1, RGBA8, RENDER_ATTACHMENT | TEXTURE_BINDING)
+        })
+        .unwrap();
+    let targets = layer_targets(wgt::TextureDimension::D2, 1, None).unwrap();
+    device.clear_texture(msaa_id, &targets, Some(wgt::Color::RED), None).unwrap();
+    let target_id = device.create_texture(SIZE, SIZE, 1, 1, RGBA8, RENDER_ATTACHMENT | COPY_SRC | COPY_DST).unwrap();
+    let blue = [255u8, 0, 0, 255];
+    device.write_texture(target_id, format, &blue.repeat((SIZE * SIZE) as usize), 0, 0, 0, 0, SIZE, SIZE).unwrap();
+
+    let rect = ResolveRect { x: 8, y: 16, width: 24, height: 8 };
+    device.resolve_msaa_region(&info(msaa_id, 4), &info(target_id, 1), rect).unwrap();
+    let out_of_bounds = ResolveRect { x: SIZE - 4, y: 0, width: 8, height: 8 };
+    assert!(device.resolve_msaa_region(&info(msaa_id, 4), &info(target_id, 1), out_of_bounds).is_err());
+
+    let pixels = read_texture(&device, target_id);
+    let red = [0u8, 0, 255, 255];
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let inside = (rect.x..rect.x + rect.width).contains(&x) && (rect.y..rect.y + rect.height).contains(&y);
+            let expected = if inside { red } else { blue };
+            assert_eq!(&pixels[((y * SIZE + x) * 4) as usize..][..4], expected, "pixel ({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn test_read_back_unaligned_width() {
+    let Some(device) = headless_device() else {
+        return;
+    };
+    let format = device.map_texture_format_public(RGBA8).unwrap();
+
+    // 10 texels make a 40 byte row, well short of the 256 byte copy alignment
+    let (width, height) = (10, 3);
+    let texture_id = device.create_texture(width, height, 1, 1, RGBA8, COPY_SRC | COPY_DST).unwrap();
+    let texels: Vec<u8> = (0..width * height * 4).map(|i| i as u8).collect();
+    device.write_texture(texture_id, format, &texels, 0, 0, 0, 0, width, height).unwrap();
+
+    assert_eq!(read_texture_region(&device, texture_id, width, height), texels);
+    device.destroy_texture(texture_id);
+}
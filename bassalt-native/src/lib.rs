@@ -27,13 +27,27 @@ mod pipeline_registry;
 mod render_bundle;
 mod timestamp_queries;
 mod msaa;
+mod debug_lines;
+mod range_allocator;
+mod atlas;
+mod frame_timing;
+mod occlusion_queries;
+mod pipeline_statistics;
+mod query_readback;
+mod staging;
+mod write_batch;
+mod log_throttle;
+mod memory_budget;
+#[cfg(test)]
+mod integration_tests;
 
 use std::borrow::Cow;
+use std::fmt;
 use std::sync::Arc;
 use std::sync::{RwLock, LazyLock};
 use std::collections::HashMap;
 use ::jni::JNIEnv;
-use ::jni::objects::{JByteArray, JClass, JString, JObject};
+use ::jni::objects::{JByteArray, JByteBuffer, JClass, JDoubleArray, JFloatArray, JString, JObject, JObjectArray, JLongArray};
 use ::jni::sys::{jlong, jint, jboolean, jstring, jfloat, jlongArray};
 use once_cell::sync::OnceCell;
 use log::info;
@@ -42,6 +56,7 @@ use wgpu_types as wgt;
 use crate::context::BasaltContext;
 use crate::device::BasaltDevice;
 use crate::error::BasaltError;
+use crate::log_throttle::debug_throttled;
 use crate::resource_handles::HANDLES;
 
 /// Global context singleton
@@ -59,7 +74,7 @@ pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltBackend_ini
 ) -> jlong {
     java_logger::init_java_logging();
 
-    let context = Arc::new(BasaltContext::new());
+    let context = Arc::new(BasaltContext::new_with_backends(context::backends_from_env()));
     match GLOBAL_CONTEXT.set(context.clone()) {
         Ok(_) => {
             info!("Basalt renderer initialized");
@@ -73,6 +88,26 @@ pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltBackend_ini
     }
 }
 
+/// Raise validation errors instead of logging them and skipping the bad call
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltBackend_setStrictValidation0(
+    _env: JNIEnv,
+    _class: JClass,
+    enabled: jboolean,
+) {
+    error::set_strict_validation(enabled != 0);
+    info!("Strict validation {}", if enabled != 0 { "enabled" } else { "disabled" });
+}
+
+/// Whether strict validation is enabled
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltBackend_isStrictValidation0(
+    _env: JNIEnv,
+    _class: JClass,
+) -> jboolean {
+    error::strict_validation() as jboolean
+}
+
 /// Get the global context
 pub fn get_global_context() -> Option<Arc<BasaltContext>> {
     GLOBAL_CONTEXT.get().cloned()
@@ -96,6 +131,265 @@ fn map_blend_factor_from_jni(factor: jint) -> Option<wgt::BlendFactor> {
     })
 }
 
+/// Map JNI compare function index to wgpu CompareFunction
+/// Map `BassaltBackend.ASPECT_*` to a texture aspect
+fn map_texture_aspect_from_jni(aspect: jint) -> Option<wgt::TextureAspect> {
+    match aspect {
+        0 => Some(wgt::TextureAspect::All),
+        1 => Some(wgt::TextureAspect::DepthOnly),
+        2 => Some(wgt::TextureAspect::StencilOnly),
+        _ => None,
+    }
+}
+
+fn map_compare_function_from_jni(compare: jint) -> wgt::CompareFunction {
+    match compare as u32 {
+        0 => wgt::CompareFunction::Never,
+        1 => wgt::CompareFunction::Less,
+        2 => wgt::CompareFunction::Equal,
+        3 => wgt::CompareFunction::LessEqual,
+        4 => wgt::CompareFunction::Greater,
+        5 => wgt::CompareFunction::NotEqual,
+        6 => wgt::CompareFunction::GreaterEqual,
+        7 => wgt::CompareFunction::Always,
+        _ => wgt::CompareFunction::Less,
+    }
+}
+
+/// Map JNI stencil operation index to wgpu StencilOperation
+fn map_stencil_operation_from_jni(op: jint) -> wgt::StencilOperation {
+    match op as u32 {
+        0 => wgt::StencilOperation::Keep,
+        1 => wgt::StencilOperation::Zero,
+        2 => wgt::StencilOperation::Replace,
+        3 => wgt::StencilOperation::Invert,
+        4 => wgt::StencilOperation::IncrementClamp,
+        5 => wgt::StencilOperation::DecrementClamp,
+        6 => wgt::StencilOperation::IncrementWrap,
+        7 => wgt::StencilOperation::DecrementWrap,
+        _ => wgt::StencilOperation::Keep,
+    }
+}
+
+/// Build a StencilState from JNI parameters
+///
+/// Each face is `[compare, fail_op, depth_fail_op, pass_op]`. Zero masks
+/// leave the stencil test disabled.
+fn stencil_state_from_jni(read_mask: jint, write_mask: jint, front: [jint; 4], back: [jint; 4]) -> wgt::StencilState {
+    let face = |[compare, fail_op, depth_fail_op, pass_op]: [jint; 4]| wgt::StencilFaceState {
+        compare: map_compare_function_from_jni(compare),
+        fail_op: map_stencil_operation_from_jni(fail_op),
+        depth_fail_op: map_stencil_operation_from_jni(depth_fail_op),
+        pass_op: map_stencil_operation_from_jni(pass_op),
+    };
+    wgt::StencilState {
+        front: face(front),
+        back: face(back),
+        read_mask: read_mask as u32 & 0xFF,
+        write_mask: write_mask as u32 & 0xFF,
+    }
+}
+
+/// Map JNI vertex format index to wgpu VertexFormat
+fn map_vertex_format_from_jni(format: jint) -> Option<wgt::VertexFormat> {
+    Some(match format as u32 {
+        0 => wgt::VertexFormat::Float32,
+        1 => wgt::VertexFormat::Float32x2,
+        2 => wgt::VertexFormat::Float32x3,
+        3 => wgt::VertexFormat::Float32x4,
+        4 => wgt::VertexFormat::Uint32,
+        5 => wgt::VertexFormat::Sint32,
+        6 => wgt::VertexFormat::Unorm8x4,
+        7 => wgt::VertexFormat::Snorm8x4,
+        8 => wgt::VertexFormat::Uint8x4,
+        9 => wgt::VertexFormat::Sint8x4,
+        10 => wgt::VertexFormat::Unorm16x2,
+        11 => wgt::VertexFormat::Snorm16x2,
+        12 => wgt::VertexFormat::Uint16x2,
+        13 => wgt::VertexFormat::Sint16x2,
+        14 => wgt::VertexFormat::Unorm16x4,
+        15 => wgt::VertexFormat::Sint16x4,
+        16 => wgt::VertexFormat::Float16x2,
+        17 => wgt::VertexFormat::Float16x4,
+        _ => return None,
+    })
+}
+
+/// Map JNI step mode index to wgpu VertexStepMode
+fn map_vertex_step_mode_from_jni(step_mode: jint) -> Option<wgt::VertexStepMode> {
+    match step_mode {
+        0 => Some(wgt::VertexStepMode::Vertex),
+        1 => Some(wgt::VertexStepMode::Instance),
+        _ => None,
+    }
+}
+
+fn read_jni_int_array(env: &mut JNIEnv, array: JObject) -> ::jni::errors::Result<Vec<i32>> {
+    if array.is_null() {
+        return Ok(Vec::new());
+    }
+    let array: ::jni::objects::JPrimitiveArray<i32> = array.into();
+    let mut buf = vec![0i32; env.get_array_length(&array)? as usize];
+    env.get_int_array_region(&array, 0, &mut buf)?;
+    Ok(buf)
+}
+
+fn read_jni_long_array(env: &mut JNIEnv, array: JObject) -> ::jni::errors::Result<Vec<i64>> {
+    if array.is_null() {
+        return Ok(Vec::new());
+    }
+    let array: ::jni::objects::JPrimitiveArray<i64> = array.into();
+    let mut buf = vec![0i64; env.get_array_length(&array)? as usize];
+    env.get_long_array_region(&array, 0, &mut buf)?;
+    Ok(buf)
+}
+
+fn new_jni_long_array(env: &mut JNIEnv, values: &[i64]) -> ::jni::errors::Result<jlongArray> {
+    let array = env.new_long_array(values.len() as i32)?;
+    env.set_long_array_region(&array, 0, values)?;
+    Ok(array.into_raw())
+}
+
+/// A String[] of the `Debug` names of `values`, e.g. "Bgra8Unorm" or "Mailbox"
+fn new_jni_name_array<'local, T: std::fmt::Debug>(
+    env: &mut JNIEnv<'local>,
+    values: &[T],
+) -> ::jni::errors::Result<JObjectArray<'local>> {
+    let array = env.new_object_array(values.len() as jint, "java/lang/String", JObject::null())?;
+    for (i, value) in values.iter().enumerate() {
+        let name = env.new_string(format!("{:?}", value))?;
+        env.set_object_array_element(&array, i as jint, name)?;
+    }
+    Ok(array)
+}
+
+/// Build vertex buffer descriptions from JNI arrays
+///
+/// Buffers are described by the parallel `strides`/`step_modes` arrays, and
+/// attributes by the parallel `buffers`/`locations`/`formats`/`offsets`
+/// arrays, where `buffers` is the vertex buffer slot each attribute reads from.
+fn vertex_buffers_from_jni(
+    strides: &[i64],
+    step_modes: &[i32],
+    attribute_buffers: &[i32],
+    attribute_locations: &[i32],
+    attribute_formats: &[i32],
+    attribute_offsets: &[i64],
+) -> error::Result<Vec<pipeline_registry::VertexBufferDesc>> {
+    if strides.len() != step_modes.len() {
+        return Err(BasaltError::invalid_parameter(
+            "bufferStepModes",
+            format!("{} strides but {} step modes", strides.len(), step_modes.len()),
+        ));
+    }
+    let attribute_count = attribute_buffers.len();
+    if [attribute_locations.len(), attribute_formats.len(), attribute_offsets.len()]
+        .iter()
+        .any(|&len| len != attribute_count)
+    {
+        return Err(BasaltError::invalid_parameter(
+            "attributes",
+            "attribute buffer, location, format and offset arrays differ in length",
+        ));
+    }
+
+    let mut buffers = strides
+        .iter()
+        .zip(step_modes)
+        .map(|(&stride, &step_mode)| {
+            Ok(pipeline_registry::VertexBufferDesc {
+                array_stride: u64::try_from(stride).map_err(|_| {
+                    BasaltError::invalid_parameter("bufferStrides", format!("negative stride {}", stride))
+                })?,
+                step_mode: map_vertex_step_mode_from_jni(step_mode).ok_or_else(|| {
+                    BasaltError::invalid_parameter("bufferStepModes", format!("unknown step mode {}", step_mode))
+                })?,
+                attributes: Vec::new(),
+            })
+        })
+        .collect::<error::Result<Vec<_>>>()?;
+
+    for i in 0..attribute_count {
+        let buffer = usize::try_from(attribute_buffers[i])
+            .ok()
+            .and_then(|index| buffers.get_mut(index))
+            .ok_or_else(|| {
+                BasaltError::invalid_parameter(
+                    "attributeBuffers",
+                    format!("attribute {} reads from undeclared vertex buffer {}", i, attribute_buffers[i]),
+                )
+            })?;
+        let format = map_vertex_format_from_jni(attribute_formats[i]).ok_or_else(|| {
+            BasaltError::invalid_parameter(
+                "attributeFormats",
+                format!("attribute {} has unknown vertex format {}", i, attribute_formats[i]),
+            )
+        })?;
+        let (Ok(shader_location), Ok(offset)) = (u32::try_from(attribute_locations[i]), u64::try_from(attribute_offsets[i])) else {
+            return Err(BasaltError::invalid_parameter(
+                "attributes",
+                format!("attribute {} has a negative location or offset", i),
+            ));
+        };
+        buffer.attributes.push(wgt::VertexAttribute { format, offset, shader_location });
+    }
+
+    Ok(buffers)
+}
+
+/// Build MRT color targets from JNI arrays
+///
+/// Target `i` has texture format `formats[i]`, blends when `blend_enabled[i]`
+/// is non-zero and takes its src color, dst color, src alpha and dst alpha
+/// factors from `blend_factors[4 * i..4 * i + 4]`.
+fn color_targets_from_jni(
+    device: &BasaltDevice,
+    formats: &[i32],
+    blend_enabled: &[i32],
+    blend_factors: &[i32],
+) -> error::Result<Vec<wgt::ColorTargetState>> {
+    if formats.is_empty() {
+        return Err(BasaltError::invalid_parameter("targetFormats", "at least one color target is required"));
+    }
+    if blend_enabled.len() != formats.len() || blend_factors.len() != formats.len() * 4 {
+        return Err(BasaltError::invalid_parameter(
+            "targets",
+            format!(
+                "{} target formats need {} blend flags and {} blend factors, got {} and {}",
+                formats.len(), formats.len(), formats.len() * 4, blend_enabled.len(), blend_factors.len()
+            ),
+        ));
+    }
+
+    formats
+        .iter()
+        .zip(blend_enabled)
+        .zip(blend_factors.chunks_exact(4))
+        .enumerate()
+        .map(|(i, ((&format, &blend_enabled), factors))| {
+            let format = device.map_texture_format_public(format as u32)?;
+            device::check_format_usages(device.context(), device.adapter_id(), device.id(), format, wgt::TextureUsages::RENDER_ATTACHMENT)?;
+            let blend = if blend_enabled != 0 {
+                let factor = |index: usize| {
+                    map_blend_factor_from_jni(factors[index]).ok_or_else(|| {
+                        BasaltError::invalid_parameter(
+                            "targetBlendFactors",
+                            format!("target {} has unknown blend factor {}", i, factors[index]),
+                        )
+                    })
+                };
+                Some(wgt::BlendState {
+                    color: wgt::BlendComponent { src_factor: factor(0)?, dst_factor: factor(1)?, operation: wgt::BlendOperation::Add },
+                    alpha: wgt::BlendComponent { src_factor: factor(2)?, dst_factor: factor(3)?, operation: wgt::BlendOperation::Add },
+                })
+            } else {
+                None
+            };
+            Ok(wgt::ColorTargetState { format, blend, write_mask: wgt::ColorWrites::ALL })
+        })
+        .collect()
+}
+
 /// Create a device from GLFW window handle
 #[no_mangle]
 pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltBackend_createDevice(
@@ -106,6 +400,79 @@ pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltBackend_cre
     display_ptr: jlong,
     width: jint,
     height: jint,
+) -> jlong {
+    create_device_handle(&mut env, context_ptr, None, &device::DeviceRequest::default(), window_ptr, display_ptr, width, height)
+}
+
+/// Create a device from GLFW window handle on the adapter at `adapter_index`
+/// in `enumerateAdapters` order
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltBackend_createDeviceOnAdapter(
+    mut env: JNIEnv,
+    _class: JClass,
+    context_ptr: jlong,
+    adapter_index: jint,
+    window_ptr: jlong,
+    display_ptr: jlong,
+    width: jint,
+    height: jint,
+) -> jlong {
+    if adapter_index < 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid adapter index {}", adapter_index));
+        return 0;
+    }
+    create_device_handle(&mut env, context_ptr, Some(adapter_index as usize), &device::DeviceRequest::default(), window_ptr, display_ptr, width, height)
+}
+
+/// Create a device with explicitly requested features and limits
+///
+/// `features` is a `FEATURE_*` bitmask and `limit_overrides` holds
+/// (`LIMIT_*`, value) pairs. A negative `adapter_index` selects automatically.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltBackend_createDeviceWithRequest(
+    mut env: JNIEnv,
+    _class: JClass,
+    context_ptr: jlong,
+    adapter_index: jint,
+    features: jlong,
+    limit_overrides: JLongArray,
+    window_ptr: jlong,
+    display_ptr: jlong,
+    width: jint,
+    height: jint,
+) -> jlong {
+    let mut limit_pairs = Vec::new();
+    if !limit_overrides.is_null() {
+        let len = env.get_array_length(&limit_overrides).unwrap_or(0) as usize;
+        limit_pairs.resize(len, 0i64);
+        if env.get_long_array_region(&limit_overrides, 0, &mut limit_pairs).is_err() {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", "Failed to read limit overrides");
+            return 0;
+        }
+    }
+
+    let request = match device::DeviceRequest::from_flags(features as u64, &limit_pairs) {
+        Ok(request) => request,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            return 0;
+        }
+    };
+    let adapter_index = (adapter_index >= 0).then_some(adapter_index as usize);
+    create_device_handle(&mut env, context_ptr, adapter_index, &request, window_ptr, display_ptr, width, height)
+}
+
+/// Shared body of the `createDevice*` entry points
+#[allow(clippy::too_many_arguments)]
+fn create_device_handle(
+    env: &mut JNIEnv,
+    context_ptr: jlong,
+    adapter_index: Option<usize>,
+    request: &device::DeviceRequest,
+    window_ptr: jlong,
+    display_ptr: jlong,
+    width: jint,
+    height: jint,
 ) -> jlong {
     let context = unsafe {
         if context_ptr == 0 {
@@ -119,8 +486,10 @@ pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltBackend_cre
     let context_clone = context.clone();
     std::mem::forget(context); // Don't drop, we still own the reference
 
-    match device::create_device_from_window(
+    match device::create_device_from_window_with_adapter(
         context_clone,
+        adapter_index,
+        request,
         window_ptr as u64,
         display_ptr as u64,
         width as u32,
@@ -162,6 +531,39 @@ pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltBackend_get
     }
 }
 
+/// List the available adapters as "name (Backend, DeviceType)"
+///
+/// Array positions are the indices `createDeviceOnAdapter` takes.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltBackend_enumerateAdapters<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    context_ptr: jlong,
+) -> JObjectArray<'local> {
+    if context_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null context pointer");
+        return JObjectArray::default();
+    }
+
+    let context = unsafe { &*(context_ptr as *const BasaltContext) };
+    let adapters: Vec<String> = context
+        .enumerate_adapters()
+        .into_iter()
+        .map(|(name, backend, device_type)| format!("{} ({:?}, {:?})", name, backend, device_type))
+        .collect();
+
+    let array = match env.new_object_array(adapters.len() as jint, "java/lang/String", JObject::null()) {
+        Ok(array) => array,
+        Err(_) => return JObjectArray::default(),
+    };
+    for (i, adapter) in adapters.iter().enumerate() {
+        if let Ok(name) = env.new_string(adapter) {
+            let _ = env.set_object_array_element(&array, i as jint, name);
+        }
+    }
+    array
+}
+
 /// Release a device
 #[no_mangle]
 pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_release(
@@ -178,22 +580,168 @@ pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_rele
 }
 
 /// Present the current frame
+///
+/// Returns a `PresentStatus` discriminant, or -1 when presenting failed.
 #[no_mangle]
 pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_presentFrame(
     _env: JNIEnv,
     _class: JClass,
     device_ptr: jlong,
-) {
-    if device_ptr != 0 {
-        unsafe {
-            let device = &*(device_ptr as *const BasaltDevice);
-            if let Err(e) = device.present_frame() {
-                log::error!("Failed to present frame: {}", e);
-            }
+) -> jint {
+    if device_ptr == 0 {
+        return device::PresentStatus::Skipped as jint;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    match device.present_frame() {
+        Ok(status) => status as jint,
+        Err(e) => {
+            log::error!("Failed to present frame: {}", e);
+            -1
         }
     }
 }
 
+/// Get CPU-side frame timings (submit, present, pipeline creation) as JSON
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getCpuTimingsJson(
+    env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+) -> jstring {
+    if device_ptr == 0 {
+        return std::ptr::null_mut();
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    match env.new_string(device.cpu_timings_json()) {
+        Ok(s) => s.into_raw(),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Get every device limit as a JSON object keyed by `wgt::Limits` field name
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getLimitsJson(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+) -> jstring {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return std::ptr::null_mut();
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    match env.new_string(device.limits_json()) {
+        Ok(s) => s.into_raw(),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Resolve a rectangle of a multisampled texture into a single-sample texture
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_resolveMsaaRegion0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    src_texture_handle: jlong,
+    dst_texture_handle: jlong,
+    x: jint,
+    y: jint,
+    width: jint,
+    height: jint,
+) {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return;
+    }
+    if x < 0 || y < 0 || width < 0 || height < 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Negative resolve rectangle");
+        return;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let Some(src_info) = HANDLES.get_texture_info(src_texture_handle as u64) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid source texture handle");
+        return;
+    };
+    let Some(dst_info) = HANDLES.get_texture_info(dst_texture_handle as u64) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid destination texture handle");
+        return;
+    };
+
+    let rect = msaa::ResolveRect { x: x as u32, y: y as u32, width: width as u32, height: height as u32 };
+    if let Err(e) = device.resolve_msaa_region(&src_info, &dst_info, rect) {
+        let class = match e {
+            BasaltError::InvalidParameter { .. } => "java/lang/IllegalArgumentException",
+            _ => "java/lang/RuntimeException",
+        };
+        let _ = env.throw_new(class, format!("Failed to resolve MSAA region: {}", e));
+    }
+}
+
+/// Draw debug lines from a vertex buffer onto a color target, optionally depth tested
+///
+/// `depth_texture_handle` 0 draws without depth testing. `view_proj` is a
+/// column-major 4x4 matrix.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_drawLines0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    color_texture_handle: jlong,
+    depth_texture_handle: jlong,
+    vertex_buffer_handle: jlong,
+    first_vertex: jint,
+    vertex_count: jint,
+    view_proj: JFloatArray,
+) {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return;
+    }
+    if first_vertex < 0 || vertex_count < 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Negative vertex range");
+        return;
+    }
+    let mut matrix = [0f32; 16];
+    if view_proj.is_null() || env.get_array_length(&view_proj).ok() != Some(16) {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "View-projection matrix must have 16 elements");
+        return;
+    }
+    if env.get_float_array_region(&view_proj, 0, &mut matrix).is_err() {
+        return;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let Some(color) = HANDLES.get_texture_info(color_texture_handle as u64) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid color texture handle");
+        return;
+    };
+    let depth = if depth_texture_handle == 0 {
+        None
+    } else {
+        let Some(depth) = HANDLES.get_texture_info(depth_texture_handle as u64) else {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid depth texture handle");
+            return;
+        };
+        Some(depth)
+    };
+    let Some(vertices) = HANDLES.get_buffer_info(vertex_buffer_handle as u64) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid vertex buffer handle");
+        return;
+    };
+
+    if let Err(e) = device.draw_lines(&color, depth.as_ref(), &vertices, first_vertex as u32, vertex_count as u32, &matrix) {
+        let class = match e {
+            BasaltError::InvalidParameter { .. } => "java/lang/IllegalArgumentException",
+            _ => "java/lang/RuntimeException",
+        };
+        let _ = env.throw_new(class, format!("Failed to draw lines: {}", e));
+    }
+}
+
 /// Set vsync mode
 #[no_mangle]
 pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_setVsync(
@@ -311,24 +859,117 @@ pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getM
     device.get_limits().max_texture_dimension_2d as jint
 }
 
-/// Get max supported anisotropy
+/// Best-effort VRAM of the device's adapter as `[total, available]` bytes
+///
+/// Both are -1 where the backend doesn't report memory (see `memory_budget`).
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getMaxSupportedAnisotropy0(
-    _env: JNIEnv,
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getVramBudget0(
+    mut env: JNIEnv,
     _class: JClass,
     device_ptr: jlong,
-) -> jint {
+) -> jlongArray {
     if device_ptr == 0 {
-        return 1;
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return std::ptr::null_mut();
     }
 
-    // wgpu doesn't expose max anisotropy in device limits
-    // Most modern GPUs support 16x anisotropy
-    // This is a safe default value
-    16
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let values = match device.vram_budget() {
+        Some(budget) => [budget.total as jlong, budget.available as jlong],
+        None => [-1, -1],
+    };
+    match env.new_long_array(values.len() as i32) {
+        Ok(array) => {
+            if env.set_long_array_region(&array, 0, &values).is_err() {
+                return std::ptr::null_mut();
+            }
+            array.into_raw()
+        }
+        Err(_) => std::ptr::null_mut(),
+    }
 }
 
-/// Get enabled extensions/features as a comma-separated string
+/// Load the driver pipeline cache saved at `path`
+///
+/// Returns false when the device doesn't support pipeline caches. Bad or
+/// missing files start an empty cache rather than failing.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_loadPipelineCache0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    path: JString,
+) -> jboolean {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+    let path: String = match env.get_string(&path) {
+        Ok(path) => path.into(),
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid path: {}", e));
+            return 0;
+        }
+    };
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    match device.create_pipeline_cache_from_file(std::path::Path::new(&path)) {
+        Ok(loaded) => loaded as jboolean,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to load pipeline cache: {}", e));
+            0
+        }
+    }
+}
+
+/// Save the driver pipeline cache to `path`
+///
+/// Returns false when there is nothing to save.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_savePipelineCache0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    path: JString,
+) -> jboolean {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+    let path: String = match env.get_string(&path) {
+        Ok(path) => path.into(),
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid path: {}", e));
+            return 0;
+        }
+    };
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    match device.save_pipeline_cache(std::path::Path::new(&path)) {
+        Ok(saved) => saved as jboolean,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to save pipeline cache: {}", e));
+            0
+        }
+    }
+}
+
+/// Get max supported anisotropy, queried from the backend at device creation
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getMaxSupportedAnisotropy0(
+    _env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+) -> jint {
+    if device_ptr == 0 {
+        return 1;
+    }
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    device.max_anisotropy() as jint
+}
+
+/// Get enabled extensions/features as a comma-separated string
 #[no_mangle]
 pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getEnabledFeatures0(
     env: JNIEnv,
@@ -415,32 +1056,216 @@ pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_clos
 }
 
 /// Translate GLSL shader to WGSL
+///
+/// `define_names` / `define_values` are parallel arrays of preprocessor
+/// defines; either may be null when there are none.
 #[no_mangle]
 pub extern "system" fn Java_com_criticalrange_bassalt_shader_WgslCompiler_translateGlslToWgsl(
     mut env: JNIEnv,
     _class: JClass,
     glsl_source: JString,
     stage: jint,
+    define_names: JObjectArray,
+    define_values: JObjectArray,
 ) -> jstring {
     let glsl_str: String = match env.get_string(&glsl_source) {
         Ok(s) => s.into(),
         Err(e) => {
-            let _ = env.throw_new("java/lang/IllegalArgumentException", &format!("Invalid string: {}", e));
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid string: {}", e));
             return std::ptr::null_mut();
         }
     };
 
-    let stage = match stage {
-        0 => naga::ShaderStage::Vertex,
-        1 => naga::ShaderStage::Fragment,
-        2 => naga::ShaderStage::Compute,
-        _ => {
-            let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid shader stage");
+    let Some(stage) = glsl_stage_from_jint(stage) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid shader stage");
+        return std::ptr::null_mut();
+    };
+
+    let defines = match read_glsl_defines(&mut env, &define_names, &define_values) {
+        Ok(defines) => defines,
+        Err(msg) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", msg);
+            return std::ptr::null_mut();
+        }
+    };
+
+    match shader::glsl_to_wgsl_with_defines(&glsl_str, stage, &defines) {
+        Ok(wgsl) => match env.new_string(&wgsl) {
+            Ok(s) => s.into_raw(),
+            Err(_) => std::ptr::null_mut(),
+        },
+        Err(e) => {
+            let msg = format!("Shader translation failed: {}", e);
+            let _ = env.throw_new("java/lang/RuntimeException", &msg);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Translate GLSL to WGSL, inlining `#moj_import` files found under `include_paths`
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_shader_WgslCompiler_translateGlslToWgslWithIncludes(
+    mut env: JNIEnv,
+    _class: JClass,
+    glsl_source: JString,
+    stage: jint,
+    include_paths: JObjectArray,
+    define_names: JObjectArray,
+    define_values: JObjectArray,
+) -> jstring {
+    let glsl_str: String = match env.get_string(&glsl_source) {
+        Ok(s) => s.into(),
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid string: {}", e));
+            return std::ptr::null_mut();
+        }
+    };
+
+    let Some(stage) = glsl_stage_from_jint(stage) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid shader stage");
+        return std::ptr::null_mut();
+    };
+
+    let dirs: Vec<std::path::PathBuf> = match read_string_array(&mut env, &include_paths) {
+        Ok(paths) => paths.into_iter().map(Into::into).collect(),
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid include path: {}", e));
+            return std::ptr::null_mut();
+        }
+    };
+
+    let defines = match read_glsl_defines(&mut env, &define_names, &define_values) {
+        Ok(defines) => defines,
+        Err(msg) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", msg);
+            return std::ptr::null_mut();
+        }
+    };
+
+    match shader::glsl_to_wgsl_with_includes(&glsl_str, stage, &dirs, &defines) {
+        Ok(wgsl) => match env.new_string(&wgsl) {
+            Ok(s) => s.into_raw(),
+            Err(_) => std::ptr::null_mut(),
+        },
+        Err(e) => {
+            let msg = format!("Shader translation failed: {}", e);
+            let _ = env.throw_new("java/lang/RuntimeException", &msg);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Read a nullable Java `String`, e.g. an optional debug label
+fn read_optional_string(env: &mut JNIEnv, string: &JString) -> Option<String> {
+    if string.is_null() {
+        return None;
+    }
+    env.get_string(string).ok().map(Into::into)
+}
+
+/// Read a Java `String[]`; null reads as empty
+fn read_string_array(env: &mut JNIEnv, array: &JObjectArray) -> ::jni::errors::Result<Vec<String>> {
+    if array.is_null() {
+        return Ok(Vec::new());
+    }
+    let len = env.get_array_length(array)?;
+    let mut strings = Vec::with_capacity(len as usize);
+    for i in 0..len {
+        let element = JString::from(env.get_object_array_element(array, i)?);
+        strings.push(env.get_string(&element)?.into());
+    }
+    Ok(strings)
+}
+
+/// Zip parallel define name/value arrays; null values define every name as empty
+fn read_glsl_defines(
+    env: &mut JNIEnv,
+    names: &JObjectArray,
+    values: &JObjectArray,
+) -> std::result::Result<Vec<(String, String)>, String> {
+    let names = read_string_array(env, names).map_err(|e| format!("Invalid define name: {}", e))?;
+    let mut values = read_string_array(env, values).map_err(|e| format!("Invalid define value: {}", e))?;
+    if values.is_empty() {
+        values = vec![String::new(); names.len()];
+    }
+    if values.len() != names.len() {
+        return Err(format!("{} define names but {} values", names.len(), values.len()));
+    }
+    Ok(names.into_iter().zip(values).collect())
+}
+
+/// Drop cached GLSL translations so reloaded resource packs are translated again
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_shader_WgslCompiler_clearTranslationCache(
+    _env: JNIEnv,
+    _class: JClass,
+) {
+    shader::clear_translation_cache();
+}
+
+/// Detect the stage of a WGSL shader from its entry point
+///
+/// Returns the stage constant (0 = vertex, 1 = fragment, 2 = compute).
+/// Throws when the source doesn't parse or doesn't have entry points for exactly one stage.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_shader_WgslCompiler_detectWgslStage(
+    mut env: JNIEnv,
+    _class: JClass,
+    wgsl_source: JString,
+) -> jint {
+    let wgsl: String = match env.get_string(&wgsl_source) {
+        Ok(s) => s.into(),
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid string: {}", e));
+            return -1;
+        }
+    };
+
+    match shader::detect_wgsl_stage(&wgsl, "detectWgslStage") {
+        Ok(naga::ShaderStage::Vertex) => 0,
+        Ok(naga::ShaderStage::Fragment) => 1,
+        Ok(naga::ShaderStage::Compute) => 2,
+        Ok(stage) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Unsupported shader stage: {:?}", stage));
+            -1
+        }
+        Err(e @ BasaltError::InvalidParameter { .. }) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            -1
+        }
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Shader parse failed: {}", e));
+            -1
+        }
+    }
+}
+
+/// Map the Java-side stage constant (0 = vertex, 1 = fragment, 2 = compute)
+fn glsl_stage_from_jint(stage: jint) -> Option<naga::ShaderStage> {
+    match stage {
+        0 => Some(naga::ShaderStage::Vertex),
+        1 => Some(naga::ShaderStage::Fragment),
+        2 => Some(naga::ShaderStage::Compute),
+        _ => None,
+    }
+}
+
+/// Translate a SPIR-V binary to WGSL
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_shader_WgslCompiler_translateSpirvToWgsl(
+    mut env: JNIEnv,
+    _class: JClass,
+    spirv: JByteArray,
+) -> jstring {
+    let bytes = match env.convert_byte_array(&spirv) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid byte array: {}", e));
             return std::ptr::null_mut();
         }
     };
 
-    match shader::glsl_to_wgsl(&glsl_str, stage) {
+    match shader::spirv_to_wgsl(&bytes, "spirv") {
         Ok(wgsl) => match env.new_string(&wgsl) {
             Ok(s) => s.into_raw(),
             Err(_) => std::ptr::null_mut(),
@@ -458,6 +1283,8 @@ pub extern "system" fn Java_com_criticalrange_bassalt_shader_WgslCompiler_transl
 // ============================================================================
 
 /// Create an empty buffer
+///
+/// `label` names the buffer in GPU captures; null keeps the usage-derived label.
 #[no_mangle]
 pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createBufferEmpty(
     mut env: JNIEnv,
@@ -465,6 +1292,8 @@ pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_crea
     device_ptr: jlong,
     size: jlong,
     usage: jint,
+    frequently_updated: jboolean,
+    label: JString,
 ) -> jlong {
     if device_ptr == 0 {
         let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
@@ -472,8 +1301,9 @@ pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_crea
     }
 
     let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let label = read_optional_string(&mut env, &label);
 
-    match device.create_buffer(size as u64, usage as u32) {
+    match device.create_buffer_with_hint(size as u64, usage as u32, frequently_updated != 0, label.as_deref()) {
         Ok(buffer_id) => {
             // Store the buffer ID and size, return a handle
             let handle = HANDLES.insert_buffer(buffer_id, size as u64);
@@ -481,12 +1311,40 @@ pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_crea
             handle as jlong
         }
         Err(e) => {
-            let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to create buffer: {}", e));
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create buffer: {}", e));
             0
         }
     }
 }
 
+/// Borrow the first `length` bytes of a direct `ByteBuffer` without copying
+///
+/// Heap buffers have no native address and fail here; Java sends those
+/// through the `byte[]` entry points instead. The slice is only valid for
+/// the duration of the JNI call, while Java holds the buffer.
+fn direct_buffer_slice<'a>(env: &JNIEnv, buffer: &JByteBuffer<'a>, length: jint) -> Result<&'a [u8], String> {
+    if length < 0 {
+        return Err(format!("negative length {}", length));
+    }
+    if length == 0 {
+        return Ok(&[]);
+    }
+
+    let address = env
+        .get_direct_buffer_address(buffer)
+        .map_err(|e| format!("ByteBuffer is not direct: {}", e))?;
+    let capacity = env
+        .get_direct_buffer_capacity(buffer)
+        .map_err(|e| format!("Failed to get ByteBuffer capacity: {}", e))?;
+    if length as usize > capacity {
+        return Err(format!("length {} exceeds ByteBuffer capacity {}", length, capacity));
+    }
+
+    // SAFETY: the address and capacity come from the JVM and the buffer stays
+    // reachable through the local reference until the call returns
+    Ok(unsafe { std::slice::from_raw_parts(address, length as usize) })
+}
+
 /// Create a buffer with initial data
 #[no_mangle]
 pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createBufferData(
@@ -495,30 +1353,63 @@ pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_crea
     device_ptr: jlong,
     data: JByteArray,
     usage: jint,
+    frequently_updated: jboolean,
 ) -> jlong {
-    if device_ptr == 0 {
-        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
-        return 0;
-    }
-
-    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
-
     // Convert Java byte array to Rust Vec
     let data_vec: Vec<u8> = match env.convert_byte_array(&data) {
         Ok(arr) => arr,
         Err(e) => {
-            let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to get byte array: {}", e));
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get byte array: {}", e));
+            return 0;
+        }
+    };
+
+    create_buffer_with_data(&mut env, device_ptr, &data_vec, usage, frequently_updated != 0)
+}
+
+/// Create a buffer with initial data read in place from a direct `ByteBuffer`
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createBufferDataDirect0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    data: JByteBuffer,
+    length: jint,
+    usage: jint,
+    frequently_updated: jboolean,
+) -> jlong {
+    let data = match direct_buffer_slice(&env, &data, length) {
+        Ok(data) => data,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e);
             return 0;
         }
     };
 
+    create_buffer_with_data(&mut env, device_ptr, data, usage, frequently_updated != 0)
+}
+
+fn create_buffer_with_data(
+    env: &mut JNIEnv,
+    device_ptr: jlong,
+    data_vec: &[u8],
+    usage: jint,
+    frequently_updated: bool,
+) -> jlong {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
     let size = data_vec.len() as u64;
 
-    match device.create_buffer(size, usage as u32) {
+    match device.create_buffer_with_hint(size, usage as u32, frequently_updated, None) {
         Ok(buffer_id) => {
             // Write initial data
-            if let Err(e) = device.write_buffer(buffer_id, 0, &data_vec) {
-                let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to write initial buffer data: {}", e));
+            if let Err(e) = device.write_buffer_staged(buffer_id, 0, data_vec) {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to write initial buffer data: {}", e));
                 return 0;
             }
 
@@ -528,7 +1419,7 @@ pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_crea
             handle as jlong
         }
         Err(e) => {
-            let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to create buffer: {}", e));
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create buffer: {}", e));
             0
         }
     }
@@ -544,6 +1435,41 @@ pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_writ
     data_ptr: JByteArray,
     offset: jlong,
 ) {
+    // Convert Java byte array to Rust Vec
+    let data: Vec<u8> = match env.convert_byte_array(&data_ptr) {
+        Ok(arr) => arr,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get byte array: {}", e));
+            return;
+        }
+    };
+
+    write_buffer_from_jni(&mut env, device_ptr, buffer_handle, &data, offset);
+}
+
+/// Write `length` bytes from a direct `ByteBuffer` to a buffer without copying them first
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_writeBufferDirect0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    buffer_handle: jlong,
+    data: JByteBuffer,
+    offset: jlong,
+    length: jint,
+) {
+    let data = match direct_buffer_slice(&env, &data, length) {
+        Ok(data) => data,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+            return;
+        }
+    };
+
+    write_buffer_from_jni(&mut env, device_ptr, buffer_handle, data, offset);
+}
+
+fn write_buffer_from_jni(env: &mut JNIEnv, device_ptr: jlong, buffer_handle: jlong, data: &[u8], offset: jlong) {
     if device_ptr == 0 || buffer_handle == 0 {
         let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
         return;
@@ -560,15 +1486,6 @@ pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_writ
         }
     };
 
-    // Convert Java byte array to Rust Vec
-    let data: Vec<u8> = match env.convert_byte_array(&data_ptr) {
-        Ok(arr) => arr,
-        Err(e) => {
-            let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to get byte array: {}", e));
-            return;
-        }
-    };
-
     // Log uniform buffer writes for debugging (offset 256+ are DynamicTransforms)
     if offset >= 256 && data.len() >= 80 {
         // ColorModulator is at offset 64 within DynamicTransforms (so offset+64 in buffer)
@@ -590,446 +1507,1295 @@ pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_writ
         offsets.insert(buffer_handle as u64, offset as u64 + data.len() as u64);
     }
 
-    if let Err(e) = device.write_buffer(buffer_id, offset as u64, &data) {
-        let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to write buffer: {}", e));
+    if let Err(e) = device.write_buffer_staged(buffer_id, offset as u64, data) {
+        let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to write buffer: {}", e));
     }
 }
 
-/// Destroy a buffer
+/// Write a small uniform update from a direct `ByteBuffer`, batched until the next pass is submitted
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_destroyBuffer(
-    _env: JNIEnv,
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_updateUniformDirect0(
+    mut env: JNIEnv,
     _class: JClass,
     device_ptr: jlong,
     buffer_handle: jlong,
+    data: JByteBuffer,
+    offset: jlong,
+    length: jint,
 ) {
     if device_ptr == 0 || buffer_handle == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
         return;
     }
 
     let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let data = match direct_buffer_slice(&env, &data, length) {
+        Ok(data) => data,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+            return;
+        }
+    };
+    let Some(buffer_id) = HANDLES.get_buffer(buffer_handle as u64) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid buffer handle");
+        return;
+    };
 
-    // Look up and remove buffer ID from handle store
-    if let Some(buffer_id) = HANDLES.remove_buffer(buffer_handle as u64) {
-        device.destroy_buffer(buffer_id);
-        log::debug!("Destroyed buffer with handle {}", buffer_handle);
+    if let Err(e) = device.update_uniform(buffer_id, offset as u64, data) {
+        let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to update uniform: {}", e));
     }
 }
 
-// ============================================================================
-// TEXTURE OPERATIONS
-// ============================================================================
-
-/// Create a texture
+/// Suballocate `size` bytes aligned to `align` from a pool buffer, returning the offset
+///
+/// Throws OutOfMemoryError when the pool has no free range that fits.
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createTexture(
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_allocRange0(
     mut env: JNIEnv,
     _class: JClass,
     device_ptr: jlong,
-    width: jint,
-    height: jint,
-    depth: jint,
-    mip_levels: jint,
-    format: jint,
-    usage: jint,
-) -> jlong {
+    buffer_handle: jlong,
+    size: jlong,
+    align: jlong,
+) -> jlong {
+    if device_ptr == 0 || buffer_handle == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
+        return -1;
+    }
+    if size < 0 || align < 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Negative size or alignment");
+        return -1;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let Some(pool) = HANDLES.get_buffer_info(buffer_handle as u64) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid buffer handle");
+        return -1;
+    };
+
+    match device.suballocate(&pool, size as u64, align as u64) {
+        Ok(offset) => offset as jlong,
+        Err(e @ BasaltError::OutOfMemory { .. }) => {
+            let _ = env.throw_new("java/lang/OutOfMemoryError", e.to_string());
+            -1
+        }
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            -1
+        }
+    }
+}
+
+/// Free the range `allocRange0` returned at `offset` of a pool buffer
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_freeRange0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    buffer_handle: jlong,
+    offset: jlong,
+) {
+    if device_ptr == 0 || buffer_handle == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
+        return;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let Some(buffer_id) = HANDLES.get_buffer(buffer_handle as u64) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid buffer handle");
+        return;
+    };
+
+    if let Err(e) = device.free_suballocation(buffer_id, offset as u64) {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+    }
+}
+
+/// Compact a pool buffer's ranges, moving at most `max_bytes`
+///
+/// Returns the moves as `[old_offset, new_offset, size]` triples; every cached
+/// offset must be rewritten from the old to the new one.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_compactPool0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    buffer_handle: jlong,
+    max_bytes: jlong,
+) -> jlongArray {
+    if device_ptr == 0 || buffer_handle == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
+        return std::ptr::null_mut();
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let Some(buffer_id) = HANDLES.get_buffer(buffer_handle as u64) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid buffer handle");
+        return std::ptr::null_mut();
+    };
+
+    let moves = match device.compact_pool(buffer_id, max_bytes.max(0) as u64) {
+        Ok(moves) => moves,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to compact pool: {}", e));
+            return std::ptr::null_mut();
+        }
+    };
+    let values: Vec<jlong> = moves
+        .iter()
+        .flat_map(|m| [m.old_offset as jlong, m.new_offset as jlong, m.size as jlong])
+        .collect();
+    new_jni_long_array(&mut env, &values).unwrap_or(std::ptr::null_mut())
+}
+
+/// Occupancy of a pool buffer
+///
+/// Returns `[capacity, used, free, largest_free, allocations, needs_compaction]`,
+/// the last being 1 when fragmentation reaches the compaction threshold, or
+/// null if nothing was ever allocated from the buffer.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getPoolStats0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    buffer_handle: jlong,
+) -> jlongArray {
+    if device_ptr == 0 || buffer_handle == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
+        return std::ptr::null_mut();
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let Some(buffer_id) = HANDLES.get_buffer(buffer_handle as u64) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid buffer handle");
+        return std::ptr::null_mut();
+    };
+
+    let Some(stats) = device.pool_stats(buffer_id) else {
+        return std::ptr::null_mut();
+    };
+    let values = [
+        stats.capacity as jlong,
+        stats.used_bytes as jlong,
+        stats.free_bytes as jlong,
+        stats.largest_free_range as jlong,
+        stats.allocations as jlong,
+        stats.needs_compaction() as jlong,
+    ];
+    new_jni_long_array(&mut env, &values).unwrap_or(std::ptr::null_mut())
+}
+
+/// Map `BassaltDevice.ATLAS_EVICT_*` to an eviction policy
+fn map_atlas_eviction_policy_from_jni(policy: jint) -> Option<atlas::AtlasEvictionPolicy> {
+    match policy {
+        0 => Some(atlas::AtlasEvictionPolicy::Reject),
+        1 => Some(atlas::AtlasEvictionPolicy::Lru),
+        _ => None,
+    }
+}
+
+/// Create an atlas allocator of `width` x `height` texels, returning its id
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_atlasCreate0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    width: jint,
+    height: jint,
+    policy: jint,
+) -> jlong {
     if device_ptr == 0 {
         let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
         return 0;
     }
+    let Some(policy) = map_atlas_eviction_policy_from_jni(policy) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Unknown atlas eviction policy {}", policy));
+        return 0;
+    };
 
     let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    match device.atlas_create(width.max(0) as u32, height.max(0) as u32, policy) {
+        Ok(atlas_id) => atlas_id as jlong,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            0
+        }
+    }
+}
 
-    // Map format first so we can store it
-    let texture_format = match device.map_texture_format_public(format as u32) {
-        Ok(f) => f,
+/// Place a sprite in an atlas
+///
+/// Returns `[entry_id, x, y]` followed by `[id, x, y, width, height]` for each
+/// sprite an LRU atlas evicted to make room. `entry_id` is 0 when the sprite
+/// doesn't fit, so the caller can grow or reset the atlas.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_atlasInsert0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    atlas_id: jlong,
+    width: jint,
+    height: jint,
+) -> jlongArray {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return std::ptr::null_mut();
+    }
+    if width <= 0 || height <= 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Sprite size must be positive");
+        return std::ptr::null_mut();
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let insert = match device.atlas_insert(atlas_id as u64, width as u32, height as u32) {
+        Ok(insert) => insert,
         Err(e) => {
-            let _ = env.throw_new("java/lang/RuntimeException", &format!("Invalid texture format: {}", e));
-            return 0;
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            return std::ptr::null_mut();
         }
     };
-    
-    match device.create_texture(
-        width as u32,
-        height as u32,
-        depth as u32,
-        mip_levels as u32,
-        format as u32,
-        usage as u32,
-    ) {
-        Ok(texture_id) => {
-            // Store texture with array layer info and format for debugging
-            let handle = HANDLES.insert_texture(
-                texture_id,
-                depth as u32,
-                wgt::TextureDimension::D2,
-                texture_format,
-            );
-            log::info!("Created texture: handle={} texture_id={:?} ({}x{}x{}) format={:?}", handle, texture_id, width, height, depth, texture_format);
-            handle as jlong
-        }
+    let placed = insert.entry.map_or([0; 3], |entry| {
+        [entry.id as jlong, entry.rect.x as jlong, entry.rect.y as jlong]
+    });
+    let mut values: Vec<jlong> = Vec::with_capacity(3 + insert.evicted.len() * 5);
+    values.extend_from_slice(&placed);
+    for evicted in &insert.evicted {
+        values.extend_from_slice(&[
+            evicted.id as jlong,
+            evicted.rect.x as jlong,
+            evicted.rect.y as jlong,
+            evicted.rect.width as jlong,
+            evicted.rect.height as jlong,
+        ]);
+    }
+    new_jni_long_array(&mut env, &values).unwrap_or(std::ptr::null_mut())
+}
+
+/// Mark an atlas sprite as used, returning false if it was evicted
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_atlasTouch0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    atlas_id: jlong,
+    entry_id: jlong,
+) -> jboolean {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    match device.atlas_touch(atlas_id as u64, entry_id as u64) {
+        Ok(live) => live as jboolean,
         Err(e) => {
-            let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to create texture: {}", e));
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
             0
         }
     }
 }
 
-/// Destroy a texture
+/// Free every sprite of an atlas
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_destroyTexture(
-    _env: JNIEnv,
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_atlasReset0(
+    mut env: JNIEnv,
     _class: JClass,
     device_ptr: jlong,
-    texture_handle: jlong,
+    atlas_id: jlong,
 ) {
-    if device_ptr == 0 || texture_handle == 0 {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
         return;
     }
 
     let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    if let Err(e) = device.atlas_reset(atlas_id as u64) {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+    }
+}
 
-    if let Some(texture_id) = HANDLES.remove_texture(texture_handle as u64) {
-        device.destroy_texture(texture_id);
-        log::debug!("Destroyed texture with handle {}", texture_handle);
+/// Free the atlas sprite occupying exactly (x, y, width, height)
+///
+/// Returns true if a sprite occupied that rectangle and was evicted.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_atlasEvict0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    atlas_id: jlong,
+    x: jint,
+    y: jint,
+    width: jint,
+    height: jint,
+) -> jboolean {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let rect = atlas::AtlasRect {
+        x: x.max(0) as u32,
+        y: y.max(0) as u32,
+        width: width.max(0) as u32,
+        height: height.max(0) as u32,
+    };
+
+    match device.atlas_evict_rect(atlas_id as u64, rect) {
+        Ok(Some(id)) => {
+            log::debug!("Evicted atlas entry {} at {:?}", id, rect);
+            1
+        }
+        Ok(None) => 0,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            0
+        }
     }
 }
 
-/// Create a texture view
+/// Compact an atlas and move its sprites inside `texture_handle`
+///
+/// Returns flat `[entry_id, x, y, width, height]` tuples for every sprite
+/// whose position changed. Sprites that no longer fit are reported with
+/// x = y = -1 and must be re-uploaded by the caller.
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createTextureView(
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_atlasRepack0(
     mut env: JNIEnv,
     _class: JClass,
     device_ptr: jlong,
+    atlas_id: jlong,
     texture_handle: jlong,
-) -> jlong {
-    if device_ptr == 0 || texture_handle == 0 {
-        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
-        return 0;
+) -> jlongArray {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return std::ptr::null_mut();
     }
 
     let device = unsafe { &*(device_ptr as *const BasaltDevice) };
 
-    // Look up texture info from handle (including array layers)
     let texture_info = match HANDLES.get_texture_info(texture_handle as u64) {
         Some(info) => info,
         None => {
             let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid texture handle");
-            return 0;
+            return std::ptr::null_mut();
         }
     };
 
-    match device.create_texture_view(texture_info.id, texture_info.array_layers) {
-        Ok((view_id, dimension)) => {
-            let handle = HANDLES.insert_texture_view(view_id, dimension, texture_info.id);
-            // Register the view-to-texture mapping in context for reliable lookups
-            device.context().register_texture_view(view_id, texture_info.id);
-            log::info!("Created texture view: handle={} view_id={:?} texture_id={:?} dimension={:?} layers={}",
-                       handle, view_id, texture_info.id, dimension, texture_info.array_layers);
-            handle as jlong
+    let repack = match device.atlas_repack(atlas_id as u64, texture_info.id, texture_info.format) {
+        Ok(repack) => repack,
+        Err(e @ BasaltError::InvalidParameter { .. }) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            return std::ptr::null_mut();
         }
         Err(e) => {
-            let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to create texture view: {}", e));
-            0
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to repack atlas texture: {}", e));
+            return std::ptr::null_mut();
         }
+    };
+
+    let mut mappings: Vec<jlong> = Vec::with_capacity((repack.relocations.len() + repack.dropped.len()) * 5);
+    for r in &repack.relocations {
+        mappings.extend_from_slice(&[
+            r.id as jlong,
+            r.new.x as jlong,
+            r.new.y as jlong,
+            r.new.width as jlong,
+            r.new.height as jlong,
+        ]);
+    }
+    for &id in &repack.dropped {
+        mappings.extend_from_slice(&[id as jlong, -1, -1, 0, 0]);
     }
-}
 
-// ============================================================================
-// SAMPLER OPERATIONS
-// ============================================================================
+    new_jni_long_array(&mut env, &mappings).unwrap_or(std::ptr::null_mut())
+}
 
-/// Create a sampler
+/// Forget an atlas allocator
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createSampler(
-    mut env: JNIEnv,
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_atlasDestroy0(
+    _env: JNIEnv,
     _class: JClass,
     device_ptr: jlong,
-    address_mode_u: jint,
-    address_mode_v: jint,
-    address_mode_w: jint,
-    min_filter: jint,
-    mag_filter: jint,
-    mipmap_filter: jint,
-    lod_min_clamp: jfloat,
-    lod_max_clamp: jfloat,
-    max_anisotropy: jint,
-) -> jlong {
+    atlas_id: jlong,
+) {
     if device_ptr == 0 {
-        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
-        return 0;
+        return;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    device.atlas_destroy(atlas_id as u64);
+}
+
+/// Destroy a buffer
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_destroyBuffer(
+    _env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    buffer_handle: jlong,
+) {
+    if device_ptr == 0 || buffer_handle == 0 {
+        return;
     }
 
     let device = unsafe { &*(device_ptr as *const BasaltDevice) };
 
-    match device.create_sampler(
-        address_mode_u as u32,
-        address_mode_v as u32,
-        address_mode_w as u32,
-        min_filter as u32,
-        mag_filter as u32,
-        mipmap_filter as u32,
-        lod_min_clamp,
-        lod_max_clamp,
-        max_anisotropy as u32,
-    ) {
-        Ok(sampler_id) => {
-            let handle = HANDLES.insert_sampler(sampler_id);
-            log::debug!("Created sampler with handle {}", handle);
-            handle as jlong
-        }
-        Err(e) => {
-            let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to create sampler: {}", e));
-            0
-        }
+    // Look up and remove buffer ID from handle store
+    if let Some(buffer_id) = HANDLES.remove_buffer(buffer_handle as u64) {
+        device.destroy_buffer(buffer_id);
+        log::debug!("Destroyed buffer with handle {}", buffer_handle);
     }
 }
 
-/// Create vertex buffer layout based on format index
-fn create_vertex_buffer_layout(format_index: usize) -> Cow<'static, [wgpu_core::pipeline::VertexBufferLayout<'static>]> {
-    use std::borrow::Cow;
+// ============================================================================
+// TEXTURE OPERATIONS
+// ============================================================================
 
-    match format_index {
-        // 255 = EMPTY (no vertex input - shader uses @builtin(vertex_index))
-        // Used by shaders like rendertype_clouds that generate geometry procedurally
-        255 => Cow::Borrowed(&[]),
-        // 0 = POSITION (3 floats)
-        0 => Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
-            array_stride: 12, // 3 floats * 4 bytes
-            step_mode: wgt::VertexStepMode::Vertex,
-            attributes: Cow::Owned(vec![
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x3,
-                    offset: 0,
-                    shader_location: 0,
-                },
-            ]),
-        }]),
-        // 1 = POSITION_COLOR (3 floats + 4 unsigned bytes)
-        // GUI uses UBYTE colors, not float! Total stride = 16 bytes
-        1 => Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
-            array_stride: 16, // 12 + 4 = 16 bytes
-            step_mode: wgt::VertexStepMode::Vertex,
-            attributes: Cow::Owned(vec![
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x3,
-                    offset: 0,
-                    shader_location: 0,
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Unorm8x4,  // UBYTE colors!
-                    offset: 12,
-                    shader_location: 1,
-                },
-            ]),
-        }]),
-        // 2 = POSITION_TEX (3 floats + 2 floats)
-        2 => Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
-            array_stride: 20, // 12 + 8 = 20 bytes
-            step_mode: wgt::VertexStepMode::Vertex,
-            attributes: Cow::Owned(vec![
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x3,
-                    offset: 0,
-                    shader_location: 0,
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x2,
-                    offset: 12,
-                    shader_location: 1,
-                },
-            ]),
-        }]),
-        // 3 = POSITION_TEX_COLOR (3 floats + 2 floats + 4 unsigned bytes)
-        // Color is UBYTE (unsigned bytes), not float! Total stride = 24 bytes
-        3 => Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
-            array_stride: 24, // 12 + 8 + 4 = 24 bytes
-            step_mode: wgt::VertexStepMode::Vertex,
-            attributes: Cow::Owned(vec![
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x3,
-                    offset: 0,
-                    shader_location: 0,
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x2,
-                    offset: 12,
-                    shader_location: 1,
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Unorm8x4,  // UBYTE colors!
-                    offset: 20,
-                    shader_location: 2,
-                },
-            ]),
-        }]),
-        // 4 = POSITION_TEX_COLOR_NORMAL (3 floats + 2 floats + 4 unsigned bytes + 3 floats)
-        // Color is UBYTE (unsigned bytes), not float! Total stride = 36 bytes
-        4 => Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
-            array_stride: 36, // 12 + 8 + 4 + 12 = 36 bytes
-            step_mode: wgt::VertexStepMode::Vertex,
-            attributes: Cow::Owned(vec![
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x3,
-                    offset: 0,
-                    shader_location: 0,
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x2,
-                    offset: 12,
-                    shader_location: 1,
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Unorm8x4,  // UBYTE colors!
-                    offset: 20,
-                    shader_location: 2,
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x3,
-                    offset: 24,
-                    shader_location: 3,
-                },
-            ]),
-        }]),
-        // 5 = POSITION_TEX_COLOR (Position + UV0 + Color)
-        // Memory layout: Position[12] + UV0[8] + Color[4] = 24 bytes
-        // Color is UBYTE (unsigned bytes), not float!
-        5 => Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
-            array_stride: 24, // 12 + 8 + 4 = 24 bytes
-            step_mode: wgt::VertexStepMode::Vertex,
-            attributes: Cow::Owned(vec![
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x3,
-                    offset: 0,
-                    shader_location: 0, // position
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x2,
-                    offset: 12,
-                    shader_location: 1, // uv
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Unorm8x4,  // UBYTE colors!
-                    offset: 20,
-                    shader_location: 2, // color
-                },
-            ]),
-        }]),
-        // 6 = POSITION_COLOR_TEX_TEX_TEX_NORMAL (position, color, uv0, uv1, uv2, normal)
-        // Color is UBYTE (unsigned bytes), not float! Total stride = 52 bytes
-        6 => Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
-            array_stride: 52, // 12 + 4 + 8 + 8 + 8 + 12 = 52 bytes
-            step_mode: wgt::VertexStepMode::Vertex,
-            attributes: Cow::Owned(vec![
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x3,
-                    offset: 0,
-                    shader_location: 0, // position
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Unorm8x4,  // UBYTE colors!
-                    offset: 12,
-                    shader_location: 1, // color
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x2,
-                    offset: 16,
-                    shader_location: 2, // uv0
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x2,
-                    offset: 24,
-                    shader_location: 3, // uv1
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x2,
-                    offset: 32,
-                    shader_location: 4, // uv2
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x3,
-                    offset: 40,
-                    shader_location: 5, // normal
-                },
-            ]),
-        }]),
-        // 7 = POSITION_COLOR_TEX_TEX_NORMAL (position, color, uv0, uv2, normal - skips uv1)
-        // Color is UBYTE (Unorm8x4) which auto-converts to vec4<f32> in shader! Total stride = 44 bytes
-        7 => Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
-            array_stride: 44, // 12 + 4 + 8 + 8 + 12 = 44 bytes
-            step_mode: wgt::VertexStepMode::Vertex,
-            attributes: Cow::Owned(vec![
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x3,
-                    offset: 0,
-                    shader_location: 0, // position
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Unorm8x4,  // UBYTE colors (auto-converted to vec4<f32>)!
-                    offset: 12,
-                    shader_location: 1, // color
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x2,
-                    offset: 16,
-                    shader_location: 2, // uv0
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x2,
-                    offset: 24,
-                    shader_location: 3, // uv2
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x3,
-                    offset: 32,
-                    shader_location: 4, // normal
-                },
-            ]),
-        }]),
-        // 8 = POSITION_COLOR_TEX_TEX (position, color, uv0, uv2 - no normal)
-        // Color is UBYTE (Unorm8x4) which auto-converts to vec4<f32> in shader! Total stride = 32 bytes
-        8 => Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
-            array_stride: 32, // 12 + 4 + 8 + 8 = 32 bytes
-            step_mode: wgt::VertexStepMode::Vertex,
-            attributes: Cow::Owned(vec![
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x3,
-                    offset: 0,
-                    shader_location: 0, // position
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Unorm8x4,  // UBYTE colors (auto-converted to vec4<f32>)!
-                    offset: 12,
-                    shader_location: 1, // color
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x2,
-                    offset: 16,
-                    shader_location: 2, // uv0
-                },
-                wgt::VertexAttribute {
-                    format: wgt::VertexFormat::Float32x2,
-                    offset: 24,
-                    shader_location: 3, // uv2
-                },
-            ]),
-        }]),
-        // Default to POSITION_TEX_COLOR for unknown formats
+/// Create a texture
+///
+/// `dimension` is 0 for a 2D texture, whose `depth` is its layer count, or 1
+/// for a 3D texture `depth` texels deep. `is_cube` marks a 2D cubemap (6
+/// square layers) whose full views are `Cube`. `label` names the texture in
+/// GPU captures; null keeps the usage-derived label.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createTexture(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    width: jint,
+    height: jint,
+    depth: jint,
+    mip_levels: jint,
+    format: jint,
+    usage: jint,
+    sample_count: jint,
+    dimension: jint,
+    is_cube: jboolean,
+    label: JString,
+) -> jlong {
+    let dimension = match dimension {
+        0 => wgt::TextureDimension::D2,
+        1 => wgt::TextureDimension::D3,
         _ => {
-            log::warn!("Unknown vertex format index: {}, defaulting to POSITION_TEX_COLOR", format_index);
-            Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
-                array_stride: 36,
-                step_mode: wgt::VertexStepMode::Vertex,
-                attributes: Cow::Owned(vec![
-                    wgt::VertexAttribute {
-                        format: wgt::VertexFormat::Float32x3,
-                        offset: 0,
-                        shader_location: 0,
-                    },
-                    wgt::VertexAttribute {
-                        format: wgt::VertexFormat::Float32x2,
-                        offset: 12,
-                        shader_location: 1,
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Unknown texture dimension {}", dimension));
+            return 0;
+        }
+    };
+    let request = device::TextureRequest {
+        sample_count: sample_count.max(1) as u32,
+        dimension,
+        label: read_optional_string(&mut env, &label),
+        ..device::TextureRequest::new(
+            width as u32, height as u32, depth as u32, mip_levels as u32, format as u32, usage as u32,
+        )
+    };
+    create_texture_handle(&mut env, device_ptr, request, is_cube != 0, None)
+}
+
+/// Create a texture with explicit view formats
+///
+/// `view_formats` holds names from `getCompatibleViewFormats`; an empty array
+/// disallows any reinterpretation.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createTextureWithViewFormats0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    width: jint,
+    height: jint,
+    depth: jint,
+    mip_levels: jint,
+    format: jint,
+    usage: jint,
+    sample_count: jint,
+    view_formats: JObjectArray,
+) -> jlong {
+    let mut names = Vec::new();
+    if !view_formats.is_null() {
+        let len = env.get_array_length(&view_formats).unwrap_or(0);
+        for i in 0..len {
+            let name: Option<String> = env
+                .get_object_array_element(&view_formats, i)
+                .ok()
+                .filter(|obj| !obj.is_null())
+                .and_then(|obj| env.get_string(&obj.into()).ok().map(Into::into));
+            match name {
+                Some(name) => names.push(name),
+                None => {
+                    let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Null view format at index {}", i));
+                    return 0;
+                }
+            }
+        }
+    }
+
+    let request = device::TextureRequest {
+        sample_count: sample_count.max(1) as u32,
+        ..device::TextureRequest::new(
+            width as u32, height as u32, depth as u32, mip_levels as u32, format as u32, usage as u32,
+        )
+    };
+    create_texture_handle(&mut env, device_ptr, request, false, Some(names))
+}
+
+/// Create a texture and register its handle; `view_formats` are format names
+/// that replace the request's view formats
+fn create_texture_handle(
+    env: &mut JNIEnv,
+    device_ptr: jlong,
+    mut request: device::TextureRequest,
+    is_cube: bool,
+    view_formats: Option<Vec<String>>,
+) -> jlong {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let device::TextureRequest { width, height, depth, mip_levels, sample_count, dimension, .. } = request;
+
+    // Map format first so we can store it
+    let texture_format = match device.map_texture_format_public(request.format) {
+        Ok(f) => f,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Invalid texture format: {}", e));
+            return 0;
+        }
+    };
+
+    match view_formats.map(|names| texture::parse_view_formats(texture_format, &names)).transpose() {
+        Ok(Some(view_formats)) => request.view_formats = Some(view_formats),
+        Ok(None) => {}
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            return 0;
+        }
+    };
+
+    if is_cube {
+        if dimension != wgt::TextureDimension::D2 {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", "Only 2D textures can be cubemaps");
+            return 0;
+        }
+        if let Err(e) = texture::validate_cube(width, height, depth) {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            return 0;
+        }
+    }
+
+    match device.create_texture_from_request(request) {
+        Ok(texture_id) => {
+            // Store texture with array layer info and format for debugging
+            let handle = HANDLES.insert_texture(resource_handles::TextureInfo {
+                id: texture_id,
+                width,
+                height,
+                depth_or_array_layers: depth,
+                mip_level_count: BasaltDevice::texture_mip_level_count(
+                    dimension, width, height, depth, mip_levels, sample_count,
+                ),
+                dimension,
+                format: texture_format,
+                sample_count,
+                is_cube,
+            });
+            log::info!("Created {:?} texture: handle={} texture_id={:?} ({}x{}x{}) format={:?} samples={}", dimension, handle, texture_id, width, height, depth, texture_format, sample_count);
+            handle as jlong
+        }
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create texture: {}", e));
+            0
+        }
+    }
+}
+
+/// Formats a texture of `format` can be viewed as, its own format first
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getCompatibleViewFormats0<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    device_ptr: jlong,
+    format: jint,
+) -> JObjectArray<'local> {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return JObjectArray::default();
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let texture_format = match device.map_texture_format_public(format as u32) {
+        Ok(f) => f,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid texture format: {}", e));
+            return JObjectArray::default();
+        }
+    };
+
+    let formats = texture::compatible_view_formats(texture_format);
+    let array = match env.new_object_array(formats.len() as jint, "java/lang/String", JObject::null()) {
+        Ok(array) => array,
+        Err(_) => return JObjectArray::default(),
+    };
+    for (i, f) in formats.iter().enumerate() {
+        if let Ok(name) = env.new_string(format!("{:?}", f)) {
+            let _ = env.set_object_array_element(&array, i as jint, name);
+        }
+    }
+    array
+}
+
+/// Destroy a texture
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_destroyTexture(
+    _env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    texture_handle: jlong,
+) {
+    if device_ptr == 0 || texture_handle == 0 {
+        return;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    if let Some(texture_id) = HANDLES.remove_texture(texture_handle as u64) {
+        device.destroy_texture(texture_id);
+        log::debug!("Destroyed texture with handle {}", texture_handle);
+    }
+}
+
+/// Create a texture view
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createTextureView(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    texture_handle: jlong,
+) -> jlong {
+    if device_ptr == 0 || texture_handle == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    // Look up texture info from handle (including array layers)
+    let texture_info = match HANDLES.get_texture_info(texture_handle as u64) {
+        Some(info) => info,
+        None => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid texture handle");
+            return 0;
+        }
+    };
+
+    match device.create_texture_view(texture_info.id, texture_info.dimension, texture_info.depth_or_array_layers, texture_info.is_cube) {
+        Ok((view_id, dimension)) => {
+            let handle = HANDLES.insert_texture_view(resource_handles::TextureViewInfo {
+                id: view_id,
+                dimension,
+                texture_id: texture_info.id,
+                texture_layers: texture_info.array_layer_count(),
+                format: texture_info.format,
+                sample_count: texture_info.sample_count,
+                aspect: wgt::TextureAspect::All,
+                width: texture_info.width,
+                height: texture_info.height,
+            });
+            // Register the view-to-texture mapping in context for reliable lookups
+            device.context().register_texture_view(view_id, texture_info.id);
+            log::info!("Created texture view: handle={} view_id={:?} texture_id={:?} dimension={:?} layers={}",
+                       handle, view_id, texture_info.id, dimension, texture_info.depth_or_array_layers);
+            handle as jlong
+        }
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create texture view: {}", e));
+            0
+        }
+    }
+}
+
+/// Create a texture view of a mip/layer range, optionally with another format
+///
+/// Counts of 0 select every remaining mip or layer. `view_dimension` uses the
+/// `VIEW_DIMENSION_*` encoding, `format` the texture format encoding and
+/// `aspect` the `ASPECT_*` encoding; pass -1 to derive any of them from the texture.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createTextureViewRanged0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    texture_handle: jlong,
+    base_mip: jint,
+    mip_count: jint,
+    base_layer: jint,
+    layer_count: jint,
+    view_dimension: jint,
+    format: jint,
+    aspect: jint,
+) -> jlong {
+    if device_ptr == 0 || texture_handle == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
+        return 0;
+    }
+    if base_mip < 0 || mip_count < 0 || base_layer < 0 || layer_count < 0 {
+        let _ = env.throw_new(
+            "java/lang/IllegalArgumentException",
+            format!("Negative view range: mips {}+{}, layers {}+{}", base_mip, mip_count, base_layer, layer_count),
+        );
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    let texture_info = match HANDLES.get_texture_info(texture_handle as u64) {
+        Some(info) => info,
+        None => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid texture handle");
+            return 0;
+        }
+    };
+
+    let dimension = if view_dimension < 0 {
+        None
+    } else {
+        match map_view_dimension_from_jni(view_dimension) {
+            Some(d) => Some(d),
+            None => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Unknown view dimension {}", view_dimension));
+                return 0;
+            }
+        }
+    };
+    let view_format = if format < 0 {
+        None
+    } else {
+        match device.map_texture_format_public(format as u32) {
+            Ok(f) => Some(f),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+                return 0;
+            }
+        }
+    };
+
+    let view_aspect = match aspect {
+        -1 => None,
+        _ => match map_texture_aspect_from_jni(aspect) {
+            Some(aspect) => Some(aspect),
+            None => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Unknown texture aspect {}", aspect));
+                return 0;
+            }
+        },
+    };
+
+    let range = texture::ViewRangeRequest {
+        base_mip: base_mip as u32,
+        mip_count: mip_count as u32,
+        base_layer: base_layer as u32,
+        layer_count: layer_count as u32,
+        dimension,
+        format: view_format,
+        aspect: view_aspect,
+    };
+    match device.create_texture_view_ranged(&texture_info, range) {
+        Ok((view_id, dimension, aspect)) => {
+            let (width, height) = texture_info.mip_size(base_mip as u32);
+            let handle = HANDLES.insert_texture_view(resource_handles::TextureViewInfo {
+                id: view_id,
+                dimension,
+                texture_id: texture_info.id,
+                texture_layers: texture_info.array_layer_count(),
+                format: view_format.unwrap_or(texture_info.format),
+                sample_count: texture_info.sample_count,
+                aspect,
+                width,
+                height,
+            });
+            log::debug!("Created ranged texture view: handle={} view_id={:?} dimension={:?}", handle, view_id, dimension);
+            handle as jlong
+        }
+        Err(e @ BasaltError::InvalidParameter { .. }) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            0
+        }
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create texture view: {}", e));
+            0
+        }
+    }
+}
+
+// ============================================================================
+// SAMPLER OPERATIONS
+// ============================================================================
+
+/// Create a sampler
+///
+/// `label` names the sampler in GPU captures; null keeps "Basalt Sampler".
+/// Identical samplers are shared, so only the first label sticks.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createSampler(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    address_mode_u: jint,
+    address_mode_v: jint,
+    address_mode_w: jint,
+    min_filter: jint,
+    mag_filter: jint,
+    mipmap_filter: jint,
+    lod_min_clamp: jfloat,
+    lod_max_clamp: jfloat,
+    max_anisotropy: jint,
+    compare_function: jint,
+    label: JString,
+) -> jlong {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let request = device::SamplerRequest {
+        address_mode_u: address_mode_u as u32,
+        address_mode_v: address_mode_v as u32,
+        address_mode_w: address_mode_w as u32,
+        min_filter: min_filter as u32,
+        mag_filter: mag_filter as u32,
+        mipmap_filter: mipmap_filter as u32,
+        lod_min_clamp,
+        lod_max_clamp,
+        max_anisotropy: max_anisotropy as u32,
+        compare_function,
+        label: read_optional_string(&mut env, &label),
+    };
+
+    match device.create_sampler(request) {
+        Ok(sampler) => {
+            log::debug!("Created sampler with handle {}", sampler.handle);
+            sampler.handle as jlong
+        }
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create sampler: {}", e));
+            0
+        }
+    }
+}
+
+/// Create a comparison sampler for PCF shadow maps
+///
+/// Linear filtering, clamp-to-edge addressing and a `LessEqual` compare.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createShadowSampler0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+) -> jlong {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    match device.create_shadow_sampler() {
+        Ok(sampler) => {
+            log::debug!("Created shadow sampler with handle {}", sampler.handle);
+            sampler.handle as jlong
+        }
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create shadow sampler: {}", e));
+            0
+        }
+    }
+}
+
+/// Get the cached sampler for a filtering setup, returning its handle
+///
+/// `address_mode` uses the `createSampler` encoding and applies to all axes.
+/// Common setups exist from device creation, see `sampler::SamplerConfig`.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getSampler0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    address_mode: jint,
+    linear: jboolean,
+    linear_mipmaps: jboolean,
+    anisotropy: jint,
+) -> jlong {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    let config = device.map_address_mode(address_mode as u32).map(|address_mode| sampler::SamplerConfig {
+        address_mode,
+        linear: linear != 0,
+        linear_mipmaps: linear_mipmaps != 0,
+        anisotropy: anisotropy.clamp(0, u16::MAX as jint) as u16,
+    });
+    match config.and_then(|config| device.get_sampler(&config)) {
+        Ok(sampler) => sampler.handle as jlong,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Failed to get sampler: {}", e));
+            0
+        }
+    }
+}
+
+/// Get sampler cache statistics
+///
+/// Returns `[hits, misses, total_samplers]`, see `sampler::SamplerCacheStats`.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getSamplerCacheStats0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+) -> jlongArray {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return std::ptr::null_mut();
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let stats = device.sampler_cache.stats();
+    let values = [stats.hits as jlong, stats.misses as jlong, stats.total_samplers as jlong];
+
+    match env.new_long_array(values.len() as i32) {
+        Ok(array) => {
+            if env.set_long_array_region(&array, 0, &values).is_err() {
+                return std::ptr::null_mut();
+            }
+            array.into_raw()
+        }
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Get bind group cache statistics
+///
+/// Returns `[hits, misses, invalidated, total_bind_groups]`, see `bind_group::BindGroupCacheStats`.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getBindGroupCacheStats0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+) -> jlongArray {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return std::ptr::null_mut();
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let stats = device.bind_group_cache.stats();
+    let values = [
+        stats.hits as jlong,
+        stats.misses as jlong,
+        stats.invalidated as jlong,
+        stats.total_bind_groups as jlong,
+    ];
+
+    match env.new_long_array(values.len() as i32) {
+        Ok(array) => {
+            if env.set_long_array_region(&array, 0, &values).is_err() {
+                return std::ptr::null_mut();
+            }
+            array.into_raw()
+        }
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Get the number of live resource handles of each type
+///
+/// Returns `[buffers, textures, textureViews, samplers, bindGroups, bindGroupLayouts,
+/// renderPipelines, commandEncoders, renderBundles]`, see `resource_handles::ResourceCounts`.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getResourceCounts0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+) -> jlongArray {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return std::ptr::null_mut();
+    }
+
+    let counts = HANDLES.counts();
+    let values = [
+        counts.buffers as jlong,
+        counts.textures as jlong,
+        counts.texture_views as jlong,
+        counts.samplers as jlong,
+        counts.bind_groups as jlong,
+        counts.bind_group_layouts as jlong,
+        counts.render_pipelines as jlong,
+        counts.command_encoders as jlong,
+        counts.render_bundles as jlong,
+    ];
+
+    match env.new_long_array(values.len() as i32) {
+        Ok(array) => {
+            if env.set_long_array_region(&array, 0, &values).is_err() {
+                return std::ptr::null_mut();
+            }
+            array.into_raw()
+        }
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Create vertex buffer layout based on format index
+fn create_vertex_buffer_layout(format_index: usize) -> Cow<'static, [wgpu_core::pipeline::VertexBufferLayout<'static>]> {
+    use std::borrow::Cow;
+
+    match format_index {
+        // 255 = EMPTY (no vertex input - shader uses @builtin(vertex_index))
+        // Used by shaders like rendertype_clouds that generate geometry procedurally
+        255 => Cow::Borrowed(&[]),
+        // 0 = POSITION (3 floats)
+        0 => Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
+            array_stride: 12, // 3 floats * 4 bytes
+            step_mode: wgt::VertexStepMode::Vertex,
+            attributes: Cow::Owned(vec![
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x3,
+                    offset: 0,
+                    shader_location: 0,
+                },
+            ]),
+        }]),
+        // 1 = POSITION_COLOR (3 floats + 4 unsigned bytes)
+        // GUI uses UBYTE colors, not float! Total stride = 16 bytes
+        1 => Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
+            array_stride: 16, // 12 + 4 = 16 bytes
+            step_mode: wgt::VertexStepMode::Vertex,
+            attributes: Cow::Owned(vec![
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x3,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Unorm8x4,  // UBYTE colors!
+                    offset: 12,
+                    shader_location: 1,
+                },
+            ]),
+        }]),
+        // 2 = POSITION_TEX (3 floats + 2 floats)
+        2 => Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
+            array_stride: 20, // 12 + 8 = 20 bytes
+            step_mode: wgt::VertexStepMode::Vertex,
+            attributes: Cow::Owned(vec![
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x3,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x2,
+                    offset: 12,
+                    shader_location: 1,
+                },
+            ]),
+        }]),
+        // 3 = POSITION_TEX_COLOR (3 floats + 2 floats + 4 unsigned bytes)
+        // Color is UBYTE (unsigned bytes), not float! Total stride = 24 bytes
+        3 => Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
+            array_stride: 24, // 12 + 8 + 4 = 24 bytes
+            step_mode: wgt::VertexStepMode::Vertex,
+            attributes: Cow::Owned(vec![
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x3,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x2,
+                    offset: 12,
+                    shader_location: 1,
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Unorm8x4,  // UBYTE colors!
+                    offset: 20,
+                    shader_location: 2,
+                },
+            ]),
+        }]),
+        // 4 = POSITION_TEX_COLOR_NORMAL (3 floats + 2 floats + 4 unsigned bytes + 3 floats)
+        // Color is UBYTE (unsigned bytes), not float! Total stride = 36 bytes
+        4 => Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
+            array_stride: 36, // 12 + 8 + 4 + 12 = 36 bytes
+            step_mode: wgt::VertexStepMode::Vertex,
+            attributes: Cow::Owned(vec![
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x3,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x2,
+                    offset: 12,
+                    shader_location: 1,
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Unorm8x4,  // UBYTE colors!
+                    offset: 20,
+                    shader_location: 2,
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x3,
+                    offset: 24,
+                    shader_location: 3,
+                },
+            ]),
+        }]),
+        // 5 = POSITION_TEX_COLOR (Position + UV0 + Color)
+        // Memory layout: Position[12] + UV0[8] + Color[4] = 24 bytes
+        // Color is UBYTE (unsigned bytes), not float!
+        5 => Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
+            array_stride: 24, // 12 + 8 + 4 = 24 bytes
+            step_mode: wgt::VertexStepMode::Vertex,
+            attributes: Cow::Owned(vec![
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x3,
+                    offset: 0,
+                    shader_location: 0, // position
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x2,
+                    offset: 12,
+                    shader_location: 1, // uv
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Unorm8x4,  // UBYTE colors!
+                    offset: 20,
+                    shader_location: 2, // color
+                },
+            ]),
+        }]),
+        // 6 = POSITION_COLOR_TEX_TEX_TEX_NORMAL (position, color, uv0, uv1, uv2, normal)
+        // Color is UBYTE (unsigned bytes), not float! Total stride = 52 bytes
+        6 => Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
+            array_stride: 52, // 12 + 4 + 8 + 8 + 8 + 12 = 52 bytes
+            step_mode: wgt::VertexStepMode::Vertex,
+            attributes: Cow::Owned(vec![
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x3,
+                    offset: 0,
+                    shader_location: 0, // position
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Unorm8x4,  // UBYTE colors!
+                    offset: 12,
+                    shader_location: 1, // color
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x2,
+                    offset: 16,
+                    shader_location: 2, // uv0
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x2,
+                    offset: 24,
+                    shader_location: 3, // uv1
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x2,
+                    offset: 32,
+                    shader_location: 4, // uv2
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x3,
+                    offset: 40,
+                    shader_location: 5, // normal
+                },
+            ]),
+        }]),
+        // 7 = POSITION_COLOR_TEX_TEX_NORMAL (position, color, uv0, uv2, normal - skips uv1)
+        // Color is UBYTE (Unorm8x4) which auto-converts to vec4<f32> in shader! Total stride = 44 bytes
+        7 => Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
+            array_stride: 44, // 12 + 4 + 8 + 8 + 12 = 44 bytes
+            step_mode: wgt::VertexStepMode::Vertex,
+            attributes: Cow::Owned(vec![
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x3,
+                    offset: 0,
+                    shader_location: 0, // position
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Unorm8x4,  // UBYTE colors (auto-converted to vec4<f32>)!
+                    offset: 12,
+                    shader_location: 1, // color
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x2,
+                    offset: 16,
+                    shader_location: 2, // uv0
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x2,
+                    offset: 24,
+                    shader_location: 3, // uv2
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x3,
+                    offset: 32,
+                    shader_location: 4, // normal
+                },
+            ]),
+        }]),
+        // 8 = POSITION_COLOR_TEX_TEX (position, color, uv0, uv2 - no normal)
+        // Color is UBYTE (Unorm8x4) which auto-converts to vec4<f32> in shader! Total stride = 32 bytes
+        8 => Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
+            array_stride: 32, // 12 + 4 + 8 + 8 = 32 bytes
+            step_mode: wgt::VertexStepMode::Vertex,
+            attributes: Cow::Owned(vec![
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x3,
+                    offset: 0,
+                    shader_location: 0, // position
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Unorm8x4,  // UBYTE colors (auto-converted to vec4<f32>)!
+                    offset: 12,
+                    shader_location: 1, // color
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x2,
+                    offset: 16,
+                    shader_location: 2, // uv0
+                },
+                wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x2,
+                    offset: 24,
+                    shader_location: 3, // uv2
+                },
+            ]),
+        }]),
+        // Default to POSITION_TEX_COLOR for unknown formats
+        _ => {
+            log::warn!("Unknown vertex format index: {}, defaulting to POSITION_TEX_COLOR", format_index);
+            Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
+                array_stride: 36,
+                step_mode: wgt::VertexStepMode::Vertex,
+                attributes: Cow::Owned(vec![
+                    wgt::VertexAttribute {
+                        format: wgt::VertexFormat::Float32x3,
+                        offset: 0,
+                        shader_location: 0,
+                    },
+                    wgt::VertexAttribute {
+                        format: wgt::VertexFormat::Float32x2,
+                        offset: 12,
+                        shader_location: 1,
                     },
                     wgt::VertexAttribute {
                         format: wgt::VertexFormat::Float32x4,
@@ -1042,948 +2808,2941 @@ fn create_vertex_buffer_layout(format_index: usize) -> Cow<'static, [wgpu_core::
     }
 }
 
-/// Detect if a fragment shader writes to the depth buffer by checking for FragDepth output.
-/// This is used to determine if a pipeline needs depth_stencil state.
-fn shader_writes_depth(fragment_module: &naga::Module) -> bool {
-    for entry_point in &fragment_module.entry_points {
-        if entry_point.stage != naga::ShaderStage::Fragment {
-            continue;
+/// Detect if a fragment shader writes to the depth buffer by checking for FragDepth output.
+/// This is used to determine if a pipeline needs depth_stencil state.
+fn shader_writes_depth(fragment_module: &naga::Module) -> bool {
+    for entry_point in &fragment_module.entry_points {
+        if entry_point.stage != naga::ShaderStage::Fragment {
+            continue;
+        }
+        
+        // Check if the entry point has early_depth_test set
+        if entry_point.early_depth_test.is_some() {
+            return true;
+        }
+        
+        // Check function result for FragDepth builtin
+        if let Some(ref result) = entry_point.function.result {
+            // Direct binding check
+            if let Some(naga::Binding::BuiltIn(naga::BuiltIn::FragDepth)) = &result.binding {
+                return true;
+            }
+            
+            // Check if result is a struct with FragDepth member
+            let ty = &fragment_module.types[result.ty];
+            if let naga::TypeInner::Struct { members, .. } = &ty.inner {
+                for member in members {
+                    if let Some(naga::Binding::BuiltIn(naga::BuiltIn::FragDepth)) = &member.binding {
+                        return true;
+                    }
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Layout entry, binding kind, minimum size and variable name of one shader binding
+type ReflectedBinding = (wgt::BindGroupLayoutEntry, resource_handles::BindingLayoutType, Option<u64>, Option<String>);
+
+/// Layouts reflected from a shader pair, see `create_layout_from_shaders`
+type ShaderLayouts = (
+    wgpu_core::id::BindGroupLayoutId,
+    wgpu_core::id::PipelineLayoutId,
+    Vec<resource_handles::BindingLayoutEntry>,
+    Vec<pipeline_registry::ReflectedGroupLayout>,
+);
+
+/// Helper function to create a bind group layout from shader reflection
+///
+/// Group 0 is the bind group `createBindGroup0` fills by name. Bindings in
+/// higher groups get their own layouts, which become explicit layout handles
+/// for `createBindGroupExplicit0` once the pipeline is known (see
+/// `PipelineCache::extra_group_layouts`). A binding the layout
+/// can't express, or one declared differently by the two stages, is an error
+/// instead of being left out.
+///
+/// Shaders whose immediates were lowered to a uniform (see
+/// `shader::lower_immediates_to_uniform`) also get `immediates_fallback_layout`
+/// at group 1, which is then reserved; others declare an immediate range of at
+/// most `max_immediate_size`.
+/// Returns (BindGroupLayoutId, PipelineLayoutId, binding_layouts, extra_groups)
+fn create_layout_from_shaders(
+    context: &Arc<BasaltContext>,
+    device_id: wgpu_core::id::DeviceId,
+    vertex_module: &naga::Module,
+    fragment_module: &naga::Module,
+    max_immediate_size: u32,
+    immediates_fallback_layout: Option<wgpu_core::id::BindGroupLayoutId>,
+) -> Result<ShaderLayouts, BasaltError> {
+    use std::collections::BTreeMap;
+    use std::borrow::Cow;
+    use std::num::NonZeroU64;
+    use wgpu_core::binding_model;
+    use resource_handles::{BindingLayoutEntry, BindingLayoutType};
+    use naga::proc::{Layouter, GlobalCtx};
+
+    // Create layouters for both modules to calculate type sizes
+    let mut vertex_layouter = Layouter::default();
+    let mut fragment_layouter = Layouter::default();
+
+    // Update layouters with module types
+    let vertex_gctx = GlobalCtx {
+        types: &vertex_module.types,
+        constants: &vertex_module.constants,
+        overrides: &vertex_module.overrides,
+        global_expressions: &vertex_module.global_expressions,
+    };
+    let fragment_gctx = GlobalCtx {
+        types: &fragment_module.types,
+        constants: &fragment_module.constants,
+        overrides: &fragment_module.overrides,
+        global_expressions: &fragment_module.global_expressions,
+    };
+
+    if let Err(e) = vertex_layouter.update(vertex_gctx) {
+        log::warn!("Failed to calculate vertex shader layouts: {:?}", e);
+    }
+    if let Err(e) = fragment_layouter.update(fragment_gctx) {
+        log::warn!("Failed to calculate fragment shader layouts: {:?}", e);
+    }
+
+    // With the fallback, group 1 holds the lowered immediates uniform, checked separately below
+    let fallback_group = immediates_fallback_layout.map(|_| shader::IMMEDIATES_FALLBACK_GROUP);
+
+    // Collect bindings from every group
+    // Key: (group, binding), Value: (wgpu entry, our layout type, min_binding_size, variable name)
+    let mut bindings: BTreeMap<(u32, u32), ReflectedBinding> = BTreeMap::new();
+    // Declared bindings the layout can't cover, reported together
+    let mut unsupported: Vec<String> = Vec::new();
+
+    // Helper to extract bindings from a module
+    let mut extract_bindings = |module: &naga::Module, layouter: &Layouter, stage: wgt::ShaderStages| {
+        log::info!("extract_bindings: processing {:?} shader, {} global variables", stage, module.global_variables.len());
+        for (_handle, global_var) in module.global_variables.iter() {
+            if let Some(binding) = &global_var.binding {
+                if Some(binding.group) == fallback_group {
+                    continue;
+                }
+                let describe = |reason: &str| format!("@group({}) @binding({}) {:?} in the {:?} shader: {}",
+                    binding.group, binding.binding, global_var.name.as_deref().unwrap_or("?"), stage, reason);
+                log::info!("  Found binding {} at group {:?}, name: {:?}, space: {:?}",
+                    binding.binding, binding.group, global_var.name, global_var.space);
+                {
+                    let ty = &module.types[global_var.ty];
+
+                    // Get the variable name from the shader
+                    let var_name = global_var.name.clone();
+
+                    let (binding_type, layout_type, min_size, binding_var_name) = match global_var.space {
+                        naga::AddressSpace::Uniform => {
+                            // Calculate the actual size of the uniform buffer struct
+                            let type_layout = layouter[global_var.ty];
+                            let struct_size = type_layout.to_stride() as u64; // Use stride for proper alignment
+
+                            log::debug!("Uniform buffer at binding {}: size = {} bytes, alignment = {}",
+                                       binding.binding, struct_size, type_layout.alignment);
+
+                            let min_binding_size = NonZeroU64::new(struct_size);
+
+                            (wgt::BindingType::Buffer {
+                                ty: wgt::BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size,
+                            }, BindingLayoutType::UniformBuffer, Some(struct_size), var_name.clone())
+                        }
+                        naga::AddressSpace::Storage { access } => {
+                            // Storage buffer (like wgpu-mc uses for uniforms/projection)
+                            let type_layout = layouter[global_var.ty];
+                            let struct_size = type_layout.to_stride() as u64;
+                            let read_only = !access.contains(naga::StorageAccess::STORE);
+
+                            log::debug!("Storage buffer at binding {}: size = {} bytes, read_only = {}",
+                                       binding.binding, struct_size, read_only);
+
+                            let min_binding_size = NonZeroU64::new(struct_size);
+
+                            (wgt::BindingType::Buffer {
+                                ty: wgt::BufferBindingType::Storage { read_only },
+                                has_dynamic_offset: false,
+                                min_binding_size,
+                            }, BindingLayoutType::StorageBuffer { read_only }, Some(struct_size), var_name.clone())
+                        }
+                        naga::AddressSpace::Handle => {
+                            // Check if it's a texture or sampler
+                            match &ty.inner {
+                                naga::TypeInner::Image { class: naga::ImageClass::Storage { .. }, .. } => {
+                                    unsupported.push(describe("storage textures are not supported"));
+                                    continue;
+                                }
+                                naga::TypeInner::Image { dim, arrayed, class } => {
+                                    // Convert naga dimension to wgpu dimension
+                                    let view_dimension = match (dim, arrayed) {
+                                        (naga::ImageDimension::D1, false) => wgt::TextureViewDimension::D1,
+                                        (naga::ImageDimension::D2, false) => wgt::TextureViewDimension::D2,
+                                        (naga::ImageDimension::D2, true) => wgt::TextureViewDimension::D2Array,
+                                        (naga::ImageDimension::D3, _) => wgt::TextureViewDimension::D3,
+                                        (naga::ImageDimension::Cube, false) => wgt::TextureViewDimension::Cube,
+                                        (naga::ImageDimension::Cube, true) => wgt::TextureViewDimension::CubeArray,
+                                        _ => wgt::TextureViewDimension::D2, // Default fallback
+                                    };
+                                    log::info!("Found texture at binding {}: dimension {:?} (naga dim={:?}, arrayed={}), name={:?})", binding.binding, view_dimension, dim, arrayed, var_name);
+                                    // Depth textures (texture_depth_*) are sampled with comparison samplers;
+                                    // texture_2d<u32> also covers the stencil aspect of depth-stencil textures
+                                    let sample_type = match class {
+                                        naga::ImageClass::Depth { .. } => wgt::TextureSampleType::Depth,
+                                        naga::ImageClass::Sampled { kind: naga::ScalarKind::Uint, .. } => wgt::TextureSampleType::Uint,
+                                        naga::ImageClass::Sampled { kind: naga::ScalarKind::Sint, .. } => wgt::TextureSampleType::Sint,
+                                        _ => wgt::TextureSampleType::Float { filterable: true },
+                                    };
+                                    (wgt::BindingType::Texture {
+                                        sample_type,
+                                        view_dimension,
+                                        multisampled: false,
+                                    }, BindingLayoutType::Texture, None, var_name.clone())
+                                }
+                                naga::TypeInner::Sampler { comparison } => {
+                                    log::info!("Found sampler at binding {}: comparison={}, name={:?}", binding.binding, comparison, var_name);
+                                    // sampler_comparison must be bound to a Comparison layout entry,
+                                    // otherwise pipeline creation fails validation
+                                    let sampler_type = if *comparison {
+                                        wgt::SamplerBindingType::Comparison
+                                    } else {
+                                        wgt::SamplerBindingType::Filtering
+                                    };
+                                    (wgt::BindingType::Sampler(sampler_type),
+                                     BindingLayoutType::Sampler, None, var_name.clone())
+                                }
+                                other => {
+                                    unsupported.push(describe(&format!("unsupported resource type {:?}", other)));
+                                    continue;
+                                }
+                            }
+                        }
+                        other => {
+                            unsupported.push(describe(&format!("unsupported address space {:?}", other)));
+                            continue;
+                        }
+                    };
+
+                    // Always use VERTEX | FRAGMENT for maximum compatibility
+                    // (even if shader only uses it in one stage), writable storage aside
+                    let visibility = bind_group::binding_visibility(layout_type);
+
+                    bindings.entry((binding.group, binding.binding))
+                        .and_modify(|(e, ty, min_sz, name)| {
+                            // Buffers may disagree on min_binding_size; the larger one is kept below
+                            let same_type = *ty == layout_type
+                                && (matches!(e.ty, wgt::BindingType::Buffer { .. }) || e.ty == binding_type);
+                            if !same_type {
+                                unsupported.push(describe(&format!("declared as {:?} by the other stage", e.ty)));
+                            }
+                            e.visibility |= visibility;
+                            // Keep the larger min_binding_size if both shaders define it
+                            if let Some(new_size) = min_size {
+                                *min_sz = Some(min_sz.map_or(new_size, |old| old.max(new_size)));
+                            }
+                            // Prefer non-None variable name
+                            if name.is_none() && var_name.is_some() {
+                                *name = var_name.clone();
+                            }
+                        })
+                        .or_insert((wgt::BindGroupLayoutEntry {
+                            binding: binding.binding,
+                            visibility,
+                            ty: binding_type,
+                            count: None,
+                        }, layout_type, min_size, binding_var_name));
+                }
+            }
+        }
+    };
+
+    // Extract bindings from both shaders
+    extract_bindings(vertex_module, &vertex_layouter, wgt::ShaderStages::VERTEX);
+    extract_bindings(fragment_module, &fragment_layouter, wgt::ShaderStages::FRAGMENT);
+
+    // Immediates: a range sized to the largest block, or the lowered uniform at group 1
+    let mut shader_immediate_size = 0;
+    let mut uses_immediates_fallback = false;
+    for (module, layouter) in [(vertex_module, &vertex_layouter), (fragment_module, &fragment_layouter)] {
+        for (_handle, global_var) in module.global_variables.iter() {
+            if global_var.space == naga::AddressSpace::Immediate {
+                shader_immediate_size = shader_immediate_size.max(layouter[global_var.ty].size);
+            }
+            match &global_var.binding {
+                Some(b) if Some(b.group) == fallback_group => {
+                    if b.binding == 0 && global_var.space == naga::AddressSpace::Uniform {
+                        uses_immediates_fallback = true;
+                    } else {
+                        unsupported.push(format!(
+                            "@group({}) @binding({}) {:?}: group {} is reserved for immediates on devices without push constants",
+                            b.group, b.binding, global_var.name.as_deref().unwrap_or("?"), b.group));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    let immediate_size = pipeline_registry::pipeline_immediate_size(shader_immediate_size, max_immediate_size)?;
+
+    if !unsupported.is_empty() {
+        return Err(BasaltError::PipelineLayout(format!(
+            "shader bindings not covered by the pipeline layout: {}",
+            unsupported.join("; ")
+        )));
+    }
+
+    // Log final bindings after merging
+    log::info!("Final merged bindings: {} entries", bindings.len());
+    for ((group, binding), (_entry, ty, _min_size, var_name)) in &bindings {
+        log::info!("  Group {} binding {}: {:?}, name: {:?}", group, binding, ty, var_name);
+    }
+
+    let global = context.inner();
+
+    // One layout per group; groups the shader skips get an empty one
+    let create_group_layout = |group: u32| -> Result<(wgpu_core::id::BindGroupLayoutId, Vec<BindingLayoutEntry>), BasaltError> {
+        let group_bindings: Vec<_> = bindings.range((group, 0)..=(group, u32::MAX)).collect();
+        let layout_entries: Vec<wgt::BindGroupLayoutEntry> = group_bindings.iter()
+            .map(|(_, (e, _, _, _))| *e)
+            .collect();
+
+        let label = format!("Pipeline Bind Group Layout (group {})", group);
+        let bgl_desc = binding_model::BindGroupLayoutDescriptor {
+            label: Some(Cow::Owned(label)),
+            entries: Cow::Owned(layout_entries),
+        };
+
+        let (bgl_id, bgl_error) = global.device_create_bind_group_layout(device_id, &bgl_desc, None);
+
+        if let Some(e) = bgl_error {
+            return Err(BasaltError::resource_creation(
+                "bind group layout",
+                format!("Failed to create bind group layout for group {}: {:?}", group, e)
+            ));
+        }
+
+        log::debug!("Created bind group layout for group {}: {:?}", group, bgl_id);
+
+        let binding_layouts = group_bindings.iter()
+            .map(|((_, binding), (entry, ty, min_size, var_name))| {
+                let expected_dimension = if let wgt::BindingType::Texture { view_dimension, .. } = entry.ty {
+                    Some(view_dimension)
+                } else {
+                    None
+                };
+                let comparison = matches!(entry.ty,
+                    wgt::BindingType::Texture { sample_type: wgt::TextureSampleType::Depth, .. }
+                    | wgt::BindingType::Sampler(wgt::SamplerBindingType::Comparison));
+                BindingLayoutEntry {
+                    binding: *binding,
+                    ty: *ty,
+                    min_binding_size: *min_size,
+                    has_dynamic_offset: false,
+                    expected_dimension,
+                    comparison,
+                    variable_name: var_name.clone(),
+                }
+            })
+            .collect();
+        Ok((bgl_id, binding_layouts))
+    };
+
+    let (bgl_id, binding_layouts) = create_group_layout(0)?;
+
+    log::info!("Creating pipeline layout with {} group 0 bindings:", binding_layouts.len());
+    for (i, layout) in binding_layouts.iter().enumerate() {
+        log::info!("  [{}] binding={}, ty={:?}, var_name={:?}",
+            i, layout.binding, layout.ty, layout.variable_name);
+    }
+
+    // Group 0, the immediates fallback if used, then the shader's other groups in order
+    let mut last_group = bindings.keys().map(|(group, _)| *group).max().unwrap_or(0);
+    if uses_immediates_fallback {
+        last_group = last_group.max(shader::IMMEDIATES_FALLBACK_GROUP);
+    }
+    let mut bind_group_layouts = vec![bgl_id];
+    let mut extra_groups = Vec::new();
+    for group in 1..=last_group {
+        match immediates_fallback_layout {
+            Some(fallback_bgl_id) if Some(group) == fallback_group => bind_group_layouts.push(fallback_bgl_id),
+            _ => {
+                let (layout_id, entries) = create_group_layout(group)?;
+                bind_group_layouts.push(layout_id);
+                extra_groups.push(pipeline_registry::ReflectedGroupLayout { group, layout_id, entries });
+            }
+        }
+    }
+
+    let pl_desc = binding_model::PipelineLayoutDescriptor {
+        label: Some(Cow::Borrowed("Pipeline Layout")),
+        bind_group_layouts: Cow::Owned(bind_group_layouts),
+        immediate_size,
+    };
+
+    let (pl_id, pl_error) = global.device_create_pipeline_layout(device_id, &pl_desc, None);
+
+    if let Some(e) = pl_error {
+        return Err(BasaltError::resource_creation(
+            "pipeline layout",
+            format!("Failed to create pipeline layout: {:?}", e)
+        ));
+    }
+
+    Ok((bgl_id, pl_id, binding_layouts, extra_groups))
+}
+
+/// Throw a `ShaderCompilationException` carrying the parse location of `error`
+///
+/// Falls back to line/column -1 for errors that carry no location.
+fn throw_shader_compilation_exception(env: &mut JNIEnv, stage: &str, error: &BasaltError) {
+    let (shader_name, line, column, source_line) = match error {
+        BasaltError::ShaderParse { shader_name, line, column, source_line, .. } => (
+            shader_name.as_str(),
+            line.map_or(-1, |l| l as jint),
+            column.map_or(-1, |c| c as jint),
+            source_line.as_deref(),
+        ),
+        _ => ("unknown", -1, -1, None),
+    };
+
+    let message = format!("Failed to parse {} WGSL: {}", stage, error);
+    log::error!("{}", message);
+
+    let exception = (|| -> ::jni::errors::Result<JObject> {
+        let message = env.new_string(&message)?;
+        let shader_name = env.new_string(shader_name)?;
+        let stage = env.new_string(stage)?;
+        let source_line = match source_line {
+            Some(s) => JObject::from(env.new_string(s)?),
+            None => JObject::null(),
+        };
+        env.new_object(
+            "com/criticalrange/bassalt/shader/ShaderCompilationException",
+            "(Ljava/lang/String;Ljava/lang/String;Ljava/lang/String;IILjava/lang/String;)V",
+            &[
+                (&message).into(),
+                (&shader_name).into(),
+                (&stage).into(),
+                line.into(),
+                column.into(),
+                (&source_line).into(),
+            ],
+        )
+    })();
+
+    match exception {
+        Ok(exception) => {
+            let _ = env.throw(::jni::objects::JThrowable::from(exception));
+        }
+        Err(_) => {
+            // Exception class unavailable, still surface the formatted message
+            let _ = env.exception_clear();
+            let _ = env.throw_new("java/lang/RuntimeException", &message);
+        }
+    }
+}
+
+/// Throw a `GpuSubmitException` whose kind tells Java why a submit failed
+///
+/// Marks the device lost first so `isDeviceLost` is already true when the
+/// exception reaches Java.
+fn throw_submit_exception(env: &mut JNIEnv, device: &BasaltDevice, error: &BasaltError) {
+    // Kind ordinals match GpuSubmitException.Kind
+    let kind: jint = match error {
+        BasaltError::DeviceLost { .. } => {
+            device.mark_lost();
+            0
+        }
+        BasaltError::OutOfMemory { .. } => 1,
+        BasaltError::Validation(_) => 2,
+        _ => 3,
+    };
+
+    let message = error.to_string();
+    log::error!("{}", message);
+
+    let exception = (|| -> ::jni::errors::Result<JObject> {
+        let message = env.new_string(&message)?;
+        env.new_object(
+            "com/criticalrange/bassalt/backend/GpuSubmitException",
+            "(Ljava/lang/String;I)V",
+            &[(&message).into(), kind.into()],
+        )
+    })();
+
+    match exception {
+        Ok(exception) => {
+            let _ = env.throw(::jni::objects::JThrowable::from(exception));
+        }
+        Err(_) => {
+            let _ = env.exception_clear();
+            let _ = env.throw_new("java/lang/IllegalStateException", &message);
+        }
+    }
+}
+
+/// Report an error from a call that skips the bad input and carries on
+///
+/// Logs it and stores it as this thread's last error for `getLastError0`.
+/// In strict mode validation errors throw `BassaltValidationError` instead.
+fn report_recoverable_error(env: &mut JNIEnv, error: BasaltError) {
+    if let Err(error) = error.log_or_escalate() {
+        let message = error.to_string();
+        log::error!("Strict validation: {}", message);
+        if env.throw_new("com/criticalrange/bassalt/backend/BassaltValidationError", &message).is_err() {
+            let _ = env.exception_clear();
+            let _ = env.throw_new("java/lang/Error", &message);
+        }
+    }
+}
+
+/// Error for a per-draw call made with a null render pass pointer
+fn null_render_pass(call: &str) -> BasaltError {
+    BasaltError::NullPointer { context: format!("{}: render pass", call) }
+}
+
+/// Pipeline state shared by every pipeline creator
+///
+/// Creators add their vertex layout and, for MRT, explicit color targets; the
+/// rest comes from the packed state array `BassaltDevice.pipelineState` builds.
+struct NativePipelineOptions {
+    primitive_topology: jint,
+    index_format: Option<wgt::IndexFormat>,
+    polygon_mode: u32,
+    depth_test_enabled: bool,
+    depth_write_enabled: bool,
+    depth_compare: jint,
+    blend_enabled: bool,
+    /// Source color, destination color, source alpha and destination alpha factors
+    blend_factors: [jint; 4],
+    sample_count: u32,
+    stencil: wgt::StencilState,
+    depth_format_override: Option<resource_handles::PipelineDepthFormat>,
+    color_targets: Option<Vec<wgt::ColorTargetState>>,
+    target_format: Option<wgt::TextureFormat>,
+    shader_name: String,
+    background: bool,
+    override_constants: pipeline_registry::OverrideConstants,
+}
+
+impl NativePipelineOptions {
+    /// Unpack the pipeline state array and the trailing arguments every creator takes
+    ///
+    /// `state` holds, in order: topology, index format (0 = Uint16, 1 = Uint32,
+    /// -1 = unknown), polygon mode (0 = Fill, 1 = Line, 2 = Point), depth test,
+    /// depth write, depth compare, blend enabled, the four blend factors, sample
+    /// count, target format (`FORMAT_*`, -1 = swapchain), depth format
+    /// (`FORMAT_DEPTH*`, -1 = picked from the depth and stencil state), the
+    /// stencil read and write masks, then the front and back stencil faces
+    /// (compare, fail, depth fail, pass).
+    ///
+    /// `constant_names` / `constant_values` are parallel arrays specializing the
+    /// shaders' WGSL `override` declarations; either may be null when there are none.
+    fn from_jni(
+        env: &mut JNIEnv,
+        device: &BasaltDevice,
+        state: JObject,
+        shader_name: &JString,
+        background: jboolean,
+        constant_names: &JObjectArray,
+        constant_values: &JDoubleArray,
+    ) -> std::result::Result<Self, String> {
+        let shader_name = if shader_name.is_null() {
+            "unknown".to_string()
+        } else {
+            env.get_string(shader_name).map(Into::into).unwrap_or_else(|_| "unknown".to_string())
+        };
+
+        let state = read_jni_int_array(env, state).map_err(|e| format!("Invalid pipeline state array: {}", e))?;
+        let [
+            primitive_topology, index_format, polygon_mode,
+            depth_test_enabled, depth_write_enabled, depth_compare,
+            blend_enabled, blend_src_color, blend_dst_color, blend_src_alpha, blend_dst_alpha,
+            sample_count, target_format, depth_format,
+            stencil_read_mask, stencil_write_mask,
+            front_compare, front_fail, front_depth_fail, front_pass,
+            back_compare, back_fail, back_depth_fail, back_pass,
+        ] = <[jint; 24]>::try_from(state.as_slice())
+            .map_err(|_| format!("Pipeline state has {} values, expected 24", state.len()))?;
+
+        let index_format = match index_format {
+            -1 => None,
+            0 => Some(wgt::IndexFormat::Uint16),
+            1 => Some(wgt::IndexFormat::Uint32),
+            _ => return Err(format!("Invalid index format {}", index_format)),
+        };
+
+        let target_format = if target_format < 0 {
+            None
+        } else {
+            let format = device
+                .map_texture_format_public(target_format as u32)
+                .map_err(|e| format!("Invalid target format: {}", e))?;
+            Some(format)
+        };
+
+        // Explicit depth formats, e.g. a Depth16Unorm shadow map, must be renderable on the adapter
+        let depth_format_override = if depth_format < 0 {
+            None
+        } else {
+            let format = device
+                .map_texture_format_public(depth_format as u32)
+                .and_then(|format| {
+                    let pipeline_format = resource_handles::PipelineDepthFormat::from_texture_format(format)
+                        .ok_or_else(|| BasaltError::invalid_parameter("depth_format", format!("{:?} is not a depth format", format)))?;
+                    device::check_format_usages(device.context(), device.adapter_id(), device.id(), format, wgt::TextureUsages::RENDER_ATTACHMENT)?;
+                    Ok(pipeline_format)
+                })
+                .map_err(|e| format!("Invalid depth format for {}: {}", shader_name, e))?;
+            Some(format)
+        };
+
+        let override_constants = read_override_constants(env, constant_names, constant_values)?;
+
+        Ok(Self {
+            primitive_topology,
+            index_format,
+            polygon_mode: polygon_mode as u32,
+            depth_test_enabled: depth_test_enabled != 0,
+            depth_write_enabled: depth_write_enabled != 0,
+            depth_compare,
+            blend_enabled: blend_enabled != 0,
+            blend_factors: [blend_src_color, blend_dst_color, blend_src_alpha, blend_dst_alpha],
+            sample_count: sample_count.max(1) as u32,
+            stencil: stencil_state_from_jni(
+                stencil_read_mask,
+                stencil_write_mask,
+                [front_compare, front_fail, front_depth_fail, front_pass],
+                [back_compare, back_fail, back_depth_fail, back_pass],
+            ),
+            depth_format_override,
+            color_targets: None,
+            target_format,
+            shader_name,
+            background: background != 0,
+            override_constants,
+        })
+    }
+}
+
+/// Zip parallel override constant name/value arrays
+fn read_override_constants(
+    env: &mut JNIEnv,
+    names: &JObjectArray,
+    values: &JDoubleArray,
+) -> std::result::Result<pipeline_registry::OverrideConstants, String> {
+    let names = read_string_array(env, names).map_err(|e| format!("Invalid override constant name: {}", e))?;
+    let mut doubles = Vec::new();
+    if !values.is_null() {
+        let len = env.get_array_length(values).map_err(|e| e.to_string())?;
+        doubles = vec![0.0; len as usize];
+        env.get_double_array_region(values, 0, &mut doubles).map_err(|e| e.to_string())?;
+    }
+    if doubles.len() != names.len() {
+        return Err(format!("{} override constant names but {} values", names.len(), doubles.len()));
+    }
+    Ok(pipeline_registry::OverrideConstants::new(names.into_iter().zip(doubles)))
+}
+
+/// Read the vertex and fragment WGSL sources of a pipeline creator
+fn read_wgsl_sources(
+    env: &mut JNIEnv,
+    vertex_shader: &JString,
+    fragment_shader: &JString,
+) -> std::result::Result<(String, String), String> {
+    if vertex_shader.is_null() {
+        return Err("Vertex shader string is null".to_string());
+    }
+    if fragment_shader.is_null() {
+        return Err("Fragment shader string is null".to_string());
+    }
+    let vertex_wgsl: String = env
+        .get_string(vertex_shader)
+        .map_err(|e| format!("Invalid vertex shader string: {}", e))?
+        .into();
+    let fragment_wgsl: String = env
+        .get_string(fragment_shader)
+        .map_err(|e| format!("Invalid fragment shader string: {}", e))?
+        .into();
+    Ok((vertex_wgsl, fragment_wgsl))
+}
+
+/// Create a render pipeline from pre-converted WGSL shaders
+/// Uses PipelineCache for fast shader compilation and pipeline reuse
+///
+/// `state` and the trailing arguments are shared by every pipeline creator; see
+/// `NativePipelineOptions::from_jni` for their layout.
+///
+/// With `background` set, parsing, reflection and compilation all run on the
+/// pipeline cache's worker pool, so the returned handle may still be pending.
+/// Poll it with `isPipelineReady0`, which also reports shader and argument
+/// errors; draws made with a pending pipeline are skipped.
+///
+/// Strip topologies use the state's index format as their strip index format,
+/// so the all-ones index restarts the strip. Polygon modes the device can't
+/// rasterize fall back to Fill with a warning.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createNativePipelineFromWgsl(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    vertex_shader: JString,
+    fragment_shader: JString,
+    vertex_format: jint,
+    state: JObject,
+    shader_name: JString,
+    background: jboolean,
+    constant_names: JObjectArray,
+    constant_values: JDoubleArray,
+) -> jlong {
+    // Validate device pointer
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+
+    // Get the device from the pointer - use the SAME device that was created during initialization
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    let args = read_wgsl_sources(&mut env, &vertex_shader, &fragment_shader).and_then(|sources| {
+        let options = NativePipelineOptions::from_jni(
+            &mut env, device, state, &shader_name, background, &constant_names, &constant_values,
+        )?;
+        Ok((sources, options))
+    });
+    let ((vertex_wgsl, fragment_wgsl), options) = match args {
+        Ok(args) => args,
+        Err(msg) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", msg);
+            return 0;
+        }
+    };
+
+    create_native_pipeline(
+        &mut env,
+        device,
+        vertex_wgsl,
+        fragment_wgsl,
+        pipeline_registry::VertexLayout::Fixed(vertex_format as usize),
+        options,
+    )
+}
+
+/// Create a render pipeline from WGSL shaders that writes multiple color targets
+///
+/// The fragment shader's `@location(0..N)` outputs map to the targets in
+/// order; see `color_targets_from_jni` for the array layout. The targets
+/// replace the state's target format and blend arguments. Creation fails with
+/// an `IllegalArgumentException` if the shader's outputs don't match the
+/// number of targets.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createNativePipelineFromWgslMRT(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    vertex_shader: JString,
+    fragment_shader: JString,
+    vertex_format: jint,
+    target_formats: JObject,
+    target_blend_enabled: JObject,
+    target_blend_factors: JObject,
+    state: JObject,
+    shader_name: JString,
+    background: jboolean,
+    constant_names: JObjectArray,
+    constant_values: JDoubleArray,
+) -> jlong {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    let args = read_wgsl_sources(&mut env, &vertex_shader, &fragment_shader).and_then(|sources| {
+        let mut options = NativePipelineOptions::from_jni(
+            &mut env, device, state, &shader_name, background, &constant_names, &constant_values,
+        )?;
+        let arrays = (|| -> ::jni::errors::Result<_> {
+            Ok((
+                read_jni_int_array(&mut env, target_formats)?,
+                read_jni_int_array(&mut env, target_blend_enabled)?,
+                read_jni_int_array(&mut env, target_blend_factors)?,
+            ))
+        })();
+        let (formats, blend_enabled, blend_factors) = arrays.map_err(|e| format!("Invalid color target arrays: {}", e))?;
+        let color_targets = color_targets_from_jni(device, &formats, &blend_enabled, &blend_factors)
+            .map_err(|e| format!("Invalid color targets for {}: {}", options.shader_name, e))?;
+        options.color_targets = Some(color_targets);
+        Ok((sources, options))
+    });
+    let ((vertex_wgsl, fragment_wgsl), options) = match args {
+        Ok(args) => args,
+        Err(msg) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", msg);
+            return 0;
+        }
+    };
+
+    create_native_pipeline(
+        &mut env,
+        device,
+        vertex_wgsl,
+        fragment_wgsl,
+        pipeline_registry::VertexLayout::Fixed(vertex_format as usize),
+        options,
+    )
+}
+
+/// Create a render pipeline from WGSL shaders with a caller-described vertex layout
+///
+/// Used for vertex formats the fixed `vertex_format` indices don't cover and for
+/// instanced data in extra vertex buffers. See `vertex_buffers_from_jni` for how
+/// the buffer and attribute arrays are laid out.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createNativePipelineFromWgslCustomVertex(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    vertex_shader: JString,
+    fragment_shader: JString,
+    buffer_strides: JObject,
+    buffer_step_modes: JObject,
+    attribute_buffers: JObject,
+    attribute_locations: JObject,
+    attribute_formats: JObject,
+    attribute_offsets: JObject,
+    state: JObject,
+    shader_name: JString,
+    background: jboolean,
+    constant_names: JObjectArray,
+    constant_values: JDoubleArray,
+) -> jlong {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    let args = read_wgsl_sources(&mut env, &vertex_shader, &fragment_shader).and_then(|sources| {
+        let options = NativePipelineOptions::from_jni(
+            &mut env, device, state, &shader_name, background, &constant_names, &constant_values,
+        )?;
+        let arrays = (|| -> ::jni::errors::Result<_> {
+            Ok((
+                read_jni_long_array(&mut env, buffer_strides)?,
+                read_jni_int_array(&mut env, buffer_step_modes)?,
+                read_jni_int_array(&mut env, attribute_buffers)?,
+                read_jni_int_array(&mut env, attribute_locations)?,
+                read_jni_int_array(&mut env, attribute_formats)?,
+                read_jni_long_array(&mut env, attribute_offsets)?,
+            ))
+        })();
+        let (strides, step_modes, buffers, locations, formats, offsets) =
+            arrays.map_err(|e| format!("Invalid vertex layout arrays: {}", e))?;
+        let vertex_layout = vertex_buffers_from_jni(&strides, &step_modes, &buffers, &locations, &formats, &offsets)
+            .and_then(pipeline_registry::VertexLayout::custom)
+            .map_err(|e| format!("Invalid vertex layout for {}: {}", options.shader_name, e))?;
+        Ok((sources, vertex_layout, options))
+    });
+    let ((vertex_wgsl, fragment_wgsl), vertex_layout, options) = match args {
+        Ok(args) => args,
+        Err(msg) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", msg);
+            return 0;
+        }
+    };
+
+    create_native_pipeline(&mut env, device, vertex_wgsl, fragment_wgsl, vertex_layout, options)
+}
+
+/// Create a render pipeline from WGSL shaders with a fixed vertex format plus a
+/// per-instance vertex buffer
+///
+/// The instance buffer is bound at the slot after the fixed layout's buffer
+/// (slot 1) and is described by `instance_stride` and the parallel
+/// `instance_locations`/`instance_formats`/`instance_offsets` arrays.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createNativePipelineFromWgslInstanced(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    vertex_shader: JString,
+    fragment_shader: JString,
+    vertex_format: jint,
+    instance_stride: jlong,
+    instance_locations: JObject,
+    instance_formats: JObject,
+    instance_offsets: JObject,
+    state: JObject,
+    shader_name: JString,
+    background: jboolean,
+    constant_names: JObjectArray,
+    constant_values: JDoubleArray,
+) -> jlong {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    let args = read_wgsl_sources(&mut env, &vertex_shader, &fragment_shader).and_then(|sources| {
+        let options = NativePipelineOptions::from_jni(
+            &mut env, device, state, &shader_name, background, &constant_names, &constant_values,
+        )?;
+        let arrays = (|| -> ::jni::errors::Result<_> {
+            Ok((
+                read_jni_int_array(&mut env, instance_locations)?,
+                read_jni_int_array(&mut env, instance_formats)?,
+                read_jni_long_array(&mut env, instance_offsets)?,
+            ))
+        })();
+        let (locations, formats, offsets) = arrays.map_err(|e| format!("Invalid instance attribute arrays: {}", e))?;
+        let vertex_layout = vertex_buffers_from_jni(
+            &[instance_stride],
+            &[1],
+            &vec![0; locations.len()],
+            &locations,
+            &formats,
+            &offsets,
+        )
+        .and_then(|mut buffers| {
+            let instance = buffers.remove(0);
+            pipeline_registry::VertexLayout::Fixed(vertex_format as usize)
+                .with_instance_buffer(instance.array_stride, instance.attributes)
+        })
+        .map_err(|e| format!("Invalid instance layout for {}: {}", options.shader_name, e))?;
+        Ok((sources, vertex_layout, options))
+    });
+    let ((vertex_wgsl, fragment_wgsl), vertex_layout, options) = match args {
+        Ok(args) => args,
+        Err(msg) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", msg);
+            return 0;
+        }
+    };
+
+    create_native_pipeline(&mut env, device, vertex_wgsl, fragment_wgsl, vertex_layout, options)
+}
+
+/// Why a pipeline couldn't be prepared, by the Java exception it becomes
+enum PipelinePrepareError {
+    /// Shader source of the named stage that fails to parse
+    Shader(&'static str, BasaltError),
+    /// Arguments that don't fit the shaders or the device
+    Invalid(String),
+    /// Device-side failure, such as a layout wgpu rejects
+    Failed(String),
+}
+
+impl PipelinePrepareError {
+    fn throw(&self, env: &mut JNIEnv) {
+        match self {
+            Self::Shader(stage, e) => throw_shader_compilation_exception(env, stage, e),
+            Self::Invalid(msg) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", msg);
+            }
+            Self::Failed(msg) => {
+                let _ = env.throw_new("java/lang/RuntimeException", msg);
+            }
+        }
+    }
+}
+
+impl fmt::Display for PipelinePrepareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Shader(stage, e) => write!(f, "Failed to parse {} WGSL: {}", stage, e),
+            Self::Invalid(msg) | Self::Failed(msg) => f.write_str(msg),
+        }
+    }
+}
+
+/// Pipeline key and parsed shaders, before any GPU objects exist
+struct PreparedPipeline {
+    key: pipeline_registry::RenderPipelineKey,
+    vertex_module: naga::Module,
+    fragment_module: naga::Module,
+    shader_name: String,
+}
+
+/// Device state `prepare_native_pipeline` reads
+#[derive(Clone, Copy)]
+struct PipelineDeviceInfo {
+    supports_push_constants: bool,
+    features: wgt::Features,
+    sdr_swapchain_format: wgt::TextureFormat,
+}
+
+impl PipelineDeviceInfo {
+    fn new(device: &BasaltDevice) -> Self {
+        Self {
+            supports_push_constants: device.supports_push_constants(),
+            features: device.context().inner().device_features(device.id()),
+            sdr_swapchain_format: device.sdr_swapchain_format(),
+        }
+    }
+}
+
+/// Build the cache key pipeline creation and `validatePipelineConfig` share
+///
+/// Resolves the vertex layout, lowers immediates when the device lacks push
+/// constants, parses the shaders and checks their stages, color target count,
+/// polygon mode and depth format against the requested state.
+fn prepare_native_pipeline(
+    device: PipelineDeviceInfo,
+    vertex_wgsl: String,
+    fragment_wgsl: String,
+    vertex_layout: pipeline_registry::VertexLayout,
+    strict_vertex_format: bool,
+    options: NativePipelineOptions,
+) -> std::result::Result<PreparedPipeline, PipelinePrepareError> {
+    let NativePipelineOptions {
+        primitive_topology,
+        index_format,
+        polygon_mode,
+        depth_test_enabled,
+        depth_write_enabled,
+        depth_compare,
+        blend_enabled,
+        blend_factors: [blend_src_color_factor, blend_dst_color_factor, blend_src_alpha_factor, blend_dst_alpha_factor],
+        sample_count,
+        stencil,
+        depth_format_override,
+        color_targets,
+        target_format,
+        shader_name: shader_name_str,
+        background: _,
+        override_constants,
+    } = options;
+    let invalid = |e: &dyn fmt::Display| PipelinePrepareError::Invalid(format!("{} for {}", e, shader_name_str));
+
+    // Unknown fixed formats fall back to POSITION_TEX_COLOR unless strict
+    let vertex_layout = match vertex_layout {
+        pipeline_registry::VertexLayout::Fixed(format_index) => {
+            pipeline_registry::VertexLayout::fixed(format_index, strict_vertex_format).map_err(|e| invalid(&e))?
+        }
+        layout => layout,
+    };
+
+    // Without push constants, `var<immediate>` blocks become a uniform fed per draw
+    let (vertex_wgsl, fragment_wgsl) = if device.supports_push_constants {
+        (vertex_wgsl, fragment_wgsl)
+    } else {
+        (
+            shader::lower_immediates_to_uniform(&vertex_wgsl).unwrap_or(vertex_wgsl),
+            shader::lower_immediates_to_uniform(&fragment_wgsl).unwrap_or(fragment_wgsl),
+        )
+    };
+
+    // Parse WGSL shaders once for layout creation and caching
+    log::debug!("Parsing WGSL shaders for layout reflection...");
+    let vertex_module = shader::parse_wgsl_named(&vertex_wgsl, &shader_name_str)
+        .map_err(|e| PipelinePrepareError::Shader("vertex", e))?;
+    let fragment_module = shader::parse_wgsl_named(&fragment_wgsl, &shader_name_str)
+        .map_err(|e| PipelinePrepareError::Shader("fragment", e))?;
+    log::debug!("WGSL shaders parsed for layout");
+
+    // Swapped sources otherwise surface as a missing entry point deep in wgpu
+    for (module, expected, which) in [
+        (&vertex_module, naga::ShaderStage::Vertex, "vertex"),
+        (&fragment_module, naga::ShaderStage::Fragment, "fragment"),
+    ] {
+        if let Ok(stage) = shader::detect_stage(module) {
+            if stage != expected {
+                return Err(PipelinePrepareError::Invalid(
+                    format!("{} shader of {} has a {:?} entry point", which, shader_name_str, stage),
+                ));
+            }
+        }
+    }
+
+    // A misspelled override would otherwise silently keep its default value
+    for name in override_constants.unknown_names(&[&vertex_module, &fragment_module]) {
+        log::warn!("Pipeline '{}' sets override constant '{}' that neither shader declares", shader_name_str, name);
+    }
+
+    // MRT: every @location output must have a target, checked before any GPU objects are made
+    if let Some(targets) = &color_targets {
+        pipeline_registry::validate_color_target_count(&fragment_module, targets.len(), &shader_name_str)
+            .map_err(|e| PipelinePrepareError::Invalid(e.to_string()))?;
+    }
+
+    // Map pipeline parameters
+    let primitive_topology = match primitive_topology as u32 {
+        0 => wgt::PrimitiveTopology::PointList,
+        1 => wgt::PrimitiveTopology::LineList,
+        2 => wgt::PrimitiveTopology::LineStrip,
+        3 => wgt::PrimitiveTopology::TriangleList,
+        4 => wgt::PrimitiveTopology::TriangleStrip,
+        _ => wgt::PrimitiveTopology::TriangleList,
+    };
+
+    let depth_compare = map_compare_function_from_jni(depth_compare);
+    let polygon_mode = pipeline_registry::polygon_mode(polygon_mode, device.features)
+        .map_err(|e| invalid(&e))?;
+
+    // Detect post-processing shaders - they should use alpha blending to avoid overwriting GUI
+    // Post-processing shaders typically:
+    // 1. Have "minecraft:post/" in comments (original shader path)
+    // 2. Use "input_texture" or "source_texture" (sampling from previous pass)
+    // 3. Are blur/outline effects that need to blend with existing content
+    let is_post_processing = fragment_wgsl.contains("minecraft:post/") ||
+                             fragment_wgsl.contains("input_texture") ||
+                             fragment_wgsl.contains("source_texture");
+
+    // Force blending on for post-processing shaders to prevent overwriting GUI
+    let effective_blend_enabled = if is_post_processing {
+        log::info!("Detected post-processing shader, forcing alpha blending enabled");
+        true
+    } else {
+        blend_enabled
+    };
+
+    // Depth-tested pipelines target the depth attachment beginRenderPass provides;
+    // GUI and other 2D pipelines that neither test nor write depth get no depth state
+    let depth_format = pipeline_registry::select_depth_format(
+        depth_format_override,
+        stencil.is_enabled(),
+        depth_test_enabled,
+        depth_write_enabled,
+        shader_writes_depth(&fragment_module),
+    )
+    .map_err(|e| invalid(&e))?;
+    log::debug!("Pipeline {} uses depth format {:?}", shader_name_str, depth_format);
+
+    log::debug!("Creating pipeline with vertex_layout={:?}, topology={:?}, depth_test={}, blend={}",
+        vertex_layout, primitive_topology, depth_test_enabled, blend_enabled);
+
+    // Use PipelineCache for fast pipeline creation
+    // The cache will:
+    // 1. Check if we've seen this (vertex_shader, fragment_shader, topology, depth, blend) combo before
+    // 2. If cached, return immediately
+    // 3. If not, compile shaders and create pipeline, then cache for next time
+    let mut key = pipeline_registry::RenderPipelineKey {
+        vertex_shader_hash: pipeline_registry::PipelineCache::hash_wgsl(&vertex_wgsl),
+        fragment_shader_hash: pipeline_registry::PipelineCache::hash_wgsl(&fragment_wgsl),
+        topology: primitive_topology,
+        strip_index_format: pipeline_registry::strip_index_format(primitive_topology, index_format),
+        polygon_mode,
+        depth_test_enabled,
+        depth_write_enabled,
+        depth_compare,
+        blend_enabled: effective_blend_enabled,
+        blend_src_color_factor: if effective_blend_enabled { map_blend_factor_from_jni(blend_src_color_factor) } else { None },
+        blend_dst_color_factor: if effective_blend_enabled { map_blend_factor_from_jni(blend_dst_color_factor) } else { None },
+        blend_src_alpha_factor: if effective_blend_enabled { map_blend_factor_from_jni(blend_src_alpha_factor) } else { None },
+        blend_dst_alpha_factor: if effective_blend_enabled { map_blend_factor_from_jni(blend_dst_alpha_factor) } else { None },
+        target_format: target_format.unwrap_or(device.sdr_swapchain_format),
+        extra_color_targets: Vec::new(),
+        depth_format,  // CRITICAL: Include depth format in cache key!
+        depth_bias_constant: 0,  // TODO: Pass from Java when Minecraft uses depth bias
+        depth_bias_slope_scale: 0,  // TODO: Pass from Java when Minecraft uses depth bias (stored as f32 bits)
+        sample_count,
+        stencil,
+        depth_only: false,
+        override_constants,
+        vertex_layout,
+    };
+
+    // MRT pipelines describe every target explicitly; target 0 takes the primary slot of the key
+    if let Some(mut targets) = color_targets {
+        let primary = targets.remove(0);
+        key.target_format = primary.format;
+        key.blend_enabled = primary.blend.is_some();
+        key.blend_src_color_factor = primary.blend.map(|blend| blend.color.src_factor);
+        key.blend_dst_color_factor = primary.blend.map(|blend| blend.color.dst_factor);
+        key.blend_src_alpha_factor = primary.blend.map(|blend| blend.alpha.src_factor);
+        key.blend_dst_alpha_factor = primary.blend.map(|blend| blend.alpha.dst_factor);
+        key.extra_color_targets = targets;
+    }
+
+    Ok(PreparedPipeline { key, vertex_module, fragment_module, shader_name: shader_name_str })
+}
+
+/// Device state and handles pipeline creation needs
+///
+/// Copied out of the device, so background pipelines can be prepared and laid
+/// out on a compile worker after the creating call has returned.
+#[derive(Clone)]
+struct PipelineBuilder {
+    context: Arc<BasaltContext>,
+    device_id: wgpu_core::id::DeviceId,
+    pipeline_cache: Arc<pipeline_registry::PipelineCache>,
+    device_info: PipelineDeviceInfo,
+    max_immediate_size: u32,
+    immediates_fallback_layout: Option<wgpu_core::id::BindGroupLayoutId>,
+    strict_vertex_format: bool,
+}
+
+/// A prepared pipeline with its layouts created, ready for the pipeline cache
+struct LaidOutPipeline {
+    prepared: PreparedPipeline,
+    layouts: ShaderLayouts,
+    immediate_ranges: resource_handles::ImmediateRanges,
+    label: String,
+}
+
+impl PipelineBuilder {
+    fn new(device: &BasaltDevice) -> Result<Self, BasaltError> {
+        Ok(Self {
+            context: device.context().clone(),
+            device_id: device.id(),
+            pipeline_cache: device.pipeline_cache.clone(),
+            device_info: PipelineDeviceInfo::new(device),
+            max_immediate_size: device.get_limits().max_immediate_size,
+            immediates_fallback_layout: device.immediates_fallback()?.map(|fallback| fallback.bind_group_layout_id),
+            strict_vertex_format: crate::error::strict_validation(),
+        })
+    }
+
+    /// Parse and reflect the shaders and create the pipeline's layouts
+    fn lay_out(
+        &self,
+        vertex_wgsl: String,
+        fragment_wgsl: String,
+        vertex_layout: pipeline_registry::VertexLayout,
+        options: NativePipelineOptions,
+    ) -> std::result::Result<LaidOutPipeline, PipelinePrepareError> {
+        let prepared = prepare_native_pipeline(
+            self.device_info, vertex_wgsl, fragment_wgsl, vertex_layout, self.strict_vertex_format, options,
+        )?;
+
+        // Create pipeline layout from shader reflection (needed for cache key)
+        let layouts = create_layout_from_shaders(
+            &self.context,
+            self.device_id,
+            &prepared.vertex_module,
+            &prepared.fragment_module,
+            self.max_immediate_size,
+            self.immediates_fallback_layout,
+        )
+        .map_err(|e| {
+            let msg = format!("Failed to create pipeline layout from shaders: {:?}", e);
+            log::error!("{}", msg);
+            PipelinePrepareError::Failed(msg)
+        })?;
+        log::debug!("Pipeline layout created for cache");
+        let immediate_ranges = pipeline_registry::immediate_ranges(&prepared.vertex_module, &prepared.fragment_module);
+
+        // The Java shader name labels the pipeline and its shaders in GPU captures;
+        // creators read a null name as "unknown"
+        let label = match (&prepared.key.vertex_layout, prepared.shader_name.as_str()) {
+            (_, name) if name != "unknown" => name.to_string(),
+            (pipeline_registry::VertexLayout::Fixed(vertex_format), _) => format!("NativePipeline_vfmt{}", vertex_format),
+            (pipeline_registry::VertexLayout::Custom(buffers), _) => format!("NativePipeline_custom{}", buffers.len()),
+        };
+        log::debug!(
+            "Checking pipeline cache for key hash {:x}...",
+            pipeline_registry::PipelineCache::hash_key(&prepared.key)
+        );
+        Ok(LaidOutPipeline { prepared, layouts, immediate_ranges, label })
+    }
+
+    /// Hand a laid out pipeline to the compile workers, completing `handle` once it's built
+    fn compile_in_background(&self, handle: u64, pipeline: LaidOutPipeline) {
+        let LaidOutPipeline { prepared, layouts, immediate_ranges, label } = pipeline;
+        let (bind_group_layout_id, pipeline_layout_id, binding_layouts, extra_groups) = layouts;
+        let key = prepared.key;
+        let vertex_buffer_count = key.vertex_layout.buffer_count();
+        let vertex_stride = key.vertex_layout.vertex_stride();
+        let depth_format = key.depth_format;
+        let depth_test_enabled = key.depth_test_enabled;
+        let depth_write_enabled = key.depth_write_enabled;
+        let sample_count = key.sample_count;
+        let color_target_count = 1 + key.extra_color_targets.len() as u32;
+        let target_format = key.target_format;
+        let shader_name = prepared.shader_name;
+
+        let pipeline_cache = self.pipeline_cache.clone();
+        let context = self.context.clone();
+        let on_ready: pipeline_registry::PipelineReadyCallback = Box::new(move |result| match result {
+            Ok(cached) => {
+                let extra_groups = pipeline_cache.extra_group_layouts(&context, cached.pipeline_id, extra_groups);
+                HANDLES.complete_render_pipeline(handle, resource_handles::RenderPipelineInfo {
+                    id: cached.pipeline_id,
+                    bind_group_layout_id,
+                    binding_layouts: cached.binding_layouts.clone(),
+                    depth_format,
+                    depth_write_enabled,
+                    depth_test_enabled,
+                    shader_name,
+                    sample_count,
+                    vertex_buffer_count,
+                    vertex_stride,
+                    color_target_count,
+                    target_format,
+                    extra_groups,
+                    immediate_ranges,
+                });
+                log::debug!("Background pipeline {} is ready", handle);
+            }
+            Err(e) => {
+                for layout in extra_groups {
+                    context.inner().bind_group_layout_drop(layout.layout_id);
+                }
+                HANDLES.fail_render_pipeline(handle, e.to_string());
+            }
+        });
+        let request = pipeline_registry::RenderPipelineRequest {
+            key,
+            bind_group_layout_id,
+            pipeline_layout_id,
+            binding_layouts,
+            label,
+        };
+        let ready = self.pipeline_cache.get_or_create_render_pipeline_async(
+            &self.context,
+            self.device_id,
+            prepared.vertex_module,
+            prepared.fragment_module,
+            request,
+            on_ready,
+        );
+        log::debug!("Render pipeline handle {} {}", handle, if ready { "ready from cache" } else { "compiling" });
+    }
+}
+
+/// Shared pipeline creation path for WGSL and SPIR-V sources
+///
+/// Parses the WGSL, reflects the bind group layout and creates (or reuses) the
+/// pipeline through the PipelineCache. Returns 0 with a pending Java exception
+/// on failure.
+///
+/// With `background` set all of that runs on the compile workers and the
+/// returned handle starts out pending; errors, parse errors included, then
+/// fail the handle instead of throwing.
+fn create_native_pipeline(
+    env: &mut JNIEnv,
+    device: &BasaltDevice,
+    vertex_wgsl: String,
+    fragment_wgsl: String,
+    vertex_layout: pipeline_registry::VertexLayout,
+    options: NativePipelineOptions,
+) -> jlong {
+    let builder = match PipelineBuilder::new(device) {
+        Ok(builder) => builder,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", e.to_string());
+            return 0;
+        }
+    };
+
+    if options.background {
+        let handle = HANDLES.reserve_render_pipeline();
+        pipeline_registry::spawn_compile_job(move || {
+            // A panic must still fail the handle, or it stays pending forever
+            let laid_out = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                builder.lay_out(vertex_wgsl, fragment_wgsl, vertex_layout, options)
+            }))
+            .unwrap_or_else(|_| Err(PipelinePrepareError::Failed("pipeline preparation panicked".to_string())));
+            match laid_out {
+                Ok(laid_out) => builder.compile_in_background(handle, laid_out),
+                Err(e) => {
+                    log::error!("Background pipeline {} failed: {}", handle, e);
+                    HANDLES.fail_render_pipeline(handle, e.to_string());
+                }
+            }
+        });
+        return handle as jlong;
+    }
+
+    let laid_out = match builder.lay_out(vertex_wgsl, fragment_wgsl, vertex_layout, options) {
+        Ok(laid_out) => laid_out,
+        Err(e) => {
+            e.throw(env);
+            return 0;
+        }
+    };
+    let LaidOutPipeline { prepared, layouts, immediate_ranges, label } = laid_out;
+    let (bind_group_layout_id, pipeline_layout_id, binding_layouts, extra_groups) = layouts;
+    let PreparedPipeline { key: cache_key, vertex_module, fragment_module, shader_name: shader_name_str } = prepared;
+    let device_context = device.context();
+    let device_id = device.id();
+
+    let vertex_buffer_count = cache_key.vertex_layout.buffer_count();
+    let vertex_stride = cache_key.vertex_layout.vertex_stride();
+    let depth_format = cache_key.depth_format;
+    let depth_test_enabled = cache_key.depth_test_enabled;
+    let depth_write_enabled = cache_key.depth_write_enabled;
+    let sample_count = cache_key.sample_count;
+    let color_target_count = 1 + cache_key.extra_color_targets.len() as u32;
+    let target_format = cache_key.target_format;
+
+    let pipeline_start = std::time::Instant::now();
+    let request = pipeline_registry::RenderPipelineRequest {
+        key: cache_key,
+        bind_group_layout_id,
+        pipeline_layout_id,
+        binding_layouts: binding_layouts.clone(),
+        label,
+    };
+    let cached_pipeline =
+        device.pipeline_cache.get_or_create_parsed_render_pipeline(device_context, device_id, vertex_module, fragment_module, request);
+    device.record_cpu_timing(frame_timing::CpuTimingKind::PipelineCreation, pipeline_start.elapsed());
+
+    let cached_pipeline = match cached_pipeline {
+        Ok(pipeline) => pipeline,
+        Err(e) => {
+            for layout in extra_groups {
+                device_context.inner().bind_group_layout_drop(layout.layout_id);
+            }
+            let msg = format!("Failed to create pipeline (via cache): {:?}", e);
+            log::error!("{}", msg);
+            let _ = env.throw_new("java/lang/RuntimeException", &msg);
+            return 0;
+        }
+    };
+
+    // Log cache statistics
+    let stats = device.pipeline_cache.stats();
+    log::debug!("Pipeline cache stats - shader_hits: {}, shader_misses: {}, pipeline_hits: {}, pipeline_misses: {}, total_pipelines: {}",
+        stats.shader_hits, stats.shader_misses, stats.pipeline_hits, stats.pipeline_misses, stats.total_pipelines);
+
+    let pipeline_id = cached_pipeline.pipeline_id;
+    log::debug!("Render pipeline created successfully via cache!");
+    let extra_groups = device.pipeline_cache.extra_group_layouts(device_context, pipeline_id, extra_groups);
+
+    let num_bindings = binding_layouts.len();
+    let handle = HANDLES.insert_render_pipeline(resource_handles::RenderPipelineInfo {
+        id: pipeline_id,
+        bind_group_layout_id,
+        binding_layouts,
+        depth_format,
+        depth_write_enabled,
+        depth_test_enabled,
+        shader_name: shader_name_str.clone(),
+        sample_count,
+        vertex_buffer_count,
+        vertex_stride,
+        color_target_count,
+        target_format,
+        extra_groups,
+        immediate_ranges,
+    });
+    log::debug!("Created render pipeline via cache with handle {} (shader: {}, bgl: {:?}, bindings: {}, depth: {:?})",
+               handle, shader_name_str, bind_group_layout_id, num_bindings, depth_format);
+    handle as jlong
+}
+
+/// Create a render pipeline from pre-compiled SPIR-V shaders
+///
+/// The SPIR-V is validated and translated to WGSL, then goes through the same
+/// reflection and caching path as `createNativePipelineFromWgsl`.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createNativePipelineFromSpirv(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    vertex_spirv: JByteArray,
+    fragment_spirv: JByteArray,
+    vertex_format: jint,
+    state: JObject,
+    shader_name: JString,
+    background: jboolean,
+    constant_names: JObjectArray,
+    constant_values: JDoubleArray,
+) -> jlong {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+
+    if vertex_spirv.is_null() || fragment_spirv.is_null() {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "SPIR-V shader array is null");
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    let options = match NativePipelineOptions::from_jni(
+        &mut env, device, state, &shader_name, background, &constant_names, &constant_values,
+    ) {
+        Ok(options) => options,
+        Err(msg) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", msg);
+            return 0;
         }
-        
-        // Check if the entry point has early_depth_test set
-        if entry_point.early_depth_test.is_some() {
-            return true;
+    };
+
+    let mut translate = |spirv: &JByteArray, stage: &str| -> Option<String> {
+        let bytes = match env.convert_byte_array(spirv) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get byte array: {}", e));
+                return None;
+            }
+        };
+        match shader::spirv_to_wgsl(&bytes, &format!("{} ({})", options.shader_name, stage)) {
+            Ok(wgsl) => Some(wgsl),
+            Err(e) => {
+                let msg = format!("Failed to translate {} SPIR-V: {}", stage, e);
+                log::error!("{}", msg);
+                let _ = env.throw_new("java/lang/RuntimeException", &msg);
+                None
+            }
         }
-        
-        // Check function result for FragDepth builtin
-        if let Some(ref result) = entry_point.function.result {
-            // Direct binding check
-            if let Some(naga::Binding::BuiltIn(naga::BuiltIn::FragDepth)) = &result.binding {
-                return true;
+    };
+
+    let Some(vertex_wgsl) = translate(&vertex_spirv, "vertex") else { return 0 };
+    let Some(fragment_wgsl) = translate(&fragment_spirv, "fragment") else { return 0 };
+
+    create_native_pipeline(
+        &mut env,
+        device,
+        vertex_wgsl,
+        fragment_wgsl,
+        pipeline_registry::VertexLayout::Fixed(vertex_format as usize),
+        options,
+    )
+}
+
+/// Check a pipeline configuration without creating GPU resources
+///
+/// Takes the same parameters as `createNativePipelineFromWgsl` and builds the
+/// same pipeline key, so the depth format, sample count and vertex layout it
+/// checks are the ones creation would use. Returns an empty string if the
+/// configuration is valid, otherwise the first problem.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_validatePipelineConfig(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    vertex_shader: JString,
+    fragment_shader: JString,
+    vertex_format: jint,
+    state: JObject,
+    shader_name: JString,
+    background: jboolean,
+    constant_names: JObjectArray,
+    constant_values: JDoubleArray,
+) -> jstring {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return std::ptr::null_mut();
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    let args = read_wgsl_sources(&mut env, &vertex_shader, &fragment_shader).and_then(|sources| {
+        let options = NativePipelineOptions::from_jni(
+            &mut env, device, state, &shader_name, background, &constant_names, &constant_values,
+        )?;
+        Ok((sources, options))
+    });
+    let result = args.and_then(|((vertex_wgsl, fragment_wgsl), options)| {
+        pipeline_registry::validate_state_encoding(
+            options.primitive_topology,
+            options.depth_compare,
+            options.blend_enabled.then_some(options.blend_factors),
+        )
+        .map_err(|e| e.to_string())?;
+        let vertex_layout = pipeline_registry::VertexLayout::Fixed(vertex_format as usize);
+        let device_info = PipelineDeviceInfo::new(device);
+        let prepared = prepare_native_pipeline(device_info, vertex_wgsl, fragment_wgsl, vertex_layout, true, options)
+            .map_err(|e| e.to_string())?;
+        pipeline_registry::validate_pipeline_config(
+            &prepared.vertex_module,
+            &prepared.fragment_module,
+            &prepared.key,
+            &prepared.shader_name,
+        )
+        .map_err(|e| e.to_string())
+    });
+
+    let problem = match result {
+        Ok(()) => String::new(),
+        Err(e) => {
+            log::debug!("Pipeline config rejected: {}", e);
+            e
+        }
+    };
+
+    match env.new_string(&problem) {
+        Ok(s) => s.into_raw(),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+// ============================================================================
+// RENDER PASS OPERATIONS
+// ============================================================================
+
+/// Begin a render pass
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_beginRenderPass(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    color_view_handle: jlong,
+    depth_view_handle: jlong,
+    should_clear_color: jboolean,
+    clear_color: jint,
+    should_clear_depth: jboolean,
+    clear_depth: jfloat,
+    clear_stencil: jint,
+    width: jint,
+    height: jint,
+) -> jlong {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    begin_render_pass(
+        &mut env,
+        device,
+        color_view_handle,
+        &[],
+        depth_view_handle,
+        0,
+        None,
+        should_clear_color,
+        clear_color,
+        should_clear_depth,
+        clear_depth,
+        clear_stencil,
+        width,
+        height,
+    )
+}
+
+/// Begin a multisampled render pass
+///
+/// `color_view_handle` must reference a multisampled texture; it is resolved
+/// into the single-sample `resolve_view_handle` when the pass ends. The resolve
+/// target is what gets tracked as the pass output.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_beginRenderPassMSAA(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    color_view_handle: jlong,
+    resolve_view_handle: jlong,
+    depth_view_handle: jlong,
+    should_clear_color: jboolean,
+    clear_color: jint,
+    should_clear_depth: jboolean,
+    clear_depth: jfloat,
+    clear_stencil: jint,
+    width: jint,
+    height: jint,
+) -> jlong {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+    if resolve_view_handle == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null resolve view handle");
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    begin_render_pass(
+        &mut env,
+        device,
+        color_view_handle,
+        &[],
+        depth_view_handle,
+        resolve_view_handle,
+        None,
+        should_clear_color,
+        clear_color,
+        should_clear_depth,
+        clear_depth,
+        clear_stencil,
+        width,
+        height,
+    )
+}
+
+/// Begin a render pass with an occlusion query set attached
+///
+/// Draws inside the pass can be wrapped in `beginOcclusionQuery` /
+/// `endOcclusionQuery` to count the samples that pass depth testing.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_beginRenderPassOcclusion(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    color_view_handle: jlong,
+    depth_view_handle: jlong,
+    occlusion_query_ptr: jlong,
+    should_clear_color: jboolean,
+    clear_color: jint,
+    should_clear_depth: jboolean,
+    clear_depth: jfloat,
+    clear_stencil: jint,
+    width: jint,
+    height: jint,
+) -> jlong {
+    if device_ptr == 0 || occlusion_query_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let queries = unsafe { &*(occlusion_query_ptr as *const occlusion_queries::OcclusionQuerySet) };
+
+    begin_render_pass(
+        &mut env,
+        device,
+        color_view_handle,
+        &[],
+        depth_view_handle,
+        0,
+        Some(queries),
+        should_clear_color,
+        clear_color,
+        should_clear_depth,
+        clear_depth,
+        clear_stencil,
+        width,
+        height,
+    )
+}
+
+/// Begin a render pass with multiple color attachments (MRT)
+///
+/// `color_view_handles[i]` is bound to `@location(i)`; all attachments share
+/// the clear settings and must have the same sample count. Element 0 is the
+/// tracked pass output.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_beginRenderPassMRT(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    color_view_handles: JObject,
+    depth_view_handle: jlong,
+    should_clear_color: jboolean,
+    clear_color: jint,
+    should_clear_depth: jboolean,
+    clear_depth: jfloat,
+    clear_stencil: jint,
+    width: jint,
+    height: jint,
+) -> jlong {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+
+    let color_view_handles = match read_jni_long_array(&mut env, color_view_handles) {
+        Ok(handles) if !handles.is_empty() => handles,
+        Ok(_) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", "At least one color view is required");
+            return 0;
+        }
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid color view array: {}", e));
+            return 0;
+        }
+    };
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    begin_render_pass(
+        &mut env,
+        device,
+        color_view_handles[0],
+        &color_view_handles[1..],
+        depth_view_handle,
+        0,
+        None,
+        should_clear_color,
+        clear_color,
+        should_clear_depth,
+        clear_depth,
+        clear_stencil,
+        width,
+        height,
+    )
+}
+
+/// Shared render pass setup for the `beginRenderPass*` entry points
+///
+/// `extra_color_view_handles` are the MRT attachments after the primary one,
+/// `resolve_view_handle` is 0 for single-sample passes and `occlusion_queries`
+/// is only set by `beginRenderPassOcclusion`. Every attachment must be
+/// `width` x `height`. Returns 0 with a pending Java exception on failure.
+#[allow(clippy::too_many_arguments)]
+fn begin_render_pass(
+    env: &mut JNIEnv,
+    device: &BasaltDevice,
+    color_view_handle: jlong,
+    extra_color_view_handles: &[jlong],
+    depth_view_handle: jlong,
+    resolve_view_handle: jlong,
+    occlusion_queries: Option<&occlusion_queries::OcclusionQuerySet>,
+    should_clear_color: jboolean,
+    clear_color: jint,
+    should_clear_depth: jboolean,
+    clear_depth: jfloat,
+    clear_stencil: jint,
+    width: jint,
+    height: jint,
+) -> jlong {
+    // Look up texture view IDs from handles
+    let color_view = if color_view_handle != 0 {
+        let view = HANDLES.get_texture_view(color_view_handle as u64);
+        log::debug!("beginRenderPass: color_view_handle={}, resolved={:?}", color_view_handle, view);
+        view
+    } else {
+        log::warn!("beginRenderPass: No color view handle provided!");
+        None
+    };
+
+    // Attachment sample count comes from the color texture; pipelines are checked against it
+    let sample_count = HANDLES
+        .get_texture_view_info(color_view_handle as u64)
+        .map_or(1, |info| info.sample_count);
+
+    let mut extra_color_views = Vec::with_capacity(extra_color_view_handles.len());
+    for (i, &handle) in extra_color_view_handles.iter().enumerate() {
+        match HANDLES.get_texture_view_info(handle as u64) {
+            Some(info) if info.sample_count == sample_count => extra_color_views.push(info.id),
+            Some(info) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", format!(
+                    "Color attachment {} has {} samples but attachment 0 has {}",
+                    i + 1, info.sample_count, sample_count));
+                return 0;
             }
-            
-            // Check if result is a struct with FragDepth member
-            let ty = &fragment_module.types[result.ty];
-            if let naga::TypeInner::Struct { members, .. } = &ty.inner {
-                for member in members {
-                    if let Some(naga::Binding::BuiltIn(naga::BuiltIn::FragDepth)) = &member.binding {
-                        return true;
-                    }
-                }
+            None => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException",
+                    format!("Invalid texture view handle for color attachment {}", i + 1));
+                return 0;
             }
         }
     }
-    false
-}
-
-/// Helper function to create a bind group layout from shader reflection
-/// Simplified to single bind group (group 0) only
-/// Returns (BindGroupLayoutId, PipelineLayoutId, binding_layouts)
-fn create_layout_from_shaders(
-    context: &Arc<BasaltContext>,
-    device_id: wgpu_core::id::DeviceId,
-    vertex_module: &naga::Module,
-    fragment_module: &naga::Module,
-) -> Result<(wgpu_core::id::BindGroupLayoutId, wgpu_core::id::PipelineLayoutId, Vec<resource_handles::BindingLayoutEntry>), BasaltError> {
-    use std::collections::BTreeMap;
-    use std::borrow::Cow;
-    use std::num::NonZeroU64;
-    use wgpu_core::binding_model;
-    use resource_handles::{BindingLayoutEntry, BindingLayoutType};
-    use naga::proc::{Layouter, GlobalCtx};
-
-    // Create layouters for both modules to calculate type sizes
-    let mut vertex_layouter = Layouter::default();
-    let mut fragment_layouter = Layouter::default();
 
-    // Update layouters with module types
-    let vertex_gctx = GlobalCtx {
-        types: &vertex_module.types,
-        constants: &vertex_module.constants,
-        overrides: &vertex_module.overrides,
-        global_expressions: &vertex_module.global_expressions,
-    };
-    let fragment_gctx = GlobalCtx {
-        types: &fragment_module.types,
-        constants: &fragment_module.constants,
-        overrides: &fragment_module.overrides,
-        global_expressions: &fragment_module.global_expressions,
+    let resolve_view = if resolve_view_handle != 0 {
+        match HANDLES.get_texture_view_info(resolve_view_handle as u64) {
+            Some(info) if info.sample_count == 1 && sample_count > 1 => Some(info.id),
+            Some(info) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", format!(
+                    "Invalid MSAA resolve: color attachment has {} samples, resolve target has {}",
+                    sample_count, info.sample_count));
+                return 0;
+            }
+            None => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid resolve view handle");
+                return 0;
+            }
+        }
+    } else {
+        None
     };
 
-    if let Err(e) = vertex_layouter.update(vertex_gctx) {
-        log::warn!("Failed to calculate vertex shader layouts: {:?}", e);
-    }
-    if let Err(e) = fragment_layouter.update(fragment_gctx) {
-        log::warn!("Failed to calculate fragment shader layouts: {:?}", e);
+    // Attachments the caller passed must all be the pass size; the auto-created depth view always is
+    let attachments: Vec<(String, u32, u32)> = std::iter::once(("color attachment 0".to_string(), color_view_handle))
+        .chain(extra_color_view_handles.iter().enumerate().map(|(i, &handle)| (format!("color attachment {}", i + 1), handle)))
+        .chain(std::iter::once(("resolve target".to_string(), resolve_view_handle)))
+        .chain(std::iter::once(("depth attachment".to_string(), depth_view_handle)))
+        .filter(|(_, handle)| *handle != 0)
+        .filter_map(|(name, handle)| HANDLES.get_texture_view_info(handle as u64).map(|info| (name, info.width, info.height)))
+        .collect();
+    if let Err(e) = render_pass::validate_attachment_sizes(width as u32, height as u32, &attachments) {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+        return 0;
     }
 
-    // Collect all bindings from group 0 only (single bind group approach)
-    // Key: binding number, Value: (wgpu entry, our layout type, min_binding_size, variable name)
-    let mut bindings: BTreeMap<u32, (wgt::BindGroupLayoutEntry, BindingLayoutType, Option<u64>, Option<String>)> = BTreeMap::new();
-
-    // Helper to extract bindings from a module (group 0 only)
-    let mut extract_bindings = |module: &naga::Module, layouter: &Layouter, stage: wgt::ShaderStages| {
-        log::info!("extract_bindings: processing {:?} shader, {} global variables", stage, module.global_variables.len());
-        for (_handle, global_var) in module.global_variables.iter() {
-            if let Some(binding) = &global_var.binding {
-                // Only process group 0 bindings (single bind group approach)
-                if binding.group != 0 {
-                    log::debug!("  Skipping binding at group {}, binding {} (not group 0)", binding.group, binding.binding);
-                    continue;
-                }
-                log::info!("  Found binding {} at group {:?}, name: {:?}, space: {:?}",
-                    binding.binding, binding.group, global_var.name, global_var.space);
-                {
-                    let ty = &module.types[global_var.ty];
-
-                    // Get the variable name from the shader
-                    let var_name = global_var.name.clone();
-
-                    let (binding_type, layout_type, min_size, binding_var_name) = match global_var.space {
-                        naga::AddressSpace::Uniform => {
-                            // Calculate the actual size of the uniform buffer struct
-                            let type_layout = layouter[global_var.ty];
-                            let struct_size = type_layout.to_stride() as u64; // Use stride for proper alignment
-
-                            log::debug!("Uniform buffer at binding {}: size = {} bytes, alignment = {}",
-                                       binding.binding, struct_size, type_layout.alignment);
+    // Use clear parameters from Java
+    let do_clear_color = should_clear_color != 0;
+    let clear_color_argb = clear_color as u32;
 
-                            let min_binding_size = NonZeroU64::new(struct_size);
+    // Always need depth view since pipelines always have depth_stencil
+    // If MC doesn't provide one, create a matching-size depth texture
+    let depth_view = if depth_view_handle != 0 {
+        HANDLES.get_texture_view(depth_view_handle as u64)
+    } else {
+        // Create depth texture matching color texture dimensions
+        log::debug!("MC didn't provide depth texture, creating one for {}x{}", width, height);
+        match device.get_or_create_depth_view(width as u32, height as u32, sample_count) {
+            Ok(view) => Some(view),
+            Err(e) => {
+                log::error!("Failed to create depth texture: {}", e);
+                None
+            }
+        }
+    };
 
-                            (wgt::BindingType::Buffer {
-                                ty: wgt::BufferBindingType::Uniform,
-                                has_dynamic_offset: false,
-                                min_binding_size,
-                            }, BindingLayoutType::UniformBuffer, Some(struct_size), var_name.clone())
-                        }
-                        naga::AddressSpace::Storage { access: _ } => {
-                            // Storage buffer (like wgpu-mc uses for uniforms/projection)
-                            let type_layout = layouter[global_var.ty];
-                            let struct_size = type_layout.to_stride() as u64;
+    // Extract the output texture ID from the color view for main framebuffer tracking
+    // This will be set as the main framebuffer AFTER the render pass completes
+    // IMPORTANT: Use our reliable view-to-texture mapping instead of potentially stale TextureViewInfo
+    // For MSAA passes the resolve target holds the final image, so track that instead
+    let (output_view, output_view_handle) = if resolve_view.is_some() {
+        (resolve_view, resolve_view_handle)
+    } else {
+        (color_view, color_view_handle)
+    };
+    let output_texture = output_view.and_then(|resolved_view| {
+        // Use our context's view-to-texture mapping for reliable lookups
+        let tex_from_context = device.context().get_texture_from_view(resolved_view);
 
-                            log::debug!("Storage buffer at binding {}: size = {} bytes",
-                                       binding.binding, struct_size);
+        // Fallback: check TextureViewInfo if not in our mapping (for externally created views)
+        if tex_from_context.is_some() {
+            tex_from_context
+        } else {
+            HANDLES.get_texture_view_info(output_view_handle as u64)
+                .and_then(|view_info| {
+                    if view_info.id == resolved_view {
+                        log::debug!("beginRenderPass: Using TextureViewInfo fallback: texture {:?} (view={:?})",
+                            view_info.texture_id, view_info.id);
+                        Some(view_info.texture_id)
+                    } else {
+                        log::warn!("beginRenderPass: Stale TextureViewInfo! Stored view_id={:?} but resolved={:?}",
+                            view_info.id, resolved_view);
+                        None
+                    }
+                })
+        }
+    }).inspect(|&tex_id| {
+        log::debug!("beginRenderPass: output texture will be {:?} (from view={:?})", tex_id, output_view.unwrap());
+    });
 
-                            let min_binding_size = NonZeroU64::new(struct_size);
+    // **CRITICAL FIX:** Auto-clear uninitialized textures
+    // If texture hasn't been rendered to before, automatically clear it
+    // This prevents black screen from LOAD on uninitialized textures
+    let (do_clear_color, clear_color_argb) = if let Some(tex_id) = output_texture {
+        let mut initialized = device.initialized_textures.lock();
+        if !do_clear_color && !initialized.contains(&tex_id) {
+            log::debug!("Auto-clearing uninitialized texture {:?} (first use)", tex_id);
+            initialized.insert(tex_id);
+            (true, 0xFF000000) // Clear to opaque black
+        } else {
+            (do_clear_color, clear_color_argb)
+        }
+    } else {
+        (do_clear_color, clear_color_argb)
+    };
 
-                            (wgt::BindingType::Buffer {
-                                ty: wgt::BufferBindingType::Storage { read_only: true },
-                                has_dynamic_offset: false,
-                                min_binding_size,
-                            }, BindingLayoutType::StorageBuffer, Some(struct_size), var_name.clone())
-                        }
-                        naga::AddressSpace::Handle => {
-                            // Check if it's a texture or sampler
-                            match &ty.inner {
-                                naga::TypeInner::Image { dim, arrayed, class: _ } => {
-                                    // Convert naga dimension to wgpu dimension
-                                    let view_dimension = match (dim, arrayed) {
-                                        (naga::ImageDimension::D1, false) => wgt::TextureViewDimension::D1,
-                                        (naga::ImageDimension::D2, false) => wgt::TextureViewDimension::D2,
-                                        (naga::ImageDimension::D2, true) => wgt::TextureViewDimension::D2Array,
-                                        (naga::ImageDimension::D3, _) => wgt::TextureViewDimension::D3,
-                                        (naga::ImageDimension::Cube, false) => wgt::TextureViewDimension::Cube,
-                                        (naga::ImageDimension::Cube, true) => wgt::TextureViewDimension::CubeArray,
-                                        _ => wgt::TextureViewDimension::D2, // Default fallback
-                                    };
-                                    log::info!("Found texture at binding {}: dimension {:?} (naga dim={:?}, arrayed={}), name={:?})", binding.binding, view_dimension, dim, arrayed, var_name);
-                                    (wgt::BindingType::Texture {
-                                        sample_type: wgt::TextureSampleType::Float { filterable: true },
-                                        view_dimension,
-                                        multisampled: false,
-                                    }, BindingLayoutType::Texture, None, var_name.clone())
-                                }
-                                naga::TypeInner::Sampler { .. } => {
-                                    log::info!("Found sampler at binding {}: name={:?}", binding.binding, var_name);
-                                    (wgt::BindingType::Sampler(wgt::SamplerBindingType::Filtering),
-                                     BindingLayoutType::Sampler, None, var_name.clone())
-                                }
-                                _ => continue, // Skip unsupported types
-                            }
-                        }
-                        _ => continue, // Skip other address spaces
-                    };
+    log::debug!("beginRenderPass: should_clear_color={}, should_clear_depth={}, output_texture={:?}",
+        do_clear_color, should_clear_depth != 0, output_texture);
 
-                    // Always use VERTEX | FRAGMENT for maximum compatibility
-                    // (even if shader only uses it in one stage)
-                    let visibility = wgt::ShaderStages::VERTEX | wgt::ShaderStages::FRAGMENT;
+    let color_format = HANDLES.get_texture_view_info(color_view_handle as u64).map(|info| info.format);
 
-                    bindings.entry(binding.binding)
-                        .and_modify(|(e, _, min_sz, name)| {
-                            e.visibility |= visibility;
-                            // Keep the larger min_binding_size if both shaders define it
-                            if let Some(new_size) = min_size {
-                                *min_sz = Some(min_sz.map_or(new_size, |old| old.max(new_size)));
-                            }
-                            // Prefer non-None variable name
-                            if name.is_none() && var_name.is_some() {
-                                *name = var_name.clone();
-                            }
-                        })
-                        .or_insert((wgt::BindGroupLayoutEntry {
-                            binding: binding.binding,
-                            visibility,
-                            ty: binding_type,
-                            count: None,
-                        }, layout_type, min_size, binding_var_name));
-                }
-            }
+    // Without push constants, immediates go through a uniform buffer bound in every pass
+    let immediates_fallback = match device.immediates_fallback() {
+        Ok(fallback) => fallback,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create render pass: {}", e));
+            return 0;
         }
     };
 
-    // Extract bindings from both shaders
-    extract_bindings(vertex_module, &vertex_layouter, wgt::ShaderStages::VERTEX);
-    extract_bindings(fragment_module, &fragment_layouter, wgt::ShaderStages::FRAGMENT);
+    // Create render pass state
+    match render_pass::RenderPassState::new(
+        device.context().clone(),
+        device.id(),
+        device.queue_id(),
+        color_view,
+        extra_color_views,
+        depth_view,
+        output_texture, // Pass output texture for main framebuffer tracking
+        resolve_view,
+        sample_count,
+        occlusion_queries,
+        do_clear_color,
+        clear_color_argb,
+        should_clear_depth != 0,
+        clear_depth,
+        clear_stencil as u32,
+        width as u32,
+        height as u32,
+    ) {
+        Ok(mut state) => {
+            state.set_color_format(color_format);
+            if device.srgb_clear_colors() {
+                state.decode_srgb_clear_color();
+            }
+            if let Some(fallback) = immediates_fallback {
+                state.set_immediates_fallback(fallback);
+            }
+            // Box the state and return as pointer
+            let boxed = Box::new(state);
+            let ptr = Box::into_raw(boxed);
+            log::debug!("Created render pass at {:?}", ptr);
+            ptr as jlong
+        }
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create render pass: {}", e));
+            0
+        }
+    }
+}
 
-    // Log final bindings after merging
-    log::info!("Final merged bindings: {} entries", bindings.len());
-    for (binding_num, (_entry, ty, _min_size, var_name)) in &bindings {
-        log::info!("  Binding {}: {:?}, name: {:?}", binding_num, ty, var_name);
+/// Set pipeline in render pass
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_setPipeline(
+    mut env: JNIEnv,
+    _class: JClass,
+    _device_ptr: jlong,
+    render_pass_ptr: jlong,
+    pipeline_handle: jlong,
+) {
+    if render_pass_ptr == 0 {
+        report_recoverable_error(&mut env, null_render_pass("setPipeline"));
+        return;
     }
 
-    let global = context.inner();
+    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
 
-    // Create single bind group layout for group 0
-    let layout_entries: Vec<wgt::BindGroupLayoutEntry> = bindings.values()
-        .map(|(e, _, _, _)| e.clone())
-        .collect();
+    // Get pipeline info to extract depth write mode
+    if let Some(pipeline_info) = HANDLES.get_render_pipeline_info(pipeline_handle as u64) {
+        let has_depth_output = !matches!(pipeline_info.depth_format,
+            resource_handles::PipelineDepthFormat::None);
+
+        state.check_target_format(&pipeline_info.shader_name, pipeline_info.target_format);
+        if let Err(e) = state.record_set_pipeline(render_pass::PipelineBinding::from(&pipeline_info)) {
+            report_recoverable_error(&mut env, e);
+            return;
+        }
+        state.set_immediate_ranges(pipeline_info.immediate_ranges);
+        state.set_vertex_stride(pipeline_info.vertex_stride);
+        debug_throttled!("Recorded setPipeline (pipeline={}) depth_write={}, depth_test={}, has_depth={}",
+            pipeline_handle, pipeline_info.depth_write_enabled,
+            pipeline_info.depth_test_enabled, has_depth_output);
+    } else {
+        match HANDLES.render_pipeline_status(pipeline_handle as u64) {
+            resource_handles::PipelineStatus::Pending => {
+                state.record_pending_pipeline();
+                debug_throttled!("Pipeline {} is still compiling, skipping its draws", pipeline_handle);
+            }
+            resource_handles::PipelineStatus::Failed(error) => {
+                state.record_pending_pipeline();
+                report_recoverable_error(&mut env, BasaltError::Validation(format!(
+                    "Pipeline {} failed to compile, skipping its draws: {}", pipeline_handle, error
+                )));
+            }
+            _ => report_recoverable_error(&mut env, BasaltError::InvalidHandle {
+                handle_type: "pipeline".into(),
+                handle: pipeline_handle as u64,
+            }),
+        }
+    }
+}
 
-    let bgl_desc = binding_model::BindGroupLayoutDescriptor {
-        label: Some(Cow::Borrowed("Pipeline Bind Group Layout (group 0)")),
-        entries: Cow::Owned(layout_entries),
-    };
+/// Whether a pipeline created in the background is usable yet
+///
+/// Throws `IllegalStateException` if its compilation failed and
+/// `IllegalArgumentException` for an unknown handle.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_isPipelineReady0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    pipeline_handle: jlong,
+) -> jboolean {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
 
-    let (bgl_id, bgl_error) = global.device_create_bind_group_layout(device_id, &bgl_desc, None);
+    match HANDLES.render_pipeline_status(pipeline_handle as u64) {
+        resource_handles::PipelineStatus::Ready => 1,
+        resource_handles::PipelineStatus::Pending => 0,
+        resource_handles::PipelineStatus::Failed(error) => {
+            let _ = env.throw_new("java/lang/IllegalStateException", format!("Pipeline failed to compile: {}", error));
+            0
+        }
+        resource_handles::PipelineStatus::Unknown => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid pipeline handle: {}", pipeline_handle));
+            0
+        }
+    }
+}
 
-    if let Some(e) = bgl_error {
-        return Err(BasaltError::resource_creation(
-            "bind group layout",
-            format!("Failed to create bind group layout: {:?}", e)
-        ));
+/// Derive the two pipelines of a depth pre-pass from a color pipeline
+///
+/// Returns `[prepass, shading]` handles: `prepass` writes depth only and goes
+/// in a render pass without color attachments, `shading` is the color
+/// pipeline with a depth-equal test for the pass that loads that depth.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createDepthPrepassPipelines0<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    device_ptr: jlong,
+    pipeline_handle: jlong,
+) -> JLongArray<'local> {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return JLongArray::default();
     }
 
-    log::debug!("Created bind group layout for group 0: {:?}", bgl_id);
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let Some(info) = HANDLES.get_render_pipeline_info(pipeline_handle as u64) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid pipeline handle: {}", pipeline_handle));
+        return JLongArray::default();
+    };
+    let Some(color) = device.pipeline_cache.find_render_pipeline(info.id) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException",
+            format!("Pipeline {} wasn't created through the pipeline cache", info.shader_name));
+        return JLongArray::default();
+    };
 
-    // Collect binding layouts (group is always 0, omitted)
-    let binding_layouts: Vec<BindingLayoutEntry> = bindings.iter()
-        .map(|(binding, (entry, ty, min_size, var_name))| {
-            let expected_dimension = if let wgt::BindingType::Texture { view_dimension, .. } = entry.ty {
-                Some(view_dimension)
-            } else {
-                None
+    let (prepass, shading) = match device.pipeline_cache.depth_prepass_pipelines(
+        device.context(),
+        device.id(),
+        &color,
+        &info.shader_name,
+    ) {
+        Ok(pipelines) => pipelines,
+        Err(e) => {
+            let class = match e {
+                BasaltError::InvalidParameter { .. } => "java/lang/IllegalArgumentException",
+                _ => "java/lang/RuntimeException",
             };
-            BindingLayoutEntry {
-                binding: *binding,
-                ty: *ty,
-                min_binding_size: *min_size,
-                expected_dimension,
-                variable_name: var_name.clone(),
-            }
-        })
-        .collect();
-
-    log::info!("Creating pipeline layout with {} bindings:", binding_layouts.len());
-    for (i, layout) in binding_layouts.iter().enumerate() {
-        log::info!("  [{}] binding={}, ty={:?}, var_name={:?}",
-            i, layout.binding, layout.ty, layout.variable_name);
-    }
-
-    // Create pipeline layout with single bind group
-    let pl_desc = binding_model::PipelineLayoutDescriptor {
-        label: Some(Cow::Borrowed("Pipeline Layout")),
-        bind_group_layouts: Cow::Owned(vec![bgl_id]),
-        // No immediates - using uniform buffers via bind groups instead
-        immediate_size: 0,
+            let _ = env.throw_new(class, format!("Failed to derive depth pre-pass for {}: {}", info.shader_name, e));
+            return JLongArray::default();
+        }
     };
 
-    let (pl_id, pl_error) = global.device_create_pipeline_layout(device_id, &pl_desc, None);
+    let register = |pipeline_info: resource_handles::RenderPipelineInfo| {
+        let handle = HANDLES.reserve_render_pipeline();
+        HANDLES.complete_render_pipeline(handle, pipeline_info);
+        handle as jlong
+    };
+    let handles = [
+        register(resource_handles::RenderPipelineInfo {
+            id: prepass.pipeline_id,
+            depth_write_enabled: true,
+            depth_test_enabled: true,
+            shader_name: format!("{} (depth pre-pass)", info.shader_name),
+            color_target_count: 0,
+            ..info.clone()
+        }),
+        register(resource_handles::RenderPipelineInfo {
+            id: shading.pipeline_id,
+            depth_write_enabled: false,
+            depth_test_enabled: true,
+            shader_name: format!("{} (depth equal)", info.shader_name),
+            ..info.clone()
+        }),
+    ];
+
+    let Ok(array) = env.new_long_array(handles.len() as i32) else {
+        return JLongArray::default();
+    };
+    if env.set_long_array_region(&array, 0, &handles).is_err() {
+        return JLongArray::default();
+    }
+    array
+}
 
-    if let Some(e) = pl_error {
-        return Err(BasaltError::resource_creation(
-            "pipeline layout",
-            format!("Failed to create pipeline layout: {:?}", e)
-        ));
+/// Set the stencil reference value used by subsequent draws
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_setStencilReference(
+    mut env: JNIEnv,
+    _class: JClass,
+    _device_ptr: jlong,
+    render_pass_ptr: jlong,
+    reference: jint,
+) {
+    if render_pass_ptr == 0 {
+        report_recoverable_error(&mut env, null_render_pass("setStencilReference"));
+        return;
     }
 
-    Ok((bgl_id, pl_id, binding_layouts))
+    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
+    state.record_set_stencil_reference(reference as u32);
 }
 
-/// Create a render pipeline from pre-converted WGSL shaders
-/// Uses PipelineCache for fast shader compilation and pipeline reuse
+/// Set vertex buffer
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createNativePipelineFromWgsl(
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_setVertexBuffer(
     mut env: JNIEnv,
     _class: JClass,
     device_ptr: jlong,
-    vertex_shader: JString,
-    fragment_shader: JString,
-    vertex_format: jint,
-    primitive_topology: jint,
-    depth_test_enabled: jboolean,
-    depth_write_enabled: jboolean,
-    depth_compare: jint,
-    blend_enabled: jboolean,
-    blend_src_color_factor: jint,
-    blend_dst_color_factor: jint,
-    blend_src_alpha_factor: jint,
-    blend_dst_alpha_factor: jint,
-    shader_name: JString,
-) -> jlong {
-    // Validate device pointer
-    if device_ptr == 0 {
-        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
-        return 0;
+    render_pass_ptr: jlong,
+    slot: jint,
+    buffer_handle: jlong,
+    offset: jlong,
+) {
+    debug_throttled!("[BassaltNative] setVertexBuffer called: slot={}, buffer_handle={}, offset={}", slot, buffer_handle, offset);
+
+    if render_pass_ptr == 0 {
+        report_recoverable_error(&mut env, null_render_pass("setVertexBuffer"));
+        return;
+    }
+
+    if buffer_handle == 0 {
+        report_recoverable_error(&mut env, BasaltError::NullPointer { context: "setVertexBuffer: buffer".into() });
+        return;
     }
 
-    // Extract shader name from Java
-    let shader_name_str: String = if shader_name.is_null() {
-        "unknown".to_string()
+    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
+
+    let max_vertex_buffers = if device_ptr != 0 {
+        let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+        device.get_limits().max_vertex_buffers
     } else {
-        match env.get_string(&shader_name) {
-            Ok(s) => s.into(),
-            Err(_) => "unknown".to_string(),
-        }
+        u32::MAX
     };
+    if let Err(e) = state.validate_vertex_buffer_slot(slot, max_vertex_buffers) {
+        report_recoverable_error(&mut env, e);
+        return;
+    }
 
-    // Get the device from the pointer - use the SAME device that was created during initialization
-    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
-    let device_context = device.context();
-    let device_id = device.id();
+    if let Some(buffer) = HANDLES.get_buffer_info(buffer_handle as u64) {
+        // Bind the rest of the buffer explicitly so draws can be checked against its size
+        let size = std::num::NonZero::new(buffer.size.saturating_sub(offset as u64));
+        state.record_set_vertex_buffer(slot as u32, buffer.id, offset as u64, size);
+        debug_throttled!("[BassaltNative] setVertexBuffer: slot={}, buffer={:?}, offset={}", slot, buffer.id, offset);
+    } else {
+        report_recoverable_error(&mut env, BasaltError::InvalidHandle {
+            handle_type: "vertex buffer".into(),
+            handle: buffer_handle as u64,
+        });
+    }
+}
 
-    // Check for null shaders
-    if vertex_shader.is_null() {
-        let _ = env.throw_new("java/lang/IllegalArgumentException", "Vertex shader string is null");
-        return 0;
+/// Set index buffer
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_setIndexBuffer(
+    mut env: JNIEnv,
+    _class: JClass,
+    _device_ptr: jlong,
+    render_pass_ptr: jlong,
+    buffer_handle: jlong,
+    index_type: jint,
+    offset: jlong,
+) {
+    debug_throttled!("[BassaltNative] setIndexBuffer called: buffer_handle={}, index_type={}, offset={}", buffer_handle, index_type, offset);
+
+    if render_pass_ptr == 0 {
+        report_recoverable_error(&mut env, null_render_pass("setIndexBuffer"));
+        return;
     }
 
-    if fragment_shader.is_null() {
-        let _ = env.throw_new("java/lang/IllegalArgumentException", "Fragment shader string is null");
-        return 0;
+    if buffer_handle == 0 {
+        report_recoverable_error(&mut env, BasaltError::NullPointer { context: "setIndexBuffer: buffer".into() });
+        return;
     }
 
-    // Extract WGSL strings from Java
-    let vertex_wgsl: String = match env.get_string(&vertex_shader) {
-        Ok(s) => s.into(),
-        Err(e) => {
-            let _ = env.throw_new("java/lang/IllegalArgumentException", &format!("Invalid vertex shader string: {}", e));
-            return 0;
-        }
-    };
+    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
 
-    let fragment_wgsl: String = match env.get_string(&fragment_shader) {
-        Ok(s) => s.into(),
-        Err(e) => {
-            let _ = env.throw_new("java/lang/IllegalArgumentException", &format!("Invalid fragment shader string: {}", e));
-            return 0;
+    let index_format = match index_type {
+        0 => wgt::IndexFormat::Uint16,
+        1 => wgt::IndexFormat::Uint32,
+        _ => {
+            report_recoverable_error(
+                &mut env,
+                BasaltError::invalid_parameter("index_type", format!("setIndexBuffer: invalid index type {}", index_type)),
+            );
+            return;
         }
     };
 
-    // Parse WGSL shaders once for layout creation and caching
-    log::debug!("Parsing WGSL shaders for layout reflection...");
-    let vertex_module = match shader::parse_wgsl_named(&vertex_wgsl, "vertex_shader") {
-        Ok(module) => module,
-        Err(e) => {
-            let msg = format!("Failed to parse vertex WGSL: {}", e);
-            log::error!("{}", msg);
-            let _ = env.throw_new("java/lang/RuntimeException", &msg);
-            return 0;
+    if let Some(buffer_id) = HANDLES.get_buffer(buffer_handle as u64) {
+        // **VALIDATION**: Calculate and store max index count for validation during draw calls
+        if let Some(buffer_info) = HANDLES.get_buffer_info(buffer_handle as u64) {
+            let bytes_per_index = match index_format {
+                wgt::IndexFormat::Uint16 => 2,
+                wgt::IndexFormat::Uint32 => 4,
+            };
+            // Check if offset is within buffer bounds
+            if offset as u64 >= buffer_info.size {
+                report_recoverable_error(
+                    &mut env,
+                    BasaltError::invalid_parameter(
+                        "offset",
+                        format!("setIndexBuffer: offset {} exceeds buffer size {}", offset, buffer_info.size),
+                    ),
+                );
+                return;
+            }
+            let available_bytes = buffer_info.size - offset as u64;
+            let max_indices = available_bytes / bytes_per_index as u64;
+            state.set_max_index_count(max_indices);
+            debug_throttled!("setIndexBuffer: Max indices = {} (buffer size={}, offset={})",
+                max_indices, buffer_info.size, offset);
         }
-    };
 
-    let fragment_module = match shader::parse_wgsl_named(&fragment_wgsl, "fragment_shader") {
-        Ok(module) => module,
-        Err(e) => {
-            let msg = format!("Failed to parse fragment WGSL: {}", e);
-            log::error!("{}", msg);
-            let _ = env.throw_new("java/lang/RuntimeException", &msg);
-            return 0;
-        }
-    };
-    log::debug!("WGSL shaders parsed for layout");
+        state.record_set_index_buffer(buffer_id, index_format, offset as u64, None);
+        debug_throttled!("[BassaltNative] setIndexBuffer: buffer={:?}, index_format={:?}", buffer_id, index_format);
+    } else {
+        report_recoverable_error(&mut env, BasaltError::InvalidHandle {
+            handle_type: "index buffer".into(),
+            handle: buffer_handle as u64,
+        });
+    }
+}
 
-    // Create pipeline layout from shader reflection (needed for cache key)
-    let (bind_group_layout_id, pipeline_layout_id, binding_layouts) = match create_layout_from_shaders(
-        device_context,
-        device_id,
-        &vertex_module,
-        &fragment_module,
-    ) {
-        Ok(layouts) => layouts,
-        Err(e) => {
-            let msg = format!("Failed to create pipeline layout from shaders: {:?}", e);
-            log::error!("{}", msg);
-            let _ = env.throw_new("java/lang/RuntimeException", &msg);
-            return 0;
+/// Draw indexed
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_drawIndexed(
+    mut env: JNIEnv,
+    _class: JClass,
+    _device_ptr: jlong,
+    render_pass_ptr: jlong,
+    index_count: jint,
+    instance_count: jint,
+    first_index: jint,
+    base_vertex: jint,
+    first_instance: jint,
+) {
+    debug_throttled!("NATIVE drawIndexed called: render_pass_ptr={}, indices={}", render_pass_ptr, index_count);
+
+    if render_pass_ptr == 0 {
+        report_recoverable_error(&mut env, null_render_pass("drawIndexed"));
+        return;
+    }
+
+    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
+
+    // **VALIDATION**: Check if index count is within buffer bounds
+    if let Some(max_indices) = state.get_max_index_count() {
+        let last_index = first_index as u64 + index_count as u64;
+        if last_index > max_indices {
+            report_recoverable_error(
+                &mut env,
+                BasaltError::invalid_parameter(
+                    "index_count",
+                    format!("drawIndexed: index count {} + first_index {} exceeds buffer size {}",
+                        index_count, first_index, max_indices),
+                ),
+            );
+            return;
         }
-    };
-    log::debug!("Pipeline layout created for cache");
+    }
 
-    // Map pipeline parameters
-    let primitive_topology = match primitive_topology as u32 {
-        0 => wgt::PrimitiveTopology::PointList,
-        1 => wgt::PrimitiveTopology::LineList,
-        2 => wgt::PrimitiveTopology::LineStrip,
-        3 => wgt::PrimitiveTopology::TriangleList,
-        4 => wgt::PrimitiveTopology::TriangleStrip,
-        _ => wgt::PrimitiveTopology::TriangleList,
-    };
+    state.record_draw_indexed(
+        index_count as u32,
+        instance_count as u32,
+        first_index as u32,
+        base_vertex,
+        first_instance as u32,
+    );
 
-    let depth_compare = match depth_compare as u32 {
-        0 => wgt::CompareFunction::Never,
-        1 => wgt::CompareFunction::Less,
-        2 => wgt::CompareFunction::Equal,
-        3 => wgt::CompareFunction::LessEqual,
-        4 => wgt::CompareFunction::Greater,
-        5 => wgt::CompareFunction::NotEqual,
-        6 => wgt::CompareFunction::GreaterEqual,
-        7 => wgt::CompareFunction::Always,
-        _ => wgt::CompareFunction::Less,
-    };
+    debug_throttled!("NATIVE drawIndexed: Recorded draw (indices={}, instances={}, first={}, base={}, firstInst={})",
+        index_count, instance_count, first_index, base_vertex, first_instance);
+}
 
-    // Detect post-processing shaders - they should use alpha blending to avoid overwriting GUI
-    // Post-processing shaders typically:
-    // 1. Have "minecraft:post/" in comments (original shader path)
-    // 2. Use "input_texture" or "source_texture" (sampling from previous pass)
-    // 3. Are blur/outline effects that need to blend with existing content
-    let is_post_processing = fragment_wgsl.contains("minecraft:post/") ||
-                             fragment_wgsl.contains("input_texture") ||
-                             fragment_wgsl.contains("source_texture");
+/// Draw (non-indexed)
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_draw(
+    mut env: JNIEnv,
+    _class: JClass,
+    _device_ptr: jlong,
+    render_pass_ptr: jlong,
+    vertex_count: jint,
+    instance_count: jint,
+    first_vertex: jint,
+    first_instance: jint,
+) {
+    if render_pass_ptr == 0 {
+        report_recoverable_error(&mut env, null_render_pass("draw"));
+        return;
+    }
 
-    // Force blending on for post-processing shaders to prevent overwriting GUI
-    let effective_blend_enabled = if is_post_processing {
-        log::info!("Detected post-processing shader, forcing alpha blending enabled");
-        true
-    } else {
-        blend_enabled != 0
-    };
+    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
 
-    // Depth format - check if fragment shader writes depth, otherwise disable depth testing
-    // GUI shaders and other 2D shaders don't write depth, so they shouldn't have depth state
-    // Note: fragment_module was already parsed above, reuse it instead of re-parsing
-    let shader_has_depth_output = shader_writes_depth(&fragment_module);
-    let depth_format = if shader_has_depth_output {
-        resource_handles::PipelineDepthFormat::Depth32Float
-    } else {
-        log::info!("Fragment shader does not write depth, disabling depth testing for this pipeline");
-        resource_handles::PipelineDepthFormat::None
-    };
+    state.record_draw(
+        vertex_count as u32,
+        instance_count as u32,
+        first_vertex as u32,
+        first_instance as u32,
+    );
 
-    // Use PipelineCache for fast pipeline creation
-    // The cache will:
-    // 1. Check if we've seen this (vertex_shader, fragment_shader, topology, depth, blend) combo before
-    // 2. If cached, return immediately
-    // 3. If not, compile shaders and create pipeline, then cache for next time
-    let cache_key = pipeline_registry::RenderPipelineKey {
-        vertex_shader_hash: pipeline_registry::PipelineCache::hash_wgsl(&vertex_wgsl),
-        fragment_shader_hash: pipeline_registry::PipelineCache::hash_wgsl(&fragment_wgsl),
-        topology: primitive_topology,
-        depth_test_enabled: depth_test_enabled != 0,
-        depth_write_enabled: depth_write_enabled != 0,
-        depth_compare,
-        blend_enabled: effective_blend_enabled,
-        blend_src_color_factor: if effective_blend_enabled { map_blend_factor_from_jni(blend_src_color_factor) } else { None },
-        blend_dst_color_factor: if effective_blend_enabled { map_blend_factor_from_jni(blend_dst_color_factor) } else { None },
-        blend_src_alpha_factor: if effective_blend_enabled { map_blend_factor_from_jni(blend_src_alpha_factor) } else { None },
-        blend_dst_alpha_factor: if effective_blend_enabled { map_blend_factor_from_jni(blend_dst_alpha_factor) } else { None },
-        target_format: device.swapchain_format(),  // Use actual swapchain format for compatibility
-        depth_format,  // CRITICAL: Include depth format in cache key!
-        depth_bias_constant: 0,  // TODO: Pass from Java when Minecraft uses depth bias
-        depth_bias_slope_scale: 0,  // TODO: Pass from Java when Minecraft uses depth bias (stored as f32 bits)
-    };
+    debug_throttled!("Recorded draw (vertices={}, instances={}, first={}, firstInst={})",
+        vertex_count, instance_count, first_vertex, first_instance);
+}
 
-    let label = format!("NativePipeline_vfmt{}", vertex_format);
+/// Record `count` indirect draws reading arguments from a buffer with INDIRECT usage
+fn record_indirect_draw(
+    env: &mut JNIEnv,
+    call: &str,
+    render_pass_ptr: jlong,
+    buffer_handle: jlong,
+    offset: jlong,
+    count: jint,
+    indexed: bool,
+) {
+    if render_pass_ptr == 0 {
+        report_recoverable_error(env, null_render_pass(call));
+        return;
+    }
 
-    log::debug!("Creating pipeline with vertex_format={}, topology={:?}, label={}, depth_test={}, blend={}",
-        vertex_format, primitive_topology, label, depth_test_enabled, blend_enabled);
-    log::debug!("Checking pipeline cache for key hash {:x}...", pipeline_registry::PipelineCache::hash_key(&cache_key));
+    if offset < 0 || count < 0 {
+        report_recoverable_error(
+            env,
+            BasaltError::invalid_parameter("offset", format!("{}: negative offset {} or count {}", call, offset, count)),
+        );
+        return;
+    }
 
-    let cached_pipeline = match device.pipeline_cache.get_or_create_render_pipeline(
-        device_context,
-        device_id,
-        cache_key,
-        &vertex_wgsl,
-        &fragment_wgsl,
-        pipeline_layout_id,
-        bind_group_layout_id,
-        binding_layouts.clone(),
-        depth_format,
-        vertex_format as usize,
-        &label,
-    ) {
-        Ok(pipeline) => pipeline,
-        Err(e) => {
-            let msg = format!("Failed to create pipeline (via cache): {:?}", e);
-            log::error!("{}", msg);
-            let _ = env.throw_new("java/lang/RuntimeException", &msg);
-            return 0;
-        }
+    let Some(buffer_id) = HANDLES.get_buffer(buffer_handle as u64) else {
+        report_recoverable_error(env, BasaltError::InvalidHandle {
+            handle_type: "indirect buffer".into(),
+            handle: buffer_handle as u64,
+        });
+        return;
     };
 
-    // Log cache statistics
-    let stats = device.pipeline_cache.stats();
-    log::debug!("Pipeline cache stats - shader_hits: {}, shader_misses: {}, pipeline_hits: {}, pipeline_misses: {}, total_pipelines: {}",
-        stats.shader_hits, stats.shader_misses, stats.pipeline_hits, stats.pipeline_misses, stats.total_pipelines);
+    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
+    if let Err(e) = state.record_multi_draw_indirect(buffer_id, offset as u64, count as u32, indexed) {
+        report_recoverable_error(env, e);
+    }
+}
 
-    let pipeline_id = cached_pipeline.pipeline_id;
-    log::debug!("Render pipeline created successfully via cache!");
+/// Draw with arguments read from an indirect buffer
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_drawIndirect(
+    mut env: JNIEnv,
+    _class: JClass,
+    _device_ptr: jlong,
+    render_pass_ptr: jlong,
+    buffer_handle: jlong,
+    offset: jlong,
+) {
+    record_indirect_draw(&mut env, "drawIndirect", render_pass_ptr, buffer_handle, offset, 1, false);
+}
 
-    let num_bindings = binding_layouts.len();
-    let handle = HANDLES.insert_render_pipeline(
-        pipeline_id,
-        bind_group_layout_id,
-        binding_layouts,
-        depth_format,
-        depth_write_enabled != 0,  // Convert jboolean to bool
-        depth_test_enabled != 0,   // Convert jboolean to bool
-        shader_name_str.clone(),
-    );
-    log::debug!("Created render pipeline via cache with handle {} (shader: {}, bgl: {:?}, bindings: {}, depth: {:?})",
-               handle, shader_name_str, bind_group_layout_id, num_bindings, depth_format);
-    handle as jlong
+/// Draw indexed with arguments read from an indirect buffer
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_drawIndexedIndirect(
+    mut env: JNIEnv,
+    _class: JClass,
+    _device_ptr: jlong,
+    render_pass_ptr: jlong,
+    buffer_handle: jlong,
+    offset: jlong,
+) {
+    record_indirect_draw(&mut env, "drawIndexedIndirect", render_pass_ptr, buffer_handle, offset, 1, true);
 }
 
-// ============================================================================
-// RENDER PASS OPERATIONS
-// ============================================================================
+/// Draw indexed `count` times with consecutive argument structs from an indirect buffer
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_multiDrawIndexedIndirect(
+    mut env: JNIEnv,
+    _class: JClass,
+    _device_ptr: jlong,
+    render_pass_ptr: jlong,
+    buffer_handle: jlong,
+    offset: jlong,
+    count: jint,
+) {
+    record_indirect_draw(&mut env, "multiDrawIndexedIndirect", render_pass_ptr, buffer_handle, offset, count, true);
+}
 
-/// Begin a render pass
+/// Set scissor rect
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_beginRenderPass(
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_setScissorRect(
     mut env: JNIEnv,
     _class: JClass,
-    device_ptr: jlong,
-    color_view_handle: jlong,
-    depth_view_handle: jlong,
-    should_clear_color: jboolean,
-    clear_color: jint,
-    should_clear_depth: jboolean,
-    clear_depth: jfloat,
-    clear_stencil: jint,
+    _device_ptr: jlong,
+    render_pass_ptr: jlong,
+    x: jint,
+    y: jint,
     width: jint,
     height: jint,
-) -> jlong {
-    if device_ptr == 0 {
-        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
-        return 0;
+) {
+    if render_pass_ptr == 0 {
+        report_recoverable_error(&mut env, null_render_pass("setScissorRect"));
+        return;
     }
 
-    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
-
-    // Look up texture view IDs from handles
-    let color_view = if color_view_handle != 0 {
-        let view = HANDLES.get_texture_view(color_view_handle as u64);
-        log::debug!("beginRenderPass: color_view_handle={}, resolved={:?}", color_view_handle, view);
-        view
-    } else {
-        log::warn!("beginRenderPass: No color view handle provided!");
-        None
-    };
-
-    // Use clear parameters from Java
-    let do_clear_color = should_clear_color != 0;
-    let clear_color_argb = clear_color as u32;
-
-    // Always need depth view since pipelines always have depth_stencil
-    // If MC doesn't provide one, create a matching-size depth texture
-    let depth_view = if depth_view_handle != 0 {
-        HANDLES.get_texture_view(depth_view_handle as u64)
-    } else {
-        // Create depth texture matching color texture dimensions
-        log::debug!("MC didn't provide depth texture, creating one for {}x{}", width, height);
-        match device.get_or_create_depth_view(width as u32, height as u32) {
-            Ok(view) => Some(view),
-            Err(e) => {
-                log::error!("Failed to create depth texture: {}", e);
-                None
-            }
-        }
-    };
+    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
 
-    // Extract the output texture ID from the color view for main framebuffer tracking
-    // This will be set as the main framebuffer AFTER the render pass completes
-    // IMPORTANT: Use our reliable view-to-texture mapping instead of potentially stale TextureViewInfo
-    let output_texture = color_view.and_then(|resolved_view| {
-        // Use our context's view-to-texture mapping for reliable lookups
-        let tex_from_context = device.context().get_texture_from_view(resolved_view);
+    state.record_set_scissor_rect(x as u32, y as u32, width as u32, height as u32);
 
-        // Fallback: check TextureViewInfo if not in our mapping (for externally created views)
-        if tex_from_context.is_some() {
-            tex_from_context
-        } else {
-            HANDLES.get_texture_view_info(color_view_handle as u64)
-                .and_then(|view_info| {
-                    if view_info.id == resolved_view {
-                        log::debug!("beginRenderPass: Using TextureViewInfo fallback: texture {:?} (view={:?})",
-                            view_info.texture_id, view_info.id);
-                        Some(view_info.texture_id)
-                    } else {
-                        log::warn!("beginRenderPass: Stale TextureViewInfo! Stored view_id={:?} but resolved={:?}",
-                            view_info.id, resolved_view);
-                        None
-                    }
-                })
-        }
-    }).map(|tex_id| {
-        log::debug!("beginRenderPass: output texture will be {:?} (from view={:?})", tex_id, color_view.unwrap());
-        tex_id
-    });
+    log::debug!("Recorded setScissorRect (x={}, y={}, width={}, height={})",
+        x, y, width, height);
+}
 
-    // **CRITICAL FIX:** Auto-clear uninitialized textures
-    // If texture hasn't been rendered to before, automatically clear it
-    // This prevents black screen from LOAD on uninitialized textures
-    let (do_clear_color, clear_color_argb) = if let Some(tex_id) = output_texture {
-        let mut initialized = device.initialized_textures.lock();
-        if !do_clear_color && !initialized.contains(&tex_id) {
-            log::debug!("Auto-clearing uninitialized texture {:?} (first use)", tex_id);
-            initialized.insert(tex_id);
-            (true, 0xFF000000) // Clear to opaque black
-        } else {
-            (do_clear_color, clear_color_argb)
-        }
-    } else {
-        (do_clear_color, clear_color_argb)
-    };
+/// Set the viewport, clamped to the render target
+///
+/// A depth range outside `[0, 1]` or a negative rect is reported through
+/// `getLastError0` and the viewport left unchanged.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_setViewport(
+    mut env: JNIEnv,
+    _class: JClass,
+    _device_ptr: jlong,
+    render_pass_ptr: jlong,
+    x: jfloat,
+    y: jfloat,
+    width: jfloat,
+    height: jfloat,
+    min_depth: jfloat,
+    max_depth: jfloat,
+) {
+    if render_pass_ptr == 0 {
+        report_recoverable_error(&mut env, null_render_pass("setViewport"));
+        return;
+    }
 
-    log::debug!("beginRenderPass: should_clear_color={}, should_clear_depth={}, output_texture={:?}",
-        do_clear_color, should_clear_depth != 0, output_texture);
+    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
 
-    // Create render pass state
-    match render_pass::RenderPassState::new(
-        device.context().clone(),
-        device.id(),
-        device.queue_id(),
-        color_view,
-        depth_view,
-        output_texture, // Pass output texture for main framebuffer tracking
-        do_clear_color,
-        clear_color_argb,
-        should_clear_depth != 0,
-        clear_depth,
-        clear_stencil as u32,
-        width as u32,
-        height as u32,
-    ) {
-        Ok(state) => {
-            // Box the state and return as pointer
-            let boxed = Box::new(state);
-            let ptr = Box::into_raw(boxed);
-            log::debug!("Created render pass at {:?}", ptr);
-            ptr as jlong
-        }
-        Err(e) => {
-            let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to create render pass: {}", e));
-            0
-        }
+    if let Err(e) = state.record_set_viewport(x, y, width, height, min_depth, max_depth) {
+        report_recoverable_error(&mut env, e);
+        return;
     }
+
+    log::debug!("Recorded setViewport (x={}, y={}, width={}, height={}, depth={}..{})",
+        x, y, width, height, min_depth, max_depth);
 }
 
-/// Set pipeline in render pass
+/// Set push constants for per-draw data
+///
+/// This allows passing small amounts of data (up to 128 bytes) directly to shaders
+/// without creating uniform buffers. Useful for:
+/// - Model matrices
+/// - Per-draw colors
+/// - Animation parameters
+///
+/// Without push constants (see `supportsPushConstants0`) the data goes to a
+/// dynamic uniform buffer instead: each draw after a change takes a 256-byte
+/// slot and a bind group rebind, and the slots are uploaded once at
+/// `endRenderPass`. That costs more than a push constant per draw, and a pass
+/// can hold at most 256 distinct blocks before this reports an error.
+///
+/// Writes must fit the device's `max_immediate_size` and the block one of
+/// `stages` declares in the bound pipeline.
+///
+/// # Arguments
+/// * `render_pass_ptr` - The active render pass
+/// * `stages` - `wgt::ShaderStages` bits the data is for: 1 vertex, 2 fragment
+/// * `offset` - Byte offset within the push constant range (must be 4-byte aligned)
+/// * `data` - The data to write (as byte array, must be 4-byte aligned)
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_setPipeline(
-    _env: JNIEnv,
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_setPushConstants(
+    mut env: JNIEnv,
     _class: JClass,
-    _device_ptr: jlong,
+    device_ptr: jlong,
     render_pass_ptr: jlong,
-    pipeline_handle: jlong,
+    stages: jint,
+    offset: jint,
+    data: JByteArray,
 ) {
     if render_pass_ptr == 0 {
+        report_recoverable_error(&mut env, null_render_pass("setPushConstants"));
         return;
     }
 
     let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
 
-    // Get pipeline info to extract depth write mode
-    if let Some(pipeline_info) = HANDLES.get_render_pipeline_info(pipeline_handle as u64) {
-        let has_depth_output = !matches!(pipeline_info.depth_format,
-            resource_handles::PipelineDepthFormat::None);
-
-        state.record_set_pipeline(
-            pipeline_info.id,
-            pipeline_info.depth_write_enabled,
-            pipeline_info.depth_test_enabled,
-            has_depth_output,
-        );
-        log::debug!("Recorded setPipeline (pipeline={}) depth_write={}, depth_test={}, has_depth={}",
-            pipeline_handle, pipeline_info.depth_write_enabled,
-            pipeline_info.depth_test_enabled, has_depth_output);
-    } else {
-        log::error!("Invalid pipeline handle: {}", pipeline_handle);
-    }
-}
-
-/// Set vertex buffer
-#[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_setVertexBuffer(
-    _env: JNIEnv,
-    _class: JClass,
-    _device_ptr: jlong,
-    render_pass_ptr: jlong,
-    slot: jint,
-    buffer_handle: jlong,
-    offset: jlong,
-) {
-    log::debug!("[BassaltNative] setVertexBuffer called: slot={}, buffer_handle={}, offset={}", slot, buffer_handle, offset);
+    // Convert Java byte array to Rust Vec
+    let data_vec: Vec<u8> = match env.convert_byte_array(&data) {
+        Ok(arr) => arr,
+        Err(e) => {
+            report_recoverable_error(&mut env, BasaltError::Jni(format!("Failed to get byte array for push constants: {}", e)));
+            return;
+        }
+    };
 
-    if render_pass_ptr == 0 {
-        log::error!("setVertexBuffer: render_pass_ptr is null!");
+    // Ensure data is 4-byte aligned
+    if !data_vec.len().is_multiple_of(4) {
+        report_recoverable_error(&mut env, BasaltError::invalid_parameter(
+            "data",
+            format!("push constants must be 4-byte aligned, got {} bytes", data_vec.len()),
+        ));
         return;
     }
 
-    if buffer_handle == 0 {
-        log::error!("setVertexBuffer: buffer_handle is null!");
+    if offset < 0 {
+        report_recoverable_error(&mut env, BasaltError::invalid_parameter("offset", format!("negative offset {}", offset)));
         return;
     }
+    let Some(stages) = wgt::ShaderStages::from_bits(stages as u32) else {
+        report_recoverable_error(&mut env, BasaltError::invalid_parameter("stages", format!("unknown stage bits {:#x}", stages)));
+        return;
+    };
 
-    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
+    // Without push constants the fallback block bounds writes instead
+    if device_ptr != 0 {
+        let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+        let max_immediate_size = device.get_limits().max_immediate_size;
+        let end = offset as u64 + data_vec.len() as u64;
+        if device.supports_push_constants() && end > max_immediate_size as u64 {
+            report_recoverable_error(&mut env, BasaltError::invalid_parameter(
+                "offset",
+                format!("push constants {}..{} exceed the device's {} byte limit", offset, end, max_immediate_size),
+            ));
+            return;
+        }
+    }
 
-    if let Some(buffer_id) = HANDLES.get_buffer(buffer_handle as u64) {
-        state.record_set_vertex_buffer(slot as u32, buffer_id, offset as u64, None);
-        log::debug!("[BassaltNative] setVertexBuffer: slot={}, buffer={:?}, offset={}", slot, buffer_id, offset);
-    } else {
-        log::error!("setVertexBuffer: Invalid buffer handle: {}", buffer_handle);
+    if let Err(e) = state.record_set_immediates(stages, offset as u32, data_vec) {
+        report_recoverable_error(&mut env, e);
+        return;
     }
+
+    debug_throttled!("Recorded setPushConstants (stages={:?}, offset={})", stages, offset);
 }
 
-/// Set index buffer
+/// End render pass and submit
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_setIndexBuffer(
-    _env: JNIEnv,
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_endRenderPass(
+    mut env: JNIEnv,
     _class: JClass,
-    _device_ptr: jlong,
+    device_ptr: jlong,
     render_pass_ptr: jlong,
-    buffer_handle: jlong,
-    index_type: jint,
-    offset: jlong,
 ) {
-    log::debug!("[BassaltNative] setIndexBuffer called: buffer_handle={}, index_type={}, offset={}", buffer_handle, index_type, offset);
-
-    if render_pass_ptr == 0 {
-        log::error!("setIndexBuffer: render_pass_ptr is null!");
+    if render_pass_ptr == 0 || device_ptr == 0 {
         return;
     }
 
-    if buffer_handle == 0 {
-        log::error!("setIndexBuffer: buffer_handle is null!");
-        return;
-    }
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
 
-    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
+    // Take ownership of the boxed RenderPassState
+    let mut state = unsafe { Box::from_raw(render_pass_ptr as *mut render_pass::RenderPassState) };
 
-    let index_format = match index_type {
-        0 => wgt::IndexFormat::Uint16,
-        1 => wgt::IndexFormat::Uint32,
-        _ => {
-            log::error!("setIndexBuffer: Invalid index type: {}", index_type);
-            return;
-        }
-    };
+    device.record_elided_state_changes(state.elided_state_changes());
 
-    if let Some(buffer_id) = HANDLES.get_buffer(buffer_handle as u64) {
-        // **VALIDATION**: Calculate and store max index count for validation during draw calls
-        if let Some(buffer_info) = HANDLES.get_buffer_info(buffer_handle as u64) {
-            let bytes_per_index = match index_format {
-                wgt::IndexFormat::Uint16 => 2,
-                wgt::IndexFormat::Uint32 => 4,
-            };
-            // Check if offset is within buffer bounds
-            if offset as u64 >= buffer_info.size {
-                log::error!("setIndexBuffer: offset {} exceeds buffer size {}", offset, buffer_info.size);
-                return;
+    // Record into the frame's encoder, submitted at present - returns the output texture that was rendered
+    let submit_start = std::time::Instant::now();
+    let submit_result = device.record_render_pass(&mut state);
+    device.record_cpu_timing(frame_timing::CpuTimingKind::Submit, submit_start.elapsed());
+
+    match submit_result {
+        Ok(output_texture) => {
+            // Set the main framebuffer AFTER the render pass has successfully executed
+            // This fixes the race condition where present_frame could be called before rendering completes
+            if let Some(texture_id) = output_texture {
+                // The handle lookup scans every texture, so only redo it when the target changes
+                if device.main_framebuffer() != Some(texture_id) {
+                    device.set_main_framebuffer(texture_id, HANDLES.find_texture_info(texture_id));
+                }
+                log::debug!("endRenderPass: Set main framebuffer to {:?} after successful render", texture_id);
+            } else {
+                log::debug!("endRenderPass: No output texture from this render pass");
             }
-            let available_bytes = buffer_info.size - offset as u64;
-            let max_indices = available_bytes / bytes_per_index as u64;
-            state.set_max_index_count(max_indices);
-            log::debug!("setIndexBuffer: Max indices = {} (buffer size={}, offset={})",
-                max_indices, buffer_info.size, offset);
+            log::debug!("Ended render pass at {:?}", render_pass_ptr as *const ());
         }
+        Err(device::RecordPassError::Submit(e)) => {
+            throw_submit_exception(&mut env, device, &e);
+        }
+        Err(device::RecordPassError::Recording(e)) => {
+            report_recoverable_error(&mut env, e);
+        }
+    }
 
-        state.record_set_index_buffer(buffer_id, index_format, offset as u64, None);
-        log::debug!("[BassaltNative] setIndexBuffer: buffer={:?}, index_format={:?}", buffer_id, index_format);
-    } else {
-        log::error!("setIndexBuffer: Invalid buffer handle: {}", buffer_handle);
+    // State is dropped here
+}
+
+/// Whether a submit has reported that the device was lost
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_isDeviceLost0(
+    _env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+) -> jboolean {
+    if device_ptr == 0 {
+        return 0;
     }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    device.is_lost() as jboolean
 }
 
-/// Draw indexed
+/// Decode clear colors from sRGB for sRGB attachments (on by default)
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_drawIndexed(
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_setSrgbClearColors0(
     _env: JNIEnv,
     _class: JClass,
-    _device_ptr: jlong,
-    render_pass_ptr: jlong,
-    index_count: jint,
-    instance_count: jint,
-    first_index: jint,
-    base_vertex: jint,
-    first_instance: jint,
+    device_ptr: jlong,
+    enabled: jboolean,
 ) {
-    log::debug!("NATIVE drawIndexed called: render_pass_ptr={}, indices={}", render_pass_ptr, index_count);
-
-    if render_pass_ptr == 0 {
-        log::error!("NATIVE drawIndexed: render_pass_ptr is 0, returning early!");
+    if device_ptr == 0 {
         return;
     }
 
-    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    device.set_srgb_clear_colors(enabled != 0);
+}
 
-    // **VALIDATION**: Check if index count is within buffer bounds
-    if let Some(max_indices) = state.get_max_index_count() {
-        let last_index = first_index as u64 + index_count as u64;
-        if last_index > max_indices {
-            log::error!("drawIndexed: Index count {} + first_index {} exceeds buffer size {}",
-                index_count, first_index, max_indices);
-            return;
+/// Present through an HDR surface format when the surface offers one
+///
+/// Returns whether HDR is on afterwards; it stays off when unsupported.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_setHdrEnabled0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    enabled: jboolean,
+) -> jboolean {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    match device.set_hdr_enabled(enabled != 0) {
+        Ok(hdr) => hdr as jboolean,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to reconfigure surface: {}", e));
+            0
         }
     }
+}
 
-    state.record_draw_indexed(
-        index_count as u32,
-        instance_count as u32,
-        first_index as u32,
-        base_vertex,
-        first_instance as u32,
-    );
+/// Whether the surface is presenting in HDR
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_isHdrEnabled0(
+    _env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+) -> jboolean {
+    if device_ptr == 0 {
+        return 0;
+    }
 
-    log::debug!("NATIVE drawIndexed: Recorded draw (indices={}, instances={}, first={}, base={}, firstInst={})",
-        index_count, instance_count, first_index, base_vertex, first_instance);
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    device.hdr_enabled() as jboolean
 }
 
-/// Draw (non-indexed)
+/// Shared body of the `getSurface*` entry points
+///
+/// Headless devices have no surface and get an empty array.
+fn surface_capability_names<'local, T: std::fmt::Debug>(
+    env: &mut JNIEnv<'local>,
+    device_ptr: jlong,
+    select: fn(&surface::SurfaceCapabilities) -> &[T],
+) -> JObjectArray<'local> {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return JObjectArray::default();
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let capabilities = device.get_surface_capabilities();
+    let values = capabilities.as_ref().map_or(&[][..], select);
+    new_jni_name_array(env, values).unwrap_or_default()
+}
+
+/// Texture formats the window surface supports, as wgpu format names
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_draw(
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getSurfaceFormats0<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    device_ptr: jlong,
+) -> JObjectArray<'local> {
+    surface_capability_names(&mut env, device_ptr, |caps| &caps.formats)
+}
+
+/// Present modes the window surface supports ("Fifo", "Mailbox", "Immediate", ...)
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getSurfacePresentModes0<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    device_ptr: jlong,
+) -> JObjectArray<'local> {
+    surface_capability_names(&mut env, device_ptr, |caps| &caps.present_modes)
+}
+
+/// Composite alpha modes the window surface supports ("Opaque", "PreMultiplied", ...)
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getSurfaceAlphaModes0<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    device_ptr: jlong,
+) -> JObjectArray<'local> {
+    surface_capability_names(&mut env, device_ptr, |caps| &caps.alpha_modes)
+}
+
+/// Force the presented image to alpha 1.0
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_setForceOpaquePresent0(
     _env: JNIEnv,
     _class: JClass,
-    _device_ptr: jlong,
-    render_pass_ptr: jlong,
-    vertex_count: jint,
-    instance_count: jint,
-    first_vertex: jint,
-    first_instance: jint,
+    device_ptr: jlong,
+    enabled: jboolean,
 ) {
-    if render_pass_ptr == 0 {
+    if device_ptr == 0 {
         return;
     }
 
-    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    device.set_force_opaque_present(enabled != 0);
+}
 
-    state.record_draw(
-        vertex_count as u32,
-        instance_count as u32,
-        first_vertex as u32,
-        first_instance as u32,
-    );
+/// Whether the present blit forces alpha to 1.0
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_isForceOpaquePresent0(
+    _env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+) -> jboolean {
+    if device_ptr == 0 {
+        return 0;
+    }
 
-    log::debug!("Recorded draw (vertices={}, instances={}, first={}, firstInst={})",
-        vertex_count, instance_count, first_vertex, first_instance);
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    device.force_opaque_present() as jboolean
 }
 
-/// Set scissor rect
+/// Whether the device has push constants (immediates)
+///
+/// When false, `setPushConstants` still works through a uniform fallback, and
+/// WGSL `var<immediate>` blocks are rewritten to read from it.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_supportsPushConstants0(
+    _env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+) -> jboolean {
+    if device_ptr == 0 {
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    device.supports_push_constants() as jboolean
+}
+
+/// Serialize command recording and queue submission, to isolate threading bugs
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_setScissorRect(
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_setSerializedSubmission0(
     _env: JNIEnv,
     _class: JClass,
-    _device_ptr: jlong,
-    render_pass_ptr: jlong,
-    x: jint,
-    y: jint,
-    width: jint,
-    height: jint,
+    device_ptr: jlong,
+    enabled: jboolean,
 ) {
-    if render_pass_ptr == 0 {
+    if device_ptr == 0 {
         return;
     }
 
-    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    device.set_serialized_submission(enabled != 0);
+}
 
-    state.record_set_scissor_rect(x as u32, y as u32, width as u32, height as u32);
+/// Whether command recording and queue submission are serialized
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_isSerializedSubmission0(
+    _env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+) -> jboolean {
+    if device_ptr == 0 {
+        return 0;
+    }
 
-    log::debug!("Recorded setScissorRect (x={}, y={}, width={}, height={})",
-        x, y, width, height);
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    device.serialized_submission() as jboolean
 }
 
-/// Set push constants for per-draw data
-///
-/// This allows passing small amounts of data (up to 128 bytes) directly to shaders
-/// without creating uniform buffers. Useful for:
-/// - Model matrices
-/// - Per-draw colors
-/// - Animation parameters
+/// Poll the device, running map callbacks and retiring finished submissions
 ///
-/// # Arguments
-/// * `render_pass_ptr` - The active render pass
-/// * `offset` - Byte offset within the push constant range (must be 4-byte aligned)
-/// * `data` - The data to write (as byte array, must be 4-byte aligned)
+/// With `wait` set this blocks until all submitted work is done. Returns
+/// whether the queue is empty.
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_setPushConstants(
-    env: JNIEnv,
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_poll0(
+    mut env: JNIEnv,
     _class: JClass,
-    _device_ptr: jlong,
-    render_pass_ptr: jlong,
-    offset: jint,
-    data: JByteArray,
-) {
-    if render_pass_ptr == 0 {
-        return;
+    device_ptr: jlong,
+    wait: jboolean,
+) -> jboolean {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
     }
 
-    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
-
-    // Convert Java byte array to Rust Vec
-    let data_vec: Vec<u8> = match env.convert_byte_array(&data) {
-        Ok(arr) => arr,
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    match device.poll_device(wait != 0) {
+        Ok(queue_empty) => queue_empty as jboolean,
         Err(e) => {
-            log::error!("Failed to get byte array for push constants: {}", e);
-            return;
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Device poll failed: {}", e));
+            0
         }
-    };
-
-    // Ensure data is 4-byte aligned
-    if data_vec.len() % 4 != 0 {
-        log::error!("Push constants data must be 4-byte aligned, got {} bytes", data_vec.len());
-        return;
     }
-
-    state.record_set_push_constants_all(offset as u32, &data_vec);
-
-    log::debug!("Recorded setPushConstants (offset={}, size={})", offset, data_vec.len());
 }
 
-/// End render pass and submit
+/// Open an error scope on the calling thread (`filter` is an `ERROR_FILTER_*` constant)
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_endRenderPass(
-    _env: JNIEnv,
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_pushErrorScope0(
+    mut env: JNIEnv,
     _class: JClass,
     device_ptr: jlong,
-    render_pass_ptr: jlong,
+    filter: jint,
 ) {
-    if render_pass_ptr == 0 || device_ptr == 0 {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
         return;
     }
 
     let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    match error::ErrorFilter::from_id(filter as u32) {
+        Ok(filter) => device.push_error_scope(filter),
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+        }
+    }
+}
 
-    // Take ownership of the boxed RenderPassState
-    let mut state = unsafe { Box::from_raw(render_pass_ptr as *mut render_pass::RenderPassState) };
+/// Close the calling thread's innermost error scope, returning its first error or null
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_popErrorScope0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+) -> jstring {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return std::ptr::null_mut();
+    }
 
-    // Finish and submit - returns the output texture that was rendered
-    match state.finish_and_submit(device.context().as_ref(), device.queue_id()) {
-        Ok(output_texture) => {
-            // Set the main framebuffer AFTER the render pass has successfully executed
-            // This fixes the race condition where present_frame could be called before rendering completes
-            if let Some(texture_id) = output_texture {
-                device.set_main_framebuffer(texture_id);
-                log::debug!("endRenderPass: Set main framebuffer to {:?} after successful render", texture_id);
-            } else {
-                log::debug!("endRenderPass: No output texture from this render pass");
-            }
-            log::debug!("Ended render pass at {:?}", render_pass_ptr as *const ());
-        }
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    match device.pop_error_scope() {
+        Ok(Some(message)) => env
+            .new_string(message)
+            .map(|s| s.into_raw())
+            .unwrap_or(std::ptr::null_mut()),
+        Ok(None) => std::ptr::null_mut(),
         Err(e) => {
-            log::error!("Failed to end render pass: {}", e);
+            let _ = env.throw_new("java/lang/IllegalStateException", e.to_string());
+            std::ptr::null_mut()
         }
     }
+}
 
-    // State is dropped here
+/// Take the calling thread's last per-draw error, or null if there was none
+///
+/// Render pass record calls (`setPipeline`, `setVertexBuffer`, `setIndexBuffer`,
+/// `setBindGroup*`, `setViewport`, `setScissorRect`, `setStencilReference`,
+/// `setPushConstants`, `draw*`, occlusion begin/end) skip bad input and report it here rather than
+/// throwing. Creation, upload and submit calls still throw. Reading clears the slot.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getLastError0(
+    env: JNIEnv,
+    _class: JClass,
+) -> jstring {
+    match error::take_last_error() {
+        Some(message) => env
+            .new_string(message)
+            .map(|s| s.into_raw())
+            .unwrap_or(std::ptr::null_mut()),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Number of render passes that have been begun and not yet ended
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getLiveRenderPassCount0(
+    _env: JNIEnv,
+    _class: JClass,
+) -> jint {
+    render_pass::LIVE_RENDER_PASSES.live().min(jint::MAX as usize) as jint
+}
+
+/// Set how many live render passes are tolerated before a leak warning is logged
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_setRenderPassLeakThreshold0(
+    mut env: JNIEnv,
+    _class: JClass,
+    threshold: jint,
+) {
+    if threshold < 1 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid leak threshold {}", threshold));
+        return;
+    }
+    render_pass::LIVE_RENDER_PASSES.set_threshold(threshold as usize);
 }
 
 // ============================================================================
@@ -2034,22 +5793,24 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass
             HANDLES.get_sampler(DEFAULT_SAMPLER_HANDLE.load(Ordering::Relaxed))
         } else {
             // Create default sampler: linear filter, clamp to edge
-            match device.create_sampler(
-                2, // Clamp to edge (AddressMode::ClampToEdge = 2)
-                2, // Clamp to edge
-                2, // Clamp to edge
-                1, // Linear (FilterMode::Linear = 1)
-                1, // Linear
-                0, // Mipmap mode: nearest (unused for GUI)
-                0.0, // lod_min_clamp
-                32.0, // lod_max_clamp
-                1, // max_anisotropy
-            ) {
-                Ok(id) => {
-                    let handle = HANDLES.insert_sampler(id);
-                    DEFAULT_SAMPLER_HANDLE.store(handle, Ordering::Relaxed);
-                    log::info!("Created default sampler {:?} (handle={}) for textures without explicit sampler", id, handle);
-                    Some(id)
+            match device.create_sampler(device::SamplerRequest {
+                address_mode_u: 2, // Clamp to edge (AddressMode::ClampToEdge = 2)
+                address_mode_v: 2,
+                address_mode_w: 2,
+                min_filter: 1, // Linear (FilterMode::Linear = 1)
+                mag_filter: 1,
+                mipmap_filter: 0, // Nearest (unused for GUI)
+                lod_min_clamp: 0.0,
+                lod_max_clamp: 32.0,
+                max_anisotropy: 1,
+                compare_function: -1, // No depth comparison
+                label: None,
+            }) {
+                Ok(sampler) => {
+                    DEFAULT_SAMPLER_HANDLE.store(sampler.handle, Ordering::Relaxed);
+                    log::info!("Created default sampler {:?} (handle={}) for textures without explicit sampler",
+                        sampler.sampler_id, sampler.handle);
+                    Some(sampler.sampler_id)
                 }
                 Err(e) => {
                     log::warn!("Failed to create default sampler: {:?}", e);
@@ -2065,7 +5826,7 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass
     static DEFAULT_WHITE_TEXTURE_VIEW: AtomicU64 = AtomicU64::new(0);
     static DEFAULT_WHITE_TEXTURE_LOCK: Mutex<()> = Mutex::new(());
 
-    let (default_white_texture_id, default_white_view_id) = if DEFAULT_WHITE_TEXTURE.load(Ordering::Relaxed) != 0 {
+    let (_default_white_texture_id, _default_white_view_id) = if DEFAULT_WHITE_TEXTURE.load(Ordering::Relaxed) != 0 {
         (
             HANDLES.get_texture(DEFAULT_WHITE_TEXTURE.load(Ordering::Relaxed)),
             HANDLES.get_texture_view(DEFAULT_WHITE_TEXTURE_VIEW.load(Ordering::Relaxed))
@@ -2085,22 +5846,33 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass
                 Ok(texture_id) => {
                     // Write white pixel data to the texture
                     let white_pixel: [u8; 4] = [255, 255, 255, 255]; // RGBA white
-                    match device.write_texture(texture_id, &white_pixel, 0, 0, 0, 0, 1, 1) {
+                    match device.write_texture(texture_id, wgt::TextureFormat::Bgra8Unorm, &white_pixel, 0, 0, 0, 0, 1, 1) {
                         Ok(_) => {
                             // Create a texture view
-                            match device.create_texture_view(texture_id, 1) {
+                            match device.create_texture_view(texture_id, wgt::TextureDimension::D2, 1, false) {
                                 Ok((view_id, _dim)) => {
-                                    let tex_handle = HANDLES.insert_texture(
+                                    let tex_handle = HANDLES.insert_texture(resource_handles::TextureInfo {
+                                        id: texture_id,
+                                        width: 1,
+                                        height: 1,
+                                        depth_or_array_layers: 1,
+                                        mip_level_count: 1,
+                                        dimension: wgt::TextureDimension::D2,
+                                        format: wgt::TextureFormat::Bgra8Unorm,
+                                        sample_count: 1,
+                                        is_cube: false,
+                                    });
+                                    let view_handle = HANDLES.insert_texture_view(resource_handles::TextureViewInfo {
+                                        id: view_id,
+                                        dimension: _dim,
                                         texture_id,
-                                        1, // array_layers
-                                        wgt::TextureDimension::D2,
-                                        wgt::TextureFormat::Bgra8Unorm
-                                    );
-                                    let view_handle = HANDLES.insert_texture_view(
-                                        view_id,
-                                        _dim,
-                                        texture_id
-                                    );
+                                        texture_layers: 1,
+                                        format: wgt::TextureFormat::Bgra8Unorm,
+                                        sample_count: 1,
+                                        aspect: wgt::TextureAspect::All,
+                                        width: 1,
+                                        height: 1,
+                                    });
                                     DEFAULT_WHITE_TEXTURE.store(tex_handle, Ordering::Relaxed);
                                     DEFAULT_WHITE_TEXTURE_VIEW.store(view_handle, Ordering::Relaxed);
                                     log::info!("Created default white texture {:?} (handle={}) and view {:?} (handle={}) for missing texture bindings",
@@ -2244,7 +6016,7 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass
                             let _ = env.throw_new("java/lang/IllegalArgumentException", &msg);
                             return 0;
                         } else {
-                            log::info!("MATCHED texture '{}' to slot {} [shader: {}]", mc_name, slot.unwrap(), shader_name);
+                            log::info!("MATCHED texture '{}' to slot {:?} [shader: {}]", mc_name, slot, shader_name);
                             slot
                         }
                     } else {
@@ -2259,7 +6031,7 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass
                     };
 
                     if let Some(slot) = binding_slot {
-                        builder = builder.add_texture(slot, view_info.id, sampler_id, view_info.dimension, view_info.texture_id);
+                        builder = builder.add_texture(slot, &view_info, sampler_id);
                         log::info!("Bound texture '{}' to slot {} [shader: {}] (view={:?}, sampler={:?})",
                             texture_name_log.unwrap_or_else(|| format!("#{}", i)), slot, shader_name, view_info.id, sampler_id);
                     } else {
@@ -2418,7 +6190,7 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass
                             // but the ring buffer rotates, making cached offsets stale.
                             // For DynamicTransforms (handles 1-3), if slice_offset is 0 but we've written to offset 256,
                             // use the ring buffer's current offset instead.
-                            let is_ring_buffer_uniform = unif_handle >= 1 && unif_handle <= 6;
+                            let is_ring_buffer_uniform = (1..=6).contains(&unif_handle);
                             if is_ring_buffer_uniform && slice_offset == 0 {
                                 // Check if we've written to offset 256 (second slot in ring buffer)
                                 let offsets = BUFFER_WRITE_OFFSETS.read().unwrap();
@@ -2445,9 +6217,16 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass
                             buffer_info.size
                         };
 
-                        log::debug!("Mapping uniform '{}' to binding slot {} (offset={}, size={})",
-                                  mc_name, slot, offset, size);
-                        builder = builder.add_uniform_buffer(slot, buffer_info.id, offset, size);
+                        // Storage buffers are set by name through the same uniform API
+                        let is_storage = pipeline_layout.as_ref().is_some_and(|info| info.binding_layouts.iter()
+                            .any(|l| l.binding == slot && matches!(l.ty, resource_handles::BindingLayoutType::StorageBuffer { .. })));
+                        log::debug!("Mapping {} '{}' to binding slot {} (offset={}, size={})",
+                                  if is_storage { "storage buffer" } else { "uniform" }, mc_name, slot, offset, size);
+                        builder = if is_storage {
+                            builder.add_storage_buffer(slot, buffer_info.id, offset, size)
+                        } else {
+                            builder.add_uniform_buffer(slot, buffer_info.id, offset, size)
+                        };
                     } else {
                         log::debug!("Failed to map uniform '{}' to any binding slot", mc_name);
                     }
@@ -2457,20 +6236,23 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass
     }
 
     // Build the bind group - use pipeline layout if available, otherwise create new
+    // Bind groups with a known layout are shared between identical resource sets
+    let build_cached = |builder: bind_group::BindGroupBuilder, pipeline_info: &resource_handles::RenderPipelineInfo| {
+        let key = builder.cache_key(pipeline_info.bind_group_layout_id);
+        device.bind_group_cache.get_or_create(key, || {
+            builder.build_with_layout(pipeline_info.bind_group_layout_id, &pipeline_info.binding_layouts)
+        })
+    };
+
     // For pipelines with 0 bindings, create an empty bind group (wgpu still requires it)
     if let Some(ref pipeline_info) = pipeline_layout {
         if pipeline_info.binding_layouts.is_empty() {
             log::debug!("Pipeline expects 0 bindings, creating empty bind group");
             // Use the builder to create an empty bind group with the pipeline's layout
-            let empty_result = builder.build_with_layout(
-                pipeline_info.bind_group_layout_id, 
-                &pipeline_info.binding_layouts
-            );
-            match empty_result {
-                Ok(bind_group_id) => {
-                    let handle = HANDLES.insert_bind_group(bind_group_id);
-                    log::debug!("Created empty bind group with handle {}", handle);
-                    return handle as jlong;
+            match build_cached(builder, pipeline_info) {
+                Ok(cached) => {
+                    log::debug!("Created empty bind group with handle {}", cached.handle);
+                    return cached.handle as jlong;
                 }
                 Err(e) => {
                     let msg = format!("Failed to create empty bind group: {:?}", e);
@@ -2485,15 +6267,14 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass
     let result = if let Some(ref pipeline_info) = pipeline_layout {
         log::debug!("Creating bind group with pipeline layout {:?} ({} bindings) [shader: {}]",
                    pipeline_info.bind_group_layout_id, pipeline_info.binding_layouts.len(), pipeline_info.shader_name);
-        builder.build_with_layout(pipeline_info.bind_group_layout_id, &pipeline_info.binding_layouts)
+        build_cached(builder, pipeline_info).map(|cached| (cached.bind_group_id, cached.handle))
     } else {
         log::debug!("Creating bind group with dynamic layout (no pipeline specified)");
-        builder.build()
+        builder.build().map(|bind_group_id| (bind_group_id, HANDLES.insert_bind_group(bind_group_id)))
     };
 
     match result {
-        Ok(bind_group_id) => {
-            let handle = HANDLES.insert_bind_group(bind_group_id);
+        Ok((bind_group_id, handle)) => {
             let binding_count = if let Some(ref pipeline_info) = pipeline_layout {
                 pipeline_info.binding_layouts.len()
             } else {
@@ -2513,6 +6294,12 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass
 
             handle as jlong
         }
+        Err(e @ BasaltError::TextureDimensionMismatch { .. }) => {
+            let shader_name = pipeline_layout.as_ref().map_or("unknown", |p| p.shader_name.as_str());
+            let _ = env.throw_new("java/lang/IllegalArgumentException",
+                format!("Failed to create bind group for {}: {}", shader_name, e));
+            0
+        }
         Err(e) => {
             // This is a critical failure - bind group creation failed
             let msg = format!("Failed to create bind group: {:?}", e);
@@ -2549,31 +6336,314 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass
 }
 
 
+/// Map JNI binding type index to BindingLayoutType
+///
+/// The flag is set for dynamic-offset uniform buffers.
+fn map_binding_type_from_jni(ty: jint) -> Option<(resource_handles::BindingLayoutType, bool)> {
+    use resource_handles::BindingLayoutType;
+    match ty {
+        0 => Some((BindingLayoutType::Texture, false)),
+        1 => Some((BindingLayoutType::Sampler, false)),
+        2 => Some((BindingLayoutType::UniformBuffer, false)),
+        3 => Some((BindingLayoutType::StorageBuffer { read_only: true }, false)),
+        4 => Some((BindingLayoutType::StorageBuffer { read_only: false }, false)),
+        5 => Some((BindingLayoutType::UniformBuffer, true)),
+        _ => None,
+    }
+}
+
+/// Map JNI texture view dimension index to wgpu TextureViewDimension
+fn map_view_dimension_from_jni(dimension: jint) -> Option<wgt::TextureViewDimension> {
+    match dimension {
+        0 => Some(wgt::TextureViewDimension::D1),
+        1 => Some(wgt::TextureViewDimension::D2),
+        2 => Some(wgt::TextureViewDimension::D2Array),
+        3 => Some(wgt::TextureViewDimension::Cube),
+        4 => Some(wgt::TextureViewDimension::CubeArray),
+        5 => Some(wgt::TextureViewDimension::D3),
+        _ => None,
+    }
+}
+
+/// Create a bind group layout from an explicit spec
+///
+/// Each entry is described by the parallel `bindings`/`types`/`dimensions`/
+/// `min_binding_sizes` arrays. `dimensions` only applies to textures and a
+/// `min_binding_sizes` of 0 leaves the buffer size unchecked. Returns a
+/// layout handle for `createBindGroupExplicit0`.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createBindGroupLayout0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    bindings: JObject,
+    types: JObject,
+    dimensions: JObject,
+    min_binding_sizes: JObject,
+) -> jlong {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    let arrays = (|| -> ::jni::errors::Result<_> {
+        Ok((
+            read_jni_int_array(&mut env, bindings)?,
+            read_jni_int_array(&mut env, types)?,
+            read_jni_int_array(&mut env, dimensions)?,
+            read_jni_long_array(&mut env, min_binding_sizes)?,
+        ))
+    })();
+    let (bindings, types, dimensions, min_binding_sizes) = match arrays {
+        Ok(arrays) => arrays,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid layout spec arrays: {}", e));
+            return 0;
+        }
+    };
+    if [types.len(), dimensions.len(), min_binding_sizes.len()].iter().any(|&len| len != bindings.len()) {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Layout spec arrays differ in length");
+        return 0;
+    }
+
+    let mut spec = Vec::with_capacity(bindings.len());
+    for i in 0..bindings.len() {
+        let (Some((ty, has_dynamic_offset)), Ok(binding)) = (map_binding_type_from_jni(types[i]), u32::try_from(bindings[i])) else {
+            let _ = env.throw_new("java/lang/IllegalArgumentException",
+                format!("Layout entry {} has an invalid binding {} or type {}", i, bindings[i], types[i]));
+            return 0;
+        };
+        let expected_dimension = if ty == resource_handles::BindingLayoutType::Texture {
+            match map_view_dimension_from_jni(dimensions[i]) {
+                Some(dimension) => Some(dimension),
+                None => {
+                    let _ = env.throw_new("java/lang/IllegalArgumentException",
+                        format!("Layout entry {} has an unknown view dimension {}", i, dimensions[i]));
+                    return 0;
+                }
+            }
+        } else {
+            None
+        };
+        spec.push(resource_handles::BindingLayoutEntry {
+            binding,
+            ty,
+            min_binding_size: u64::try_from(min_binding_sizes[i]).ok().filter(|&size| size > 0),
+            has_dynamic_offset,
+            expected_dimension,
+            comparison: false,
+            variable_name: None,
+        });
+    }
+
+    match bind_group::create_explicit_layout(device.context(), device.id(), &spec) {
+        Ok(layout_id) => HANDLES.insert_bind_group_layout(layout_id, spec) as jlong,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            0
+        }
+    }
+}
+
+/// Layout handle of a pipeline's bind group `group` for `createBindGroupExplicit0`
+///
+/// Only groups after 0 have one: group 0 is bound by uniform name through
+/// `createBindGroup0`, and the immediates fallback group is managed natively.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getPipelineBindGroupLayout0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    pipeline_handle: jlong,
+    group: jint,
+) -> jlong {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+
+    let Some(pipeline_info) = HANDLES.get_render_pipeline_info(pipeline_handle as u64) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException",
+            format!("Invalid pipeline handle: {}", pipeline_handle));
+        return 0;
+    };
+    match pipeline_info.extra_groups.iter().find(|extra| extra.group as jint == group) {
+        Some(extra) => extra.layout_handle as jlong,
+        None => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException",
+                format!("Pipeline {} has no explicitly bound group {}", pipeline_info.shader_name, group));
+            0
+        }
+    }
+}
+
+/// Create a bind group for a layout from `createBindGroupLayout0`
+///
+/// The caller names the exact binding slot of every resource, so no
+/// reflection or uniform name matching is involved. `resources` holds texture
+/// view, sampler or buffer handles; `offsets`/`sizes` only apply to buffers
+/// (a size of 0 binds the rest of the buffer).
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createBindGroupExplicit0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    layout_handle: jlong,
+    bindings: JObject,
+    resources: JObject,
+    offsets: JObject,
+    sizes: JObject,
+) -> jlong {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    let Some(layout) = HANDLES.get_bind_group_layout_info(layout_handle as u64) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException",
+            format!("Invalid bind group layout handle {}", layout_handle));
+        return 0;
+    };
+
+    let arrays = (|| -> ::jni::errors::Result<_> {
+        Ok((
+            read_jni_int_array(&mut env, bindings)?,
+            read_jni_long_array(&mut env, resources)?,
+            read_jni_long_array(&mut env, offsets)?,
+            read_jni_long_array(&mut env, sizes)?,
+        ))
+    })();
+    let (bindings, resources, offsets, sizes) = match arrays {
+        Ok(arrays) => arrays,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid bind group entry arrays: {}", e));
+            return 0;
+        }
+    };
+    if [resources.len(), offsets.len(), sizes.len()].iter().any(|&len| len != bindings.len()) {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Bind group entry arrays differ in length");
+        return 0;
+    }
+
+    let mut entries = Vec::with_capacity(bindings.len());
+    for i in 0..bindings.len() {
+        let handle = resources[i] as u64;
+        let resource = if let Some(view_id) = HANDLES.get_texture_view(handle) {
+            bind_group::ExplicitResource::TextureView(view_id)
+        } else if let Some(sampler_id) = HANDLES.get_sampler(handle) {
+            bind_group::ExplicitResource::Sampler(sampler_id)
+        } else if let Some(buffer_id) = HANDLES.get_buffer(handle) {
+            bind_group::ExplicitResource::Buffer {
+                buffer_id,
+                offset: offsets[i].max(0) as u64,
+                size: (sizes[i] > 0).then_some(sizes[i] as u64),
+            }
+        } else {
+            let _ = env.throw_new("java/lang/IllegalArgumentException",
+                format!("Binding {}: {} is not a texture view, sampler or buffer handle", bindings[i], resources[i]));
+            return 0;
+        };
+        entries.push((bindings[i] as u32, resource));
+    }
+
+    match bind_group::create_explicit_bind_group(device.context(), device.id(), layout.id, &layout.entries, &entries) {
+        Ok(bind_group_id) => HANDLES.insert_bind_group(bind_group_id) as jlong,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            0
+        }
+    }
+}
+
 /// Set a bind group on the render pass
 #[no_mangle]
 pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass_setBindGroup0(
-    _env: JNIEnv,
+    mut env: JNIEnv,
     _class: JClass,
     _device_ptr: jlong,
     render_pass_ptr: jlong,
     index: jint,
     bind_group_handle: jlong,
 ) {
-    if render_pass_ptr == 0 || bind_group_handle == 0 {
+    if render_pass_ptr == 0 {
+        report_recoverable_error(&mut env, null_render_pass("setBindGroup"));
+        return;
+    }
+    if bind_group_handle == 0 {
+        report_recoverable_error(&mut env, BasaltError::NullPointer { context: "setBindGroup: bind group".into() });
+        return;
+    }
+
+    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
+
+    // Look up bind group ID
+    if let Some(bind_group_id) = HANDLES.get_bind_group(bind_group_handle as u64) {
+        debug_throttled!("setBindGroup0: setting bind group {:?} at index {}", bind_group_id, index);
+        // Record the set bind group command
+        state.record_set_bind_group(index as u32, Some(bind_group_id), Vec::new());
+        debug_throttled!("Recorded setBindGroup (index={}, bind_group={})", index, bind_group_handle);
+    } else {
+        report_recoverable_error(&mut env, BasaltError::InvalidHandle {
+            handle_type: "bind group".into(),
+            handle: bind_group_handle as u64,
+        });
+    }
+}
+
+/// Set a bind group on the render pass with dynamic uniform buffer offsets
+///
+/// The bind group's layout must come from `createBindGroupLayout0` with
+/// dynamic uniform bindings; one offset is consumed per dynamic binding, in
+/// binding order.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass_setBindGroupDynamic0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    render_pass_ptr: jlong,
+    index: jint,
+    bind_group_handle: jlong,
+    offsets: JObject,
+) {
+    if device_ptr == 0 || render_pass_ptr == 0 || bind_group_handle == 0 {
+        report_recoverable_error(&mut env, BasaltError::NullPointer {
+            context: "setBindGroupDynamic: device, render pass or bind group".into(),
+        });
         return;
     }
 
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
     let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
 
-    // Look up bind group ID
-    if let Some(bind_group_id) = HANDLES.get_bind_group(bind_group_handle as u64) {
-        log::debug!("setBindGroup0: setting bind group {:?} at index {}", bind_group_id, index);
-        // Record the set bind group command
-        state.record_set_bind_group(index as u32, Some(bind_group_id), Vec::new());
-        log::debug!("Recorded setBindGroup (index={}, bind_group={})", index, bind_group_handle);
+    let Some(bind_group_id) = HANDLES.get_bind_group(bind_group_handle as u64) else {
+        report_recoverable_error(&mut env, BasaltError::InvalidHandle {
+            handle_type: "bind group".into(),
+            handle: bind_group_handle as u64,
+        });
+        return;
+    };
+
+    let raw_offsets = if offsets.is_null() {
+        Vec::new()
     } else {
-        log::warn!("setBindGroup: invalid bind group handle {}", bind_group_handle);
-        log::debug!("Bind group set (placeholder implementation)");
+        let offsets_array: ::jni::objects::JPrimitiveArray<i64> = offsets.into();
+        let len = env.get_array_length(&offsets_array).unwrap_or(0) as usize;
+        let mut buf = vec![0i64; len];
+        if env.get_long_array_region(&offsets_array, 0, &mut buf).is_err() {
+            report_recoverable_error(&mut env, BasaltError::Jni("Failed to read dynamic offsets".into()));
+            return;
+        }
+        buf
+    };
+
+    let alignment = device.get_limits().min_uniform_buffer_offset_alignment;
+    match bind_group::validate_dynamic_offsets(&raw_offsets, alignment) {
+        Ok(dynamic_offsets) => {
+            state.record_set_bind_group(index as u32, Some(bind_group_id), dynamic_offsets);
+        }
+        Err(e) => report_recoverable_error(&mut env, e),
     }
 }
 
@@ -2655,6 +6725,34 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass
 // CLEAR OPERATIONS
 // ============================================================================
 
+/// Resolve a texture handle to its ID and the layers a clear should touch
+///
+/// `layer` selects a single array layer, cube face or 3D slice; `None` clears
+/// every layer the texture was created with.
+fn clear_targets_for_handle(
+    handle: u64,
+    layer: Option<u32>,
+) -> error::Result<(resource_handles::TextureInfo, Vec<texture::LayerTarget>)> {
+    let info = HANDLES
+        .get_texture_info(handle)
+        .ok_or_else(|| BasaltError::invalid_parameter("texture", "Invalid texture handle"))?;
+    let targets = texture::layer_targets(info.dimension, info.depth_or_array_layers, layer)?;
+    Ok((info, targets))
+}
+
+/// Unpack a Minecraft ARGB clear color for a texture of `format`
+///
+/// Decoded from sRGB for sRGB formats unless the device turned that off, see
+/// `render_pass::linear_clear_color`.
+fn clear_color_for(device: &BasaltDevice, argb: jint, format: wgt::TextureFormat) -> wgt::Color {
+    let color = render_pass::unpack_argb_color(argb as u32);
+    if device.srgb_clear_colors() {
+        render_pass::linear_clear_color(color, Some(format))
+    } else {
+        color
+    }
+}
+
 /// Clear a color texture
 #[no_mangle]
 pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEncoder_clearColorTexture0(
@@ -2671,25 +6769,54 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEnc
 
     let device = unsafe { &*(device_ptr as *const BasaltDevice) };
 
-    // Look up texture ID
-    let texture_id = match HANDLES.get_texture(texture_handle as u64) {
-        Some(id) => id,
-        None => {
-            let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid texture handle");
+    // Look up texture ID and every layer it has
+    let (texture, targets) = match clear_targets_for_handle(texture_handle as u64, None) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
             return;
         }
     };
 
-    // Convert clear color from packed ARGB (Minecraft format) to Color struct
-    let a = ((clear_color >> 24) & 0xFF) as f64 / 255.0;
-    let r = ((clear_color >> 16) & 0xFF) as f64 / 255.0;
-    let g = ((clear_color >> 8) & 0xFF) as f64 / 255.0;
-    let b = (clear_color & 0xFF) as f64 / 255.0;
-    let color = wgt::Color { r, g, b, a };
-
     // Create a command encoder and clear the texture
-    if let Err(e) = device.clear_texture(texture_id, Some(color), None) {
-        let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to clear color texture: {}", e));
+    let color = clear_color_for(device, clear_color, texture.format);
+    if let Err(e) = device.clear_texture(texture.id, &targets, Some(color), None) {
+        let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to clear color texture: {}", e));
+    }
+}
+
+/// Clear a single layer of a color texture (array layer, cube face or 3D slice)
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEncoder_clearColorTextureLayer0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    texture_handle: jlong,
+    clear_color: jint,
+    layer: jint,
+) {
+    if device_ptr == 0 || texture_handle == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
+        return;
+    }
+    if layer < 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Negative layer {}", layer));
+        return;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    let (texture, targets) = match clear_targets_for_handle(texture_handle as u64, Some(layer as u32)) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            return;
+        }
+    };
+
+    let color = clear_color_for(device, clear_color, texture.format);
+    if let Err(e) = device.clear_texture(texture.id, &targets, Some(color), None) {
+        let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to clear color texture layer: {}", e));
     }
 }
 
@@ -2709,18 +6836,52 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEnc
 
     let device = unsafe { &*(device_ptr as *const BasaltDevice) };
 
-    // Look up texture ID
-    let texture_id = match HANDLES.get_texture(texture_handle as u64) {
-        Some(id) => id,
-        None => {
-            let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid texture handle");
+    // Look up texture ID and every layer it has
+    let (texture, targets) = match clear_targets_for_handle(texture_handle as u64, None) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
             return;
         }
     };
 
     // Clear depth texture
-    if let Err(e) = device.clear_texture(texture_id, None, Some(clear_depth)) {
-        let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to clear depth texture: {}", e));
+    if let Err(e) = device.clear_texture(texture.id, &targets, None, Some(clear_depth)) {
+        let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to clear depth texture: {}", e));
+    }
+}
+
+/// Clear a single layer of a depth texture
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEncoder_clearDepthTextureLayer0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    texture_handle: jlong,
+    clear_depth: jfloat,
+    layer: jint,
+) {
+    if device_ptr == 0 || texture_handle == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
+        return;
+    }
+    if layer < 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Negative layer {}", layer));
+        return;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    let (texture, targets) = match clear_targets_for_handle(texture_handle as u64, Some(layer as u32)) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            return;
+        }
+    };
+
+    if let Err(e) = device.clear_texture(texture.id, &targets, None, Some(clear_depth)) {
+        let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to clear depth texture layer: {}", e));
     }
 }
 
@@ -2748,15 +6909,11 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEnc
 
     // Clear color texture if provided
     if color_texture_handle != 0 {
-        if let Some(color_id) = HANDLES.get_texture(color_texture_handle as u64) {
-            let r = ((clear_color >> 24) & 0xFF) as f64 / 255.0;
-            let g = ((clear_color >> 16) & 0xFF) as f64 / 255.0;
-            let b = ((clear_color >> 8) & 0xFF) as f64 / 255.0;
-            let a = (clear_color & 0xFF) as f64 / 255.0;
-            let color = wgt::Color { r, g, b, a };
-
-            if let Err(e) = device.clear_texture(color_id, Some(color), None) {
-                let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to clear color texture: {}", e));
+        if let Ok((color_texture, targets)) = clear_targets_for_handle(color_texture_handle as u64, None) {
+            // Same ARGB packing as the other clears
+            let color = clear_color_for(device, clear_color, color_texture.format);
+            if let Err(e) = device.clear_texture(color_texture.id, &targets, Some(color), None) {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to clear color texture: {}", e));
                 return;
             }
         }
@@ -2764,16 +6921,15 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEnc
 
     // Clear depth texture if provided
     if depth_texture_handle != 0 {
-        if let Some(depth_id) = HANDLES.get_texture(depth_texture_handle as u64) {
-            if let Err(e) = device.clear_texture(depth_id, None, Some(clear_depth)) {
-                let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to clear depth texture: {}", e));
-                return;
+        if let Ok((depth_texture, targets)) = clear_targets_for_handle(depth_texture_handle as u64, None) {
+            if let Err(e) = device.clear_texture(depth_texture.id, &targets, None, Some(clear_depth)) {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to clear depth texture: {}", e));
             }
         }
     }
 }
 
-/// Copy texture to texture
+/// Copy texture to texture, between possibly different mip levels
 #[no_mangle]
 pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEncoder_copyTextureToTexture0(
     mut env: JNIEnv,
@@ -2781,7 +6937,8 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEnc
     device_ptr: jlong,
     src_texture_handle: jlong,
     dst_texture_handle: jlong,
-    mip_level: jint,
+    src_mip_level: jint,
+    dst_mip_level: jint,
     dest_x: jint,
     dest_y: jint,
     source_x: jint,
@@ -2796,8 +6953,8 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEnc
 
     let device = unsafe { &*(device_ptr as *const BasaltDevice) };
 
-    // Look up texture IDs
-    let src_id = match HANDLES.get_texture(src_texture_handle as u64) {
+    // Look up texture info (mip counts and sizes are needed for validation)
+    let src_info = match HANDLES.get_texture_info(src_texture_handle as u64) {
         Some(id) => id,
         None => {
             let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid source texture handle");
@@ -2805,7 +6962,7 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEnc
         }
     };
 
-    let dst_id = match HANDLES.get_texture(dst_texture_handle as u64) {
+    let dst_info = match HANDLES.get_texture_info(dst_texture_handle as u64) {
         Some(id) => id,
         None => {
             let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid destination texture handle");
@@ -2813,10 +6970,16 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEnc
         }
     };
 
+    if src_mip_level < 0 || dst_mip_level < 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Negative mip level");
+        return;
+    }
+
     if let Err(e) = device.copy_texture_to_texture(
-        src_id,
-        dst_id,
-        mip_level as u32,
+        &src_info,
+        &dst_info,
+        src_mip_level as u32,
+        dst_mip_level as u32,
         dest_x as u32,
         dest_y as u32,
         source_x as u32,
@@ -2824,7 +6987,7 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEnc
         width as u32,
         height as u32,
     ) {
-        let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to copy texture: {}", e));
+        let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to copy texture: {}", e));
     }
 }
 
@@ -2846,10 +7009,73 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEnc
     dest_y: jint,
     width: jint,
     height: jint,
-    _format: jint,
+    format: jint,
+    source_swizzle: jint,
+) {
+    log::info!("JNI: writeToTexture0 called: handle={} size={}x{} mip={} format={}", texture_handle, width, height, mip_level, format);
+
+    // Convert Java byte array to Rust Vec
+    let data_vec: Vec<u8> = match env.convert_byte_array(&data) {
+        Ok(arr) => arr,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get byte array: {}", e));
+            return;
+        }
+    };
+
+    write_texture_from_jni(
+        &mut env, device_ptr, texture_handle, Cow::Owned(data_vec),
+        mip_level, _depth_or_layer, dest_x, dest_y, width, height, format, source_swizzle,
+    );
+}
+
+/// Write image data to a texture, reading `length` bytes in place from a direct `ByteBuffer`
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEncoder_writeToTextureDirect0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    texture_handle: jlong,
+    data: JByteBuffer,
+    length: jint,
+    mip_level: jint,
+    depth_or_layer: jint,
+    dest_x: jint,
+    dest_y: jint,
+    width: jint,
+    height: jint,
+    format: jint,
+    source_swizzle: jint,
 ) {
-    log::info!("JNI: writeToTexture0 called: handle={} size={}x{} mip={}", texture_handle, width, height, mip_level);
+    let data = match direct_buffer_slice(&env, &data, length) {
+        Ok(data) => data,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+            return;
+        }
+    };
 
+    write_texture_from_jni(
+        &mut env, device_ptr, texture_handle, Cow::Borrowed(data),
+        mip_level, depth_or_layer, dest_x, dest_y, width, height, format, source_swizzle,
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_texture_from_jni(
+    env: &mut JNIEnv,
+    device_ptr: jlong,
+    texture_handle: jlong,
+    data_vec: Cow<[u8]>,
+    mip_level: jint,
+    depth_or_layer: jint,
+    dest_x: jint,
+    dest_y: jint,
+    width: jint,
+    height: jint,
+    format: jint,
+    source_swizzle: jint,
+) {
     if device_ptr == 0 || texture_handle == 0 {
         let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
         return;
@@ -2857,22 +7083,79 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEnc
 
     let device = unsafe { &*(device_ptr as *const BasaltDevice) };
 
-    // Look up texture ID
-    let texture_id = match HANDLES.get_texture(texture_handle as u64) {
-        Some(id) => id,
+    // Look up texture ID and creation format
+    let texture_info = match HANDLES.get_texture_info(texture_handle as u64) {
+        Some(info) => info,
         None => {
             let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid texture handle");
             return;
         }
     };
+    let texture_id = texture_info.id;
 
-    // Convert Java byte array to Rust Vec
-    let data_vec: Vec<u8> = match env.convert_byte_array(&data) {
-        Ok(arr) => arr,
+    // Layer of a 2D texture, or depth slice of a 3D one (which shrinks with the mip)
+    let layers = texture_info.mip_depth_or_layers(mip_level.max(0) as u32);
+    if depth_or_layer < 0 || depth_or_layer as u32 >= layers {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!(
+            "Layer {} out of range for mip {} with {} layers", depth_or_layer, mip_level, layers));
+        return;
+    }
+
+    // `format` describes the packing of the source data (BassaltBackend.FORMAT_*).
+    // RGB8 sources are widened to the 4-byte texture format; any other size
+    // mismatch would misalign every row, so reject it instead. Compressed
+    // textures take raw blocks, so `format` is ignored for them.
+    const FORMAT_RGB8: jint = 2;
+    const FORMAT_RG8: jint = 3;
+    const FORMAT_R8: jint = 4;
+    let source_bytes_per_texel = match format {
+        FORMAT_RGB8 => 3,
+        FORMAT_RG8 => 2,
+        FORMAT_R8 => 1,
+        _ => 4,
+    };
+    let texture_bytes_per_texel = texture_info.format.block_copy_size(None).unwrap_or(4);
+    let data_vec = if texture_info.format.is_compressed() {
+        data_vec
+    } else if source_bytes_per_texel == 3 && texture_bytes_per_texel == 4 {
+        let bgra = matches!(texture_info.format,
+            wgt::TextureFormat::Bgra8Unorm | wgt::TextureFormat::Bgra8UnormSrgb);
+        match texture::expand_rgb8_to_rgba8(&data_vec, width as u32, height as u32, bgra) {
+            Ok(expanded) => {
+                log::debug!("Expanded RGB8 upload {}x{} to {:?}", width, height, texture_info.format);
+                Cow::Owned(expanded)
+            }
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Failed to write texture: {}", e));
+                return;
+            }
+        }
+    } else if source_bytes_per_texel != texture_bytes_per_texel {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!(
+            "Source format {} ({} bytes/texel) does not match texture format {:?} ({} bytes/texel)",
+            format, source_bytes_per_texel, texture_info.format, texture_bytes_per_texel));
+        return;
+    } else {
+        data_vec
+    };
+
+    // Reorder channels of sources whose order differs from the texture's (e.g. BGRA into RGBA)
+    let swizzle = match texture::SourceSwizzle::from_index(source_swizzle) {
+        Ok(swizzle) => swizzle,
         Err(e) => {
-            let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to get byte array: {}", e));
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Failed to write texture: {}", e));
+            return;
+        }
+    };
+    let data_vec = if swizzle == texture::SourceSwizzle::None {
+        data_vec
+    } else {
+        let mut swizzled = data_vec.into_owned();
+        if let Err(e) = swizzle.apply(&mut swizzled, texture_info.format) {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Failed to write texture: {}", e));
             return;
         }
+        Cow::Owned(swizzled)
     };
 
     // DEBUG: Print first 16 bytes of texture data to verify
@@ -2886,18 +7169,98 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEnc
 
     if let Err(e) = device.write_texture(
         texture_id,
+        texture_info.format,
         &data_vec,
         mip_level as u32,
         dest_x as u32,
         dest_y as u32,
-        _depth_or_layer as u32, // Use the depth_or_layer parameter as origin_z for cubemap faces
+        depth_or_layer as u32, // Array layer (e.g. cubemap face) or 3D depth slice
         width as u32,
         height as u32,
     ) {
-        let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to write texture: {}", e));
+        let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to write texture: {}", e));
     } else {
         log::info!("SUCCESS: Wrote {}x{} texture data ({} bytes) to texture {:?} at ({}, {}, layer={})",
-                  width, height, data_vec.len(), texture_id, dest_x, dest_y, _depth_or_layer);
+                  width, height, data_vec.len(), texture_id, dest_x, dest_y, depth_or_layer);
+    }
+}
+
+/// Write many texture regions from one combined byte array in a single submission
+///
+/// Entry `i` writes texture `texture_handles[i]`; its region is
+/// `regions[i*6..i*6+6]` = (mipLevel, layer, x, y, width, height) and its rows
+/// start at `data_offsets[i]` in `data`, tightly packed in the texture's format.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEncoder_writeTexturesBatch0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    texture_handles: JObject,
+    regions: JObject,
+    data_offsets: JObject,
+    data: JByteArray,
+) {
+    if device_ptr == 0 || data.is_null() {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
+        return;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    let arrays = (|| -> ::jni::errors::Result<_> {
+        Ok((
+            read_jni_long_array(&mut env, texture_handles)?,
+            read_jni_int_array(&mut env, regions)?,
+            read_jni_long_array(&mut env, data_offsets)?,
+            env.convert_byte_array(&data)?,
+        ))
+    })();
+    let (handles, regions, offsets, data) = match arrays {
+        Ok(arrays) => arrays,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid batch arrays: {}", e));
+            return;
+        }
+    };
+
+    if regions.len() != handles.len() * 6 || offsets.len() != handles.len() {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!(
+            "Batch of {} textures needs {} region ints and {} offsets, got {} and {}",
+            handles.len(), handles.len() * 6, handles.len(), regions.len(), offsets.len()));
+        return;
+    }
+
+    let mut writes = Vec::with_capacity(handles.len());
+    for (i, ((&handle, region), &offset)) in handles.iter().zip(regions.chunks_exact(6)).zip(&offsets).enumerate() {
+        let Some(info) = HANDLES.get_texture_info(handle as u64) else {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid texture handle at batch entry {}", i));
+            return;
+        };
+        if region.iter().any(|&v| v < 0) || offset < 0 {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Negative region or offset at batch entry {}", i));
+            return;
+        }
+        writes.push(texture::TextureWrite {
+            texture_id: info.id,
+            format: info.format,
+            mip_level: region[0] as u32,
+            layer: region[1] as u32,
+            x: region[2] as u32,
+            y: region[3] as u32,
+            width: region[4] as u32,
+            height: region[5] as u32,
+            data_offset: offset as u64,
+        });
+    }
+
+    match device.write_textures_batch(&writes, &data) {
+        Ok(()) => log::debug!("Wrote {} texture regions in one batch", writes.len()),
+        Err(e @ BasaltError::InvalidParameter { .. }) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+        }
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to write texture batch: {}", e));
+        }
     }
 }
 
@@ -2944,12 +7307,55 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEnc
         dst_offset as u64,
         size as u64,
     ) {
-        let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to copy buffer: {}", e));
+        let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to copy buffer: {}", e));
     } else {
         log::debug!("Copied {} bytes from buffer to buffer", size);
     }
 }
 
+/// Zero a buffer range
+///
+/// `offset` and `size` must be 4-byte aligned; a `size` of -1 clears to the
+/// end of the buffer.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEncoder_clearBuffer0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    buffer_handle: jlong,
+    offset: jlong,
+    size: jlong,
+) {
+    if device_ptr == 0 || buffer_handle == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
+        return;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    let Some(buffer_info) = HANDLES.get_buffer_info(buffer_handle as u64) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid buffer handle");
+        return;
+    };
+    if offset < 0 || size < -1 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException",
+            format!("Invalid clear range: offset {}, size {}", offset, size));
+        return;
+    }
+
+    let size = match buffer::clear_range(buffer_info.size, offset as u64, (size >= 0).then_some(size as u64)) {
+        Ok(size) => size,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            return;
+        }
+    };
+
+    if let Err(e) = device.clear_buffer(buffer_info.id, offset as u64, size) {
+        let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to clear buffer: {}", e));
+    }
+}
+
 /// Copy texture to buffer (readback)
 #[no_mangle]
 pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEncoder_copyTextureToBuffer0(
@@ -2962,17 +7368,22 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEnc
     mip_level: jint,
     width: jint,
     height: jint,
+    aspect: jint,
 ) {
     if device_ptr == 0 || texture_handle == 0 || buffer_handle == 0 {
         let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
         return;
     }
+    let Some(aspect) = map_texture_aspect_from_jni(aspect) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Unknown texture aspect {}", aspect));
+        return;
+    };
 
     let device = unsafe { &*(device_ptr as *const BasaltDevice) };
 
     // Look up texture and buffer IDs
-    let texture_id = match HANDLES.get_texture(texture_handle as u64) {
-        Some(id) => id,
+    let texture = match HANDLES.get_texture_info(texture_handle as u64) {
+        Some(info) => info,
         None => {
             let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid texture handle");
             return;
@@ -2987,15 +7398,16 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEnc
         }
     };
 
-    if let Err(e) = device.copy_texture_to_buffer(
-        texture_id,
-        buffer_id,
-        buffer_offset as u64,
-        mip_level as u32,
-        width as u32,
-        height as u32,
-    ) {
-        let _ = env.throw_new("java/lang/RuntimeException", &format!("Failed to copy texture to buffer: {}", e));
+    let readback = texture::TextureReadback {
+        texture_id: texture.id,
+        format: texture.format,
+        aspect,
+        mip_level: mip_level as u32,
+        width: width as u32,
+        height: height as u32,
+    };
+    if let Err(e) = device.copy_texture_to_buffer(readback, buffer_id, buffer_offset as u64) {
+        let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to copy texture to buffer: {}", e));
     } else {
         log::debug!("Copied {}x{} texture to buffer at offset {}", width, height, buffer_offset);
     }
@@ -3005,8 +7417,7 @@ pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltCommandEnc
 // FENCE AND SYNCHRONIZATION
 // ============================================================================
 
-/// Get current submission index for fence tracking
-/// In wgpu, we use device polling instead of explicit submission indices
+/// Submit everything recorded so far and return a submission index covering it
 #[no_mangle]
 pub extern "system" fn Java_com_criticalrange_bassalt_sync_BassaltFence_getSubmissionIndex(
     _env: JNIEnv,
@@ -3017,69 +7428,110 @@ pub extern "system" fn Java_com_criticalrange_bassalt_sync_BassaltFence_getSubmi
         return 0;
     }
     let device = unsafe { &*(device_ptr as *const BasaltDevice) };
-    
-    // Poll device to process any pending work
-    let _ = device.context().inner().device_poll(
-        device.id(),
-        wgt::PollType::Poll,
-    );
-    
-    // Return current timestamp as submission index (simplified)
-    std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map(|d| d.as_nanos() as i64)
-        .unwrap_or(0)
+
+    // A fence covers the passes recorded so far, so they're submitted with it
+    match device.submit_and_get_submission_index() {
+        Ok(index) => index as jlong,
+        Err(e) => {
+            log::warn!("Failed to submit for a fence: {}", e);
+            0
+        }
+    }
+}
+
+/// Poll device for completed work
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_sync_BassaltFence_pollDevice(
+    _env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    wait: jboolean,
+) -> jboolean {
+    if device_ptr == 0 {
+        return 1; // Return true if no device
+    }
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    // Waiting on passes that were never submitted would return at once
+    if wait != 0 {
+        if let Err(e) = device.submit_frame() {
+            log::warn!("Failed to submit the frame's render passes: {}", e);
+        }
+    }
+
+    match device.poll_device(wait != 0) {
+        Ok(queue_empty) => queue_empty as jboolean,
+        Err(e) => {
+            log::warn!("Device poll error: {}", e);
+            1 // Treat as complete on error
+        }
+    }
+}
+
+/// Check if work up to submission index is complete
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_sync_BassaltFence_isWorkComplete(
+    _env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    submission_index: jlong,
+) -> jboolean {
+    if device_ptr == 0 || submission_index == 0 {
+        return 1; // Treat as complete if no device or the fence's submit failed
+    }
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    match device.is_submission_complete(submission_index as u64) {
+        Ok(complete) => complete as jboolean,
+        Err(e) => {
+            log::warn!("Submission poll error: {}", e);
+            1 // Treat as complete on error
+        }
+    }
 }
 
-/// Poll device for completed work
+/// Submit everything recorded so far, returning an index for `isSubmissionComplete0`
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_sync_BassaltFence_pollDevice(
-    _env: JNIEnv,
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_submitAndGetSubmissionIndex0(
+    mut env: JNIEnv,
     _class: JClass,
     device_ptr: jlong,
-    wait: jboolean,
-) -> jboolean {
+) -> jlong {
     if device_ptr == 0 {
-        return 1; // Return true if no device
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
     }
     let device = unsafe { &*(device_ptr as *const BasaltDevice) };
-    
-    let poll_type = if wait != 0 {
-        wgt::PollType::wait_indefinitely()
-    } else {
-        wgt::PollType::Poll
-    };
-    
-    match device.context().inner().device_poll(device.id(), poll_type) {
-        Ok(status) => {
-            if status.is_queue_empty() { 1 } else { 0 }
-        }
+
+    match device.submit_and_get_submission_index() {
+        Ok(index) => index as jlong,
         Err(e) => {
-            log::warn!("Device poll error: {:?}", e);
-            1 // Treat as complete on error
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to submit: {}", e));
+            0
         }
     }
 }
 
-/// Check if work up to submission index is complete
+/// Whether the GPU finished the work a submission index covers, without blocking
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_sync_BassaltFence_isWorkComplete(
-    _env: JNIEnv,
+pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_isSubmissionComplete0(
+    mut env: JNIEnv,
     _class: JClass,
     device_ptr: jlong,
-    _submission_index: jlong,
+    submission_index: jlong,
 ) -> jboolean {
     if device_ptr == 0 {
-        return 1; // Treat as complete if no device
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
     }
     let device = unsafe { &*(device_ptr as *const BasaltDevice) };
-    
-    // Poll and check if queue is empty
-    match device.context().inner().device_poll(device.id(), wgt::PollType::Poll) {
-        Ok(status) => {
-            if status.is_queue_empty() { 1 } else { 0 }
+
+    match device.is_submission_complete(submission_index as u64) {
+        Ok(complete) => complete as jboolean,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            0
         }
-        Err(_) => 1 // Treat as complete on error
     }
 }
 
@@ -3255,38 +7707,393 @@ pub extern "system" fn Java_com_criticalrange_bassalt_sync_BassaltQuery_getTimes
                 .map(|&ts| (ts as f64 * period as f64) as jlong)
                 .collect();
 
-            let result = match env.new_long_array(timestamps_ns.len() as i32) {
-                Ok(arr) => arr,
-                Err(_) => {
-                    let _ = env.throw_new("java/lang/RuntimeException", "Failed to create long array");
-                    return std::ptr::null_mut();
-                }
-            };
+            let result = match env.new_long_array(timestamps_ns.len() as i32) {
+                Ok(arr) => arr,
+                Err(_) => {
+                    let _ = env.throw_new("java/lang/RuntimeException", "Failed to create long array");
+                    return std::ptr::null_mut();
+                }
+            };
+
+            match env.set_long_array_region(&result, 0, &timestamps_ns) {
+                Ok(_) => result.into_raw(),
+                Err(_) => {
+                    let _ = env.throw_new("java/lang/RuntimeException", "Failed to populate long array");
+                    std::ptr::null_mut()
+                }
+            }
+        }
+        Err(e) => {
+            let msg = format!("Failed to read timestamps: {}", e);
+            log::error!("{}", msg);
+            let _ = env.throw_new("java/lang/RuntimeException", &msg);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Resolve `count` timestamp queries starting at `first` and read them back in one map
+///
+/// Returns the timestamps in nanoseconds.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_sync_BassaltQuery_resolveAndReadQuerySet0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    query_ptr: jlong,
+    first: jint,
+    count: jint,
+) -> jlongArray {
+    if device_ptr == 0 || query_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
+        return std::ptr::null_mut();
+    }
+    if first < 0 || count <= 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid query range {}+{}", first, count));
+        return std::ptr::null_mut();
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let queries = unsafe { &*(query_ptr as *const timestamp_queries::TimestampQuerySet) };
+
+    let timestamps = match device.resolve_timestamp_queries(queries, first as u32, count as u32) {
+        Ok(timestamps) => timestamps,
+        Err(e @ BasaltError::InvalidParameter { .. }) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            return std::ptr::null_mut();
+        }
+        Err(e) => {
+            throw_submit_exception(&mut env, device, &e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    let timestamps: Vec<jlong> = timestamps.into_iter().map(|ns| ns.min(jlong::MAX as u64) as jlong).collect();
+    let result = match env.new_long_array(timestamps.len() as i32) {
+        Ok(arr) => arr,
+        Err(_) => {
+            let _ = env.throw_new("java/lang/RuntimeException", "Failed to create long array");
+            return std::ptr::null_mut();
+        }
+    };
+
+    match env.set_long_array_region(&result, 0, &timestamps) {
+        Ok(_) => result.into_raw(),
+        Err(_) => {
+            let _ = env.throw_new("java/lang/RuntimeException", "Failed to populate long array");
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Get the number of skipped undersized buffers (for statistics)
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_sync_BassaltQuery_getSkippedBufferCount(
+    _env: JNIEnv,
+    _class: JClass,
+) -> jlong {
+    timestamp_queries::get_skipped_buffer_count() as jlong
+}
+
+// ============================================================================
+// OCCLUSION QUERIES
+// ============================================================================
+
+/// Create an occlusion query set
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_sync_BassaltOcclusionQuerySet_create0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    count: jint,
+) -> jlong {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+    if count <= 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid query count {}", count));
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+
+    match device.create_occlusion_query_set(count as u32) {
+        Ok(queries) => Box::into_raw(Box::new(queries)) as jlong,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create occlusion query set: {}", e));
+            0
+        }
+    }
+}
+
+/// Destroy an occlusion query set
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_sync_BassaltOcclusionQuerySet_destroy0(
+    _env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    query_ptr: jlong,
+) {
+    if device_ptr == 0 || query_ptr == 0 {
+        return;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let queries = unsafe { Box::from_raw(query_ptr as *mut occlusion_queries::OcclusionQuerySet) };
+    queries.destroy(device.context());
+}
+
+/// Read back the sample counts of `count` queries starting at `first`
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_sync_BassaltOcclusionQuerySet_resolve0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    query_ptr: jlong,
+    first: jint,
+    count: jint,
+) -> jlongArray {
+    if device_ptr == 0 || query_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
+        return std::ptr::null_mut();
+    }
+    if first < 0 || count <= 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid query range {}+{}", first, count));
+        return std::ptr::null_mut();
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let queries = unsafe { &*(query_ptr as *const occlusion_queries::OcclusionQuerySet) };
+
+    let samples = match device.resolve_occlusion_results(queries, first as u32, count as u32) {
+        Ok(samples) => samples,
+        Err(e @ BasaltError::InvalidParameter { .. }) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            return std::ptr::null_mut();
+        }
+        Err(e) => {
+            throw_submit_exception(&mut env, device, &e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    let samples: Vec<jlong> = samples.into_iter().map(|n| n.min(jlong::MAX as u64) as jlong).collect();
+    let result = match env.new_long_array(samples.len() as i32) {
+        Ok(arr) => arr,
+        Err(_) => {
+            let _ = env.throw_new("java/lang/RuntimeException", "Failed to create long array");
+            return std::ptr::null_mut();
+        }
+    };
+
+    match env.set_long_array_region(&result, 0, &samples) {
+        Ok(_) => result.into_raw(),
+        Err(_) => {
+            let _ = env.throw_new("java/lang/RuntimeException", "Failed to populate long array");
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Start counting samples into occlusion query `index`
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass_beginOcclusionQuery0(
+    mut env: JNIEnv,
+    _class: JClass,
+    render_pass_ptr: jlong,
+    index: jint,
+) {
+    if render_pass_ptr == 0 {
+        report_recoverable_error(&mut env, null_render_pass("beginOcclusionQuery"));
+        return;
+    }
+    if index < 0 {
+        report_recoverable_error(&mut env, BasaltError::invalid_parameter("index", format!("negative query index {}", index)));
+        return;
+    }
+
+    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
+    if let Err(e) = state.record_begin_occlusion_query(index as u32) {
+        report_recoverable_error(&mut env, e);
+    }
+}
+
+/// Stop counting samples into the open occlusion query
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass_endOcclusionQuery0(
+    mut env: JNIEnv,
+    _class: JClass,
+    render_pass_ptr: jlong,
+) {
+    if render_pass_ptr == 0 {
+        report_recoverable_error(&mut env, null_render_pass("endOcclusionQuery"));
+        return;
+    }
+
+    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
+    if let Err(e) = state.record_end_occlusion_query() {
+        report_recoverable_error(&mut env, e);
+    }
+}
+
+// ============================================================================
+// PIPELINE STATISTICS QUERIES
+// ============================================================================
+
+/// Create a pipeline statistics query set
+///
+/// Throws `UnsupportedOperationException` when the device lacks `PIPELINE_STATISTICS_QUERY`.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_sync_BassaltPipelineStatsQuerySet_create0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    count: jint,
+) -> jlong {
+    if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
+        return 0;
+    }
+    if count <= 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid query count {}", count));
+        return 0;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    if !device.context().inner().device_features(device.id()).contains(wgt::Features::PIPELINE_STATISTICS_QUERY) {
+        let _ = env.throw_new(
+            "java/lang/UnsupportedOperationException",
+            "Pipeline statistics queries are not supported by this device",
+        );
+        return 0;
+    }
+
+    match device.create_pipeline_statistics_query_set(count as u32) {
+        Ok(queries) => Box::into_raw(Box::new(queries)) as jlong,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create pipeline statistics query set: {}", e));
+            0
+        }
+    }
+}
+
+/// Destroy a pipeline statistics query set
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_sync_BassaltPipelineStatsQuerySet_destroy0(
+    _env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    query_ptr: jlong,
+) {
+    if device_ptr == 0 || query_ptr == 0 {
+        return;
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let queries = unsafe { Box::from_raw(query_ptr as *mut pipeline_statistics::PipelineStatisticsQuerySet) };
+    queries.destroy(device.context());
+}
+
+/// Read back the counters of `count` queries starting at `first`
+///
+/// Returns `STATISTICS_PER_QUERY` values per query, in `PipelineStatistic` order.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_sync_BassaltPipelineStatsQuerySet_resolve0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    query_ptr: jlong,
+    first: jint,
+    count: jint,
+) -> jlongArray {
+    if device_ptr == 0 || query_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null pointer");
+        return std::ptr::null_mut();
+    }
+    if first < 0 || count <= 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid query range {}+{}", first, count));
+        return std::ptr::null_mut();
+    }
+
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let queries = unsafe { &*(query_ptr as *const pipeline_statistics::PipelineStatisticsQuerySet) };
 
-            match env.set_long_array_region(&result, 0, &timestamps_ns) {
-                Ok(_) => result.into_raw(),
-                Err(_) => {
-                    let _ = env.throw_new("java/lang/RuntimeException", "Failed to populate long array");
-                    std::ptr::null_mut()
-                }
-            }
+    let stats = match device.resolve_pipeline_statistics(queries, first as u32, count as u32) {
+        Ok(stats) => stats,
+        Err(e @ BasaltError::InvalidParameter { .. }) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            return std::ptr::null_mut();
         }
         Err(e) => {
-            let msg = format!("Failed to read timestamps: {}", e);
-            log::error!("{}", msg);
-            let _ = env.throw_new("java/lang/RuntimeException", &msg);
+            throw_submit_exception(&mut env, device, &e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    let stats: Vec<jlong> = stats.into_iter().map(|n| n.min(jlong::MAX as u64) as jlong).collect();
+    let result = match env.new_long_array(stats.len() as i32) {
+        Ok(arr) => arr,
+        Err(_) => {
+            let _ = env.throw_new("java/lang/RuntimeException", "Failed to create long array");
+            return std::ptr::null_mut();
+        }
+    };
+
+    match env.set_long_array_region(&result, 0, &stats) {
+        Ok(_) => result.into_raw(),
+        Err(_) => {
+            let _ = env.throw_new("java/lang/RuntimeException", "Failed to populate long array");
             std::ptr::null_mut()
         }
     }
 }
 
-/// Get the number of skipped undersized buffers (for statistics)
+/// Start counting draw work into pipeline statistics query `index` of a set
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_sync_BassaltQuery_getSkippedBufferCount(
-    _env: JNIEnv,
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass_beginPipelineStatsQuery0(
+    mut env: JNIEnv,
     _class: JClass,
-) -> jlong {
-    timestamp_queries::get_skipped_buffer_count() as jlong
+    render_pass_ptr: jlong,
+    query_ptr: jlong,
+    index: jint,
+) {
+    if render_pass_ptr == 0 {
+        report_recoverable_error(&mut env, null_render_pass("beginPipelineStatsQuery"));
+        return;
+    }
+    if query_ptr == 0 {
+        report_recoverable_error(&mut env, BasaltError::NullPointer { context: "beginPipelineStatsQuery: query set".into() });
+        return;
+    }
+    if index < 0 {
+        report_recoverable_error(&mut env, BasaltError::invalid_parameter("index", format!("negative query index {}", index)));
+        return;
+    }
+
+    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
+    let queries = unsafe { &*(query_ptr as *const pipeline_statistics::PipelineStatisticsQuerySet) };
+    if let Err(e) = state.record_begin_pipeline_statistics_query(queries, index as u32) {
+        report_recoverable_error(&mut env, e);
+    }
+}
+
+/// Stop counting into the open pipeline statistics query
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass_endPipelineStatsQuery0(
+    mut env: JNIEnv,
+    _class: JClass,
+    render_pass_ptr: jlong,
+) {
+    if render_pass_ptr == 0 {
+        report_recoverable_error(&mut env, null_render_pass("endPipelineStatsQuery"));
+        return;
+    }
+
+    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
+    if let Err(e) = state.record_end_pipeline_statistics_query() {
+        report_recoverable_error(&mut env, e);
+    }
 }
 
 // ============================================================================
@@ -3429,72 +8236,338 @@ pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_getM
 // RENDER BUNDLE SUPPORT
 // ============================================================================
 
-/// Create a render bundle encoder
-///
-/// # Arguments
-/// - `device_ptr` - Pointer to BasaltDevice
-/// - `color_format` - Color attachment format (as Java constant)
-/// - `sample_count` - MSAA sample count
+/// Start recording a render bundle
 ///
-/// # Returns
-/// A pointer to RenderBundleEncoder (boxed), or 0 on failure
+/// `color_formats` are the texture format constants (`FORMAT_*`) of the color
+/// attachments of the passes the bundle will run in, and `depth_format` their
+/// depth attachment's format, or -1 for none. Returns a pointer to the boxed
+/// recorder, owned by Java until `finish0` or `destroy0`.
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_createRenderBundleEncoder(
-    _env: JNIEnv,
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderBundleEncoder_create0(
+    mut env: JNIEnv,
     _class: JClass,
     device_ptr: jlong,
-    color_format_int: jint,
+    color_formats: JObject,
+    depth_format: jint,
     sample_count: jint,
 ) -> jlong {
     if device_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device pointer");
         return 0;
     }
-
     let device = unsafe { &*(device_ptr as *const BasaltDevice) };
 
-    // Convert Java format constant to wgpu format
-    let color_format = match color_format_int {
-        1 => wgt::TextureFormat::Rgba8UnormSrgb,
-        2 => wgt::TextureFormat::Bgra8UnormSrgb,
-        3 => wgt::TextureFormat::Rgba8Unorm,
-        4 => wgt::TextureFormat::Bgra8Unorm,
-        _ => wgt::TextureFormat::Bgra8UnormSrgb,
+    let format_indices = match read_jni_int_array(&mut env, color_formats) {
+        Ok(indices) => indices,
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid color formats: {}", e));
+            return 0;
+        }
+    };
+    let formats: error::Result<Vec<wgt::TextureFormat>> =
+        format_indices.iter().map(|&index| device.map_texture_format_public(index as u32)).collect();
+    let depth_format = if depth_format < 0 {
+        Ok(None)
+    } else {
+        device.map_texture_format_public(depth_format as u32).map(Some)
+    };
+    let (formats, depth_format) = match (formats, depth_format) {
+        (Ok(formats), Ok(depth_format)) => (formats, depth_format),
+        (Err(e), _) | (_, Err(e)) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            return 0;
+        }
     };
 
-    match render_bundle::create_simple_encoder(
-        device.context(),
-        device.id(),
-        color_format,
-        sample_count.max(1) as u32,
-    ) {
-        Ok(encoder) => {
-            log::debug!("Created render bundle encoder");
-            // Box the encoder and return the pointer
-            Box::into_raw(Box::new(encoder)) as jlong
+    match device.create_render_bundle_encoder(&formats, depth_format, sample_count.max(1) as u32) {
+        Ok(recorder) => Box::into_raw(Box::new(recorder)) as jlong,
+        Err(e @ BasaltError::InvalidParameter { .. }) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", e.to_string());
+            0
         }
         Err(e) => {
-            log::error!("Failed to create render bundle encoder: {}", e);
+            let _ = env.throw_new("java/lang/RuntimeException", e.to_string());
             0
         }
     }
 }
 
-/// Destroy a render bundle encoder
+/// Abandon a render bundle that hasn't been finished
 #[no_mangle]
-pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltDevice_destroyRenderBundleEncoder(
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderBundleEncoder_destroy0(
     _env: JNIEnv,
     _class: JClass,
-    _device_ptr: jlong,
     encoder_ptr: jlong,
 ) {
     if encoder_ptr == 0 {
         return;
     }
+    let _recorder = unsafe { Box::from_raw(encoder_ptr as *mut render_bundle::RenderBundleRecorder) };
+}
+
+/// Null recorder error for the render bundle record calls
+fn null_bundle_encoder(call: &str) -> BasaltError {
+    BasaltError::NullPointer { context: format!("{}: render bundle encoder", call) }
+}
+
+/// Record a pipeline change into a render bundle
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderBundleEncoder_setPipeline0(
+    mut env: JNIEnv,
+    _class: JClass,
+    encoder_ptr: jlong,
+    pipeline_handle: jlong,
+) {
+    if encoder_ptr == 0 {
+        report_recoverable_error(&mut env, null_bundle_encoder("setPipeline"));
+        return;
+    }
+    let recorder = unsafe { &mut *(encoder_ptr as *mut render_bundle::RenderBundleRecorder) };
+
+    // Pipelines still compiling can't be recorded; the bundle would have to be re-recorded anyway
+    match HANDLES.get_render_pipeline(pipeline_handle as u64) {
+        Some(pipeline_id) => recorder.set_pipeline(pipeline_id),
+        None => report_recoverable_error(&mut env, BasaltError::InvalidHandle {
+            handle_type: "ready pipeline".into(),
+            handle: pipeline_handle as u64,
+        }),
+    }
+}
+
+/// Record a bind group into a render bundle
+///
+/// `offsets` are the dynamic uniform offsets, or null for bind groups without any.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderBundleEncoder_setBindGroup0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    encoder_ptr: jlong,
+    index: jint,
+    bind_group_handle: jlong,
+    offsets: JObject,
+) {
+    if device_ptr == 0 || encoder_ptr == 0 {
+        report_recoverable_error(&mut env, null_bundle_encoder("setBindGroup"));
+        return;
+    }
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let recorder = unsafe { &mut *(encoder_ptr as *mut render_bundle::RenderBundleRecorder) };
+
+    let Some(bind_group_id) = HANDLES.get_bind_group(bind_group_handle as u64) else {
+        report_recoverable_error(&mut env, BasaltError::InvalidHandle {
+            handle_type: "bind group".into(),
+            handle: bind_group_handle as u64,
+        });
+        return;
+    };
+    let raw_offsets = match read_jni_long_array(&mut env, offsets) {
+        Ok(offsets) => offsets,
+        Err(_) => {
+            report_recoverable_error(&mut env, BasaltError::Jni("Failed to read dynamic offsets".into()));
+            return;
+        }
+    };
+
+    let alignment = device.get_limits().min_uniform_buffer_offset_alignment;
+    match bind_group::validate_dynamic_offsets(&raw_offsets, alignment) {
+        Ok(dynamic_offsets) => recorder.set_bind_group(index as u32, Some(bind_group_id), &dynamic_offsets),
+        Err(e) => report_recoverable_error(&mut env, e),
+    }
+}
+
+/// Record a vertex buffer into a render bundle
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderBundleEncoder_setVertexBuffer0(
+    mut env: JNIEnv,
+    _class: JClass,
+    encoder_ptr: jlong,
+    slot: jint,
+    buffer_handle: jlong,
+    offset: jlong,
+) {
+    if encoder_ptr == 0 {
+        report_recoverable_error(&mut env, null_bundle_encoder("setVertexBuffer"));
+        return;
+    }
+    let recorder = unsafe { &mut *(encoder_ptr as *mut render_bundle::RenderBundleRecorder) };
+
+    match HANDLES.get_buffer(buffer_handle as u64) {
+        Some(buffer_id) => recorder.set_vertex_buffer(slot as u32, buffer_id, offset as u64, None),
+        None => report_recoverable_error(&mut env, BasaltError::InvalidHandle {
+            handle_type: "vertex buffer".into(),
+            handle: buffer_handle as u64,
+        }),
+    }
+}
+
+/// Record an index buffer into a render bundle (`index_type` 0 = Uint16, 1 = Uint32)
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderBundleEncoder_setIndexBuffer0(
+    mut env: JNIEnv,
+    _class: JClass,
+    encoder_ptr: jlong,
+    buffer_handle: jlong,
+    index_type: jint,
+    offset: jlong,
+) {
+    if encoder_ptr == 0 {
+        report_recoverable_error(&mut env, null_bundle_encoder("setIndexBuffer"));
+        return;
+    }
+    let recorder = unsafe { &mut *(encoder_ptr as *mut render_bundle::RenderBundleRecorder) };
+
+    let index_format = match index_type {
+        0 => wgt::IndexFormat::Uint16,
+        1 => wgt::IndexFormat::Uint32,
+        _ => {
+            report_recoverable_error(
+                &mut env,
+                BasaltError::invalid_parameter("index_type", format!("setIndexBuffer: invalid index type {}", index_type)),
+            );
+            return;
+        }
+    };
+    match HANDLES.get_buffer(buffer_handle as u64) {
+        Some(buffer_id) => recorder.set_index_buffer(buffer_id, index_format, offset as u64, None),
+        None => report_recoverable_error(&mut env, BasaltError::InvalidHandle {
+            handle_type: "index buffer".into(),
+            handle: buffer_handle as u64,
+        }),
+    }
+}
+
+/// Record a draw into a render bundle
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderBundleEncoder_draw0(
+    mut env: JNIEnv,
+    _class: JClass,
+    encoder_ptr: jlong,
+    vertex_count: jint,
+    instance_count: jint,
+    first_vertex: jint,
+    first_instance: jint,
+) {
+    if encoder_ptr == 0 {
+        report_recoverable_error(&mut env, null_bundle_encoder("draw"));
+        return;
+    }
+    let recorder = unsafe { &mut *(encoder_ptr as *mut render_bundle::RenderBundleRecorder) };
+    recorder.draw(vertex_count as u32, instance_count as u32, first_vertex as u32, first_instance as u32);
+}
+
+/// Record an indexed draw into a render bundle
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderBundleEncoder_drawIndexed0(
+    mut env: JNIEnv,
+    _class: JClass,
+    encoder_ptr: jlong,
+    index_count: jint,
+    instance_count: jint,
+    first_index: jint,
+    base_vertex: jint,
+    first_instance: jint,
+) {
+    if encoder_ptr == 0 {
+        report_recoverable_error(&mut env, null_bundle_encoder("drawIndexed"));
+        return;
+    }
+    let recorder = unsafe { &mut *(encoder_ptr as *mut render_bundle::RenderBundleRecorder) };
+    recorder.draw_indexed(index_count as u32, instance_count as u32, first_index as u32, base_vertex, first_instance as u32);
+}
+
+/// Finish a render bundle, consuming its recorder
+///
+/// Returns the bundle handle; release it with `BassaltRenderBundle.destroy0`.
+/// The recorder pointer is invalid afterwards, even when this throws.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderBundleEncoder_finish0(
+    mut env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    encoder_ptr: jlong,
+    label: JString,
+) -> jlong {
+    if device_ptr == 0 || encoder_ptr == 0 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", "Null device or render bundle encoder pointer");
+        return 0;
+    }
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    let recorder = unsafe { Box::from_raw(encoder_ptr as *mut render_bundle::RenderBundleRecorder) };
+
+    let label: Option<String> = if label.is_null() {
+        None
+    } else {
+        env.get_string(&label).ok().map(Into::into)
+    };
+    let draw_count = recorder.draw_count();
+    match recorder.finish(device.context(), label.as_deref()) {
+        Ok(bundle_id) => {
+            log::debug!("Finished render bundle {:?} with {} draws", label, draw_count);
+            HANDLES.insert_render_bundle(bundle_id) as jlong
+        }
+        Err(e) => {
+            let _ = env.throw_new("java/lang/IllegalStateException", e.to_string());
+            0
+        }
+    }
+}
+
+/// Release a finished render bundle
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderBundle_destroy0(
+    _env: JNIEnv,
+    _class: JClass,
+    device_ptr: jlong,
+    bundle_handle: jlong,
+) {
+    if device_ptr == 0 {
+        return;
+    }
+    let device = unsafe { &*(device_ptr as *const BasaltDevice) };
+    if let Some(bundle_id) = HANDLES.remove_render_bundle(bundle_handle as u64) {
+        device.destroy_render_bundle(bundle_id);
+    }
+}
 
-    // Take ownership of the boxed encoder and drop it
-    let _encoder = unsafe { Box::from_raw(encoder_ptr as *mut wgpu_core::command::RenderBundleEncoder) };
+/// Replay render bundles in the render pass
+///
+/// The pass's pipeline, bind groups and buffers are unset afterwards; set
+/// them again before drawing outside a bundle.
+#[no_mangle]
+pub extern "system" fn Java_com_criticalrange_bassalt_pipeline_BassaltRenderPass_executeBundles0(
+    mut env: JNIEnv,
+    _class: JClass,
+    render_pass_ptr: jlong,
+    bundle_handles: JObject,
+) {
+    if render_pass_ptr == 0 {
+        report_recoverable_error(&mut env, null_render_pass("executeBundles"));
+        return;
+    }
+    let state = unsafe { &mut *(render_pass_ptr as *mut render_pass::RenderPassState) };
 
-    log::debug!("Destroyed render bundle encoder");
+    let handles = match read_jni_long_array(&mut env, bundle_handles) {
+        Ok(handles) => handles,
+        Err(_) => {
+            report_recoverable_error(&mut env, BasaltError::Jni("Failed to read render bundle handles".into()));
+            return;
+        }
+    };
+    let mut bundle_ids = Vec::with_capacity(handles.len());
+    for handle in handles {
+        match HANDLES.get_render_bundle(handle as u64) {
+            Some(bundle_id) => bundle_ids.push(bundle_id),
+            None => {
+                report_recoverable_error(&mut env, BasaltError::InvalidHandle {
+                    handle_type: "render bundle".into(),
+                    handle: handle as u64,
+                });
+                return;
+            }
+        }
+    }
+    state.record_execute_bundles(&bundle_ids);
 }
 
 // ============================================================================
@@ -3767,3 +8840,4 @@ pub extern "system" fn Java_com_criticalrange_bassalt_backend_BassaltBackend_des
 
     log::debug!("Destroyed compilation info");
 }
+
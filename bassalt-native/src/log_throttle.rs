@@ -0,0 +1,110 @@
+//! Rate-limited logging for per-draw hot paths
+//!
+//! Draws, buffer binds and bind group binds happen tens of thousands of times
+//! a frame, so logging each one at debug level floods the log and stalls the
+//! game. `debug_throttled!` gives every call site its own `LogThrottle`: a few
+//! messages per interval get through, the rest are counted and the count is
+//! reported with the first message of the next interval.
+
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// Interval `debug_throttled!` call sites are limited over
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Messages each `debug_throttled!` call site may log per interval
+pub const DEFAULT_BURST: u32 = 4;
+
+#[derive(Debug)]
+struct Window {
+    /// Start of the current interval, `None` before the first message
+    start: Option<Instant>,
+    logged: u32,
+    suppressed: u64,
+}
+
+/// Limits one call site to `burst` messages per `interval`
+#[derive(Debug)]
+pub struct LogThrottle {
+    interval: Duration,
+    burst: u32,
+    window: Mutex<Window>,
+}
+
+impl LogThrottle {
+    pub const fn new(interval: Duration, burst: u32) -> Self {
+        Self {
+            interval,
+            burst,
+            window: parking_lot::const_mutex(Window { start: None, logged: 0, suppressed: 0 }),
+        }
+    }
+
+    /// Whether a message at `now` should be logged
+    ///
+    /// Returns how many messages were suppressed since the last logged one,
+    /// or `None` if this one should be suppressed too.
+    pub fn admit(&self, now: Instant) -> Option<u64> {
+        let mut window = self.window.lock();
+        let expired = window.start.is_none_or(|start| now.saturating_duration_since(start) >= self.interval);
+        if expired {
+            window.start = Some(now);
+            window.logged = 1;
+            return Some(std::mem::take(&mut window.suppressed));
+        }
+        if window.logged < self.burst {
+            window.logged += 1;
+            return Some(0);
+        }
+        window.suppressed += 1;
+        None
+    }
+}
+
+/// `log::debug!` limited to `DEFAULT_BURST` messages per `DEFAULT_INTERVAL` per call site
+///
+/// Nothing is formatted or counted while debug logging is disabled.
+macro_rules! debug_throttled {
+    ($($arg:tt)+) => {{
+        static THROTTLE: $crate::log_throttle::LogThrottle = $crate::log_throttle::LogThrottle::new(
+            $crate::log_throttle::DEFAULT_INTERVAL,
+            $crate::log_throttle::DEFAULT_BURST,
+        );
+        if log::log_enabled!(log::Level::Debug) {
+            match THROTTLE.admit(std::time::Instant::now()) {
+                Some(0) => log::debug!($($arg)+),
+                Some(suppressed) => {
+                    log::debug!("{} ({} similar messages suppressed)", format_args!($($arg)+), suppressed)
+                }
+                None => {}
+            }
+        }
+    }};
+}
+pub(crate) use debug_throttled;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle_limits_repeated_messages() {
+        let throttle = LogThrottle::new(Duration::from_millis(100), 3);
+        let start = Instant::now();
+
+        // A burst of 3 gets through, the remaining 7 are suppressed
+        let admitted: Vec<_> = (0..10).map(|_| throttle.admit(start)).collect();
+        assert_eq!(admitted[..3], [Some(0), Some(0), Some(0)]);
+        assert!(admitted[3..].iter().all(Option::is_none));
+
+        // Still inside the interval
+        assert_eq!(throttle.admit(start + Duration::from_millis(99)), None);
+
+        // The next interval reports what was dropped, then starts counting again
+        let next = start + Duration::from_millis(100);
+        assert_eq!(throttle.admit(next), Some(8));
+        assert_eq!(throttle.admit(next), Some(0));
+        assert_eq!(throttle.admit(next + Duration::from_millis(200)), Some(0));
+    }
+}
@@ -0,0 +1,90 @@
+//! Best-effort VRAM budget of an adapter
+//!
+//! wgpu doesn't report memory budgets, so this asks the backend directly.
+//! Support per backend:
+//! - Vulkan: device-local heap sizes, with the budget left to this process
+//!   from `VK_EXT_memory_budget` (Vulkan 1.1 instances). Without the
+//!   extension the whole heap size is reported as available.
+//! - Metal, DX12, GL and the noop backend: not reported.
+
+use wgpu_core::id;
+
+use crate::context::BasaltContext;
+
+/// Device-local memory of an adapter, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VramBudget {
+    /// Combined size of the device-local heaps
+    pub total: u64,
+    /// What this process can still allocate before exceeding the budget
+    pub available: u64,
+}
+
+/// VRAM budget of `adapter_id`, or `None` where the backend doesn't report one
+pub fn query(context: &BasaltContext, adapter_id: id::AdapterId) -> Option<VramBudget> {
+    #[cfg(feature = "vulkan")]
+    if let Some(budget) = query_vulkan(context, adapter_id) {
+        return Some(budget);
+    }
+
+    let _ = (context, adapter_id);
+    None
+}
+
+#[cfg(feature = "vulkan")]
+fn query_vulkan(context: &BasaltContext, adapter_id: id::AdapterId) -> Option<VramBudget> {
+    use ash::vk;
+
+    // SAFETY: the raw handles are only used for property queries below and
+    // are neither destroyed nor kept past the guard
+    let adapter = unsafe { context.inner().adapter_as_hal::<wgpu_hal::api::Vulkan>(adapter_id) }?;
+    let shared = adapter.shared_instance();
+    let instance = shared.raw_instance();
+    let physical_device = adapter.raw_physical_device();
+
+    // SAFETY: `physical_device` belongs to `instance`, which outlives this call
+    let has_budget = shared.instance_api_version() >= vk::API_VERSION_1_1
+        && unsafe { instance.enumerate_device_extension_properties(physical_device) }.is_ok_and(|extensions| {
+            extensions.iter().any(|e| e.extension_name_as_c_str() == Ok(ash::ext::memory_budget::NAME))
+        });
+
+    let mut budget_properties = vk::PhysicalDeviceMemoryBudgetPropertiesEXT::default();
+    let memory_properties = if has_budget {
+        let mut properties = vk::PhysicalDeviceMemoryProperties2::default().push_next(&mut budget_properties);
+        // SAFETY: as above; the chained struct is supported by the extension check
+        unsafe { instance.get_physical_device_memory_properties2(physical_device, &mut properties) };
+        properties.memory_properties
+    } else {
+        // SAFETY: as above
+        unsafe { instance.get_physical_device_memory_properties(physical_device) }
+    };
+
+    let mut budget = VramBudget { total: 0, available: 0 };
+    for (i, heap) in memory_properties.memory_heaps_as_slice().iter().enumerate() {
+        if !heap.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL) {
+            continue;
+        }
+        budget.total += heap.size;
+        budget.available += if has_budget {
+            budget_properties.heap_budget[i].saturating_sub(budget_properties.heap_usage[i])
+        } else {
+            heap.size
+        };
+    }
+    (budget.total > 0).then_some(budget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noop_adapter_has_no_budget() {
+        let context = BasaltContext::new_noop();
+        let adapter_id = context
+            .inner()
+            .request_adapter(&wgpu_core::instance::RequestAdapterOptions::default(), wgpu_types::Backends::NOOP, None)
+            .unwrap();
+        assert_eq!(query(&context, adapter_id), None);
+    }
+}
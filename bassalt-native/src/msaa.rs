@@ -43,6 +43,7 @@ use wgpu_types as wgt;
 
 use crate::context::BasaltContext;
 use crate::error::{BasaltError, Result};
+use crate::resource_handles::TextureInfo;
 
 /// MSAA configuration and resources
 ///
@@ -341,6 +342,247 @@ pub fn create_color_attachment(
     }
 }
 
+/// Region of a resolve target to update, in pixels from the top-left corner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolveRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ResolveRect {
+    /// Check the rect is non-empty and inside a `width` x `height` attachment
+    pub fn validate(&self, width: u32, height: u32) -> Result<()> {
+        let fits = |offset: u32, extent: u32, size: u32| offset.checked_add(extent).is_some_and(|end| end <= size);
+        if self.width == 0 || self.height == 0 {
+            return Err(BasaltError::invalid_parameter("rect", format!("{:?} is empty", self)));
+        }
+        if !fits(self.x, self.width, width) || !fits(self.y, self.height, height) {
+            return Err(BasaltError::invalid_parameter(
+                "rect",
+                format!("{:?} exceeds the {}x{} attachment", self, width, height),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Check a multisampled `src` can be resolved into the `rect` of `dst`
+///
+/// Both must be the same size and format, and the format must be a float
+/// color format the resolve shader can load samples from.
+pub fn validate_partial_resolve(src: &TextureInfo, dst: &TextureInfo, rect: ResolveRect) -> Result<()> {
+    if src.sample_count <= 1 || dst.sample_count != 1 {
+        return Err(BasaltError::invalid_parameter(
+            "sample_count",
+            format!("resolve needs a multisampled source and single-sample target, got {} and {}", src.sample_count, dst.sample_count),
+        ));
+    }
+    if (src.width, src.height, src.format) != (dst.width, dst.height, dst.format) {
+        return Err(BasaltError::invalid_parameter(
+            "dst",
+            format!(
+                "resolve target is {}x{} {:?}, source is {}x{} {:?}",
+                dst.width, dst.height, dst.format, src.width, src.height, src.format
+            ),
+        ));
+    }
+    if !matches!(src.format.sample_type(None, None), Some(wgt::TextureSampleType::Float { .. })) {
+        return Err(BasaltError::invalid_parameter(
+            "format",
+            format!("{:?} can't be resolved by the resolve shader", src.format),
+        ));
+    }
+    rect.validate(dst.width, dst.height)
+}
+
+/// Pipeline averaging the samples of a multisampled texture into one target format
+#[derive(Debug, Clone, Copy)]
+pub struct PartialResolvePipeline {
+    pub bind_group_layout_id: id::BindGroupLayoutId,
+    pub pipeline_id: id::RenderPipelineId,
+}
+
+impl PartialResolvePipeline {
+    pub fn new(
+        context: &BasaltContext,
+        device_id: id::DeviceId,
+        format: wgt::TextureFormat,
+        driver_cache: Option<id::PipelineCacheId>,
+    ) -> Result<Self> {
+        let global = context.inner();
+        let bgl_entries = [wgt::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgt::ShaderStages::FRAGMENT,
+            ty: wgt::BindingType::Texture {
+                sample_type: wgt::TextureSampleType::Float { filterable: false },
+                view_dimension: wgt::TextureViewDimension::D2,
+                multisampled: true,
+            },
+            count: None,
+        }];
+        let (bind_group_layout_id, error) = global.device_create_bind_group_layout(
+            device_id,
+            &wgpu_core::binding_model::BindGroupLayoutDescriptor {
+                label: Some(Cow::Borrowed("Partial Resolve BGL")),
+                entries: Cow::Borrowed(&bgl_entries),
+            },
+            None,
+        );
+        if let Some(e) = error {
+            global.bind_group_layout_drop(bind_group_layout_id);
+            return Err(BasaltError::resource_creation("partial resolve bind group layout", format!("{:?}", e)));
+        }
+
+        let (pipeline_layout_id, error) = global.device_create_pipeline_layout(
+            device_id,
+            &wgpu_core::binding_model::PipelineLayoutDescriptor {
+                label: Some(Cow::Borrowed("Partial Resolve Pipeline Layout")),
+                bind_group_layouts: Cow::Borrowed(&[bind_group_layout_id]),
+                immediate_size: 0,
+            },
+            None,
+        );
+        let fail = |resource: &str, e: String| {
+            global.pipeline_layout_drop(pipeline_layout_id);
+            global.bind_group_layout_drop(bind_group_layout_id);
+            Err(BasaltError::resource_creation(resource, e))
+        };
+        if let Some(e) = error {
+            return fail("partial resolve pipeline layout", format!("{:?}", e));
+        }
+
+        let module = match crate::shader::parse_wgsl_named(include_str!("shaders/msaa_resolve.wgsl"), "msaa_resolve") {
+            Ok(module) => module,
+            Err(e) => return fail("partial resolve shader", e.to_string()),
+        };
+        let (module_id, error) = global.device_create_shader_module(
+            device_id,
+            &wgpu_core::pipeline::ShaderModuleDescriptor {
+                label: Some(Cow::Borrowed("Partial Resolve Shader")),
+                runtime_checks: wgt::ShaderRuntimeChecks::default(),
+            },
+            wgpu_core::pipeline::ShaderModuleSource::Naga(Cow::Owned(module)),
+            None,
+        );
+        if let Some(e) = error {
+            global.shader_module_drop(module_id);
+            return fail("partial resolve shader", format!("{:?}", e));
+        }
+
+        let stage = |entry_point: &'static str| wgpu_core::pipeline::ProgrammableStageDescriptor {
+            module: module_id,
+            entry_point: Some(Cow::Borrowed(entry_point)),
+            constants: Default::default(),
+            zero_initialize_workgroup_memory: true,
+        };
+        let (pipeline_id, error) = global.device_create_render_pipeline(
+            device_id,
+            &wgpu_core::pipeline::RenderPipelineDescriptor {
+                label: Some(Cow::Borrowed("Partial Resolve Pipeline")),
+                layout: Some(pipeline_layout_id),
+                vertex: wgpu_core::pipeline::VertexState { stage: stage("vs_main"), buffers: Cow::Borrowed(&[]) },
+                primitive: wgt::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgt::MultisampleState::default(),
+                fragment: Some(wgpu_core::pipeline::FragmentState {
+                    stage: stage("fs_main"),
+                    targets: Cow::Owned(vec![Some(wgt::ColorTargetState {
+                        format,
+                        blend: None,
+                        write_mask: wgt::ColorWrites::ALL,
+                    })]),
+                }),
+                multiview_mask: None,
+                cache: driver_cache,
+            },
+            None,
+        );
+        global.shader_module_drop(module_id);
+        if let Some(e) = error {
+            global.render_pipeline_drop(pipeline_id);
+            return fail("partial resolve pipeline", format!("{:?}", e));
+        }
+        global.pipeline_layout_drop(pipeline_layout_id);
+
+        log::info!("Created partial resolve pipeline for {:?}", format);
+        Ok(Self { bind_group_layout_id, pipeline_id })
+    }
+
+    /// Record a pass resolving `src_view` into the `rect` of `dst_view`
+    ///
+    /// The rest of `dst_view` is loaded and stored unchanged.
+    pub fn record(
+        &self,
+        context: &BasaltContext,
+        device_id: id::DeviceId,
+        encoder_id: id::CommandEncoderId,
+        src_view: id::TextureViewId,
+        dst_view: id::TextureViewId,
+        rect: ResolveRect,
+    ) -> Result<()> {
+        let global = context.inner();
+        let entries = [wgpu_core::binding_model::BindGroupEntry {
+            binding: 0,
+            resource: wgpu_core::binding_model::BindingResource::TextureView(src_view),
+        }];
+        let (bind_group_id, error) = global.device_create_bind_group(
+            device_id,
+            &wgpu_core::binding_model::BindGroupDescriptor {
+                label: Some(Cow::Borrowed("Partial Resolve Bind Group")),
+                layout: self.bind_group_layout_id,
+                entries: Cow::Borrowed(&entries),
+            },
+            None,
+        );
+        if let Some(e) = error {
+            return Err(BasaltError::resource_creation("partial resolve bind group", format!("{:?}", e)));
+        }
+
+        let color_attachments = [Some(command::RenderPassColorAttachment {
+            view: dst_view,
+            depth_slice: None,
+            resolve_target: None,
+            load_op: wgt::LoadOp::Load,
+            store_op: wgt::StoreOp::Store,
+        })];
+        let (mut pass, error) = global.command_encoder_begin_render_pass(
+            encoder_id,
+            &command::RenderPassDescriptor {
+                label: Some(Cow::Borrowed("Partial Resolve Pass")),
+                color_attachments: Cow::Borrowed(&color_attachments),
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+                multiview_mask: None,
+            },
+        );
+        let result = match error {
+            Some(e) => Err(BasaltError::RenderPass(format!("{:?}", e))),
+            None => {
+                let recorded = global
+                    .render_pass_set_pipeline(&mut pass, self.pipeline_id)
+                    .and_then(|_| global.render_pass_set_bind_group(&mut pass, 0, Some(bind_group_id), &[]))
+                    .and_then(|_| global.render_pass_set_scissor_rect(&mut pass, rect.x, rect.y, rect.width, rect.height))
+                    .and_then(|_| global.render_pass_draw(&mut pass, 3, 1, 0, 0))
+                    .map_err(|e| BasaltError::RenderPass(format!("{:?}", e)));
+                // The pass is ended even after a failed command so the encoder stays usable
+                let ended = global.render_pass_end(&mut pass).map_err(|e| BasaltError::RenderPass(format!("{:?}", e)));
+                recorded.and(ended)
+            }
+        };
+        global.bind_group_drop(bind_group_id);
+        result
+    }
+
+    pub fn destroy(&self, context: &BasaltContext) {
+        let global = context.inner();
+        global.render_pipeline_drop(self.pipeline_id);
+        global.bind_group_layout_drop(self.bind_group_layout_id);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -349,8 +591,8 @@ mod tests {
     fn test_msaa_config() {
         // Test that MSAAConfig fields are correctly set
         let config = MSAAConfig {
-            framebuffer_view_id: id::TextureViewId::ERROR,
-            framebuffer_texture_id: id::TextureId::ERROR,
+            framebuffer_view_id: unsafe { id::TextureViewId::from_raw(id::RawId::zip(1, 1)) },
+            framebuffer_texture_id: unsafe { id::TextureId::from_raw(id::RawId::zip(1, 1)) },
             sample_count: 4,
             format: wgt::TextureFormat::Bgra8Unorm,
             width: 1920,
@@ -365,8 +607,8 @@ mod tests {
     #[test]
     fn test_no_msaa() {
         let config = MSAAConfig {
-            framebuffer_view_id: id::TextureViewId::ERROR,
-            framebuffer_texture_id: id::TextureId::ERROR,
+            framebuffer_view_id: unsafe { id::TextureViewId::from_raw(id::RawId::zip(1, 1)) },
+            framebuffer_texture_id: unsafe { id::TextureId::from_raw(id::RawId::zip(1, 1)) },
             sample_count: 1,
             format: wgt::TextureFormat::Bgra8Unorm,
             width: 1920,
@@ -377,4 +619,34 @@ mod tests {
         assert!(!config.is_enabled());
         assert!(!config.color_attachment_needs_resolve());
     }
+
+    #[test]
+    fn test_partial_resolve_validation() {
+        let info = |sample_count, format| TextureInfo {
+            id: unsafe { id::TextureId::from_raw(id::RawId::zip(1, 1)) },
+            width: 64,
+            height: 32,
+            depth_or_array_layers: 1,
+            mip_level_count: 1,
+            dimension: wgt::TextureDimension::D2,
+            format,
+            sample_count,
+            is_cube: false,
+        };
+        let src = info(4, wgt::TextureFormat::Bgra8Unorm);
+        let dst = info(1, wgt::TextureFormat::Bgra8Unorm);
+        let rect = |x, y, width, height| ResolveRect { x, y, width, height };
+
+        assert!(validate_partial_resolve(&src, &dst, rect(0, 0, 64, 32)).is_ok());
+        assert!(validate_partial_resolve(&src, &dst, rect(60, 30, 4, 2)).is_ok());
+        // Empty, out of bounds and overflowing rects
+        assert!(validate_partial_resolve(&src, &dst, rect(0, 0, 0, 8)).is_err());
+        assert!(validate_partial_resolve(&src, &dst, rect(60, 0, 5, 8)).is_err());
+        assert!(validate_partial_resolve(&src, &dst, rect(0, u32::MAX, 8, 2)).is_err());
+        // Source and target swapped, mismatched formats, depth formats
+        assert!(validate_partial_resolve(&dst, &src, rect(0, 0, 8, 8)).is_err());
+        assert!(validate_partial_resolve(&src, &info(1, wgt::TextureFormat::Rgba8Unorm), rect(0, 0, 8, 8)).is_err());
+        let depth = info(4, wgt::TextureFormat::Depth32Float);
+        assert!(validate_partial_resolve(&depth, &info(1, wgt::TextureFormat::Depth32Float), rect(0, 0, 8, 8)).is_err());
+    }
 }
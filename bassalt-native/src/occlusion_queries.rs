@@ -0,0 +1,173 @@
+//! Occlusion query support for visibility culling
+//!
+//! An occlusion query counts the samples that pass the depth/stencil tests
+//! between `begin_occlusion_query` and `end_occlusion_query` in a render pass.
+//! Drawing a chunk's bounding box inside a query and reading back a zero
+//! count tells the caller the chunk is hidden and can be skipped.
+//!
+//! # Usage
+//!
+//! 1. Create a set with `BasaltDevice::create_occlusion_query_set`
+//! 2. Begin a render pass with the set attached
+//! 3. Wrap draws in `record_begin_occlusion_query(i)` / `record_end_occlusion_query()`
+//! 4. After the pass is submitted, `BasaltDevice::resolve_occlusion_results`
+//!    returns one sample count per query
+
+use std::borrow::Cow;
+use wgpu_core::id;
+use wgpu_types as wgt;
+
+use crate::context::BasaltContext;
+use crate::error::{BasaltError, Result};
+use crate::query_readback::{validate_query_range, QueryReadback, RESULT_SIZE};
+
+/// A set of occlusion queries with the buffers needed to read them back
+pub struct OcclusionQuerySet {
+    /// The query set ID
+    pub query_set_id: id::QuerySetId,
+    /// Buffer the query set is resolved into (QUERY_RESOLVE)
+    pub resolve_buffer_id: id::BufferId,
+    /// Buffer the results are copied to for mapping (MAP_READ)
+    pub destination_buffer_id: id::BufferId,
+    /// Number of queries in the set
+    pub count: u32,
+}
+
+impl OcclusionQuerySet {
+    /// Create a query set with `count` occlusion queries
+    pub fn new(context: &BasaltContext, device_id: id::DeviceId, count: u32) -> Result<Self> {
+        if count == 0 || count > wgt::QUERY_SET_MAX_QUERIES {
+            return Err(BasaltError::invalid_parameter(
+                "count",
+                format!("must be in 1..={}, got {}", wgt::QUERY_SET_MAX_QUERIES, count),
+            ));
+        }
+
+        let global = context.inner();
+
+        let query_set_desc = wgt::QuerySetDescriptor {
+            label: Some(Cow::Borrowed("Occlusion Query Set")),
+            count,
+            ty: wgt::QueryType::Occlusion,
+        };
+
+        let (query_set_id, error) = global.device_create_query_set(device_id, &query_set_desc, None);
+
+        if let Some(e) = error {
+            return Err(BasaltError::resource_creation("occlusion query set", format!("{:?}", e)));
+        }
+
+        let resolve_buffer_desc = wgt::BufferDescriptor {
+            label: Some(Cow::Borrowed("Occlusion Resolve Buffer")),
+            size: RESULT_SIZE * count as u64,
+            usage: wgt::BufferUsages::COPY_SRC | wgt::BufferUsages::QUERY_RESOLVE,
+            mapped_at_creation: false,
+        };
+
+        let (resolve_buffer_id, error) = global.device_create_buffer(device_id, &resolve_buffer_desc, None);
+
+        if let Some(e) = error {
+            return Err(BasaltError::resource_creation("occlusion resolve buffer", format!("{:?}", e)));
+        }
+
+        let dest_buffer_desc = wgt::BufferDescriptor {
+            label: Some(Cow::Borrowed("Occlusion Destination Buffer")),
+            size: RESULT_SIZE * count as u64,
+            usage: wgt::BufferUsages::COPY_DST | wgt::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        };
+
+        let (destination_buffer_id, error) = global.device_create_buffer(device_id, &dest_buffer_desc, None);
+
+        if let Some(e) = error {
+            return Err(BasaltError::resource_creation("occlusion destination buffer", format!("{:?}", e)));
+        }
+
+        log::info!("Created occlusion query set with {} queries", count);
+
+        Ok(Self {
+            query_set_id,
+            resolve_buffer_id,
+            destination_buffer_id,
+            count,
+        })
+    }
+
+    /// Check that `first..first + count` lies inside the set
+    pub fn validate_range(&self, first: u32, count: u32) -> Result<std::ops::Range<u32>> {
+        validate_query_range(first, count, self.count)
+    }
+
+    /// Resolve queries in `range` and read back their sample counts
+    ///
+    /// Records the resolve and copy into a new encoder, submits it and blocks
+    /// until the results are mapped. Call after the render passes that wrote
+    /// the queries have been submitted.
+    pub fn resolve(
+        &self,
+        context: &BasaltContext,
+        device_id: id::DeviceId,
+        queue_id: id::QueueId,
+        range: std::ops::Range<u32>,
+    ) -> Result<Vec<u64>> {
+        let range = self.validate_range(range.start, range.end.saturating_sub(range.start))?;
+        let readback = QueryReadback {
+            query_set_id: self.query_set_id,
+            resolve_buffer_id: self.resolve_buffer_id,
+            destination_buffer_id: self.destination_buffer_id,
+            values_per_query: 1,
+        };
+        readback.resolve_and_read(context, device_id, queue_id, range, "occlusion")
+    }
+
+    /// Release the query set and its buffers
+    pub fn destroy(&self, context: &BasaltContext) {
+        let global = context.inner();
+        global.query_set_drop(self.query_set_id);
+        global.buffer_drop(self.resolve_buffer_id);
+        global.buffer_drop(self.destination_buffer_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_pass::RenderPassState;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_occlusion_queries_in_render_pass() {
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, queue_id) = context.create_noop_device();
+        let queries = OcclusionQuerySet::new(&context, device_id, 4).unwrap();
+        assert!(OcclusionQuerySet::new(&context, device_id, 0).is_err());
+
+        let new_pass = |occlusion: Option<&OcclusionQuerySet>| {
+            RenderPassState::new(
+                context.clone(), device_id, queue_id, None, Vec::new(), None, None, None, 1, occlusion,
+                false, 0, false, 1.0, 0, 16, 16,
+            )
+            .unwrap()
+        };
+
+        let mut pass = new_pass(Some(&queries));
+        pass.record_begin_occlusion_query(0).unwrap();
+        assert!(pass.record_begin_occlusion_query(1).is_err(), "queries can't nest");
+        pass.record_end_occlusion_query().unwrap();
+        assert!(pass.record_end_occlusion_query().is_err());
+        assert!(pass.record_begin_occlusion_query(4).is_err());
+        pass.record_begin_occlusion_query(3).unwrap();
+        pass.record_end_occlusion_query().unwrap();
+
+        let mut plain = new_pass(None);
+        assert!(plain.record_begin_occlusion_query(0).is_err());
+
+        assert!(queries.validate_range(3, 2).is_err());
+        assert!(queries.validate_range(0, 0).is_err());
+        let samples = queries.resolve(&context, device_id, queue_id, 0..4).unwrap();
+        assert_eq!(samples.len(), 4);
+        let samples = queries.resolve(&context, device_id, queue_id, 1..3).unwrap();
+        assert_eq!(samples.len(), 2);
+        queries.destroy(&context);
+    }
+}
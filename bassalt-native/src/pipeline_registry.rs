@@ -11,14 +11,14 @@
 
 use std::borrow::Cow;
 use std::collections::HashMap;
-use std::sync::Arc;
-use parking_lot::RwLock;
+use std::sync::{mpsc, Arc, LazyLock};
+use parking_lot::{Mutex, RwLock};
 use wgpu_core::{id, pipeline};
 use wgpu_types as wgt;
 
 use crate::context::BasaltContext;
 use crate::error::{BasaltError, Result};
-use crate::resource_handles::{BindingLayoutEntry, PipelineDepthFormat};
+use crate::resource_handles::{BindingLayoutEntry, ExtraGroupLayout, ImmediateRanges, PipelineDepthFormat, VertexStride, HANDLES};
 use crate::shader_processor;
 use crate::shader_validator;
 
@@ -31,6 +31,10 @@ pub struct RenderPipelineKey {
     pub fragment_shader_hash: u64,
     /// Primitive topology
     pub topology: wgt::PrimitiveTopology,
+    /// Index format whose all-ones value restarts a strip; always `None` for list topologies
+    pub strip_index_format: Option<wgt::IndexFormat>,
+    /// Rasterization mode; Line/Point need the matching `POLYGON_MODE_*` feature
+    pub polygon_mode: wgt::PolygonMode,
     /// Whether depth test is enabled
     pub depth_test_enabled: bool,
     /// Whether depth write is enabled
@@ -49,12 +53,263 @@ pub struct RenderPipelineKey {
     pub blend_dst_alpha_factor: Option<wgt::BlendFactor>,
     /// Target format (color attachment format)
     pub target_format: wgt::TextureFormat,
+    /// Additional color targets for `@location(1..N)` outputs (MRT), in location order
+    pub extra_color_targets: Vec<wgt::ColorTargetState>,
     /// Depth format (CRITICAL: pipelines with different depth formats are incompatible!)
     pub depth_format: PipelineDepthFormat,
     /// Depth bias constant factor (polygon offset units)
     pub depth_bias_constant: i32,
     /// Depth bias slope scale factor (polygon offset factor)
     pub depth_bias_slope_scale: u32, // Stored as bits for hashing
+    /// MSAA sample count (must match the render pass attachments)
+    pub sample_count: u32,
+    /// Stencil test state (`StencilState::default()` = stencil disabled)
+    pub stencil: wgt::StencilState,
+    /// Depth-only variant: no fragment stage and no color targets
+    pub depth_only: bool,
+    /// Values for the shaders' WGSL `override` declarations
+    pub override_constants: OverrideConstants,
+    /// Vertex input layout
+    pub vertex_layout: VertexLayout,
+}
+
+impl RenderPipelineKey {
+    /// Depth-only variant for a depth pre-pass
+    ///
+    /// Same vertex stage, depth format and bias, with depth writes on and no
+    /// fragment stage. Fragments the shader would discard (alpha-tested
+    /// foliage, for instance) still write depth here, so such pipelines
+    /// shouldn't take part in a pre-pass.
+    pub fn depth_prepass(&self) -> Result<Self> {
+        if self.depth_format == PipelineDepthFormat::None {
+            return Err(BasaltError::invalid_parameter(
+                "depth_format",
+                "a depth pre-pass needs a pipeline that renders with a depth attachment",
+            ));
+        }
+        Ok(Self {
+            depth_only: true,
+            depth_test_enabled: true,
+            depth_write_enabled: true,
+            depth_compare: if self.depth_test_enabled { self.depth_compare } else { wgt::CompareFunction::LessEqual },
+            blend_enabled: false,
+            blend_src_color_factor: None,
+            blend_dst_color_factor: None,
+            blend_src_alpha_factor: None,
+            blend_dst_alpha_factor: None,
+            extra_color_targets: Vec::new(),
+            stencil: wgt::StencilState::default(),
+            ..self.clone()
+        })
+    }
+
+    /// Shading variant drawn after a depth pre-pass
+    ///
+    /// Only fragments at exactly the pre-pass depth pass, and depth isn't
+    /// written again. Both variants share the vertex module, so positions match.
+    pub fn depth_equal(&self) -> Result<Self> {
+        if self.depth_format == PipelineDepthFormat::None || self.depth_only {
+            return Err(BasaltError::invalid_parameter(
+                "depth_format",
+                "depth-equal shading needs a color pipeline with a depth attachment",
+            ));
+        }
+        Ok(Self {
+            depth_test_enabled: true,
+            depth_write_enabled: false,
+            depth_compare: wgt::CompareFunction::Equal,
+            ..self.clone()
+        })
+    }
+}
+
+/// Pipeline-overridable constants, sorted by name so equal sets hash equally
+///
+/// Names are the `override` identifiers, or for `@id(n)` overrides `n` as a decimal string.
+/// Values are kept as f64 bits so the set can be part of `RenderPipelineKey`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct OverrideConstants(Vec<(String, u64)>);
+
+impl OverrideConstants {
+    pub fn new(constants: impl IntoIterator<Item = (String, f64)>) -> Self {
+        let mut pairs: Vec<(String, u64)> = constants.into_iter().map(|(name, value)| (name, value.to_bits())).collect();
+        pairs.sort();
+        pairs.dedup_by(|a, b| a.0 == b.0);
+        Self(pairs)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The constants in the form `ProgrammableStageDescriptor` takes
+    ///
+    /// Both stages get the full set; a stage ignores names its module doesn't declare.
+    pub fn to_map(&self) -> naga::back::PipelineConstants {
+        self.0.iter().map(|(name, bits)| (name.clone(), f64::from_bits(*bits))).collect()
+    }
+
+    /// Names that match no `override` in any of `modules`
+    ///
+    /// An override declared with `@id(n)` is only addressable as `"n"`, so
+    /// its identifier counts as unknown, as it does at pipeline creation.
+    pub fn unknown_names(&self, modules: &[&naga::Module]) -> Vec<&str> {
+        self.0
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .filter(|name| {
+                !modules.iter().any(|module| {
+                    module.overrides.iter().any(|(_, o)| match o.id {
+                        Some(id) => id.to_string() == *name,
+                        None => o.name.as_deref() == Some(*name),
+                    })
+                })
+            })
+            .collect()
+    }
+}
+
+/// Vertex input layout of a render pipeline
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum VertexLayout {
+    /// One of the built-in layouts selected by format index
+    Fixed(usize),
+    /// Layout described by the caller, one entry per vertex buffer slot
+    Custom(Vec<VertexBufferDesc>),
+}
+
+/// A caller-described vertex buffer slot
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VertexBufferDesc {
+    pub array_stride: u64,
+    pub step_mode: wgt::VertexStepMode,
+    pub attributes: Vec<wgt::VertexAttribute>,
+}
+
+impl VertexLayout {
+    /// Built-in layout for `format_index`
+    ///
+    /// Unknown indices are accepted and fall back to POSITION_TEX_COLOR
+    /// unless `strict` is set, in which case they are an error.
+    pub fn fixed(format_index: usize, strict: bool) -> Result<Self> {
+        if strict && PipelineCache::fixed_vertex_buffer_layout(format_index).is_none() {
+            return Err(BasaltError::invalid_parameter(
+                "vertex_format",
+                format!("unknown vertex format index {}", format_index),
+            ));
+        }
+        Ok(Self::Fixed(format_index))
+    }
+
+    /// Validate and wrap a caller-described layout
+    ///
+    /// Strides must be multiples of `VERTEX_ALIGNMENT`, attributes must
+    /// fit inside their stride and each shader location may only be used once.
+    pub fn custom(buffers: Vec<VertexBufferDesc>) -> Result<Self> {
+        let mut locations = std::collections::HashSet::new();
+        for (slot, buffer) in buffers.iter().enumerate() {
+            if !buffer.array_stride.is_multiple_of(wgt::VERTEX_ALIGNMENT) {
+                return Err(BasaltError::invalid_parameter(
+                    "array_stride",
+                    format!("vertex buffer {} stride {} is not a multiple of {}",
+                        slot, buffer.array_stride, wgt::VERTEX_ALIGNMENT),
+                ));
+            }
+            for attribute in &buffer.attributes {
+                let size = attribute.format.size();
+                if !attribute.offset.is_multiple_of(size.min(wgt::VERTEX_ALIGNMENT)) {
+                    return Err(BasaltError::invalid_parameter(
+                        "offset",
+                        format!("attribute @location({}) offset {} is misaligned for {:?}",
+                            attribute.shader_location, attribute.offset, attribute.format),
+                    ));
+                }
+                if buffer.array_stride != 0 && attribute.offset + size > buffer.array_stride {
+                    return Err(BasaltError::invalid_parameter(
+                        "offset",
+                        format!("attribute @location({}) ({:?} at offset {}) exceeds vertex buffer {} stride {}",
+                            attribute.shader_location, attribute.format, attribute.offset, slot, buffer.array_stride),
+                    ));
+                }
+                if !locations.insert(attribute.shader_location) {
+                    return Err(BasaltError::invalid_parameter(
+                        "shader_location",
+                        format!("@location({}) is used by more than one attribute", attribute.shader_location),
+                    ));
+                }
+            }
+        }
+        Ok(Self::Custom(buffers))
+    }
+
+    /// Append a per-instance vertex buffer after the existing slots
+    ///
+    /// Fixed layouts are expanded into their buffer descriptions, so the
+    /// instance buffer lands in the slot after the built-in vertex buffer
+    /// (slot 1 for every fixed format except EMPTY).
+    pub fn with_instance_buffer(self, array_stride: u64, attributes: Vec<wgt::VertexAttribute>) -> Result<Self> {
+        let mut buffers = match self {
+            Self::Fixed(_) => self
+                .buffers()
+                .iter()
+                .map(|buffer| VertexBufferDesc {
+                    array_stride: buffer.array_stride,
+                    step_mode: buffer.step_mode,
+                    attributes: buffer.attributes.to_vec(),
+                })
+                .collect(),
+            Self::Custom(buffers) => buffers,
+        };
+        buffers.push(VertexBufferDesc {
+            array_stride,
+            step_mode: wgt::VertexStepMode::Instance,
+            attributes,
+        });
+        Self::custom(buffers)
+    }
+
+    /// wgpu vertex buffer layouts for pipeline creation
+    pub fn buffers(&self) -> Cow<'static, [pipeline::VertexBufferLayout<'static>]> {
+        match self {
+            Self::Fixed(format_index) => PipelineCache::create_vertex_buffer_layout(*format_index),
+            Self::Custom(buffers) => Cow::Owned(
+                buffers
+                    .iter()
+                    .map(|buffer| pipeline::VertexBufferLayout {
+                        array_stride: buffer.array_stride,
+                        step_mode: buffer.step_mode,
+                        attributes: Cow::Owned(buffer.attributes.clone()),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Number of vertex buffer slots the layout declares
+    pub fn buffer_count(&self) -> u32 {
+        match self {
+            Self::Fixed(format_index) => PipelineCache::create_vertex_buffer_layout(*format_index).len() as u32,
+            Self::Custom(buffers) => buffers.len() as u32,
+        }
+    }
+
+    /// Stride and attribute extent of the per-vertex buffer in slot 0
+    ///
+    /// `None` when the layout has no buffers or slot 0 steps per instance.
+    pub fn vertex_stride(&self) -> Option<VertexStride> {
+        self.buffers()
+            .first()
+            .filter(|buffer| buffer.step_mode == wgt::VertexStepMode::Vertex)
+            .map(|buffer| VertexStride {
+                array_stride: buffer.array_stride,
+                attributes_end: buffer
+                    .attributes
+                    .iter()
+                    .map(|attribute| attribute.offset + attribute.format.size())
+                    .max()
+                    .unwrap_or(0),
+            })
+    }
 }
 
 /// Cached shader module with metadata
@@ -88,6 +343,31 @@ pub struct CachedRenderPipeline {
     pub key: RenderPipelineKey,
 }
 
+/// What a render pipeline is created from, besides its shaders
+///
+/// The depth attachment format is `key.depth_format`.
+#[derive(Debug, Clone)]
+pub struct RenderPipelineRequest {
+    /// Cache key
+    pub key: RenderPipelineKey,
+    /// Bind group layout ID (group 0)
+    pub bind_group_layout_id: id::BindGroupLayoutId,
+    /// Pipeline layout ID
+    pub pipeline_layout_id: id::PipelineLayoutId,
+    /// Binding layouts from shader reflection
+    pub binding_layouts: Vec<BindingLayoutEntry>,
+    /// Labels the pipeline and its shader modules
+    pub label: String,
+}
+
+/// Layout reflected for one of a shader's groups after group 0, before it gets a handle
+#[derive(Debug, Clone)]
+pub struct ReflectedGroupLayout {
+    pub group: u32,
+    pub layout_id: id::BindGroupLayoutId,
+    pub entries: Vec<BindingLayoutEntry>,
+}
+
 /// Pipeline cache manager
 ///
 /// Maintains caches for:
@@ -98,10 +378,19 @@ pub struct PipelineCache {
     shader_modules: RwLock<HashMap<u64, CachedShaderModule>>,
     /// Cached render pipelines
     render_pipelines: RwLock<HashMap<RenderPipelineKey, CachedRenderPipeline>>,
+    /// Driver pipeline cache new pipelines are created with, if one was loaded
+    driver_cache: RwLock<Option<id::PipelineCacheId>>,
+    /// Pipelines being compiled on the worker pool, with everyone waiting for them
+    in_flight: Mutex<HashMap<RenderPipelineKey, Vec<PipelineReadyCallback>>>,
+    /// Explicit layout handles registered for each cached pipeline's groups after group 0
+    group_layouts: Mutex<HashMap<id::RenderPipelineId, Vec<ExtraGroupLayout>>>,
     /// Cache statistics
     stats: RwLock<CacheStats>,
 }
 
+/// Called with the outcome of a background pipeline compilation
+pub type PipelineReadyCallback = Box<dyn FnOnce(std::result::Result<&CachedRenderPipeline, &BasaltError>) + Send>;
+
 /// Cache statistics for monitoring effectiveness
 #[derive(Debug, Default, Clone, Copy)]
 pub struct CacheStats {
@@ -125,6 +414,9 @@ impl PipelineCache {
         Self {
             shader_modules: RwLock::new(HashMap::new()),
             render_pipelines: RwLock::new(HashMap::new()),
+            driver_cache: RwLock::new(None),
+            in_flight: Mutex::new(HashMap::new()),
+            group_layouts: Mutex::new(HashMap::new()),
             stats: RwLock::new(CacheStats::default()),
         }
     }
@@ -141,24 +433,56 @@ impl PipelineCache {
         label: &str,
     ) -> Result<id::ShaderModuleId> {
         let source_hash = Self::hash_wgsl(wgsl_source);
+        if let Some(module_id) = self.cached_shader_module(source_hash, label) {
+            return Ok(module_id);
+        }
 
-        // Check cache
-        {
-            let shaders = self.shader_modules.read();
-            if let Some(cached) = shaders.get(&source_hash) {
-                log::debug!("Shader cache HIT: '{}' (hash: {:x})", label, source_hash);
-                self.stats.write().shader_hits += 1;
-                return Ok(cached.module_id);
-            }
+        // Parse WGSL to naga module with compilation info logging
+        let naga_module = crate::shader::parse_wgsl_named(wgsl_source, label)?;
+        self.create_shader_module(context, device_id, source_hash, naga_module, entry_point, label)
+    }
+
+    /// Get or create a shader module from a module the caller already parsed
+    ///
+    /// `source_hash` is `hash_wgsl` of the source `naga_module` was parsed
+    /// from, so this shares cache entries with `get_or_create_shader_module`.
+    pub fn get_or_create_parsed_shader_module(
+        &self,
+        context: &Arc<BasaltContext>,
+        device_id: id::DeviceId,
+        source_hash: u64,
+        naga_module: naga::Module,
+        entry_point: &str,
+        label: &str,
+    ) -> Result<id::ShaderModuleId> {
+        if let Some(module_id) = self.cached_shader_module(source_hash, label) {
+            return Ok(module_id);
         }
+        self.create_shader_module(context, device_id, source_hash, naga_module, entry_point, label)
+    }
 
-        // Cache miss - create new shader module
+    /// Shader cache lookup that counts the hit or miss
+    fn cached_shader_module(&self, source_hash: u64, label: &str) -> Option<id::ShaderModuleId> {
+        if let Some(cached) = self.shader_modules.read().get(&source_hash) {
+            log::debug!("Shader cache HIT: '{}' (hash: {:x})", label, source_hash);
+            self.stats.write().shader_hits += 1;
+            return Some(cached.module_id);
+        }
         log::debug!("Shader cache MISS: '{}' (hash: {:x})", label, source_hash);
         self.stats.write().shader_misses += 1;
+        None
+    }
 
-        // Parse WGSL to naga module with compilation info logging
-        let naga_module = crate::shader::parse_wgsl_named(wgsl_source, label)?;
-
+    /// Process and validate a parsed shader, then create and cache its module
+    fn create_shader_module(
+        &self,
+        context: &Arc<BasaltContext>,
+        device_id: id::DeviceId,
+        source_hash: u64,
+        naga_module: naga::Module,
+        entry_point: &str,
+        label: &str,
+    ) -> Result<id::ShaderModuleId> {
         // Apply enhanced shader processing passes
         // This includes constant evaluation, bounds checking, terminator validation, etc.
         let naga_module = shader_processor::process_shader(naga_module)
@@ -184,8 +508,9 @@ impl PipelineCache {
             }
         }
 
-        log::debug!("Shader metrics for '{}': functions={}, globals={}, entry_points={}, instructions={}, complexity={}",
+        log::debug!("Shader metrics for '{}' ({:?}): functions={}, globals={}, entry_points={}, instructions={}, complexity={}",
             label,
+            validation_report.stage,
             validation_report.metrics.function_count,
             validation_report.metrics.global_count,
             validation_report.metrics.entry_point_count,
@@ -242,31 +567,15 @@ impl PipelineCache {
         &self,
         context: &Arc<BasaltContext>,
         device_id: id::DeviceId,
-        key: RenderPipelineKey,
         vertex_wgsl: &str,
         fragment_wgsl: &str,
-        pipeline_layout_id: id::PipelineLayoutId,
-        bind_group_layout_id: id::BindGroupLayoutId,
-        binding_layouts: Vec<BindingLayoutEntry>,
-        depth_format: PipelineDepthFormat,
-        vertex_format_index: usize,
-        label: &str,
+        request: RenderPipelineRequest,
     ) -> Result<CachedRenderPipeline> {
-        // Check cache
-        {
-            let pipelines = self.render_pipelines.read();
-            if let Some(cached) = pipelines.get(&key) {
-                log::info!("Pipeline cache HIT: '{}' (hash: {:x}), cached pipeline ID={:?}, depth_format={:?}",
-                    label, Self::hash_key(&key), cached.pipeline_id, cached.depth_format);
-                self.stats.write().pipeline_hits += 1;
-                return Ok(cached.clone());
-            }
+        let label = request.label.as_str();
+        if let Some(cached) = self.cached_render_pipeline(&request.key, label) {
+            return Ok(cached);
         }
 
-        // Cache miss - create new pipeline
-        log::debug!("Pipeline cache MISS: '{}' (hash: {:x})", label, Self::hash_key(&key));
-        self.stats.write().pipeline_misses += 1;
-
         // Get or create shader modules
         let vs_module = self.get_or_create_shader_module(
             context,
@@ -276,16 +585,168 @@ impl PipelineCache {
             &format!("{} - VS", label),
         )?;
 
-        let fs_module = self.get_or_create_shader_module(
+        let fs_module = if request.key.depth_only {
+            None
+        } else {
+            Some(self.get_or_create_shader_module(
+                context,
+                device_id,
+                fragment_wgsl,
+                "main",
+                &format!("{} - FS", label),
+            )?)
+        };
+
+        self.create_render_pipeline(context, device_id, vs_module, fs_module, request)
+    }
+
+    /// Get or create a render pipeline from shaders the caller already parsed
+    ///
+    /// Like `get_or_create_render_pipeline`, but the modules stand in for the
+    /// sources whose hashes `key` holds, so a cache miss doesn't parse them again.
+    pub fn get_or_create_parsed_render_pipeline(
+        &self,
+        context: &Arc<BasaltContext>,
+        device_id: id::DeviceId,
+        vertex_module: naga::Module,
+        fragment_module: naga::Module,
+        request: RenderPipelineRequest,
+    ) -> Result<CachedRenderPipeline> {
+        let (key, label) = (&request.key, request.label.as_str());
+        if let Some(cached) = self.cached_render_pipeline(key, label) {
+            return Ok(cached);
+        }
+
+        let vs_module = self.get_or_create_parsed_shader_module(
             context,
             device_id,
-            fragment_wgsl,
+            key.vertex_shader_hash,
+            vertex_module,
             "main",
-            &format!("{} - FS", label),
+            &format!("{} - VS", label),
         )?;
 
+        let fs_module = if key.depth_only {
+            None
+        } else {
+            Some(self.get_or_create_parsed_shader_module(
+                context,
+                device_id,
+                key.fragment_shader_hash,
+                fragment_module,
+                "main",
+                &format!("{} - FS", label),
+            )?)
+        };
+
+        self.create_render_pipeline(context, device_id, vs_module, fs_module, request)
+    }
+
+    /// Derive a variant of a cached pipeline, such as a depth pre-pass
+    ///
+    /// `key` is `base.key` with some state changed. The shader modules of
+    /// `base` are looked up by their source hashes, so the sources aren't
+    /// needed again; this fails if the cache was cleared since.
+    pub fn derive_render_pipeline(
+        &self,
+        context: &Arc<BasaltContext>,
+        device_id: id::DeviceId,
+        base: &CachedRenderPipeline,
+        key: RenderPipelineKey,
+        label: &str,
+    ) -> Result<CachedRenderPipeline> {
+        if let Some(cached) = self.cached_render_pipeline(&key, label) {
+            return Ok(cached);
+        }
+
+        let module = |hash: u64| {
+            self.shader_modules.read().get(&hash).map(|cached| cached.module_id).ok_or_else(|| BasaltError::NotFound {
+                resource_type: "cached shader module".to_string(),
+                name: format!("{:x} for '{}'", hash, label),
+            })
+        };
+        let vs_module = module(key.vertex_shader_hash)?;
+        let fs_module = if key.depth_only { None } else { Some(module(key.fragment_shader_hash)?) };
+
+        let request = RenderPipelineRequest {
+            key,
+            bind_group_layout_id: base.bind_group_layout_id,
+            pipeline_layout_id: base.pipeline_layout_id,
+            binding_layouts: base.binding_layouts.clone(),
+            label: label.to_string(),
+        };
+        self.create_render_pipeline(context, device_id, vs_module, fs_module, request)
+    }
+
+    /// Pipelines for drawing `color`'s geometry with a depth pre-pass
+    ///
+    /// Returns `(prepass, shading)`: draw everything with `prepass` into a
+    /// depth-only pass, then again with `shading` in a pass that loads that
+    /// depth, so the fragment shader runs once per covered pixel.
+    pub fn depth_prepass_pipelines(
+        &self,
+        context: &Arc<BasaltContext>,
+        device_id: id::DeviceId,
+        color: &CachedRenderPipeline,
+        label: &str,
+    ) -> Result<(CachedRenderPipeline, CachedRenderPipeline)> {
+        let prepass = self.derive_render_pipeline(
+            context,
+            device_id,
+            color,
+            color.key.depth_prepass()?,
+            &format!("{} - Depth Pre-pass", label),
+        )?;
+        let shading = self.derive_render_pipeline(
+            context,
+            device_id,
+            color,
+            color.key.depth_equal()?,
+            &format!("{} - Depth Equal", label),
+        )?;
+        Ok((prepass, shading))
+    }
+
+    /// Cached pipeline whose handle is `pipeline_id`, if it came from this cache
+    pub fn find_render_pipeline(&self, pipeline_id: id::RenderPipelineId) -> Option<CachedRenderPipeline> {
+        self.render_pipelines.read().values().find(|cached| cached.pipeline_id == pipeline_id).cloned()
+    }
+
+    /// Cache lookup that counts the hit or miss
+    fn cached_render_pipeline(&self, key: &RenderPipelineKey, label: &str) -> Option<CachedRenderPipeline> {
+        let pipelines = self.render_pipelines.read();
+        match pipelines.get(key) {
+            Some(cached) => {
+                log::info!("Pipeline cache HIT: '{}' (hash: {:x}), cached pipeline ID={:?}, depth_format={:?}",
+                    label, Self::hash_key(key), cached.pipeline_id, cached.depth_format);
+                self.stats.write().pipeline_hits += 1;
+                Some(cached.clone())
+            }
+            None => {
+                log::debug!("Pipeline cache MISS: '{}' (hash: {:x})", label, Self::hash_key(key));
+                self.stats.write().pipeline_misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Create a render pipeline from its shader modules and cache it under `key`
+    ///
+    /// Depth-only keys get no fragment stage, so `fs_module` is ignored for them.
+    fn create_render_pipeline(
+        &self,
+        context: &Arc<BasaltContext>,
+        device_id: id::DeviceId,
+        vs_module: id::ShaderModuleId,
+        fs_module: Option<id::ShaderModuleId>,
+        request: RenderPipelineRequest,
+    ) -> Result<CachedRenderPipeline> {
+        let RenderPipelineRequest { key, bind_group_layout_id, pipeline_layout_id, binding_layouts, label } = request;
+        let label = label.as_str();
+        let depth_format = key.depth_format;
+
         // Create vertex buffer layout
-        let vertex_buffers = Self::create_vertex_buffer_layout(vertex_format_index);
+        let vertex_buffers = key.vertex_layout.buffers();
 
         // Create depth stencil state
         log::info!("About to call create_depth_stencil_state with depth_format={:?}, bias=({}, {})", 
@@ -297,6 +758,7 @@ impl PipelineCache {
             depth_format,
             key.depth_bias_constant,
             f32::from_bits(key.depth_bias_slope_scale),
+            key.stencil.clone(),
         );
         log::info!("create_depth_stencil_state returned: {:?}", depth_stencil.is_some());
 
@@ -326,41 +788,46 @@ impl PipelineCache {
                 stage: pipeline::ProgrammableStageDescriptor {
                     module: vs_module,
                     entry_point: Some(Cow::Borrowed("main")),
-                    constants: Default::default(),
+                    constants: key.override_constants.to_map(),
                     zero_initialize_workgroup_memory: true,
                 },
                 buffers: vertex_buffers,
             },
             primitive: wgt::PrimitiveState {
                 topology: key.topology,
-                strip_index_format: None,
+                strip_index_format: key.strip_index_format,
                 front_face: wgt::FrontFace::Ccw,
                 cull_mode: None,
                 unclipped_depth: false,
-                polygon_mode: wgt::PolygonMode::Fill,
+                polygon_mode: key.polygon_mode,
                 conservative: false,
             },
             depth_stencil,
             multisample: wgt::MultisampleState {
-                count: 1,
+                count: key.sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
-            fragment: Some(pipeline::FragmentState {
+            fragment: fs_module.filter(|_| !key.depth_only).map(|fs_module| pipeline::FragmentState {
                 stage: pipeline::ProgrammableStageDescriptor {
                     module: fs_module,
                     entry_point: Some(Cow::Borrowed("main")),
-                    constants: Default::default(),
+                    constants: key.override_constants.to_map(),
                     zero_initialize_workgroup_memory: true,
                 },
-                targets: Cow::Owned(vec![Some(wgt::ColorTargetState {
-                    format: key.target_format,
-                    blend,
-                    write_mask: wgt::ColorWrites::ALL,
-                })]),
+                targets: Cow::Owned(
+                    std::iter::once(wgt::ColorTargetState {
+                        format: key.target_format,
+                        blend,
+                        write_mask: wgt::ColorWrites::ALL,
+                    })
+                    .chain(key.extra_color_targets.iter().cloned())
+                    .map(Some)
+                    .collect(),
+                ),
             }),
             multiview_mask: None,
-            cache: None,
+            cache: self.driver_cache(),
         };
 
         // Create the pipeline
@@ -399,6 +866,110 @@ impl PipelineCache {
         Ok(cached)
     }
 
+    /// Get or create a render pipeline without blocking the caller
+    ///
+    /// A cached pipeline is handed to `on_ready` before this returns and
+    /// `true` is returned. Otherwise shader processing and module and pipeline
+    /// creation run on the compile worker pool, `on_ready` is called from a
+    /// worker once they finish and `false` is returned. Requests for a key that
+    /// is already compiling wait for that compilation instead of starting
+    /// another one. The modules are parsed by the caller, which needs them for
+    /// the key and layouts anyway; see `spawn_compile_job` to do that off the
+    /// render thread too.
+    pub fn get_or_create_render_pipeline_async(
+        self: &Arc<Self>,
+        context: &Arc<BasaltContext>,
+        device_id: id::DeviceId,
+        vertex_module: naga::Module,
+        fragment_module: naga::Module,
+        request: RenderPipelineRequest,
+        on_ready: PipelineReadyCallback,
+    ) -> bool {
+        let key = request.key.clone();
+        let label = request.label.clone();
+        {
+            // Checked under the in-flight lock so a compilation finishing
+            // in between can't be missed
+            let mut in_flight = self.in_flight.lock();
+            if let Some(cached) = self.render_pipelines.read().get(&key) {
+                self.stats.write().pipeline_hits += 1;
+                drop(in_flight);
+                on_ready(Ok(cached));
+                return true;
+            }
+            if let Some(waiters) = in_flight.get_mut(&key) {
+                log::debug!("Pipeline '{}' is already compiling, waiting for it", label);
+                waiters.push(on_ready);
+                return false;
+            }
+            in_flight.insert(key.clone(), vec![on_ready]);
+        }
+
+        log::debug!("Compiling pipeline '{}' (hash: {:x}) in the background", label, Self::hash_key(&key));
+        let cache = self.clone();
+        let context = context.clone();
+        COMPILE_POOL.execute(Box::new(move || {
+            // A panic must still reach the waiters, or their handles stay pending forever
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                cache.get_or_create_parsed_render_pipeline(&context, device_id, vertex_module, fragment_module, request)
+            }))
+            .unwrap_or_else(|_| Err(BasaltError::PipelineCreation {
+                pipeline_name: label.clone(),
+                error: "compilation panicked".to_string(),
+                validation_errors: vec![],
+            }));
+            if let Err(e) = &result {
+                log::error!("Background compilation of pipeline '{}' failed: {}", label, e);
+            }
+            let waiters = cache.in_flight.lock().remove(&key).unwrap_or_default();
+            for on_ready in waiters {
+                on_ready(result.as_ref());
+            }
+        }));
+        false
+    }
+
+    /// Layout handles for the groups after group 0 of a cached pipeline
+    ///
+    /// The first caller for a pipeline registers `reflected` as explicit layout
+    /// handles. Later callers hit the cache with the same shaders, so they get
+    /// those handles back and their own reflected layouts are dropped.
+    pub fn extra_group_layouts(
+        &self,
+        context: &BasaltContext,
+        pipeline_id: id::RenderPipelineId,
+        reflected: Vec<ReflectedGroupLayout>,
+    ) -> Vec<ExtraGroupLayout> {
+        let mut group_layouts = self.group_layouts.lock();
+        if let Some(registered) = group_layouts.get(&pipeline_id) {
+            for layout in reflected {
+                context.inner().bind_group_layout_drop(layout.layout_id);
+            }
+            return registered.clone();
+        }
+        let registered: Vec<_> = reflected
+            .into_iter()
+            .map(|layout| ExtraGroupLayout {
+                group: layout.group,
+                layout_handle: HANDLES.insert_bind_group_layout(layout.layout_id, layout.entries),
+            })
+            .collect();
+        group_layouts.insert(pipeline_id, registered.clone());
+        registered
+    }
+
+    /// Driver pipeline cache passed to every pipeline created from now on
+    ///
+    /// Returns the cache it replaces.
+    pub fn set_driver_cache(&self, cache: Option<id::PipelineCacheId>) -> Option<id::PipelineCacheId> {
+        std::mem::replace(&mut *self.driver_cache.write(), cache)
+    }
+
+    /// Driver pipeline cache new pipelines are created with
+    pub fn driver_cache(&self) -> Option<id::PipelineCacheId> {
+        *self.driver_cache.read()
+    }
+
     /// Get cache statistics
     pub fn stats(&self) -> CacheStats {
         *self.stats.read()
@@ -414,6 +985,8 @@ impl PipelineCache {
         let count = shaders.len() + pipelines.len();
         shaders.clear();
         pipelines.clear();
+        // Handles already given out stay valid for the pipeline handles using them
+        self.group_layouts.lock().clear();
         *self.stats.write() = CacheStats::default();
         log::info!("Cleared pipeline cache: {} entries removed", count);
     }
@@ -443,8 +1016,39 @@ impl PipelineCache {
 
     /// Create vertex buffer layout based on format index
     /// Matches the full implementation in lib.rs
+    ///
+    /// Unknown indices fall back to POSITION_TEX_COLOR; strict devices reject
+    /// them earlier through [`VertexLayout::fixed`].
     fn create_vertex_buffer_layout(format_index: usize) -> Cow<'static, [wgpu_core::pipeline::VertexBufferLayout<'static>]> {
-        match format_index {
+        Self::fixed_vertex_buffer_layout(format_index).unwrap_or_else(|| {
+            log::warn!("Unknown vertex format index: {}, defaulting to POSITION_TEX_COLOR", format_index);
+            Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
+                array_stride: 36,
+                step_mode: wgt::VertexStepMode::Vertex,
+                attributes: Cow::Owned(vec![
+                    wgt::VertexAttribute {
+                        format: wgt::VertexFormat::Float32x3,
+                        offset: 0,
+                        shader_location: 0,
+                    },
+                    wgt::VertexAttribute {
+                        format: wgt::VertexFormat::Float32x2,
+                        offset: 12,
+                        shader_location: 1,
+                    },
+                    wgt::VertexAttribute {
+                        format: wgt::VertexFormat::Float32x4,
+                        offset: 20,
+                        shader_location: 2,
+                    },
+                ]),
+            }])
+        })
+    }
+
+    /// Vertex buffer layout of a built-in format index, `None` if the index is unknown
+    fn fixed_vertex_buffer_layout(format_index: usize) -> Option<Cow<'static, [wgpu_core::pipeline::VertexBufferLayout<'static>]>> {
+        let layout: Cow<'static, [wgpu_core::pipeline::VertexBufferLayout<'static>]> = match format_index {
             // 255 = EMPTY (no vertex input - shader uses @builtin(vertex_index))
             255 => Cow::Borrowed(&[]),
             // 0 = POSITION (3 floats)
@@ -668,32 +1272,9 @@ impl PipelineCache {
                     },
                 ]),
             }]),
-            // Default to POSITION_TEX_COLOR for unknown formats
-            _ => {
-                log::warn!("Unknown vertex format index: {}, defaulting to POSITION_TEX_COLOR", format_index);
-                Cow::Owned(vec![wgpu_core::pipeline::VertexBufferLayout {
-                    array_stride: 36,
-                    step_mode: wgt::VertexStepMode::Vertex,
-                    attributes: Cow::Owned(vec![
-                        wgt::VertexAttribute {
-                            format: wgt::VertexFormat::Float32x3,
-                            offset: 0,
-                            shader_location: 0,
-                        },
-                        wgt::VertexAttribute {
-                            format: wgt::VertexFormat::Float32x2,
-                            offset: 12,
-                            shader_location: 1,
-                        },
-                        wgt::VertexAttribute {
-                            format: wgt::VertexFormat::Float32x4,
-                            offset: 20,
-                            shader_location: 2,
-                        },
-                    ]),
-                }])
-            }
-        }
+            _ => return None,
+        };
+        Some(layout)
     }
 
     /// Create depth stencil state
@@ -711,20 +1292,13 @@ impl PipelineCache {
         depth_format: PipelineDepthFormat,
         depth_bias_constant: i32,
         depth_bias_slope_scale: f32,
+        stencil: wgt::StencilState,
     ) -> Option<wgt::DepthStencilState> {
         // CRITICAL: If pipeline doesn't write depth, return None
         // This ensures pipeline and render pass depth state match
-        if matches!(depth_format, PipelineDepthFormat::None) {
+        let Some(format) = depth_format.texture_format() else {
             log::info!("Creating pipeline WITHOUT depth stencil state (shader doesn't write depth)");
             return None;
-        }
-
-        // Determine the format to use
-        let format = match depth_format {
-            PipelineDepthFormat::None => unreachable!(), // Already handled above
-            PipelineDepthFormat::Depth32Float => wgt::TextureFormat::Depth32Float,
-            PipelineDepthFormat::Depth24Plus => wgt::TextureFormat::Depth24Plus,
-            PipelineDepthFormat::Depth24PlusStencil8 => wgt::TextureFormat::Depth24PlusStencil8,
         };
 
         log::info!("Creating pipeline WITH depth stencil state: format={:?}, bias=({}, {})", 
@@ -733,7 +1307,7 @@ impl PipelineCache {
             format,
             depth_write_enabled: if depth_test_enabled { depth_write_enabled } else { false },
             depth_compare: if depth_test_enabled { depth_compare } else { wgt::CompareFunction::Always },
-            stencil: wgt::StencilState::default(),
+            stencil,
             bias: wgt::DepthBiasState {
                 constant: depth_bias_constant,
                 slope_scale: depth_bias_slope_scale,
@@ -749,12 +1323,397 @@ impl Default for PipelineCache {
     }
 }
 
+/// Upper bound on compile workers; driver compilation is CPU-bound and the
+/// render thread needs a core of its own
+const MAX_COMPILE_WORKERS: usize = 4;
+
+type CompileJob = Box<dyn FnOnce() + Send>;
+
+/// Worker threads background pipeline compilations run on
+static COMPILE_POOL: LazyLock<CompilePool> = LazyLock::new(|| {
+    let workers = std::thread::available_parallelism()
+        .map_or(1, |n| n.get().saturating_sub(1))
+        .clamp(1, MAX_COMPILE_WORKERS);
+    CompilePool::new(workers)
+});
+
+/// Run `job` on the pipeline compile worker pool
+///
+/// For the work that leads up to `get_or_create_render_pipeline_async`, such
+/// as parsing and reflecting the shaders, when the caller shouldn't wait for it.
+pub fn spawn_compile_job(job: impl FnOnce() + Send + 'static) {
+    COMPILE_POOL.execute(Box::new(job));
+}
+
+/// Fixed set of threads taking jobs from a shared queue
+struct CompilePool {
+    /// None if no worker could be started; jobs then run on the caller
+    sender: Option<Mutex<mpsc::Sender<CompileJob>>>,
+}
+
+impl CompilePool {
+    fn new(workers: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<CompileJob>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let mut started = 0;
+        for i in 0..workers {
+            let receiver = receiver.clone();
+            let spawned = std::thread::Builder::new()
+                .name(format!("bassalt-compile-{}", i))
+                .spawn(move || loop {
+                    // The lock is released before the job runs
+                    let job = receiver.lock().recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => break,
+                    }
+                });
+            match spawned {
+                Ok(_) => started += 1,
+                Err(e) => log::error!("Failed to spawn pipeline compile worker {}: {}", i, e),
+            }
+        }
+        log::info!("Started {} pipeline compile workers", started);
+        Self { sender: (started > 0).then(|| Mutex::new(sender)) }
+    }
+
+    fn execute(&self, job: CompileJob) {
+        match &self.sender {
+            // Workers only stop once the sender is dropped, which never
+            // happens for the static pool
+            Some(sender) => {
+                let _ = sender.lock().send(job);
+            }
+            None => job(),
+        }
+    }
+}
+
+/// Strip index format for a pipeline drawn with `index_format` index buffers
+///
+/// Strip topologies restart the strip at the index format's all-ones value
+/// (0xFFFF / 0xFFFFFFFF), so one indexed draw can cover many strips. wgpu
+/// rejects a strip index format on list topologies, so those always get `None`.
+pub fn strip_index_format(
+    topology: wgt::PrimitiveTopology,
+    index_format: Option<wgt::IndexFormat>,
+) -> Option<wgt::IndexFormat> {
+    if topology.is_strip() { index_format } else { None }
+}
+
+/// Map a JNI polygon mode (0 = Fill, 1 = Line, 2 = Point) to one the device can rasterize
+///
+/// Line and Point are debugging aids (wireframe chunk meshes, for instance)
+/// gated behind optional features; without them the pipeline falls back to
+/// Fill with a warning rather than failing.
+pub fn polygon_mode(index: u32, features: wgt::Features) -> Result<wgt::PolygonMode> {
+    let (mode, feature) = match index {
+        0 => return Ok(wgt::PolygonMode::Fill),
+        1 => (wgt::PolygonMode::Line, wgt::Features::POLYGON_MODE_LINE),
+        2 => (wgt::PolygonMode::Point, wgt::Features::POLYGON_MODE_POINT),
+        _ => return Err(BasaltError::invalid_parameter("polygon_mode", format!("unknown polygon mode {}", index))),
+    };
+    if features.contains(feature) {
+        Ok(mode)
+    } else {
+        log::warn!("{:?} polygon mode needs {:?}, which the device lacks; falling back to Fill", mode, feature);
+        Ok(wgt::PolygonMode::Fill)
+    }
+}
+
+/// Check the raw JNI encodings of topology, compare function and blend factors
+///
+/// Pipeline creation maps unknown values to defaults; validation reports them.
+pub fn validate_state_encoding(primitive_topology: i32, depth_compare: i32, blend_factors: Option<[i32; 4]>) -> Result<()> {
+    if !(0..=4).contains(&primitive_topology) {
+        return Err(BasaltError::invalid_parameter(
+            "primitive_topology",
+            format!("unknown primitive topology {}", primitive_topology),
+        ));
+    }
+    if !(0..=7).contains(&depth_compare) {
+        return Err(BasaltError::invalid_parameter(
+            "depth_compare",
+            format!("unknown compare function {}", depth_compare),
+        ));
+    }
+    if let Some(factor) = blend_factors.iter().flatten().find(|f| !(0..=9).contains(*f)) {
+        return Err(BasaltError::invalid_parameter("blend_factor", format!("unknown blend factor {}", factor)));
+    }
+    Ok(())
+}
+
+/// Check a pipeline key against its parsed shaders without creating any GPU objects
+///
+/// Takes the key pipeline creation would build, so the color targets, depth
+/// format, sample count and vertex layout (fixed or custom) checked here are
+/// the ones creation uses. Validates both shaders, checks the entry points,
+/// vertex inputs, inter-stage interface and color outputs, and returns the
+/// first problem found. Running this before pipeline creation avoids leaking
+/// shader modules and layouts when creation would fail halfway.
+pub fn validate_pipeline_config(
+    vertex_module: &naga::Module,
+    fragment_module: &naga::Module,
+    key: &RenderPipelineKey,
+    shader_name: &str,
+) -> Result<()> {
+    // Format/feature compatibility of every color target
+    let primary = wgt::ColorTargetState {
+        format: key.target_format,
+        blend: key.blend_enabled.then_some(wgt::BlendState::ALPHA_BLENDING),
+        write_mask: wgt::ColorWrites::ALL,
+    };
+    for target in std::iter::once(&primary).chain(&key.extra_color_targets) {
+        let features = target.format.guaranteed_format_features(wgt::Features::empty());
+        if !features.flags.sample_count_supported(key.sample_count) {
+            return Err(BasaltError::invalid_parameter(
+                "sample_count",
+                format!("{:?} does not support {}x multisampling", target.format, key.sample_count),
+            ));
+        }
+        if target.blend.is_some() && !features.flags.contains(wgt::TextureFormatFeatureFlags::BLENDABLE) {
+            return Err(BasaltError::invalid_parameter(
+                "blend_enabled",
+                format!("{:?} is not blendable", target.format),
+            ));
+        }
+    }
+
+    // The depth attachment must take the same sample count as the color targets
+    if let Some(depth_format) = key.depth_format.texture_format() {
+        let features = depth_format.guaranteed_format_features(wgt::Features::empty());
+        if !features.flags.sample_count_supported(key.sample_count) {
+            return Err(BasaltError::invalid_parameter(
+                "sample_count",
+                format!("{:?} does not support {}x multisampling", depth_format, key.sample_count),
+            ));
+        }
+    }
+
+    shader_validator::validate_shader(vertex_module, shader_name)?;
+    shader_validator::validate_shader(fragment_module, shader_name)?;
+
+    let invalid = |error: String| BasaltError::ShaderValidation {
+        shader_name: shader_name.to_string(),
+        error,
+    };
+
+    fn find_entry(module: &naga::Module, stage: naga::ShaderStage) -> Option<&naga::EntryPoint> {
+        module.entry_points.iter().find(|ep| ep.stage == stage && ep.name == "main")
+    }
+    let vertex_entry = find_entry(vertex_module, naga::ShaderStage::Vertex)
+        .ok_or_else(|| invalid("vertex shader has no @vertex entry point named 'main'".to_string()))?;
+    let fragment_entry = find_entry(fragment_module, naga::ShaderStage::Fragment)
+        .ok_or_else(|| invalid("fragment shader has no @fragment entry point named 'main'".to_string()))?;
+
+    // Every vertex input must be provided by the vertex buffer layout
+    let provided: Vec<u32> = key
+        .vertex_layout
+        .buffers()
+        .iter()
+        .flat_map(|buffer| buffer.attributes.iter().map(|a| a.shader_location))
+        .collect();
+    for (location, _) in entry_argument_locations(vertex_module, &vertex_entry.function) {
+        if !provided.contains(&location) {
+            let layout = match &key.vertex_layout {
+                VertexLayout::Fixed(format_index) => format!("vertex format {}", format_index),
+                VertexLayout::Custom(_) => "the custom vertex layout".to_string(),
+            };
+            return Err(invalid(format!("vertex input @location({}) is not provided by {}", location, layout)));
+        }
+    }
+
+    // Every fragment input must be written by the vertex stage
+    let vertex_outputs: Vec<u32> = entry_result_locations(vertex_module, &vertex_entry.function)
+        .into_iter()
+        .map(|(location, _)| location)
+        .collect();
+    for (location, _) in entry_argument_locations(fragment_module, &fragment_entry.function) {
+        if !vertex_outputs.contains(&location) {
+            return Err(invalid(format!(
+                "fragment input @location({}) is not written by the vertex shader",
+                location
+            )));
+        }
+    }
+
+    // MRT pipelines need one output per target; a single target must be written with a float type
+    if !key.extra_color_targets.is_empty() {
+        return validate_color_target_count(fragment_module, 1 + key.extra_color_targets.len(), shader_name);
+    }
+    let color_output = entry_result_locations(fragment_module, &fragment_entry.function)
+        .into_iter()
+        .find(|(location, _)| *location == 0);
+    match color_output {
+        None => return Err(invalid("fragment shader does not write @location(0)".to_string())),
+        Some((_, ty)) => {
+            let scalar = fragment_module.types[ty].inner.scalar();
+            if scalar.is_some_and(|s| s.kind != naga::ScalarKind::Float) {
+                return Err(invalid(format!(
+                    "fragment output @location(0) is not a float type but target is {:?}",
+                    key.target_format
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that the fragment shader writes exactly one output per color target
+///
+/// Outputs map to attachments in location order, so a shader with
+/// `target_count` targets must write `@location(0)` through
+/// `@location(target_count - 1)` and nothing else.
+pub fn validate_color_target_count(fragment_module: &naga::Module, target_count: usize, shader_name: &str) -> Result<()> {
+    let entry = fragment_module
+        .entry_points
+        .iter()
+        .find(|ep| ep.stage == naga::ShaderStage::Fragment)
+        .ok_or_else(|| BasaltError::ShaderValidation {
+            shader_name: shader_name.to_string(),
+            error: "fragment shader has no @fragment entry point".to_string(),
+        })?;
+
+    let mut locations: Vec<u32> = entry_result_locations(fragment_module, &entry.function)
+        .into_iter()
+        .map(|(location, _)| location)
+        .collect();
+    locations.sort_unstable();
+
+    if !locations.iter().copied().eq(0..target_count as u32) {
+        return Err(BasaltError::ShaderValidation {
+            shader_name: shader_name.to_string(),
+            error: format!(
+                "pipeline has {} color target(s) but the fragment shader writes @location outputs {:?}; \
+                 expected @location(0) through @location({})",
+                target_count,
+                locations,
+                target_count.saturating_sub(1)
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Pick the depth format a pipeline is created with
+///
+/// An explicit `depth_format` wins, but must have a stencil aspect when the
+/// stencil test is on. Otherwise stencil needs Depth24PlusStencil8, and depth
+/// testing, depth writes or a shader writing `@builtin(frag_depth)` use the
+/// format of the depth attachment `beginRenderPass` creates. Pipelines with
+/// none of these get no depth state, for GUI and post-processing passes.
+pub fn select_depth_format(
+    depth_format: Option<PipelineDepthFormat>,
+    stencil_enabled: bool,
+    depth_test_enabled: bool,
+    depth_write_enabled: bool,
+    shader_writes_depth: bool,
+) -> Result<PipelineDepthFormat> {
+    if let Some(depth_format) = depth_format {
+        if stencil_enabled && !depth_format.has_stencil() {
+            return Err(BasaltError::invalid_parameter(
+                "depth_format",
+                format!("stencil test needs a stencil depth format, got {:?}", depth_format),
+            ));
+        }
+        return Ok(depth_format);
+    }
+
+    Ok(if stencil_enabled {
+        PipelineDepthFormat::Depth24PlusStencil8
+    } else if depth_test_enabled || depth_write_enabled || shader_writes_depth {
+        PipelineDepthFormat::from_texture_format(crate::device::DEFAULT_DEPTH_FORMAT)
+            .expect("default depth format is a pipeline depth format")
+    } else {
+        PipelineDepthFormat::None
+    })
+}
+
+/// Size of the immediate range a pipeline layout declares
+///
+/// `shader_size` is the largest `var<immediate>` block of the pipeline's
+/// shaders. Pipelines without immediates declare no range, so layouts stay
+/// valid on devices without push constants.
+pub fn pipeline_immediate_size(shader_size: u32, max_immediate_size: u32) -> Result<u32> {
+    if shader_size == 0 {
+        return Ok(0);
+    }
+    if shader_size > max_immediate_size {
+        return Err(BasaltError::invalid_parameter(
+            "immediate_size",
+            format!("shader needs {} bytes of immediates, device allows {}", shader_size, max_immediate_size),
+        ));
+    }
+    Ok(shader_size.next_multiple_of(4))
+}
+
+/// Sizes of the `var<immediate>` blocks of a vertex and fragment shader
+///
+/// A stage without immediates reports 0. Modules that fail layout (already
+/// rejected by validation) also report 0.
+pub fn immediate_ranges(vertex_module: &naga::Module, fragment_module: &naga::Module) -> ImmediateRanges {
+    let block_size = |module: &naga::Module| {
+        let mut layouter = naga::proc::Layouter::default();
+        if layouter.update(module.to_ctx()).is_err() {
+            return 0;
+        }
+        module
+            .global_variables
+            .iter()
+            .filter(|(_, global)| global.space == naga::AddressSpace::Immediate)
+            .map(|(_, global)| layouter[global.ty].size)
+            .max()
+            .unwrap_or(0)
+    };
+    ImmediateRanges { vertex: block_size(vertex_module), fragment: block_size(fragment_module) }
+}
+
+/// `@location` bindings of an entry point's arguments, flattening structs
+fn entry_argument_locations(module: &naga::Module, function: &naga::Function) -> Vec<(u32, naga::Handle<naga::Type>)> {
+    let mut locations = Vec::new();
+    for argument in &function.arguments {
+        collect_locations(module, argument.binding.as_ref(), argument.ty, &mut locations);
+    }
+    locations
+}
+
+/// `@location` bindings of an entry point's result, flattening structs
+fn entry_result_locations(module: &naga::Module, function: &naga::Function) -> Vec<(u32, naga::Handle<naga::Type>)> {
+    let mut locations = Vec::new();
+    if let Some(result) = &function.result {
+        collect_locations(module, result.binding.as_ref(), result.ty, &mut locations);
+    }
+    locations
+}
+
+fn collect_locations(
+    module: &naga::Module,
+    binding: Option<&naga::Binding>,
+    ty: naga::Handle<naga::Type>,
+    out: &mut Vec<(u32, naga::Handle<naga::Type>)>,
+) {
+    match binding {
+        Some(naga::Binding::Location { location, .. }) => out.push((*location, ty)),
+        Some(naga::Binding::BuiltIn(_)) => {}
+        None => {
+            if let naga::TypeInner::Struct { members, .. } = &module.types[ty].inner {
+                for member in members {
+                    collect_locations(module, member.binding.as_ref(), member.ty, out);
+                }
+            }
+        }
+    }
+}
+
 // Implement Hash for RenderPipelineKey
 impl std::hash::Hash for RenderPipelineKey {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.vertex_shader_hash.hash(state);
         self.fragment_shader_hash.hash(state);
         self.topology.hash(state);
+        self.strip_index_format.hash(state);
+        self.polygon_mode.hash(state);
         self.depth_test_enabled.hash(state);
         self.depth_write_enabled.hash(state);
         self.depth_compare.hash(state);
@@ -764,9 +1723,15 @@ impl std::hash::Hash for RenderPipelineKey {
         self.blend_src_alpha_factor.hash(state);
         self.blend_dst_alpha_factor.hash(state);
         self.target_format.hash(state);
+        self.extra_color_targets.hash(state);
         self.depth_format.hash(state);  // CRITICAL: Include depth_format in hash!
         self.depth_bias_constant.hash(state);  // Include depth bias in hash
         self.depth_bias_slope_scale.hash(state);  // Stored as bits for hashing
+        self.sample_count.hash(state);
+        self.stencil.hash(state);
+        self.depth_only.hash(state);
+        self.override_constants.hash(state);
+        self.vertex_layout.hash(state);
     }
 }
 
@@ -789,39 +1754,662 @@ mod tests {
         let key1 = RenderPipelineKey {
             vertex_shader_hash: 123,
             fragment_shader_hash: 456,
-            topology: wgt::PrimitiveTopology::TriangleList,
             depth_test_enabled: true,
-            depth_write_enabled: false,
             depth_compare: wgt::CompareFunction::Less,
-            blend_enabled: false,
-            blend_src_color_factor: None,
-            blend_dst_color_factor: None,
-            blend_src_alpha_factor: None,
-            blend_dst_alpha_factor: None,
             target_format: wgt::TextureFormat::Bgra8UnormSrgb,  // Updated to match swapchain
             depth_format: PipelineDepthFormat::Depth32Float,
-            depth_bias_constant: 0,
-            depth_bias_slope_scale: 0,
+            vertex_layout: VertexLayout::Fixed(3),
+            ..test_key()
         };
 
         let key2 = RenderPipelineKey {
             vertex_shader_hash: 123,
             fragment_shader_hash: 456,
-            topology: wgt::PrimitiveTopology::TriangleList,
             depth_test_enabled: true,
-            depth_write_enabled: false,
             depth_compare: wgt::CompareFunction::Less,
+            target_format: wgt::TextureFormat::Bgra8UnormSrgb,  // Updated to match swapchain
+            depth_format: PipelineDepthFormat::Depth32Float,
+            vertex_layout: VertexLayout::Fixed(3),
+            ..test_key()
+        };
+
+        assert_eq!(key1, key2);
+
+        // MSAA pipelines must not be shared with single-sample ones
+        let msaa_key = RenderPipelineKey { sample_count: 4, ..key1.clone() };
+        assert_ne!(key1, msaa_key);
+        assert_ne!(PipelineCache::hash_key(&key1), PipelineCache::hash_key(&msaa_key));
+
+        // Stencil-masked pipelines must not be shared with unmasked ones
+        let face = wgt::StencilFaceState {
+            compare: wgt::CompareFunction::Equal,
+            fail_op: wgt::StencilOperation::Keep,
+            depth_fail_op: wgt::StencilOperation::Keep,
+            pass_op: wgt::StencilOperation::Replace,
+        };
+        let stencil_key = RenderPipelineKey {
+            stencil: wgt::StencilState { front: face, back: face, read_mask: 0xFF, write_mask: 0xFF },
+            depth_format: PipelineDepthFormat::Depth24PlusStencil8,
+            ..key1.clone()
+        };
+        assert_ne!(key1, stencil_key);
+        assert_ne!(PipelineCache::hash_key(&key1), PipelineCache::hash_key(&stencil_key));
+    }
+
+    #[test]
+    fn test_custom_vertex_layout() {
+        let attribute = |shader_location, format, offset| wgt::VertexAttribute { format, offset, shader_location };
+        let per_vertex = VertexBufferDesc {
+            array_stride: 20,
+            step_mode: wgt::VertexStepMode::Vertex,
+            attributes: vec![
+                attribute(0, wgt::VertexFormat::Float32x3, 0),
+                attribute(1, wgt::VertexFormat::Float32x2, 12),
+            ],
+        };
+        // Per-instance data in slot 1
+        let per_instance = VertexBufferDesc {
+            array_stride: 16,
+            step_mode: wgt::VertexStepMode::Instance,
+            attributes: vec![attribute(2, wgt::VertexFormat::Float32x4, 0)],
+        };
+
+        let layout = VertexLayout::custom(vec![per_vertex.clone(), per_instance.clone()]).unwrap();
+        assert_eq!(layout.buffer_count(), 2);
+        let buffers = layout.buffers();
+        assert_eq!(buffers[1].step_mode, wgt::VertexStepMode::Instance);
+        assert_eq!(buffers[0].attributes.len(), 2);
+        assert_ne!(layout, VertexLayout::Fixed(2));
+
+        // Attribute past the end of its stride
+        let overflow = VertexBufferDesc { array_stride: 16, ..per_vertex.clone() };
+        assert!(VertexLayout::custom(vec![overflow]).is_err());
+
+        // Location reused across buffers
+        let duplicate = VertexBufferDesc {
+            attributes: vec![attribute(1, wgt::VertexFormat::Float32x4, 0)],
+            ..per_instance
+        };
+        assert!(VertexLayout::custom(vec![per_vertex.clone(), duplicate]).is_err());
+
+        let bad_stride = VertexBufferDesc { array_stride: 22, ..per_vertex };
+        assert!(VertexLayout::custom(vec![bad_stride]).is_err());
+    }
+
+    /// Single-sample, depthless, unblended pipeline over `VERTEX` / `FRAGMENT`
+    fn test_key() -> RenderPipelineKey {
+        RenderPipelineKey {
+            vertex_shader_hash: PipelineCache::hash_wgsl(VERTEX),
+            fragment_shader_hash: PipelineCache::hash_wgsl(FRAGMENT),
+            topology: wgt::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            polygon_mode: wgt::PolygonMode::Fill,
+            depth_test_enabled: false,
+            depth_write_enabled: false,
+            depth_compare: wgt::CompareFunction::Always,
             blend_enabled: false,
             blend_src_color_factor: None,
             blend_dst_color_factor: None,
             blend_src_alpha_factor: None,
             blend_dst_alpha_factor: None,
-            target_format: wgt::TextureFormat::Bgra8UnormSrgb,  // Updated to match swapchain
-            depth_format: PipelineDepthFormat::Depth32Float,
+            target_format: wgt::TextureFormat::Rgba8Unorm,
+            extra_color_targets: Vec::new(),
+            depth_format: PipelineDepthFormat::None,
             depth_bias_constant: 0,
             depth_bias_slope_scale: 0,
+            sample_count: 1,
+            stencil: wgt::StencilState::default(),
+            depth_only: false,
+            override_constants: OverrideConstants::default(),
+            vertex_layout: VertexLayout::Fixed(1),
+        }
+    }
+
+    fn check_config(vertex: &str, fragment: &str, key: &RenderPipelineKey) -> Result<()> {
+        let vertex_module = crate::shader::parse_wgsl_named(vertex, "test")?;
+        let fragment_module = crate::shader::parse_wgsl_named(fragment, "test")?;
+        validate_pipeline_config(&vertex_module, &fragment_module, key, "test")
+    }
+
+    const VERTEX: &str = "
+        struct VsOut {
+            @builtin(position) pos: vec4<f32>,
+            @location(0) color: vec4<f32>,
+        }
+        @vertex
+        fn main(@location(0) pos: vec3<f32>, @location(1) color: vec4<f32>) -> VsOut {
+            return VsOut(vec4<f32>(pos, 1.0), color);
+        }
+    ";
+
+    const FRAGMENT: &str = "
+        @fragment
+        fn main(@location(0) color: vec4<f32>) -> @location(0) vec4<f32> {
+            return color;
+        }
+    ";
+
+    /// Pipeline layout with a single empty bind group
+    fn empty_pipeline_layout(
+        context: &BasaltContext,
+        device_id: id::DeviceId,
+    ) -> (id::BindGroupLayoutId, id::PipelineLayoutId) {
+        let global = context.inner();
+        let (bind_group_layout_id, error) = global.device_create_bind_group_layout(
+            device_id,
+            &wgpu_core::binding_model::BindGroupLayoutDescriptor { label: None, entries: Cow::Borrowed(&[]) },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+        let (pipeline_layout_id, error) = global.device_create_pipeline_layout(
+            device_id,
+            &wgpu_core::binding_model::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: Cow::Owned(vec![bind_group_layout_id]),
+                immediate_size: 0,
+            },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+        (bind_group_layout_id, pipeline_layout_id)
+    }
+
+    #[test]
+    fn test_strict_vertex_format_rejects_unknown_index() {
+        // Lenient: unknown indices are kept and fall back to POSITION_TEX_COLOR
+        let fallback = VertexLayout::fixed(42, false).unwrap();
+        assert_eq!(fallback.buffers()[0].array_stride, 36);
+
+        // Strict: only the built-in indices are accepted
+        let error = VertexLayout::fixed(42, true).unwrap_err();
+        assert!(error.to_string().contains("unknown vertex format index 42"), "{}", error);
+        assert!(VertexLayout::fixed(9, true).is_err());
+        assert_eq!(VertexLayout::fixed(3, true).unwrap(), VertexLayout::Fixed(3));
+        assert_eq!(VertexLayout::fixed(255, true).unwrap().buffer_count(), 0);
+    }
+
+    #[test]
+    fn test_instanced_pipeline_creation() {
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, _queue_id) = context.create_noop_device();
+        let (bind_group_layout_id, pipeline_layout_id) = empty_pipeline_layout(&context, device_id);
+
+        // POSITION in slot 0, per-instance color in slot 1
+        let vertex_layout = VertexLayout::Fixed(0)
+            .with_instance_buffer(16, vec![wgt::VertexAttribute {
+                format: wgt::VertexFormat::Float32x4,
+                offset: 0,
+                shader_location: 1,
+            }])
+            .unwrap();
+        assert_eq!(vertex_layout.buffer_count(), 2);
+        assert_eq!(vertex_layout.buffers()[1].step_mode, wgt::VertexStepMode::Instance);
+
+        let key = RenderPipelineKey {
+            vertex_layout,
+            ..test_key()
         };
 
-        assert_eq!(key1, key2);
+        let cache = PipelineCache::new();
+        let pipeline = cache.get_or_create_render_pipeline(
+            &context,
+            device_id,
+            VERTEX,
+            FRAGMENT,
+            RenderPipelineRequest {
+                key,
+                bind_group_layout_id,
+                pipeline_layout_id,
+                binding_layouts: Vec::new(),
+                label: "instanced".to_string(),
+            },
+        );
+        assert!(pipeline.is_ok(), "{:?}", pipeline.err());
+    }
+
+    #[test]
+    fn test_depth16_pipeline() {
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, _queue_id) = context.create_noop_device();
+        let (bind_group_layout_id, pipeline_layout_id) = empty_pipeline_layout(&context, device_id);
+
+        let format = wgt::TextureFormat::Depth16Unorm;
+        assert_eq!(PipelineDepthFormat::from_texture_format(format), Some(PipelineDepthFormat::Depth16Unorm));
+        assert!(!PipelineDepthFormat::Depth16Unorm.has_stencil());
+
+        let adapter_id = context
+            .inner()
+            .request_adapter(&wgpu_core::instance::RequestAdapterOptions::default(), wgt::Backends::NOOP, None)
+            .unwrap();
+        let usage = wgt::TextureUsages::RENDER_ATTACHMENT;
+        crate::device::check_format_usages(&context, adapter_id, device_id, format, usage).unwrap();
+        let storage = crate::device::check_format_usages(&context, adapter_id, device_id, format, wgt::TextureUsages::STORAGE_BINDING);
+        assert!(storage.is_err());
+
+        let (_texture_id, error) = context.inner().device_create_texture(
+            device_id,
+            &wgpu_core::resource::TextureDescriptor {
+                label: Some(Cow::Borrowed("Shadow Map")),
+                size: wgt::Extent3d { width: 64, height: 64, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgt::TextureDimension::D2,
+                format,
+                usage: wgt::TextureUsages::RENDER_ATTACHMENT | wgt::TextureUsages::TEXTURE_BINDING,
+                view_formats: vec![],
+            },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+
+        let key = RenderPipelineKey {
+            depth_test_enabled: true,
+            depth_write_enabled: true,
+            depth_compare: wgt::CompareFunction::LessEqual,
+            depth_format: PipelineDepthFormat::Depth16Unorm,
+            depth_bias_constant: 2,
+            depth_bias_slope_scale: 1.5f32.to_bits(),
+            ..test_key()
+        };
+
+        let pipeline = PipelineCache::new().get_or_create_render_pipeline(
+            &context,
+            device_id,
+            VERTEX,
+            FRAGMENT,
+            RenderPipelineRequest {
+                key,
+                bind_group_layout_id,
+                pipeline_layout_id,
+                binding_layouts: Vec::new(),
+                label: "shadow".to_string(),
+            },
+        );
+        assert!(pipeline.is_ok(), "{:?}", pipeline.err());
+    }
+
+    #[test]
+    fn test_multiple_render_targets() {
+        const GBUFFER_FRAGMENT: &str = "
+            struct GBuffer {
+                @location(0) albedo: vec4<f32>,
+                @location(1) normal: vec4<f32>,
+            }
+            @fragment
+            fn main(@location(0) color: vec4<f32>) -> GBuffer {
+                return GBuffer(color, vec4<f32>(0.0, 0.0, 1.0, 1.0));
+            }
+        ";
+        let module = crate::shader::parse_wgsl_named(GBUFFER_FRAGMENT, "gbuffer").unwrap();
+        assert!(validate_color_target_count(&module, 2, "gbuffer").is_ok());
+        let err = validate_color_target_count(&module, 3, "gbuffer").unwrap_err();
+        assert!(err.to_string().contains("3 color target(s)"), "{}", err);
+        assert!(validate_color_target_count(&module, 1, "gbuffer").is_err());
+
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, _queue_id) = context.create_noop_device();
+        let (bind_group_layout_id, pipeline_layout_id) = empty_pipeline_layout(&context, device_id);
+
+        let normal_target = wgt::ColorTargetState {
+            format: wgt::TextureFormat::Rgba16Float,
+            blend: None,
+            write_mask: wgt::ColorWrites::ALL,
+        };
+        let key = RenderPipelineKey {
+            fragment_shader_hash: PipelineCache::hash_wgsl(GBUFFER_FRAGMENT),
+            extra_color_targets: vec![normal_target.clone()],
+            ..test_key()
+        };
+        let single_key = RenderPipelineKey { extra_color_targets: Vec::new(), ..key.clone() };
+        assert_ne!(PipelineCache::hash_key(&key), PipelineCache::hash_key(&single_key));
+
+        let pipeline = PipelineCache::new().get_or_create_render_pipeline(
+            &context,
+            device_id,
+            VERTEX,
+            GBUFFER_FRAGMENT,
+            RenderPipelineRequest {
+                key,
+                bind_group_layout_id,
+                pipeline_layout_id,
+                binding_layouts: Vec::new(),
+                label: "gbuffer".to_string(),
+            },
+        );
+        assert!(pipeline.is_ok(), "{:?}", pipeline.err());
+    }
+
+    #[test]
+    fn test_validate_pipeline_config() {
+        assert!(check_config(VERTEX, FRAGMENT, &test_key()).is_ok());
+
+        // Fragment reads a varying the vertex stage never writes
+        let fragment = FRAGMENT.replace("@location(0) color", "@location(3) color");
+        let err = check_config(VERTEX, &fragment, &test_key()).unwrap_err();
+        assert!(err.to_string().contains("@location(3)"), "{}", err);
+
+        // POSITION has no color attribute for @location(1)
+        let key = RenderPipelineKey { vertex_layout: VertexLayout::Fixed(0), ..test_key() };
+        let err = check_config(VERTEX, FRAGMENT, &key).unwrap_err();
+        assert!(err.to_string().contains("vertex format 0"), "{}", err);
+
+        // Custom layouts are checked the same way
+        let position_only = VertexBufferDesc {
+            array_stride: 12,
+            step_mode: wgt::VertexStepMode::Vertex,
+            attributes: vec![wgt::VertexAttribute { format: wgt::VertexFormat::Float32x3, offset: 0, shader_location: 0 }],
+        };
+        let key = RenderPipelineKey { vertex_layout: VertexLayout::custom(vec![position_only]).unwrap(), ..test_key() };
+        let err = check_config(VERTEX, FRAGMENT, &key).unwrap_err();
+        assert!(err.to_string().contains("custom vertex layout"), "{}", err);
+
+        // Depth attachment and MRT targets take the pipeline's sample count too
+        let key = RenderPipelineKey { sample_count: 4, depth_format: PipelineDepthFormat::Depth32Float, ..test_key() };
+        assert!(check_config(VERTEX, FRAGMENT, &key).is_ok());
+        let key = RenderPipelineKey { sample_count: 16, ..test_key() };
+        assert!(check_config(VERTEX, FRAGMENT, &key).is_err());
+        let float_target = wgt::ColorTargetState {
+            format: wgt::TextureFormat::Rgba32Float,
+            blend: None,
+            write_mask: wgt::ColorWrites::ALL,
+        };
+        let key = RenderPipelineKey { sample_count: 4, extra_color_targets: vec![float_target], ..test_key() };
+        let err = check_config(VERTEX, FRAGMENT, &key).unwrap_err();
+        assert!(err.to_string().contains("Rgba32Float"), "{}", err);
+
+        assert!(validate_state_encoding(3, 1, Some([6, 7, 1, 7])).is_ok());
+        assert!(validate_state_encoding(9, 1, None).is_err());
+        assert!(validate_state_encoding(3, 1, Some([6, 7, 1, 12])).is_err());
+        assert!(check_config("fn main( {", FRAGMENT, &test_key()).is_err());
+    }
+
+    #[test]
+    fn test_depth_tested_pipelines_get_depth_state() {
+        // Plain 3D pipelines never write frag_depth but still need depth testing
+        let depth_format = select_depth_format(None, false, true, true, false).unwrap();
+        assert_eq!(
+            PipelineDepthFormat::from_texture_format(crate::device::DEFAULT_DEPTH_FORMAT),
+            Some(depth_format)
+        );
+        let state = PipelineCache::create_depth_stencil_state(
+            true, true, wgt::CompareFunction::LessEqual, depth_format, 0, 0.0, wgt::StencilState::default(),
+        )
+        .expect("depth state");
+        assert_eq!(state.format, crate::device::DEFAULT_DEPTH_FORMAT);
+        assert_eq!(state.depth_compare, wgt::CompareFunction::LessEqual);
+        assert!(state.depth_write_enabled);
+
+        assert_eq!(select_depth_format(None, false, false, false, true).unwrap(), depth_format);
+        assert_eq!(select_depth_format(None, false, false, false, false).unwrap(), PipelineDepthFormat::None);
+        assert_eq!(
+            select_depth_format(None, true, false, false, false).unwrap(),
+            PipelineDepthFormat::Depth24PlusStencil8
+        );
+        assert_eq!(
+            select_depth_format(Some(PipelineDepthFormat::Depth16Unorm), false, false, false, false).unwrap(),
+            PipelineDepthFormat::Depth16Unorm
+        );
+        assert!(select_depth_format(Some(PipelineDepthFormat::Depth16Unorm), true, true, true, false).is_err());
+    }
+
+    #[test]
+    fn test_background_pipeline_creation() {
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, _queue_id) = context.create_noop_device();
+        let (bind_group_layout_id, pipeline_layout_id) = empty_pipeline_layout(&context, device_id);
+        let key = RenderPipelineKey {
+            vertex_layout: VertexLayout::Fixed(0)
+                .with_instance_buffer(16, vec![wgt::VertexAttribute {
+                    format: wgt::VertexFormat::Float32x4,
+                    offset: 0,
+                    shader_location: 1,
+                }])
+                .unwrap(),
+            ..test_key()
+        };
+
+        let cache = Arc::new(PipelineCache::new());
+        let (sender, receiver) = mpsc::channel();
+        let create = |fragment: &str| {
+            let sender = sender.clone();
+            let mut key = key.clone();
+            key.fragment_shader_hash = PipelineCache::hash_wgsl(fragment);
+            cache.get_or_create_render_pipeline_async(
+                &context,
+                device_id,
+                crate::shader::parse_wgsl(VERTEX).unwrap(),
+                crate::shader::parse_wgsl(fragment).unwrap(),
+                RenderPipelineRequest {
+                    key,
+                    bind_group_layout_id,
+                    pipeline_layout_id,
+                    binding_layouts: Vec::new(),
+                    label: "background".to_string().to_string(),
+                },
+                Box::new(move |result| sender.send(result.map(|p| p.pipeline_id).map_err(|e| e.to_string())).unwrap()),
+            )
+        };
+
+        // A cold key compiles on a worker; requests made meanwhile share it
+        assert!(!create(FRAGMENT));
+        let _ = create(FRAGMENT);
+        let timeout = std::time::Duration::from_secs(30);
+        let first = receiver.recv_timeout(timeout).unwrap().unwrap();
+        let second = receiver.recv_timeout(timeout).unwrap().unwrap();
+        assert_eq!(first, second);
+        assert_eq!(cache.stats().pipeline_misses, 1);
+
+        // Once compiled it is served from the cache before returning
+        assert!(create(FRAGMENT));
+        assert_eq!(receiver.try_recv().unwrap().unwrap(), first);
+
+        // Compilation errors reach the callback instead of leaving it waiting
+        assert!(!create("@fragment fn other() -> @location(0) vec4<f32> { return vec4<f32>(1.0); }"));
+        assert!(receiver.recv_timeout(timeout).unwrap().is_err());
+        assert!(cache.in_flight.lock().is_empty());
+    }
+
+    #[test]
+    fn test_override_constants_specialize_pipeline() {
+        const VS: &str = "@vertex fn main(@location(0) pos: vec3<f32>) -> @builtin(position) vec4<f32> { return vec4<f32>(pos, 1.0); }";
+        const FS: &str = "
+            override fog_strength: f32 = 1.0;
+            @id(7) override fog_mode: u32 = 0u;
+            @fragment
+            fn main() -> @location(0) vec4<f32> {
+                return vec4<f32>(fog_strength, f32(fog_mode), 0.0, 1.0);
+            }
+        ";
+
+        // Order doesn't matter, values do
+        let constants = OverrideConstants::new([("fog_strength".to_string(), 0.5), ("7".to_string(), 2.0)]);
+        assert_eq!(constants, OverrideConstants::new([("7".to_string(), 2.0), ("fog_strength".to_string(), 0.5)]));
+        assert_ne!(constants, OverrideConstants::new([("7".to_string(), 1.0), ("fog_strength".to_string(), 0.5)]));
+
+        let vertex_module = crate::shader::parse_wgsl_named(VS, "override vs").unwrap();
+        let fragment_module = crate::shader::parse_wgsl_named(FS, "override fs").unwrap();
+        assert!(constants.unknown_names(&[&vertex_module, &fragment_module]).is_empty());
+        let typo = OverrideConstants::new([("fog_strenght".to_string(), 0.5)]);
+        assert_eq!(typo.unknown_names(&[&vertex_module, &fragment_module]), vec!["fog_strenght"]);
+        let by_name = OverrideConstants::new([("fog_mode".to_string(), 2.0)]);
+        assert_eq!(by_name.unknown_names(&[&vertex_module, &fragment_module]), vec!["fog_mode"]);
+
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, _queue_id) = context.create_noop_device();
+        let (bind_group_layout_id, pipeline_layout_id) = empty_pipeline_layout(&context, device_id);
+        let key = RenderPipelineKey {
+            vertex_shader_hash: PipelineCache::hash_wgsl(VS),
+            fragment_shader_hash: PipelineCache::hash_wgsl(FS),
+            override_constants: constants,
+            vertex_layout: VertexLayout::Fixed(0),
+            ..test_key()
+        };
+
+        let cache = PipelineCache::new();
+        let create = |key: RenderPipelineKey| {
+            cache.get_or_create_render_pipeline(
+                &context,
+                device_id,
+                VS,
+                FS,
+                RenderPipelineRequest {
+                    key,
+                    bind_group_layout_id,
+                    pipeline_layout_id,
+                    binding_layouts: Vec::new(),
+                    label: "overrides".to_string(),
+                },
+            )
+        };
+        let specialized = create(key.clone()).unwrap();
+        let defaults = create(RenderPipelineKey { override_constants: OverrideConstants::default(), ..key }).unwrap();
+        assert_ne!(specialized.pipeline_id, defaults.pipeline_id, "each constant set is its own pipeline");
+    }
+
+    #[test]
+    fn test_derive_depth_prepass_pipeline() {
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, _queue_id) = context.create_noop_device();
+        let (bind_group_layout_id, pipeline_layout_id) = empty_pipeline_layout(&context, device_id);
+        let key = RenderPipelineKey {
+            depth_test_enabled: true,
+            depth_write_enabled: true,
+            depth_compare: wgt::CompareFunction::LessEqual,
+            blend_enabled: true,
+            blend_src_color_factor: Some(wgt::BlendFactor::SrcAlpha),
+            blend_dst_color_factor: Some(wgt::BlendFactor::OneMinusSrcAlpha),
+            depth_format: PipelineDepthFormat::Depth32Float,
+            ..test_key()
+        };
+
+        let cache = PipelineCache::new();
+        let create = |key: RenderPipelineKey| {
+            cache.get_or_create_render_pipeline(
+                &context,
+                device_id,
+                VERTEX,
+                FRAGMENT,
+                RenderPipelineRequest {
+                    key,
+                    bind_group_layout_id,
+                    pipeline_layout_id,
+                    binding_layouts: Vec::new(),
+                    label: "terrain".to_string(),
+                },
+            )
+        };
+        let color = create(key.clone()).unwrap();
+
+        let (prepass, shading) = cache.depth_prepass_pipelines(&context, device_id, &color, "terrain").unwrap();
+        // Depth-only: same vertex stage, no fragment stage or color targets, depth written
+        assert!(prepass.key.depth_only);
+        assert!(!prepass.key.blend_enabled && prepass.key.extra_color_targets.is_empty());
+        assert!(prepass.key.depth_write_enabled);
+        assert_eq!(prepass.key.vertex_shader_hash, key.vertex_shader_hash);
+        assert_eq!(prepass.pipeline_layout_id, color.pipeline_layout_id);
+        // Shading keeps the color state and only draws fragments at the pre-pass depth
+        assert!(!shading.key.depth_only && shading.key.blend_enabled);
+        assert!(!shading.key.depth_write_enabled);
+        assert_eq!(shading.key.depth_compare, wgt::CompareFunction::Equal);
+        assert_ne!(prepass.pipeline_id, color.pipeline_id);
+        assert_ne!(shading.pipeline_id, color.pipeline_id);
+
+        // Deriving again reuses the cached pipelines
+        let (again, _) = cache.depth_prepass_pipelines(&context, device_id, &color, "terrain").unwrap();
+        assert_eq!(again.pipeline_id, prepass.pipeline_id);
+        assert_eq!(cache.find_render_pipeline(prepass.pipeline_id).unwrap().key, prepass.key);
+
+        // Pipelines without a depth attachment have nothing to pre-pass
+        let flat_key = RenderPipelineKey { depth_format: PipelineDepthFormat::None, depth_test_enabled: false, ..key };
+        let flat = create(flat_key).unwrap();
+        assert!(cache.depth_prepass_pipelines(&context, device_id, &flat, "flat").is_err());
+    }
+
+    #[test]
+    fn test_triangle_strip_restart_pipeline() {
+        let uint16 = Some(wgt::IndexFormat::Uint16);
+        assert_eq!(strip_index_format(wgt::PrimitiveTopology::TriangleStrip, uint16), uint16);
+        assert_eq!(strip_index_format(wgt::PrimitiveTopology::TriangleList, uint16), None);
+        assert_eq!(strip_index_format(wgt::PrimitiveTopology::LineStrip, None), None);
+
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, _queue_id) = context.create_noop_device();
+        let (bind_group_layout_id, pipeline_layout_id) = empty_pipeline_layout(&context, device_id);
+        let key = RenderPipelineKey {
+            topology: wgt::PrimitiveTopology::TriangleStrip,
+            strip_index_format: strip_index_format(wgt::PrimitiveTopology::TriangleStrip, uint16),
+            ..test_key()
+        };
+        let unrestarted_key = RenderPipelineKey { strip_index_format: None, ..key.clone() };
+        assert_ne!(PipelineCache::hash_key(&key), PipelineCache::hash_key(&unrestarted_key));
+
+        let cache = PipelineCache::new();
+        let strip = cache
+            .get_or_create_render_pipeline(
+                &context,
+                device_id,
+                VERTEX,
+                FRAGMENT,
+                RenderPipelineRequest {
+                    key,
+                    bind_group_layout_id,
+                    pipeline_layout_id,
+                    binding_layouts: Vec::new(),
+                    label: "strip".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(strip.key.strip_index_format, uint16);
+    }
+
+    #[test]
+    fn test_wireframe_polygon_mode() {
+        let line = wgt::Features::POLYGON_MODE_LINE;
+        assert_eq!(polygon_mode(0, wgt::Features::empty()).unwrap(), wgt::PolygonMode::Fill);
+        assert_eq!(polygon_mode(1, line).unwrap(), wgt::PolygonMode::Line);
+        // Missing features fall back to Fill instead of failing pipeline creation
+        assert_eq!(polygon_mode(1, wgt::Features::empty()).unwrap(), wgt::PolygonMode::Fill);
+        assert_eq!(polygon_mode(2, line).unwrap(), wgt::PolygonMode::Fill);
+        assert!(polygon_mode(3, line).is_err());
+
+        let context = Arc::new(BasaltContext::new_noop());
+        let adapter_id = context
+            .inner()
+            .request_adapter(&wgpu_core::instance::RequestAdapterOptions::default(), wgt::Backends::NOOP, None)
+            .unwrap();
+        let (device_id, _queue_id) = context
+            .inner()
+            .adapter_request_device(
+                adapter_id,
+                &wgt::DeviceDescriptor { required_features: line, ..Default::default() },
+                None,
+                None,
+            )
+            .unwrap();
+        let (bind_group_layout_id, pipeline_layout_id) = empty_pipeline_layout(&context, device_id);
+        let key = RenderPipelineKey {
+            polygon_mode: polygon_mode(1, context.inner().device_features(device_id)).unwrap(),
+            ..test_key()
+        };
+        let filled_key = RenderPipelineKey { polygon_mode: wgt::PolygonMode::Fill, ..key.clone() };
+        assert_ne!(PipelineCache::hash_key(&key), PipelineCache::hash_key(&filled_key));
+
+        let cache = PipelineCache::new();
+        let wireframe = cache
+            .get_or_create_render_pipeline(
+                &context,
+                device_id,
+                VERTEX,
+                FRAGMENT,
+                RenderPipelineRequest {
+                    key,
+                    bind_group_layout_id,
+                    pipeline_layout_id,
+                    binding_layouts: Vec::new(),
+                    label: "wireframe".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(wireframe.key.polygon_mode, wgt::PolygonMode::Line);
     }
 }
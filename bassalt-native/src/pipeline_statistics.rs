@@ -0,0 +1,210 @@
+//! Pipeline statistics queries for per-pass rendering cost
+//!
+//! A pipeline statistics query counts the work the GPU did for the draws
+//! between `begin_pipeline_statistics_query` and `end_pipeline_statistics_query`
+//! in a render pass: vertex shader invocations, primitives entering and
+//! leaving clipping, and fragment shader invocations. Needs the
+//! `PIPELINE_STATISTICS_QUERY` feature, which the device requests when the
+//! adapter has it.
+//!
+//! # Usage
+//!
+//! 1. Create a set with `BasaltDevice::create_pipeline_statistics_query_set`
+//! 2. Wrap draws in `record_begin_pipeline_statistics_query(&set, i)` /
+//!    `record_end_pipeline_statistics_query()`
+//! 3. After the pass is submitted, `BasaltDevice::resolve_pipeline_statistics`
+//!    returns `STATISTICS_PER_QUERY` counters per query, in `PipelineStatistic` order
+
+use std::borrow::Cow;
+use wgpu_core::id;
+use wgpu_types as wgt;
+
+use crate::context::BasaltContext;
+use crate::error::{BasaltError, Result};
+use crate::query_readback::{validate_query_range, QueryReadback, RESULT_SIZE};
+
+/// Counters every query records, in the order they are resolved
+pub const RENDER_STATISTICS: wgt::PipelineStatisticsTypes = wgt::PipelineStatisticsTypes::VERTEX_SHADER_INVOCATIONS
+    .union(wgt::PipelineStatisticsTypes::CLIPPER_INVOCATIONS)
+    .union(wgt::PipelineStatisticsTypes::CLIPPER_PRIMITIVES_OUT)
+    .union(wgt::PipelineStatisticsTypes::FRAGMENT_SHADER_INVOCATIONS);
+
+/// Number of counters each query resolves to
+pub const STATISTICS_PER_QUERY: u32 = RENDER_STATISTICS.bits().count_ones();
+
+/// Position of each counter within a query's results
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(usize)]
+pub enum PipelineStatistic {
+    VertexShaderInvocations = 0,
+    ClipperInvocations = 1,
+    ClipperPrimitivesOut = 2,
+    FragmentShaderInvocations = 3,
+}
+
+/// A set of pipeline statistics queries with the buffers needed to read them back
+pub struct PipelineStatisticsQuerySet {
+    /// The query set ID
+    pub query_set_id: id::QuerySetId,
+    /// Buffer the query set is resolved into (QUERY_RESOLVE)
+    pub resolve_buffer_id: id::BufferId,
+    /// Buffer the results are copied to for mapping (MAP_READ)
+    pub destination_buffer_id: id::BufferId,
+    /// Number of queries in the set
+    pub count: u32,
+}
+
+impl PipelineStatisticsQuerySet {
+    /// Create a query set with `count` pipeline statistics queries
+    pub fn new(context: &BasaltContext, device_id: id::DeviceId, count: u32) -> Result<Self> {
+        if count == 0 || count > wgt::QUERY_SET_MAX_QUERIES {
+            return Err(BasaltError::invalid_parameter(
+                "count",
+                format!("must be in 1..={}, got {}", wgt::QUERY_SET_MAX_QUERIES, count),
+            ));
+        }
+
+        let global = context.inner();
+        if !global.device_features(device_id).contains(wgt::Features::PIPELINE_STATISTICS_QUERY) {
+            return Err(BasaltError::resource_creation(
+                "pipeline statistics query set",
+                "device does not support PIPELINE_STATISTICS_QUERY",
+            ));
+        }
+
+        let query_set_desc = wgt::QuerySetDescriptor {
+            label: Some(Cow::Borrowed("Pipeline Statistics Query Set")),
+            count,
+            ty: wgt::QueryType::PipelineStatistics(RENDER_STATISTICS),
+        };
+
+        let (query_set_id, error) = global.device_create_query_set(device_id, &query_set_desc, None);
+
+        if let Some(e) = error {
+            return Err(BasaltError::resource_creation("pipeline statistics query set", format!("{:?}", e)));
+        }
+
+        let size = RESULT_SIZE * STATISTICS_PER_QUERY as u64 * count as u64;
+        let resolve_buffer_desc = wgt::BufferDescriptor {
+            label: Some(Cow::Borrowed("Pipeline Statistics Resolve Buffer")),
+            size,
+            usage: wgt::BufferUsages::COPY_SRC | wgt::BufferUsages::QUERY_RESOLVE,
+            mapped_at_creation: false,
+        };
+
+        let (resolve_buffer_id, error) = global.device_create_buffer(device_id, &resolve_buffer_desc, None);
+
+        if let Some(e) = error {
+            global.query_set_drop(query_set_id);
+            return Err(BasaltError::resource_creation("pipeline statistics resolve buffer", format!("{:?}", e)));
+        }
+
+        let dest_buffer_desc = wgt::BufferDescriptor {
+            label: Some(Cow::Borrowed("Pipeline Statistics Destination Buffer")),
+            size,
+            usage: wgt::BufferUsages::COPY_DST | wgt::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        };
+
+        let (destination_buffer_id, error) = global.device_create_buffer(device_id, &dest_buffer_desc, None);
+
+        if let Some(e) = error {
+            global.query_set_drop(query_set_id);
+            global.buffer_drop(resolve_buffer_id);
+            return Err(BasaltError::resource_creation("pipeline statistics destination buffer", format!("{:?}", e)));
+        }
+
+        log::info!("Created pipeline statistics query set with {} queries", count);
+
+        Ok(Self {
+            query_set_id,
+            resolve_buffer_id,
+            destination_buffer_id,
+            count,
+        })
+    }
+
+    /// Check that `first..first + count` lies inside the set
+    pub fn validate_range(&self, first: u32, count: u32) -> Result<std::ops::Range<u32>> {
+        validate_query_range(first, count, self.count)
+    }
+
+    /// Resolve queries in `range` and read back their counters
+    ///
+    /// Returns `STATISTICS_PER_QUERY` values per query. Blocks until the
+    /// results are mapped; call after the passes that wrote the queries have
+    /// been submitted.
+    pub fn resolve(
+        &self,
+        context: &BasaltContext,
+        device_id: id::DeviceId,
+        queue_id: id::QueueId,
+        range: std::ops::Range<u32>,
+    ) -> Result<Vec<u64>> {
+        let range = self.validate_range(range.start, range.end.saturating_sub(range.start))?;
+        let readback = QueryReadback {
+            query_set_id: self.query_set_id,
+            resolve_buffer_id: self.resolve_buffer_id,
+            destination_buffer_id: self.destination_buffer_id,
+            values_per_query: STATISTICS_PER_QUERY,
+        };
+        readback.resolve_and_read(context, device_id, queue_id, range, "pipeline statistics")
+    }
+
+    /// Release the query set and its buffers
+    pub fn destroy(&self, context: &BasaltContext) {
+        let global = context.inner();
+        global.query_set_drop(self.query_set_id);
+        global.buffer_drop(self.resolve_buffer_id);
+        global.buffer_drop(self.destination_buffer_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_pass::RenderPassState;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_pipeline_statistics_queries_in_render_pass() {
+        assert_eq!(STATISTICS_PER_QUERY, 4);
+        assert_eq!(PipelineStatistic::FragmentShaderInvocations as u32, STATISTICS_PER_QUERY - 1);
+
+        let context = Arc::new(BasaltContext::new_noop());
+        let adapter_id = context
+            .inner()
+            .request_adapter(&wgpu_core::instance::RequestAdapterOptions::default(), wgt::Backends::NOOP, None)
+            .unwrap();
+        let device = |required_features| {
+            context
+                .inner()
+                .adapter_request_device(adapter_id, &wgt::DeviceDescriptor { required_features, ..Default::default() }, None, None)
+                .unwrap()
+        };
+
+        // Devices without the feature get an error rather than a broken query set
+        let (plain_device, _) = device(wgt::Features::empty());
+        assert!(PipelineStatisticsQuerySet::new(&context, plain_device, 4).is_err());
+
+        let (device_id, queue_id) = device(wgt::Features::PIPELINE_STATISTICS_QUERY);
+        let queries = PipelineStatisticsQuerySet::new(&context, device_id, 4).unwrap();
+        assert!(PipelineStatisticsQuerySet::new(&context, device_id, 0).is_err());
+
+        let mut pass = RenderPassState::new(
+            context.clone(), device_id, queue_id, None, Vec::new(), None, None, None, 1, None,
+            false, 0, false, 1.0, 0, 16, 16,
+        )
+        .unwrap();
+        pass.record_begin_pipeline_statistics_query(&queries, 0).unwrap();
+        assert!(pass.record_begin_pipeline_statistics_query(&queries, 1).is_err(), "queries can't nest");
+        pass.record_end_pipeline_statistics_query().unwrap();
+        assert!(pass.record_end_pipeline_statistics_query().is_err());
+        assert!(pass.record_begin_pipeline_statistics_query(&queries, 4).is_err());
+
+        let stats = queries.resolve(&context, device_id, queue_id, 1..3).unwrap();
+        assert_eq!(stats.len(), 2 * STATISTICS_PER_QUERY as usize);
+        assert!(queries.validate_range(3, 2).is_err());
+        queries.destroy(&context);
+    }
+}
@@ -0,0 +1,149 @@
+//! Bulk readback of resolved query results
+//!
+//! Timestamp, occlusion and pipeline statistics query sets all resolve into a `QUERY_RESOLVE`
+//! buffer, copy into a `MAP_READ` buffer and map it. Doing the whole range in
+//! one submit and one map keeps the poll overhead flat no matter how many
+//! queries are read.
+
+use std::borrow::Cow;
+use std::sync::mpsc;
+use wgpu_core::id;
+use wgpu_types as wgt;
+
+use crate::context::BasaltContext;
+use crate::error::{BasaltError, Result};
+
+/// Bytes per resolved query result
+pub const RESULT_SIZE: u64 = std::mem::size_of::<u64>() as u64;
+
+/// Buffers a query set is resolved through
+pub struct QueryReadback {
+    pub query_set_id: id::QuerySetId,
+    /// Buffer the query set is resolved into (QUERY_RESOLVE)
+    pub resolve_buffer_id: id::BufferId,
+    /// Buffer the results are copied to for mapping (MAP_READ)
+    pub destination_buffer_id: id::BufferId,
+    /// 64-bit results each query resolves to (1 except for pipeline statistics)
+    pub values_per_query: u32,
+}
+
+impl QueryReadback {
+    /// Resolve queries in `range`, copy them out and read them back with a single map
+    ///
+    /// Blocks until the results are mapped. The raw 64-bit results are
+    /// returned as written by the GPU, `values_per_query` per query.
+    pub fn resolve_and_read(
+        &self,
+        context: &BasaltContext,
+        device_id: id::DeviceId,
+        queue_id: id::QueueId,
+        range: std::ops::Range<u32>,
+        label: &str,
+    ) -> Result<Vec<u64>> {
+        let global = context.inner();
+        let _serialized = context.serialize_submission();
+        // Resolve destinations must be QUERY_RESOLVE_BUFFER_ALIGNMENT aligned, so
+        // every range lands at the start of the buffers rather than at its own slot
+        let offset = 0;
+        let size = (range.end - range.start) as u64 * self.values_per_query as u64 * RESULT_SIZE;
+
+        let (encoder_id, error) = global.device_create_command_encoder(
+            device_id,
+            &wgt::CommandEncoderDescriptor { label: Some(Cow::Owned(format!("{} Resolve Encoder", label))) },
+            None,
+        );
+
+        if let Some(e) = error {
+            return Err(BasaltError::Wgpu(format!("{:?}", e)));
+        }
+
+        global
+            .command_encoder_resolve_query_set(
+                encoder_id,
+                self.query_set_id,
+                range.start,
+                range.end - range.start,
+                self.resolve_buffer_id,
+                offset,
+            )
+            .map_err(|e| BasaltError::Wgpu(format!("Failed to resolve {} queries: {:?}", label, e)))?;
+
+        global
+            .command_encoder_copy_buffer_to_buffer(
+                encoder_id,
+                self.resolve_buffer_id,
+                offset,
+                self.destination_buffer_id,
+                offset,
+                Some(size),
+            )
+            .map_err(|e| BasaltError::Wgpu(format!("Failed to copy {} results: {:?}", label, e)))?;
+
+        let (command_buffer, error) = global.command_encoder_finish(
+            encoder_id,
+            &wgt::CommandBufferDescriptor::default(),
+            None,
+        );
+
+        if let Some(e) = error {
+            return Err(BasaltError::Wgpu(format!("{:?}", e)));
+        }
+
+        global
+            .queue_submit(queue_id, &[command_buffer])
+            .map_err(|(_, e)| BasaltError::from_webgpu_error(format!("{} query resolve submit", label), &e))?;
+
+        // Map the destination buffer and wait for the copy to land
+        let (tx, rx) = mpsc::channel();
+        let map_op = wgpu_core::resource::BufferMapOperation {
+            host: wgpu_core::device::HostMap::Read,
+            callback: Some(Box::new(move |result: wgpu_core::resource::BufferAccessResult| {
+                let _ = tx.send(result.map_err(|e| format!("Buffer mapping failed: {:?}", e)));
+            })),
+        };
+
+        global
+            .buffer_map_async(self.destination_buffer_id, offset, Some(size), map_op)
+            .map_err(|e| BasaltError::Generic(format!("Failed to map buffer: {:?}", e)))?;
+
+        global
+            .device_poll(device_id, wgt::PollType::wait_indefinitely())
+            .map_err(|e| BasaltError::Generic(format!("Device poll failed: {:?}", e)))?;
+
+        match rx.recv() {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => return Err(BasaltError::Generic(e)),
+            Err(e) => return Err(BasaltError::Generic(format!("Channel receive failed: {}", e))),
+        }
+
+        let (ptr, mapped_size) = global
+            .buffer_get_mapped_range(self.destination_buffer_id, offset, Some(size))
+            .map_err(|e| BasaltError::Generic(format!("Failed to get mapped range: {:?}", e)))?;
+
+        let results = if mapped_size < size {
+            Err(BasaltError::Generic(format!("Mapped size mismatch: expected {}, got {}", size, mapped_size)))
+        } else {
+            // SAFETY: the mapped range holds `size` bytes until the buffer is unmapped below
+            let bytes = unsafe { std::slice::from_raw_parts(ptr.as_ptr(), size as usize) };
+            Ok(bytes
+                .chunks_exact(RESULT_SIZE as usize)
+                .map(|chunk| u64::from_le_bytes(chunk.try_into().expect("8-byte chunk")))
+                .collect())
+        };
+
+        let _ = global.buffer_unmap(self.destination_buffer_id);
+        results
+    }
+}
+
+/// Check that `first..first + count` lies inside a set of `len` queries
+pub fn validate_query_range(first: u32, count: u32, len: u32) -> Result<std::ops::Range<u32>> {
+    let end = first.checked_add(count).filter(|&end| count > 0 && end <= len);
+    match end {
+        Some(end) => Ok(first..end),
+        None => Err(BasaltError::invalid_parameter(
+            "range",
+            format!("queries {}..{} out of range for a set of {}", first, first as u64 + count as u64, len),
+        )),
+    }
+}
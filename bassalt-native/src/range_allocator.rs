@@ -0,0 +1,411 @@
+//! Range allocator for sub-allocating large GPU buffers
+//!
+//! Chunk geometry is packed into a few large arena buffers instead of
+//! thousands of small ones. The allocator only tracks byte ranges; the
+//! GPU buffer itself is owned by the caller.
+//!
+//! # Fragmentation
+//!
+//! Over a long session chunk meshes are freed and reallocated at varying
+//! sizes, leaving holes that are individually too small to be reused.
+//! [`RangeAllocator::defragment`] compacts live allocations towards the
+//! start of the arena and returns the list of moves so the caller can
+//! issue the matching GPU copies (see `BasaltDevice::apply_range_moves`)
+//! and update any cached offsets.
+//!
+//! Defragmentation is incremental: each call moves at most `max_bytes`,
+//! so the work can be spread across frames instead of stalling one.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+
+use wgpu_types as wgt;
+
+use crate::error::{BasaltError, Result};
+
+/// Handle identifying a live allocation inside a [`RangeAllocator`]
+pub type RangeHandle = u64;
+
+/// Fragmentation at or above which [`RangeAllocatorStats::needs_compaction`] recommends compacting
+pub const COMPACTION_FRAGMENTATION_THRESHOLD: f32 = 0.5;
+
+/// A live allocation inside the arena
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeAllocation {
+    pub handle: RangeHandle,
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// A single relocation produced by [`RangeAllocator::defragment`]
+///
+/// The allocation identified by `handle` now lives at `new_offset`; its
+/// contents must be copied from `old_offset` before the range is used again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeMove {
+    pub handle: RangeHandle,
+    pub old_offset: u64,
+    pub new_offset: u64,
+    pub size: u64,
+}
+
+/// Snapshot of an allocator's occupancy
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RangeAllocatorStats {
+    pub capacity: u64,
+    pub used_bytes: u64,
+    pub free_bytes: u64,
+    pub largest_free_range: u64,
+    pub allocations: usize,
+    pub fragmentation: f32,
+}
+
+impl RangeAllocatorStats {
+    /// Whether free space is scattered enough that [`RangeAllocator::defragment`] is worth running
+    pub fn needs_compaction(&self) -> bool {
+        self.fragmentation >= COMPACTION_FRAGMENTATION_THRESHOLD
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LiveRange {
+    offset: u64,
+    size: u64,
+    align: u64,
+}
+
+/// First-fit range allocator with free-range coalescing
+#[derive(Debug, Clone)]
+pub struct RangeAllocator {
+    capacity: u64,
+    /// Free ranges keyed by start offset, value is the end offset (exclusive)
+    free: BTreeMap<u64, u64>,
+    live: HashMap<RangeHandle, LiveRange>,
+    next_handle: RangeHandle,
+}
+
+impl RangeAllocator {
+    /// Create an allocator managing `capacity` bytes
+    pub fn new(capacity: u64) -> Self {
+        let mut free = BTreeMap::new();
+        if capacity > 0 {
+            free.insert(0, capacity);
+        }
+        Self {
+            capacity,
+            free,
+            live: HashMap::new(),
+            next_handle: 1, // 0 is reserved as the null handle
+        }
+    }
+
+    /// Total size of the arena in bytes
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    /// Bytes currently held by live allocations
+    pub fn used_bytes(&self) -> u64 {
+        self.live.values().map(|r| r.size).sum()
+    }
+
+    /// Bytes not held by any live allocation
+    pub fn free_bytes(&self) -> u64 {
+        self.free.iter().map(|(start, end)| end - start).sum()
+    }
+
+    /// Size of the largest contiguous free range
+    pub fn largest_free_range(&self) -> u64 {
+        self.free.iter().map(|(start, end)| end - start).max().unwrap_or(0)
+    }
+
+    /// Number of live allocations
+    pub fn allocation_count(&self) -> usize {
+        self.live.len()
+    }
+
+    /// Look up a live allocation
+    pub fn get(&self, handle: RangeHandle) -> Option<RangeAllocation> {
+        self.live.get(&handle).map(|r| RangeAllocation {
+            handle,
+            offset: r.offset,
+            size: r.size,
+        })
+    }
+
+    /// Allocate `size` bytes aligned to `align`
+    ///
+    /// Sizes are rounded up and alignments raised to `COPY_BUFFER_ALIGNMENT`
+    /// so every allocation can later be relocated with a buffer copy.
+    pub fn allocate(&mut self, size: u64, align: u64) -> Result<RangeAllocation> {
+        if size == 0 {
+            return Err(BasaltError::invalid_parameter("size", "allocation size must be non-zero"));
+        }
+        if align != 0 && !align.is_power_of_two() {
+            return Err(BasaltError::invalid_parameter(
+                "align",
+                format!("alignment {} is not a power of two", align),
+            ));
+        }
+
+        let size = wgt::math::align_to(size, wgt::COPY_BUFFER_ALIGNMENT);
+        let align = align.max(wgt::COPY_BUFFER_ALIGNMENT);
+
+        let offset = self
+            .free
+            .iter()
+            .find_map(|(&start, &end)| {
+                let aligned = wgt::math::align_to(start, align);
+                (aligned + size <= end).then_some(aligned)
+            })
+            .ok_or_else(|| {
+                BasaltError::out_of_memory(format!(
+                    "range allocator: no free range for {} bytes (free={}, largest={})",
+                    size,
+                    self.free_bytes(),
+                    self.largest_free_range()
+                ))
+            })?;
+
+        self.reserve(offset..offset + size);
+
+        let handle = self.next_handle;
+        self.next_handle += 1;
+        self.live.insert(handle, LiveRange { offset, size, align });
+
+        Ok(RangeAllocation { handle, offset, size })
+    }
+
+    /// Free a live allocation, coalescing it with neighbouring free ranges
+    pub fn free(&mut self, handle: RangeHandle) -> Option<RangeAllocation> {
+        let range = self.live.remove(&handle)?;
+        self.release(range.offset..range.offset + range.size);
+        Some(RangeAllocation {
+            handle,
+            offset: range.offset,
+            size: range.size,
+        })
+    }
+
+    /// Fraction of free space that is not part of the largest free range
+    ///
+    /// 0.0 means all free space is contiguous; values close to 1.0 mean the
+    /// free space is scattered across many small holes.
+    pub fn fragmentation(&self) -> f32 {
+        let free = self.free_bytes();
+        if free == 0 {
+            return 0.0;
+        }
+        1.0 - self.largest_free_range() as f32 / free as f32
+    }
+
+    pub fn stats(&self) -> RangeAllocatorStats {
+        RangeAllocatorStats {
+            capacity: self.capacity,
+            used_bytes: self.used_bytes(),
+            free_bytes: self.free_bytes(),
+            largest_free_range: self.largest_free_range(),
+            allocations: self.allocation_count(),
+            fragmentation: self.fragmentation(),
+        }
+    }
+
+    /// Compact live allocations towards the start of the arena
+    ///
+    /// Moves at most `max_bytes` worth of allocations (at least one move is
+    /// always made if any is pending, so progress is guaranteed). Call
+    /// repeatedly until it returns an empty list to fully compact the arena.
+    ///
+    /// Allocations are processed in offset order and only ever move down, so
+    /// a move never lands on another live allocation. A move may overlap its
+    /// own old range, so the caller must copy through a scratch buffer.
+    pub fn defragment(&mut self, max_bytes: u64) -> Vec<RangeMove> {
+        let mut order: Vec<(RangeHandle, LiveRange)> =
+            self.live.iter().map(|(&h, &r)| (h, r)).collect();
+        order.sort_by_key(|(_, r)| r.offset);
+
+        let mut moves = Vec::new();
+        let mut moved_bytes = 0u64;
+        let mut cursor = 0u64;
+
+        for (handle, range) in order {
+            let target = wgt::math::align_to(cursor, range.align);
+            if target < range.offset {
+                if !moves.is_empty() && moved_bytes + range.size > max_bytes {
+                    break;
+                }
+
+                self.release(range.offset..range.offset + range.size);
+                self.reserve(target..target + range.size);
+                self.live.insert(handle, LiveRange { offset: target, ..range });
+
+                moves.push(RangeMove {
+                    handle,
+                    old_offset: range.offset,
+                    new_offset: target,
+                    size: range.size,
+                });
+                moved_bytes += range.size;
+                cursor = target + range.size;
+            } else {
+                cursor = range.offset + range.size;
+            }
+        }
+
+        if !moves.is_empty() {
+            log::debug!(
+                "Range allocator defragment: moved {} allocations ({} bytes), fragmentation now {:.2}",
+                moves.len(),
+                moved_bytes,
+                self.fragmentation()
+            );
+        }
+
+        moves
+    }
+
+    /// Remove `range` from the free list. The range must lie inside one free range.
+    fn reserve(&mut self, range: Range<u64>) {
+        let (&start, &end) = self
+            .free
+            .range(..=range.start)
+            .next_back()
+            .filter(|(_, &end)| end >= range.end)
+            .expect("reserved range must be free");
+
+        self.free.remove(&start);
+        if start < range.start {
+            self.free.insert(start, range.start);
+        }
+        if range.end < end {
+            self.free.insert(range.end, end);
+        }
+    }
+
+    /// Return `range` to the free list, merging with adjacent free ranges
+    fn release(&mut self, range: Range<u64>) {
+        let mut start = range.start;
+        let mut end = range.end;
+
+        if let Some((&prev_start, &prev_end)) = self.free.range(..start).next_back() {
+            if prev_end == start {
+                self.free.remove(&prev_start);
+                start = prev_start;
+            }
+        }
+        if let Some(next_end) = self.free.remove(&end) {
+            end = next_end;
+        }
+
+        self.free.insert(start, end);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply moves to a CPU mirror of the arena the same way the GPU path
+    /// does: copy each source range out before writing the destination.
+    fn apply_moves(arena: &mut [u8], moves: &[RangeMove]) {
+        for m in moves {
+            let src = m.old_offset as usize..(m.old_offset + m.size) as usize;
+            let scratch = arena[src].to_vec();
+            let dst = m.new_offset as usize;
+            arena[dst..dst + scratch.len()].copy_from_slice(&scratch);
+        }
+    }
+
+    fn fill(arena: &mut [u8], alloc: &RangeAllocation, value: u8) {
+        arena[alloc.offset as usize..(alloc.offset + alloc.size) as usize].fill(value);
+    }
+
+    #[test]
+    fn test_free_ranges_coalesce() {
+        let mut alloc = RangeAllocator::new(256);
+        let a = alloc.allocate(64, 4).unwrap();
+        let b = alloc.allocate(64, 4).unwrap();
+        let c = alloc.allocate(64, 4).unwrap();
+
+        alloc.free(a.handle);
+        alloc.free(c.handle);
+        assert_eq!(alloc.largest_free_range(), 128);
+
+        alloc.free(b.handle);
+        assert_eq!(alloc.largest_free_range(), 256);
+        assert_eq!(alloc.fragmentation(), 0.0);
+        assert!(!alloc.stats().needs_compaction());
+    }
+
+    #[test]
+    fn test_needs_compaction_at_threshold() {
+        let mut alloc = RangeAllocator::new(256);
+        let a = alloc.allocate(64, 4).unwrap();
+        alloc.allocate(64, 4).unwrap();
+        let c = alloc.allocate(64, 4).unwrap();
+        alloc.allocate(64, 4).unwrap();
+
+        // Two equal holes: half the free space is outside the largest range
+        alloc.free(a.handle);
+        alloc.free(c.handle);
+        let stats = alloc.stats();
+        assert_eq!(stats.fragmentation, COMPACTION_FRAGMENTATION_THRESHOLD);
+        assert!(stats.needs_compaction());
+    }
+
+    #[test]
+    fn test_defragment_preserves_live_data() {
+        let mut alloc = RangeAllocator::new(1024);
+        let mut arena = vec![0u8; 1024];
+
+        // Fill the arena with allocations of varying size, then free every
+        // other one to leave holes behind.
+        let sizes = [48u64, 100, 16, 200, 64, 36, 128, 80];
+        let allocations: Vec<_> = sizes
+            .iter()
+            .enumerate()
+            .map(|(i, &size)| {
+                let a = alloc.allocate(size, 16).unwrap();
+                fill(&mut arena, &a, i as u8 + 1);
+                a
+            })
+            .collect();
+        for a in allocations.iter().step_by(2) {
+            alloc.free(a.handle);
+        }
+        let fragmentation_before = alloc.fragmentation();
+        assert!(fragmentation_before > 0.0);
+
+        // Defragment incrementally with a small budget
+        let mut passes = 0;
+        loop {
+            let moves = alloc.defragment(64);
+            if moves.is_empty() {
+                break;
+            }
+            apply_moves(&mut arena, &moves);
+            passes += 1;
+        }
+        assert!(passes > 1, "small budget should require several passes");
+        // Only alignment padding may remain between live allocations
+        assert!(alloc.fragmentation() < fragmentation_before);
+        assert!(alloc.defragment(u64::MAX).is_empty());
+
+        for (i, a) in allocations.iter().enumerate().skip(1).step_by(2) {
+            let live = alloc.get(a.handle).unwrap();
+            assert_eq!(live.offset % 16, 0);
+            let bytes = &arena[live.offset as usize..(live.offset + live.size) as usize];
+            assert!(bytes.iter().all(|&b| b == i as u8 + 1), "allocation {} corrupted", i);
+        }
+
+        // The reclaimed space is usable as one contiguous range again
+        assert!(alloc.largest_free_range() >= 1024 - 16 * 4 - (100 + 200 + 36 + 80));
+    }
+
+    #[test]
+    fn test_allocate_fails_when_full() {
+        let mut alloc = RangeAllocator::new(128);
+        alloc.allocate(128, 4).unwrap();
+        assert!(alloc.allocate(4, 4).is_err());
+    }
+}
@@ -7,13 +7,17 @@
 //! Based on wgpu example: examples/features/src/msaa_line/mod.rs
 
 use std::borrow::Cow;
+use std::num::NonZero;
 use std::sync::Arc;
 use wgpu_core::command;
+use wgpu_core::command::bundle_ffi;
 use wgpu_core::id;
 use wgpu_types as wgt;
 
 use crate::context::BasaltContext;
 use crate::error::{BasaltError, Result};
+use crate::render_pass::ImmediatesFallback;
+use crate::shader::IMMEDIATES_FALLBACK_GROUP;
 
 // Import RenderBundleEncoderDescriptor from wgpu_core::command
 use wgpu_core::command::RenderBundleEncoderDescriptor;
@@ -45,7 +49,7 @@ impl BasaltRenderBundle {
 
         // wgpu 27.0 uses RenderBundleEncoderDescriptor directly
         let (encoder, error) = global
-            .device_create_render_bundle_encoder(device_id, &descriptor);
+            .device_create_render_bundle_encoder(device_id, descriptor);
 
         if let Some(e) = error {
             return Err(BasaltError::resource_creation("render bundle encoder", format!("{:?}", e)));
@@ -174,6 +178,127 @@ impl RenderBundleBuilder {
     }
 }
 
+/// A render bundle being recorded
+///
+/// Records the same state changes and draws as a render pass, for sequences
+/// replayed unchanged every frame such as the HUD. Commands are only
+/// validated when the bundle is finished. A finished bundle can be executed
+/// in any pass whose attachment formats and sample count match the ones the
+/// recorder was created with.
+pub struct RenderBundleRecorder {
+    encoder: command::RenderBundleEncoder,
+    draw_count: u32,
+}
+
+impl RenderBundleRecorder {
+    /// Start recording a bundle for passes with these attachments
+    ///
+    /// `depth_format` is the pass's depth attachment, if any; bundles write
+    /// depth and stencil, so they can't run in passes with a read-only one.
+    ///
+    /// On devices without push constants, pass the device's
+    /// `immediates_fallback` so pipelines reading immediates can draw. Bundles
+    /// can't update immediates; those pipelines read the zeroed block in the
+    /// fallback's first slot, which is never written.
+    pub fn new(
+        context: &Arc<BasaltContext>,
+        device_id: id::DeviceId,
+        color_formats: &[wgt::TextureFormat],
+        depth_format: Option<wgt::TextureFormat>,
+        sample_count: u32,
+        immediates_fallback: Option<ImmediatesFallback>,
+    ) -> Result<Self> {
+        if color_formats.is_empty() && depth_format.is_none() {
+            return Err(BasaltError::invalid_parameter(
+                "color_formats",
+                "a render bundle needs at least one color or depth attachment",
+            ));
+        }
+        let mut builder = RenderBundleBuilder::new().color_formats(color_formats).sample_count(sample_count);
+        if let Some(format) = depth_format {
+            builder = builder.depth_stencil(wgt::RenderBundleDepthStencil {
+                format,
+                depth_read_only: false,
+                stencil_read_only: false,
+            });
+        }
+        let mut recorder = Self { encoder: builder.build_encoder(context, device_id)?, draw_count: 0 };
+        if let Some(fallback) = immediates_fallback {
+            recorder.set_bind_group(IMMEDIATES_FALLBACK_GROUP, Some(fallback.bind_group_id), &[0]);
+        }
+        Ok(recorder)
+    }
+
+    pub fn set_pipeline(&mut self, pipeline_id: id::RenderPipelineId) {
+        bundle_ffi::wgpu_render_bundle_set_pipeline(&mut self.encoder, pipeline_id);
+    }
+
+    pub fn set_bind_group(&mut self, index: u32, bind_group_id: Option<id::BindGroupId>, offsets: &[u32]) {
+        // SAFETY: the pointer and length come from a live slice
+        unsafe {
+            bundle_ffi::wgpu_render_bundle_set_bind_group(
+                &mut self.encoder,
+                index,
+                bind_group_id,
+                offsets.as_ptr(),
+                offsets.len(),
+            );
+        }
+    }
+
+    pub fn set_vertex_buffer(&mut self, slot: u32, buffer_id: id::BufferId, offset: u64, size: Option<NonZero<u64>>) {
+        bundle_ffi::wgpu_render_bundle_set_vertex_buffer(&mut self.encoder, slot, buffer_id, offset, size);
+    }
+
+    pub fn set_index_buffer(
+        &mut self,
+        buffer_id: id::BufferId,
+        index_format: wgt::IndexFormat,
+        offset: u64,
+        size: Option<NonZero<u64>>,
+    ) {
+        bundle_ffi::wgpu_render_bundle_set_index_buffer(&mut self.encoder, buffer_id, index_format, offset, size);
+    }
+
+    pub fn draw(&mut self, vertex_count: u32, instance_count: u32, first_vertex: u32, first_instance: u32) {
+        bundle_ffi::wgpu_render_bundle_draw(&mut self.encoder, vertex_count, instance_count, first_vertex, first_instance);
+        self.draw_count += 1;
+    }
+
+    pub fn draw_indexed(
+        &mut self,
+        index_count: u32,
+        instance_count: u32,
+        first_index: u32,
+        base_vertex: i32,
+        first_instance: u32,
+    ) {
+        bundle_ffi::wgpu_render_bundle_draw_indexed(
+            &mut self.encoder,
+            index_count,
+            instance_count,
+            first_index,
+            base_vertex,
+            first_instance,
+        );
+        self.draw_count += 1;
+    }
+
+    /// Draws recorded so far
+    pub fn draw_count(&self) -> u32 {
+        self.draw_count
+    }
+
+    /// Validate the recorded commands and create the bundle
+    pub fn finish(self, context: &Arc<BasaltContext>, label: Option<&str>) -> Result<id::RenderBundleId> {
+        BasaltRenderBundle::finish(
+            context,
+            self.encoder,
+            &wgt::RenderBundleDescriptor { label: label.map(Cow::Borrowed) },
+        )
+    }
+}
+
 /// Convenience function to create a simple render bundle
 ///
 /// This is the easiest way to create a bundle for common cases.
@@ -8,11 +8,88 @@
 use std::borrow::Cow;
 use std::num::NonZero;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use wgpu_core::id;
 use wgpu_types as wgt;
 
 use crate::context::BasaltContext;
 use crate::error::{BasaltError, Result};
+use crate::occlusion_queries::OcclusionQuerySet;
+use crate::pipeline_statistics::PipelineStatisticsQuerySet;
+use crate::resource_handles::{ImmediateRanges, PipelineDepthFormat, RenderPipelineInfo, VertexStride};
+use crate::shader::IMMEDIATES_FALLBACK_GROUP;
+
+/// Largest immediate block Bassalt uses, and the size of one fallback slot's data
+pub const MAX_IMMEDIATE_SIZE: u32 = 128;
+
+/// Distance between fallback slots: the largest `min_uniform_buffer_offset_alignment` allowed
+pub const IMMEDIATES_FALLBACK_SLOT_SIZE: u32 = 256;
+
+/// Immediate updates a single pass can make on the uniform fallback
+pub const IMMEDIATES_FALLBACK_SLOTS: u32 = 256;
+
+/// Slots in the fallback buffer, shared as a ring by the passes of a frame
+pub const IMMEDIATES_FALLBACK_RING_SLOTS: u32 = 16 * IMMEDIATES_FALLBACK_SLOTS;
+
+/// Dynamic uniform buffer standing in for immediates when the device has no push constants
+///
+/// The first draw after immediates change appends a copy of the whole block to
+/// the pass's slots and rebinds it at that slot, so each draw sees the data set
+/// before it. Compared to push constants this costs a 256-byte slot and a
+/// bind group change per updated draw, plus one buffer upload per pass, and
+/// caps a pass at `IMMEDIATES_FALLBACK_SLOTS` distinct blocks. Slot 0 is never
+/// written: passes start on that zeroed block, and render bundles bind it.
+#[derive(Debug, Clone, Copy)]
+pub struct ImmediatesFallback {
+    pub buffer_id: id::BufferId,
+    pub bind_group_layout_id: id::BindGroupLayoutId,
+    pub bind_group_id: id::BindGroupId,
+}
+
+/// Hands each pass of a frame its own run of immediates fallback slots
+///
+/// Queue writes land before the next submit, so a pass writing slots that a
+/// pass still waiting in the frame reads would change what that pass sees.
+/// Runs never overlap until the frame is submitted; after that, queue order
+/// puts new writes behind the submitted passes.
+#[derive(Debug)]
+pub struct ImmediatesRing {
+    next: u32,
+    frame_slots: u32,
+}
+
+impl ImmediatesRing {
+    pub fn new() -> Self {
+        Self { next: 1, frame_slots: 0 }
+    }
+
+    /// First of `count` consecutive slots, or None until the frame is submitted
+    pub fn reserve(&mut self, count: u32) -> Option<u32> {
+        let (start, used) = if self.next + count > IMMEDIATES_FALLBACK_RING_SLOTS {
+            // Wrap past slot 0, the shared zeroed block; the skipped tail counts as used
+            (1, count + IMMEDIATES_FALLBACK_RING_SLOTS - self.next)
+        } else {
+            (self.next, count)
+        };
+        if self.frame_slots + used > IMMEDIATES_FALLBACK_RING_SLOTS - 1 {
+            return None;
+        }
+        self.frame_slots += used;
+        self.next = start + count;
+        Some(start)
+    }
+
+    /// The frame's passes were submitted, so their slots can be reused
+    pub fn frame_submitted(&mut self) {
+        self.frame_slots = 0;
+    }
+}
+
+impl Default for ImmediatesRing {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 /// Commands that can be recorded in a render pass
 #[derive(Debug, Clone)]
@@ -59,6 +136,18 @@ pub enum RenderCommand {
         first_vertex: u32,
         first_instance: u32,
     },
+    /// `count` draws read from `buffer_id` as `DrawIndirectArgs`, 16 bytes apart
+    DrawIndirect {
+        buffer_id: id::BufferId,
+        offset: u64,
+        count: u32,
+    },
+    /// `count` draws read from `buffer_id` as `DrawIndexedIndirectArgs`, 20 bytes apart
+    DrawIndexedIndirect {
+        buffer_id: id::BufferId,
+        offset: u64,
+        count: u32,
+    },
     SetViewport {
         x: f32,
         y: f32,
@@ -73,6 +162,10 @@ pub enum RenderCommand {
         width: u32,
         height: u32,
     },
+    /// Reference value for stencil tests using `Replace` or a compare function
+    SetStencilReference {
+        reference: u32,
+    },
     PushDebugGroup {
         label: String,
     },
@@ -80,24 +173,43 @@ pub enum RenderCommand {
     InsertDebugMarker {
         label: String,
     },
+    BeginOcclusionQuery {
+        query_index: u32,
+    },
+    EndOcclusionQuery,
+    BeginPipelineStatisticsQuery {
+        query_set_id: id::QuerySetId,
+        query_index: u32,
+    },
+    EndPipelineStatisticsQuery,
+    /// Replay pre-recorded bundles; the pass's bound state is reset afterwards
+    ExecuteBundles {
+        bundle_ids: Vec<id::RenderBundleId>,
+    },
 }
 
 /// Active render pass state with command recording
 ///
-/// Records render commands and executes them atomically using wgpu-core 27's
-/// command_encoder_run_render_pass closure pattern.
+/// Records render commands and replays them into a command encoder when the
+/// pass ends, either the device's frame encoder (`finish`) or one of its own
+/// (`finish_and_submit`).
 pub struct RenderPassState {
     context: Arc<BasaltContext>,
     device_id: id::DeviceId,
     queue_id: id::QueueId,
-    command_encoder_id: id::CommandEncoderId,
 
     // Render pass configuration
     color_view: Option<id::TextureViewId>,
+    // MRT: attachments for @location(1..N), after `color_view`
+    extra_color_views: Vec<id::TextureViewId>,
     depth_view: Option<id::TextureViewId>,
     // Track the output texture for main framebuffer detection
     // This will be set as the main framebuffer AFTER the render pass executes
     output_texture: Option<id::TextureId>,
+    // MSAA: single-sample view the color attachment is resolved into
+    resolve_view: Option<id::TextureViewId>,
+    // Sample count of the attachments; pipelines must match it
+    sample_count: u32,
     should_clear_color: bool,
     clear_color: wgt::Color,
     should_clear_depth: bool,
@@ -107,6 +219,8 @@ pub struct RenderPassState {
     // Viewport dimensions for scissor clamping
     viewport_width: u32,
     viewport_height: u32,
+    // Device `max_texture_dimension_2d`, which bounds viewport rects
+    max_texture_dimension: u32,
 
     // Recorded commands
     commands: Vec<RenderCommand>,
@@ -128,9 +242,89 @@ pub struct RenderPassState {
     // This is used to set the read_only flag on the depth attachment
     depth_mode: DepthMode,
 
-    // Track if current pipeline is compatible with depth mode
-    // When incompatible, draws are skipped to prevent validation errors
+    // Track if current pipeline is compatible with the pass attachments
+    // When incompatible (sample count mismatch), draws are skipped to prevent validation errors
     pipeline_compatible: bool,
+
+    // Vertex buffer slots declared by the current pipeline (None until a pipeline is set)
+    pipeline_vertex_buffers: Option<u32>,
+
+    // Occlusion query set attached to the pass and its query count
+    occlusion_query_set: Option<(id::QuerySetId, u32)>,
+    // Index of the occlusion query currently open (queries can't nest)
+    active_occlusion_query: Option<u32>,
+    // Index of the pipeline statistics query currently open (queries can't nest)
+    active_pipeline_statistics_query: Option<u32>,
+
+    // Currently bound state, used to skip sets that wouldn't change anything
+    bound_pipeline: Option<id::RenderPipelineId>,
+    bound_bind_groups: [Option<(Option<id::BindGroupId>, Vec<u32>)>; 4],
+    bound_vertex_buffers: Vec<Option<BoundVertexBuffer>>,
+    // Redundant pipeline, bind group and vertex buffer sets skipped in this pass
+    elided_state_changes: u32,
+
+    // Format of color attachment 0, when known, to check pipeline targets against
+    color_format: Option<wgt::TextureFormat>,
+
+    // Uniform fallback for immediates, with the current block and this pass's slots
+    immediates_fallback: Option<ImmediatesFallback>,
+    immediates: Vec<u8>,
+    immediates_staging: Vec<u8>,
+    // Fallback slot the pass's first staged block is uploaded to
+    immediates_base_slot: u32,
+    // The block changed since its last slot; the next draw appends a new one
+    immediates_dirty: bool,
+    // Immediate blocks of the bound pipeline's stages, when known
+    immediate_ranges: Option<ImmediateRanges>,
+    // Slot 0 vertex stride of the bound pipeline, when known
+    pipeline_vertex_stride: Option<VertexStride>,
+    // Slot 0 buffer size was checked against the stride since either last changed
+    vertex_stride_checked: bool,
+}
+
+/// Vertex buffer binding: (buffer, offset, size)
+type BoundVertexBuffer = (id::BufferId, u64, Option<NonZero<u64>>);
+
+/// What `RenderPassState::record_set_pipeline` checks a pipeline against the pass with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PipelineBinding {
+    pub id: id::RenderPipelineId,
+    pub depth_write_enabled: bool,
+    pub depth_test_enabled: bool,
+    /// Whether the pipeline has a depth-stencil state at all
+    pub has_depth_output: bool,
+    pub sample_count: u32,
+    pub vertex_buffer_count: u32,
+    pub color_target_count: u32,
+}
+
+impl PipelineBinding {
+    /// A single-sampled pipeline with one color target, no depth and no vertex buffers
+    pub fn new(id: id::RenderPipelineId) -> Self {
+        Self {
+            id,
+            depth_write_enabled: false,
+            depth_test_enabled: false,
+            has_depth_output: false,
+            sample_count: 1,
+            vertex_buffer_count: 0,
+            color_target_count: 1,
+        }
+    }
+}
+
+impl From<&RenderPipelineInfo> for PipelineBinding {
+    fn from(info: &RenderPipelineInfo) -> Self {
+        Self {
+            id: info.id,
+            depth_write_enabled: info.depth_write_enabled,
+            depth_test_enabled: info.depth_test_enabled,
+            has_depth_output: !matches!(info.depth_format, PipelineDepthFormat::None),
+            sample_count: info.sample_count,
+            vertex_buffer_count: info.vertex_buffer_count,
+            color_target_count: info.color_target_count,
+        }
+    }
 }
 
 /// Depth write mode for a render pass
@@ -151,13 +345,18 @@ enum DepthMode {
 
 impl RenderPassState {
     /// Create a new render pass with command recording
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         context: Arc<BasaltContext>,
         device_id: id::DeviceId,
         queue_id: id::QueueId,
         color_view: Option<id::TextureViewId>,
+        extra_color_views: Vec<id::TextureViewId>,
         depth_view: Option<id::TextureViewId>,
         output_texture: Option<id::TextureId>, // The texture that will be rendered
+        resolve_view: Option<id::TextureViewId>,
+        sample_count: u32,
+        occlusion_queries: Option<&OcclusionQuerySet>,
         should_clear_color: bool,
         clear_color: u32,
         should_clear_depth: bool,
@@ -166,52 +365,38 @@ impl RenderPassState {
         width: u32,
         height: u32,
     ) -> Result<Self> {
-        // Create command encoder
-        let encoder_desc = wgt::CommandEncoderDescriptor {
-            label: Some(Cow::Borrowed("Basalt Command Encoder")),
-        };
-
-        let (command_encoder_id, error) = context
-            .inner()
-            .device_create_command_encoder(device_id, &encoder_desc, None);
-
-        if let Some(e) = error {
-            return Err(BasaltError::device_creation(format!("Failed to create command encoder: {:?}", e)));
-        }
-
         // Validate depth clear value is in range [0.0, 1.0]
         // wgpu-core requires this validation to prevent GPU errors
-        if should_clear_depth && (clear_depth < 0.0 || clear_depth > 1.0) {
+        if should_clear_depth && !(0.0..=1.0).contains(&clear_depth) {
             return Err(BasaltError::device_creation(format!(
                 "Invalid depth clear value: {} (must be in range [0.0, 1.0])",
                 clear_depth
             )));
         }
 
-        // Convert clear color from u32 ARGB (Minecraft format) to wgt::Color
-        let a = ((clear_color >> 24) & 0xFF) as f64 / 255.0;
-        let r = ((clear_color >> 16) & 0xFF) as f64 / 255.0;
-        let g = ((clear_color >> 8) & 0xFF) as f64 / 255.0;
-        let b = (clear_color & 0xFF) as f64 / 255.0;
-
         // Create the render pass state with default viewport and scissor
         // CRITICAL: WebGPU viewport defaults to (0,0,0,0) which clips everything!
         // We MUST set viewport to the full render target size
+        let max_texture_dimension = context.inner().device_limits(device_id).max_texture_dimension_2d;
+        LIVE_RENDER_PASSES.begin();
         let mut state = Self {
             context,
             device_id,
             queue_id,
-            command_encoder_id,
             color_view,
+            extra_color_views,
             depth_view,
             output_texture,
+            resolve_view,
+            sample_count,
             should_clear_color,
-            clear_color: wgt::Color { r, g, b, a },
+            clear_color: unpack_argb_color(clear_color),
             should_clear_depth,
             clear_depth,
             clear_stencil,
             viewport_width: width,
             viewport_height: height,
+            max_texture_dimension,
             commands: Vec::with_capacity(32), // Pre-allocate for typical frame
             is_active: true,
             bind_groups_set: [false; 4],
@@ -220,6 +405,23 @@ impl RenderPassState {
             vertex_buffer_size: None,
             depth_mode: DepthMode::Unknown, // Will be determined by first pipeline
             pipeline_compatible: true, // Initially true, set false when incompatible pipeline is set
+            pipeline_vertex_buffers: None,
+            occlusion_query_set: occlusion_queries.map(|set| (set.query_set_id, set.count)),
+            active_occlusion_query: None,
+            active_pipeline_statistics_query: None,
+            bound_pipeline: None,
+            bound_bind_groups: Default::default(),
+            bound_vertex_buffers: Vec::new(),
+            elided_state_changes: 0,
+            color_format: None,
+            immediates_fallback: None,
+            immediates: Vec::new(),
+            immediates_staging: Vec::new(),
+            immediates_base_slot: 1,
+            immediates_dirty: false,
+            immediate_ranges: None,
+            pipeline_vertex_stride: None,
+            vertex_stride_checked: false,
         };
 
         // IMPORTANT: Set default viewport and scissor rect to the full render target
@@ -243,28 +445,69 @@ impl RenderPassState {
         Ok(state)
     }
 
-    /// Get the command encoder ID
-    pub fn encoder_id(&self) -> id::CommandEncoderId {
-        self.command_encoder_id
-    }
-
     /// Check if the render pass is active
     pub fn is_active(&self) -> bool {
         self.is_active
     }
 
+    /// Number of color attachments, including the MRT ones
+    pub fn color_attachment_count(&self) -> u32 {
+        self.color_view.map_or(0, |_| 1 + self.extra_color_views.len() as u32)
+    }
+
+    /// Skip draws until the next pipeline is set
+    ///
+    /// Used for a pipeline that is still compiling in the background, so the
+    /// frame goes on without its geometry instead of waiting for it.
+    pub fn record_pending_pipeline(&mut self) {
+        self.pipeline_compatible = false;
+        self.immediate_ranges = None;
+    }
+
+    /// Immediate blocks of the pipeline just set, to check later writes against
+    pub fn set_immediate_ranges(&mut self, ranges: ImmediateRanges) {
+        self.immediate_ranges = Some(ranges);
+    }
+
     /// Record a set pipeline command and track depth write mode
     ///
     /// The depth mode is determined by the first pipeline set in the render pass.
     /// Subsequent pipelines with different depth modes will log warnings but not skip draws.
     /// This allows wgpu-core to handle validation and prevents missing geometry.
-    pub fn record_set_pipeline(
-        &mut self,
-        pipeline_id: id::RenderPipelineId,
-        depth_write_enabled: bool,
-        depth_test_enabled: bool,
-        has_depth_output: bool,
-    ) {
+    ///
+    /// A pipeline whose sample count or color target count differs from the
+    /// pass attachments would invalidate the whole pass, so draws are skipped
+    /// until a compatible pipeline is set. In strict validation mode the
+    /// mismatch is returned as an error instead.
+    pub fn record_set_pipeline(&mut self, pipeline: PipelineBinding) -> Result<()> {
+        let PipelineBinding {
+            id: pipeline_id,
+            depth_write_enabled,
+            depth_test_enabled,
+            has_depth_output,
+            sample_count,
+            vertex_buffer_count,
+            color_target_count,
+        } = pipeline;
+        self.pipeline_compatible = sample_count == self.sample_count;
+        if !self.pipeline_compatible {
+            return BasaltError::Validation(format!(
+                "Pipeline sample count {} does not match render pass sample count {}, skipping its draws",
+                sample_count, self.sample_count
+            ))
+            .log_or_escalate();
+        }
+
+        let attachment_count = self.color_attachment_count();
+        if color_target_count != attachment_count {
+            self.pipeline_compatible = false;
+            return BasaltError::Validation(format!(
+                "Pipeline writes {} color targets but the render pass has {} color attachments, skipping its draws",
+                color_target_count, attachment_count
+            ))
+            .log_or_escalate();
+        }
+
         // Determine depth mode on first pipeline set
         if matches!(self.depth_mode, DepthMode::Unknown) {
             self.depth_mode = if !has_depth_output {
@@ -295,10 +538,92 @@ impl RenderPassState {
             }
         }
 
+        self.pipeline_vertex_buffers = Some(vertex_buffer_count);
+        if self.bound_pipeline == Some(pipeline_id) {
+            self.elided_state_changes += 1;
+            return Ok(());
+        }
+
         self.commands.push(RenderCommand::SetPipeline { pipeline_id });
         self.pipeline_set = true;
+        self.bound_pipeline = Some(pipeline_id);
         // Reset bind groups when pipeline changes
         self.bind_groups_set = [false; 4];
+        self.bound_bind_groups = Default::default();
+        Ok(())
+    }
+
+    /// Slot 0 vertex stride of the pipeline just set, to check draws against
+    pub fn set_vertex_stride(&mut self, stride: Option<VertexStride>) {
+        if self.pipeline_vertex_stride != stride {
+            self.pipeline_vertex_stride = stride;
+            self.vertex_stride_checked = false;
+        }
+    }
+
+    /// Check a draw's vertex range against the slot 0 buffer and pipeline stride
+    ///
+    /// Warns once per binding when the buffer size isn't a whole number of
+    /// vertices. Returns an error when the range runs past the buffer, which
+    /// usually means the pipeline was created with the wrong vertex format.
+    fn check_vertex_range(&mut self, first_vertex: u64, vertex_count: u64) -> Result<()> {
+        let (Some(buffer_size), Some(stride)) = (self.vertex_buffer_size, self.pipeline_vertex_stride) else {
+            return Ok(());
+        };
+        if !self.vertex_stride_checked {
+            self.vertex_stride_checked = true;
+            let array_stride = stride.array_stride;
+            if array_stride != 0 && !buffer_size.is_multiple_of(array_stride) {
+                log::warn!(
+                    "Vertex buffer holds {} bytes, not a whole number of {}-byte vertices; \
+                     the pipeline's vertex format may not match the buffer's layout",
+                    buffer_size, array_stride
+                );
+            }
+        }
+        validate_vertex_range(buffer_size, stride, first_vertex, vertex_count)
+    }
+
+    /// Set the format of color attachment 0 for `check_target_format`
+    pub fn set_color_format(&mut self, format: Option<wgt::TextureFormat>) {
+        self.color_format = format;
+    }
+
+    /// Decode the clear color for an sRGB color attachment, see `linear_clear_color`
+    ///
+    /// Uses the format from `set_color_format`.
+    pub fn decode_srgb_clear_color(&mut self) {
+        self.clear_color = linear_clear_color(self.clear_color, self.color_format);
+    }
+
+    /// Warn when a pipeline's color target format differs from the attachment
+    ///
+    /// wgpu-core rejects draws whose pipeline targets a different format than
+    /// the pass renders into. Returns false on a mismatch.
+    pub fn check_target_format(&self, shader_name: &str, target_format: wgt::TextureFormat) -> bool {
+        match self.color_format {
+            Some(color_format) if color_format != target_format => {
+                log::warn!(
+                    "Pipeline '{}' targets {:?} but the render pass color attachment is {:?}; \
+                     create it with a matching target format or its draws will fail validation",
+                    shader_name, target_format, color_format
+                );
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Check a vertex buffer slot against the device limit and the current pipeline
+    pub fn validate_vertex_buffer_slot(&self, slot: i32, max_vertex_buffers: u32) -> Result<()> {
+        validate_vertex_buffer_slot(slot, max_vertex_buffers, self.pipeline_vertex_buffers)
+    }
+
+    /// Redundant pipeline, bind group and vertex buffer sets skipped so far
+    ///
+    /// A set that binds what is already bound is not recorded.
+    pub fn elided_state_changes(&self) -> u32 {
+        self.elided_state_changes
     }
 
     /// Record a set vertex buffer command
@@ -311,11 +636,26 @@ impl RenderPassState {
     ) {
         // Track vertex buffer size for slot 0 (main vertex buffer) to detect overflows
         if slot == 0 {
-            self.vertex_buffer_size = size.map(|sz| sz.get());
+            let buffer_size = size.map(|sz| sz.get());
+            if buffer_size != self.vertex_buffer_size {
+                self.vertex_buffer_size = buffer_size;
+                self.vertex_stride_checked = false;
+            }
             log::debug!("[Bassalt] Set vertex buffer slot 0: buffer={:?}, offset={}, size={:?}",
                 buffer_id, offset, size);
         }
 
+        let binding = Some((buffer_id, offset, size));
+        let bound = &mut self.bound_vertex_buffers;
+        if bound.len() <= slot as usize {
+            bound.resize(slot as usize + 1, None);
+        }
+        if bound[slot as usize] == binding {
+            self.elided_state_changes += 1;
+            return;
+        }
+        bound[slot as usize] = binding;
+
         self.commands.push(RenderCommand::SetVertexBuffer {
             slot,
             buffer_id,
@@ -347,14 +687,20 @@ impl RenderPassState {
         bind_group_id: Option<id::BindGroupId>,
         offsets: Vec<u32>,
     ) {
+        if let Some(bound) = self.bound_bind_groups.get_mut(index as usize) {
+            self.bind_groups_set[index as usize] = true;
+            if bound.as_ref().is_some_and(|(group, bound_offsets)| *group == bind_group_id && *bound_offsets == offsets) {
+                self.elided_state_changes += 1;
+                return;
+            }
+            *bound = Some((bind_group_id, offsets.clone()));
+        }
+
         self.commands.push(RenderCommand::SetBindGroup {
             index,
             bind_group_id,
             offsets,
         });
-        if (index as usize) < self.bind_groups_set.len() {
-            self.bind_groups_set[index as usize] = true;
-        }
     }
 
     /// Record a draw indexed command
@@ -366,6 +712,11 @@ impl RenderPassState {
         base_vertex: i32,
         first_instance: u32,
     ) {
+        if !self.pipeline_compatible {
+            log::debug!("Skipping drawIndexed: pipeline incompatible with render pass");
+            return;
+        }
+
         // Validate state before draw
         if !self.pipeline_set {
             log::warn!("DrawIndexed called without pipeline set!");
@@ -374,38 +725,14 @@ impl RenderPassState {
             log::warn!("DrawIndexed called without bind group 0 set!");
         }
 
-        // VALIDATION: Check for vertex buffer overflow
-        // For POSITION_COLOR format, vertex stride is 16 bytes
-        // This is a heuristic - actual stride depends on the vertex format
-        const VERTEX_STRIDE: u64 = 16; // POSITION_COLOR: 12 bytes pos + 4 bytes color
-
-        if let Some(vbuf_size) = self.vertex_buffer_size {
-            // Calculate max vertex index that could be accessed
-            // Worst case: base_vertex + index_count (if indices are 0,1,2,...)
-            let max_vertex_index = if base_vertex >= 0 {
-                base_vertex as u64 + index_count as u64 - 1
-            } else {
-                // Negative base_vertex means we're reading from before the buffer start
-                index_count as u64
-            };
-
-            // Calculate how many vertices fit in the buffer
-            let max_vertices = vbuf_size / VERTEX_STRIDE;
-
-            if max_vertex_index >= max_vertices {
-                log::warn!(
-                    "[Bassalt] VERTEX BUFFER OVERFLOW DETECTED: \
-                    drawIndexed(indices={}, baseVertex={}) will access vertex {} \
-                    but buffer only fits {} vertices ({} bytes / {} stride). \
-                    This will cause flashing triangles from unwritten vertices!",
-                    index_count, base_vertex, max_vertex_index, max_vertices, vbuf_size, VERTEX_STRIDE
-                );
-
-                // Don't skip the draw - let wgpu-core validate and error if needed
-                // But at least we warned about the root cause
-            }
+        // Indices aren't known here, so assume they cover 0..index_count and only
+        // warn; wgpu-core doesn't bounds check indexed vertex fetches either
+        let first_vertex = base_vertex.max(0) as u64;
+        if let Err(e) = self.check_vertex_range(first_vertex, index_count as u64) {
+            log::warn!("drawIndexed(indices={}, baseVertex={}): {}", index_count, base_vertex, e);
         }
 
+        self.flush_immediates();
         self.commands.push(RenderCommand::DrawIndexed {
             index_count,
             instance_count,
@@ -423,6 +750,17 @@ impl RenderPassState {
         first_vertex: u32,
         first_instance: u32,
     ) {
+        if !self.pipeline_compatible {
+            log::debug!("Skipping draw: pipeline incompatible with render pass");
+            return;
+        }
+        // An out of range draw fails validation and takes the whole pass with it
+        if let Err(e) = self.check_vertex_range(first_vertex as u64, vertex_count as u64) {
+            log::warn!("Skipping draw(vertices={}, firstVertex={}): {}", vertex_count, first_vertex, e);
+            return;
+        }
+
+        self.flush_immediates();
         self.commands.push(RenderCommand::Draw {
             vertex_count,
             instance_count,
@@ -431,7 +769,60 @@ impl RenderPassState {
         });
     }
 
+    /// Record a draw whose arguments are read from `buffer_id` at `offset`
+    ///
+    /// The buffer needs INDIRECT usage and `offset` must be 4-byte aligned.
+    pub fn record_draw_indirect(&mut self, buffer_id: id::BufferId, offset: u64) -> Result<()> {
+        self.record_multi_draw_indirect(buffer_id, offset, 1, false)
+    }
+
+    /// Record an indexed draw whose arguments are read from `buffer_id` at `offset`
+    pub fn record_draw_indexed_indirect(&mut self, buffer_id: id::BufferId, offset: u64) -> Result<()> {
+        self.record_multi_draw_indirect(buffer_id, offset, 1, true)
+    }
+
+    /// Record `count` consecutive indirect draws starting at `offset`
+    ///
+    /// wgpu 28 no longer gates multi-draw behind `MULTI_DRAW_INDIRECT`: backends
+    /// without native support replay it as `count` single indirect draws.
+    pub fn record_multi_draw_indirect(
+        &mut self,
+        buffer_id: id::BufferId,
+        offset: u64,
+        count: u32,
+        indexed: bool,
+    ) -> Result<()> {
+        if !offset.is_multiple_of(4) {
+            return Err(BasaltError::invalid_parameter(
+                "offset",
+                format!("indirect draw offset {} is not 4-byte aligned", offset),
+            ));
+        }
+        if count == 0 {
+            return Ok(());
+        }
+
+        if !self.pipeline_compatible {
+            log::debug!("Skipping indirect draw: pipeline incompatible with render pass");
+            return Ok(());
+        }
+
+        self.flush_immediates();
+        self.commands.push(if indexed {
+            RenderCommand::DrawIndexedIndirect { buffer_id, offset, count }
+        } else {
+            RenderCommand::DrawIndirect { buffer_id, offset, count }
+        });
+        Ok(())
+    }
+
     /// Record a set viewport command
+    ///
+    /// Unlike the scissor rect, the viewport may reach past the render target;
+    /// wgpu clips the primitives. The rect is only rejected outside the bounds
+    /// wgpu allows: a size of at most `max_texture_dimension_2d`, placed within
+    /// twice that from the origin. The depth range must lie in `[0, 1]` with
+    /// `min_depth <= max_depth`.
     pub fn record_set_viewport(
         &mut self,
         x: f32,
@@ -440,38 +831,46 @@ impl RenderPassState {
         height: f32,
         min_depth: f32,
         max_depth: f32,
-    ) {
-        // Validate viewport dimensions against render target size
-        if width > self.viewport_width as f32 || height > self.viewport_height as f32 {
-            log::warn!(
-                "Viewport dimensions ({}, {}) exceed render target ({}, {}) - clamping",
-                width, height, self.viewport_width, self.viewport_height
-            );
+    ) -> Result<()> {
+        if ![x, y, width, height].iter().all(|v| v.is_finite()) || width < 0.0 || height < 0.0 {
+            return Err(BasaltError::invalid_parameter(
+                "viewport",
+                format!("({}, {}) {}x{} is not a valid rect", x, y, width, height),
+            ));
         }
-
-        // Clamp viewport to render target bounds
-        let clamped_width = width.min(self.viewport_width as f32);
-        let clamped_height = height.min(self.viewport_height as f32);
-
-        // Validate depth range
-        if min_depth < 0.0 || min_depth > 1.0 {
-            log::warn!("Viewport min_depth {} is outside [0, 1] range - this may cause issues", min_depth);
+        let max_size = self.max_texture_dimension as f32;
+        let max_range = max_size * 2.0;
+        if width > max_size
+            || height > max_size
+            || x < -max_range
+            || y < -max_range
+            || x + width > max_range - 1.0
+            || y + height > max_range - 1.0
+        {
+            return Err(BasaltError::invalid_parameter(
+                "viewport",
+                format!(
+                    "({}, {}) {}x{} exceeds the device limits (size <= {}, within +-{})",
+                    x, y, width, height, max_size, max_range
+                ),
+            ));
         }
-        if max_depth < 0.0 || max_depth > 1.0 {
-            log::warn!("Viewport max_depth {} is outside [0, 1] range - this may cause issues", max_depth);
-        }
-        if min_depth > max_depth {
-            log::warn!("Viewport min_depth {} > max_depth {} - swapping values", min_depth, max_depth);
+        if !(0.0..=1.0).contains(&min_depth) || !(0.0..=1.0).contains(&max_depth) || min_depth > max_depth {
+            return Err(BasaltError::invalid_parameter(
+                "depth_range",
+                format!("{}..{} must lie in [0, 1] with min <= max", min_depth, max_depth),
+            ));
         }
 
         self.commands.push(RenderCommand::SetViewport {
             x,
             y,
-            width: clamped_width,
-            height: clamped_height,
+            width,
+            height,
             min_depth,
             max_depth,
         });
+        Ok(())
     }
 
     /// Record a set scissor rect command
@@ -492,6 +891,14 @@ impl RenderPassState {
         });
     }
 
+    /// Record a set stencil reference command
+    ///
+    /// The reference is dynamic state, so it can change between draws without
+    /// switching pipelines (e.g. one value per portal or outline mask).
+    pub fn record_set_stencil_reference(&mut self, reference: u32) {
+        self.commands.push(RenderCommand::SetStencilReference { reference });
+    }
+
     /// Record a push debug group command
     pub fn record_push_debug_group(&mut self, label: String) {
         self.commands.push(RenderCommand::PushDebugGroup { label });
@@ -507,12 +914,142 @@ impl RenderPassState {
         self.commands.push(RenderCommand::InsertDebugMarker { label });
     }
 
+    /// Record execution of pre-recorded render bundles, in order
+    ///
+    /// Executing bundles resets the pass's pipeline, bind groups and vertex
+    /// and index buffers, so draws after it are skipped until a pipeline is
+    /// set again, and nothing bound before is treated as still bound. The
+    /// immediates fallback, if any, is bound again at its current slot.
+    pub fn record_execute_bundles(&mut self, bundle_ids: &[id::RenderBundleId]) {
+        if bundle_ids.is_empty() {
+            return;
+        }
+        self.commands.push(RenderCommand::ExecuteBundles { bundle_ids: bundle_ids.to_vec() });
+
+        self.pipeline_set = false;
+        self.pipeline_compatible = false;
+        self.pipeline_vertex_buffers = None;
+        self.immediate_ranges = None;
+        self.bound_pipeline = None;
+        self.bind_groups_set = [false; 4];
+        self.bound_bind_groups = Default::default();
+        self.bound_vertex_buffers.clear();
+        self.vertex_buffer_size = None;
+        self.pipeline_vertex_stride = None;
+        self.max_index_count = None;
+
+        // A pending update binds its own slot at the next draw
+        if let Some(fallback) = self.immediates_fallback.filter(|_| !self.immediates_dirty) {
+            let slot = self.immediates_slot_count();
+            self.record_set_bind_group(
+                IMMEDIATES_FALLBACK_GROUP,
+                Some(fallback.bind_group_id),
+                vec![slot * IMMEDIATES_FALLBACK_SLOT_SIZE],
+            );
+        }
+    }
+
+    /// Record the start of occlusion query `query_index`
+    ///
+    /// Samples passing depth/stencil tests until the matching
+    /// `record_end_occlusion_query` are counted into that query.
+    pub fn record_begin_occlusion_query(&mut self, query_index: u32) -> Result<()> {
+        let Some((_, count)) = self.occlusion_query_set else {
+            return Err(BasaltError::invalid_parameter(
+                "query_index",
+                "render pass was begun without an occlusion query set",
+            ));
+        };
+        if query_index >= count {
+            return Err(BasaltError::invalid_parameter(
+                "query_index",
+                format!("occlusion query {} out of range for a set of {}", query_index, count),
+            ));
+        }
+        if let Some(active) = self.active_occlusion_query {
+            return Err(BasaltError::invalid_parameter(
+                "query_index",
+                format!("occlusion query {} is still open; queries can't nest", active),
+            ));
+        }
+
+        self.active_occlusion_query = Some(query_index);
+        self.commands.push(RenderCommand::BeginOcclusionQuery { query_index });
+        Ok(())
+    }
+
+    /// Record the end of the open occlusion query
+    pub fn record_end_occlusion_query(&mut self) -> Result<()> {
+        if self.active_occlusion_query.take().is_none() {
+            return Err(BasaltError::invalid_parameter("query", "no occlusion query is open"));
+        }
+        self.commands.push(RenderCommand::EndOcclusionQuery);
+        Ok(())
+    }
+
+    /// Record the start of pipeline statistics query `query_index` of `queries`
+    ///
+    /// Work done by draws until the matching
+    /// `record_end_pipeline_statistics_query` is counted into that query.
+    pub fn record_begin_pipeline_statistics_query(
+        &mut self,
+        queries: &PipelineStatisticsQuerySet,
+        query_index: u32,
+    ) -> Result<()> {
+        if query_index >= queries.count {
+            return Err(BasaltError::invalid_parameter(
+                "query_index",
+                format!("pipeline statistics query {} out of range for a set of {}", query_index, queries.count),
+            ));
+        }
+        if let Some(active) = self.active_pipeline_statistics_query {
+            return Err(BasaltError::invalid_parameter(
+                "query_index",
+                format!("pipeline statistics query {} is still open; queries can't nest", active),
+            ));
+        }
+
+        self.active_pipeline_statistics_query = Some(query_index);
+        self.commands.push(RenderCommand::BeginPipelineStatisticsQuery {
+            query_set_id: queries.query_set_id,
+            query_index,
+        });
+        Ok(())
+    }
+
+    /// Record the end of the open pipeline statistics query
+    pub fn record_end_pipeline_statistics_query(&mut self) -> Result<()> {
+        if self.active_pipeline_statistics_query.take().is_none() {
+            return Err(BasaltError::invalid_parameter("query", "no pipeline statistics query is open"));
+        }
+        self.commands.push(RenderCommand::EndPipelineStatisticsQuery);
+        Ok(())
+    }
+
+    /// Route immediates through `fallback` instead of push constants
+    ///
+    /// Binds the zeroed block in slot 0 right away, so pipelines reading
+    /// immediates can draw before the first update.
+    pub fn set_immediates_fallback(&mut self, fallback: ImmediatesFallback) {
+        self.immediates_fallback = Some(fallback);
+        self.immediates = vec![0; MAX_IMMEDIATE_SIZE as usize];
+        self.immediates_staging.clear();
+        self.immediates_dirty = false;
+        self.record_set_bind_group(IMMEDIATES_FALLBACK_GROUP, Some(fallback.bind_group_id), vec![0]);
+    }
+
     /// Record a set push constants command
     ///
     /// Immediates allow passing small amounts of per-draw data directly to shaders
-    /// without the overhead of creating and binding uniform buffers.
+    /// without the overhead of creating and binding uniform buffers. On devices
+    /// without push constants the data goes to the uniform fallback instead.
+    ///
+    /// All stages share one immediate range, so `stages` doesn't limit who
+    /// sees the data; it names the stages the write is meant for, and the write
+    /// must fit the block one of them declares in the bound pipeline.
     ///
     /// # Arguments
+    /// * `stages` - Stages the data is for, `VERTEX`, `FRAGMENT` or both
     /// * `offset` - Byte offset within the immediate data range (must be 4-byte aligned)
     /// * `data` - The data to write (must be 4-byte aligned)
     ///
@@ -520,14 +1057,86 @@ impl RenderPassState {
     /// ```wgsl
     /// var<immediate> model_matrix: mat4x4<f32>;
     /// ```
-    pub fn record_set_immediates(&mut self, offset: u32, data: Vec<u8>) {
-        self.commands.push(RenderCommand::SetImmediates { offset, data });
+    pub fn record_set_immediates(&mut self, stages: wgt::ShaderStages, offset: u32, data: Vec<u8>) -> Result<()> {
+        if stages.is_empty() || !wgt::ShaderStages::VERTEX_FRAGMENT.contains(stages) {
+            return Err(BasaltError::invalid_parameter(
+                "stages",
+                format!("immediates can target the vertex and fragment stages, got {:?}", stages),
+            ));
+        }
+        if !offset.is_multiple_of(4) {
+            return Err(BasaltError::invalid_parameter("offset", format!("{} is not 4-byte aligned", offset)));
+        }
+
+        if self.immediates_fallback.is_none() {
+            let end = offset as u64 + data.len() as u64;
+            if let Some(size) = self.immediate_ranges.map(|ranges| ranges.size_for(stages)) {
+                if end > size as u64 {
+                    return Err(BasaltError::invalid_parameter(
+                        "offset",
+                        format!("immediates {}..{} exceed the {} byte block the pipeline's {:?} stages declare", offset, end, size, stages),
+                    ));
+                }
+            }
+            self.commands.push(RenderCommand::SetImmediates { offset, data });
+            return Ok(());
+        }
+
+        let start = offset as usize;
+        let end = start + data.len();
+        if end > self.immediates.len() {
+            return Err(BasaltError::invalid_parameter(
+                "offset",
+                format!("immediates {}..{} exceed the {} byte fallback block", start, end, MAX_IMMEDIATE_SIZE),
+            ));
+        }
+        // Reserve the slot the next draw will need, so draws themselves can't fail
+        if !self.immediates_dirty && self.immediates_slot_count() >= IMMEDIATES_FALLBACK_SLOTS {
+            return Err(BasaltError::RenderPass(format!(
+                "immediate updates exceed the {} uniform fallback slots of one pass",
+                IMMEDIATES_FALLBACK_SLOTS
+            )));
+        }
+        self.immediates[start..end].copy_from_slice(&data);
+        self.immediates_dirty = true;
+        Ok(())
     }
 
-    /// Record immediates (convenience method)
-    /// This method is kept for compatibility with the old API name
-    pub fn record_set_push_constants_all(&mut self, offset: u32, data: &[u8]) {
-        self.record_set_immediates(offset, data.to_vec());
+    /// Fallback slots written so far in this pass, which `upload_immediates` uploads
+    pub fn immediates_slot_count(&self) -> u32 {
+        (self.immediates_staging.len() / IMMEDIATES_FALLBACK_SLOT_SIZE as usize) as u32
+    }
+
+    /// Before a draw: append a changed immediate block as a new fallback slot and bind it
+    ///
+    /// `record_set_immediates` already reserved the slot. Its offset is
+    /// recorded relative to the pass, as `(index + 1) * IMMEDIATES_FALLBACK_SLOT_SIZE`
+    /// with 0 left for the zeroed block, and resolved when the pass ends.
+    fn flush_immediates(&mut self) {
+        let Some(fallback) = self.immediates_fallback.filter(|_| self.immediates_dirty) else {
+            return;
+        };
+
+        let slot = self.immediates_slot_count() + 1;
+        self.immediates_staging.extend_from_slice(&self.immediates);
+        self.immediates_staging.resize((slot * IMMEDIATES_FALLBACK_SLOT_SIZE) as usize, 0);
+        self.immediates_dirty = false;
+        self.record_set_bind_group(
+            IMMEDIATES_FALLBACK_GROUP,
+            Some(fallback.bind_group_id),
+            vec![slot * IMMEDIATES_FALLBACK_SLOT_SIZE],
+        );
+    }
+
+    /// Dynamic offsets to bind, with pass-relative fallback slots moved to the uploaded ones
+    fn resolve_offsets<'a>(&self, index: u32, bind_group_id: Option<id::BindGroupId>, offsets: &'a [u32]) -> Cow<'a, [u32]> {
+        let is_fallback = self.immediates_fallback.is_some_and(|fallback| Some(fallback.bind_group_id) == bind_group_id);
+        match offsets {
+            [slot_offset] if is_fallback && index == IMMEDIATES_FALLBACK_GROUP && *slot_offset != 0 => {
+                Cow::Owned(vec![slot_offset + (self.immediates_base_slot - 1) * IMMEDIATES_FALLBACK_SLOT_SIZE])
+            }
+            _ => Cow::Borrowed(offsets),
+        }
     }
 
     /// Set the maximum index count for validation (from index buffer size)
@@ -543,9 +1152,12 @@ impl RenderPassState {
         self.max_index_count
     }
 
-    /// End the render pass and submit to the queue
+    /// End the render pass and submit it on a command buffer of its own
     ///
-    /// Executes all recorded commands using wgpu-core 27's command_encoder_run_render_pass.
+    /// For passes outside a frame; `BasaltDevice::record_render_pass` records a
+    /// frame's passes into one encoder and submits them together instead.
+    /// Immediates go to the fallback's first slots, which frame passes still
+    /// waiting for their submit may be reading.
     /// Returns the output texture (if any) for main framebuffer tracking.
     pub fn finish_and_submit(&mut self, context: &BasaltContext, queue_id: id::QueueId) -> Result<Option<id::TextureId>> {
         if !self.is_active {
@@ -553,6 +1165,80 @@ impl RenderPassState {
             return Ok(None);
         }
 
+        let global = context.inner();
+        let _serialized = context.serialize_submission();
+        // Queue writes land before the submit below
+        self.upload_immediates(context, queue_id, 1)?;
+        let encoder_desc = wgt::CommandEncoderDescriptor {
+            label: Some(Cow::Borrowed("Basalt Command Encoder")),
+        };
+        let (encoder_id, error) = global.device_create_command_encoder(self.device_id, &encoder_desc, None);
+        if let Some(e) = error {
+            return Err(BasaltError::device_creation(format!("Failed to create command encoder: {:?}", e)));
+        }
+
+        let output = match self.finish(context, encoder_id) {
+            Ok(output) => output,
+            Err(e) => {
+                global.command_encoder_drop(encoder_id);
+                return Err(e);
+            }
+        };
+
+        // Finish the command encoder
+        let (command_buffer_id, error) = global.command_encoder_finish(
+            encoder_id,
+            &wgt::CommandBufferDescriptor::default(),
+            None,
+        );
+
+        if let Some(e) = error {
+            return Err(BasaltError::device_creation(format!(
+                "Failed to finish command encoder: {:?}", e
+            )));
+        }
+
+        // Submit to queue
+        let result = global.queue_submit(queue_id, &[command_buffer_id]);
+
+        if let Err((_, e)) = result {
+            return Err(BasaltError::from_webgpu_error("render pass submit", &e));
+        }
+
+        // Poll the device to drive GPU progress and internal state machines
+        // This is important for proper frame synchronization and preventing stalls
+        // Use Poll (non-blocking) here - frame limiting is handled elsewhere
+        let _ = global.device_poll(self.device_id, wgt::PollType::Poll);
+
+        Ok(output)
+    }
+
+    /// Write the pass's immediates to the uniform fallback, starting at `base_slot`
+    ///
+    /// Call before the pass ends, so its draws bind the slots written here.
+    /// Queue writes land before the next submit, so the slots must not be read
+    /// by passes recorded but not yet submitted; see `ImmediatesRing`.
+    pub fn upload_immediates(&mut self, context: &BasaltContext, queue_id: id::QueueId, base_slot: u32) -> Result<()> {
+        self.immediates_base_slot = base_slot;
+        if let Some(fallback) = self.immediates_fallback.filter(|_| !self.immediates_staging.is_empty()) {
+            let offset = base_slot as u64 * IMMEDIATES_FALLBACK_SLOT_SIZE as u64;
+            if let Err(e) = context.inner().queue_write_buffer(queue_id, fallback.buffer_id, offset, &self.immediates_staging) {
+                return Err(BasaltError::RenderPass(format!("Failed to upload immediates: {:?}", e)));
+            }
+        }
+        Ok(())
+    }
+
+    /// End the render pass by recording it into `encoder_id`, leaving the encoder open
+    ///
+    /// Replays every recorded command. On error the encoder is invalid and must
+    /// be dropped. Returns the output texture (if any) for main framebuffer tracking.
+    pub fn finish(&mut self, context: &BasaltContext, encoder_id: id::CommandEncoderId) -> Result<Option<id::TextureId>> {
+        if !self.is_active {
+            log::warn!("Render pass is not active, skipping it");
+            return Ok(None);
+        }
+
         log::debug!("Finishing render pass with {} commands, color_view={:?}", 
             self.commands.len(), self.color_view);
 
@@ -571,11 +1257,21 @@ impl RenderPassState {
             };
             color_attachments.push(Some(wgpu_core::command::RenderPassColorAttachment {
                 view,
-                resolve_target: None,
+                resolve_target: self.resolve_view,
                 load_op,
                 store_op: wgpu_core::command::StoreOp::Store,
                 depth_slice: None,
             }));
+            // MRT attachments share the primary load op and are never resolved
+            for &view in &self.extra_color_views {
+                color_attachments.push(Some(wgpu_core::command::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    load_op,
+                    store_op: wgpu_core::command::StoreOp::Store,
+                    depth_slice: None,
+                }));
+            }
         }
 
         // Depth attachment - use Clear or Load based on should_clear_depth
@@ -626,10 +1322,20 @@ impl RenderPassState {
             color_attachments: Cow::Borrowed(&color_attachments),
             depth_stencil_attachment: depth_stencil_attachment.as_ref(),
             timestamp_writes: None,
-            occlusion_query_set: None,
+            occlusion_query_set: self.occlusion_query_set.map(|(query_set_id, _)| query_set_id),
             multiview_mask: None,  // No multiview rendering (wgpu 28.0+)
         };
 
+        // A query left open would fail validation when the pass ends
+        if let Some(index) = self.active_occlusion_query.take() {
+            log::warn!("Occlusion query {} was not ended; closing it at the end of the pass", index);
+            self.commands.push(RenderCommand::EndOcclusionQuery);
+        }
+        if let Some(index) = self.active_pipeline_statistics_query.take() {
+            log::warn!("Pipeline statistics query {} was not ended; closing it at the end of the pass", index);
+            self.commands.push(RenderCommand::EndPipelineStatisticsQuery);
+        }
+
         // Take ownership of commands vec to execute them
         let commands = std::mem::take(&mut self.commands);
 
@@ -651,7 +1357,7 @@ impl RenderPassState {
 
         // Begin render pass
         let (mut render_pass, error) = global.command_encoder_begin_render_pass(
-            self.command_encoder_id,
+            encoder_id,
             &desc,
         );
 
@@ -677,7 +1383,8 @@ impl RenderPassState {
                         .map_err(|e| BasaltError::RenderPass(format!("Command {}: Failed to set index buffer {:?}: {:?}", cmd_index, buffer_id, e)))?;
                 }
                 RenderCommand::SetBindGroup { index, bind_group_id, offsets } => {
-                    global.render_pass_set_bind_group(&mut render_pass, *index, *bind_group_id, offsets)
+                    let offsets = self.resolve_offsets(*index, *bind_group_id, offsets);
+                    global.render_pass_set_bind_group(&mut render_pass, *index, *bind_group_id, &offsets)
                         .map_err(|e| BasaltError::RenderPass(format!("Command {}: Failed to set bind group (index={}, group={:?}): {:?}", cmd_index, index, bind_group_id, e)))?;
                 }
                 RenderCommand::DrawIndexed {
@@ -714,6 +1421,22 @@ impl RenderPassState {
                         *first_instance,
                     ).map_err(|e| BasaltError::RenderPass(format!("Command {}: Failed to draw (vertices={}, instances={}): {:?}", cmd_index, vertex_count, instance_count, e)))?;
                 }
+                RenderCommand::DrawIndirect { buffer_id, offset, count } => {
+                    let result = if *count == 1 {
+                        global.render_pass_draw_indirect(&mut render_pass, *buffer_id, *offset)
+                    } else {
+                        global.render_pass_multi_draw_indirect(&mut render_pass, *buffer_id, *offset, *count)
+                    };
+                    result.map_err(|e| BasaltError::RenderPass(format!("Command {}: Failed to draw indirect (buffer={:?}, offset={}, count={}): {:?}", cmd_index, buffer_id, offset, count, e)))?;
+                }
+                RenderCommand::DrawIndexedIndirect { buffer_id, offset, count } => {
+                    let result = if *count == 1 {
+                        global.render_pass_draw_indexed_indirect(&mut render_pass, *buffer_id, *offset)
+                    } else {
+                        global.render_pass_multi_draw_indexed_indirect(&mut render_pass, *buffer_id, *offset, *count)
+                    };
+                    result.map_err(|e| BasaltError::RenderPass(format!("Command {}: Failed to draw indexed indirect (buffer={:?}, offset={}, count={}): {:?}", cmd_index, buffer_id, offset, count, e)))?;
+                }
                 RenderCommand::SetViewport { x, y, width, height, min_depth, max_depth } => {
                     global.render_pass_set_viewport(&mut render_pass, *x, *y, *width, *height, *min_depth, *max_depth)
                         .map_err(|e| BasaltError::RenderPass(format!("Command {}: Failed to set viewport: {:?}", cmd_index, e)))?;
@@ -722,6 +1445,10 @@ impl RenderPassState {
                     global.render_pass_set_scissor_rect(&mut render_pass, *x, *y, *width, *height)
                         .map_err(|e| BasaltError::RenderPass(format!("Command {}: Failed to set scissor rect: {:?}", cmd_index, e)))?;
                 }
+                RenderCommand::SetStencilReference { reference } => {
+                    global.render_pass_set_stencil_reference(&mut render_pass, *reference)
+                        .map_err(|e| BasaltError::RenderPass(format!("Command {}: Failed to set stencil reference: {:?}", cmd_index, e)))?;
+                }
                 RenderCommand::PushDebugGroup { label } => {
                     // Debug groups are optional - log errors but don't fail
                     let _ = global.render_pass_push_debug_group(&mut render_pass, label, 0xFFFFFFFF);
@@ -737,6 +1464,26 @@ impl RenderPassState {
                     global.render_pass_set_immediates(&mut render_pass, *offset, data)
                         .map_err(|e| BasaltError::RenderPass(format!("Command {}: Failed to set immediates (offset={}, size={}): {:?}", cmd_index, offset, data.len(), e)))?;
                 }
+                RenderCommand::BeginOcclusionQuery { query_index } => {
+                    global.render_pass_begin_occlusion_query(&mut render_pass, *query_index)
+                        .map_err(|e| BasaltError::RenderPass(format!("Command {}: Failed to begin occlusion query {}: {:?}", cmd_index, query_index, e)))?;
+                }
+                RenderCommand::EndOcclusionQuery => {
+                    global.render_pass_end_occlusion_query(&mut render_pass)
+                        .map_err(|e| BasaltError::RenderPass(format!("Command {}: Failed to end occlusion query: {:?}", cmd_index, e)))?;
+                }
+                RenderCommand::BeginPipelineStatisticsQuery { query_set_id, query_index } => {
+                    global.render_pass_begin_pipeline_statistics_query(&mut render_pass, *query_set_id, *query_index)
+                        .map_err(|e| BasaltError::RenderPass(format!("Command {}: Failed to begin pipeline statistics query {}: {:?}", cmd_index, query_index, e)))?;
+                }
+                RenderCommand::EndPipelineStatisticsQuery => {
+                    global.render_pass_end_pipeline_statistics_query(&mut render_pass)
+                        .map_err(|e| BasaltError::RenderPass(format!("Command {}: Failed to end pipeline statistics query: {:?}", cmd_index, e)))?;
+                }
+                RenderCommand::ExecuteBundles { bundle_ids } => {
+                    global.render_pass_execute_bundles(&mut render_pass, bundle_ids)
+                        .map_err(|e| BasaltError::RenderPass(format!("Command {}: Failed to execute {} render bundles: {:?}", cmd_index, bundle_ids.len(), e)))?;
+                }
             }
         }
 
@@ -747,35 +1494,8 @@ impl RenderPassState {
             )));
         }
 
-        // Finish the command encoder
-        let (command_buffer_id, error) = global.command_encoder_finish(
-            self.command_encoder_id,
-            &wgt::CommandBufferDescriptor::default(),
-            None,
-        );
-
-        if let Some(e) = error {
-            return Err(BasaltError::device_creation(format!(
-                "Failed to finish command encoder: {:?}", e
-            )));
-        }
-
-        // Submit to queue
-        let result = global.queue_submit(queue_id, &[command_buffer_id]);
-
-        if let Err(e) = result {
-            return Err(BasaltError::device_creation(format!(
-                "Failed to submit command buffer: {:?}", e
-            )));
-        }
-
-        // Poll the device to drive GPU progress and internal state machines
-        // This is important for proper frame synchronization and preventing stalls
-        // Use Poll (non-blocking) here - frame limiting is handled elsewhere
-        let _ = global.device_poll(self.device_id, wgt::PollType::Poll);
-
         self.is_active = false;
-        log::debug!("Render pass executed with {} commands and submitted to queue", commands.len());
+        log::debug!("Render pass recorded with {} commands", commands.len());
 
         // Return the output texture for main framebuffer tracking
         // This is set AFTER rendering completes, avoiding the race condition
@@ -792,3 +1512,887 @@ impl RenderPassState {
         self.is_active = false;
     }
 }
+
+/// Check that `vertex_count` vertices from `first_vertex` fit in a vertex
+/// buffer binding of `buffer_size` bytes
+///
+/// Like wgpu, the last vertex only needs room up to the end of its last
+/// attribute: `(first_vertex + vertex_count - 1) * array_stride + attributes_end`.
+pub fn validate_vertex_range(buffer_size: u64, stride: VertexStride, first_vertex: u64, vertex_count: u64) -> Result<()> {
+    if stride.array_stride == 0 || vertex_count == 0 {
+        return Ok(());
+    }
+    let end = first_vertex.saturating_add(vertex_count);
+    let required = (end - 1).saturating_mul(stride.array_stride).saturating_add(stride.attributes_end);
+    if required > buffer_size {
+        return Err(BasaltError::Validation(format!(
+            "draw reads vertices {}..{} ({} bytes at a {}-byte stride) but the bound vertex buffer only holds \
+             {} bytes; check the pipeline's vertex format against the buffer's layout",
+            first_vertex, end, required, stride.array_stride, buffer_size
+        )));
+    }
+    Ok(())
+}
+
+impl Drop for RenderPassState {
+    fn drop(&mut self) {
+        LIVE_RENDER_PASSES.end();
+    }
+}
+
+/// Default number of live render passes above which a leak is reported
+pub const DEFAULT_RENDER_PASS_LEAK_THRESHOLD: usize = 64;
+
+/// Counts live `RenderPassState`s to catch passes that are begun but never ended
+///
+/// Render passes cross JNI as raw pointers, so a pass Java forgets to end
+/// leaks its recorded commands silently. A warning is logged each time the
+/// count climbs past the threshold.
+pub struct RenderPassLeakTracker {
+    live: AtomicUsize,
+    threshold: AtomicUsize,
+}
+
+impl RenderPassLeakTracker {
+    pub const fn new(threshold: usize) -> Self {
+        Self {
+            live: AtomicUsize::new(0),
+            threshold: AtomicUsize::new(threshold),
+        }
+    }
+
+    /// Count a new pass; returns true if it pushed the count past the threshold
+    pub fn begin(&self) -> bool {
+        let live = self.live.fetch_add(1, Ordering::Relaxed) + 1;
+        let threshold = self.threshold.load(Ordering::Relaxed);
+        let exceeded = live == threshold + 1;
+        if exceeded {
+            log::warn!(
+                "{} render passes are alive (threshold {}); passes are probably being begun without being ended",
+                live, threshold
+            );
+        }
+        exceeded
+    }
+
+    pub fn end(&self) {
+        let _ = self.live.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |live| live.checked_sub(1));
+    }
+
+    pub fn live(&self) -> usize {
+        self.live.load(Ordering::Relaxed)
+    }
+
+    pub fn set_threshold(&self, threshold: usize) {
+        self.threshold.store(threshold, Ordering::Relaxed);
+    }
+}
+
+/// Live render passes across all devices
+pub static LIVE_RENDER_PASSES: RenderPassLeakTracker =
+    RenderPassLeakTracker::new(DEFAULT_RENDER_PASS_LEAK_THRESHOLD);
+
+/// Convert a packed ARGB color (Minecraft format) to a wgpu color
+pub fn unpack_argb_color(argb: u32) -> wgt::Color {
+    let channel = |shift: u32| ((argb >> shift) & 0xFF) as f64 / 255.0;
+    wgt::Color { r: channel(16), g: channel(8), b: channel(0), a: channel(24) }
+}
+
+/// Clear color to use for an attachment of `format`
+///
+/// Clear values are linear, and an sRGB attachment encodes them as it stores
+/// them. Minecraft's packed colors are already sRGB-encoded, so for sRGB
+/// formats the color channels are decoded here: 0x808080 then stores 0x808080
+/// (visually mid-gray) instead of 0xBCBCBC. Alpha is linear in every format,
+/// and non-sRGB formats store the color as given.
+pub fn linear_clear_color(color: wgt::Color, format: Option<wgt::TextureFormat>) -> wgt::Color {
+    if !format.is_some_and(|format| format.is_srgb()) {
+        return color;
+    }
+    let decode = |c: f64| if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) };
+    wgt::Color { r: decode(color.r), g: decode(color.g), b: decode(color.b), a: color.a }
+}
+
+/// Check a vertex buffer slot against `max_vertex_buffers` and, once a
+/// pipeline is set, against the slots its vertex layout declares
+pub fn validate_vertex_buffer_slot(
+    slot: i32,
+    max_vertex_buffers: u32,
+    pipeline_vertex_buffers: Option<u32>,
+) -> Result<()> {
+    let slot = u32::try_from(slot)
+        .map_err(|_| BasaltError::invalid_parameter("slot", format!("vertex buffer slot {} is negative", slot)))?;
+    if slot >= max_vertex_buffers {
+        return Err(BasaltError::invalid_parameter(
+            "slot",
+            format!(
+                "vertex buffer slot {} exceeds the device limit (max_vertex_buffers = {}, valid slots 0..{})",
+                slot, max_vertex_buffers, max_vertex_buffers
+            ),
+        ));
+    }
+    if let Some(declared) = pipeline_vertex_buffers {
+        if slot >= declared {
+            return Err(BasaltError::invalid_parameter(
+                "slot",
+                format!(
+                    "vertex buffer slot {} is not declared by the current pipeline's vertex layout ({} slot(s))",
+                    slot, declared
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check that every attachment of a pass has the same size, and that it is
+/// the `width` x `height` the pass was begun with
+///
+/// `attachments` are `(name, width, height)`; the first one is what the others
+/// are compared against. A resize that recreates only some of the targets
+/// shows up here instead of as a wgpu validation error at the end of the pass.
+pub fn validate_attachment_sizes(width: u32, height: u32, attachments: &[(String, u32, u32)]) -> Result<()> {
+    let Some((first_name, first_width, first_height)) = attachments.first() else {
+        return Ok(());
+    };
+    for (name, attachment_width, attachment_height) in &attachments[1..] {
+        if (attachment_width, attachment_height) != (first_width, first_height) {
+            return Err(BasaltError::invalid_parameter(
+                "attachments",
+                format!(
+                    "{} is {}x{} but {} is {}x{}",
+                    name, attachment_width, attachment_height, first_name, first_width, first_height
+                ),
+            ));
+        }
+    }
+    if (*first_width, *first_height) != (width, height) {
+        return Err(BasaltError::invalid_parameter(
+            "attachments",
+            format!(
+                "attachments are {}x{} but the render pass was begun with {}x{}",
+                first_width, first_height, width, height
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_srgb_clear_color_decodes_channels() {
+        let gray = unpack_argb_color(0x80808080);
+        assert_eq!(gray.a, 128.0 / 255.0);
+
+        assert_eq!(linear_clear_color(gray, Some(wgt::TextureFormat::Rgba8Unorm)), gray);
+        assert_eq!(linear_clear_color(gray, None), gray);
+
+        // Encoding the decoded value again, as the sRGB attachment does, gives back 0x80
+        let linear = linear_clear_color(gray, Some(wgt::TextureFormat::Bgra8UnormSrgb));
+        assert!((linear.r - 0.2158).abs() < 1e-4, "{:?}", linear);
+        assert_eq!((linear.r, linear.g, linear.a), (linear.b, linear.b, gray.a));
+        let encoded = 1.055 * linear.r.powf(1.0 / 2.4) - 0.055;
+        assert_eq!((encoded * 255.0).round(), 128.0);
+
+        let black = unpack_argb_color(0xFF000000);
+        assert_eq!(linear_clear_color(black, Some(wgt::TextureFormat::Rgba8UnormSrgb)), black);
+    }
+
+    #[test]
+    fn test_vertex_buffer_slot_out_of_range() {
+        assert!(validate_vertex_buffer_slot(0, 8, Some(1)).is_ok());
+        assert!(validate_vertex_buffer_slot(7, 8, None).is_ok());
+
+        let err = validate_vertex_buffer_slot(8, 8, None).unwrap_err().to_string();
+        assert!(err.contains("max_vertex_buffers = 8"), "{}", err);
+
+        // Off-by-one when adding an instance buffer the layout does not declare
+        let err = validate_vertex_buffer_slot(1, 8, Some(1)).unwrap_err().to_string();
+        assert!(err.contains("not declared by the current pipeline"), "{}", err);
+
+        assert!(validate_vertex_buffer_slot(-1, 8, None).is_err());
+    }
+
+    #[test]
+    fn test_mismatched_attachment_sizes() {
+        let attachment = |name: &str, width, height| (name.to_string(), width, height);
+        assert!(validate_attachment_sizes(854, 480, &[]).is_ok());
+        assert!(validate_attachment_sizes(854, 480, &[
+            attachment("color attachment 0", 854, 480),
+            attachment("depth attachment", 854, 480),
+        ]).is_ok());
+
+        // Depth target left at the old window size after a resize
+        let err = validate_attachment_sizes(1920, 1080, &[
+            attachment("color attachment 0", 1920, 1080),
+            attachment("depth attachment", 854, 480),
+        ]).unwrap_err().to_string();
+        assert!(err.contains("depth attachment is 854x480 but color attachment 0 is 1920x1080"), "{}", err);
+
+        let err = validate_attachment_sizes(1920, 1080, &[attachment("color attachment 0", 854, 480)])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("begun with 1920x1080"), "{}", err);
+    }
+
+    #[test]
+    fn test_immediates_ring_keeps_frame_slots_apart() {
+        let mut ring = ImmediatesRing::new();
+        assert_eq!(ring.reserve(4), Some(1));
+        assert_eq!(ring.reserve(IMMEDIATES_FALLBACK_SLOTS), Some(5));
+
+        // Slots still held by the frame are never handed out again...
+        let free = IMMEDIATES_FALLBACK_RING_SLOTS - 1 - 4 - IMMEDIATES_FALLBACK_SLOTS;
+        assert_eq!(ring.reserve(free), Some(5 + IMMEDIATES_FALLBACK_SLOTS));
+        assert_eq!(ring.reserve(1), None);
+
+        // ...until it is submitted; runs then wrap past the zeroed slot 0
+        ring.frame_submitted();
+        assert_eq!(ring.reserve(2), Some(1));
+        let tail = IMMEDIATES_FALLBACK_RING_SLOTS - 3 - 1;
+        assert_eq!(ring.reserve(tail), Some(3));
+        ring.frame_submitted();
+        assert_eq!(ring.reserve(2), Some(1), "a run that doesn't fit the tail starts over");
+    }
+
+    #[test]
+    fn test_live_render_pass_count() {
+        let tracker = RenderPassLeakTracker::new(2);
+        assert!(!tracker.begin());
+        assert!(!tracker.begin());
+        // Third pass without any end crosses the threshold, and warns only once
+        assert!(tracker.begin());
+        assert!(!tracker.begin());
+        assert_eq!(tracker.live(), 4);
+
+        tracker.end();
+        tracker.end();
+        assert_eq!(tracker.live(), 2);
+        assert!(tracker.begin());
+
+        // Real passes that are never ended stay counted until dropped
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, queue_id) = context.create_noop_device();
+        let before = LIVE_RENDER_PASSES.live();
+        let passes: Vec<_> = (0..3)
+            .map(|_| {
+                RenderPassState::new(
+                    context.clone(), device_id, queue_id, None, Vec::new(), None, None, None, 1, None,
+                    false, 0, false, 1.0, 0, 16, 16,
+                )
+                .unwrap()
+            })
+            .collect();
+        assert!(LIVE_RENDER_PASSES.live() >= before + 3);
+        drop(passes);
+    }
+
+    #[test]
+    fn test_strict_validation_raises_pipeline_mismatch() {
+        let _strict = crate::error::STRICT_VALIDATION_TEST_LOCK.lock();
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, queue_id) = context.create_noop_device();
+        let mut state = RenderPassState::new(
+            context.clone(), device_id, queue_id, None, Vec::new(), None, None, None, 1, None,
+            false, 0, false, 1.0, 0, 16, 16,
+        )
+        .unwrap();
+        let pipeline = PipelineBinding {
+            depth_write_enabled: true,
+            depth_test_enabled: true,
+            sample_count: 4,
+            vertex_buffer_count: 1,
+            color_target_count: 0,
+            ..PipelineBinding::new(id::RenderPipelineId::zip(0, 1))
+        };
+
+        // Lenient: the 4x MSAA pipeline is logged and its draws skipped, and the
+        // error is left in this thread's last-error slot
+        crate::error::set_strict_validation(false);
+        let _ = crate::error::take_last_error();
+        assert!(state.record_set_pipeline(pipeline).is_ok());
+        assert!(!state.pipeline_compatible);
+        let last = crate::error::take_last_error().expect("last error recorded");
+        assert!(last.contains("sample count 4"), "{}", last);
+        assert_eq!(crate::error::take_last_error(), None);
+
+        crate::error::set_strict_validation(true);
+        let result = state.record_set_pipeline(pipeline);
+        crate::error::set_strict_validation(false);
+        let err = result.unwrap_err();
+        assert!(err.is_validation(), "{:?}", err);
+        assert!(err.to_string().contains("sample count 4"), "{}", err);
+
+        // Errors that aren't the caller's fault are still only logged
+        crate::error::set_strict_validation(true);
+        let result = BasaltError::out_of_memory("staging").log_or_escalate();
+        crate::error::set_strict_validation(false);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_multiple_color_attachments() {
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, queue_id) = context.create_noop_device();
+        let global = context.inner();
+
+        let color_view = |format| {
+            let (texture_id, error) = global.device_create_texture(
+                device_id,
+                &wgpu_core::resource::TextureDescriptor {
+                    label: Some(Cow::Borrowed("GBuffer")),
+                    size: wgt::Extent3d { width: 16, height: 16, depth_or_array_layers: 1 },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgt::TextureDimension::D2,
+                    format,
+                    usage: wgt::TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: vec![],
+                },
+                None,
+            );
+            assert!(error.is_none(), "{:?}", error);
+            let (view_id, error) = global.texture_create_view(
+                texture_id,
+                &wgpu_core::resource::TextureViewDescriptor::default(),
+                None,
+            );
+            assert!(error.is_none(), "{:?}", error);
+            view_id
+        };
+        let albedo = color_view(wgt::TextureFormat::Rgba8Unorm);
+        let normal = color_view(wgt::TextureFormat::Rgba16Float);
+
+        let mut pass = RenderPassState::new(
+            context.clone(), device_id, queue_id, Some(albedo), vec![normal], None, None, None, 1, None,
+            true, 0, false, 1.0, 0, 16, 16,
+        )
+        .unwrap();
+        assert_eq!(pass.color_attachment_count(), 2);
+        pass.finish_and_submit(&context, queue_id).unwrap();
+    }
+
+    /// 16x16 Rgba8Unorm color target and a pipeline drawing a vertex-index triangle into it
+    fn triangle_target(context: &BasaltContext, device_id: id::DeviceId) -> (id::TextureViewId, id::RenderPipelineId) {
+        let global = context.inner();
+
+        let (texture_id, error) = global.device_create_texture(
+            device_id,
+            &wgpu_core::resource::TextureDescriptor {
+                label: None,
+                size: wgt::Extent3d { width: 16, height: 16, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgt::TextureDimension::D2,
+                format: wgt::TextureFormat::Rgba8Unorm,
+                usage: wgt::TextureUsages::RENDER_ATTACHMENT,
+                view_formats: vec![],
+            },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+        let (view_id, error) =
+            global.texture_create_view(texture_id, &wgpu_core::resource::TextureViewDescriptor::default(), None);
+        assert!(error.is_none(), "{:?}", error);
+
+        let module = crate::shader::parse_wgsl(
+            "@vertex fn vs_main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+                return vec4<f32>(f32(i), 0.0, 0.0, 1.0);
+            }
+            @fragment fn fs_main() -> @location(0) vec4<f32> { return vec4<f32>(1.0); }",
+        )
+        .unwrap();
+        let (shader_id, error) = global.device_create_shader_module(
+            device_id,
+            &wgpu_core::pipeline::ShaderModuleDescriptor { label: None, runtime_checks: Default::default() },
+            wgpu_core::pipeline::ShaderModuleSource::Naga(Cow::Owned(module)),
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+        let stage = |entry_point: &'static str| wgpu_core::pipeline::ProgrammableStageDescriptor {
+            module: shader_id,
+            entry_point: Some(Cow::Borrowed(entry_point)),
+            constants: Default::default(),
+            zero_initialize_workgroup_memory: true,
+        };
+        let (pipeline_id, error) = global.device_create_render_pipeline(
+            device_id,
+            &wgpu_core::pipeline::RenderPipelineDescriptor {
+                label: None,
+                layout: None,
+                vertex: wgpu_core::pipeline::VertexState { stage: stage("vs_main"), buffers: Cow::Borrowed(&[]) },
+                primitive: wgt::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgt::MultisampleState::default(),
+                fragment: Some(wgpu_core::pipeline::FragmentState {
+                    stage: stage("fs_main"),
+                    targets: Cow::Borrowed(&[Some(wgt::TextureFormat::Rgba8Unorm.into())]),
+                }),
+                multiview_mask: None,
+                cache: None,
+            },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+
+        (view_id, pipeline_id)
+    }
+
+    #[test]
+    fn test_indirect_draws_submit() {
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, queue_id) = context.create_noop_device();
+        let global = context.inner();
+        let (view_id, pipeline_id) = triangle_target(&context, device_id);
+
+        // Two indexed draws' worth of arguments, also read as non-indexed draws
+        let create_buffer = |usage| {
+            let (buffer_id, error) = global.device_create_buffer(
+                device_id,
+                &wgt::BufferDescriptor { label: None, size: 64, usage, mapped_at_creation: false },
+                None,
+            );
+            assert!(error.is_none(), "{:?}", error);
+            buffer_id
+        };
+        let indirect = create_buffer(wgt::BufferUsages::INDIRECT | wgt::BufferUsages::COPY_DST);
+        let index = create_buffer(wgt::BufferUsages::INDEX);
+        let args: Vec<u8> = [3u32, 1, 0, 0, 0, 3, 1, 0, 0, 0].iter().flat_map(|v| v.to_le_bytes()).collect();
+        global.queue_write_buffer(queue_id, indirect, 0, &args).unwrap();
+
+        let mut pass = RenderPassState::new(
+            context.clone(), device_id, queue_id, Some(view_id), Vec::new(), None, None, None, 1, None,
+            true, 0, false, 1.0, 0, 16, 16,
+        )
+        .unwrap();
+        pass.record_set_pipeline(PipelineBinding::new(pipeline_id)).unwrap();
+        pass.record_set_index_buffer(index, wgt::IndexFormat::Uint16, 0, None);
+        pass.record_draw_indirect(indirect, 0).unwrap();
+        pass.record_draw_indexed_indirect(indirect, 0).unwrap();
+        pass.record_multi_draw_indirect(indirect, 0, 2, true).unwrap();
+        assert!(pass.record_draw_indirect(indirect, 2).is_err());
+        pass.finish_and_submit(&context, queue_id).unwrap();
+    }
+
+    #[test]
+    fn test_execute_render_bundles() {
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, queue_id) = context.create_noop_device();
+        let (view_id, pipeline_id) = triangle_target(&context, device_id);
+
+        let mut recorder = crate::render_bundle::RenderBundleRecorder::new(
+            &context, device_id, &[wgt::TextureFormat::Rgba8Unorm], None, 1, None,
+        )
+        .unwrap();
+        recorder.set_pipeline(pipeline_id);
+        recorder.draw(3, 1, 0, 0);
+        recorder.draw(3, 1, 0, 0);
+        assert_eq!(recorder.draw_count(), 2);
+        let bundle = recorder.finish(&context, Some("HUD")).unwrap();
+        assert!(crate::render_bundle::RenderBundleRecorder::new(&context, device_id, &[], None, 1, None).is_err());
+
+        // The same bundle replays in every frame's pass
+        for _ in 0..2 {
+            let mut pass = RenderPassState::new(
+                context.clone(), device_id, queue_id, Some(view_id), Vec::new(), None, None, None, 1, None,
+                true, 0, false, 1.0, 0, 16, 16,
+            )
+            .unwrap();
+            pass.record_set_pipeline(PipelineBinding::new(pipeline_id)).unwrap();
+            pass.record_draw(3, 1, 0, 0);
+            pass.record_execute_bundles(&[bundle]);
+
+            // Bundles leave nothing bound: draws wait for a pipeline, which isn't elided
+            let recorded = pass.commands.len();
+            pass.record_draw(3, 1, 0, 0);
+            assert_eq!(pass.commands.len(), recorded);
+            pass.record_set_pipeline(PipelineBinding::new(pipeline_id)).unwrap();
+            pass.record_draw(3, 1, 0, 0);
+            assert_eq!(pass.elided_state_changes(), 0);
+            assert_eq!(pass.commands.len(), recorded + 2);
+            pass.finish_and_submit(&context, queue_id).unwrap();
+        }
+        context.inner().render_bundle_drop(bundle);
+    }
+
+    #[test]
+    fn test_redundant_state_changes_are_elided() {
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, queue_id) = context.create_noop_device();
+        let mut state = RenderPassState::new(
+            context.clone(), device_id, queue_id, None, Vec::new(), None, None, None, 1, None,
+            false, 0, false, 1.0, 0, 16, 16,
+        )
+        .unwrap();
+        let recorded = state.commands.len();
+        let (pipeline_a, pipeline_b) = (id::RenderPipelineId::zip(0, 1), id::RenderPipelineId::zip(1, 1));
+        let group = id::BindGroupId::zip(0, 1);
+        let buffer = id::BufferId::zip(0, 1);
+
+        let pipeline = PipelineBinding { vertex_buffer_count: 1, color_target_count: 0, ..PipelineBinding::new(pipeline_a) };
+        state.record_set_pipeline(pipeline).unwrap();
+        state.record_set_pipeline(pipeline).unwrap();
+        state.record_set_bind_group(0, Some(group), vec![256]);
+        state.record_set_bind_group(0, Some(group), vec![256]);
+        state.record_set_vertex_buffer(0, buffer, 0, None);
+        state.record_set_vertex_buffer(0, buffer, 0, None);
+        assert_eq!(state.elided_state_changes(), 3);
+        assert_eq!(state.commands.len(), recorded + 3);
+
+        // Different values are still recorded
+        state.record_set_bind_group(0, Some(group), vec![512]);
+        state.record_set_vertex_buffer(0, buffer, 64, None);
+        state.record_set_vertex_buffer(1, buffer, 64, None);
+
+        // A pipeline change forgets the bound groups, so rebinding records again
+        state.record_set_pipeline(PipelineBinding { id: pipeline_b, vertex_buffer_count: 2, ..pipeline }).unwrap();
+        state.record_set_bind_group(0, Some(group), vec![512]);
+        state.record_set_vertex_buffer(1, buffer, 64, None);
+        assert_eq!(state.elided_state_changes(), 4);
+        assert_eq!(state.commands.len(), recorded + 8);
+    }
+
+    #[test]
+    fn test_draws_checked_against_vertex_stride() {
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, queue_id) = context.create_noop_device();
+        let mut state = RenderPassState::new(
+            context.clone(), device_id, queue_id, None, Vec::new(), None, None, None, 1, None,
+            false, 0, false, 1.0, 0, 16, 16,
+        )
+        .unwrap();
+        let buffer = id::BufferId::zip(0, 1);
+        let pipeline = PipelineBinding::new(id::RenderPipelineId::zip(0, 1));
+        state.record_set_pipeline(PipelineBinding { vertex_buffer_count: 1, color_target_count: 0, ..pipeline }).unwrap();
+        let stride = VertexStride { array_stride: 28, attributes_end: 28 };
+        state.set_vertex_stride(Some(stride));
+        state.record_set_vertex_buffer(0, buffer, 0, NonZero::new(280));
+
+        // Ten 28-byte vertices fit, an eleventh runs past the buffer
+        let recorded = state.commands.len();
+        state.record_draw(10, 1, 0, 0);
+        state.record_draw(4, 1, 6, 0);
+        assert_eq!(state.commands.len(), recorded + 2);
+        state.record_draw(4, 1, 7, 0);
+        assert_eq!(state.commands.len(), recorded + 2);
+
+        // Indexed draws only warn since the indices aren't known
+        state.record_draw_indexed(12, 1, 0, 0, 0);
+        assert_eq!(state.commands.len(), recorded + 3);
+
+        assert!(validate_vertex_range(280, stride, 0, 10).is_ok());
+        assert!(validate_vertex_range(280, stride, u64::MAX, 2).is_err());
+        let unstrided = VertexStride { array_stride: 0, attributes_end: 12 };
+        assert!(validate_vertex_range(280, unstrided, 0, 100).is_ok());
+
+        // The last vertex only needs its attributes, not the stride padding after them
+        let padded = VertexStride { array_stride: 32, attributes_end: 28 };
+        assert!(validate_vertex_range(9 * 32 + 28, padded, 0, 10).is_ok());
+        assert!(validate_vertex_range(9 * 32 + 27, padded, 0, 10).is_err());
+    }
+
+    #[test]
+    fn test_pipeline_target_format_mismatch() {
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, queue_id) = context.create_noop_device();
+        let mut state = RenderPassState::new(
+            context.clone(), device_id, queue_id, None, Vec::new(), None, None, None, 1, None,
+            false, 0, false, 1.0, 0, 16, 16,
+        )
+        .unwrap();
+
+        // Unknown attachment formats aren't checked
+        assert!(state.check_target_format("any", wgt::TextureFormat::Rgba8UnormSrgb));
+
+        state.set_color_format(Some(wgt::TextureFormat::Rgba16Float));
+        assert!(state.check_target_format("hdr", wgt::TextureFormat::Rgba16Float));
+        assert!(!state.check_target_format("ldr", wgt::TextureFormat::Bgra8UnormSrgb));
+    }
+
+    #[test]
+    fn test_viewport_passes_through_unclamped() {
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, queue_id) = context.create_noop_device();
+        let mut state = RenderPassState::new(
+            context.clone(), device_id, queue_id, None, Vec::new(), None, None, None, 1, None,
+            false, 0, false, 1.0, 0, 100, 50,
+        )
+        .unwrap();
+        let last_viewport = |state: &RenderPassState| match state.commands.last() {
+            Some(RenderCommand::SetViewport { x, y, width, height, min_depth, max_depth }) => {
+                (*x, *y, *width, *height, *min_depth, *max_depth)
+            }
+            other => panic!("expected a viewport, got {:?}", other),
+        };
+
+        // Right half, e.g. the second player of a split screen
+        state.record_set_viewport(50.0, 0.0, 50.0, 50.0, 0.0, 1.0).unwrap();
+        assert_eq!(last_viewport(&state), (50.0, 0.0, 50.0, 50.0, 0.0, 1.0));
+
+        // Reaching past the target, or starting left of it, keeps the projection intact
+        state.record_set_viewport(80.0, 40.0, 40.0, 40.0, 0.0, 0.5).unwrap();
+        assert_eq!(last_viewport(&state), (80.0, 40.0, 40.0, 40.0, 0.0, 0.5));
+        state.record_set_viewport(-10.0, 0.0, 30.0, 50.0, 0.0, 1.0).unwrap();
+        assert_eq!(last_viewport(&state), (-10.0, 0.0, 30.0, 50.0, 0.0, 1.0));
+
+        let recorded = state.commands.len();
+        assert!(state.record_set_viewport(0.0, 0.0, 10.0, 10.0, -0.5, 1.0).is_err());
+        assert!(state.record_set_viewport(0.0, 0.0, 10.0, 10.0, 0.8, 0.2).is_err());
+        assert!(state.record_set_viewport(0.0, 0.0, f32::NAN, 10.0, 0.0, 1.0).is_err());
+        assert!(state.record_set_viewport(0.0, 0.0, -1.0, 10.0, 0.0, 1.0).is_err());
+        let max = context.inner().device_limits(device_id).max_texture_dimension_2d as f32;
+        assert!(state.record_set_viewport(0.0, 0.0, max + 1.0, 10.0, 0.0, 1.0).is_err());
+        assert!(state.record_set_viewport(-2.0 * max - 1.0, 0.0, 10.0, 10.0, 0.0, 1.0).is_err());
+        assert!(state.record_set_viewport(0.0, 2.0 * max - 5.0, 10.0, 10.0, 0.0, 1.0).is_err());
+        assert_eq!(state.commands.len(), recorded);
+    }
+
+    #[test]
+    fn test_immediate_writes_checked_against_stage_blocks() {
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, queue_id) = context.create_noop_device();
+        let mut state = RenderPassState::new(
+            context.clone(), device_id, queue_id, None, Vec::new(), None, None, None, 1, None,
+            false, 0, false, 1.0, 0, 16, 16,
+        )
+        .unwrap();
+        let vertex_fragment = wgt::ShaderStages::VERTEX_FRAGMENT;
+
+        // A 64-byte vertex block and a 16-byte fragment block share one range
+        let vertex = naga::front::wgsl::parse_str(
+            "var<immediate> mvp: mat4x4<f32>;
+            @vertex fn main() -> @builtin(position) vec4<f32> { return mvp[0]; }",
+        )
+        .unwrap();
+        let fragment = naga::front::wgsl::parse_str(
+            "var<immediate> tint: vec4<f32>;
+            @fragment fn main() -> @location(0) vec4<f32> { return tint; }",
+        )
+        .unwrap();
+        let ranges = crate::pipeline_registry::immediate_ranges(&vertex, &fragment);
+        assert_eq!(ranges, ImmediateRanges { vertex: 64, fragment: 16 });
+        assert_eq!(ranges.size_for(vertex_fragment), 64);
+
+        // Before a pipeline is bound only alignment and stages are checked
+        assert!(state.record_set_immediates(wgt::ShaderStages::FRAGMENT, 48, vec![0; 16]).is_ok());
+        assert!(state.record_set_immediates(wgt::ShaderStages::FRAGMENT, 2, vec![0; 4]).is_err());
+        assert!(state.record_set_immediates(wgt::ShaderStages::empty(), 0, vec![0; 4]).is_err());
+        assert!(state.record_set_immediates(wgt::ShaderStages::COMPUTE, 0, vec![0; 4]).is_err());
+
+        state.set_immediate_ranges(ranges);
+        let recorded = state.commands.len();
+        assert!(state.record_set_immediates(wgt::ShaderStages::VERTEX, 0, vec![0; 64]).is_ok());
+        assert!(state.record_set_immediates(wgt::ShaderStages::FRAGMENT, 0, vec![0; 16]).is_ok());
+        assert!(state.record_set_immediates(vertex_fragment, 48, vec![0; 16]).is_ok());
+        let err = state.record_set_immediates(wgt::ShaderStages::FRAGMENT, 16, vec![0; 4]).unwrap_err().to_string();
+        assert!(err.contains("16 byte block"), "{}", err);
+        assert!(state.record_set_immediates(vertex_fragment, 64, vec![0; 4]).is_err());
+        assert_eq!(state.commands.len(), recorded + 3);
+    }
+
+    #[test]
+    fn test_immediates_reach_draws_through_uniform_fallback() {
+        let context = Arc::new(BasaltContext::new_noop());
+        let (device_id, queue_id) = context.create_noop_device();
+        let global = context.inner();
+
+        let create_buffer = |size, usage| {
+            let (buffer_id, error) = global.device_create_buffer(
+                device_id,
+                &wgt::BufferDescriptor { label: None, size, usage, mapped_at_creation: false },
+                None,
+            );
+            assert!(error.is_none(), "{:?}", error);
+            buffer_id
+        };
+        let create_layout = |entries: Vec<wgt::BindGroupLayoutEntry>| {
+            let (layout_id, error) = global.device_create_bind_group_layout(
+                device_id,
+                &wgpu_core::binding_model::BindGroupLayoutDescriptor { label: None, entries: Cow::Owned(entries) },
+                None,
+            );
+            assert!(error.is_none(), "{:?}", error);
+            layout_id
+        };
+        let create_bind_group = |layout, entries: Vec<wgpu_core::binding_model::BindGroupEntry>| {
+            let (bind_group_id, error) = global.device_create_bind_group(
+                device_id,
+                &wgpu_core::binding_model::BindGroupDescriptor { label: None, layout, entries: Cow::Owned(entries) },
+                None,
+            );
+            assert!(error.is_none(), "{:?}", error);
+            bind_group_id
+        };
+
+        // The fallback as BasaltDevice builds it, plus COPY_SRC to read it back
+        let fallback_size = (IMMEDIATES_FALLBACK_SLOTS * IMMEDIATES_FALLBACK_SLOT_SIZE) as u64;
+        let buffer_id = create_buffer(
+            fallback_size,
+            wgt::BufferUsages::UNIFORM | wgt::BufferUsages::COPY_DST | wgt::BufferUsages::COPY_SRC,
+        );
+        let bind_group_layout_id = create_layout(vec![wgt::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgt::ShaderStages::VERTEX | wgt::ShaderStages::FRAGMENT,
+            ty: wgt::BindingType::Buffer {
+                ty: wgt::BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: NonZero::new(MAX_IMMEDIATE_SIZE as u64),
+            },
+            count: None,
+        }]);
+        let bind_group_id = create_bind_group(bind_group_layout_id, vec![wgpu_core::binding_model::BindGroupEntry {
+            binding: 0,
+            resource: wgpu_core::binding_model::BindingResource::Buffer(wgpu_core::binding_model::BufferBinding {
+                buffer: buffer_id,
+                offset: 0,
+                size: Some(MAX_IMMEDIATE_SIZE as u64),
+            }),
+        }]);
+        let fallback = ImmediatesFallback { buffer_id, bind_group_layout_id, bind_group_id };
+        let empty_layout_id = create_layout(Vec::new());
+        let empty_group_id = create_bind_group(empty_layout_id, Vec::new());
+
+        let (pipeline_layout_id, error) = global.device_create_pipeline_layout(
+            device_id,
+            &wgpu_core::binding_model::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: Cow::Owned(vec![empty_layout_id, bind_group_layout_id]),
+                immediate_size: 0,
+            },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+
+        let wgsl = crate::shader::lower_immediates_to_uniform(
+            "var<immediate> tint: vec4<f32>;
+            @vertex fn vs_main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+                return vec4<f32>(f32(i), 0.0, 0.0, 1.0) * tint;
+            }
+            @fragment fn fs_main() -> @location(0) vec4<f32> { return tint; }",
+        )
+        .unwrap();
+        let (shader_id, error) = global.device_create_shader_module(
+            device_id,
+            &wgpu_core::pipeline::ShaderModuleDescriptor { label: None, runtime_checks: Default::default() },
+            wgpu_core::pipeline::ShaderModuleSource::Naga(Cow::Owned(crate::shader::parse_wgsl(&wgsl).unwrap())),
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+        let stage = |entry_point: &'static str| wgpu_core::pipeline::ProgrammableStageDescriptor {
+            module: shader_id,
+            entry_point: Some(Cow::Borrowed(entry_point)),
+            constants: Default::default(),
+            zero_initialize_workgroup_memory: true,
+        };
+        let (pipeline_id, error) = global.device_create_render_pipeline(
+            device_id,
+            &wgpu_core::pipeline::RenderPipelineDescriptor {
+                label: None,
+                layout: Some(pipeline_layout_id),
+                vertex: wgpu_core::pipeline::VertexState { stage: stage("vs_main"), buffers: Cow::Borrowed(&[]) },
+                primitive: wgt::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgt::MultisampleState::default(),
+                fragment: Some(wgpu_core::pipeline::FragmentState {
+                    stage: stage("fs_main"),
+                    targets: Cow::Borrowed(&[Some(wgt::TextureFormat::Rgba8Unorm.into())]),
+                }),
+                multiview_mask: None,
+                cache: None,
+            },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+
+        let (texture_id, error) = global.device_create_texture(
+            device_id,
+            &wgpu_core::resource::TextureDescriptor {
+                label: None,
+                size: wgt::Extent3d { width: 16, height: 16, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgt::TextureDimension::D2,
+                format: wgt::TextureFormat::Rgba8Unorm,
+                usage: wgt::TextureUsages::RENDER_ATTACHMENT,
+                view_formats: vec![],
+            },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+        let (view_id, error) =
+            global.texture_create_view(texture_id, &wgpu_core::resource::TextureViewDescriptor::default(), None);
+        assert!(error.is_none(), "{:?}", error);
+
+        let mut state = RenderPassState::new(
+            context.clone(), device_id, queue_id, Some(view_id), Vec::new(), None, None, None, 1, None, false, 0, false,
+            1.0, 0, 16, 16,
+        )
+        .unwrap();
+        state.set_immediates_fallback(fallback);
+        state.record_set_pipeline(PipelineBinding::new(pipeline_id)).unwrap();
+        state.record_set_bind_group(0, Some(empty_group_id), Vec::new());
+
+        let tint = |value: f32| value.to_le_bytes().repeat(4);
+        state.record_set_immediates(wgt::ShaderStages::VERTEX_FRAGMENT, 0, tint(1.0)).unwrap();
+        state.record_draw(3, 1, 0, 0);
+        // Unchanged immediates reuse the slot; several updates before a draw share one
+        state.record_draw(3, 1, 0, 0);
+        state.record_set_immediates(wgt::ShaderStages::VERTEX_FRAGMENT, 0, tint(2.0)).unwrap();
+        state.record_set_immediates(wgt::ShaderStages::VERTEX_FRAGMENT, 0, tint(3.0)).unwrap();
+        state.record_draw(3, 1, 0, 0);
+
+        let bound_offsets: Vec<u32> = state
+            .commands
+            .iter()
+            .filter_map(|command| match command {
+                RenderCommand::SetBindGroup { index: IMMEDIATES_FALLBACK_GROUP, offsets, .. } => Some(offsets[0]),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(bound_offsets, [0, IMMEDIATES_FALLBACK_SLOT_SIZE, 2 * IMMEDIATES_FALLBACK_SLOT_SIZE]);
+
+        // Bundles bind the zeroed slot, and the pass rebinds its current one after them
+        let mut recorder = crate::render_bundle::RenderBundleRecorder::new(
+            &context, device_id, &[wgt::TextureFormat::Rgba8Unorm], None, 1, Some(fallback),
+        )
+        .unwrap();
+        recorder.set_pipeline(pipeline_id);
+        recorder.set_bind_group(0, Some(empty_group_id), &[]);
+        recorder.draw(3, 1, 0, 0);
+        let bundle = recorder.finish(&context, None).unwrap();
+        state.record_execute_bundles(&[bundle]);
+        assert!(matches!(
+            state.commands.last(),
+            Some(RenderCommand::SetBindGroup { index: IMMEDIATES_FALLBACK_GROUP, offsets, .. })
+                if offsets[..] == [2 * IMMEDIATES_FALLBACK_SLOT_SIZE]
+        ));
+        state.finish_and_submit(&context, queue_id).unwrap();
+        global.render_bundle_drop(bundle);
+
+        // Each draw's dynamic offset points at the block it was recorded with
+        let readback_size = 3 * IMMEDIATES_FALLBACK_SLOT_SIZE as u64;
+        let readback = create_buffer(readback_size, wgt::BufferUsages::MAP_READ | wgt::BufferUsages::COPY_DST);
+        let (encoder_id, error) = global.device_create_command_encoder(device_id, &Default::default(), None);
+        assert!(error.is_none(), "{:?}", error);
+        global.command_encoder_copy_buffer_to_buffer(encoder_id, buffer_id, 0, readback, 0, Some(readback_size)).unwrap();
+        let (command_buffer_id, error) = global.command_encoder_finish(encoder_id, &Default::default(), None);
+        assert!(error.is_none(), "{:?}", error);
+        global.queue_submit(queue_id, &[command_buffer_id]).unwrap();
+
+        let map_op = wgpu_core::resource::BufferMapOperation { host: wgpu_core::device::HostMap::Read, callback: None };
+        global.buffer_map_async(readback, 0, Some(readback_size), map_op).unwrap();
+        global.device_poll(device_id, wgt::PollType::wait_indefinitely()).unwrap();
+        let (ptr, _) = global.buffer_get_mapped_range(readback, 0, Some(readback_size)).unwrap();
+        // SAFETY: the buffer stays mapped for `readback_size` bytes until unmapped below
+        let slots = unsafe { std::slice::from_raw_parts(ptr.as_ptr(), readback_size as usize) }.to_vec();
+        global.buffer_unmap(readback).unwrap();
+
+        let slot = |i: usize| &slots[i * IMMEDIATES_FALLBACK_SLOT_SIZE as usize..][..16];
+        assert_eq!(slot(0), [0; 16], "the block bound at pass start is zeroed");
+        assert_eq!(slot(1), tint(1.0));
+        assert_eq!(slot(2), tint(3.0));
+    }
+}
@@ -8,7 +8,6 @@ use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use parking_lot::RwLock;
 use wgpu_core::id;
-use wgpu_types;
 
 /// Buffer info stored alongside ID
 #[derive(Debug, Clone, Copy)]
@@ -24,15 +23,62 @@ pub struct TextureViewInfo {
     pub dimension: wgpu_types::TextureViewDimension,
     /// The underlying texture that this view was created from
     pub texture_id: id::TextureId,
+    /// Array layer count of the underlying texture
+    pub texture_layers: u32,
+    /// Format the view is read and rendered as
+    pub format: wgpu_types::TextureFormat,
+    /// Sample count of the underlying texture (1 = not multisampled)
+    pub sample_count: u32,
+    /// Aspect the view covers; bind groups keep it when recreating the view
+    pub aspect: wgpu_types::TextureAspect,
+    /// Size of the view's base mip, what it covers as a render attachment
+    pub width: u32,
+    pub height: u32,
 }
 
 /// Texture info stored alongside ID
 #[derive(Debug, Clone, Copy)]
 pub struct TextureInfo {
     pub id: id::TextureId,
-    pub array_layers: u32,
+    /// Size of mip level 0
+    pub width: u32,
+    pub height: u32,
+    /// Depth of a 3D texture, or the array layer count of any other
+    ///
+    /// Mirrors `Extent3d::depth_or_array_layers`; use `array_layer_count` when
+    /// only array layers matter.
+    pub depth_or_array_layers: u32,
+    /// Mip count after clamping to the texture's size
+    pub mip_level_count: u32,
     pub dimension: wgpu_types::TextureDimension,
     pub format: wgpu_types::TextureFormat,
+    /// Sample count (1 = not multisampled)
+    pub sample_count: u32,
+    /// Created as a cubemap, so full views are `Cube` rather than `D2Array`
+    pub is_cube: bool,
+}
+
+impl TextureInfo {
+    /// Width and height of `mip`, never smaller than 1x1
+    pub fn mip_size(&self, mip: u32) -> (u32, u32) {
+        ((self.width >> mip).max(1), (self.height >> mip).max(1))
+    }
+
+    /// Layers of `mip`; the depth of a 3D texture halves with each mip
+    pub fn mip_depth_or_layers(&self, mip: u32) -> u32 {
+        match self.dimension {
+            wgpu_types::TextureDimension::D3 => (self.depth_or_array_layers >> mip).max(1),
+            _ => self.depth_or_array_layers.max(1),
+        }
+    }
+
+    /// Array layers views can select; a 3D texture has one, however deep it is
+    pub fn array_layer_count(&self) -> u32 {
+        match self.dimension {
+            wgpu_types::TextureDimension::D3 => 1,
+            _ => self.depth_or_array_layers.max(1),
+        }
+    }
 }
 
 /// Type of binding expected at a slot
@@ -41,7 +87,8 @@ pub enum BindingLayoutType {
     Texture,
     Sampler,
     UniformBuffer,
-    StorageBuffer,
+    /// `var<storage, read>` or, with `read_only == false`, `var<storage, read_write>`
+    StorageBuffer { read_only: bool },
 }
 
 /// Binding layout entry for a specific slot
@@ -54,19 +101,62 @@ pub struct BindingLayoutEntry {
     /// Minimum binding size expected by the shader (for buffer bindings)
     /// None means size is determined at bind time
     pub min_binding_size: Option<u64>,
+    /// Uniform buffer whose offset is supplied at `set_bind_group` time
+    pub has_dynamic_offset: bool,
     /// Expected texture dimension (for texture bindings)
     /// The texture view must match this dimension
     pub expected_dimension: Option<wgpu_types::TextureViewDimension>,
+    /// Shadow sampling: a `texture_depth_*` texture or a `sampler_comparison` sampler
+    pub comparison: bool,
     /// Variable name in the shader (for uniform buffers)
     /// Used to map Minecraft's named uniforms to binding slots
     /// Example: "DynamicTransforms" for DynamicTransforms uniform
     pub variable_name: Option<String>,
 }
 
+/// Layout of one vertex in the per-vertex buffer in slot 0
+///
+/// The last vertex a draw reads only needs its attributes in the buffer,
+/// not the padding after them up to the next stride.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VertexStride {
+    /// Bytes between consecutive vertices
+    pub array_stride: u64,
+    /// End of the vertex's last attribute, from the start of the vertex
+    pub attributes_end: u64,
+}
+
+/// Bytes of immediates (push constants) each stage of a pipeline declares
+///
+/// wgpu has one immediate range shared by every stage, sized to the larger
+/// block; these per-stage sizes let writes aimed at one stage be checked
+/// against what that stage actually reads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImmediateRanges {
+    pub vertex: u32,
+    pub fragment: u32,
+}
+
+impl ImmediateRanges {
+    /// Bytes a write to `stages` may cover: the largest block among them
+    pub fn size_for(&self, stages: wgpu_types::ShaderStages) -> u32 {
+        let mut size = 0;
+        if stages.contains(wgpu_types::ShaderStages::VERTEX) {
+            size = size.max(self.vertex);
+        }
+        if stages.contains(wgpu_types::ShaderStages::FRAGMENT) {
+            size = size.max(self.fragment);
+        }
+        size
+    }
+}
+
 /// Depth format expectation for a pipeline
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Default)]
 pub enum PipelineDepthFormat {
     /// Pipeline has no depth state (depth attachment must be None)
+    #[default]
     None,
     /// Pipeline expects Depth32Float
     Depth32Float,
@@ -74,14 +164,40 @@ pub enum PipelineDepthFormat {
     Depth24Plus,
     /// Pipeline expects Depth24PlusStencil8
     Depth24PlusStencil8,
+    /// Pipeline expects Depth16Unorm (cheaper shadow maps, no stencil)
+    Depth16Unorm,
 }
 
-impl Default for PipelineDepthFormat {
-    fn default() -> Self {
-        PipelineDepthFormat::None
+impl PipelineDepthFormat {
+    /// Pipeline depth format matching a depth attachment format
+    pub fn from_texture_format(format: wgpu_types::TextureFormat) -> Option<Self> {
+        match format {
+            wgpu_types::TextureFormat::Depth32Float => Some(Self::Depth32Float),
+            wgpu_types::TextureFormat::Depth24Plus => Some(Self::Depth24Plus),
+            wgpu_types::TextureFormat::Depth24PlusStencil8 => Some(Self::Depth24PlusStencil8),
+            wgpu_types::TextureFormat::Depth16Unorm => Some(Self::Depth16Unorm),
+            _ => None,
+        }
+    }
+
+    /// Depth attachment format the pipeline renders with, `None` without depth state
+    pub fn texture_format(self) -> Option<wgpu_types::TextureFormat> {
+        match self {
+            Self::None => None,
+            Self::Depth32Float => Some(wgpu_types::TextureFormat::Depth32Float),
+            Self::Depth24Plus => Some(wgpu_types::TextureFormat::Depth24Plus),
+            Self::Depth24PlusStencil8 => Some(wgpu_types::TextureFormat::Depth24PlusStencil8),
+            Self::Depth16Unorm => Some(wgpu_types::TextureFormat::Depth16Unorm),
+        }
+    }
+
+    /// Whether the format has a stencil aspect
+    pub fn has_stencil(self) -> bool {
+        matches!(self, Self::Depth24PlusStencil8)
     }
 }
 
+
 /// Render pipeline info stored alongside ID
 /// Simplified to single bind group (group 0) only
 #[derive(Debug, Clone)]
@@ -97,8 +213,68 @@ pub struct RenderPipelineInfo {
     pub depth_test_enabled: bool,
     /// Shader/pipeline name for debugging
     pub shader_name: String,
+    /// MSAA sample count the pipeline was created with
+    pub sample_count: u32,
+    /// Number of vertex buffer slots declared by the vertex layout
+    pub vertex_buffer_count: u32,
+    /// Vertex layout of the per-vertex buffer in slot 0, see `VertexLayout::vertex_stride`
+    pub vertex_stride: Option<VertexStride>,
+    /// Number of color targets (attachments) the pipeline writes
+    pub color_target_count: u32,
+    /// Format of color target 0
+    pub target_format: wgpu_types::TextureFormat,
+    /// Layouts of the shader's groups after group 0, in group order
+    pub extra_groups: Vec<ExtraGroupLayout>,
+    /// Immediate block sizes of the vertex and fragment shaders
+    pub immediate_ranges: ImmediateRanges,
 }
 
+/// Where a render pipeline handle is in its creation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PipelineStatus {
+    /// Still compiling in the background
+    Pending,
+    /// Usable
+    Ready,
+    /// Background compilation failed with this error
+    Failed(String),
+    /// Not a render pipeline handle
+    Unknown,
+}
+
+/// Layout of a pipeline bind group other than group 0
+///
+/// `layout_handle` is an explicit layout handle (see `BindGroupLayoutInfo`),
+/// so bind groups for it are made with `createBindGroupExplicit0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtraGroupLayout {
+    pub group: u32,
+    pub layout_handle: u64,
+}
+
+/// Information about an explicitly created bind group layout
+#[derive(Debug, Clone)]
+pub struct BindGroupLayoutInfo {
+    pub id: id::BindGroupLayoutId,
+    /// The spec the layout was created from, used to validate bind groups
+    pub entries: Vec<BindingLayoutEntry>,
+}
+
+
+/// Live handle counts per resource type, see `ResourceHandleStore::counts`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceCounts {
+    pub buffers: usize,
+    pub textures: usize,
+    pub texture_views: usize,
+    pub samplers: usize,
+    pub bind_groups: usize,
+    pub bind_group_layouts: usize,
+    /// Includes pipelines still compiling in the background or that failed to
+    pub render_pipelines: usize,
+    pub command_encoders: usize,
+    pub render_bundles: usize,
+}
 
 /// Thread-safe handle store for wgpu resources
 pub struct ResourceHandleStore {
@@ -108,9 +284,12 @@ pub struct ResourceHandleStore {
     texture_views: RwLock<HashMap<u64, TextureViewInfo>>,
     samplers: RwLock<HashMap<u64, id::SamplerId>>,
     bind_groups: RwLock<HashMap<u64, id::BindGroupId>>,
-    bind_group_layouts: RwLock<HashMap<u64, id::BindGroupLayoutId>>,
+    bind_group_layouts: RwLock<HashMap<u64, BindGroupLayoutInfo>>,
     render_pipelines: RwLock<HashMap<u64, RenderPipelineInfo>>,
+    /// Reserved pipeline handles still compiling (None) or that failed to (Some)
+    pending_render_pipelines: RwLock<HashMap<u64, Option<String>>>,
     command_encoders: RwLock<HashMap<u64, id::CommandEncoderId>>,
+    render_bundles: RwLock<HashMap<u64, id::RenderBundleId>>,
 }
 
 impl ResourceHandleStore {
@@ -124,7 +303,9 @@ impl ResourceHandleStore {
             bind_groups: RwLock::new(HashMap::new()),
             bind_group_layouts: RwLock::new(HashMap::new()),
             render_pipelines: RwLock::new(HashMap::new()),
+            pending_render_pipelines: RwLock::new(HashMap::new()),
             command_encoders: RwLock::new(HashMap::new()),
+            render_bundles: RwLock::new(HashMap::new()),
         }
     }
 
@@ -153,17 +334,10 @@ impl ResourceHandleStore {
     }
 
     // Texture operations
-    pub fn insert_texture(
-        &self,
-        texture_id: id::TextureId,
-        array_layers: u32,
-        dimension: wgpu_types::TextureDimension,
-        format: wgpu_types::TextureFormat,
-    ) -> u64 {
+    pub fn insert_texture(&self, info: TextureInfo) -> u64 {
         let handle = self.next();
-        let info = TextureInfo { id: texture_id, array_layers, dimension, format };
         self.textures.write().insert(handle, info);
-        log::info!("Stored texture handle {} with format {:?}", handle, format);
+        log::info!("Stored texture handle {} with format {:?}", handle, info.format);
         handle
     }
 
@@ -175,19 +349,20 @@ impl ResourceHandleStore {
         self.textures.read().get(&handle).copied()
     }
 
+    /// Info of the texture with `texture_id`, for callers that only have the wgpu id
+    ///
+    /// Scans every texture, so look up once and keep the result.
+    pub fn find_texture_info(&self, texture_id: id::TextureId) -> Option<TextureInfo> {
+        self.textures.read().values().find(|info| info.id == texture_id).copied()
+    }
+
     pub fn remove_texture(&self, handle: u64) -> Option<id::TextureId> {
         self.textures.write().remove(&handle).map(|info| info.id)
     }
 
     // Texture view operations
-    pub fn insert_texture_view(
-        &self,
-        view_id: id::TextureViewId,
-        dimension: wgpu_types::TextureViewDimension,
-        texture_id: id::TextureId,
-    ) -> u64 {
+    pub fn insert_texture_view(&self, info: TextureViewInfo) -> u64 {
         let handle = self.next();
-        let info = TextureViewInfo { id: view_id, dimension, texture_id };
         self.texture_views.write().insert(handle, info);
         handle
     }
@@ -235,41 +410,27 @@ impl ResourceHandleStore {
     }
 
     // Bind group layout operations
-    pub fn insert_bind_group_layout(&self, layout_id: id::BindGroupLayoutId) -> u64 {
+    pub fn insert_bind_group_layout(&self, layout_id: id::BindGroupLayoutId, entries: Vec<BindingLayoutEntry>) -> u64 {
         let handle = self.next();
-        self.bind_group_layouts.write().insert(handle, layout_id);
+        self.bind_group_layouts.write().insert(handle, BindGroupLayoutInfo { id: layout_id, entries });
         handle
     }
 
     pub fn get_bind_group_layout(&self, handle: u64) -> Option<id::BindGroupLayoutId> {
-        self.bind_group_layouts.read().get(&handle).copied()
+        self.bind_group_layouts.read().get(&handle).map(|info| info.id)
+    }
+
+    pub fn get_bind_group_layout_info(&self, handle: u64) -> Option<BindGroupLayoutInfo> {
+        self.bind_group_layouts.read().get(&handle).cloned()
     }
 
     pub fn remove_bind_group_layout(&self, handle: u64) -> Option<id::BindGroupLayoutId> {
-        self.bind_group_layouts.write().remove(&handle)
+        self.bind_group_layouts.write().remove(&handle).map(|info| info.id)
     }
 
     // Render pipeline operations
-    pub fn insert_render_pipeline(
-        &self,
-        pipeline_id: id::RenderPipelineId,
-        bind_group_layout_id: id::BindGroupLayoutId,
-        binding_layouts: Vec<BindingLayoutEntry>,
-        depth_format: PipelineDepthFormat,
-        depth_write_enabled: bool,
-        depth_test_enabled: bool,
-        shader_name: String,
-    ) -> u64 {
+    pub fn insert_render_pipeline(&self, info: RenderPipelineInfo) -> u64 {
         let handle = self.next();
-        let info = RenderPipelineInfo {
-            id: pipeline_id,
-            bind_group_layout_id,
-            binding_layouts,
-            depth_format,
-            depth_write_enabled,
-            depth_test_enabled,
-            shader_name,
-        };
         self.render_pipelines.write().insert(handle, info);
         handle
     }
@@ -283,9 +444,54 @@ impl ResourceHandleStore {
     }
 
     pub fn remove_render_pipeline(&self, handle: u64) -> Option<id::RenderPipelineId> {
+        self.pending_render_pipelines.write().remove(&handle);
         self.render_pipelines.write().remove(&handle).map(|info| info.id)
     }
 
+    /// Reserve a handle for a pipeline that is compiled in the background
+    ///
+    /// The handle is pending until `complete_render_pipeline` or
+    /// `fail_render_pipeline` is called for it.
+    pub fn reserve_render_pipeline(&self) -> u64 {
+        let handle = self.next();
+        self.pending_render_pipelines.write().insert(handle, None);
+        handle
+    }
+
+    /// Make a reserved pipeline handle usable
+    ///
+    /// Does nothing if the handle was removed while it was pending.
+    pub fn complete_render_pipeline(&self, handle: u64, info: RenderPipelineInfo) {
+        let mut pending = self.pending_render_pipelines.write();
+        if pending.contains_key(&handle) {
+            // Inserted before the pending entry goes, so the handle is never
+            // neither pending nor ready
+            self.render_pipelines.write().insert(handle, info);
+            pending.remove(&handle);
+        }
+    }
+
+    /// Record that a reserved pipeline handle won't become usable
+    pub fn fail_render_pipeline(&self, handle: u64, error: String) {
+        if let Some(state) = self.pending_render_pipelines.write().get_mut(&handle) {
+            *state = Some(error);
+        }
+    }
+
+    pub fn render_pipeline_status(&self, handle: u64) -> PipelineStatus {
+        // Held across both lookups, in the order `complete_render_pipeline`
+        // takes them, so a completion can't slip in between
+        let pending = self.pending_render_pipelines.read();
+        if self.render_pipelines.read().contains_key(&handle) {
+            return PipelineStatus::Ready;
+        }
+        match pending.get(&handle) {
+            Some(None) => PipelineStatus::Pending,
+            Some(Some(error)) => PipelineStatus::Failed(error.clone()),
+            None => PipelineStatus::Unknown,
+        }
+    }
+
     // Command encoder operations
     pub fn insert_command_encoder(&self, encoder_id: id::CommandEncoderId) -> u64 {
         let handle = self.next();
@@ -300,6 +506,41 @@ impl ResourceHandleStore {
     pub fn remove_command_encoder(&self, handle: u64) -> Option<id::CommandEncoderId> {
         self.command_encoders.write().remove(&handle)
     }
+
+    // Render bundle operations
+    pub fn insert_render_bundle(&self, bundle_id: id::RenderBundleId) -> u64 {
+        let handle = self.next();
+        self.render_bundles.write().insert(handle, bundle_id);
+        handle
+    }
+
+    pub fn get_render_bundle(&self, handle: u64) -> Option<id::RenderBundleId> {
+        self.render_bundles.read().get(&handle).copied()
+    }
+
+    pub fn remove_render_bundle(&self, handle: u64) -> Option<id::RenderBundleId> {
+        self.render_bundles.write().remove(&handle)
+    }
+
+    /// Number of live handles of each type
+    ///
+    /// Handles are only released by the matching `remove_*`, so a count that
+    /// doesn't return to its baseline after destroying what was created is a leak.
+    pub fn counts(&self) -> ResourceCounts {
+        let pending = self.pending_render_pipelines.read();
+        let ready = self.render_pipelines.read();
+        ResourceCounts {
+            buffers: self.buffers.read().len(),
+            textures: self.textures.read().len(),
+            texture_views: self.texture_views.read().len(),
+            samplers: self.samplers.read().len(),
+            bind_groups: self.bind_groups.read().len(),
+            bind_group_layouts: self.bind_group_layouts.read().len(),
+            render_pipelines: ready.len() + pending.keys().filter(|h| !ready.contains_key(h)).count(),
+            command_encoders: self.command_encoders.read().len(),
+            render_bundles: self.render_bundles.read().len(),
+        }
+    }
 }
 
 impl Default for ResourceHandleStore {
@@ -312,3 +553,106 @@ impl Default for ResourceHandleStore {
 lazy_static::lazy_static! {
     pub static ref HANDLES: ResourceHandleStore = ResourceHandleStore::new();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::BasaltContext;
+    use std::borrow::Cow;
+    use wgpu_types as wgt;
+
+    #[test]
+    fn test_counts_return_to_baseline_after_removal() {
+        let context = BasaltContext::new_noop();
+        let global = context.inner();
+        let adapter_id = global
+            .request_adapter(&wgpu_core::instance::RequestAdapterOptions::default(), wgt::Backends::NOOP, None)
+            .unwrap();
+        let (device_id, _queue_id) = global.adapter_request_device(adapter_id, &Default::default(), None, None).unwrap();
+
+        // A private store so handles created by other tests don't show up
+        let store = ResourceHandleStore::new();
+        let baseline = store.counts();
+        assert_eq!(baseline, ResourceCounts::default());
+
+        let desc = wgt::BufferDescriptor {
+            label: Some(Cow::Borrowed("counted")),
+            size: 256,
+            usage: wgt::BufferUsages::UNIFORM,
+            mapped_at_creation: false,
+        };
+        let buffers: Vec<u64> = (0..3)
+            .map(|_| {
+                let (buffer_id, error) = global.device_create_buffer(device_id, &desc, None);
+                assert!(error.is_none());
+                store.insert_buffer(buffer_id, desc.size)
+            })
+            .collect();
+        let (encoder_id, error) =
+            global.device_create_command_encoder(device_id, &wgt::CommandEncoderDescriptor { label: None }, None);
+        assert!(error.is_none());
+        let encoder = store.insert_command_encoder(encoder_id);
+        let pipeline = store.reserve_render_pipeline();
+
+        let counts = store.counts();
+        assert_eq!(counts.buffers, 3);
+        assert_eq!(counts.command_encoders, 1);
+        assert_eq!(counts.render_pipelines, 1, "pending pipelines are live handles");
+
+        for handle in buffers {
+            global.buffer_drop(store.remove_buffer(handle).unwrap());
+        }
+        global.command_encoder_drop(store.remove_command_encoder(encoder).unwrap());
+        store.remove_render_pipeline(pipeline);
+        assert_eq!(store.counts(), baseline);
+    }
+
+    #[test]
+    fn test_texture_info_keeps_dimension() {
+        let context = BasaltContext::new_noop();
+        let (device_id, _queue_id) = context.create_noop_device();
+        let global = context.inner();
+        let texture = |dimension, depth_or_array_layers| {
+            let desc = wgt::TextureDescriptor {
+                label: None,
+                size: wgt::Extent3d { width: 4, height: 4, depth_or_array_layers },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension,
+                format: wgt::TextureFormat::Rgba8Unorm,
+                usage: wgt::TextureUsages::TEXTURE_BINDING,
+                view_formats: vec![],
+            };
+            let (texture_id, error) = global.device_create_texture(device_id, &desc, None);
+            assert!(error.is_none(), "{:?}", error);
+            texture_id
+        };
+        let (d3, d2) = (wgt::TextureDimension::D3, wgt::TextureDimension::D2);
+        let (volume_id, array_id) = (texture(d3, 16), texture(d2, 6));
+
+        let store = ResourceHandleStore::new();
+        let info = |id, dimension, depth_or_array_layers| TextureInfo {
+            id,
+            width: 4,
+            height: 4,
+            depth_or_array_layers,
+            mip_level_count: 1,
+            dimension,
+            format: wgt::TextureFormat::Rgba8Unorm,
+            sample_count: 1,
+            is_cube: false,
+        };
+        let volume = store.insert_texture(info(volume_id, d3, 16));
+        let array = store.insert_texture(info(array_id, d2, 6));
+
+        // Depth isn't mistaken for array layers, or the other way round
+        let volume = store.get_texture_info(volume).unwrap();
+        assert_eq!(volume.dimension, d3);
+        assert_eq!((volume.depth_or_array_layers, volume.array_layer_count()), (16, 1));
+        let array = store.get_texture_info(array).unwrap();
+        assert_eq!(array.dimension, d2);
+        assert_eq!((array.depth_or_array_layers, array.array_layer_count()), (6, 6));
+
+        assert_eq!(store.find_texture_info(volume_id).map(|info| info.dimension), Some(d3));
+    }
+}
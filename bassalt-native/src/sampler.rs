@@ -1,7 +1,20 @@
 //! Sampler management
+//!
+//! Minecraft asks for the same handful of linear/nearest samplers thousands
+//! of times, so samplers are deduplicated by descriptor in `SamplerCache`.
+//! The common filtering setups in `SamplerConfig::presets` are created up
+//! front, so swapping filtering between draws never creates a sampler.
 
+use std::borrow::Cow;
+use std::collections::HashMap;
+use parking_lot::Mutex;
+use wgpu_core::id;
 use wgpu_types as wgt;
 
+use crate::context::BasaltContext;
+use crate::error::{BasaltError, Result};
+use crate::resource_handles::HANDLES;
+
 /// Sampler descriptor for creating samplers
 #[derive(Debug, Clone)]
 pub struct SamplerDescriptor {
@@ -37,3 +50,355 @@ impl Default for SamplerDescriptor {
         }
     }
 }
+
+impl SamplerDescriptor {
+    /// Comparison sampler for percentage-closer filtered (PCF) shadow maps
+    ///
+    /// With linear filtering the hardware compares the four nearest depth
+    /// texels against the reference and blends the results, which softens
+    /// shadow edges. Clamping keeps lookups outside the map from wrapping.
+    pub fn shadow() -> Self {
+        Self {
+            label: Some("Shadow Sampler".to_string()),
+            mipmap_filter: wgt::MipmapFilterMode::Nearest,
+            compare: Some(wgt::CompareFunction::LessEqual),
+            ..Default::default()
+        }
+    }
+
+    /// Bilinear clamped sampler for the present blit
+    ///
+    /// The blit reads a single-mip view, so LOD is pinned to it.
+    pub fn blit() -> Self {
+        Self {
+            label: Some("Blit Sampler".to_string()),
+            mipmap_filter: wgt::MipmapFilterMode::Nearest,
+            lod_max_clamp: 0.0,
+            ..Default::default()
+        }
+    }
+
+    /// Fit `anisotropy_clamp` to a device supporting up to `max_anisotropy`
+    ///
+    /// The clamp ends up in 1..=`max_anisotropy`. WebGPU only allows
+    /// anisotropy with linear min, mag and mipmap filtering, so when any
+    /// remains the filters are made linear rather than failing validation.
+    pub fn with_anisotropy_limit(mut self, max_anisotropy: u16) -> Self {
+        self.anisotropy_clamp = self.anisotropy_clamp.clamp(1, max_anisotropy.max(1));
+        if self.anisotropy_clamp > 1 {
+            self.mag_filter = wgt::FilterMode::Linear;
+            self.min_filter = wgt::FilterMode::Linear;
+            self.mipmap_filter = wgt::MipmapFilterMode::Linear;
+        }
+        self
+    }
+
+    /// Cache key covering every field that affects the created sampler
+    pub fn key(&self) -> SamplerKey {
+        SamplerKey {
+            address_modes: [self.address_mode_u, self.address_mode_v, self.address_mode_w],
+            mag_filter: self.mag_filter,
+            min_filter: self.min_filter,
+            mipmap_filter: self.mipmap_filter,
+            lod_min_clamp: self.lod_min_clamp.to_bits(),
+            lod_max_clamp: self.lod_max_clamp.to_bits(),
+            compare: self.compare,
+            anisotropy_clamp: self.anisotropy_clamp,
+            border_color: self.border_color,
+        }
+    }
+
+    /// Convert to the wgpu-core sampler descriptor
+    pub fn to_wgpu(&self) -> wgpu_core::resource::SamplerDescriptor<'_> {
+        wgpu_core::resource::SamplerDescriptor {
+            label: self.label.as_deref().map(Cow::Borrowed),
+            address_modes: [self.address_mode_u, self.address_mode_v, self.address_mode_w],
+            mag_filter: self.mag_filter,
+            min_filter: self.min_filter,
+            mipmap_filter: self.mipmap_filter,
+            lod_min_clamp: self.lod_min_clamp,
+            lod_max_clamp: self.lod_max_clamp,
+            compare: self.compare,
+            anisotropy_clamp: self.anisotropy_clamp,
+            border_color: self.border_color,
+        }
+    }
+}
+
+/// Filtering setup for `SamplerCache::get_config`, e.g. to drop anisotropy
+/// for UI drawn over the world
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SamplerConfig {
+    /// Used for all three axes
+    pub address_mode: wgt::AddressMode,
+    /// Linear instead of nearest min/mag filtering
+    pub linear: bool,
+    /// Linear instead of nearest filtering between mip levels
+    pub linear_mipmaps: bool,
+    /// Maximum anisotropy, 1 to 16; above 1 needs both linear flags
+    pub anisotropy: u16,
+}
+
+impl SamplerConfig {
+    /// Setups created with the cache: nearest, bilinear, trilinear and
+    /// anisotropic trilinear at 2x to 16x, each repeating and clamped
+    pub fn presets() -> impl Iterator<Item = SamplerConfig> {
+        [wgt::AddressMode::Repeat, wgt::AddressMode::ClampToEdge].into_iter().flat_map(|mode| {
+            let bilinear = Self { linear: true, ..Self::nearest(mode) };
+            [Self::nearest(mode), bilinear]
+                .into_iter()
+                .chain([1, 2, 4, 8, 16].map(|anisotropy| Self::trilinear(mode, anisotropy)))
+        })
+    }
+
+    /// Nearest filtering without anisotropy
+    pub const fn nearest(address_mode: wgt::AddressMode) -> Self {
+        Self { address_mode, linear: false, linear_mipmaps: false, anisotropy: 1 }
+    }
+
+    /// Linear filtering within and between mips, with up to `anisotropy` samples
+    pub const fn trilinear(address_mode: wgt::AddressMode, anisotropy: u16) -> Self {
+        Self { address_mode, linear: true, linear_mipmaps: true, anisotropy }
+    }
+
+    /// Sampler descriptor for this setup
+    ///
+    /// Fails for anisotropy outside 1..=16 or with nearest filtering, which
+    /// WebGPU rejects.
+    pub fn descriptor(&self) -> Result<SamplerDescriptor> {
+        if !(1..=16).contains(&self.anisotropy) {
+            return Err(BasaltError::invalid_parameter(
+                "anisotropy",
+                format!("anisotropy {} is outside 1..=16", self.anisotropy),
+            ));
+        }
+        if self.anisotropy > 1 && !(self.linear && self.linear_mipmaps) {
+            return Err(BasaltError::invalid_parameter(
+                "anisotropy",
+                format!("anisotropy {} requires linear and linear mipmap filtering", self.anisotropy),
+            ));
+        }
+
+        let filter = if self.linear { wgt::FilterMode::Linear } else { wgt::FilterMode::Nearest };
+        Ok(SamplerDescriptor {
+            label: Some("Basalt Sampler".to_string()),
+            address_mode_u: self.address_mode,
+            address_mode_v: self.address_mode,
+            address_mode_w: self.address_mode,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: if self.linear_mipmaps {
+                wgt::MipmapFilterMode::Linear
+            } else {
+                wgt::MipmapFilterMode::Nearest
+            },
+            anisotropy_clamp: self.anisotropy,
+            ..Default::default()
+        })
+    }
+}
+
+/// Cache key for a sampler (the descriptor without its label)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SamplerKey {
+    pub address_modes: [wgt::AddressMode; 3],
+    pub mag_filter: wgt::FilterMode,
+    pub min_filter: wgt::FilterMode,
+    pub mipmap_filter: wgt::MipmapFilterMode,
+    /// Stored as bits for hashing
+    pub lod_min_clamp: u32,
+    /// Stored as bits for hashing
+    pub lod_max_clamp: u32,
+    pub compare: Option<wgt::CompareFunction>,
+    pub anisotropy_clamp: u16,
+    pub border_color: Option<wgt::SamplerBorderColor>,
+}
+
+/// A cached sampler and the handle it was registered under
+#[derive(Debug, Clone, Copy)]
+pub struct CachedSampler {
+    pub sampler_id: id::SamplerId,
+    pub handle: u64,
+}
+
+/// Sampler cache statistics
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SamplerCacheStats {
+    /// Number of requests served from the cache
+    pub hits: usize,
+    /// Number of requests that created a new sampler
+    pub misses: usize,
+    /// Total samplers cached
+    pub total_samplers: usize,
+}
+
+/// Cache of samplers keyed by descriptor
+///
+/// Samplers are never destroyed individually, so a cached sampler and its
+/// handle stay valid for the lifetime of the device.
+pub struct SamplerCache {
+    samplers: Mutex<HashMap<SamplerKey, CachedSampler>>,
+    stats: Mutex<SamplerCacheStats>,
+}
+
+impl SamplerCache {
+    /// Create an empty sampler cache
+    pub fn new() -> Self {
+        Self {
+            samplers: Mutex::new(HashMap::new()),
+            stats: Mutex::new(SamplerCacheStats::default()),
+        }
+    }
+
+    /// Get the sampler matching `desc`, creating and registering it on a miss
+    pub fn get_or_create(
+        &self,
+        context: &BasaltContext,
+        device_id: id::DeviceId,
+        desc: &SamplerDescriptor,
+    ) -> Result<CachedSampler> {
+        let key = desc.key();
+        // Held across creation so concurrent requests can't create duplicates
+        let mut samplers = self.samplers.lock();
+        if let Some(cached) = samplers.get(&key) {
+            self.stats.lock().hits += 1;
+            return Ok(*cached);
+        }
+
+        let (sampler_id, error) = context
+            .inner()
+            .device_create_sampler(device_id, &desc.to_wgpu(), None);
+
+        if let Some(e) = error {
+            return Err(BasaltError::Wgpu(format!("{:?}", e)));
+        }
+
+        let cached = CachedSampler { sampler_id, handle: HANDLES.insert_sampler(sampler_id) };
+        samplers.insert(key, cached);
+
+        let mut stats = self.stats.lock();
+        stats.misses += 1;
+        stats.total_samplers = samplers.len();
+        log::debug!("Sampler cache MISS: created {:?} (handle {}), {} cached", sampler_id, cached.handle, samplers.len());
+        Ok(cached)
+    }
+
+    /// Get the sampler for a filtering setup, see `SamplerConfig`
+    pub fn get_config(
+        &self,
+        context: &BasaltContext,
+        device_id: id::DeviceId,
+        config: &SamplerConfig,
+    ) -> Result<CachedSampler> {
+        self.get_or_create(context, device_id, &config.descriptor()?)
+    }
+
+    /// Create the samplers in `SamplerConfig::presets`
+    pub fn prewarm(&self, context: &BasaltContext, device_id: id::DeviceId) -> Result<()> {
+        for config in SamplerConfig::presets() {
+            self.get_config(context, device_id, &config)?;
+        }
+        Ok(())
+    }
+
+    /// Get cache statistics
+    pub fn stats(&self) -> SamplerCacheStats {
+        *self.stats.lock()
+    }
+}
+
+impl Default for SamplerCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sampler_cache_deduplicates() {
+        let context = BasaltContext::new_noop();
+        let (device_id, _queue_id) = context.create_noop_device();
+        let cache = SamplerCache::new();
+
+        let linear = SamplerDescriptor::default();
+        let first = cache.get_or_create(&context, device_id, &linear).unwrap();
+        // Labels don't affect the sampler, so they don't split the cache
+        let relabeled = SamplerDescriptor { label: Some("Other".to_string()), ..linear.clone() };
+        let second = cache.get_or_create(&context, device_id, &relabeled).unwrap();
+        assert_eq!(first.sampler_id, second.sampler_id);
+        assert_eq!(first.handle, second.handle);
+
+        let nearest = SamplerDescriptor {
+            mag_filter: wgt::FilterMode::Nearest,
+            min_filter: wgt::FilterMode::Nearest,
+            ..linear.clone()
+        };
+        let third = cache.get_or_create(&context, device_id, &nearest).unwrap();
+        assert_ne!(first.sampler_id, third.sampler_id);
+        let lod = SamplerDescriptor { lod_max_clamp: 4.0, ..linear };
+        cache.get_or_create(&context, device_id, &lod).unwrap();
+        cache.get_or_create(&context, device_id, &SamplerDescriptor::shadow()).unwrap();
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.total_samplers), (1, 4, 4));
+        assert_eq!(HANDLES.get_sampler(first.handle), Some(first.sampler_id));
+    }
+
+    #[test]
+    fn test_sampler_configs_reuse_prewarmed_samplers() {
+        let context = BasaltContext::new_noop();
+        let (device_id, _queue_id) = context.create_noop_device();
+        let cache = SamplerCache::new();
+        cache.prewarm(&context, device_id).unwrap();
+        assert_eq!(cache.stats().misses, SamplerConfig::presets().count());
+
+        // Swapping between preset setups only hits the cache
+        let world = SamplerConfig::trilinear(wgt::AddressMode::Repeat, 16);
+        let ui = SamplerConfig::nearest(wgt::AddressMode::ClampToEdge);
+        let first = cache.get_config(&context, device_id, &world).unwrap();
+        cache.get_config(&context, device_id, &ui).unwrap();
+        let again = cache.get_config(&context, device_id, &world).unwrap();
+        assert_eq!(first.handle, again.handle);
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (3, SamplerConfig::presets().count()));
+
+        // The same setup through a full descriptor is the same sampler
+        let via_descriptor = cache.get_or_create(&context, device_id, &world.descriptor().unwrap()).unwrap();
+        assert_eq!(via_descriptor.handle, first.handle);
+
+        let nearest_anisotropic = SamplerConfig { anisotropy: 4, ..ui };
+        assert!(cache.get_config(&context, device_id, &nearest_anisotropic).is_err());
+        assert!(SamplerConfig { anisotropy: 0, ..world }.descriptor().is_err());
+    }
+
+    #[test]
+    fn test_anisotropy_limit() {
+        let nearest = SamplerDescriptor {
+            mag_filter: wgt::FilterMode::Nearest,
+            min_filter: wgt::FilterMode::Nearest,
+            mipmap_filter: wgt::MipmapFilterMode::Nearest,
+            ..Default::default()
+        };
+
+        // Anisotropy the device supports makes every filter linear
+        let anisotropic = SamplerDescriptor { anisotropy_clamp: 64, ..nearest.clone() }.with_anisotropy_limit(16);
+        assert_eq!(anisotropic.anisotropy_clamp, 16);
+        assert_eq!((anisotropic.mag_filter, anisotropic.min_filter), (wgt::FilterMode::Linear, wgt::FilterMode::Linear));
+        assert_eq!(anisotropic.mipmap_filter, wgt::MipmapFilterMode::Linear);
+
+        // Without device support, or without a request, the filters stay as asked
+        for (requested, max) in [(8, 1), (0, 16), (1, 16)] {
+            let fitted = SamplerDescriptor { anisotropy_clamp: requested, ..nearest.clone() }.with_anisotropy_limit(max);
+            assert_eq!(fitted.anisotropy_clamp, 1);
+            assert_eq!(fitted.key(), nearest.key());
+        }
+
+        let context = BasaltContext::new_noop();
+        let (device_id, _queue_id) = context.create_noop_device();
+        let cache = SamplerCache::new();
+        assert!(cache.get_or_create(&context, device_id, &anisotropic).is_ok());
+    }
+}
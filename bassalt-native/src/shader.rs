@@ -1,16 +1,133 @@
 //! Shader compilation and translation using naga
 
 use naga::{ShaderStage, Module, front, back, valid};
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::LazyLock;
 use crate::error::{BasaltError, Result, CompilationInfo};
+use crate::pipeline_registry::PipelineCache;
+
+/// Translations shared by every `glsl_to_wgsl` call
+static TRANSLATION_CACHE: LazyLock<TranslationCache> = LazyLock::new(TranslationCache::new);
+
+/// GLSL to WGSL translations keyed on a hash of the source, defines and stage
+///
+/// Only successful translations are kept. The source is stored next to the
+/// WGSL so a hash collision translates again instead of returning another
+/// shader's output.
+pub struct TranslationCache {
+    entries: Mutex<HashMap<(u64, ShaderStage), (String, String)>>,
+    hits: AtomicU64,
+}
+
+impl TranslationCache {
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+        }
+    }
+
+    /// Return the cached translation, translating and storing it on a miss
+    pub fn translate(&self, glsl_source: &str, stage: ShaderStage) -> Result<String> {
+        self.translate_with_defines(glsl_source, stage, &[])
+    }
+
+    /// Like `translate`, with `defines` visible to the GLSL preprocessor
+    ///
+    /// Each define set is its own cache entry, so every variant of a shader
+    /// is translated once.
+    pub fn translate_with_defines(
+        &self,
+        glsl_source: &str,
+        stage: ShaderStage,
+        defines: &[(String, String)],
+    ) -> Result<String> {
+        let variant = variant_key(glsl_source, defines);
+        let key = (PipelineCache::hash_wgsl(&variant), stage);
+        if let Some((cached_variant, wgsl)) = self.entries.lock().get(&key) {
+            if *cached_variant == variant {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(wgsl.clone());
+            }
+        }
+
+        // Translated without the lock so other shaders aren't held up
+        let wgsl = translate_glsl(glsl_source, stage, defines)?;
+        self.entries.lock().insert(key, (variant, wgsl.clone()));
+        Ok(wgsl)
+    }
+
+    /// Number of translations served from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().clear();
+    }
+}
+
+impl Default for TranslationCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Drop every cached translation, e.g. when resource packs are reloaded
+pub fn clear_translation_cache() {
+    let count = TRANSLATION_CACHE.len();
+    TRANSLATION_CACHE.clear();
+    log::info!("Cleared {} cached GLSL translations", count);
+}
 
 /// Translate GLSL to WGSL
+///
+/// Repeated translations of the same source and stage come from the cache.
 pub fn glsl_to_wgsl(glsl_source: &str, stage: ShaderStage) -> Result<String> {
+    TRANSLATION_CACHE.translate(glsl_source, stage)
+}
+
+/// Translate GLSL to WGSL with preprocessor defines, e.g. `("USE_FOG", "1")`
+///
+/// Selects the shader variant for `#ifdef` / `#if` blocks without editing
+/// the source. An empty value is enough for `#ifdef`.
+pub fn glsl_to_wgsl_with_defines(
+    glsl_source: &str,
+    stage: ShaderStage,
+    defines: &[(String, String)],
+) -> Result<String> {
+    TRANSLATION_CACHE.translate_with_defines(glsl_source, stage, defines)
+}
+
+/// Source plus defines in a stable order, identifying one shader variant
+fn variant_key(glsl_source: &str, defines: &[(String, String)]) -> String {
+    let mut sorted: Vec<_> = defines.iter().collect();
+    sorted.sort();
+    let mut key = glsl_source.to_string();
+    for (name, value) in sorted {
+        key.push_str(&format!("\0{}={}", name, value));
+    }
+    key
+}
+
+fn translate_glsl(glsl_source: &str, stage: ShaderStage, defines: &[(String, String)]) -> Result<String> {
     // Parse GLSL with the new naga 27 API
     let mut frontend = front::glsl::Frontend::default();
 
     let glsl_options = front::glsl::Options {
         stage,
-        defines: Default::default(), // Uses naga's internal FastHashMap
+        defines: defines.iter().cloned().collect(),
     };
 
     let module = frontend
@@ -122,7 +239,11 @@ pub fn parse_wgsl(wgsl_source: &str) -> Result<Module> {
 
 /// Compile WGSL with a shader name for better error logging
 pub fn parse_wgsl_named(wgsl_source: &str, shader_name: &str) -> Result<Module> {
-    front::wgsl::parse_str(&wgsl_source).map_err(|e| {
+    front::wgsl::parse_str(wgsl_source).map_err(|e| {
+        // Keep the short message and location; the full report goes to the log
+        let message = e.message().to_string();
+        let location = e.location(wgsl_source);
+
         // Extract line/column info from naga's ShaderError
         let compilation_info: CompilationInfo = naga::error::ShaderError {
             source: wgsl_source.to_string(),
@@ -133,31 +254,102 @@ pub fn parse_wgsl_named(wgsl_source: &str, shader_name: &str) -> Result<Module>
         // Log the compilation info with all messages
         log_compilation_info(shader_name, &compilation_info);
 
-        // Format the error with location info
-        if let Some(msg) = compilation_info.messages.first() {
-            if let Some(loc) = &msg.location {
-                BasaltError::ShaderParse {
-                    error: msg.message.clone(),
-                    line: Some(loc.line_number as usize),
-                    column: Some(loc.line_position as usize),
-                }
-            } else {
-                BasaltError::ShaderParse {
-                    error: msg.message.clone(),
-                    line: None,
-                    column: None,
-                }
-            }
-        } else {
-            BasaltError::ShaderParse {
-                error: "Unknown parse error".to_string(),
-                line: None,
-                column: None,
-            }
+        // Format the error with location info and the offending source line
+        let line = location.map(|loc| loc.line_number as usize);
+        let column = location.map(|loc| loc.line_position as usize);
+        let source_line = line
+            .and_then(|line| wgsl_source.lines().nth(line.saturating_sub(1)))
+            .map(|s| s.to_string());
+
+        BasaltError::ShaderParse {
+            shader_name: shader_name.to_string(),
+            error: message,
+            line,
+            column,
+            source_line,
         }
     })
 }
 
+/// Parse and validate a SPIR-V binary into a naga Module
+///
+/// The input must be a whole number of 32-bit words; anything else is
+/// rejected up front so truncated files get a clear error instead of an
+/// opaque parser failure.
+pub fn parse_spirv_named(spirv: &[u8], shader_name: &str) -> Result<Module> {
+    parse_and_validate_spirv(spirv, shader_name).map(|(module, _)| module)
+}
+
+fn parse_and_validate_spirv(spirv: &[u8], shader_name: &str) -> Result<(Module, valid::ModuleInfo)> {
+    if !spirv.len().is_multiple_of(4) {
+        return Err(BasaltError::ShaderCompilation {
+            shader_name: shader_name.to_string(),
+            error: format!(
+                "SPIR-V binary length {} is not a multiple of 4 bytes (truncated or not SPIR-V?)",
+                spirv.len()
+            ),
+            stage: "spirv".to_string(),
+            source: None,
+        });
+    }
+
+    let module = front::spv::parse_u8_slice(spirv, &front::spv::Options::default())
+        .map_err(|e| {
+            let compilation_info: CompilationInfo = naga::error::ShaderError {
+                source: String::new(), // Binary input has no text to point into
+                label: Some(shader_name.to_string()),
+                inner: Box::new(e),
+            }.into();
+
+            log_compilation_info(shader_name, &compilation_info);
+
+            BasaltError::ShaderCompilation {
+                shader_name: shader_name.to_string(),
+                error: compilation_info.to_string(),
+                stage: "spirv".to_string(),
+                source: None,
+            }
+        })?;
+
+    // Validate the module before handing it to anything else
+    let mut validator = valid::Validator::new(
+        valid::ValidationFlags::all(),
+        valid::Capabilities::all(),
+    );
+
+    let module_info = validator
+        .validate(&module)
+        .map_err(|e| {
+            let compilation_info: CompilationInfo = naga::error::ShaderError {
+                source: String::new(),
+                label: Some(shader_name.to_string()),
+                inner: Box::new(e),
+            }.into();
+
+            BasaltError::ShaderValidation {
+                shader_name: shader_name.to_string(),
+                error: compilation_info.to_string(),
+            }
+        })?;
+
+    Ok((module, module_info))
+}
+
+/// Translate a SPIR-V binary to WGSL
+///
+/// Lets pre-compiled SPIR-V reuse the WGSL pipeline path, including layout
+/// reflection and the pipeline cache.
+pub fn spirv_to_wgsl(spirv: &[u8], shader_name: &str) -> Result<String> {
+    let (module, module_info) = parse_and_validate_spirv(spirv, shader_name)?;
+
+    back::wgsl::write_string(&module, &module_info, back::wgsl::WriterFlags::empty())
+        .map_err(|e| BasaltError::shader_compilation(
+            shader_name,
+            format!("WGSL generation error: {}", e),
+            "wgsl_write",
+        ))
+}
+
 /// Log compilation info to the logger
 fn log_compilation_info(shader_name: &str, info: &CompilationInfo) {
     if info.messages.is_empty() {
@@ -186,7 +378,7 @@ fn log_compilation_info(shader_name: &str, info: &CompilationInfo) {
 /// This is useful for getting detailed error messages with line/column information
 /// for debugging shader compilation issues.
 pub fn get_wgsl_compilation_info(wgsl_source: &str) -> CompilationInfo {
-    match front::wgsl::parse_str(&wgsl_source) {
+    match front::wgsl::parse_str(wgsl_source) {
         Ok(_) => CompilationInfo::new(),
         Err(e) => {
             naga::error::ShaderError {
@@ -198,6 +390,114 @@ pub fn get_wgsl_compilation_info(wgsl_source: &str) -> CompilationInfo {
     }
 }
 
+/// Translate GLSL to WGSL after inlining its `#moj_import` directives
+///
+/// See `resolve_moj_imports` for how imports are looked up.
+pub fn glsl_to_wgsl_with_includes(
+    glsl_source: &str,
+    stage: ShaderStage,
+    include_paths: &[PathBuf],
+    defines: &[(String, String)],
+) -> Result<String> {
+    let source = resolve_moj_imports(glsl_source, include_paths)?;
+    glsl_to_wgsl_with_defines(&source, stage, defines)
+}
+
+/// Recursively inline `#moj_import <name>` and `#moj_import "name"` directives
+///
+/// `<name>` is searched in each include path in order; a `namespace:path`
+/// name is tried as `namespace/path` and then as plain `path`. `"name"` is
+/// looked up next to the importing file first, then in the include paths.
+/// Each file is inlined once, so repeated imports and cycles are dropped.
+pub fn resolve_moj_imports(source: &str, include_paths: &[PathBuf]) -> Result<String> {
+    let mut included = HashSet::new();
+    let mut output = String::with_capacity(source.len());
+    inline_moj_imports(source, None, include_paths, &mut included, &mut output)?;
+    Ok(output)
+}
+
+fn inline_moj_imports(
+    source: &str,
+    current_dir: Option<&Path>,
+    include_paths: &[PathBuf],
+    included: &mut HashSet<PathBuf>,
+    output: &mut String,
+) -> Result<()> {
+    for line in source.lines() {
+        let Some(directive) = line.trim_start().strip_prefix("#moj_import") else {
+            output.push_str(line);
+            output.push('\n');
+            continue;
+        };
+
+        let directive = directive.trim();
+        let (name, relative) = if let Some(name) = directive.strip_prefix('<').and_then(|d| d.strip_suffix('>')) {
+            (name, false)
+        } else if let Some(name) = directive.strip_prefix('"').and_then(|d| d.strip_suffix('"')) {
+            (name, true)
+        } else {
+            return Err(BasaltError::invalid_parameter(
+                "moj_import",
+                format!("malformed directive: {}", line.trim()),
+            ));
+        };
+
+        let path = find_moj_import(name, relative.then_some(current_dir).flatten(), include_paths)
+            .ok_or_else(|| {
+                log::error!("#moj_import <{}> not found in {:?}", name, include_paths);
+                BasaltError::NotFound {
+                    resource_type: "shader include".to_string(),
+                    name: name.to_string(),
+                }
+            })?;
+
+        if !included.insert(path.clone()) {
+            continue;
+        }
+
+        let contents = std::fs::read_to_string(&path)?;
+        inline_moj_imports(&contents, path.parent(), include_paths, included, output)?;
+    }
+    Ok(())
+}
+
+fn find_moj_import(name: &str, current_dir: Option<&Path>, include_paths: &[PathBuf]) -> Option<PathBuf> {
+    let mut candidates = vec![PathBuf::from(name.replace(':', "/"))];
+    if let Some((_, path)) = name.split_once(':') {
+        candidates.push(PathBuf::from(path));
+    }
+
+    current_dir
+        .into_iter()
+        .chain(include_paths.iter().map(PathBuf::as_path))
+        .flat_map(|dir| candidates.iter().map(move |c| dir.join(c)))
+        .find(|path| path.is_file())
+        .map(|path| path.canonicalize().unwrap_or(path))
+}
+
+/// Stage of a module's entry points
+///
+/// WGSL marks entry points with `@vertex` / `@fragment` / `@compute`, so
+/// already-WGSL shaders don't need the stage passed alongside them. Fails
+/// when the module has no entry point or entry points for several stages.
+pub fn detect_stage(module: &Module) -> Result<ShaderStage> {
+    let mut stages: Vec<ShaderStage> = module.entry_points.iter().map(|ep| ep.stage).collect();
+    stages.dedup();
+    match stages.as_slice() {
+        [stage] => Ok(*stage),
+        [] => Err(BasaltError::invalid_parameter("stage", "module has no entry point to take the stage from")),
+        _ => Err(BasaltError::invalid_parameter(
+            "stage",
+            format!("module has entry points for several stages ({:?}), pass the stage explicitly", stages),
+        )),
+    }
+}
+
+/// Parse WGSL and return the stage of its entry points, see `detect_stage`
+pub fn detect_wgsl_stage(wgsl_source: &str, shader_name: &str) -> Result<ShaderStage> {
+    detect_stage(&parse_wgsl_named(wgsl_source, shader_name)?)
+}
+
 /// Get shader stage from string
 pub fn parse_shader_stage(stage: &str) -> Result<ShaderStage> {
     match stage.to_lowercase().as_str() {
@@ -210,3 +510,121 @@ pub fn parse_shader_stage(stage: &str) -> Result<ShaderStage> {
         )),
     }
 }
+
+/// Bind group holding the uniform that replaces immediates on devices without push constants
+pub const IMMEDIATES_FALLBACK_GROUP: u32 = 1;
+
+/// Rewrite `var<immediate>` declarations into a uniform at the fallback bind group
+///
+/// Returns None when the shader declares no immediates. The uniform is fed by
+/// `setPushConstants` through a dynamic offset, so shaders keep a single source
+/// for both paths.
+pub fn lower_immediates_to_uniform(wgsl_source: &str) -> Option<String> {
+    if !wgsl_source.contains("var<immediate>") {
+        return None;
+    }
+    let uniform = format!("@group({}) @binding(0) var<uniform>", IMMEDIATES_FALLBACK_GROUP);
+    Some(wgsl_source.replace("var<immediate>", &uniform))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_error_reports_location() {
+        let source = "@fragment\nfn main() -> @location(0) vec4<f32> {\n    return vec4<f32>(1.0)\n}\n";
+        let err = parse_wgsl_named(source, "broken").unwrap_err();
+
+        match &err {
+            BasaltError::ShaderParse { shader_name, line, column, source_line, .. } => {
+                assert_eq!(shader_name, "broken");
+                assert!(line.is_some());
+                assert!(column.is_some());
+                assert_eq!(source_line.as_deref(), source.lines().nth(line.unwrap() - 1));
+            }
+            other => panic!("expected ShaderParse, got {:?}", other),
+        }
+
+        let message = err.to_string();
+        assert!(message.starts_with("Shader parse error in 'broken' at "));
+        assert!(message.contains('^'));
+    }
+
+    #[test]
+    fn test_spirv_rejects_unaligned_input() {
+        let err = parse_spirv_named(&[0x03, 0x02, 0x23, 0x07, 0x00], "truncated").unwrap_err();
+        assert!(err.to_string().contains("truncated"));
+        assert!(matches!(err, BasaltError::ShaderCompilation { ref error, .. } if error.contains("multiple of 4")));
+
+        // Aligned but not a SPIR-V module
+        assert!(spirv_to_wgsl(&[0u8; 16], "garbage").is_err());
+    }
+
+    #[test]
+    fn test_moj_import_inlines_each_file_once() {
+        let dir = std::env::temp_dir().join(format!("bassalt-moj-import-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("minecraft")).unwrap();
+        std::fs::write(dir.join("minecraft/fog.glsl"), "#moj_import \"common.glsl\"\nfloat fog() { return common(); }\n").unwrap();
+        std::fs::write(dir.join("minecraft/common.glsl"), "#moj_import <minecraft:fog.glsl>\nfloat common() { return 1.0; }\n").unwrap();
+
+        let source = "#version 450\n#moj_import <minecraft:fog.glsl>\n#moj_import <minecraft:common.glsl>\nvoid main() {}\n";
+        let resolved = resolve_moj_imports(source, std::slice::from_ref(&dir)).unwrap();
+        assert!(!resolved.contains("#moj_import"));
+        assert_eq!(resolved.matches("float common()").count(), 1, "cycles and repeats are inlined once");
+        assert!(resolved.find("float common()").unwrap() < resolved.find("float fog()").unwrap());
+
+        let err = resolve_moj_imports("#moj_import <missing.glsl>\n", std::slice::from_ref(&dir)).unwrap_err();
+        assert!(err.to_string().contains("missing.glsl"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_translation_cache_hits_on_repeat() {
+        let cache = TranslationCache::new();
+        let source = "#version 450\nlayout(location = 0) out vec4 color;\nvoid main() { color = vec4(1.0); }\n";
+
+        let first = cache.translate(source, ShaderStage::Fragment).unwrap();
+        assert_eq!(cache.hits(), 0);
+        let second = cache.translate(source, ShaderStage::Fragment).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(cache.hits(), 1);
+
+        // Failed translations aren't cached
+        assert!(cache.translate("not glsl", ShaderStage::Fragment).is_err());
+        assert_eq!(cache.len(), 1);
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_defines_select_shader_variant() {
+        let source = "#version 450\nlayout(location = 0) out vec4 color;\nvoid main() {\n#ifdef USE_FOG\n    color = vec4(0.25);\n#else\n    color = vec4(1.0);\n#endif\n}\n";
+        let cache = TranslationCache::new();
+
+        let plain = cache.translate(source, ShaderStage::Fragment).unwrap();
+        let fog_defines = [("USE_FOG".to_string(), "1".to_string())];
+        let fog = cache.translate_with_defines(source, ShaderStage::Fragment, &fog_defines).unwrap();
+        assert!(plain.contains("1f") && !plain.contains("0.25f"));
+        assert!(fog.contains("0.25f"));
+
+        // Each define set is cached separately
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.translate_with_defines(source, ShaderStage::Fragment, &fog_defines).unwrap(), fog);
+        assert_eq!(cache.hits(), 1);
+    }
+
+    #[test]
+    fn test_detect_stage_from_entry_point() {
+        let compute = "@compute @workgroup_size(64) fn main(@builtin(global_invocation_id) id: vec3<u32>) {}";
+        assert_eq!(detect_wgsl_stage(compute, "compute").unwrap(), ShaderStage::Compute);
+
+        let fragment = "@fragment fn main() -> @location(0) vec4<f32> { return vec4<f32>(1.0); }";
+        assert_eq!(detect_wgsl_stage(fragment, "fragment").unwrap(), ShaderStage::Fragment);
+
+        // Combined modules and helper-only sources are ambiguous
+        let combined = format!("@vertex fn vs() -> @builtin(position) vec4<f32> {{ return vec4<f32>(); }}\n{}", fragment.replace("fn main", "fn fs"));
+        assert!(detect_wgsl_stage(&combined, "combined").is_err());
+        assert!(detect_wgsl_stage("fn helper() -> f32 { return 1.0; }", "helper").is_err());
+    }
+}
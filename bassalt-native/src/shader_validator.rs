@@ -6,7 +6,7 @@
 //! - Performance issue detection
 
 use crate::error::{BasaltError, Result};
-use naga::{Module, valid};
+use naga::{Module, ShaderStage, valid};
 
 /// Detailed validation report for a shader
 #[derive(Debug, Clone)]
@@ -14,6 +14,9 @@ pub struct ShaderValidationReport {
     /// Shader name/identifier
     pub shader_name: String,
 
+    /// Stage taken from the entry points, None when there isn't exactly one
+    pub stage: Option<ShaderStage>,
+
     /// Basic validation passed
     pub is_valid: bool,
 
@@ -75,6 +78,7 @@ impl ShaderValidator {
 
         Ok(ShaderValidationReport {
             shader_name: shader_name.to_string(),
+            stage: crate::shader::detect_stage(module).ok(),
             is_valid: true,
             warnings,
             metrics,
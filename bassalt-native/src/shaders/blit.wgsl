@@ -3,6 +3,14 @@
 //
 // This shader is used by the native code for final swapchain presentation.
 // It's a simple pass-through shader that copies the rendered framebuffer to the screen.
+//
+// Orientation: UVs are passed from the vertex shader as a varying, never derived
+// from @builtin(position), so there's exactly one mapping. Texel row 0 of the
+// source (the top row, WebGPU's origin is top-left) lands on the top row of the
+// target, and column 0 on the left, so the blit neither flips nor mirrors. The
+// source is expected in that top-down layout already: an upside-down image
+// means whatever rendered the source flipped it, not this shader.
+// `test_blit_keeps_orientation` in integration_tests.rs checks this on a GPU.
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
@@ -33,6 +41,20 @@ fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
     return out;
 }
 
+// Set by the pipeline when the surface composites alpha: the framebuffer's
+// alpha channel would otherwise show through as window transparency
+override force_opaque_alpha: bool = false;
+
+// Set for HDR swapchains: their extended-range color space is linear, while
+// the game's framebuffer holds sRGB-encoded values in a non-sRGB format
+override decode_srgb: bool = false;
+
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    let low = c / 12.92;
+    let high = pow((c + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, c <= vec3<f32>(0.04045));
+}
+
 @group(0) @binding(0) var src_texture: texture_2d<f32>;
 @group(0) @binding(1) var src_sampler: sampler;
 
@@ -40,5 +62,12 @@ fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     // Simple pass-through blit - sample and output directly
     // No color modulation needed for final swapchain presentation
-    return textureSample(src_texture, src_sampler, in.tex_coord);
+    var color = textureSample(src_texture, src_sampler, in.tex_coord);
+    if force_opaque_alpha {
+        color.a = 1.0;
+    }
+    if decode_srgb {
+        color = vec4<f32>(srgb_to_linear(color.rgb), color.a);
+    }
+    return color;
 }
@@ -0,0 +1,29 @@
+// Debug lines - colored LineList segments for hitbox outlines and overlays
+//
+// wgpu has no line width: lines always rasterize exactly one pixel wide.
+// Wider strokes have to be expanded into quads (two triangles per segment)
+// and drawn through a regular triangle pipeline.
+
+struct Globals {
+    view_proj: mat4x4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> globals: Globals;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>, @location(1) color: vec4<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = globals.view_proj * vec4<f32>(position, 1.0);
+    out.color = color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
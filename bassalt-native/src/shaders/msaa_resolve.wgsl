@@ -0,0 +1,24 @@
+// Partial MSAA resolve - averages the samples of each covered pixel
+//
+// Drawn as a fullscreen triangle with a scissor rect around the region to
+// resolve; pixels outside the scissor keep their previous contents.
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    // One triangle covering the whole target: (-1,-1), (3,-1), (-1,3)
+    let corner = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    return vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@group(0) @binding(0) var src_texture: texture_multisampled_2d<f32>;
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let coord = vec2<i32>(position.xy);
+    let samples = textureNumSamples(src_texture);
+    var sum = vec4<f32>(0.0);
+    for (var i = 0u; i < samples; i++) {
+        sum += textureLoad(src_texture, coord, i32(i));
+    }
+    return sum / f32(samples);
+}
@@ -0,0 +1,275 @@
+//! Recycled staging buffers for large buffer uploads
+//!
+//! `queue_write_buffer` allocates fresh staging memory for every call, which
+//! churns for large per-frame uploads such as terrain. The pool keeps mapped
+//! `MAP_WRITE | COPY_SRC` buffers in power-of-two size buckets: an upload copies
+//! into a free buffer, records a `copy_buffer_to_buffer` and submits, and the
+//! buffer is re-mapped afterwards. Once the GPU is done with the copy the map
+//! callback hands it back, and the next upload of that bucket reuses it.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use wgpu_core::id;
+use wgpu_types as wgt;
+
+use crate::context::BasaltContext;
+use crate::error::{BasaltError, Result};
+
+/// Smallest staging buffer the pool creates
+pub const MIN_BUCKET_SIZE: u64 = 64 * 1024;
+
+/// Free buffers kept per bucket; extra buffers are released when they come back
+const MAX_FREE_PER_BUCKET: usize = 4;
+
+/// Staging pool counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StagingPoolStats {
+    /// Uploads served by a recycled buffer
+    pub reused: u64,
+    /// Staging buffers created
+    pub created: u64,
+    /// Buffers mapped and ready for the next upload
+    pub free: u64,
+    /// Buffers still waiting for their copy to finish
+    pub in_flight: u64,
+}
+
+/// A staging buffer handed back by its map callback
+struct Returned {
+    bucket: u64,
+    buffer_id: id::BufferId,
+    /// False if re-mapping failed; the buffer is released instead of reused
+    mapped: bool,
+}
+
+/// Pool of mapped staging buffers bucketed by size
+pub struct StagingBeltPool {
+    free: Mutex<HashMap<u64, Vec<id::BufferId>>>,
+    /// Filled from map callbacks during device polls
+    returned: Arc<Mutex<Vec<Returned>>>,
+    stats: Mutex<StagingPoolStats>,
+}
+
+impl StagingBeltPool {
+    pub fn new() -> Self {
+        Self {
+            free: Mutex::new(HashMap::new()),
+            returned: Arc::new(Mutex::new(Vec::new())),
+            stats: Mutex::new(StagingPoolStats::default()),
+        }
+    }
+
+    /// Bucket a `size`-byte upload is staged in
+    pub fn bucket_size(size: u64) -> u64 {
+        size.next_power_of_two().max(MIN_BUCKET_SIZE)
+    }
+
+    /// Upload `data` into `dst` at `offset` through a recycled staging buffer
+    ///
+    /// `offset` and `data.len()` must be multiples of `COPY_BUFFER_ALIGNMENT`,
+    /// and `dst` needs COPY_DST usage. Submits the copy on `queue_id`.
+    pub fn write_buffer(
+        &self,
+        context: &BasaltContext,
+        device_id: id::DeviceId,
+        queue_id: id::QueueId,
+        dst: id::BufferId,
+        offset: u64,
+        data: &[u8],
+    ) -> Result<()> {
+        let size = data.len() as u64;
+        if size == 0
+            || !size.is_multiple_of(wgt::COPY_BUFFER_ALIGNMENT)
+            || !offset.is_multiple_of(wgt::COPY_BUFFER_ALIGNMENT)
+        {
+            return Err(BasaltError::invalid_parameter(
+                "data",
+                format!("staged upload of {} bytes at offset {} is not 4-byte aligned", size, offset),
+            ));
+        }
+
+        let global = context.inner();
+        let _serialized = context.serialize_submission();
+        self.recall(context);
+
+        let bucket = Self::bucket_size(size);
+        let staging_id = self.acquire(context, device_id, bucket)?;
+
+        // The buffer is mapped (at creation, or by the previous upload's re-map)
+        let written = global
+            .buffer_get_mapped_range(staging_id, 0, Some(size))
+            .map_err(|e| BasaltError::Generic(format!("Failed to get mapped range: {:?}", e)))
+            .map(|(ptr, mapped_size)| {
+                // SAFETY: the mapped range holds at least `size` bytes until the unmap below
+                unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), ptr.as_ptr(), size.min(mapped_size) as usize) };
+            });
+        let _ = global.buffer_unmap(staging_id);
+        if let Err(e) = written {
+            global.buffer_drop(staging_id);
+            return Err(e);
+        }
+
+        let submit = || -> Result<()> {
+            let encoder_desc = wgt::CommandEncoderDescriptor {
+                label: Some(Cow::Borrowed("Staged Upload Encoder")),
+            };
+            let (encoder_id, error) = global.device_create_command_encoder(device_id, &encoder_desc, None);
+
+            if let Some(e) = error {
+                return Err(BasaltError::Wgpu(format!("{:?}", e)));
+            }
+
+            global
+                .command_encoder_copy_buffer_to_buffer(encoder_id, staging_id, 0, dst, offset, Some(size))
+                .map_err(|e| BasaltError::wgpu_context("staged upload copy", e))?;
+
+            let (command_buffer, error) = global.command_encoder_finish(
+                encoder_id,
+                &wgt::CommandBufferDescriptor::default(),
+                None,
+            );
+
+            if let Some(e) = error {
+                return Err(BasaltError::wgpu_context("staged upload", e.1));
+            }
+
+            global
+                .queue_submit(queue_id, &[command_buffer])
+                .map_err(|(_, e)| BasaltError::from_webgpu_error("staged upload submit", &e))?;
+
+            Ok(())
+        };
+
+        if let Err(e) = submit() {
+            global.buffer_drop(staging_id);
+            return Err(e);
+        }
+
+        // Map again for the next upload; the map completes once the copy has executed
+        let returned = self.returned.clone();
+        let map_op = wgpu_core::resource::BufferMapOperation {
+            host: wgpu_core::device::HostMap::Write,
+            callback: Some(Box::new(move |result: wgpu_core::resource::BufferAccessResult| {
+                if let Err(e) = &result {
+                    log::warn!("Staging buffer re-map failed, releasing it: {:?}", e);
+                }
+                returned.lock().push(Returned { bucket, buffer_id: staging_id, mapped: result.is_ok() });
+            })),
+        };
+
+        if let Err(e) = global.buffer_map_async(staging_id, 0, Some(bucket), map_op) {
+            log::warn!("Failed to re-map staging buffer: {:?}", e);
+        }
+        self.stats.lock().in_flight += 1;
+
+        Ok(())
+    }
+
+    /// Move buffers whose copies finished back into the free lists
+    ///
+    /// Map callbacks fire during device polls; uploads call this on their own.
+    pub fn recall(&self, context: &BasaltContext) {
+        let returned = std::mem::take(&mut *self.returned.lock());
+        if returned.is_empty() {
+            return;
+        }
+
+        let mut free = self.free.lock();
+        let mut stats = self.stats.lock();
+        for r in returned {
+            stats.in_flight = stats.in_flight.saturating_sub(1);
+            let list = free.entry(r.bucket).or_default();
+            if r.mapped && list.len() < MAX_FREE_PER_BUCKET {
+                list.push(r.buffer_id);
+                stats.free += 1;
+            } else {
+                context.inner().buffer_drop(r.buffer_id);
+            }
+        }
+    }
+
+    /// Current pool counters
+    pub fn stats(&self) -> StagingPoolStats {
+        *self.stats.lock()
+    }
+
+    /// Take a mapped buffer of `bucket` bytes, creating one if none is free
+    fn acquire(&self, context: &BasaltContext, device_id: id::DeviceId, bucket: u64) -> Result<id::BufferId> {
+        if let Some(buffer_id) = self.free.lock().get_mut(&bucket).and_then(Vec::pop) {
+            let mut stats = self.stats.lock();
+            stats.free -= 1;
+            stats.reused += 1;
+            return Ok(buffer_id);
+        }
+
+        let desc = wgt::BufferDescriptor {
+            label: Some(Cow::Owned(format!("Bassalt Staging Buffer ({} bytes)", bucket))),
+            size: bucket,
+            usage: wgt::BufferUsages::MAP_WRITE | wgt::BufferUsages::COPY_SRC,
+            mapped_at_creation: true,
+        };
+
+        let (buffer_id, error) = context.inner().device_create_buffer(device_id, &desc, None);
+
+        if let Some(e) = error {
+            return Err(BasaltError::resource_creation("staging buffer", format!("{:?}", e)));
+        }
+
+        self.stats.lock().created += 1;
+        Ok(buffer_id)
+    }
+}
+
+impl Default for StagingBeltPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_staging_buffers_are_recycled() {
+        let context = BasaltContext::new_noop();
+        let (device_id, queue_id) = context.create_noop_device();
+        let global = context.inner();
+
+        let (dst, error) = global.device_create_buffer(
+            device_id,
+            &wgt::BufferDescriptor {
+                label: None,
+                size: 256 * 1024,
+                usage: wgt::BufferUsages::COPY_DST | wgt::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            },
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+
+        assert_eq!(StagingBeltPool::bucket_size(1), MIN_BUCKET_SIZE);
+        assert_eq!(StagingBeltPool::bucket_size(100 * 1024), 128 * 1024);
+
+        let pool = StagingBeltPool::new();
+        let data = vec![0x5A; 100 * 1024];
+        pool.write_buffer(&context, device_id, queue_id, dst, 0, &data).unwrap();
+        assert_eq!(pool.stats(), StagingPoolStats { created: 1, in_flight: 1, ..Default::default() });
+
+        // Before the copy completes the buffer is still in flight, so a new one is made
+        pool.write_buffer(&context, device_id, queue_id, dst, 4, &data).unwrap();
+        assert_eq!(pool.stats().created, 2);
+
+        global.device_poll(device_id, wgt::PollType::wait_indefinitely()).unwrap();
+        pool.write_buffer(&context, device_id, queue_id, dst, 0, &data[..64 * 1024 + 4]).unwrap();
+        let stats = pool.stats();
+        assert_eq!((stats.created, stats.reused, stats.free), (2, 1, 1), "{:?}", stats);
+
+        // Unaligned uploads are rejected rather than silently truncated
+        assert!(pool.write_buffer(&context, device_id, queue_id, dst, 0, &data[..3]).is_err());
+        assert!(pool.write_buffer(&context, device_id, queue_id, dst, 2, &data[..4]).is_err());
+    }
+}
@@ -8,6 +8,26 @@ use raw_window_handle::RawWindowHandle;
 use crate::context::BasaltContext;
 use crate::error::{BasaltError, Result};
 
+/// Formats, present modes and alpha modes a surface supports on the device's adapter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SurfaceCapabilities {
+    pub formats: Vec<wgt::TextureFormat>,
+    pub present_modes: Vec<wgt::PresentMode>,
+    pub alpha_modes: Vec<wgt::CompositeAlphaMode>,
+    pub usages: wgt::TextureUsages,
+}
+
+impl From<&wgt::SurfaceCapabilities> for SurfaceCapabilities {
+    fn from(caps: &wgt::SurfaceCapabilities) -> Self {
+        Self {
+            formats: caps.formats.clone(),
+            present_modes: caps.present_modes.clone(),
+            alpha_modes: caps.alpha_modes.clone(),
+            usages: caps.usages,
+        }
+    }
+}
+
 /// Wrapper for a surface with error recovery and lifecycle management
 ///
 /// Based on wgpu examples' SurfaceWrapper pattern:
@@ -17,9 +37,10 @@ use crate::error::{BasaltError, Result};
 pub struct BasaltSurface {
     context: Arc<BasaltContext>,
     surface_id: id::SurfaceId,
-    config: Option<wgt::SurfaceConfiguration<Vec<wgt::TextureFormat>>>,
+    config: parking_lot::Mutex<Option<wgt::SurfaceConfiguration<Vec<wgt::TextureFormat>>>>,
     device_id: Option<id::DeviceId>,  // Track device for reconfigure
     max_retries: u32,  // Maximum retries for get_current_texture
+    capabilities: Option<SurfaceCapabilities>,  // Queried when the device is created
 }
 
 impl BasaltSurface {
@@ -45,9 +66,10 @@ impl BasaltSurface {
         Self {
             context,
             surface_id,
-            config: None,
+            config: parking_lot::Mutex::new(None),
             device_id: None,
             max_retries: 3,  // Allow up to 3 retries for transient errors
+            capabilities: None,
         }
     }
 
@@ -58,8 +80,18 @@ impl BasaltSurface {
 
     /// wgpu 28.0: Get the current surface configuration
     /// Returns the configuration if the surface has been configured
-    pub fn get_configuration(&self) -> Option<&wgt::SurfaceConfiguration<Vec<wgt::TextureFormat>>> {
-        self.config.as_ref()
+    pub fn get_configuration(&self) -> Option<wgt::SurfaceConfiguration<Vec<wgt::TextureFormat>>> {
+        self.config.lock().clone()
+    }
+
+    /// Capabilities stored by `set_capabilities`
+    pub fn capabilities(&self) -> Option<&SurfaceCapabilities> {
+        self.capabilities.as_ref()
+    }
+
+    /// Store the capabilities queried for the device's adapter
+    pub fn set_capabilities(&mut self, capabilities: SurfaceCapabilities) {
+        self.capabilities = Some(capabilities);
     }
 
     /// Get the supported formats for this surface
@@ -102,17 +134,33 @@ impl BasaltSurface {
             .inner()
             .surface_configure(self.surface_id, device_id, &config);
 
-        self.config = Some(config);
+        *self.config.get_mut() = Some(config);
         self.device_id = Some(device_id);
         Ok(())
     }
 
-    /// Reconfigure the surface (used for error recovery)
-    fn reconfigure(&self) -> Result<()> {
-        if let (Some(config), Some(device_id)) = (&self.config, self.device_id) {
-            self.context
-                .inner()
-                .surface_configure(self.surface_id, device_id, config);
+    /// Replace the configuration of an already configured surface
+    ///
+    /// Used to switch formats at runtime, e.g. for HDR. The caller must not
+    /// hold a texture acquired under the old configuration.
+    pub fn update_configuration(&self, config: wgt::SurfaceConfiguration<Vec<wgt::TextureFormat>>) -> Result<()> {
+        let device_id = self.device_id.ok_or_else(|| BasaltError::surface("Cannot reconfigure: surface was never configured"))?;
+        let mut current = self.config.lock();
+        if let Some(e) = self.context.inner().surface_configure(self.surface_id, device_id, &config) {
+            return Err(BasaltError::surface(format!("Failed to reconfigure surface: {:?}", e)));
+        }
+        *current = Some(config);
+        Ok(())
+    }
+
+    /// Reconfigure the surface with its stored configuration
+    ///
+    /// Used for error recovery, e.g. after the swapchain went outdated or lost.
+    pub fn reconfigure(&self) -> Result<()> {
+        if let (Some(config), Some(device_id)) = (&*self.config.lock(), self.device_id) {
+            if let Some(e) = self.context.inner().surface_configure(self.surface_id, device_id, config) {
+                return Err(BasaltError::surface(format!("Failed to reconfigure surface: {:?}", e)));
+            }
             log::debug!("Surface reconfigured after error");
             Ok(())
         } else {
@@ -197,12 +245,13 @@ impl BasaltSurface {
     }
 
     /// Present the surface
-    pub fn present(&self, _queue_id: id::QueueId) -> Result<()> {
+    ///
+    /// An outdated or lost swapchain is reported through the status, not as an error.
+    pub fn present(&self, _queue_id: id::QueueId) -> Result<wgt::SurfaceStatus> {
         self.context
             .inner()
             .surface_present(self.surface_id)
-            .map_err(|e| BasaltError::surface(format!("Failed to present: {:?}", e)))?;
-        Ok(())
+            .map_err(|e| BasaltError::surface(format!("Failed to present: {:?}", e)))
     }
 
     /// Pre-present notification (important for macOS frame timing)
@@ -233,7 +282,7 @@ impl BasaltSurface {
             log::info!("Suspending surface (Android)");
             // Drop the surface - it will be recreated on resume
             self.context.inner().surface_drop(self.surface_id);
-            self.config = None;
+            *self.config.get_mut() = None;
             self.device_id = None;
         }
     }
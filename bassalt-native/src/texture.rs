@@ -2,6 +2,9 @@
 
 use wgpu_types as wgt;
 
+use crate::error::{BasaltError, Result};
+use crate::resource_handles::TextureInfo;
+
 /// Texture descriptor for creating textures
 #[derive(Debug, Clone)]
 pub struct TextureDescriptor {
@@ -33,3 +36,674 @@ impl Default for TextureDescriptor {
         }
     }
 }
+
+/// Expand tightly packed 3-byte RGB texels to 4-byte texels with opaque alpha
+///
+/// RGB8 textures are created as 4-byte formats on the GPU (there is no 3-byte
+/// texture format), so RGB source data has to be widened before upload. When
+/// `bgra` is set the red and blue channels are swapped to match a BGRA texture.
+pub fn expand_rgb8_to_rgba8(data: &[u8], width: u32, height: u32, bgra: bool) -> Result<Vec<u8>> {
+    let texels = width as usize * height as usize;
+    if data.len() < texels * 3 {
+        return Err(BasaltError::invalid_parameter(
+            "data",
+            format!("RGB8 upload of {}x{} needs {} bytes, got {}", width, height, texels * 3, data.len()),
+        ));
+    }
+
+    let mut out = Vec::with_capacity(texels * 4);
+    for rgb in data[..texels * 3].chunks_exact(3) {
+        let (r, g, b) = (rgb[0], rgb[1], rgb[2]);
+        if bgra {
+            out.extend_from_slice(&[b, g, r, 0xFF]);
+        } else {
+            out.extend_from_slice(&[r, g, b, 0xFF]);
+        }
+    }
+    Ok(out)
+}
+
+/// Channel reordering applied to source data during a texture upload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceSwizzle {
+    /// Upload the data as is
+    None,
+    /// Swap red and blue, e.g. BGRA image data into an RGBA texture
+    SwapRedBlue,
+}
+
+impl SourceSwizzle {
+    /// Swizzle from its JNI index (`BassaltBackend.SWIZZLE_*`)
+    pub fn from_index(index: i32) -> Result<Self> {
+        match index {
+            0 => Ok(Self::None),
+            1 => Ok(Self::SwapRedBlue),
+            _ => Err(BasaltError::invalid_parameter("source_swizzle", format!("unknown swizzle {}", index))),
+        }
+    }
+
+    /// Reorder the 4-byte texels of `data` in place for a `format` texture
+    ///
+    /// Swapping channels only makes sense for 8-bit four channel formats;
+    /// anything else is rejected rather than corrupted.
+    pub fn apply(self, data: &mut [u8], format: wgt::TextureFormat) -> Result<()> {
+        if self == Self::None {
+            return Ok(());
+        }
+        use wgt::TextureFormat as F;
+        if !matches!(
+            format,
+            F::Rgba8Unorm | F::Rgba8UnormSrgb | F::Rgba8Snorm | F::Rgba8Uint | F::Rgba8Sint | F::Bgra8Unorm | F::Bgra8UnormSrgb
+        ) {
+            return Err(BasaltError::invalid_parameter(
+                "source_swizzle",
+                format!("{:?} can't be applied to a {:?} texture", self, format),
+            ));
+        }
+        for texel in data.chunks_exact_mut(4) {
+            texel.swap(0, 2);
+        }
+        Ok(())
+    }
+}
+
+/// Tightly packed row pitch and row count for uploading a `width`x`height` region
+///
+/// Block-compressed formats are laid out in rows of blocks, so the region is
+/// rounded up to whole blocks: a BC1 row of a 6x6 mip is two 8-byte blocks.
+/// Returns `(bytes_per_row, rows)`.
+pub fn upload_row_layout(format: wgt::TextureFormat, width: u32, height: u32) -> Result<(u32, u32)> {
+    let block_size = format.block_copy_size(Some(wgt::TextureAspect::All)).ok_or_else(|| {
+        BasaltError::invalid_parameter("format", format!("{:?} can't be written directly", format))
+    })?;
+    let (block_width, block_height) = format.block_dimensions();
+
+    Ok((
+        width.div_ceil(block_width) * block_size,
+        height.div_ceil(block_height),
+    ))
+}
+
+/// Row layout for reading `aspect` of a region back into a buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadbackLayout {
+    /// Tightly packed row pitch, as the caller's buffer is laid out
+    pub bytes_per_row: u32,
+    /// Row pitch of the texture copy, rounded up to `COPY_BYTES_PER_ROW_ALIGNMENT`
+    pub padded_bytes_per_row: u32,
+    pub rows: u32,
+}
+
+impl ReadbackLayout {
+    /// Whether rows must be copied through a padded scratch buffer
+    pub fn needs_unpadding(&self) -> bool {
+        self.bytes_per_row != self.padded_bytes_per_row
+    }
+}
+
+/// Row layout for reading `aspect` of a region back
+///
+/// Depth is only copyable from some formats: `Depth24Plus` keeps an
+/// implementation-defined layout, so textures that are read back need
+/// `Depth32Float`. Combined depth-stencil formats are copied one aspect at a
+/// time. Texture-to-buffer copies need 256-byte aligned rows; narrower
+/// regions are copied padded and then unpadded row by row with buffer copies,
+/// which need the tight row to be a multiple of 4 bytes.
+pub fn readback_row_layout(
+    format: wgt::TextureFormat,
+    aspect: wgt::TextureAspect,
+    width: u32,
+    height: u32,
+) -> Result<ReadbackLayout> {
+    if aspect == wgt::TextureAspect::All && format.is_combined_depth_stencil_format() {
+        return Err(BasaltError::invalid_parameter(
+            "aspect",
+            format!("{:?} must be read back one aspect at a time: choose DepthOnly or StencilOnly", format),
+        ));
+    }
+    let has_aspect = match aspect {
+        wgt::TextureAspect::DepthOnly => format.has_depth_aspect(),
+        wgt::TextureAspect::StencilOnly => format.has_stencil_aspect(),
+        _ => true,
+    };
+    let block_size = format.block_copy_size(Some(aspect)).filter(|_| has_aspect).ok_or_else(|| {
+        if format.has_depth_aspect() && aspect != wgt::TextureAspect::StencilOnly {
+            BasaltError::invalid_parameter(
+                "format",
+                format!("depth of {:?} can't be copied to a buffer; use Depth32Float for depth that's read back", format),
+            )
+        } else {
+            BasaltError::invalid_parameter("aspect", format!("{:?} has no copyable {:?} aspect", format, aspect))
+        }
+    })?;
+    let (block_width, block_height) = format.block_dimensions();
+
+    let bytes_per_row = width.div_ceil(block_width) * block_size;
+    let layout = ReadbackLayout {
+        bytes_per_row,
+        padded_bytes_per_row: bytes_per_row.next_multiple_of(wgt::COPY_BYTES_PER_ROW_ALIGNMENT),
+        rows: height.div_ceil(block_height),
+    };
+    if layout.needs_unpadding() && !(bytes_per_row as u64).is_multiple_of(wgt::COPY_BUFFER_ALIGNMENT) {
+        return Err(BasaltError::invalid_parameter(
+            "width",
+            format!(
+                "rows of {} bytes can't be read back unpadded; {:?} readbacks need a width of a multiple of {} texels",
+                bytes_per_row,
+                format,
+                wgt::COPY_BUFFER_ALIGNMENT / block_size as u64 * block_width as u64,
+            ),
+        ));
+    }
+    Ok(layout)
+}
+
+/// Region of one mip read back into a buffer
+///
+/// Rows are copied tightly packed at the size of one texel of `aspect` of
+/// `format`, see `readback_row_layout`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureReadback {
+    pub texture_id: wgpu_core::id::TextureId,
+    pub format: wgt::TextureFormat,
+    pub aspect: wgt::TextureAspect,
+    pub mip_level: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// One region of a batched texture upload
+///
+/// `data_offset` points into the batch's combined byte array, where the region's
+/// rows are tightly packed as described by `upload_row_layout`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureWrite {
+    pub texture_id: wgpu_core::id::TextureId,
+    pub format: wgt::TextureFormat,
+    pub mip_level: u32,
+    /// Array layer (or 3D slice) the region is written to
+    pub layer: u32,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub data_offset: u64,
+}
+
+impl TextureWrite {
+    /// Tightly packed `(bytes_per_row, rows)` of the region, after checking that
+    /// it is non-empty, block aligned and backed by `data_len` bytes of data
+    pub fn validate(&self, data_len: usize) -> Result<(u32, u32)> {
+        if self.width == 0 || self.height == 0 {
+            return Err(BasaltError::invalid_parameter(
+                "region",
+                format!("empty {}x{} region", self.width, self.height),
+            ));
+        }
+        let (block_width, block_height) = self.format.block_dimensions();
+        if !self.x.is_multiple_of(block_width) || !self.y.is_multiple_of(block_height) {
+            return Err(BasaltError::invalid_parameter(
+                "region",
+                format!("origin ({}, {}) is not aligned to {:?} blocks", self.x, self.y, self.format),
+            ));
+        }
+
+        let (bytes_per_row, rows) = upload_row_layout(self.format, self.width, self.height)?;
+        let end = self.data_offset.checked_add(bytes_per_row as u64 * rows as u64);
+        if end.is_none_or(|end| end > data_len as u64) {
+            return Err(BasaltError::invalid_parameter(
+                "data_offset",
+                format!(
+                    "{}x{} region at offset {} needs {} bytes, batch data has {}",
+                    self.width, self.height, self.data_offset, bytes_per_row as u64 * rows as u64, data_len
+                ),
+            ));
+        }
+        Ok((bytes_per_row, rows))
+    }
+}
+
+/// Single layer (or 3D slice) of a texture used as a clear target or blit source
+///
+/// Render attachments must be single-layer views, so array and cube textures
+/// are addressed through a 2D view of one layer. 3D textures keep a 3D view
+/// and select the slice through the attachment's `depth_slice`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayerTarget {
+    pub view_dimension: wgt::TextureViewDimension,
+    pub base_array_layer: u32,
+    pub array_layer_count: u32,
+    /// Slice of a 3D texture, `None` for layered textures
+    pub depth_slice: Option<u32>,
+}
+
+impl LayerTarget {
+    /// Subresource range covered by the view of this target
+    pub fn range(&self, aspect: wgt::TextureAspect) -> wgt::ImageSubresourceRange {
+        wgt::ImageSubresourceRange {
+            aspect,
+            base_mip_level: 0,
+            mip_level_count: Some(1),
+            base_array_layer: self.base_array_layer,
+            array_layer_count: Some(self.array_layer_count),
+        }
+    }
+}
+
+/// Resolve one layer of a texture with the given stored dimension
+///
+/// `depth_or_array_layers` is the texture's layer count (or depth for 3D
+/// textures); `layer` must be inside it. 1D textures can't be rendered to.
+pub fn layer_target(
+    dimension: wgt::TextureDimension,
+    depth_or_array_layers: u32,
+    layer: u32,
+) -> Result<LayerTarget> {
+    if layer >= depth_or_array_layers.max(1) {
+        return Err(BasaltError::invalid_parameter(
+            "layer",
+            format!("layer {} out of range for texture with {} layers", layer, depth_or_array_layers.max(1)),
+        ));
+    }
+
+    match dimension {
+        wgt::TextureDimension::D1 => Err(BasaltError::invalid_parameter(
+            "dimension",
+            "1D textures can't be used as render targets",
+        )),
+        wgt::TextureDimension::D2 => Ok(LayerTarget {
+            view_dimension: wgt::TextureViewDimension::D2,
+            base_array_layer: layer,
+            array_layer_count: 1,
+            depth_slice: None,
+        }),
+        wgt::TextureDimension::D3 => Ok(LayerTarget {
+            view_dimension: wgt::TextureViewDimension::D3,
+            base_array_layer: 0,
+            array_layer_count: 1,
+            depth_slice: Some(layer),
+        }),
+    }
+}
+
+/// Targets for `layer`, or for every layer of the texture when `None`
+pub fn layer_targets(
+    dimension: wgt::TextureDimension,
+    depth_or_array_layers: u32,
+    layer: Option<u32>,
+) -> Result<Vec<LayerTarget>> {
+    match layer {
+        Some(layer) => Ok(vec![layer_target(dimension, depth_or_array_layers, layer)?]),
+        None => (0..depth_or_array_layers.max(1))
+            .map(|layer| layer_target(dimension, depth_or_array_layers, layer))
+            .collect(),
+    }
+}
+
+/// Subresource selection requested for a ranged texture view
+///
+/// Counts of 0 select every remaining mip or layer, and `None` derives the
+/// dimension, format or aspect from the texture, so the default covers the
+/// whole texture.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ViewRangeRequest {
+    pub base_mip: u32,
+    pub mip_count: u32,
+    pub base_layer: u32,
+    pub layer_count: u32,
+    pub dimension: Option<wgt::TextureViewDimension>,
+    pub format: Option<wgt::TextureFormat>,
+    pub aspect: Option<wgt::TextureAspect>,
+}
+
+/// Validated subresource selection for a ranged texture view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ViewRange {
+    pub dimension: wgt::TextureViewDimension,
+    /// View format, `None` to use the texture's own format
+    pub format: Option<wgt::TextureFormat>,
+    pub range: wgt::ImageSubresourceRange,
+}
+
+/// Check that a cubemap texture has six square faces
+pub fn validate_cube(width: u32, height: u32, array_layers: u32) -> Result<()> {
+    if width != height {
+        return Err(BasaltError::invalid_parameter(
+            "is_cube",
+            format!("cubemap faces must be square, got {}x{}", width, height),
+        ));
+    }
+    if array_layers != 6 {
+        return Err(BasaltError::invalid_parameter(
+            "is_cube",
+            format!("cubemaps need 6 layers, got {}", array_layers),
+        ));
+    }
+    Ok(())
+}
+
+/// Dimension of a view covering every layer of a texture
+///
+/// Only textures created as cubemaps get `Cube`; other layered textures,
+/// including ones that happen to have 6 layers, get `D2Array`. 3D textures
+/// are viewed as a whole volume.
+pub fn full_view_dimension(
+    dimension: wgt::TextureDimension,
+    array_layers: u32,
+    is_cube: bool,
+) -> wgt::TextureViewDimension {
+    if dimension == wgt::TextureDimension::D3 {
+        wgt::TextureViewDimension::D3
+    } else if is_cube {
+        wgt::TextureViewDimension::Cube
+    } else if array_layers > 1 {
+        wgt::TextureViewDimension::D2Array
+    } else {
+        wgt::TextureViewDimension::D2
+    }
+}
+
+/// Check a view's mip/layer range, dimension and format against its texture
+///
+/// A count of 0 selects every remaining mip or layer. Without an explicit
+/// dimension a single layer gets a D2 view, all six faces of a cubemap a Cube
+/// view and other layer ranges a D2Array view. 3D textures have a single
+/// layer and get a D3 view.
+/// The view format may be the texture format, its sRGB/linear counterpart, or
+/// the depth aspect of a depth-stencil format, which selects `DepthOnly`.
+/// An explicit `aspect` must exist in the texture format; combined
+/// depth-stencil textures need one to be sampled.
+pub fn resolve_view_range(texture: &TextureInfo, request: ViewRangeRequest) -> Result<ViewRange> {
+    let ViewRangeRequest { base_mip, mip_count, base_layer, layer_count, dimension, format, aspect } = request;
+    let texture_mips = texture.mip_level_count.max(1);
+    let is_3d = texture.dimension == wgt::TextureDimension::D3;
+    let texture_layers = texture.array_layer_count();
+
+    if base_mip >= texture_mips {
+        return Err(BasaltError::invalid_parameter(
+            "base_mip",
+            format!("mip {} out of range for texture with {} mips", base_mip, texture_mips),
+        ));
+    }
+    let mip_count = if mip_count == 0 { texture_mips - base_mip } else { mip_count };
+    if base_mip + mip_count > texture_mips {
+        return Err(BasaltError::invalid_parameter(
+            "mip_count",
+            format!("mips {}..{} exceed the texture's {} mips", base_mip, base_mip + mip_count, texture_mips),
+        ));
+    }
+
+    if base_layer >= texture_layers {
+        return Err(BasaltError::invalid_parameter(
+            "base_layer",
+            format!("layer {} out of range for texture with {} layers", base_layer, texture_layers),
+        ));
+    }
+    let layer_count = if layer_count == 0 { texture_layers - base_layer } else { layer_count };
+    if base_layer + layer_count > texture_layers {
+        return Err(BasaltError::invalid_parameter(
+            "layer_count",
+            format!("layers {}..{} exceed the texture's {} layers", base_layer, base_layer + layer_count, texture_layers),
+        ));
+    }
+
+    let dimension = dimension.unwrap_or(if is_3d {
+        wgt::TextureViewDimension::D3
+    } else if layer_count == 1 {
+        wgt::TextureViewDimension::D2
+    } else if texture.is_cube && layer_count == texture_layers {
+        wgt::TextureViewDimension::Cube
+    } else {
+        wgt::TextureViewDimension::D2Array
+    });
+    if dimension.compatible_texture_dimension() != texture.dimension {
+        return Err(BasaltError::invalid_parameter(
+            "view_dimension",
+            format!("{:?} view of a {:?} texture", dimension, texture.dimension),
+        ));
+    }
+    let layers_ok = match dimension {
+        wgt::TextureViewDimension::D1 | wgt::TextureViewDimension::D2 | wgt::TextureViewDimension::D3 => layer_count == 1,
+        wgt::TextureViewDimension::Cube => layer_count == 6,
+        wgt::TextureViewDimension::CubeArray => layer_count % 6 == 0,
+        wgt::TextureViewDimension::D2Array => true,
+    };
+    if !layers_ok {
+        return Err(BasaltError::invalid_parameter(
+            "layer_count",
+            format!("{:?} view can't cover {} layers", dimension, layer_count),
+        ));
+    }
+
+    // An explicit `All` behaves like no aspect, keeping format reinterpretation
+    if let Some(aspect) = aspect.filter(|a| *a != wgt::TextureAspect::All) {
+        return resolve_explicit_aspect(texture, format, aspect).map(|format| ViewRange {
+            dimension,
+            format,
+            range: wgt::ImageSubresourceRange {
+                aspect,
+                base_mip_level: base_mip,
+                mip_level_count: Some(mip_count),
+                base_array_layer: base_layer,
+                array_layer_count: Some(layer_count),
+            },
+        });
+    }
+
+    let depth_only = texture.format.aspect_specific_format(wgt::TextureAspect::DepthOnly);
+    let aspect = match format {
+        None => wgt::TextureAspect::All,
+        Some(f) if f == texture.format => wgt::TextureAspect::All,
+        Some(f) if texture.format.has_stencil_aspect() && Some(f) == depth_only => wgt::TextureAspect::DepthOnly,
+        Some(f) if f.remove_srgb_suffix() == texture.format.remove_srgb_suffix() => wgt::TextureAspect::All,
+        Some(f) => {
+            return Err(BasaltError::invalid_parameter(
+                "format",
+                format!("{:?} view of a {:?} texture", f, texture.format),
+            ))
+        }
+    };
+
+    Ok(ViewRange {
+        dimension,
+        format,
+        range: wgt::ImageSubresourceRange {
+            aspect,
+            base_mip_level: base_mip,
+            mip_level_count: Some(mip_count),
+            base_array_layer: base_layer,
+            array_layer_count: Some(layer_count),
+        },
+    })
+}
+
+/// Check that a `width`x`height` region at (`x`, `y`) fits inside `mip` of `texture`
+///
+/// `parameter` names the side of the copy in the error.
+pub fn validate_mip_region(
+    texture: &TextureInfo,
+    parameter: &str,
+    mip: u32,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> Result<()> {
+    let texture_mips = texture.mip_level_count.max(1);
+    if mip >= texture_mips {
+        return Err(BasaltError::invalid_parameter(
+            parameter,
+            format!("mip {} out of range for texture with {} mips", mip, texture_mips),
+        ));
+    }
+
+    let (mip_width, mip_height) = texture.mip_size(mip);
+    let fits = |origin: u32, extent: u32, size: u32| origin.checked_add(extent).is_some_and(|end| end <= size);
+    if !fits(x, width, mip_width) || !fits(y, height, mip_height) {
+        return Err(BasaltError::invalid_parameter(
+            parameter,
+            format!(
+                "{}x{} region at ({}, {}) exceeds mip {} ({}x{})",
+                width, height, x, y, mip, mip_width, mip_height
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Formats a texture of `format` may list in `view_formats`, starting with `format`
+///
+/// WebGPU only allows views that differ from the texture format in sRGB-ness,
+/// so this is the format plus its sRGB or linear counterpart when it has one.
+pub fn compatible_view_formats(format: wgt::TextureFormat) -> Vec<wgt::TextureFormat> {
+    let pair = if format.is_srgb() {
+        format.remove_srgb_suffix()
+    } else {
+        format.add_srgb_suffix()
+    };
+    if pair != format {
+        vec![format, pair]
+    } else {
+        vec![format]
+    }
+}
+
+/// Resolve view format names as returned by `getCompatibleViewFormats`
+///
+/// Names are the `wgt::TextureFormat` variant names. Each must be compatible
+/// with `format`; the texture's own format is dropped since it is always allowed.
+pub fn parse_view_formats<S: AsRef<str>>(format: wgt::TextureFormat, names: &[S]) -> Result<Vec<wgt::TextureFormat>> {
+    let compatible = compatible_view_formats(format);
+    let mut view_formats = Vec::with_capacity(names.len());
+    for name in names {
+        let name = name.as_ref();
+        let view_format = compatible
+            .iter()
+            .copied()
+            .find(|f| format!("{:?}", f) == name)
+            .ok_or_else(|| {
+                BasaltError::invalid_parameter(
+                    "view_formats",
+                    format!("{} is not a compatible view format for {:?} (compatible: {:?})", name, format, compatible),
+                )
+            })?;
+        if view_format != format && !view_formats.contains(&view_format) {
+            view_formats.push(view_format);
+        }
+    }
+    Ok(view_formats)
+}
+
+/// Check that `aspect` exists in the texture's format and that a requested view
+/// format matches it, returning the view format to use
+fn resolve_explicit_aspect(
+    texture: &TextureInfo,
+    format: Option<wgt::TextureFormat>,
+    aspect: wgt::TextureAspect,
+) -> Result<Option<wgt::TextureFormat>> {
+    let present = match aspect {
+        wgt::TextureAspect::DepthOnly => texture.format.has_depth_aspect(),
+        wgt::TextureAspect::StencilOnly => texture.format.has_stencil_aspect(),
+        _ => false,
+    };
+    if !present {
+        return Err(BasaltError::invalid_parameter(
+            "aspect",
+            format!("{:?} texture has no {:?} aspect", texture.format, aspect),
+        ));
+    }
+
+    let aspect_format = texture.format.aspect_specific_format(aspect).unwrap_or(texture.format);
+    match format {
+        Some(f) if f != aspect_format && f != texture.format => Err(BasaltError::invalid_parameter(
+            "format",
+            format!("{:?} view of the {:?} aspect of a {:?} texture", f, aspect, texture.format),
+        )),
+        _ => Ok(format.map(|_| aspect_format)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgb_source_into_rgba_texture() {
+        // 2x2 RGB image: red, green, blue, white
+        let rgb = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+
+        let rgba = expand_rgb8_to_rgba8(&rgb, 2, 2, false).unwrap();
+        assert_eq!(rgba, [255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 255]);
+
+        let bgra = expand_rgb8_to_rgba8(&rgb, 2, 2, true).unwrap();
+        assert_eq!(&bgra[..4], &[0, 0, 255, 255]);
+        assert_eq!(&bgra[8..12], &[255, 0, 0, 255]);
+
+        // Source too small for the requested extent
+        assert!(expand_rgb8_to_rgba8(&rgb, 3, 2, false).is_err());
+    }
+
+    #[test]
+    fn test_bgra_source_into_rgba_texture() {
+        // 2x1 BGRA image: red, then a half-transparent blue
+        let mut data = vec![0, 0, 255, 255, 255, 0, 0, 128];
+        SourceSwizzle::SwapRedBlue.apply(&mut data, wgt::TextureFormat::Rgba8Unorm).unwrap();
+        assert_eq!(data, [255, 0, 0, 255, 0, 0, 255, 128]);
+
+        SourceSwizzle::None.apply(&mut data, wgt::TextureFormat::Rgba8Unorm).unwrap();
+        assert_eq!(data, [255, 0, 0, 255, 0, 0, 255, 128]);
+
+        // Only 8-bit four channel formats can be swizzled
+        assert!(SourceSwizzle::SwapRedBlue.apply(&mut data, wgt::TextureFormat::Rgba16Float).is_err());
+        assert!(SourceSwizzle::SwapRedBlue.apply(&mut data, wgt::TextureFormat::Bc1RgbaUnorm).is_err());
+        assert!(SourceSwizzle::None.apply(&mut data, wgt::TextureFormat::R8Unorm).is_ok());
+        assert_eq!(SourceSwizzle::from_index(1).unwrap(), SourceSwizzle::SwapRedBlue);
+        assert!(SourceSwizzle::from_index(2).is_err());
+    }
+
+    #[test]
+    fn test_readback_row_layout_follows_aspect() {
+        use wgt::TextureAspect::{All, DepthOnly, StencilOnly};
+        use wgt::TextureFormat::*;
+
+        let layout = |format, aspect, width, height| {
+            let layout = readback_row_layout(format, aspect, width, height).unwrap();
+            (layout.bytes_per_row, layout.padded_bytes_per_row, layout.rows)
+        };
+        assert_eq!(layout(Rgba8Unorm, All, 10, 3), (40, 256, 3));
+        assert_eq!(layout(Rgba8Unorm, All, 64, 3), (256, 256, 3));
+        assert_eq!(layout(Depth32Float, DepthOnly, 1366, 2), (5464, 5632, 2));
+        assert_eq!(layout(Depth16Unorm, All, 10, 3), (20, 256, 3));
+        assert_eq!(layout(Depth24PlusStencil8, StencilOnly, 256, 3), (256, 256, 3));
+        assert_eq!(layout(Bc1RgbaUnorm, All, 6, 6), (16, 256, 2));
+        assert!(!readback_row_layout(Rgba8Unorm, All, 128, 1).unwrap().needs_unpadding());
+
+        // Padded rows are unpadded with buffer copies, which need 4-byte rows
+        let error = readback_row_layout(Depth24PlusStencil8, StencilOnly, 10, 3).unwrap_err();
+        assert!(error.to_string().contains("multiple of 4 texels"), "{}", error);
+        assert!(readback_row_layout(Depth16Unorm, All, 9, 3).is_err());
+
+        let error = readback_row_layout(Depth24Plus, DepthOnly, 10, 3).unwrap_err();
+        assert!(error.to_string().contains("Depth32Float"), "{}", error);
+        assert!(readback_row_layout(Depth32FloatStencil8, All, 10, 3).is_err());
+        assert!(readback_row_layout(Rgba8Unorm, DepthOnly, 10, 3).is_err());
+    }
+
+    #[test]
+    fn test_compatible_view_formats() {
+        let formats = compatible_view_formats(wgt::TextureFormat::Rgba8Unorm);
+        assert_eq!(formats, vec![wgt::TextureFormat::Rgba8Unorm, wgt::TextureFormat::Rgba8UnormSrgb]);
+        assert_eq!(
+            compatible_view_formats(wgt::TextureFormat::Bgra8UnormSrgb),
+            vec![wgt::TextureFormat::Bgra8UnormSrgb, wgt::TextureFormat::Bgra8Unorm]
+        );
+        assert_eq!(compatible_view_formats(wgt::TextureFormat::Rgba16Float), vec![wgt::TextureFormat::Rgba16Float]);
+
+        let names: Vec<String> = formats.iter().map(|f| format!("{:?}", f)).collect();
+        assert_eq!(
+            parse_view_formats(wgt::TextureFormat::Rgba8Unorm, &names).unwrap(),
+            vec![wgt::TextureFormat::Rgba8UnormSrgb]
+        );
+        assert!(parse_view_formats(wgt::TextureFormat::Rgba8Unorm, &["Bgra8UnormSrgb"]).is_err());
+    }
+}
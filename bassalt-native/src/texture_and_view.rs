@@ -42,6 +42,7 @@ pub struct TextureAndView {
 
 impl TextureAndView {
     /// Create a new TextureAndView from separate IDs
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         texture: id::TextureId,
         view: id::TextureViewId,
@@ -113,7 +114,7 @@ impl TextureAndView {
 
         if let Some(e) = view_error {
             // Clean up texture on failure
-            let _ = global.texture_destroy(texture_id);
+            global.texture_destroy(texture_id);
             return Err(crate::error::BasaltError::ResourceCreation {
                 resource_type: "texture view".to_string(),
                 reason: format!("Failed to create texture view for '{}': {:?}", label, e),
@@ -230,7 +231,8 @@ impl TextureAndView {
     pub fn is_depth(&self) -> bool {
         matches!(
             self.format,
-            wgt::TextureFormat::Depth24Plus
+            wgt::TextureFormat::Depth16Unorm
+                | wgt::TextureFormat::Depth24Plus
                 | wgt::TextureFormat::Depth24PlusStencil8
                 | wgt::TextureFormat::Depth32Float
         )
@@ -271,6 +273,7 @@ impl TextureRegistry {
     }
 
     /// Get or create a texture with the given properties
+    #[allow(clippy::too_many_arguments)]
     pub fn get_or_create(
         &self,
         context: &Arc<BasaltContext>,
@@ -34,8 +34,8 @@
 //! // ... do some work ...
 //! queries.write_timestamp(1)?;
 //!
-//! // Resolve and read the timestamps
-//! let timestamps = queries.resolve_and_read(&context, device_id, 0..2)?;
+//! // Resolve and read the timestamps (in nanoseconds) with one map
+//! let timestamps = queries.resolve_and_read(&context, device_id, queue_id, 0..2)?;
 //! let duration_ns = timestamps[1].wrapping_sub(timestamps[0]);
 //! ```
 
@@ -46,6 +46,7 @@ use wgpu_core::id;
 use wgpu_types as wgt;
 
 use crate::error::{BasaltError, Result};
+use crate::query_readback::{validate_query_range, QueryReadback};
 
 /// Global statistics for skipped undersized buffers
 pub static SKIPPED_BUFFER_COUNT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
@@ -306,20 +307,32 @@ impl TimestampQuerySet {
         Ok(timestamps)
     }
 
-    /// Convenience method to resolve and read timestamps
+    /// Resolve timestamps in `range` and read them all back in nanoseconds
     ///
-    /// This combines `resolve()` and `read()` into a single call.
-    /// Note that this requires submitting the command buffer and waiting
-    /// for GPU completion.
+    /// The whole range is resolved, copied and mapped at once, so profiling
+    /// many passes costs one submit and one poll. Call after the commands that
+    /// wrote the timestamps have been submitted.
     pub fn resolve_and_read(
         &self,
         context: &Arc<crate::context::BasaltContext>,
         device_id: id::DeviceId,
+        queue_id: id::QueueId,
         range: std::ops::Range<u32>,
     ) -> Result<Vec<u64>> {
-        // This would need to be called after command buffer submission
-        // For now, delegate to read()
-        self.read(context, device_id, range)
+        let range = validate_query_range(
+            range.start,
+            range.end.saturating_sub(range.start),
+            self.num_queries as u32,
+        )?;
+        let readback = QueryReadback {
+            query_set_id: self.query_set_id,
+            resolve_buffer_id: self.resolve_buffer_id,
+            destination_buffer_id: self.destination_buffer_id,
+            values_per_query: 1,
+        };
+        let ticks = readback.resolve_and_read(context, device_id, queue_id, range, "timestamp")?;
+        let period = Self::get_timestamp_period(context, queue_id)?;
+        Ok(ticks_to_nanoseconds(&ticks, period))
     }
 
     /// Get the timestamp period for the device
@@ -343,6 +356,11 @@ impl TimestampQuerySet {
     }
 }
 
+/// Scale raw timestamp ticks by `period` (ns per tick) to nanoseconds
+pub fn ticks_to_nanoseconds(ticks: &[u64], period: f32) -> Vec<u64> {
+    ticks.iter().map(|&t| (t as f64 * period as f64) as u64).collect()
+}
+
 /// Helper to calculate elapsed time between two timestamps
 ///
 /// # Arguments
@@ -369,4 +387,53 @@ mod tests {
         let elapsed = elapsed_microseconds(start, end, period);
         assert_eq!(elapsed, 1.0); // 1 microsecond
     }
+
+    #[test]
+    fn test_resolve_and_read_many_timestamps() {
+        let context = Arc::new(crate::context::BasaltContext::new_noop());
+        let global = context.inner();
+        let adapter_id = global
+            .request_adapter(&wgpu_core::instance::RequestAdapterOptions::default(), wgt::Backends::NOOP, None)
+            .expect("noop adapter");
+        let (device_id, queue_id) = global
+            .adapter_request_device(
+                adapter_id,
+                &wgt::DeviceDescriptor {
+                    required_features: wgt::Features::TIMESTAMP_QUERY
+                        | wgt::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS,
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .expect("noop device");
+
+        let mut queries = TimestampQuerySet::new(&context, device_id, 8).unwrap();
+        let (encoder_id, error) = global.device_create_command_encoder(
+            device_id,
+            &wgt::CommandEncoderDescriptor { label: None },
+            None,
+        );
+        assert!(error.is_none());
+        for i in 0..6 {
+            queries.write_timestamp(i).unwrap();
+            global
+                .command_encoder_write_timestamp(encoder_id, queries.query_set_id, i)
+                .unwrap();
+        }
+        let (command_buffer, error) = global.command_encoder_finish(
+            encoder_id,
+            &wgt::CommandBufferDescriptor::default(),
+            None,
+        );
+        assert!(error.is_none(), "{:?}", error);
+        global.queue_submit(queue_id, &[command_buffer]).unwrap();
+
+        let timestamps = queries.resolve_and_read(&context, device_id, queue_id, 1..6).unwrap();
+        assert_eq!(timestamps.len(), 5);
+        assert!(queries.resolve_and_read(&context, device_id, queue_id, 4..9).is_err());
+        assert!(queries.resolve_and_read(&context, device_id, queue_id, 3..3).is_err());
+
+        assert_eq!(ticks_to_nanoseconds(&[0, 10, 1000], 2.5), vec![0, 25, 2500]);
+    }
 }
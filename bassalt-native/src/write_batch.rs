@@ -0,0 +1,285 @@
+//! Small CPU-to-buffer writes batched until the next submit
+//!
+//! Every `queue_write_buffer` call allocates its own staging memory, and
+//! Minecraft rewrites small uniform blocks many times per frame. The batch
+//! collects those writes instead: writes to touching or overlapping ranges of
+//! one buffer merge into a single run, later bytes winning, and each run
+//! becomes one `queue_write_buffer` when the batch is flushed before a submit.
+//! When work that must run first is still waiting to be submitted, the batch
+//! is recorded instead: the runs are staged in one buffer and copied by a
+//! command buffer that is submitted after that work.
+//!
+//! Use `copy_buffer_to_buffer` for GPU-to-GPU copies, the staging pool for
+//! large uploads, and this batch for small CPU data such as uniforms.
+
+use std::borrow::Cow;
+
+use parking_lot::Mutex;
+use wgpu_core::id;
+use wgpu_types as wgt;
+
+use crate::context::BasaltContext;
+use crate::error::{BasaltError, Result};
+
+/// Largest write the batch takes; bigger ones are written straight away
+pub const MAX_BATCHED_WRITE: u64 = 4 * 1024;
+
+/// Pending bytes that trigger a flush on the next write
+pub const MAX_BATCHED_BYTES: u64 = 256 * 1024;
+
+/// Contiguous bytes waiting to be written to one buffer
+struct PendingRun {
+    buffer_id: id::BufferId,
+    offset: u64,
+    data: Vec<u8>,
+}
+
+impl PendingRun {
+    fn end(&self) -> u64 {
+        self.offset + self.data.len() as u64
+    }
+}
+
+/// Batch counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteBatchStats {
+    /// Writes taken into the batch
+    pub writes: u64,
+    /// `queue_write_buffer` calls the flushes made
+    pub flushed_runs: u64,
+}
+
+/// Pending buffer writes, merged per buffer
+///
+/// Runs of the same buffer never touch each other; a write bridging two runs
+/// merges all three.
+pub struct WriteBatch {
+    runs: Mutex<Vec<PendingRun>>,
+    stats: Mutex<WriteBatchStats>,
+}
+
+impl WriteBatch {
+    pub fn new() -> Self {
+        Self {
+            runs: Mutex::new(Vec::new()),
+            stats: Mutex::new(WriteBatchStats::default()),
+        }
+    }
+
+    /// Queue `data` for `offset` in `buffer_id`
+    ///
+    /// `offset` and the length must be multiples of 4, as `queue_write_buffer`
+    /// requires; that's checked here rather than failing later in `flush`.
+    pub fn push(&self, buffer_id: id::BufferId, offset: u64, data: &[u8]) -> Result<()> {
+        let len = data.len() as u64;
+        if !offset.is_multiple_of(wgt::COPY_BUFFER_ALIGNMENT) || !len.is_multiple_of(wgt::COPY_BUFFER_ALIGNMENT) {
+            return Err(BasaltError::invalid_parameter(
+                "offset",
+                format!("batched write of {} bytes at {} isn't 4-byte aligned", len, offset),
+            ));
+        }
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let mut runs = self.runs.lock();
+        let end = offset + len;
+        let touching = |run: &PendingRun| run.buffer_id == buffer_id && run.offset <= end && offset <= run.end();
+
+        let (mut start, mut stop) = (offset, end);
+        for run in runs.iter().filter(|run| touching(run)) {
+            start = start.min(run.offset);
+            stop = stop.max(run.end());
+        }
+        let mut merged = PendingRun { buffer_id, offset: start, data: vec![0; (stop - start) as usize] };
+        // Older runs never overlap each other, so only the new bytes need to go last
+        runs.retain(|run| {
+            if !touching(run) {
+                return true;
+            }
+            let at = (run.offset - start) as usize;
+            merged.data[at..at + run.data.len()].copy_from_slice(&run.data);
+            false
+        });
+        let at = (offset - start) as usize;
+        merged.data[at..at + data.len()].copy_from_slice(data);
+        runs.push(merged);
+
+        self.stats.lock().writes += 1;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.runs.lock().is_empty()
+    }
+
+    /// Bytes waiting for the next flush
+    pub fn pending_bytes(&self) -> u64 {
+        self.runs.lock().iter().map(|run| run.data.len() as u64).sum()
+    }
+
+    /// Drop pending writes to a buffer that's being destroyed
+    pub fn discard(&self, buffer_id: id::BufferId) {
+        self.runs.lock().retain(|run| run.buffer_id != buffer_id);
+    }
+
+    /// Write every pending run to its buffer
+    ///
+    /// The writes land before the next submit on `queue_id`, so call this
+    /// before submitting work that reads the buffers. Every run is attempted;
+    /// the first error is returned.
+    pub fn flush(&self, context: &BasaltContext, queue_id: id::QueueId) -> Result<()> {
+        let _serialized = context.serialize_submission();
+        // Held until the writes are queued, so concurrent flushes of the same range stay in order
+        let mut pending = self.runs.lock();
+        let runs = std::mem::take(&mut *pending);
+        if runs.is_empty() {
+            return Ok(());
+        }
+
+        let mut result = Ok(());
+        for run in &runs {
+            if let Err(e) = context.inner().queue_write_buffer(queue_id, run.buffer_id, run.offset, &run.data) {
+                if result.is_ok() {
+                    result = Err(BasaltError::Wgpu(format!("{:?}", e)));
+                }
+            }
+        }
+        self.stats.lock().flushed_runs += runs.len() as u64;
+        result
+    }
+
+    /// Record every pending run as a copy from one staging buffer
+    ///
+    /// Unlike `flush`, the writes are ordered with other command buffers: they
+    /// land when the returned command buffer runs. Returns `None` when nothing
+    /// is pending; on error the pending runs are lost, as with `flush`.
+    pub fn record(&self, context: &BasaltContext, device_id: id::DeviceId) -> Result<Option<id::CommandBufferId>> {
+        let _serialized = context.serialize_submission();
+        // Held until the copies are recorded, as in `flush`
+        let mut pending = self.runs.lock();
+        let runs = std::mem::take(&mut *pending);
+        if runs.is_empty() {
+            return Ok(None);
+        }
+
+        let global = context.inner();
+        let size: u64 = runs.iter().map(|run| run.data.len() as u64).sum();
+        let staging_desc = wgt::BufferDescriptor {
+            label: Some(Cow::Borrowed("Bassalt Write Batch Staging")),
+            size,
+            usage: wgt::BufferUsages::COPY_SRC,
+            mapped_at_creation: true,
+        };
+        let (staging_id, error) = global.device_create_buffer(device_id, &staging_desc, None);
+        if let Some(e) = error {
+            return Err(BasaltError::resource_creation("write batch staging buffer", format!("{:?}", e)));
+        }
+
+        let record = || -> Result<id::CommandBufferId> {
+            let (ptr, _) = global
+                .buffer_get_mapped_range(staging_id, 0, Some(size))
+                .map_err(|e| BasaltError::Generic(format!("Failed to get mapped range: {:?}", e)))?;
+            let mut at = 0;
+            for run in &runs {
+                // SAFETY: the mapped range holds `size` bytes, the sum of the run lengths, until the unmap below
+                unsafe { std::ptr::copy_nonoverlapping(run.data.as_ptr(), ptr.as_ptr().add(at), run.data.len()) };
+                at += run.data.len();
+            }
+            global.buffer_unmap(staging_id).map_err(|e| BasaltError::Wgpu(format!("{:?}", e)))?;
+
+            let encoder_desc = wgt::CommandEncoderDescriptor {
+                label: Some(Cow::Borrowed("Write Batch Encoder")),
+            };
+            let (encoder_id, error) = global.device_create_command_encoder(device_id, &encoder_desc, None);
+            if let Some(e) = error {
+                return Err(BasaltError::Wgpu(format!("{:?}", e)));
+            }
+            let mut at = 0;
+            for run in &runs {
+                let len = run.data.len() as u64;
+                global
+                    .command_encoder_copy_buffer_to_buffer(encoder_id, staging_id, at, run.buffer_id, run.offset, Some(len))
+                    .map_err(|e| BasaltError::wgpu_context("batched write copy", e))?;
+                at += len;
+            }
+            let (command_buffer, error) =
+                global.command_encoder_finish(encoder_id, &wgt::CommandBufferDescriptor::default(), None);
+            if let Some(e) = error {
+                return Err(BasaltError::wgpu_context("batched write", e.1));
+            }
+            Ok(command_buffer)
+        };
+
+        let result = record();
+        // The command buffer keeps the staging buffer alive until its copies run
+        global.buffer_drop(staging_id);
+        if result.is_ok() {
+            self.stats.lock().flushed_runs += runs.len() as u64;
+        }
+        result.map(Some)
+    }
+
+    pub fn stats(&self) -> WriteBatchStats {
+        *self.stats.lock()
+    }
+}
+
+impl Default for WriteBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_writes_merge_per_buffer() {
+        let context = BasaltContext::new_noop();
+        let (device_id, queue_id) = context.create_noop_device();
+        let global = context.inner();
+        let desc = wgt::BufferDescriptor {
+            label: None,
+            size: 256,
+            usage: wgt::BufferUsages::UNIFORM | wgt::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        };
+        let (uniforms, error) = global.device_create_buffer(device_id, &desc, None);
+        assert!(error.is_none());
+        let (other, error) = global.device_create_buffer(device_id, &desc, None);
+        assert!(error.is_none());
+
+        let batch = WriteBatch::new();
+        batch.push(uniforms, 0, &[1; 16]).unwrap();
+        batch.push(uniforms, 32, &[2; 16]).unwrap();
+        // Bridges the two runs and overwrites the end of the first
+        batch.push(uniforms, 8, &[3; 24]).unwrap();
+        batch.push(other, 0, &[4; 16]).unwrap();
+        assert_eq!(batch.pending_bytes(), 48 + 16);
+        {
+            let runs = batch.runs.lock();
+            let merged = runs.iter().find(|run| run.buffer_id == uniforms).unwrap();
+            assert_eq!(merged.offset, 0);
+            assert_eq!(&merged.data[..8], &[1; 8]);
+            assert_eq!(&merged.data[8..32], &[3; 24]);
+            assert_eq!(&merged.data[32..], &[2; 16]);
+        }
+
+        assert!(batch.push(uniforms, 2, &[0; 4]).is_err());
+        batch.discard(other);
+        batch.flush(&context, queue_id).unwrap();
+        assert_eq!(batch.pending_bytes(), 0);
+        assert_eq!(batch.stats(), WriteBatchStats { writes: 4, flushed_runs: 1 });
+
+        // Recording stages the runs into a command buffer for a later submit
+        assert_eq!(batch.record(&context, device_id).unwrap(), None);
+        batch.push(uniforms, 0, &[5; 16]).unwrap();
+        batch.push(other, 64, &[6; 8]).unwrap();
+        let command_buffer = batch.record(&context, device_id).unwrap().unwrap();
+        assert!(batch.is_empty());
+        global.queue_submit(queue_id, &[command_buffer]).unwrap();
+        assert_eq!(batch.stats().flushed_runs, 3);
+    }
+}
@@ -128,6 +128,12 @@ public class BassaltBackend implements GpuBackend {
     public static final int TEXTURE_USAGE_STORAGE_BINDING = 1 << 3;
     public static final int TEXTURE_USAGE_RENDER_ATTACHMENT = 1 << 4;
 
+    /**
+     * Texture dimension constants
+     */
+    public static final int TEXTURE_DIMENSION_2D = 0;
+    public static final int TEXTURE_DIMENSION_3D = 1;
+
     /**
      * Texture format constants
      */
@@ -141,6 +147,39 @@ public class BassaltBackend implements GpuBackend {
     public static final int FORMAT_DEPTH24 = 7;
     public static final int FORMAT_DEPTH32F = 8;
     public static final int FORMAT_DEPTH24_STENCIL8 = 9;
+    /** 16-bit depth, half the bandwidth of FORMAT_DEPTH32F; suited to shadow maps */
+    public static final int FORMAT_DEPTH16 = 10;
+    /** sRGB counterparts of FORMAT_RGBA8/FORMAT_BGRA8, for reinterpreting views */
+    public static final int FORMAT_RGBA8_SRGB = 11;
+    public static final int FORMAT_BGRA8_SRGB = 12;
+    /**
+     * Block-compressed formats. Each needs the matching TEXTURE_COMPRESSION_*
+     * entry in {@code BassaltDevice.getEnabledFeatures()}; data is uploaded as raw blocks.
+     */
+    public static final int FORMAT_BC1_RGBA = 13;
+    public static final int FORMAT_BC1_RGBA_SRGB = 14;
+    public static final int FORMAT_BC3_RGBA = 15;
+    public static final int FORMAT_BC3_RGBA_SRGB = 16;
+    public static final int FORMAT_BC4_R = 17;
+    public static final int FORMAT_BC5_RG = 18;
+    public static final int FORMAT_BC7_RGBA = 19;
+    public static final int FORMAT_BC7_RGBA_SRGB = 20;
+    public static final int FORMAT_ETC2_RGB8 = 21;
+    public static final int FORMAT_ETC2_RGB8_SRGB = 22;
+    public static final int FORMAT_ETC2_RGBA8 = 23;
+    public static final int FORMAT_ETC2_RGBA8_SRGB = 24;
+    public static final int FORMAT_ASTC_4X4 = 25;
+    public static final int FORMAT_ASTC_4X4_SRGB = 26;
+    public static final int FORMAT_ASTC_8X8 = 27;
+    public static final int FORMAT_ASTC_8X8_SRGB = 28;
+
+    /**
+     * Channel reordering applied to texture uploads: SWIZZLE_SWAP_RB swaps red and
+     * blue, e.g. for BGRA image data going into an RGBA texture. Only valid for
+     * 8-bit four channel textures.
+     */
+    public static final int SWIZZLE_NONE = 0;
+    public static final int SWIZZLE_SWAP_RB = 1;
 
     /**
      * Address mode constants
@@ -156,6 +195,28 @@ public class BassaltBackend implements GpuBackend {
     public static final int FILTER_MODE_NEAREST = 0;
     public static final int FILTER_MODE_LINEAR = 1;
 
+    /**
+     * Optional device feature flags for {@link #requestFeatures(long)}
+     */
+    public static final long FEATURE_PUSH_CONSTANTS = 1L << 0;
+    public static final long FEATURE_DEPTH_CLIP_CONTROL = 1L << 1;
+    public static final long FEATURE_TIMESTAMP_QUERY = 1L << 2;
+    public static final long FEATURE_TEXTURE_COMPRESSION_BC = 1L << 3;
+    public static final long FEATURE_POLYGON_MODE_LINE = 1L << 4;
+    public static final long FEATURE_DUAL_SOURCE_BLENDING = 1L << 5;
+    public static final long FEATURE_FLOAT32_FILTERABLE = 1L << 6;
+    public static final long FEATURE_SHADER_F16 = 1L << 7;
+    public static final long FEATURE_INDIRECT_FIRST_INSTANCE = 1L << 8;
+
+    /**
+     * Device limit ids for {@link #overrideLimit(int, long)}
+     */
+    public static final int LIMIT_MAX_PUSH_CONSTANT_SIZE = 0;
+    public static final int LIMIT_MAX_BIND_GROUPS = 1;
+    public static final int LIMIT_MAX_TEXTURE_DIMENSION_2D = 2;
+    public static final int LIMIT_MAX_STORAGE_BUFFER_BINDING_SIZE = 3;
+    public static final int LIMIT_MAX_BUFFER_SIZE = 4;
+
     /**
      * Blend factor constants
      */
@@ -182,6 +243,75 @@ public class BassaltBackend implements GpuBackend {
     public static final int COMPARE_FUNC_GREATER_EQUAL = 6;
     public static final int COMPARE_FUNC_ALWAYS = 7;
 
+    /**
+     * Stencil operation constants
+     */
+    public static final int STENCIL_OP_KEEP = 0;
+    public static final int STENCIL_OP_ZERO = 1;
+    public static final int STENCIL_OP_REPLACE = 2;
+    public static final int STENCIL_OP_INVERT = 3;
+    public static final int STENCIL_OP_INCREMENT_CLAMP = 4;
+    public static final int STENCIL_OP_DECREMENT_CLAMP = 5;
+    public static final int STENCIL_OP_INCREMENT_WRAP = 6;
+    public static final int STENCIL_OP_DECREMENT_WRAP = 7;
+
+    /**
+     * Vertex attribute format constants (custom vertex layouts)
+     */
+    public static final int VERTEX_FORMAT_FLOAT32 = 0;
+    public static final int VERTEX_FORMAT_FLOAT32X2 = 1;
+    public static final int VERTEX_FORMAT_FLOAT32X3 = 2;
+    public static final int VERTEX_FORMAT_FLOAT32X4 = 3;
+    public static final int VERTEX_FORMAT_UINT32 = 4;
+    public static final int VERTEX_FORMAT_SINT32 = 5;
+    public static final int VERTEX_FORMAT_UNORM8X4 = 6;
+    public static final int VERTEX_FORMAT_SNORM8X4 = 7;
+    public static final int VERTEX_FORMAT_UINT8X4 = 8;
+    public static final int VERTEX_FORMAT_SINT8X4 = 9;
+    public static final int VERTEX_FORMAT_UNORM16X2 = 10;
+    public static final int VERTEX_FORMAT_SNORM16X2 = 11;
+    public static final int VERTEX_FORMAT_UINT16X2 = 12;
+    public static final int VERTEX_FORMAT_SINT16X2 = 13;
+    public static final int VERTEX_FORMAT_UNORM16X4 = 14;
+    public static final int VERTEX_FORMAT_SINT16X4 = 15;
+    public static final int VERTEX_FORMAT_FLOAT16X2 = 16;
+    public static final int VERTEX_FORMAT_FLOAT16X4 = 17;
+
+    /**
+     * Vertex buffer step mode constants
+     */
+    public static final int VERTEX_STEP_MODE_VERTEX = 0;
+    public static final int VERTEX_STEP_MODE_INSTANCE = 1;
+
+    /**
+     * Binding type constants (explicit bind group layouts)
+     */
+    public static final int BINDING_TYPE_TEXTURE = 0;
+    public static final int BINDING_TYPE_SAMPLER = 1;
+    public static final int BINDING_TYPE_UNIFORM_BUFFER = 2;
+    public static final int BINDING_TYPE_STORAGE_BUFFER = 3;
+    /** {@code var<storage, read_write>}; only visible to the fragment stage */
+    public static final int BINDING_TYPE_READ_WRITE_STORAGE_BUFFER = 4;
+    /** Uniform buffer whose offset is passed to {@code BassaltRenderPass.setBindGroupDynamic} */
+    public static final int BINDING_TYPE_DYNAMIC_UNIFORM_BUFFER = 5;
+
+    /**
+     * Texture view dimension constants (explicit bind group layouts)
+     */
+    public static final int VIEW_DIMENSION_D1 = 0;
+    public static final int VIEW_DIMENSION_D2 = 1;
+    public static final int VIEW_DIMENSION_D2_ARRAY = 2;
+    public static final int VIEW_DIMENSION_CUBE = 3;
+    public static final int VIEW_DIMENSION_CUBE_ARRAY = 4;
+    public static final int VIEW_DIMENSION_D3 = 5;
+
+    /**
+     * Texture aspect constants for views of depth-stencil textures
+     */
+    public static final int ASPECT_ALL = 0;
+    public static final int ASPECT_DEPTH_ONLY = 1;
+    public static final int ASPECT_STENCIL_ONLY = 2;
+
     /**
      * Primitive topology constants
      */
@@ -196,10 +326,44 @@ public class BassaltBackend implements GpuBackend {
 
     private static native long createDevice(long contextPtr, long windowPtr, long displayPtr, int width, int height);
 
+    private static native long createDeviceOnAdapter(long contextPtr, int adapterIndex, long windowPtr, long displayPtr, int width, int height);
+
+    private static native long createDeviceWithRequest(long contextPtr, int adapterIndex, long features, long[] limitOverrides,
+            long windowPtr, long displayPtr, int width, int height);
+
     private static native String getAdapterInfo(long contextPtr);
 
+    private static native String[] enumerateAdapters(long contextPtr);
+
+    private static native void setStrictValidation0(boolean enabled);
+
+    private static native boolean isStrictValidation0();
+
+    /**
+     * Throw on validation errors instead of logging them and skipping the bad call.
+     *
+     * In strict mode the first validation error raises a {@link BassaltValidationError}
+     * at the offending call, and pipelines with unknown fixed vertex format indices fail to
+     * build instead of falling back to POSITION_TEX_COLOR. Off by default;
+     * {@code BASALT_STRICT=1} enables it at startup.
+     */
+    public static void setStrictValidation(boolean enabled) {
+        setStrictValidation0(enabled);
+    }
+
+    public static boolean isStrictValidation() {
+        return isStrictValidation0();
+    }
+
     private final long contextPtr;
 
+    // Index into enumerateAdapters(), or -1 to let the driver pick
+    private int adapterIndex = Integer.getInteger("bassalt.adapter", -1);
+
+    // FEATURE_* flags and (LIMIT_*, value) pairs required on top of the defaults
+    private long requestedFeatures = 0;
+    private final java.util.Map<Integer, Long> limitOverrides = new java.util.LinkedHashMap<>();
+
     /**
      * Create a new Bassalt backend instance
      */
@@ -217,6 +381,44 @@ public class BassaltBackend implements GpuBackend {
         LOGGER.debug("Backend initialized successfully (contextPtr: {})", contextPtr);
     }
 
+    /**
+     * List the available GPUs as "name (Backend, DeviceType)". A GPU appears once
+     * per backend it supports, so e.g. Vulkan and OpenGL can be picked separately.
+     */
+    public String[] getAdapters() {
+        return enumerateAdapters(contextPtr);
+    }
+
+    /**
+     * Create the device on the adapter at {@code index} in {@link #getAdapters()},
+     * or pass -1 to pick the high-performance adapter automatically (the default,
+     * overridable with {@code -Dbassalt.adapter=N}).
+     */
+    public void setAdapterIndex(int index) {
+        this.adapterIndex = index;
+    }
+
+    public int getAdapterIndex() {
+        return adapterIndex;
+    }
+
+    /**
+     * Require the given {@code FEATURE_*} flags on the device. Device creation
+     * fails if the adapter lacks any of them. Requesting
+     * {@link #FEATURE_PUSH_CONSTANTS} without a size override uses a 128 byte range.
+     */
+    public void requestFeatures(long features) {
+        this.requestedFeatures |= features;
+    }
+
+    /**
+     * Raise a {@code LIMIT_*} device limit above Bassalt's default. Device creation
+     * fails if the adapter cannot provide it.
+     */
+    public void overrideLimit(int limit, long value) {
+        limitOverrides.put(limit, value);
+    }
+
     @Override
     public String getName() {
         return "Bassalt (WebGPU)";
@@ -281,10 +483,24 @@ public class BassaltBackend implements GpuBackend {
             }
         }
 
-        LOGGER.debug("Calling native createDevice: contextPtr={}, window={}, display={}, size={}x{}",
-                contextPtr, nativeWindowPtr, displayPtr, width, height);
+        LOGGER.debug("Calling native createDevice: contextPtr={}, adapter={}, window={}, display={}, size={}x{}",
+                contextPtr, adapterIndex, nativeWindowPtr, displayPtr, width, height);
 
-        long devicePtr = createDevice(contextPtr, nativeWindowPtr, displayPtr, width, height);
+        long devicePtr;
+        if (requestedFeatures != 0 || !limitOverrides.isEmpty()) {
+            long[] limits = new long[limitOverrides.size() * 2];
+            int i = 0;
+            for (java.util.Map.Entry<Integer, Long> entry : limitOverrides.entrySet()) {
+                limits[i++] = entry.getKey();
+                limits[i++] = entry.getValue();
+            }
+            devicePtr = createDeviceWithRequest(contextPtr, adapterIndex, requestedFeatures, limits,
+                    nativeWindowPtr, displayPtr, width, height);
+        } else {
+            devicePtr = adapterIndex >= 0
+                    ? createDeviceOnAdapter(contextPtr, adapterIndex, nativeWindowPtr, displayPtr, width, height)
+                    : createDevice(contextPtr, nativeWindowPtr, displayPtr, width, height);
+        }
 
         LOGGER.debug("createDevice returned: {}", devicePtr);
 
@@ -293,6 +509,9 @@ public class BassaltBackend implements GpuBackend {
             throw new BackendCreationException("Failed to create Bassalt device");
         }
 
+        // Logs the adapter and backend the device ended up on (BASALT_BACKEND limits the choice)
+        getAdapterInfo(contextPtr);
+
         BassaltDevice device = new BassaltDevice(devicePtr, defaultShaderSource);
         return new WindowAndDevice(window, device);
     }
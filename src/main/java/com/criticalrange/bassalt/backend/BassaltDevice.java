@@ -8,9 +8,15 @@ import com.mojang.blaze3d.shaders.ShaderSource;
 import com.mojang.blaze3d.systems.CommandEncoder;
 import com.mojang.blaze3d.systems.GpuDevice;
 import com.mojang.blaze3d.textures.*;
+import com.criticalrange.bassalt.pipeline.BassaltBindGroupEntry;
+import com.criticalrange.bassalt.pipeline.BassaltBindGroupLayoutSpec;
 import com.criticalrange.bassalt.pipeline.BassaltCommandEncoder;
 import com.criticalrange.bassalt.pipeline.BassaltCompiledRenderPipeline;
+import com.criticalrange.bassalt.pipeline.BassaltPipelineOptions;
+import com.criticalrange.bassalt.pipeline.BassaltStencilState;
+import com.criticalrange.bassalt.pipeline.BassaltVertexLayout;
 import com.criticalrange.bassalt.buffer.BassaltBuffer;
+import com.criticalrange.bassalt.sync.BassaltPipelineStatsQuerySet;
 import com.criticalrange.bassalt.texture.BassaltSampler;
 import com.criticalrange.bassalt.texture.BassaltTexture;
 import com.criticalrange.bassalt.texture.BassaltTextureView;
@@ -36,6 +42,46 @@ public class BassaltDevice implements GpuDevice {
 
     private static final Logger LOGGER = LogManager.getLogger("Bassalt");
 
+    /** Pipeline target format meaning "render into the swapchain format". */
+    public static final int TARGET_FORMAT_SWAPCHAIN = -1;
+
+    /** Pipeline depth format meaning "pick from the depth and stencil state". */
+    private static final int DEPTH_FORMAT_AUTO = -1;
+
+    /**
+     * Index types a pipeline is drawn with, for strip primitive restart. Strip topologies
+     * restart the strip at the all-ones index (0xFFFF / 0xFFFFFFFF); list topologies ignore it.
+     */
+    public static final int INDEX_FORMAT_UNKNOWN = -1;
+    public static final int INDEX_FORMAT_UINT16 = 0;
+    public static final int INDEX_FORMAT_UINT32 = 1;
+
+    /**
+     * Pipeline rasterization modes. Line (wireframe) and point are debugging aids that need
+     * optional device features; without them pipelines fall back to fill with a warning.
+     */
+    public static final int POLYGON_MODE_FILL = 0;
+    public static final int POLYGON_MODE_LINE = 1;
+    public static final int POLYGON_MODE_POINT = 2;
+
+    /**
+     * Native present outcomes. An outdated or lost swapchain, routine after a resize or a
+     * display change, is reconfigured instead of failing; the frame may be dropped then.
+     */
+    public static final int PRESENT_FAILED = -1;
+    public static final int PRESENT_OK = 0;
+    public static final int PRESENT_SKIPPED = 1;
+    public static final int PRESENT_RECONFIGURED = 2;
+
+    /** Error scope filters for {@link #pushErrorScope(int)}. */
+    public static final int ERROR_FILTER_VALIDATION = 0;
+    public static final int ERROR_FILTER_OUT_OF_MEMORY = 1;
+    public static final int ERROR_FILTER_INTERNAL = 2;
+
+    /** Shader stage bits for {@link #setPushConstants(long, long, int, int, byte[])}. */
+    public static final int SHADER_STAGE_VERTEX = 1;
+    public static final int SHADER_STAGE_FRAGMENT = 2;
+
     // Native method declarations
     private static native String getImplementationInfo(long ptr);
 
@@ -53,35 +99,286 @@ public class BassaltDevice implements GpuDevice {
 
     private static native void setVsync(long ptr, boolean enabled);
 
-    private static native void presentFrame(long ptr);
+    private static native int presentFrame(long ptr);
+
+    private static native String getCpuTimingsJson(long ptr);
+
+    // Every wgpu device limit, keyed by limit name
+    private static native String getLimitsJson(long ptr);
+
+    private static native void resolveMsaaRegion0(long ptr, long srcTexture, long dstTexture,
+                                                  int x, int y, int width, int height);
+
+    private static native void drawLines0(long ptr, long colorTexture, long depthTexture, long vertexBuffer,
+                                          int firstVertex, int vertexCount, float[] viewProj);
 
     private static native void close(long ptr);
 
     // Buffer operations
-    private static native long createBufferEmpty(long ptr, long size, int usage);
+    // label names the resource in GPU captures (RenderDoc, PIX); null keeps the native default
+    private static native long createBufferEmpty(long ptr, long size, int usage, boolean frequentlyUpdated,
+            String label);
+
+    private static native long createBufferData(long ptr, byte[] data, int usage, boolean frequentlyUpdated);
 
-    private static native long createBufferData(long ptr, byte[] data, int usage);
+    private static native long createBufferDataDirect0(long ptr, ByteBuffer data, int length, int usage,
+            boolean frequentlyUpdated);
 
     public static void writeBuffer(long ptr, long bufferPtr, byte[] data, long offset) {
         writeBuffer0(ptr, bufferPtr, data, offset);
     }
 
+    /**
+     * Write the remaining bytes of {@code data} to a buffer, consuming them.
+     *
+     * Direct buffers are read in place by the native side; heap buffers are
+     * copied to an array first.
+     */
+    public static void writeBuffer(long ptr, long bufferPtr, ByteBuffer data, long offset) {
+        int length = data.remaining();
+        if (data.isDirect()) {
+            writeBufferDirect0(ptr, bufferPtr, data.slice(data.position(), length), offset, length);
+            data.position(data.limit());
+        } else {
+            byte[] arr = new byte[length];
+            data.get(arr);
+            writeBuffer0(ptr, bufferPtr, arr, offset);
+        }
+    }
+
+    /**
+     * Write a small uniform update, batched with other updates until the next render pass is submitted.
+     *
+     * Cheaper than {@link #writeBuffer} for the many tiny per-draw uniform writes, since updates
+     * to neighbouring ranges of a buffer are merged into one upload. Offset and length must be
+     * multiples of 4; heap buffers and writes over 4 KiB go through {@link #writeBuffer}.
+     */
+    public static void updateUniform(long ptr, long bufferPtr, ByteBuffer data, long offset) {
+        int length = data.remaining();
+        if (!data.isDirect() || length > MAX_BATCHED_UNIFORM_WRITE) {
+            writeBuffer(ptr, bufferPtr, data, offset);
+            return;
+        }
+        updateUniformDirect0(ptr, bufferPtr, data.slice(data.position(), length), offset, length);
+        data.position(data.limit());
+    }
+
+    /** Largest write {@link #updateUniform} batches; matches the native batch limit */
+    private static final int MAX_BATCHED_UNIFORM_WRITE = 4 * 1024;
+
     private static native void writeBuffer0(long ptr, long bufferPtr, byte[] data, long offset);
 
+    private static native void updateUniformDirect0(long ptr, long bufferPtr, ByteBuffer data, long offset, int length);
+
+    private static native void writeBufferDirect0(long ptr, long bufferPtr, ByteBuffer data, long offset, int length);
+
     private static native void destroyBuffer(long ptr, long bufferPtr);
 
+    /**
+     * Suballocate {@code size} bytes from a pool buffer, so many meshes can share one large buffer.
+     *
+     * Freed neighbouring ranges are merged, so the pool can be reused at varying sizes.
+     *
+     * @param align power-of-two alignment of the offset, 0 for the minimum of 4
+     * @return offset of the range inside the buffer
+     * @throws OutOfMemoryError if no free range of the pool fits
+     */
+    public static long allocRange(long ptr, long bufferPtr, long size, long align) {
+        return allocRange0(ptr, bufferPtr, size, align);
+    }
+
+    /** Return a range {@link #allocRange} handed out at {@code offset} to its pool */
+    public static void freeRange(long ptr, long bufferPtr, long offset) {
+        freeRange0(ptr, bufferPtr, offset);
+    }
+
+    /**
+     * Move a pool buffer's live ranges towards its start so freed holes merge into one range.
+     *
+     * Moves at most {@code maxBytes} per call; call again until it returns an empty array.
+     *
+     * @return {@code [oldOffset, newOffset, size]} triples; cached offsets must be rewritten
+     */
+    public static long[] compactPool(long ptr, long bufferPtr, long maxBytes) {
+        return compactPool0(ptr, bufferPtr, maxBytes);
+    }
+
+    /**
+     * Occupancy of a pool buffer.
+     *
+     * @return {@code [capacity, used, free, largestFree, allocations, needsCompaction]}, where
+     *         needsCompaction is 1 when {@link #compactPool} is worth running; null if nothing
+     *         was ever allocated from the buffer
+     */
+    public static long @Nullable [] getPoolStats(long ptr, long bufferPtr) {
+        return getPoolStats0(ptr, bufferPtr);
+    }
+
+    /** Atlas policy: {@link #atlasInsert} fails when the sprite doesn't fit */
+    public static final int ATLAS_EVICT_REJECT = 0;
+    /** Atlas policy: {@link #atlasInsert} evicts least recently used sprites until it fits */
+    public static final int ATLAS_EVICT_LRU = 1;
+
+    /**
+     * Create an atlas allocator that places sprites inside a {@code width} x {@code height} texture.
+     *
+     * Only rectangles are tracked; the caller owns the texture and uploads each sprite where
+     * {@link #atlasInsert} puts it.
+     *
+     * @param policy {@link #ATLAS_EVICT_REJECT} or {@link #ATLAS_EVICT_LRU}
+     * @return id of the atlas
+     */
+    public static long atlasCreate(long ptr, int width, int height, int policy) {
+        return atlasCreate0(ptr, width, height, policy);
+    }
+
+    /**
+     * Place a {@code width} x {@code height} sprite in an atlas.
+     *
+     * @return {@code [entryId, x, y]} followed by {@code [id, x, y, width, height]} for each sprite
+     *         evicted to make room; entryId is 0 when the sprite doesn't fit, so the caller can
+     *         grow or reset the atlas. Evicted sprites' regions may be reused by this or later inserts.
+     */
+    public static long[] atlasInsert(long ptr, long atlasId, int width, int height) {
+        return atlasInsert0(ptr, atlasId, width, height);
+    }
+
+    /**
+     * Mark a sprite as used so an LRU atlas evicts it last.
+     *
+     * @return false if the sprite was already evicted
+     */
+    public static boolean atlasTouch(long ptr, long atlasId, long entryId) {
+        return atlasTouch0(ptr, atlasId, entryId);
+    }
+
+    /**
+     * Free the sprite occupying exactly the given rectangle of an atlas.
+     *
+     * @return true if a sprite was evicted
+     */
+    public static boolean atlasEvict(long ptr, long atlasId, int x, int y, int width, int height) {
+        return atlasEvict0(ptr, atlasId, x, y, width, height);
+    }
+
+    /**
+     * Compact an atlas and move its live sprites inside {@code textureHandle}. If the texel
+     * copies fail this throws and the atlas keeps its old layout.
+     *
+     * @return {@code [entryId, x, y, width, height]} for every sprite that moved; sprites that
+     *         no longer fit have x = y = -1 and must be re-inserted
+     */
+    public static long[] atlasRepack(long ptr, long atlasId, long textureHandle) {
+        return atlasRepack0(ptr, atlasId, textureHandle);
+    }
+
+    /** Free every sprite of an atlas */
+    public static void atlasReset(long ptr, long atlasId) {
+        atlasReset0(ptr, atlasId);
+    }
+
+    public static void atlasDestroy(long ptr, long atlasId) {
+        atlasDestroy0(ptr, atlasId);
+    }
+
+    private static native long allocRange0(long ptr, long bufferPtr, long size, long align);
+
+    private static native void freeRange0(long ptr, long bufferPtr, long offset);
+
+    private static native long[] compactPool0(long ptr, long bufferPtr, long maxBytes);
+
+    private static native long @Nullable [] getPoolStats0(long ptr, long bufferPtr);
+
+    private static native long atlasCreate0(long ptr, int width, int height, int policy);
+
+    private static native long[] atlasInsert0(long ptr, long atlasId, int width, int height);
+
+    private static native boolean atlasTouch0(long ptr, long atlasId, long entryId);
+
+    private static native boolean atlasEvict0(long ptr, long atlasId, int x, int y, int width, int height);
+
+    private static native long[] atlasRepack0(long ptr, long atlasId, long textureHandle);
+
+    private static native void atlasReset0(long ptr, long atlasId);
+
+    private static native void atlasDestroy0(long ptr, long atlasId);
+
     // Texture operations
+    // dimension is a BassaltBackend.TEXTURE_DIMENSION_* constant; isCube needs 6 square 2D layers
     private static native long createTexture(long ptr, int width, int height, int depth,
-            int mipLevels, int format, int usage);
+            int mipLevels, int format, int usage, int sampleCount, int dimension, boolean isCube, String label);
+
+    private static native long createTextureWithViewFormats0(long ptr, int width, int height, int depth,
+            int mipLevels, int format, int usage, int sampleCount, String[] viewFormats);
+
+    private static native String[] getCompatibleViewFormats0(long ptr, int format);
 
     private static native long createTextureView(long ptr, long texturePtr);
 
+    // View of a mip/layer range; -1 dimension/format/aspect derive from the texture
+    private static native long createTextureViewRanged0(long ptr, long texturePtr, int baseMip, int mipCount,
+            int baseLayer, int layerCount, int viewDimension, int format, int aspect);
+
     private static native void destroyTexture(long ptr, long texturePtr);
 
     // Sampler operations
     private static native long createSampler(long ptr, int addressModeU, int addressModeV, int addressModeW,
             int minFilter, int magFilter, int mipmapFilter,
-            float lodMinClamp, float lodMaxClamp, int maxAnisotropy);
+            float lodMinClamp, float lodMaxClamp, int maxAnisotropy, int compareFunction, String label);
+
+    // Comparison sampler for PCF shadow maps (linear, clamp-to-edge, LessEqual)
+    private static native long createShadowSampler0(long ptr);
+
+    // Cached sampler for a filtering setup; common setups are created with the device
+    private static native long getSampler0(long ptr, int addressMode, boolean linear, boolean linearMipmaps,
+            int anisotropy);
+
+    // Sampler cache statistics: [hits, misses, totalSamplers]
+    private static native long[] getSamplerCacheStats0(long ptr);
+
+    private static native long[] getBindGroupCacheStats0(long ptr);
+
+    // Live handle counts: [buffers, textures, textureViews, samplers, bindGroups,
+    // bindGroupLayouts, renderPipelines, commandEncoders]
+    private static native long[] getResourceCounts0(long ptr);
+
+    // Set once a queue submit reports device loss
+    private static native boolean isDeviceLost0(long ptr);
+
+    private static native long submitAndGetSubmissionIndex0(long ptr);
+
+    private static native boolean isSubmissionComplete0(long ptr, long submissionIndex);
+
+    private static native void setForceOpaquePresent0(long ptr, boolean enabled);
+
+    private static native boolean isForceOpaquePresent0(long ptr);
+
+    private static native void setSrgbClearColors0(long ptr, boolean enabled);
+
+    private static native boolean setHdrEnabled0(long ptr, boolean enabled);
+
+    private static native boolean isHdrEnabled0(long ptr);
+
+    private static native String[] getSurfaceFormats0(long ptr);
+
+    private static native String[] getSurfacePresentModes0(long ptr);
+
+    private static native String[] getSurfaceAlphaModes0(long ptr);
+
+    private static native boolean supportsPushConstants0(long ptr);
+
+    private static native void setSerializedSubmission0(long ptr, boolean enabled);
+
+    private static native boolean isSerializedSubmission0(long ptr);
+
+    // Last error from a per-draw call on the calling thread, cleared on read
+    private static native String getLastError0();
+
+    private static native boolean poll0(long ptr, boolean wait);
+
+    private static native void pushErrorScope0(long ptr, int filter);
+
+    private static native String popErrorScope0(long ptr);
 
     // Pipeline operations
     private static native long createRenderPipeline(long ptr, String vertexShader, String fragmentShader,
@@ -91,13 +388,51 @@ public class BassaltDevice implements GpuDevice {
             int blendColorFactor, int blendAlphaFactor);
 
     // Create pipeline from pre-converted WGSL (for offline shader conversion)
+    // Every pipeline creator takes the packed state from pipelineState(...) plus the trailing
+    // shaderName/background/constant arguments; shaderName also labels the pipeline in GPU captures
     private static native long createNativePipelineFromWgsl(long ptr, String vertexWgsl, String fragmentWgsl,
-            int vertexFormat, int primitiveTopology,
-            boolean depthTestEnabled, boolean depthWriteEnabled,
-            int depthCompare, boolean blendEnabled,
-            int blendSrcColorFactor, int blendDstColorFactor,
-            int blendSrcAlphaFactor, int blendDstAlphaFactor,
-            String shaderName);
+            int vertexFormat, int[] state,
+            String shaderName, boolean background, String[] constantNames, double[] constantValues);
+
+    private static native boolean isPipelineReady0(long ptr, long pipelineHandle);
+
+    private static native long[] createDepthPrepassPipelines0(long ptr, long pipelineHandle);
+
+    // Create pipeline from WGSL with a caller-described vertex layout (parallel buffer/attribute arrays)
+    private static native long createNativePipelineFromWgslCustomVertex(long ptr, String vertexWgsl, String fragmentWgsl,
+            long[] bufferStrides, int[] bufferStepModes,
+            int[] attributeBuffers, int[] attributeLocations, int[] attributeFormats, long[] attributeOffsets,
+            int[] state, String shaderName, boolean background, String[] constantNames, double[] constantValues);
+
+    // Create pipeline from WGSL writing several color targets (MRT); blend factors are 4 per target
+    private static native long createNativePipelineFromWgslMRT(long ptr, String vertexWgsl, String fragmentWgsl,
+            int vertexFormat, int[] targetFormats, int[] targetBlendEnabled, int[] targetBlendFactors,
+            int[] state, String shaderName, boolean background, String[] constantNames, double[] constantValues);
+
+    // Create pipeline from WGSL with a fixed vertex format plus a per-instance buffer in slot 1
+    private static native long createNativePipelineFromWgslInstanced(long ptr, String vertexWgsl, String fragmentWgsl,
+            int vertexFormat, long instanceStride,
+            int[] instanceLocations, int[] instanceFormats, long[] instanceOffsets,
+            int[] state, String shaderName, boolean background, String[] constantNames, double[] constantValues);
+
+    // Explicit bind group layouts and bind groups (parallel arrays, bypass reflection name matching)
+    private static native long createBindGroupLayout0(long ptr, int[] bindings, int[] types, int[] dimensions,
+            long[] minBindingSizes);
+
+    private static native long createBindGroupExplicit0(long ptr, long layoutHandle, int[] bindings,
+            long[] resources, long[] offsets, long[] sizes);
+
+    private static native long getPipelineBindGroupLayout0(long ptr, long pipelineHandle, int group);
+
+    // Create pipeline from pre-compiled SPIR-V (translated to WGSL natively)
+    private static native long createNativePipelineFromSpirv(long ptr, byte[] vertexSpirv, byte[] fragmentSpirv,
+            int vertexFormat, int[] state,
+            String shaderName, boolean background, String[] constantNames, double[] constantValues);
+
+    // Check a pipeline configuration without creating GPU objects; returns "" or the first problem
+    private static native String validatePipelineConfig(long ptr, String vertexWgsl, String fragmentWgsl,
+            int vertexFormat, int[] state,
+            String shaderName, boolean background, String[] constantNames, double[] constantValues);
 
     // Render pass operations
     public static native long beginRenderPass(long ptr, long colorTexture, long depthTexture,
@@ -105,8 +440,32 @@ public class BassaltDevice implements GpuDevice {
             boolean shouldClearDepth, float clearDepth, int clearStencil,
             int width, int height);
 
+    // MSAA render pass: colorTexture is multisampled and resolved into resolveTexture
+    public static native long beginRenderPassMSAA(long ptr, long colorTexture, long resolveTexture,
+            long depthTexture, boolean shouldClearColor, int clearColor,
+            boolean shouldClearDepth, float clearDepth, int clearStencil,
+            int width, int height);
+
+    // Render pass with an occlusion query set attached (occlusionQueries is a BassaltOcclusionQuerySet pointer)
+    public static native long beginRenderPassOcclusion(long ptr, long colorTexture, long depthTexture,
+            long occlusionQueries, boolean shouldClearColor, int clearColor,
+            boolean shouldClearDepth, float clearDepth, int clearStencil,
+            int width, int height);
+
+    // Render pass with several color attachments (MRT); colorTextures[i] is bound to @location(i)
+    public static native long beginRenderPassMRT(long ptr, long[] colorTextures, long depthTexture,
+            boolean shouldClearColor, int clearColor,
+            boolean shouldClearDepth, float clearDepth, int clearStencil,
+            int width, int height);
+
     public static native void setPipeline(long ptr, long renderPass, long pipeline);
 
+    private static native int getLiveRenderPassCount0();
+
+    private static native void setRenderPassLeakThreshold0(int threshold);
+
+    public static native void setStencilReference(long ptr, long renderPass, int reference);
+
     // Buffer operations - duplicate declarations removed, moved above
 
     private final long nativePtr;
@@ -157,11 +516,38 @@ public class BassaltDevice implements GpuDevice {
                 toBassaltFilterMode(minFilter), // mipmapFilter - use minFilter for now
                 0.0f, // lodMinClamp
                 (float) maxLod.orElse(1000.0), // lodMaxClamp
-                maxAnisotropy);
+                maxAnisotropy,
+                -1, // compareFunction - no depth comparison
+                null);
         return new BassaltSampler(ptr, addressModeU, addressModeV, minFilter, magFilter,
                 maxAnisotropy, maxLod.orElse(1000.0));
     }
 
+    /**
+     * Create a comparison sampler for percentage-closer filtered shadows: linear
+     * filtering, clamp-to-edge addressing and a LessEqual compare. Bind it to a
+     * {@code sampler_comparison} next to the shadow map's {@code texture_depth_2d}.
+     */
+    public GpuSampler createShadowSampler() {
+        long ptr = createShadowSampler0(nativePtr);
+        return new BassaltSampler(ptr, AddressMode.CLAMP_TO_EDGE, AddressMode.CLAMP_TO_EDGE,
+                FilterMode.LINEAR, FilterMode.LINEAR, 1, 32.0);
+    }
+
+    /**
+     * Get a shared sampler for a filtering setup, e.g. to drop anisotropy for UI drawn over
+     * the world. Nearest, bilinear and trilinear filtering with 1x to 16x anisotropy are
+     * created with the device, so swapping between them doesn't create sampler objects;
+     * identical setups always return the same native sampler.
+     *
+     * @param anisotropy 1 to 16; above 1 needs linear filtering and linear mipmaps
+     */
+    public GpuSampler getSampler(AddressMode addressMode, FilterMode filter, boolean linearMipmaps, int anisotropy) {
+        long ptr = getSampler0(nativePtr, toBassaltAddressMode(addressMode), filter == FilterMode.LINEAR,
+                linearMipmaps, anisotropy);
+        return new BassaltSampler(ptr, addressMode, addressMode, filter, filter, anisotropy, 32.0);
+    }
+
     @Override
     public GpuTexture createTexture(
             @Nullable Supplier<String> label,
@@ -174,9 +560,12 @@ public class BassaltDevice implements GpuDevice {
         int basaltFormat = toBassaltTextureFormat(format);
         int basaltUsage = toBassaltTextureUsage(usage);
 
-        long ptr = createTexture(nativePtr, width, height, depthOrLayers, mipLevels, basaltFormat, basaltUsage);
-        String labelStr = label != null ? label.get() : "BassaltTexture";
-        return new BassaltTexture(this, ptr, usage, labelStr, format, width, height, depthOrLayers, mipLevels);
+        boolean isCube = (usage & GpuTexture.USAGE_CUBEMAP_COMPATIBLE) != 0;
+        String labelStr = label != null ? label.get() : null;
+        long ptr = createTexture(nativePtr, width, height, depthOrLayers, mipLevels, basaltFormat, basaltUsage, 1,
+                BassaltBackend.TEXTURE_DIMENSION_2D, isCube, labelStr);
+        return new BassaltTexture(this, ptr, usage, labelStr != null ? labelStr : "BassaltTexture", format, width,
+                height, depthOrLayers, mipLevels);
     }
 
     @Override
@@ -192,6 +581,55 @@ public class BassaltDevice implements GpuDevice {
                 mipLevels);
     }
 
+    /**
+     * Create a 3D texture (3D noise, color-grading LUTs) {@code depth} texels deep.
+     * Its views are 3D views; write it one depth slice at a time by passing the slice
+     * as {@code depthOrLayer} to {@link BassaltCommandEncoder#writeToTexture}.
+     */
+    public GpuTexture createTexture3D(String label, int usage, TextureFormat format, int width, int height,
+            int depth, int mipLevels) {
+        long ptr = createTexture(nativePtr, width, height, depth, mipLevels, toBassaltTextureFormat(format),
+                toBassaltTextureUsage(usage), 1, BassaltBackend.TEXTURE_DIMENSION_3D, false, label);
+        return new BassaltTexture(this, ptr, usage, label, format, width, height, depth, mipLevels);
+    }
+
+    /**
+     * Formats a texture of the given {@code BassaltBackend.FORMAT_*} format may be
+     * viewed as: the texture's own format followed by its sRGB or linear counterpart,
+     * if it has one. Pass a subset to {@link #createTextureWithViewFormats}.
+     */
+    public String[] getCompatibleViewFormats(int format) {
+        return getCompatibleViewFormats0(nativePtr, format);
+    }
+
+    /**
+     * Create a texture whose views may use only the listed formats, taken from
+     * {@link #getCompatibleViewFormats}. {@link #createTexture} allows the
+     * sRGB/linear counterpart by default.
+     */
+    public GpuTexture createTextureWithViewFormats(String label, int usage, TextureFormat format, int width,
+            int height, int depthOrLayers, int mipLevels, String[] viewFormats) {
+        long ptr = createTextureWithViewFormats0(nativePtr, width, height, depthOrLayers, mipLevels,
+                toBassaltTextureFormat(format), toBassaltTextureUsage(usage), 1, viewFormats);
+        return new BassaltTexture(this, ptr, usage, label, format, width, height, depthOrLayers, mipLevels);
+    }
+
+    /**
+     * Create a sampled texture in a block-compressed format ({@code BassaltBackend.FORMAT_BC*},
+     * {@code FORMAT_ETC2*} or {@code FORMAT_ASTC*}). Fill it with
+     * {@link BassaltCommandEncoder#writeCompressedToTexture}. Throws if the format's
+     * compression feature isn't enabled on this device.
+     */
+    public GpuTexture createCompressedTexture(String label, int compressedFormat, int width, int height,
+            int depthOrLayers, int mipLevels) {
+        int usage = GpuTexture.USAGE_TEXTURE_BINDING | GpuTexture.USAGE_COPY_DST;
+        long ptr = createTexture(nativePtr, width, height, depthOrLayers, mipLevels, compressedFormat,
+                toBassaltTextureUsage(usage), 1, BassaltBackend.TEXTURE_DIMENSION_2D, false, label);
+        // The Minecraft-side format is only metadata; the native texture keeps the compressed format
+        return new BassaltTexture(this, ptr, usage, label, TextureFormat.RGBA8, width, height, depthOrLayers,
+                mipLevels);
+    }
+
     @Override
     public GpuTextureView createTextureView(GpuTexture texture) {
         BassaltTexture basaltTexture = (BassaltTexture) texture;
@@ -206,18 +644,59 @@ public class BassaltDevice implements GpuDevice {
         return new BassaltTextureView(texture, ptr, baseMipLevel, mipLevels);
     }
 
+    /**
+     * Create a view of a mip/layer range of a texture, e.g. a single cubemap
+     * face or a single mip for manual mip generation. Counts of 0 select every
+     * remaining mip or layer.
+     *
+     * @param viewDimension a {@code BassaltBackend.VIEW_DIMENSION_*} constant, or -1
+     *        for D2 (one layer) / D2_ARRAY (several layers)
+     * @param format a {@code BassaltBackend.FORMAT_*} constant, or -1 for the texture's
+     *        format. May be the sRGB/linear counterpart of the texture format, or the
+     *        depth format of a depth-stencil texture to view its depth aspect
+     */
+    public GpuTextureView createTextureViewRanged(GpuTexture texture, int baseMipLevel, int mipLevels,
+            int baseLayer, int layerCount, int viewDimension, int format) {
+        return createTextureViewRanged(texture, baseMipLevel, mipLevels, baseLayer, layerCount, viewDimension,
+                format, -1);
+    }
+
+    /**
+     * Like {@link #createTextureViewRanged(GpuTexture, int, int, int, int, int, int)} with an
+     * explicit aspect. A depth-stencil texture needs a depth-only or stencil-only view to be
+     * sampled; a stencil-only view is read in WGSL as {@code texture_2d<u32>}.
+     *
+     * @param aspect a {@code BassaltBackend.ASPECT_*} constant, or -1 to derive it from the format
+     */
+    public GpuTextureView createTextureViewRanged(GpuTexture texture, int baseMipLevel, int mipLevels,
+            int baseLayer, int layerCount, int viewDimension, int format, int aspect) {
+        BassaltTexture basaltTexture = (BassaltTexture) texture;
+        long ptr = createTextureViewRanged0(nativePtr, basaltTexture.getNativePtr(), baseMipLevel, mipLevels,
+                baseLayer, layerCount, viewDimension, format, aspect);
+        return new BassaltTextureView(texture, ptr, baseMipLevel, mipLevels);
+    }
+
     @Override
     public GpuBuffer createBuffer(@Nullable Supplier<String> label, int usage, long size) {
-        long ptr = createBufferEmpty(nativePtr, size, toBassaltBufferUsage(usage));
+        long ptr = createBufferEmpty(nativePtr, size, toBassaltBufferUsage(usage), isFrequentlyUpdated(usage),
+                label != null ? label.get() : null);
         return new BassaltBuffer(this, ptr, usage, size);
     }
 
     @Override
     public GpuBuffer createBuffer(@Nullable Supplier<String> label, int usage, ByteBuffer data) {
-        byte[] arr = new byte[data.remaining()];
-        data.get(arr);
-        long ptr = createBufferData(nativePtr, arr, toBassaltBufferUsage(usage));
-        return new BassaltBuffer(this, ptr, usage, arr.length);
+        int length = data.remaining();
+        long ptr;
+        if (data.isDirect()) {
+            ptr = createBufferDataDirect0(nativePtr, data.slice(data.position(), length), length,
+                toBassaltBufferUsage(usage), isFrequentlyUpdated(usage));
+            data.position(data.limit());
+        } else {
+            byte[] arr = new byte[length];
+            data.get(arr);
+            ptr = createBufferData(nativePtr, arr, toBassaltBufferUsage(usage), isFrequentlyUpdated(usage));
+        }
+        return new BassaltBuffer(this, ptr, usage, length);
     }
 
     @Override
@@ -317,8 +796,7 @@ public class BassaltDevice implements GpuDevice {
         int blendDstAlphaFactor = blendEnabled ? getBlendFactorIndex(pipeline.getBlendFunction().get().destAlpha()) : 0;
 
         // Create the native pipeline from WGSL
-        long nativePipelinePtr = createNativePipelineFromWgsl(
-                nativePtr,
+        long nativePipelinePtr = createNativePipeline(
                 vertexWgsl,
                 fragmentWgsl,
                 vertexFormat,
@@ -331,6 +809,8 @@ public class BassaltDevice implements GpuDevice {
                 blendDstColorFactor,
                 blendSrcAlphaFactor,
                 blendDstAlphaFactor,
+                1,
+                BassaltStencilState.DISABLED,
                 pipeline.getLocation().toString());
 
         BassaltCompiledRenderPipeline compiled = new BassaltCompiledRenderPipeline(this, nativePipelinePtr);
@@ -518,6 +998,53 @@ public class BassaltDevice implements GpuDevice {
         return Arrays.asList(features.split(", "));
     }
 
+    /**
+     * Bytes of VRAM this process can still allocate on the adapter, or -1 if unknown.
+     * Best-effort: only Vulkan reports it, using {@code VK_EXT_memory_budget} where
+     * available and the size of the device-local heaps otherwise. Metal, DX12 and GL
+     * return -1.
+     */
+    public long getVramBudget() {
+        return getVramBudget0(nativePtr)[1];
+    }
+
+    /**
+     * Combined size in bytes of the adapter's device-local memory, or -1 if unknown.
+     * See {@link #getVramBudget} for which backends report it.
+     */
+    public long getVramTotal() {
+        return getVramBudget0(nativePtr)[0];
+    }
+
+    /**
+     * Create {@code count} pipeline statistics queries for measuring the cost of draws.
+     *
+     * @throws UnsupportedOperationException if the device doesn't support pipeline statistics queries
+     */
+    public BassaltPipelineStatsQuerySet createPipelineStatsQuery(int count) {
+        return new BassaltPipelineStatsQuerySet(this, count);
+    }
+
+    /**
+     * Load the driver's compiled-pipeline cache saved by {@link #savePipelineCache},
+     * so pipelines created afterwards skip recompilation. A missing or stale file
+     * (e.g. after a driver update) starts an empty cache.
+     *
+     * @return false if the backend has no pipeline caches (currently only Vulkan does)
+     */
+    public boolean loadPipelineCache(String path) {
+        return loadPipelineCache0(nativePtr, path);
+    }
+
+    /**
+     * Save the pipeline cache loaded with {@link #loadPipelineCache} to {@code path}.
+     *
+     * @return false if no cache was loaded or there is nothing to save
+     */
+    public boolean savePipelineCache(String path) {
+        return savePipelineCache0(nativePtr, path);
+    }
+
     @Override
     public int getMaxSupportedAnisotropy() {
         return getMaxSupportedAnisotropy0(nativePtr);
@@ -525,6 +1052,13 @@ public class BassaltDevice implements GpuDevice {
 
     private static native int getMaxSupportedAnisotropy0(long ptr);
 
+    // [total, available] bytes of VRAM, -1 where the backend doesn't report them
+    private static native long[] getVramBudget0(long ptr);
+
+    private static native boolean loadPipelineCache0(long ptr, String path);
+
+    private static native boolean savePipelineCache0(long ptr, String path);
+
     private static native String getEnabledFeatures0(long ptr);
 
     @Override
@@ -542,7 +1076,263 @@ public class BassaltDevice implements GpuDevice {
 
     @Override
     public void presentFrame() {
-        presentFrame(nativePtr);
+        int status = presentFrame(nativePtr);
+        if (status == PRESENT_RECONFIGURED) {
+            LOGGER.info("Swapchain was outdated or lost, reconfigured the surface");
+        } else if (status == PRESENT_FAILED) {
+            LOGGER.warn("Failed to present frame");
+        }
+    }
+
+    /**
+     * CPU time spent in submit, present and pipeline creation, for the last
+     * completed frame and the frame in progress. Complements GPU timestamps
+     * when telling CPU-bound frames from GPU-bound ones. Also reports how many
+     * redundant pipeline, bind group and vertex buffer sets were skipped.
+     */
+    public String getCpuTimings() {
+        return getCpuTimingsJson(nativePtr);
+    }
+
+    /**
+     * Every device limit as a JSON object keyed by wgpu limit name, e.g.
+     * {@code max_vertex_buffers}, {@code max_bind_groups},
+     * {@code max_storage_buffer_binding_size}, {@code max_immediate_size} and the
+     * {@code max_compute_workgroup_size_*} values. Resources and pipelines exceeding
+     * these fail validation, so configure from them instead of assuming defaults.
+     */
+    public String getLimits() {
+        return getLimitsJson(nativePtr);
+    }
+
+    /**
+     * Resolve only the given rectangle of a multisampled texture into a single-sample
+     * texture of the same size and format; pixels outside it keep their contents.
+     * The source needs texture-binding usage and the destination render-attachment usage.
+     *
+     * @throws IllegalArgumentException if the rectangle is empty or out of bounds, or the
+     *         textures don't form a valid resolve pair
+     */
+    public void resolveMsaaRegion(long srcTexture, long dstTexture, int x, int y, int width, int height) {
+        resolveMsaaRegion0(nativePtr, srcTexture, dstTexture, x, y, width, height);
+    }
+
+    /**
+     * Draw debug lines (hitboxes, chunk borders) from a vertex buffer onto a color texture.
+     * Each vertex is 16 bytes: three floats of position followed by RGBA8 color, two
+     * vertices per line. Lines are depth tested against {@code depthTexture} but don't
+     * write it; pass 0 to skip depth testing.
+     *
+     * <p>Lines are always one pixel wide: wgpu has no line width, so wider strokes have
+     * to be drawn as quads.
+     *
+     * @param viewProj column-major 4x4 view-projection matrix
+     * @throws IllegalArgumentException if the vertex range isn't whole lines inside the buffer
+     */
+    public void drawLines(long colorTexture, long depthTexture, long vertexBuffer,
+                          int firstVertex, int vertexCount, float[] viewProj) {
+        drawLines0(nativePtr, colorTexture, depthTexture, vertexBuffer, firstVertex, vertexCount, viewProj);
+    }
+
+    /**
+     * Sampler cache statistics as {@code [hits, misses, totalSamplers]}. Samplers
+     * with identical parameters are shared, so hits count the samplers that
+     * didn't need a new native object.
+     */
+    public long[] getSamplerCacheStats() {
+        return getSamplerCacheStats0(nativePtr);
+    }
+
+    /**
+     * Bind group cache statistics as {@code [hits, misses, invalidated, totalBindGroups]}.
+     * Draws binding the same textures, samplers and uniform ranges share one bind
+     * group; destroying a texture or buffer invalidates the groups that use it.
+     */
+    public long[] getBindGroupCacheStats() {
+        return getBindGroupCacheStats0(nativePtr);
+    }
+
+    /**
+     * Live native handle counts as {@code [buffers, textures, textureViews, samplers,
+     * bindGroups, bindGroupLayouts, renderPipelines, commandEncoders, renderBundles]}. Counts that
+     * don't return to their baseline after destroying what was created point to a leak.
+     */
+    public long[] getResourceCounts() {
+        return getResourceCounts0(nativePtr);
+    }
+
+    /**
+     * Submit everything recorded so far and return an index covering it. Indices increase with
+     * every call, so N-buffered resources can remember the index of the frame that last used them.
+     */
+    public long submitAndGetSubmissionIndex() {
+        return submitAndGetSubmissionIndex0(nativePtr);
+    }
+
+    /**
+     * Whether the GPU finished all work submitted up to {@code submissionIndex}, without blocking.
+     * Buffers that work read can be rewritten once this is true.
+     */
+    public boolean isSubmissionComplete(long submissionIndex) {
+        return isSubmissionComplete0(nativePtr, submissionIndex);
+    }
+
+    /**
+     * Whether a queue submit has reported that the GPU device was lost. Once
+     * true, the device has to be recreated before rendering can continue.
+     */
+    public boolean isDeviceLost() {
+        return isDeviceLost0(nativePtr);
+    }
+
+    /**
+     * Force the presented image to be fully opaque. On surfaces whose composite
+     * alpha mode isn't opaque, the compositor would otherwise blend the window
+     * by the framebuffer's alpha channel. Defaults to on for those surfaces.
+     */
+    public void setForceOpaquePresent(boolean enabled) {
+        setForceOpaquePresent0(nativePtr, enabled);
+    }
+
+    /**
+     * Whether the final present writes alpha 1.0.
+     */
+    public boolean isForceOpaquePresent() {
+        return isForceOpaquePresent0(nativePtr);
+    }
+
+    /**
+     * Clear colors are packed sRGB-encoded, as Minecraft specifies them. For sRGB attachments
+     * they're decoded to linear, so the stored texels match the packed value: 0x808080 clears
+     * to visually mid-gray. On by default; disable to pass the channels through as linear.
+     */
+    public void setSrgbClearColors(boolean enabled) {
+        setSrgbClearColors0(nativePtr, enabled);
+    }
+
+    /**
+     * Present through an extended-range {@code Rgba16Float} surface on HDR displays. The
+     * surface is reconfigured and the present blit decodes the game's sRGB output to linear.
+     * Stays in SDR when the surface doesn't offer the format.
+     *
+     * @return whether HDR is on afterwards
+     */
+    public boolean setHdrEnabled(boolean enabled) {
+        return setHdrEnabled0(nativePtr, enabled);
+    }
+
+    public boolean isHdrEnabled() {
+        return isHdrEnabled0(nativePtr);
+    }
+
+    /**
+     * Texture formats the window surface supports, by wgpu name (e.g. "Bgra8Unorm",
+     * "Rgba16Float"). Empty for a headless device.
+     */
+    public String[] getSurfaceFormats() {
+        return getSurfaceFormats0(nativePtr);
+    }
+
+    /**
+     * Present modes the window surface supports, by wgpu name ("Fifo", "FifoRelaxed",
+     * "Mailbox", "Immediate"). "Fifo" is vsync and always available on a real surface;
+     * use this to offer only the modes the hardware has. Empty for a headless device.
+     */
+    public String[] getSurfacePresentModes() {
+        return getSurfacePresentModes0(nativePtr);
+    }
+
+    /**
+     * Composite alpha modes the window surface supports, by wgpu name ("Opaque",
+     * "PreMultiplied", "PostMultiplied", "Inherit"). Empty for a headless device.
+     */
+    public String[] getSurfaceAlphaModes() {
+        return getSurfaceAlphaModes0(nativePtr);
+    }
+
+    /**
+     * Whether the backend has push constants. When it doesn't (e.g. GL),
+     * {@code setPushConstants} goes through a dynamic uniform buffer and WGSL
+     * {@code var<immediate>} blocks are rewritten to read from it, so callers
+     * only need this to pick a cheaper path.
+     */
+    public boolean supportsPushConstants() {
+        return supportsPushConstants0(nativePtr);
+    }
+
+    /**
+     * Serialize all command recording and queue submission behind one lock. A
+     * debugging aid for races between render threads; costs throughput, so it is
+     * off by default.
+     */
+    public void setSerializedSubmission(boolean enabled) {
+        setSerializedSubmission0(nativePtr, enabled);
+    }
+
+    public boolean isSerializedSubmission() {
+        return isSerializedSubmission0(nativePtr);
+    }
+
+    /**
+     * Take the last error reported by a per-draw call on this thread.
+     *
+     * Render pass record calls (set pipeline, vertex/index buffers, bind groups,
+     * scissor, stencil reference, push constants, draws and occlusion queries)
+     * skip bad input and report it here instead of throwing, to keep exceptions
+     * off the render hot path. Creation, upload and submit calls still throw.
+     * With strict validation on, validation errors throw {@link BassaltValidationError}
+     * from the call itself.
+     *
+     * @return the error message, or null if none was reported since the last call
+     */
+    public static @Nullable String getLastError() {
+        return getLastError0();
+    }
+
+    /**
+     * Poll the device so pending buffer mappings complete and finished
+     * submissions are retired. With {@code wait} set, blocks until all submitted
+     * work is done, e.g. before a screenshot readback or freeing resources.
+     *
+     * @return true if the queue has no pending work
+     */
+    public boolean poll(boolean wait) {
+        return poll0(nativePtr, wait);
+    }
+
+    /**
+     * Open an error scope on the calling thread, e.g. around a frame.
+     *
+     * Until {@link #popErrorScope()}, per-draw errors matching {@code filter}
+     * (one of the {@code ERROR_FILTER_*} constants) are caught by the scope
+     * instead of being logged, stored as the last error, or thrown in strict
+     * mode. Scopes nest, and the innermost matching scope catches the error.
+     */
+    public void pushErrorScope(int filter) {
+        pushErrorScope0(nativePtr, filter);
+    }
+
+    /**
+     * Close the innermost error scope on the calling thread.
+     *
+     * @return the first error the scope caught, or null if there was none
+     * @throws IllegalStateException if no scope is open
+     */
+    public @Nullable String popErrorScope() {
+        return popErrorScope0(nativePtr);
+    }
+
+    /**
+     * Number of render passes begun and not yet ended. Grows without bound
+     * when passes leak; a warning is logged once it passes the leak threshold.
+     */
+    public static int getLiveRenderPassCount() {
+        return getLiveRenderPassCount0();
+    }
+
+    /** Set how many live render passes are tolerated before a leak warning (default 64) */
+    public static void setRenderPassLeakThreshold(int threshold) {
+        setRenderPassLeakThreshold0(threshold);
     }
 
     @Override
@@ -576,6 +1366,15 @@ public class BassaltDevice implements GpuDevice {
         };
     }
 
+    /**
+     * Whether Minecraft expects to rewrite the buffer from the CPU often: mapped
+     * for writing (0x02) or hinted as client storage (0x04). The native side
+     * keeps these writable and labels them as dynamic.
+     */
+    private static boolean isFrequentlyUpdated(int minecraftUsage) {
+        return (minecraftUsage & (0x02 | 0x04)) != 0;
+    }
+
     private static int toBassaltBufferUsage(int minecraftUsage) {
         int usage = 0;
         // Map Minecraft's GpuBuffer.Usage to Bassalt's flags
@@ -627,8 +1426,8 @@ public class BassaltDevice implements GpuDevice {
             usage |= BassaltBackend.TEXTURE_USAGE_TEXTURE_BINDING; // MC TEXTURE_BINDING → Bassalt TEXTURE_BINDING
         if ((minecraftUsage & 0x08) != 0)
             usage |= BassaltBackend.TEXTURE_USAGE_RENDER_ATTACHMENT; // MC RENDER_ATTACHMENT → Bassalt RENDER_ATTACHMENT
-        // Note: Minecraft's CUBEMAP_COMPATIBLE (0x10) doesn't have a direct Bassalt
-        // equivalent
+        // Minecraft's CUBEMAP_COMPATIBLE (0x10) isn't a usage here; createTexture
+        // passes it as the isCube flag instead
 
         // WebGPU requires COPY_DST to upload texture data, but OpenGL doesn't
         // distinguish.
@@ -649,6 +1448,15 @@ public class BassaltDevice implements GpuDevice {
         destroyBuffer(nativePtr, bufferPtr);
     }
 
+    /**
+     * Create a multisampled render target (single mip level) for use with
+     * {@link #beginRenderPassMSAA}.
+     */
+    public long createNativeMultisampledTexture(int width, int height, int format, int usage, int sampleCount) {
+        return createTexture(nativePtr, width, height, 1, 1, format, usage, sampleCount,
+                BassaltBackend.TEXTURE_DIMENSION_2D, false, null);
+    }
+
     public void destroyNativeTexture(long texturePtr) {
         destroyTexture(nativePtr, texturePtr);
     }
@@ -659,10 +1467,10 @@ public class BassaltDevice implements GpuDevice {
             int depthCompare, boolean blendEnabled,
             int blendSrcColorFactor, int blendDstColorFactor,
             int blendSrcAlphaFactor, int blendDstAlphaFactor) {
-        return createNativePipelineFromWgsl(nativePtr, vertexWgsl, fragmentWgsl,
-                vertexFormat, primitiveTopology, depthTestEnabled, depthWriteEnabled,
-                depthCompare, blendEnabled, blendSrcColorFactor, blendDstColorFactor,
-                blendSrcAlphaFactor, blendDstAlphaFactor, "unknown");
+        return createNativePipeline(vertexWgsl, fragmentWgsl, vertexFormat, primitiveTopology,
+                depthTestEnabled, depthWriteEnabled, depthCompare, blendEnabled,
+                blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                1, BassaltStencilState.DISABLED, "unknown");
     }
 
     public long createNativePipeline(String vertexWgsl, String fragmentWgsl,
@@ -672,10 +1480,490 @@ public class BassaltDevice implements GpuDevice {
             int blendSrcColorFactor, int blendDstColorFactor,
             int blendSrcAlphaFactor, int blendDstAlphaFactor,
             String shaderName) {
-        return createNativePipelineFromWgsl(nativePtr, vertexWgsl, fragmentWgsl,
-                vertexFormat, primitiveTopology, depthTestEnabled, depthWriteEnabled,
-                depthCompare, blendEnabled, blendSrcColorFactor, blendDstColorFactor,
-                blendSrcAlphaFactor, blendDstAlphaFactor, shaderName);
+        return createNativePipeline(vertexWgsl, fragmentWgsl, vertexFormat, primitiveTopology,
+                depthTestEnabled, depthWriteEnabled, depthCompare, blendEnabled,
+                blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                1, BassaltStencilState.DISABLED, shaderName);
+    }
+
+    public long createNativePipeline(String vertexWgsl, String fragmentWgsl,
+            int vertexFormat, int primitiveTopology,
+            boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, boolean blendEnabled,
+            int blendSrcColorFactor, int blendDstColorFactor,
+            int blendSrcAlphaFactor, int blendDstAlphaFactor,
+            int sampleCount, String shaderName) {
+        return createNativePipeline(vertexWgsl, fragmentWgsl, vertexFormat, primitiveTopology,
+                depthTestEnabled, depthWriteEnabled, depthCompare, blendEnabled,
+                blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                sampleCount, BassaltStencilState.DISABLED, shaderName);
+    }
+
+    public long createNativePipeline(String vertexWgsl, String fragmentWgsl,
+            int vertexFormat, int primitiveTopology,
+            boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, boolean blendEnabled,
+            int blendSrcColorFactor, int blendDstColorFactor,
+            int blendSrcAlphaFactor, int blendDstAlphaFactor,
+            int sampleCount, BassaltStencilState stencil, String shaderName) {
+        return createNativePipeline(vertexWgsl, fragmentWgsl, vertexFormat, primitiveTopology,
+                depthTestEnabled, depthWriteEnabled, depthCompare, blendEnabled,
+                blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                sampleCount, TARGET_FORMAT_SWAPCHAIN, stencil, shaderName);
+    }
+
+    /**
+     * Create a pipeline rendering into a color attachment of {@code targetFormat}
+     * ({@code BassaltBackend.FORMAT_*}), e.g. an sRGB or HDR render target, or
+     * {@link #TARGET_FORMAT_SWAPCHAIN}. The format must match the attachment of the
+     * render passes the pipeline is used in.
+     */
+    public long createNativePipeline(String vertexWgsl, String fragmentWgsl,
+            int vertexFormat, int primitiveTopology,
+            boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, boolean blendEnabled,
+            int blendSrcColorFactor, int blendDstColorFactor,
+            int blendSrcAlphaFactor, int blendDstAlphaFactor,
+            int sampleCount, int targetFormat, BassaltStencilState stencil, String shaderName) {
+        return createNativePipeline(vertexWgsl, fragmentWgsl, vertexFormat, primitiveTopology,
+                depthTestEnabled, depthWriteEnabled, depthCompare, blendEnabled,
+                blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                sampleCount, targetFormat, stencil, shaderName, BassaltPipelineOptions.DEFAULT);
+    }
+
+    /**
+     * Like {@link #createNativePipeline(String, String, int, int, boolean, boolean, int, boolean,
+     * int, int, int, int, int, int, BassaltStencilState, String)}, with the optional index format,
+     * polygon mode, background compilation and override constants of {@code options}.
+     */
+    public long createNativePipeline(String vertexWgsl, String fragmentWgsl,
+            int vertexFormat, int primitiveTopology,
+            boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, boolean blendEnabled,
+            int blendSrcColorFactor, int blendDstColorFactor,
+            int blendSrcAlphaFactor, int blendDstAlphaFactor,
+            int sampleCount, int targetFormat, BassaltStencilState stencil, String shaderName,
+            BassaltPipelineOptions options) {
+        return createNativePipelineFromWgsl(nativePtr, vertexWgsl, fragmentWgsl, vertexFormat,
+                pipelineState(primitiveTopology, depthTestEnabled, depthWriteEnabled, depthCompare, blendEnabled,
+                        blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                        sampleCount, targetFormat, DEPTH_FORMAT_AUTO, stencil, options),
+                shaderName, options.background(), constantNames(options), constantValues(options));
+    }
+
+    /**
+     * Like {@link #createNativePipeline(String, String, int, int, boolean, boolean, int, boolean,
+     * int, int, int, int, int, int, BassaltStencilState, String)}, with explicit rasterization state.
+     * Strip topologies drawn with {@code indexFormat} ({@code INDEX_FORMAT_*}) index buffers restart
+     * at the all-ones index, so many strips can share one indexed draw. {@code polygonMode}
+     * ({@code POLYGON_MODE_*}) selects wireframe or point rendering for debugging meshes.
+     */
+    public long createNativePipeline(String vertexWgsl, String fragmentWgsl,
+            int vertexFormat, int primitiveTopology, int indexFormat, int polygonMode,
+            boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, boolean blendEnabled,
+            int blendSrcColorFactor, int blendDstColorFactor,
+            int blendSrcAlphaFactor, int blendDstAlphaFactor,
+            int sampleCount, int targetFormat, BassaltStencilState stencil, String shaderName) {
+        return createNativePipeline(vertexWgsl, fragmentWgsl, vertexFormat, primitiveTopology,
+                depthTestEnabled, depthWriteEnabled, depthCompare, blendEnabled,
+                blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                sampleCount, targetFormat, stencil, shaderName,
+                new BassaltPipelineOptions(indexFormat, polygonMode, false, Map.of()));
+    }
+
+    /**
+     * Like {@link #createNativePipeline(String, String, int, int, boolean, boolean, int, boolean,
+     * int, int, int, int, int, int, BassaltStencilState, String)}, specializing the shaders' WGSL
+     * {@code override} declarations, e.g. a fog mode, without recompiling the source. Keys are
+     * override names or their {@code @id}; names neither shader declares are logged and ignored.
+     */
+    public long createNativePipeline(String vertexWgsl, String fragmentWgsl,
+            int vertexFormat, int primitiveTopology,
+            boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, boolean blendEnabled,
+            int blendSrcColorFactor, int blendDstColorFactor,
+            int blendSrcAlphaFactor, int blendDstAlphaFactor,
+            int sampleCount, int targetFormat, BassaltStencilState stencil, String shaderName,
+            Map<String, Double> overrideConstants) {
+        return createNativePipeline(vertexWgsl, fragmentWgsl, vertexFormat, primitiveTopology,
+                depthTestEnabled, depthWriteEnabled, depthCompare, blendEnabled,
+                blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                sampleCount, targetFormat, stencil, shaderName,
+                new BassaltPipelineOptions(INDEX_FORMAT_UNKNOWN, POLYGON_MODE_FILL, false, overrideConstants));
+    }
+
+    /**
+     * Like {@link #createNativePipeline(String, String, int, int, boolean, boolean, int, boolean,
+     * int, int, int, int, int, int, BassaltStencilState, String)}, but parses and compiles the
+     * shaders on a background thread instead of blocking. The returned handle can be set on render
+     * passes right away; draws made with it are skipped until {@link #isPipelineReady(long)} is
+     * true. Shader errors don't throw here; {@code isPipelineReady} reports them.
+     */
+    public long createNativePipelineAsync(String vertexWgsl, String fragmentWgsl,
+            int vertexFormat, int primitiveTopology,
+            boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, boolean blendEnabled,
+            int blendSrcColorFactor, int blendDstColorFactor,
+            int blendSrcAlphaFactor, int blendDstAlphaFactor,
+            int sampleCount, int targetFormat, BassaltStencilState stencil, String shaderName) {
+        return createNativePipeline(vertexWgsl, fragmentWgsl, vertexFormat, primitiveTopology,
+                depthTestEnabled, depthWriteEnabled, depthCompare, blendEnabled,
+                blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                sampleCount, targetFormat, stencil, shaderName,
+                new BassaltPipelineOptions(INDEX_FORMAT_UNKNOWN, POLYGON_MODE_FILL, true, Map.of()));
+    }
+
+    /**
+     * Whether a pipeline from {@link #createNativePipelineAsync} has finished compiling.
+     *
+     * @throws IllegalStateException if its shaders failed to parse or compile
+     */
+    public boolean isPipelineReady(long pipelineHandle) {
+        return isPipelineReady0(nativePtr, pipelineHandle);
+    }
+
+    /**
+     * Derive depth pre-pass pipelines from a color pipeline that renders with a depth
+     * attachment. Returns {@code [prepass, shading]}: draw the geometry with
+     * {@code prepass} in a render pass with only a depth attachment, then again with
+     * {@code shading} in a pass that loads that depth; its depth-equal test runs the
+     * fragment shader once per covered pixel. Leave out shaders that discard fragments,
+     * since the pre-pass writes depth for the whole primitive.
+     *
+     * @throws IllegalArgumentException if the pipeline has no depth attachment or is still compiling
+     */
+    public long[] createDepthPrepassPipelines(long pipelineHandle) {
+        return createDepthPrepassPipelines0(nativePtr, pipelineHandle);
+    }
+
+    /**
+     * Create a pipeline whose vertex input is described by {@code vertexLayout}
+     * instead of a fixed vertex format index.
+     */
+    public long createNativePipeline(String vertexWgsl, String fragmentWgsl,
+            BassaltVertexLayout vertexLayout, int primitiveTopology,
+            boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, boolean blendEnabled,
+            int blendSrcColorFactor, int blendDstColorFactor,
+            int blendSrcAlphaFactor, int blendDstAlphaFactor,
+            int sampleCount, BassaltStencilState stencil, String shaderName) {
+        return createNativePipeline(vertexWgsl, fragmentWgsl, vertexLayout, primitiveTopology,
+                depthTestEnabled, depthWriteEnabled, depthCompare, blendEnabled,
+                blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                sampleCount, stencil, shaderName, BassaltPipelineOptions.DEFAULT);
+    }
+
+    /** Like the layout-described overload above, with the optional state of {@code options}. */
+    public long createNativePipeline(String vertexWgsl, String fragmentWgsl,
+            BassaltVertexLayout vertexLayout, int primitiveTopology,
+            boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, boolean blendEnabled,
+            int blendSrcColorFactor, int blendDstColorFactor,
+            int blendSrcAlphaFactor, int blendDstAlphaFactor,
+            int sampleCount, BassaltStencilState stencil, String shaderName,
+            BassaltPipelineOptions options) {
+        List<BassaltVertexLayout.Buffer> buffers = vertexLayout.buffers();
+        long[] bufferStrides = new long[buffers.size()];
+        int[] bufferStepModes = new int[buffers.size()];
+        int attributeCount = buffers.stream().mapToInt(buffer -> buffer.attributes().size()).sum();
+        int[] attributeBuffers = new int[attributeCount];
+        int[] attributeLocations = new int[attributeCount];
+        int[] attributeFormats = new int[attributeCount];
+        long[] attributeOffsets = new long[attributeCount];
+
+        int a = 0;
+        for (int i = 0; i < buffers.size(); i++) {
+            BassaltVertexLayout.Buffer buffer = buffers.get(i);
+            bufferStrides[i] = buffer.arrayStride();
+            bufferStepModes[i] = buffer.stepMode();
+            for (BassaltVertexLayout.Attribute attribute : buffer.attributes()) {
+                attributeBuffers[a] = i;
+                attributeLocations[a] = attribute.shaderLocation();
+                attributeFormats[a] = attribute.format();
+                attributeOffsets[a] = attribute.offset();
+                a++;
+            }
+        }
+
+        return createNativePipelineFromWgslCustomVertex(nativePtr, vertexWgsl, fragmentWgsl,
+                bufferStrides, bufferStepModes,
+                attributeBuffers, attributeLocations, attributeFormats, attributeOffsets,
+                pipelineState(primitiveTopology, depthTestEnabled, depthWriteEnabled, depthCompare, blendEnabled,
+                        blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                        sampleCount, TARGET_FORMAT_SWAPCHAIN, DEPTH_FORMAT_AUTO, stencil, options),
+                shaderName, options.background(), constantNames(options), constantValues(options));
+    }
+
+    /**
+     * Create a pipeline whose depth state targets {@code depthFormat} (a
+     * {@code BassaltBackend.FORMAT_DEPTH*} constant) instead of the format picked
+     * from the shader, e.g. {@link BassaltBackend#FORMAT_DEPTH16} for shadow maps.
+     * The render pass depth attachment must use the same format.
+     */
+    public long createNativePipelineWithDepthFormat(String vertexWgsl, String fragmentWgsl,
+            int vertexFormat, int depthFormat, int primitiveTopology,
+            boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, boolean blendEnabled,
+            int blendSrcColorFactor, int blendDstColorFactor,
+            int blendSrcAlphaFactor, int blendDstAlphaFactor,
+            int sampleCount, BassaltStencilState stencil, String shaderName) {
+        return createNativePipelineWithDepthFormat(vertexWgsl, fragmentWgsl, vertexFormat, depthFormat,
+                primitiveTopology, depthTestEnabled, depthWriteEnabled, depthCompare, blendEnabled,
+                blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                sampleCount, stencil, shaderName, BassaltPipelineOptions.DEFAULT);
+    }
+
+    /** Like the overload above, with the optional state of {@code options}. */
+    public long createNativePipelineWithDepthFormat(String vertexWgsl, String fragmentWgsl,
+            int vertexFormat, int depthFormat, int primitiveTopology,
+            boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, boolean blendEnabled,
+            int blendSrcColorFactor, int blendDstColorFactor,
+            int blendSrcAlphaFactor, int blendDstAlphaFactor,
+            int sampleCount, BassaltStencilState stencil, String shaderName,
+            BassaltPipelineOptions options) {
+        return createNativePipelineFromWgsl(nativePtr, vertexWgsl, fragmentWgsl, vertexFormat,
+                pipelineState(primitiveTopology, depthTestEnabled, depthWriteEnabled, depthCompare, blendEnabled,
+                        blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                        sampleCount, TARGET_FORMAT_SWAPCHAIN, depthFormat, stencil, options),
+                shaderName, options.background(), constantNames(options), constantValues(options));
+    }
+
+    /**
+     * Create a pipeline that writes one color target per fragment output. Target
+     * {@code i} (format {@code targetFormats[i]}) receives {@code @location(i)};
+     * its blend factors are {@code targetBlendFactors[4 * i .. 4 * i + 3]} (src color,
+     * dst color, src alpha, dst alpha) and are ignored unless blending is enabled.
+     * Throws if the shader's outputs don't match the number of targets.
+     */
+    public long createNativePipelineMRT(String vertexWgsl, String fragmentWgsl,
+            int vertexFormat, int[] targetFormats, boolean[] targetBlendEnabled, int[] targetBlendFactors,
+            int primitiveTopology, boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, int sampleCount, String shaderName) {
+        return createNativePipelineMRT(vertexWgsl, fragmentWgsl, vertexFormat, targetFormats,
+                targetBlendEnabled, targetBlendFactors, primitiveTopology, depthTestEnabled, depthWriteEnabled,
+                depthCompare, sampleCount, shaderName, BassaltPipelineOptions.DEFAULT);
+    }
+
+    /** Like the overload above, with the optional state of {@code options}. */
+    public long createNativePipelineMRT(String vertexWgsl, String fragmentWgsl,
+            int vertexFormat, int[] targetFormats, boolean[] targetBlendEnabled, int[] targetBlendFactors,
+            int primitiveTopology, boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, int sampleCount, String shaderName, BassaltPipelineOptions options) {
+        int[] blendEnabled = new int[targetBlendEnabled.length];
+        for (int i = 0; i < targetBlendEnabled.length; i++) {
+            blendEnabled[i] = targetBlendEnabled[i] ? 1 : 0;
+        }
+        return createNativePipelineFromWgslMRT(nativePtr, vertexWgsl, fragmentWgsl,
+                vertexFormat, targetFormats, blendEnabled, targetBlendFactors,
+                pipelineState(primitiveTopology, depthTestEnabled, depthWriteEnabled, depthCompare, false,
+                        0, 0, 0, 0, sampleCount, TARGET_FORMAT_SWAPCHAIN, DEPTH_FORMAT_AUTO,
+                        BassaltStencilState.DISABLED, options),
+                shaderName, options.background(), constantNames(options), constantValues(options));
+    }
+
+    /**
+     * Create a pipeline with a fixed vertex format in slot 0 and per-instance
+     * data from {@code instanceBuffer} in slot 1. Bind the instance buffer with
+     * {@code setVertexBuffer(1, ...)} and draw with an instance count above 1.
+     */
+    public long createInstancedNativePipeline(String vertexWgsl, String fragmentWgsl,
+            int vertexFormat, BassaltVertexLayout.Buffer instanceBuffer, int primitiveTopology,
+            boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, boolean blendEnabled,
+            int blendSrcColorFactor, int blendDstColorFactor,
+            int blendSrcAlphaFactor, int blendDstAlphaFactor,
+            int sampleCount, BassaltStencilState stencil, String shaderName) {
+        return createInstancedNativePipeline(vertexWgsl, fragmentWgsl, vertexFormat, instanceBuffer,
+                primitiveTopology, depthTestEnabled, depthWriteEnabled, depthCompare, blendEnabled,
+                blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                sampleCount, stencil, shaderName, BassaltPipelineOptions.DEFAULT);
+    }
+
+    /** Like the overload above, with the optional state of {@code options}. */
+    public long createInstancedNativePipeline(String vertexWgsl, String fragmentWgsl,
+            int vertexFormat, BassaltVertexLayout.Buffer instanceBuffer, int primitiveTopology,
+            boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, boolean blendEnabled,
+            int blendSrcColorFactor, int blendDstColorFactor,
+            int blendSrcAlphaFactor, int blendDstAlphaFactor,
+            int sampleCount, BassaltStencilState stencil, String shaderName,
+            BassaltPipelineOptions options) {
+        List<BassaltVertexLayout.Attribute> attributes = instanceBuffer.attributes();
+        int[] locations = new int[attributes.size()];
+        int[] formats = new int[attributes.size()];
+        long[] offsets = new long[attributes.size()];
+        for (int i = 0; i < attributes.size(); i++) {
+            locations[i] = attributes.get(i).shaderLocation();
+            formats[i] = attributes.get(i).format();
+            offsets[i] = attributes.get(i).offset();
+        }
+
+        return createNativePipelineFromWgslInstanced(nativePtr, vertexWgsl, fragmentWgsl,
+                vertexFormat, instanceBuffer.arrayStride(), locations, formats, offsets,
+                pipelineState(primitiveTopology, depthTestEnabled, depthWriteEnabled, depthCompare, blendEnabled,
+                        blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                        sampleCount, TARGET_FORMAT_SWAPCHAIN, DEPTH_FORMAT_AUTO, stencil, options),
+                shaderName, options.background(), constantNames(options), constantValues(options));
+    }
+
+    /**
+     * Create a bind group layout from an explicit spec.
+     *
+     * @return a layout handle for {@link #createBindGroupExplicit}
+     */
+    public long createBindGroupLayout(BassaltBindGroupLayoutSpec spec) {
+        List<BassaltBindGroupLayoutSpec.Entry> entries = spec.entries();
+        int[] bindings = new int[entries.size()];
+        int[] types = new int[entries.size()];
+        int[] dimensions = new int[entries.size()];
+        long[] minBindingSizes = new long[entries.size()];
+        for (int i = 0; i < entries.size(); i++) {
+            BassaltBindGroupLayoutSpec.Entry entry = entries.get(i);
+            bindings[i] = entry.binding();
+            types[i] = entry.type();
+            dimensions[i] = entry.viewDimension();
+            minBindingSizes[i] = entry.minBindingSize();
+        }
+        return createBindGroupLayout0(nativePtr, bindings, types, dimensions, minBindingSizes);
+    }
+
+    /**
+     * Create a bind group with caller-chosen binding slots.
+     * Every binding in the layout needs exactly one entry of a matching resource kind.
+     *
+     * @return a bind group handle
+     */
+    public long createBindGroupExplicit(long layoutHandle, List<BassaltBindGroupEntry> entries) {
+        int[] bindings = new int[entries.size()];
+        long[] resources = new long[entries.size()];
+        long[] offsets = new long[entries.size()];
+        long[] sizes = new long[entries.size()];
+        for (int i = 0; i < entries.size(); i++) {
+            BassaltBindGroupEntry entry = entries.get(i);
+            bindings[i] = entry.binding();
+            resources[i] = entry.resource();
+            offsets[i] = entry.offset();
+            sizes[i] = entry.size();
+        }
+        return createBindGroupExplicit0(nativePtr, layoutHandle, bindings, resources, offsets, sizes);
+    }
+
+    /**
+     * Layout of a pipeline's bind group {@code group} (1 and up) as declared by its shaders.
+     * Group 0 keeps name-based binding through {@link #createBindGroup}.
+     *
+     * @return a layout handle for {@link #createBindGroupExplicit}, bound with
+     *         {@link com.criticalrange.bassalt.pipeline.BassaltRenderPass#setBindGroup} at the same index
+     */
+    public long getPipelineBindGroupLayout(long pipelineHandle, int group) {
+        return getPipelineBindGroupLayout0(nativePtr, pipelineHandle, group);
+    }
+
+    /**
+     * Validate a pipeline configuration before calling {@link #createNativePipeline}.
+     *
+     * @return an empty string if the configuration is valid, otherwise the first problem found
+     */
+    public String validateNativePipeline(String vertexWgsl, String fragmentWgsl,
+            int vertexFormat, int primitiveTopology, int depthCompare, boolean blendEnabled,
+            int blendSrcColorFactor, int blendDstColorFactor,
+            int blendSrcAlphaFactor, int blendDstAlphaFactor,
+            int sampleCount, String shaderName) {
+        return validateNativePipeline(vertexWgsl, fragmentWgsl, vertexFormat, primitiveTopology,
+                false, false, depthCompare, blendEnabled,
+                blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                sampleCount, TARGET_FORMAT_SWAPCHAIN, BassaltStencilState.DISABLED, shaderName,
+                BassaltPipelineOptions.DEFAULT);
+    }
+
+    /**
+     * Validate the pipeline the matching {@link #createNativePipeline(String, String, int, int,
+     * boolean, boolean, int, boolean, int, int, int, int, int, int, BassaltStencilState, String,
+     * BassaltPipelineOptions)} call would create, including its depth format, sample count and
+     * vertex format.
+     *
+     * @return an empty string if the configuration is valid, otherwise the first problem found
+     */
+    public String validateNativePipeline(String vertexWgsl, String fragmentWgsl,
+            int vertexFormat, int primitiveTopology,
+            boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, boolean blendEnabled,
+            int blendSrcColorFactor, int blendDstColorFactor,
+            int blendSrcAlphaFactor, int blendDstAlphaFactor,
+            int sampleCount, int targetFormat, BassaltStencilState stencil, String shaderName,
+            BassaltPipelineOptions options) {
+        return validatePipelineConfig(nativePtr, vertexWgsl, fragmentWgsl, vertexFormat,
+                pipelineState(primitiveTopology, depthTestEnabled, depthWriteEnabled, depthCompare, blendEnabled,
+                        blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                        sampleCount, targetFormat, DEPTH_FORMAT_AUTO, stencil, options),
+                shaderName, options.background(), constantNames(options), constantValues(options));
+    }
+
+    public long createNativePipelineFromSpirv(byte[] vertexSpirv, byte[] fragmentSpirv,
+            int vertexFormat, int primitiveTopology,
+            boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, boolean blendEnabled,
+            int blendSrcColorFactor, int blendDstColorFactor,
+            int blendSrcAlphaFactor, int blendDstAlphaFactor,
+            String shaderName) {
+        return createNativePipelineFromSpirv(vertexSpirv, fragmentSpirv, vertexFormat, primitiveTopology,
+                depthTestEnabled, depthWriteEnabled, depthCompare, blendEnabled,
+                blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                shaderName, BassaltPipelineOptions.DEFAULT);
+    }
+
+    public long createNativePipelineFromSpirv(byte[] vertexSpirv, byte[] fragmentSpirv,
+            int vertexFormat, int primitiveTopology,
+            boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, boolean blendEnabled,
+            int blendSrcColorFactor, int blendDstColorFactor,
+            int blendSrcAlphaFactor, int blendDstAlphaFactor,
+            String shaderName, BassaltPipelineOptions options) {
+        return createNativePipelineFromSpirv(nativePtr, vertexSpirv, fragmentSpirv, vertexFormat,
+                pipelineState(primitiveTopology, depthTestEnabled, depthWriteEnabled, depthCompare, blendEnabled,
+                        blendSrcColorFactor, blendDstColorFactor, blendSrcAlphaFactor, blendDstAlphaFactor,
+                        1, TARGET_FORMAT_SWAPCHAIN, DEPTH_FORMAT_AUTO, BassaltStencilState.DISABLED, options),
+                shaderName, options.background(), constantNames(options), constantValues(options));
+    }
+
+    /**
+     * Pack the pipeline state every native pipeline creator takes, in the order
+     * {@code NativePipelineOptions::from_jni} reads it.
+     */
+    private static int[] pipelineState(int primitiveTopology,
+            boolean depthTestEnabled, boolean depthWriteEnabled,
+            int depthCompare, boolean blendEnabled,
+            int blendSrcColorFactor, int blendDstColorFactor,
+            int blendSrcAlphaFactor, int blendDstAlphaFactor,
+            int sampleCount, int targetFormat, int depthFormat,
+            BassaltStencilState stencil, BassaltPipelineOptions options) {
+        return new int[] {
+                primitiveTopology, options.indexFormat(), options.polygonMode(),
+                depthTestEnabled ? 1 : 0, depthWriteEnabled ? 1 : 0, depthCompare,
+                blendEnabled ? 1 : 0, blendSrcColorFactor, blendDstColorFactor,
+                blendSrcAlphaFactor, blendDstAlphaFactor,
+                sampleCount, targetFormat, depthFormat,
+                stencil.readMask(), stencil.writeMask(),
+                stencil.front().compare(), stencil.front().failOp(),
+                stencil.front().depthFailOp(), stencil.front().passOp(),
+                stencil.back().compare(), stencil.back().failOp(),
+                stencil.back().depthFailOp(), stencil.back().passOp(),
+        };
+    }
+
+    private static String[] constantNames(BassaltPipelineOptions options) {
+        return options.overrideConstants().keySet().toArray(new String[0]);
+    }
+
+    private static double[] constantValues(BassaltPipelineOptions options) {
+        String[] names = constantNames(options);
+        double[] values = new double[names.length];
+        for (int i = 0; i < names.length; i++) {
+            values[i] = options.overrideConstants().get(names[i]);
+        }
+        return values;
     }
 
     // Public access to native render pass methods for BassaltRenderPass
@@ -689,7 +1977,48 @@ public class BassaltDevice implements GpuDevice {
     public static native void draw(long ptr, long renderPass, int vertexCount, int instanceCount, int firstVertex,
             int firstInstance);
 
+    /**
+     * Draw with {@code DrawIndirectArgs} read from {@code buffer} at {@code offset}.
+     * The buffer needs INDIRECT usage and the offset must be 4-byte aligned.
+     */
+    public static native void drawIndirect(long ptr, long renderPass, long buffer, long offset);
+
+    /**
+     * Indexed draw with {@code DrawIndexedIndirectArgs} read from {@code buffer} at {@code offset}.
+     */
+    public static native void drawIndexedIndirect(long ptr, long renderPass, long buffer, long offset);
+
+    /**
+     * {@code count} indexed draws whose 20-byte argument structs start at {@code offset}.
+     */
+    public static native void multiDrawIndexedIndirect(long ptr, long renderPass, long buffer, long offset, int count);
+
+    /**
+     * Set the viewport in framebuffer pixels, clamped to the render target. Depths
+     * must lie in [0, 1] with {@code minDepth <= maxDepth}; bad values are reported
+     * through {@link #getLastError()} and leave the viewport unchanged.
+     */
+    public static native void setViewport(long ptr, long renderPass, float x, float y, float width, float height,
+                                          float minDepth, float maxDepth);
+
     public static native void setScissorRect(long ptr, long renderPass, int x, int y, int width, int height);
 
+    /**
+     * Set up to 128 bytes of per-draw data read by WGSL {@code var<immediate>} blocks.
+     * On backends without push constants this is emulated with a dynamic uniform
+     * buffer, which adds a bind group change to every draw that follows a change.
+     * <p>
+     * {@code stages} is a mask of {@link #SHADER_STAGE_VERTEX} and
+     * {@link #SHADER_STAGE_FRAGMENT}. All stages share one range, so the data is
+     * visible to both; the write must fit the block one of {@code stages} declares
+     * in the bound pipeline, and the device's push constant limit.
+     */
+    public static native void setPushConstants(long ptr, long renderPass, int stages, int offset, byte[] data);
+
+    /**
+     * Finish and submit a render pass.
+     *
+     * @throws GpuSubmitException if the queue rejects the submission
+     */
     public static native void endRenderPass(long ptr, long renderPass);
 }
@@ -0,0 +1,20 @@
+package com.criticalrange.bassalt.backend;
+
+import net.fabricmc.api.EnvType;
+import net.fabricmc.api.Environment;
+
+/**
+ * Thrown by the native layer on a validation error when strict validation is on.
+ *
+ * This is an {@link Error} so it isn't swallowed by handlers meant for
+ * recoverable failures; the stack trace points at the call that was rejected.
+ *
+ * @see BassaltBackend#setStrictValidation(boolean)
+ */
+@Environment(EnvType.CLIENT)
+public class BassaltValidationError extends Error {
+
+    public BassaltValidationError(String message) {
+        super(message);
+    }
+}
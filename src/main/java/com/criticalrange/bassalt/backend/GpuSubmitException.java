@@ -0,0 +1,43 @@
+package com.criticalrange.bassalt.backend;
+
+import net.fabricmc.api.EnvType;
+import net.fabricmc.api.Environment;
+
+/**
+ * Thrown by the native layer when submitting work to the GPU queue fails.
+ *
+ * The kind separates failures the game can recover from (a bad command
+ * buffer) from ones that need the device recreated (device loss).
+ */
+@Environment(EnvType.CLIENT)
+public class GpuSubmitException extends RuntimeException {
+
+    /**
+     * Why the submit failed. Ordinals match the native side.
+     */
+    public enum Kind {
+        DEVICE_LOST,
+        OUT_OF_MEMORY,
+        VALIDATION,
+        INTERNAL
+    }
+
+    private final Kind kind;
+
+    public GpuSubmitException(String message, int kind) {
+        super(message);
+        Kind[] kinds = Kind.values();
+        this.kind = kind >= 0 && kind < kinds.length ? kinds[kind] : Kind.INTERNAL;
+    }
+
+    public Kind getKind() {
+        return kind;
+    }
+
+    /**
+     * Whether the device is gone and has to be recreated
+     */
+    public boolean isDeviceLost() {
+        return kind == Kind.DEVICE_LOST;
+    }
+}
@@ -103,24 +103,15 @@ public class BassaltMappedView implements GpuBuffer.MappedView {
                 alignedSize = shadowBuffer.capacity();
             }
 
-            // Read only the actual data (with 4-byte alignment padding)
-            shadowBuffer.position(0);
-            shadowBuffer.limit(alignedSize);
-
-            byte[] data = new byte[alignedSize];
-            shadowBuffer.get(data);
-
-            // Restore old position/limit (though we're closing anyway)
-            shadowBuffer.position(oldPosition);
-            shadowBuffer.limit(oldLimit);
-
+            // Upload only the actual data (with 4-byte alignment padding); the shadow
+            // buffer is direct, so the native side reads it in place
             BassaltDevice.writeBuffer(
                 device.getNativePtr(),
                 buffer.getNativePtr(),
-                data,
+                shadowBuffer.slice(0, alignedSize),
                 offset
             );
-            LOGGER.debug("BassaltMappedView CLOSED: bufferPtr={}, offset={}, dataSize={}", buffer.getNativePtr(), offset, data.length);
+            LOGGER.debug("BassaltMappedView CLOSED: bufferPtr={}, offset={}, dataSize={}", buffer.getNativePtr(), offset, alignedSize);
         }
 
         // Note: we don't close the buffer itself, just the mapped view
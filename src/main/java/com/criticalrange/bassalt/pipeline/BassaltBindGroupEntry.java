@@ -0,0 +1,22 @@
+package com.criticalrange.bassalt.pipeline;
+
+import net.fabricmc.api.EnvType;
+import net.fabricmc.api.Environment;
+
+/**
+ * A resource bound at an explicit slot of a {@link BassaltBindGroupLayoutSpec} layout
+ *
+ * {@code resource} is a native texture view, sampler or buffer handle.
+ * {@code offset} and {@code size} only apply to buffers; a size of 0 binds the
+ * rest of the buffer.
+ */
+@Environment(EnvType.CLIENT)
+public record BassaltBindGroupEntry(int binding, long resource, long offset, long size) {
+    public static BassaltBindGroupEntry of(int binding, long resource) {
+        return new BassaltBindGroupEntry(binding, resource, 0, 0);
+    }
+
+    public static BassaltBindGroupEntry buffer(int binding, long buffer, long offset, long size) {
+        return new BassaltBindGroupEntry(binding, buffer, offset, size);
+    }
+}
@@ -0,0 +1,54 @@
+package com.criticalrange.bassalt.pipeline;
+
+import com.criticalrange.bassalt.backend.BassaltBackend;
+import net.fabricmc.api.EnvType;
+import net.fabricmc.api.Environment;
+
+import java.util.List;
+
+/**
+ * Explicit bind group layout for shaders the reflection heuristics can't handle
+ *
+ * Types use the {@code BassaltBackend.BINDING_TYPE_*} constants and texture
+ * dimensions the {@code BassaltBackend.VIEW_DIMENSION_*} constants. Bind groups
+ * for the layout are created with {@code BassaltDevice.createBindGroupExplicit}.
+ */
+@Environment(EnvType.CLIENT)
+public record BassaltBindGroupLayoutSpec(List<Entry> entries) {
+
+    /** One binding slot; {@code minBindingSize} of 0 leaves buffer sizes unchecked */
+    public record Entry(int binding, int type, int viewDimension, long minBindingSize) {
+        public static Entry texture(int binding, int viewDimension) {
+            return new Entry(binding, BassaltBackend.BINDING_TYPE_TEXTURE, viewDimension, 0);
+        }
+
+        public static Entry sampler(int binding) {
+            return new Entry(binding, BassaltBackend.BINDING_TYPE_SAMPLER, BassaltBackend.VIEW_DIMENSION_D2, 0);
+        }
+
+        public static Entry uniformBuffer(int binding, long minBindingSize) {
+            return new Entry(binding, BassaltBackend.BINDING_TYPE_UNIFORM_BUFFER, BassaltBackend.VIEW_DIMENSION_D2,
+                    minBindingSize);
+        }
+
+        /** Bind groups for it must give the buffer an explicit size, the window each offset moves */
+        public static Entry dynamicUniformBuffer(int binding, long minBindingSize) {
+            return new Entry(binding, BassaltBackend.BINDING_TYPE_DYNAMIC_UNIFORM_BUFFER,
+                    BassaltBackend.VIEW_DIMENSION_D2, minBindingSize);
+        }
+
+        public static Entry storageBuffer(int binding, long minBindingSize) {
+            return new Entry(binding, BassaltBackend.BINDING_TYPE_STORAGE_BUFFER, BassaltBackend.VIEW_DIMENSION_D2,
+                    minBindingSize);
+        }
+
+        public static Entry readWriteStorageBuffer(int binding, long minBindingSize) {
+            return new Entry(binding, BassaltBackend.BINDING_TYPE_READ_WRITE_STORAGE_BUFFER,
+                    BassaltBackend.VIEW_DIMENSION_D2, minBindingSize);
+        }
+    }
+
+    public static BassaltBindGroupLayoutSpec of(Entry... entries) {
+        return new BassaltBindGroupLayoutSpec(List.of(entries));
+    }
+}
@@ -1,5 +1,6 @@
 package com.criticalrange.bassalt.pipeline;
 
+import com.criticalrange.bassalt.backend.BassaltBackend;
 import com.criticalrange.bassalt.backend.BassaltDevice;
 import com.criticalrange.bassalt.buffer.BassaltBuffer;
 import com.criticalrange.bassalt.texture.BassaltTexture;
@@ -20,6 +21,7 @@ import org.jspecify.annotations.Nullable;
 
 import java.nio.ByteBuffer;
 import java.util.Collection;
+import java.util.List;
 import java.util.OptionalDouble;
 import java.util.OptionalInt;
 import java.util.OptionalLong;
@@ -29,6 +31,7 @@ import java.util.function.Supplier;
 import org.lwjgl.system.MemoryUtil;
 
 import com.criticalrange.bassalt.sync.BassaltFence;
+import com.criticalrange.bassalt.sync.BassaltOcclusionQuerySet;
 import com.criticalrange.bassalt.sync.BassaltQuery;
 
 /**
@@ -47,18 +50,28 @@ public class BassaltCommandEncoder implements CommandEncoder {
     // Native method declarations
     private static native void writeToTexture0(long devicePtr, long texturePtr, byte[] data,
                                                 int mipLevel, int depthOrLayer, int destX, int destY,
-                                                int width, int height, int format);
+                                                int width, int height, int format, int sourceSwizzle);
+    private static native void writeToTextureDirect0(long devicePtr, long texturePtr, ByteBuffer data, int length,
+                                                      int mipLevel, int depthOrLayer, int destX, int destY,
+                                                      int width, int height, int format, int sourceSwizzle);
+    private static native void writeTexturesBatch0(long devicePtr, long[] texturePtrs, int[] regions,
+                                                    long[] dataOffsets, byte[] data);
     private static native void copyToBuffer0(long devicePtr, long srcBufferPtr, long dstBufferPtr,
                                               long srcOffset, long dstOffset, long size);
+    private static native void clearBuffer0(long devicePtr, long bufferPtr, long offset, long size);
     private static native void copyTextureToBuffer0(long devicePtr, long texturePtr, long bufferPtr,
-                                                     long bufferOffset, int mipLevel, int width, int height);
+                                                     long bufferOffset, int mipLevel, int width, int height,
+                                                     int aspect);
     private static native void clearColorTexture0(long devicePtr, long texturePtr, int clearColor);
     private static native void clearDepthTexture0(long devicePtr, long texturePtr, float clearDepth);
+    private static native void clearColorTextureLayer0(long devicePtr, long texturePtr, int clearColor, int layer);
+    private static native void clearDepthTextureLayer0(long devicePtr, long texturePtr, float clearDepth, int layer);
     private static native void clearColorAndDepthTextures0(long devicePtr, long colorTexturePtr, int clearColor,
                                                            long depthTexturePtr, float clearDepth,
                                                            int x, int y, int width, int height);
     private static native void copyTextureToTexture0(long devicePtr, long srcTexturePtr, long dstTexturePtr,
-                                                      int mipLevel, int destX, int destY, int sourceX, int sourceY,
+                                                      int srcMipLevel, int dstMipLevel, int destX, int destY,
+                                                      int sourceX, int sourceY,
                                                       int width, int height);
 
     public BassaltCommandEncoder(BassaltDevice device) {
@@ -85,6 +98,21 @@ public class BassaltCommandEncoder implements CommandEncoder {
         OptionalInt clearColor,
         @Nullable GpuTextureView depthTexture,
         OptionalDouble clearDepth
+    ) {
+        return createRenderPass(label, colorTexture, clearColor, depthTexture, clearDepth, null);
+    }
+
+    /**
+     * Create a render pass with an occlusion query set attached, so draws can be
+     * wrapped in {@link BassaltRenderPass#beginOcclusionQuery} / {@link BassaltRenderPass#endOcclusionQuery}.
+     */
+    public RenderPass createRenderPass(
+        @Nullable Supplier<String> label,
+        @Nullable GpuTextureView colorTexture,
+        OptionalInt clearColor,
+        @Nullable GpuTextureView depthTexture,
+        OptionalDouble clearDepth,
+        @Nullable BassaltOcclusionQuerySet occlusionQueries
     ) {
         // Get native pointers from texture views
         long colorPtr = 0;
@@ -94,9 +122,9 @@ public class BassaltCommandEncoder implements CommandEncoder {
 
         if (colorTexture instanceof com.criticalrange.bassalt.texture.BassaltTextureView) {
             colorPtr = ((com.criticalrange.bassalt.texture.BassaltTextureView) colorTexture).getNativePtr();
-            // Get actual dimensions from the texture
-            width = colorTexture.texture().getWidth(0);
-            height = colorTexture.texture().getHeight(0);
+            // Size of the mip the view renders to; native checks every attachment against it
+            width = colorTexture.texture().getWidth(colorTexture.baseMipLevel());
+            height = colorTexture.texture().getHeight(colorTexture.baseMipLevel());
         }
         if (depthTexture instanceof com.criticalrange.bassalt.texture.BassaltTextureView) {
             depthPtr = ((com.criticalrange.bassalt.texture.BassaltTextureView) depthTexture).getNativePtr();
@@ -107,17 +135,79 @@ public class BassaltCommandEncoder implements CommandEncoder {
         int clear = clearColor.orElse(0xFF000000); // Opaque black default
         float depthVal = (float) clearDepth.orElse(1.0);
 
-        currentRenderPass = device.beginRenderPass(
+        if (occlusionQueries != null) {
+            currentRenderPass = BassaltDevice.beginRenderPassOcclusion(
+                device.getNativePtr(),
+                colorPtr,
+                depthPtr,
+                occlusionQueries.getNativePtr(),
+                shouldClearColor,
+                clear,
+                shouldClearDepth,
+                depthVal,
+                0,
+                width,
+                height
+            );
+        } else {
+            currentRenderPass = device.beginRenderPass(
+                device.getNativePtr(),
+                colorPtr,
+                depthPtr,
+                shouldClearColor,
+                clear,
+                shouldClearDepth,
+                depthVal,
+                0,
+                width,
+                height
+            );
+        }
+
+        isActive = true;
+        return new BassaltRenderPass(device, currentRenderPass);
+    }
+
+    /**
+     * Create a render pass with several color attachments (MRT). {@code colorTextures.get(i)}
+     * receives the fragment shader's {@code @location(i)} output; all attachments must have
+     * the same size and sample count and share the clear color.
+     */
+    public RenderPass createRenderPassMRT(
+        @Nullable Supplier<String> label,
+        List<GpuTextureView> colorTextures,
+        OptionalInt clearColor,
+        @Nullable GpuTextureView depthTexture,
+        OptionalDouble clearDepth
+    ) {
+        if (colorTextures.isEmpty()) {
+            throw new IllegalArgumentException("At least one color texture is required");
+        }
+
+        long[] colorPtrs = new long[colorTextures.size()];
+        for (int i = 0; i < colorPtrs.length; i++) {
+            if (!(colorTextures.get(i) instanceof com.criticalrange.bassalt.texture.BassaltTextureView view)) {
+                throw new IllegalArgumentException("Color texture " + i + " is not a Bassalt texture view");
+            }
+            colorPtrs[i] = view.getNativePtr();
+        }
+        long depthPtr = 0;
+        if (depthTexture instanceof com.criticalrange.bassalt.texture.BassaltTextureView) {
+            depthPtr = ((com.criticalrange.bassalt.texture.BassaltTextureView) depthTexture).getNativePtr();
+        }
+
+        GpuTextureView primary = colorTextures.get(0);
+        currentRenderPass = BassaltDevice.beginRenderPassMRT(
             device.getNativePtr(),
-            colorPtr,
+            colorPtrs,
             depthPtr,
-            shouldClearColor,
-            clear,
-            shouldClearDepth,
-            depthVal,
+            clearColor.isPresent(),
+            clearColor.orElse(0xFF000000),
+            clearDepth.isPresent(),
+            (float) clearDepth.orElse(1.0),
             0,
-            width,
-            height
+            primary.texture().getWidth(primary.baseMipLevel()),
+            primary.texture().getHeight(primary.baseMipLevel())
         );
 
         isActive = true;
@@ -225,10 +315,11 @@ public class BassaltCommandEncoder implements CommandEncoder {
                     destination, width, height, source.format(), Long.toHexString(nativePointer));
         }
 
+        // Pixels were expanded to 4 bytes per texel above
         long texturePtr = ((BassaltTexture) destination).getNativePtr();
         writeToTexture0(device.getNativePtr(), texturePtr, pixels,
             mipLevel, depthOrLayer, destX, destY, width, height,
-            destination.getFormat().ordinal());
+            BassaltBackend.FORMAT_RGBA8, BassaltBackend.SWIZZLE_NONE);
     }
 
 
@@ -244,22 +335,109 @@ public class BassaltCommandEncoder implements CommandEncoder {
         int width,
         int height
     ) {
-        byte[] data = new byte[source.remaining()];
+        long texturePtr = ((BassaltTexture) destination).getNativePtr();
+        int length = source.remaining();
+        if (source.isDirect()) {
+            // Read in place; avoids copying the whole image onto the Java heap
+            writeToTextureDirect0(device.getNativePtr(), texturePtr, source.slice(source.position(), length), length,
+                mipLevel, depthOrLayer, destX, destY, width, height,
+                toSourceFormat(format), BassaltBackend.SWIZZLE_NONE);
+            source.position(source.limit());
+            return;
+        }
+
+        byte[] data = new byte[length];
         source.get(data);
+        writeToTexture0(device.getNativePtr(), texturePtr, data,
+            mipLevel, depthOrLayer, destX, destY, width, height,
+            toSourceFormat(format), BassaltBackend.SWIZZLE_NONE);
+    }
 
+    /**
+     * Upload 4-byte texels whose channel order differs from the texture's, reordered
+     * natively by {@code sourceSwizzle} ({@link BassaltBackend#SWIZZLE_SWAP_RB} for BGRA
+     * data into an RGBA texture) instead of a per-pixel loop on the JVM.
+     *
+     * @throws IllegalArgumentException if the swizzle doesn't apply to the texture's format
+     */
+    public void writeSwizzledToTexture(GpuTexture destination, byte[] data, int sourceSwizzle, int mipLevel,
+            int depthOrLayer, int destX, int destY, int width, int height) {
         long texturePtr = ((BassaltTexture) destination).getNativePtr();
         writeToTexture0(device.getNativePtr(), texturePtr, data,
             mipLevel, depthOrLayer, destX, destY, width, height,
-            format.ordinal());
+            BassaltBackend.FORMAT_RGBA8, sourceSwizzle);
+    }
+
+    /**
+     * One region of {@link #writeTexturesBatch}. Its rows start at {@code dataOffset}
+     * in the batch's data, tightly packed in the texture's format.
+     */
+    public record TextureWrite(GpuTexture texture, int mipLevel, int layer, int destX, int destY,
+            int width, int height, long dataOffset) {
+    }
+
+    /**
+     * Upload many small regions (e.g. sprites while stitching an atlas) through one
+     * staging buffer and a single submission instead of one upload per region.
+     * Every region is validated before anything is uploaded.
+     */
+    public void writeTexturesBatch(List<TextureWrite> writes, byte[] data) {
+        if (writes.isEmpty()) {
+            return;
+        }
+        long[] texturePtrs = new long[writes.size()];
+        int[] regions = new int[writes.size() * 6];
+        long[] dataOffsets = new long[writes.size()];
+        for (int i = 0; i < writes.size(); i++) {
+            TextureWrite write = writes.get(i);
+            texturePtrs[i] = ((BassaltTexture) write.texture()).getNativePtr();
+            regions[i * 6] = write.mipLevel();
+            regions[i * 6 + 1] = write.layer();
+            regions[i * 6 + 2] = write.destX();
+            regions[i * 6 + 3] = write.destY();
+            regions[i * 6 + 4] = write.width();
+            regions[i * 6 + 5] = write.height();
+            dataOffsets[i] = write.dataOffset();
+        }
+        writeTexturesBatch0(device.getNativePtr(), texturePtrs, regions, dataOffsets, data);
+    }
+
+    /**
+     * Upload raw compressed blocks to a texture from
+     * {@link BassaltDevice#createCompressedTexture}. {@code width}/{@code height} are in
+     * texels and the data is tightly packed rows of blocks, as stored in DDS/KTX files.
+     */
+    public void writeCompressedToTexture(GpuTexture destination, byte[] blocks, int mipLevel, int depthOrLayer,
+            int destX, int destY, int width, int height) {
+        long texturePtr = ((BassaltTexture) destination).getNativePtr();
+        writeToTexture0(device.getNativePtr(), texturePtr, blocks,
+            mipLevel, depthOrLayer, destX, destY, width, height, -1, BassaltBackend.SWIZZLE_NONE);
+    }
+
+    /**
+     * Describe the packing of raw image data so the native side can widen
+     * RGB sources to the 4-byte texture format.
+     */
+    private static int toSourceFormat(NativeImage.Format format) {
+        return switch (format.components()) {
+            case 3 -> BassaltBackend.FORMAT_RGB8;
+            case 2 -> BassaltBackend.FORMAT_RG8;
+            case 1 -> BassaltBackend.FORMAT_R8;
+            default -> BassaltBackend.FORMAT_RGBA8;
+        };
     }
 
     @Override
     public void writeToBuffer(GpuBufferSlice destination, ByteBuffer data) {
-        byte[] arr = new byte[data.remaining()];
-        data.get(arr);
         long bufferPtr = ((com.criticalrange.bassalt.buffer.BassaltBuffer) destination.buffer()).getNativePtr();
 
-        BassaltDevice.writeBuffer(device.getNativePtr(), bufferPtr, arr, destination.offset());
+        // Uniform writes are small and frequent, so they're batched until the next pass
+        boolean aligned = (destination.offset() & 3) == 0 && (data.remaining() & 3) == 0;
+        if (aligned && (destination.buffer().usage() & GpuBuffer.USAGE_UNIFORM) != 0) {
+            BassaltDevice.updateUniform(device.getNativePtr(), bufferPtr, data, destination.offset());
+        } else {
+            BassaltDevice.writeBuffer(device.getNativePtr(), bufferPtr, data, destination.offset());
+        }
     }
 
     @Override
@@ -313,6 +491,21 @@ public class BassaltCommandEncoder implements CommandEncoder {
         clearDepthTexture0(device.getNativePtr(), texturePtr, (float) depth);
     }
 
+    /**
+     * Clear a single layer of a texture: an array layer, a cubemap face or a 3D slice.
+     * Other layers keep their contents.
+     */
+    public void clearColorTextureLayer(GpuTexture texture, int clearColor, int layer) {
+        long texturePtr = ((BassaltTexture) texture).getNativePtr();
+        clearColorTextureLayer0(device.getNativePtr(), texturePtr, clearColor, layer);
+    }
+
+    /** Clear a single layer of a depth texture; other layers keep their contents */
+    public void clearDepthTextureLayer(GpuTexture texture, double depth, int layer) {
+        long texturePtr = ((BassaltTexture) texture).getNativePtr();
+        clearDepthTextureLayer0(device.getNativePtr(), texturePtr, (float) depth, layer);
+    }
+
     @Override
     public void clearColorAndDepthTextures(GpuTexture colorTexture, int clearColor, GpuTexture depthTexture,
                                            double clearDepth) {
@@ -331,6 +524,16 @@ public class BassaltCommandEncoder implements CommandEncoder {
                                      x, y, width, height);
     }
 
+    /**
+     * Zero {@code size} bytes of a buffer starting at {@code offset}, e.g. to reset indirect
+     * draw counts or atomic counters between frames. Both must be multiples of 4; a size of
+     * -1 clears to the end of the buffer. The buffer needs copy-destination usage.
+     */
+    public void clearBuffer(GpuBuffer buffer, long offset, long size) {
+        long bufferPtr = ((com.criticalrange.bassalt.buffer.BassaltBuffer) buffer).getNativePtr();
+        clearBuffer0(device.getNativePtr(), bufferPtr, offset, size);
+    }
+
     @Override
     public void copyToBuffer(GpuBufferSlice source, GpuBufferSlice target) {
         long srcPtr = ((com.criticalrange.bassalt.buffer.BassaltBuffer) source.buffer()).getNativePtr();
@@ -354,7 +557,7 @@ public class BassaltCommandEncoder implements CommandEncoder {
 
         // Submit async copy
         copyTextureToBuffer0(device.getNativePtr(), texturePtr, bufferPtr,
-            offset, mipLevel, source.getWidth(mipLevel), source.getHeight(mipLevel));
+            offset, mipLevel, source.getWidth(mipLevel), source.getHeight(mipLevel), readbackAspect(source));
 
         // Run callback (in a real implementation, this should be called when the copy completes)
         if (callback != null) {
@@ -379,7 +582,7 @@ public class BassaltCommandEncoder implements CommandEncoder {
 
         // Submit async copy with region
         copyTextureToBuffer0(device.getNativePtr(), texturePtr, bufferPtr,
-            offset, mipLevel, width, height);
+            offset, mipLevel, width, height, readbackAspect(source));
 
         // Run callback (in a real implementation, this should be called when the copy completes)
         if (callback != null) {
@@ -387,6 +590,11 @@ public class BassaltCommandEncoder implements CommandEncoder {
         }
     }
 
+    /** Depth textures are read back as their depth values, 4 bytes per texel for DEPTH32 */
+    private static int readbackAspect(GpuTexture texture) {
+        return texture.getFormat().hasDepthAspect() ? BassaltBackend.ASPECT_DEPTH_ONLY : BassaltBackend.ASPECT_ALL;
+    }
+
     @Override
     public void presentTexture(GpuTextureView textureView) {
         // Texture presentation is handled via swapchain, not individual textures
@@ -422,17 +630,38 @@ public class BassaltCommandEncoder implements CommandEncoder {
         int sourceY,
         int width,
         int height
+    ) {
+        copyTextureToTexture(source, mipLevel, destination, mipLevel, destX, destY, sourceX, sourceY, width, height);
+    }
+
+    /**
+     * Copy a region from {@code srcMipLevel} of one texture into {@code dstMipLevel}
+     * of another, e.g. to assemble a mip chain from separately loaded images.
+     * The region must fit inside both mip levels.
+     */
+    public void copyTextureToTexture(
+        GpuTexture source,
+        int srcMipLevel,
+        GpuTexture destination,
+        int dstMipLevel,
+        int destX,
+        int destY,
+        int sourceX,
+        int sourceY,
+        int width,
+        int height
     ) {
         long srcPtr = ((BassaltTexture) source).getNativePtr();
         long dstPtr = ((BassaltTexture) destination).getNativePtr();
         copyTextureToTexture0(device.getNativePtr(), srcPtr, dstPtr,
-                               mipLevel, destX, destY, sourceX, sourceY, width, height);
+                               srcMipLevel, dstMipLevel, destX, destY, sourceX, sourceY, width, height);
     }
 
     public void finish() {
         if (currentRenderPass != 0) {
-            device.endRenderPass(device.getNativePtr(), currentRenderPass);
+            long renderPass = currentRenderPass;
             currentRenderPass = 0;
+            device.endRenderPass(device.getNativePtr(), renderPass);
         }
         isActive = false;
         finished = true;
@@ -0,0 +1,26 @@
+package com.criticalrange.bassalt.pipeline;
+
+import com.criticalrange.bassalt.backend.BassaltDevice;
+import net.fabricmc.api.EnvType;
+import net.fabricmc.api.Environment;
+
+import java.util.Map;
+
+/**
+ * Optional pipeline state accepted by every {@code BassaltDevice} pipeline creator
+ *
+ * {@code indexFormat} ({@code BassaltDevice.INDEX_FORMAT_*}) is the index type strip
+ * topologies are drawn with, so the all-ones index restarts the strip.
+ * {@code polygonMode} ({@code BassaltDevice.POLYGON_MODE_*}) selects wireframe or point
+ * rendering. With {@code background} set the shaders compile on a worker thread; see
+ * {@code BassaltDevice.isPipelineReady}. {@code overrideConstants} specializes the
+ * shaders' WGSL {@code override} declarations by name or {@code @id}.
+ */
+@Environment(EnvType.CLIENT)
+public record BassaltPipelineOptions(int indexFormat, int polygonMode, boolean background,
+        Map<String, Double> overrideConstants) {
+
+    /** Unknown index format, filled polygons, blocking compilation and no override constants */
+    public static final BassaltPipelineOptions DEFAULT = new BassaltPipelineOptions(
+            BassaltDevice.INDEX_FORMAT_UNKNOWN, BassaltDevice.POLYGON_MODE_FILL, false, Map.of());
+}
@@ -0,0 +1,42 @@
+package com.criticalrange.bassalt.pipeline;
+
+import com.criticalrange.bassalt.backend.BassaltDevice;
+import net.fabricmc.api.EnvType;
+import net.fabricmc.api.Environment;
+
+/**
+ * Bassalt Render Bundle - a finished draw sequence from {@link BassaltRenderBundleEncoder}.
+ *
+ * Replay it with {@link BassaltRenderPass#executeBundles} as often as needed; close it once
+ * no pass will run it again. Passes that already executed it still run it; the native
+ * bundle is released after the frame they belong to is submitted.
+ */
+@Environment(EnvType.CLIENT)
+public class BassaltRenderBundle implements AutoCloseable {
+
+    private final BassaltDevice device;
+    private final long handle;
+    private boolean closed = false;
+
+    private static native void destroy0(long devicePtr, long bundleHandle);
+
+    BassaltRenderBundle(BassaltDevice device, long handle) {
+        this.device = device;
+        this.handle = handle;
+    }
+
+    public long getHandle() {
+        if (closed) {
+            throw new IllegalStateException("Render bundle is closed");
+        }
+        return handle;
+    }
+
+    @Override
+    public void close() {
+        if (!closed && handle != 0) {
+            destroy0(device.getNativePtr(), handle);
+        }
+        closed = true;
+    }
+}
@@ -0,0 +1,104 @@
+package com.criticalrange.bassalt.pipeline;
+
+import com.criticalrange.bassalt.backend.BassaltDevice;
+import com.criticalrange.bassalt.buffer.BassaltBuffer;
+import net.fabricmc.api.EnvType;
+import net.fabricmc.api.Environment;
+import org.jspecify.annotations.Nullable;
+
+/**
+ * Bassalt Render Bundle Encoder - records a draw sequence once for replay in many passes.
+ *
+ * Static sequences such as the HUD can be recorded here and replayed every frame with
+ * {@link BassaltRenderPass#executeBundles}, skipping per-draw recording. Record calls report
+ * problems through {@code BassaltDevice.getLastError()}; the recorded commands are validated
+ * by {@link #finish}. The bundle only runs in passes whose attachment formats and sample count
+ * match the ones given here.
+ */
+@Environment(EnvType.CLIENT)
+public class BassaltRenderBundleEncoder implements AutoCloseable {
+
+    /** Depth format meaning "the passes have no depth attachment". */
+    public static final int NO_DEPTH = -1;
+
+    private final BassaltDevice device;
+    private long nativePtr;
+
+    private static native long create0(long devicePtr, int[] colorFormats, int depthFormat, int sampleCount);
+    private static native void destroy0(long encoderPtr);
+    private static native void setPipeline0(long encoderPtr, long pipelineHandle);
+    private static native void setBindGroup0(long devicePtr, long encoderPtr, int index, long bindGroupHandle,
+            long[] offsets);
+    private static native void setVertexBuffer0(long encoderPtr, int slot, long bufferHandle, long offset);
+    private static native void setIndexBuffer0(long encoderPtr, long bufferHandle, int indexType, long offset);
+    private static native void draw0(long encoderPtr, int vertexCount, int instanceCount, int firstVertex,
+            int firstInstance);
+    private static native void drawIndexed0(long encoderPtr, int indexCount, int instanceCount, int firstIndex,
+            int baseVertex, int firstInstance);
+    private static native long finish0(long devicePtr, long encoderPtr, String label);
+
+    /**
+     * @param colorFormats {@code BassaltBackend.FORMAT_*} of the passes' color attachments
+     * @param depthFormat  {@code BassaltBackend.FORMAT_*} of their depth attachment, or {@link #NO_DEPTH}
+     */
+    public BassaltRenderBundleEncoder(BassaltDevice device, int[] colorFormats, int depthFormat, int sampleCount) {
+        this.device = device;
+        this.nativePtr = create0(device.getNativePtr(), colorFormats, depthFormat, sampleCount);
+    }
+
+    public void setPipeline(BassaltCompiledRenderPipeline pipeline) {
+        setPipeline0(checkOpen(), pipeline.getNativePtr());
+    }
+
+    /**
+     * @param dynamicOffsets offsets for the group's dynamic uniform bindings, or null if it has none
+     */
+    public void setBindGroup(int index, long bindGroupHandle, long @Nullable [] dynamicOffsets) {
+        setBindGroup0(device.getNativePtr(), checkOpen(), index, bindGroupHandle, dynamicOffsets);
+    }
+
+    public void setVertexBuffer(int slot, BassaltBuffer buffer, long offset) {
+        setVertexBuffer0(checkOpen(), slot, buffer.getNativePtr(), offset);
+    }
+
+    /**
+     * @param indexType 0 for 16-bit indices, 1 for 32-bit
+     */
+    public void setIndexBuffer(BassaltBuffer buffer, int indexType, long offset) {
+        setIndexBuffer0(checkOpen(), buffer.getNativePtr(), indexType, offset);
+    }
+
+    public void draw(int vertexCount, int instanceCount, int firstVertex, int firstInstance) {
+        draw0(checkOpen(), vertexCount, instanceCount, firstVertex, firstInstance);
+    }
+
+    public void drawIndexed(int indexCount, int instanceCount, int firstIndex, int baseVertex, int firstInstance) {
+        drawIndexed0(checkOpen(), indexCount, instanceCount, firstIndex, baseVertex, firstInstance);
+    }
+
+    /**
+     * Finish recording. The encoder can't be used afterwards, whether or not this succeeds.
+     *
+     * @throws IllegalStateException if the recorded commands fail validation
+     */
+    public BassaltRenderBundle finish(@Nullable String label) {
+        long encoderPtr = checkOpen();
+        nativePtr = 0;
+        return new BassaltRenderBundle(device, finish0(device.getNativePtr(), encoderPtr, label));
+    }
+
+    private long checkOpen() {
+        if (nativePtr == 0) {
+            throw new IllegalStateException("Render bundle encoder is finished or closed");
+        }
+        return nativePtr;
+    }
+
+    @Override
+    public void close() {
+        if (nativePtr != 0) {
+            destroy0(nativePtr);
+            nativePtr = 0;
+        }
+    }
+}
@@ -2,6 +2,7 @@ package com.criticalrange.bassalt.pipeline;
 
 import com.criticalrange.bassalt.backend.BassaltDevice;
 import com.criticalrange.bassalt.buffer.BassaltBuffer;
+import com.criticalrange.bassalt.sync.BassaltPipelineStatsQuerySet;
 import com.criticalrange.bassalt.texture.BassaltSampler;
 import com.criticalrange.bassalt.texture.BassaltTextureView;
 import com.mojang.blaze3d.buffers.GpuBuffer;
@@ -52,11 +53,19 @@ public class BassaltRenderPass implements RenderPass {
     private static native long createBindGroup0(long devicePtr, long renderPassPtr, long pipelineHandle,
             String[] textureNames, long[] textures, long[] samplers,
             String[] uniformNames, long[] uniforms, long[] uniformOffsets, long[] uniformSizes);
+    private static native void setBindGroup0(long devicePtr, long renderPassPtr, int index, long bindGroupHandle);
+    private static native void setBindGroupDynamic0(long devicePtr, long renderPassPtr, int index,
+            long bindGroupHandle, long[] offsets);
 
     // Native methods for debug groups and markers
     private static native void pushDebugGroup(long renderPassPtr, String label);
     private static native void popDebugGroup(long renderPassPtr);
     private static native void insertDebugMarker(long renderPassPtr, String label);
+    private static native void beginOcclusionQuery0(long renderPassPtr, int index);
+    private static native void endOcclusionQuery0(long renderPassPtr);
+    private static native void beginPipelineStatsQuery0(long renderPassPtr, long queryPtr, int index);
+    private static native void endPipelineStatsQuery0(long renderPassPtr);
+    private static native void executeBundles0(long renderPassPtr, long[] bundleHandles);
 
     BassaltRenderPass(BassaltDevice device, long nativePassPtr) {
         this.device = device;
@@ -143,6 +152,24 @@ public class BassaltRenderPass implements RenderPass {
         }
     }
 
+    /**
+     * Bind a storage buffer ({@code var<storage>}) by its shader variable name. Storage
+     * buffers go through the same name matching as uniforms; the pipeline's reflected
+     * layout decides how the buffer is bound. The buffer needs storage usage.
+     */
+    public void bindStorageBuffer(String name, GpuBufferSlice value) {
+        setUniform(name, value);
+    }
+
+    /**
+     * Draw into a sub-rectangle of the attachments, e.g. one half of a split screen or
+     * a map inset. Render passes start with the full-framebuffer viewport.
+     */
+    public void setViewport(float x, float y, float width, float height, float minDepth, float maxDepth) {
+        checkClosed();
+        BassaltDevice.setViewport(device.getNativePtr(), nativePassPtr, x, y, width, height, minDepth, maxDepth);
+    }
+
     @Override
     public void enableScissor(int x, int y, int width, int height) {
         checkClosed();
@@ -233,6 +260,50 @@ public class BassaltRenderPass implements RenderPass {
                 0);
     }
 
+    /**
+     * Per-draw data for shaders with a {@code var<immediate>} block, such as a
+     * model matrix. Offset and length must be multiples of 4; see
+     * {@link BassaltDevice#supportsPushConstants()} for the cost on backends without them.
+     */
+    public void setPushConstants(int offset, byte[] data) {
+        setPushConstants(BassaltDevice.SHADER_STAGE_VERTEX | BassaltDevice.SHADER_STAGE_FRAGMENT, offset, data);
+    }
+
+    /**
+     * Per-draw data for the given stages only, a mask of
+     * {@link BassaltDevice#SHADER_STAGE_VERTEX} and {@link BassaltDevice#SHADER_STAGE_FRAGMENT}.
+     * The write must fit the immediate block one of those stages declares.
+     */
+    public void setPushConstants(int stages, int offset, byte[] data) {
+        checkClosed();
+        device.setPushConstants(device.getNativePtr(), nativePassPtr, stages, offset, data);
+    }
+
+    /**
+     * Indexed draws whose arguments live in a GPU buffer, for renderers that
+     * batch chunk draws on the GPU. Each argument struct is 20 bytes.
+     */
+    public void drawIndexedIndirect(GpuBuffer indirectBuffer, long offset, int drawCount) {
+        checkClosed();
+
+        if (currentPipelineHandle == 0) {
+            return;
+        }
+
+        applyBindings();
+
+        if (!hasValidBindGroup) {
+            return;
+        }
+
+        long bufferPtr = ((BassaltBuffer) indirectBuffer).getNativePtr();
+        if (drawCount == 1) {
+            device.drawIndexedIndirect(device.getNativePtr(), nativePassPtr, bufferPtr, offset);
+        } else {
+            device.multiDrawIndexedIndirect(device.getNativePtr(), nativePassPtr, bufferPtr, offset, drawCount);
+        }
+    }
+
     @Override
     public <T> void drawMultipleIndexed(
             Collection<Draw<T>> draws,
@@ -292,8 +363,9 @@ public class BassaltRenderPass implements RenderPass {
     @Override
     public void close() {
         if (!closed) {
-            device.endRenderPass(device.getNativePtr(), nativePassPtr);
+            // Native side owns the pass from here on, even if the submit throws
             closed = true;
+            device.endRenderPass(device.getNativePtr(), nativePassPtr);
         }
     }
 
@@ -355,6 +427,69 @@ public class BassaltRenderPass implements RenderPass {
         }
     }
 
+    /**
+     * Bind a bind group from {@link BassaltDevice#createBindGroupExplicit} at group {@code index}.
+     */
+    public void setBindGroup(int index, long bindGroupHandle) {
+        checkClosed();
+        setBindGroup0(device.getNativePtr(), nativePassPtr, index, bindGroupHandle);
+    }
+
+    /**
+     * Bind a bind group whose layout has {@link BassaltBindGroupLayoutSpec.Entry#dynamicUniformBuffer}
+     * entries, supplying one offset per dynamic binding in binding order.
+     * Each offset must be a multiple of {@link BassaltDevice#getUniformOffsetAlignment()}.
+     */
+    public void setBindGroupDynamic(int index, long bindGroupHandle, long[] offsets) {
+        checkClosed();
+        setBindGroupDynamic0(device.getNativePtr(), nativePassPtr, index, bindGroupHandle, offsets);
+    }
+
+    /**
+     * Start counting samples that pass depth testing into occlusion query {@code index}.
+     * The pass must have been created with an occlusion query set; queries can't nest.
+     */
+    public void beginOcclusionQuery(int index) {
+        checkClosed();
+        beginOcclusionQuery0(nativePassPtr, index);
+    }
+
+    /** End the occlusion query opened by {@link #beginOcclusionQuery} */
+    public void endOcclusionQuery() {
+        checkClosed();
+        endOcclusionQuery0(nativePassPtr);
+    }
+
+    /**
+     * Start counting the work of the following draws into pipeline statistics query
+     * {@code index} of {@code queries}. Queries can't nest.
+     */
+    public void beginPipelineStatsQuery(BassaltPipelineStatsQuerySet queries, int index) {
+        checkClosed();
+        beginPipelineStatsQuery0(nativePassPtr, queries.getNativePtr(), index);
+    }
+
+    /** End the pipeline statistics query opened by {@link #beginPipelineStatsQuery} */
+    public void endPipelineStatsQuery() {
+        checkClosed();
+        endPipelineStatsQuery0(nativePassPtr);
+    }
+
+    /**
+     * Replay pre-recorded render bundles, in order. The pass's pipeline, bind groups and
+     * buffers are unset afterwards, so set a pipeline again before drawing outside a bundle.
+     */
+    public void executeBundles(BassaltRenderBundle... bundles) {
+        checkClosed();
+        long[] handles = new long[bundles.length];
+        for (int i = 0; i < bundles.length; i++) {
+            handles[i] = bundles[i].getHandle();
+        }
+        executeBundles0(nativePassPtr, handles);
+        currentPipeline = null;
+        currentPipelineHandle = 0;
+    }
+
     private void checkClosed() {
         if (closed) {
             throw new IllegalStateException("Render pass is closed");
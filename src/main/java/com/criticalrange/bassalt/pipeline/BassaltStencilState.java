@@ -0,0 +1,29 @@
+package com.criticalrange.bassalt.pipeline;
+
+import com.criticalrange.bassalt.backend.BassaltBackend;
+import net.fabricmc.api.EnvType;
+import net.fabricmc.api.Environment;
+
+/**
+ * Stencil test state for a native pipeline
+ *
+ * Compare functions use the {@code BassaltBackend.COMPARE_FUNC_*} constants and
+ * operations the {@code BassaltBackend.STENCIL_OP_*} constants. Pipelines with an
+ * enabled stencil test need a {@code FORMAT_DEPTH24_STENCIL8} depth attachment;
+ * the reference value is set per draw with {@code BassaltDevice.setStencilReference}.
+ */
+@Environment(EnvType.CLIENT)
+public record BassaltStencilState(int readMask, int writeMask, Face front, Face back) {
+
+    /**
+     * Stencil behaviour for one face: compare, then the operation for stencil
+     * failure, depth failure and pass.
+     */
+    public record Face(int compare, int failOp, int depthFailOp, int passOp) {
+        public static final Face IGNORE = new Face(BassaltBackend.COMPARE_FUNC_ALWAYS,
+                BassaltBackend.STENCIL_OP_KEEP, BassaltBackend.STENCIL_OP_KEEP, BassaltBackend.STENCIL_OP_KEEP);
+    }
+
+    /** Stencil test disabled */
+    public static final BassaltStencilState DISABLED = new BassaltStencilState(0, 0, Face.IGNORE, Face.IGNORE);
+}
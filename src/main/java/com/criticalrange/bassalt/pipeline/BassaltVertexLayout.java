@@ -0,0 +1,39 @@
+package com.criticalrange.bassalt.pipeline;
+
+import com.criticalrange.bassalt.backend.BassaltBackend;
+import net.fabricmc.api.EnvType;
+import net.fabricmc.api.Environment;
+
+import java.util.List;
+
+/**
+ * Caller-described vertex input layout for a native pipeline
+ *
+ * Used instead of the fixed vertex format indices when a vertex format isn't
+ * one of the built-in layouts, or when per-instance data lives in a second
+ * vertex buffer. Buffer {@code i} in {@link #buffers} is bound at vertex buffer
+ * slot {@code i}. Formats use the {@code BassaltBackend.VERTEX_FORMAT_*}
+ * constants and step modes the {@code BassaltBackend.VERTEX_STEP_MODE_*} constants.
+ */
+@Environment(EnvType.CLIENT)
+public record BassaltVertexLayout(List<Buffer> buffers) {
+
+    /** One vertex buffer slot: stride in bytes, step mode and the attributes it feeds */
+    public record Buffer(long arrayStride, int stepMode, List<Attribute> attributes) {
+        public static Buffer perVertex(long arrayStride, Attribute... attributes) {
+            return new Buffer(arrayStride, BassaltBackend.VERTEX_STEP_MODE_VERTEX, List.of(attributes));
+        }
+
+        public static Buffer perInstance(long arrayStride, Attribute... attributes) {
+            return new Buffer(arrayStride, BassaltBackend.VERTEX_STEP_MODE_INSTANCE, List.of(attributes));
+        }
+    }
+
+    /** A shader input read from {@code offset} bytes into each element */
+    public record Attribute(int shaderLocation, int format, long offset) {
+    }
+
+    public static BassaltVertexLayout of(Buffer... buffers) {
+        return new BassaltVertexLayout(List.of(buffers));
+    }
+}
@@ -0,0 +1,65 @@
+package com.criticalrange.bassalt.shader;
+
+import net.fabricmc.api.EnvType;
+import net.fabricmc.api.Environment;
+
+/**
+ * Thrown by the native layer when a WGSL shader fails to parse.
+ *
+ * Carries the location reported by naga so the broken line can be found
+ * without digging through the native log.
+ */
+@Environment(EnvType.CLIENT)
+public class ShaderCompilationException extends RuntimeException {
+
+    private final String shaderName;
+    private final String stage;
+    private final int line;
+    private final int column;
+    private final String sourceLine;
+
+    public ShaderCompilationException(String message, String shaderName, String stage,
+            int line, int column, String sourceLine) {
+        super(message);
+        this.shaderName = shaderName;
+        this.stage = stage;
+        this.line = line;
+        this.column = column;
+        this.sourceLine = sourceLine;
+    }
+
+    /**
+     * Name of the pipeline/shader that failed to compile
+     */
+    public String getShaderName() {
+        return shaderName;
+    }
+
+    /**
+     * Shader stage that failed ("vertex" or "fragment")
+     */
+    public String getStage() {
+        return stage;
+    }
+
+    /**
+     * 1-based line of the error, or -1 if unknown
+     */
+    public int getLine() {
+        return line;
+    }
+
+    /**
+     * 1-based column of the error, or -1 if unknown
+     */
+    public int getColumn() {
+        return column;
+    }
+
+    /**
+     * The offending source line, or null if unknown
+     */
+    public String getSourceLine() {
+        return sourceLine;
+    }
+}
@@ -1,5 +1,7 @@
 package com.criticalrange.bassalt.shader;
 
+import java.util.Map;
+
 import net.fabricmc.api.EnvType;
 import net.fabricmc.api.Environment;
 
@@ -19,27 +21,42 @@ public class WgslCompiler {
     private static final int STAGE_COMPUTE = 2;
 
     // Native method declaration
-    private static native String translateGlslToWgsl(String glslSource, int stage);
+    // defineNames/defineValues are parallel arrays, null when there are no defines
+    private static native String translateGlslToWgsl(String glslSource, int stage, String[] defineNames,
+            String[] defineValues);
+
+    private static native String translateGlslToWgslWithIncludes(String glslSource, int stage, String[] includePaths,
+            String[] defineNames, String[] defineValues);
+
+    private static native String translateSpirvToWgsl(byte[] spirv);
+
+    private static native int detectWgslStage(String wgslSource);
+
+    /**
+     * Drop cached translations. Identical GLSL is only translated once, so call
+     * this when resource packs are reloaded to release the old shaders.
+     */
+    public static native void clearTranslationCache();
 
     /**
      * Translate a vertex shader from GLSL to WGSL
      */
     public static String translateVertexShader(String glslSource) {
-        return translateGlslToWgsl(glslSource, STAGE_VERTEX);
+        return translateGlslToWgsl(glslSource, STAGE_VERTEX, null, null);
     }
 
     /**
      * Translate a fragment shader from GLSL to WGSL
      */
     public static String translateFragmentShader(String glslSource) {
-        return translateGlslToWgsl(glslSource, STAGE_FRAGMENT);
+        return translateGlslToWgsl(glslSource, STAGE_FRAGMENT, null, null);
     }
 
     /**
      * Translate a compute shader from GLSL to WGSL
      */
     public static String translateComputeShader(String glslSource) {
-        return translateGlslToWgsl(glslSource, STAGE_COMPUTE);
+        return translateGlslToWgsl(glslSource, STAGE_COMPUTE, null, null);
     }
 
     /**
@@ -50,7 +67,61 @@ public class WgslCompiler {
      * @return WGSL shader source code
      */
     public static String translate(String glslSource, int stage) {
-        return translateGlslToWgsl(glslSource, stage);
+        return translateGlslToWgsl(glslSource, stage, null, null);
+    }
+
+    /**
+     * Translate GLSL shader to WGSL, inlining #moj_import directives first
+     *
+     * @param glslSource GLSL shader source code
+     * @param stage Shader stage (0=vertex, 1=fragment, 2=compute)
+     * @param includePaths Directories searched for imported files, in order
+     * @return WGSL shader source code
+     */
+    public static String translate(String glslSource, int stage, String... includePaths) {
+        return translateGlslToWgslWithIncludes(glslSource, stage, includePaths, null, null);
+    }
+
+    /**
+     * Translate GLSL shader to WGSL with preprocessor defines, selecting the
+     * variant for {@code #ifdef USE_FOG}-style blocks
+     *
+     * @param glslSource GLSL shader source code
+     * @param stage Shader stage (0=vertex, 1=fragment, 2=compute)
+     * @param defines Macro names to values; an empty value is enough for {@code #ifdef}
+     * @param includePaths Directories searched for #moj_import files, in order
+     * @return WGSL shader source code
+     */
+    public static String translate(String glslSource, int stage, Map<String, String> defines,
+            String... includePaths) {
+        String[] names = defines.keySet().toArray(new String[0]);
+        String[] values = new String[names.length];
+        for (int i = 0; i < names.length; i++) {
+            values[i] = defines.get(names[i]);
+        }
+        return translateGlslToWgslWithIncludes(glslSource, stage, includePaths, names, values);
+    }
+
+    /**
+     * Translate a pre-compiled SPIR-V binary to WGSL
+     *
+     * @param spirv SPIR-V binary (length must be a multiple of 4)
+     * @return WGSL shader source code
+     */
+    public static String translateSpirv(byte[] spirv) {
+        return translateSpirvToWgsl(spirv);
+    }
+
+    /**
+     * Detect the stage of a WGSL shader from its {@code @vertex}/{@code @fragment}/{@code @compute}
+     * entry point. GLSL has no such marker, so translating GLSL still takes an explicit stage.
+     *
+     * @param wgslSource WGSL shader source code
+     * @return Shader stage (0=vertex, 1=fragment, 2=compute)
+     * @throws IllegalArgumentException if the module has no entry point or entry points for several stages
+     */
+    public static int detectStage(String wgslSource) {
+        return detectWgslStage(wgslSource);
     }
 
     /**
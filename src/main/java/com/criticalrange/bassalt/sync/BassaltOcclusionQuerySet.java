@@ -0,0 +1,64 @@
+package com.criticalrange.bassalt.sync;
+
+import com.criticalrange.bassalt.backend.BassaltDevice;
+import net.fabricmc.api.EnvType;
+import net.fabricmc.api.Environment;
+
+/**
+ * Bassalt Occlusion Query Set - a fixed number of GPU occlusion queries.
+ *
+ * Attach the set to a render pass with
+ * {@code BassaltCommandEncoder.createRenderPass(..., occlusionQueries)}, wrap
+ * draws (e.g. chunk bounding boxes) in {@code beginOcclusionQuery(i)} /
+ * {@code endOcclusionQuery()}, then call {@link #resolve} after the pass has
+ * ended. A sample count of zero means nothing drawn inside the query was visible.
+ */
+@Environment(EnvType.CLIENT)
+public class BassaltOcclusionQuerySet implements AutoCloseable {
+
+    private final BassaltDevice device;
+    private final long nativePtr;
+    private final int count;
+    private boolean closed = false;
+
+    private static native long create0(long devicePtr, int count);
+    private static native void destroy0(long devicePtr, long queryPtr);
+    private static native long[] resolve0(long devicePtr, long queryPtr, int first, int count);
+
+    public BassaltOcclusionQuerySet(BassaltDevice device, int count) {
+        this.device = device;
+        this.count = count;
+        this.nativePtr = create0(device.getNativePtr(), count);
+    }
+
+    public long getNativePtr() {
+        return nativePtr;
+    }
+
+    public int getCount() {
+        return count;
+    }
+
+    /**
+     * Read back the sample counts of {@code count} queries starting at {@code first}.
+     * Blocks until the GPU has finished the passes that wrote them.
+     */
+    public long[] resolve(int first, int count) {
+        checkClosed();
+        return resolve0(device.getNativePtr(), nativePtr, first, count);
+    }
+
+    private void checkClosed() {
+        if (closed) {
+            throw new IllegalStateException("Occlusion query set is closed");
+        }
+    }
+
+    @Override
+    public void close() {
+        if (!closed && nativePtr != 0) {
+            destroy0(device.getNativePtr(), nativePtr);
+        }
+        closed = true;
+    }
+}
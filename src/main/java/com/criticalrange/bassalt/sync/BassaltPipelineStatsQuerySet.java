@@ -0,0 +1,80 @@
+package com.criticalrange.bassalt.sync;
+
+import com.criticalrange.bassalt.backend.BassaltDevice;
+import net.fabricmc.api.EnvType;
+import net.fabricmc.api.Environment;
+
+/**
+ * Bassalt Pipeline Statistics Query Set - a fixed number of GPU queries counting
+ * the work done by the draws they enclose.
+ *
+ * Wrap draws in {@code BassaltRenderPass.beginPipelineStatsQuery(set, i)} /
+ * {@code endPipelineStatsQuery()}, then call {@link #getPipelineStats} after the
+ * pass has ended. Each query reads back {@link #STATS_PER_QUERY} counters,
+ * indexed by the {@code STAT_*} constants. Requires a device that supports
+ * pipeline statistics queries.
+ */
+@Environment(EnvType.CLIENT)
+public class BassaltPipelineStatsQuerySet implements AutoCloseable {
+
+    /** Vertex shader invocations */
+    public static final int STAT_VERTEX_SHADER_INVOCATIONS = 0;
+    /** Primitives that entered clipping */
+    public static final int STAT_CLIPPER_INVOCATIONS = 1;
+    /** Primitives that survived clipping */
+    public static final int STAT_CLIPPER_PRIMITIVES_OUT = 2;
+    /** Fragment shader invocations */
+    public static final int STAT_FRAGMENT_SHADER_INVOCATIONS = 3;
+    /** Counters read back per query */
+    public static final int STATS_PER_QUERY = 4;
+
+    private final BassaltDevice device;
+    private final long nativePtr;
+    private final int count;
+    private boolean closed = false;
+
+    private static native long create0(long devicePtr, int count);
+    private static native void destroy0(long devicePtr, long queryPtr);
+    private static native long[] resolve0(long devicePtr, long queryPtr, int first, int count);
+
+    /**
+     * @throws UnsupportedOperationException if the device doesn't support pipeline statistics queries
+     */
+    public BassaltPipelineStatsQuerySet(BassaltDevice device, int count) {
+        this.device = device;
+        this.count = count;
+        this.nativePtr = create0(device.getNativePtr(), count);
+    }
+
+    public long getNativePtr() {
+        return nativePtr;
+    }
+
+    public int getCount() {
+        return count;
+    }
+
+    /**
+     * Read back the counters of {@code count} queries starting at {@code first},
+     * {@link #STATS_PER_QUERY} per query. Blocks until the GPU has finished the
+     * passes that wrote them.
+     */
+    public long[] getPipelineStats(int first, int count) {
+        checkClosed();
+        return resolve0(device.getNativePtr(), nativePtr, first, count);
+    }
+
+    private void checkClosed() {
+        if (closed) {
+            throw new IllegalStateException("Pipeline statistics query set is closed");
+        }
+    }
+
+    @Override
+    public void close() {
+        if (!closed && nativePtr != 0) {
+            destroy0(device.getNativePtr(), nativePtr);
+        }
+        closed = true;
+    }
+}
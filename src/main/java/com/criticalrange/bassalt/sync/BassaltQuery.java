@@ -1,6 +1,7 @@
 package com.criticalrange.bassalt.sync;
 
 import com.criticalrange.bassalt.backend.BassaltDevice;
+import com.criticalrange.bassalt.backend.GpuSubmitException;
 import com.mojang.blaze3d.systems.GpuQuery;
 import net.fabricmc.api.EnvType;
 import net.fabricmc.api.Environment;
@@ -28,6 +29,7 @@ public class BassaltQuery implements GpuQuery {
     private static native void destroyTimestampQuery(long devicePtr, long queryPtr);
     private static native long getTimestampValue(long devicePtr, long queryPtr);
     private static native boolean isTimestampQuerySupported(long devicePtr);
+    private static native long[] resolveAndReadQuerySet0(long devicePtr, long queryPtr, int firstQuery, int count);
 
     public BassaltQuery(BassaltDevice device) {
         this.device = device;
@@ -79,6 +81,24 @@ public class BassaltQuery implements GpuQuery {
         }
     }
 
+    /**
+     * Resolve {@code count} timestamps starting at {@code firstQuery} and read them
+     * all back with a single buffer map.
+     *
+     * @return the timestamps in nanoseconds, or an empty array when timestamp
+     *         queries are unsupported and this query uses CPU timing
+     * @throws GpuSubmitException if submitting the resolve fails
+     */
+    public long[] resolveAndReadQuerySet(int firstQuery, int count) {
+        if (closed) {
+            throw new IllegalStateException("Query has been closed");
+        }
+        if (nativePtr == 0) {
+            return new long[0];
+        }
+        return resolveAndReadQuerySet0(device.getNativePtr(), nativePtr, firstQuery, count);
+    }
+
     @Override
     public void close() {
         if (!closed && nativePtr != 0) {
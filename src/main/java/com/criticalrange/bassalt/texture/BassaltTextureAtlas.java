@@ -0,0 +1,84 @@
+package com.criticalrange.bassalt.texture;
+
+import com.criticalrange.bassalt.backend.BassaltDevice;
+import net.fabricmc.api.EnvType;
+import net.fabricmc.api.Environment;
+
+import java.util.ArrayList;
+import java.util.List;
+
+/**
+ * Bassalt Texture Atlas - Wraps a device-owned sprite atlas allocator bound to one texture
+ *
+ * The atlas id comes from {@link BassaltDevice#atlasCreate}, and every operation goes
+ * through the device's atlas API. This class pairs the atlas with its texture and turns
+ * repacked placements into UVs.
+ */
+@Environment(EnvType.CLIENT)
+public class BassaltTextureAtlas {
+
+    /**
+     * New placement of a sprite after repacking. Dropped sprites no longer fit
+     * in the atlas and must be re-inserted by the caller.
+     */
+    public record Relocation(long entryId, int x, int y, int width, int height,
+                             float u0, float v0, float u1, float v1, boolean dropped) {
+    }
+
+    private final long devicePtr;
+    private final long atlasId;
+    private final long textureHandle;
+    private final int width;
+    private final int height;
+
+    public BassaltTextureAtlas(long devicePtr, long atlasId, long textureHandle, int width, int height) {
+        this.devicePtr = devicePtr;
+        this.atlasId = atlasId;
+        this.textureHandle = textureHandle;
+        this.width = width;
+        this.height = height;
+    }
+
+    public long getAtlasId() {
+        return atlasId;
+    }
+
+    public int getWidth() {
+        return width;
+    }
+
+    public int getHeight() {
+        return height;
+    }
+
+    /**
+     * Free the sprite occupying exactly the given rectangle
+     *
+     * @return true if a sprite was evicted
+     */
+    public boolean evict(int x, int y, int spriteWidth, int spriteHeight) {
+        return BassaltDevice.atlasEvict(devicePtr, atlasId, x, y, spriteWidth, spriteHeight);
+    }
+
+    /**
+     * Compact live sprites inside the atlas texture
+     *
+     * @return new placement and UVs for every sprite that moved or was dropped
+     */
+    public List<Relocation> repack() {
+        long[] mappings = BassaltDevice.atlasRepack(devicePtr, atlasId, textureHandle);
+        List<Relocation> result = new ArrayList<>(mappings.length / 5);
+        for (int i = 0; i + 4 < mappings.length; i += 5) {
+            int x = (int) mappings[i + 1];
+            int y = (int) mappings[i + 2];
+            int w = (int) mappings[i + 3];
+            int h = (int) mappings[i + 4];
+            boolean dropped = x < 0;
+            result.add(new Relocation(mappings[i], x, y, w, h,
+                    (float) x / width, (float) y / height,
+                    (float) (x + w) / width, (float) (y + h) / height,
+                    dropped));
+        }
+        return result;
+    }
+}